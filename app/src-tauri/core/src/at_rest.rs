@@ -0,0 +1,254 @@
+//! Optional AES-256-GCM encryption at rest for recordings, history and logs.
+//!
+//! When encryption is configured, everything written through this module is sealed with a
+//! random 256-bit key supplied by the embedding program (the desktop app keeps it in the OS
+//! keychain, never on disk next to the data):
+//! - whole files (recordings, `persist` JSON files) start with `MAGIC`, then a 12-byte
+//!   nonce, then the ciphertext;
+//! - line-oriented logs store each line as `enc:<base64 of the sealed line>`.
+//!
+//! Reads accept both formats, so files written before encryption was switched on keep
+//! working until they are rewritten. Switching encryption off keeps the key loaded so
+//! sealed files can still be read (and migrated back to plaintext).
+//!
+//! If the key can't be loaded (keychain locked, entry deleted), writes are refused for the
+//! rest of the session (see `set_key_unavailable`) rather than falling back to plaintext.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Header of a sealed file.
+const MAGIC: &[u8] = b"TGRENC1\0";
+const NONCE_LEN: usize = 12;
+/// Prefix of a sealed line in a line-oriented log.
+const LINE_PREFIX: &str = "enc:";
+
+/// AES-256-GCM cipher with the at-rest key.
+pub struct AtRestCipher {
+    cipher: Aes256Gcm,
+}
+
+impl AtRestCipher {
+    pub fn from_key(key: &[u8]) -> Result<Self, String> {
+        let cipher = Aes256Gcm::new_from_slice(key)
+            .map_err(|_| "At-rest key must be 32 bytes".to_string())?;
+        Ok(Self { cipher })
+    }
+
+    /// A new random 256-bit key, for `from_key`.
+    pub fn generate_key() -> Vec<u8> {
+        Aes256Gcm::generate_key(OsRng).to_vec()
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| "Encryption failed".to_string())?;
+
+        let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        let body = sealed
+            .strip_prefix(MAGIC)
+            .filter(|body| body.len() >= NONCE_LEN)
+            .ok_or_else(|| "Not an encrypted file".to_string())?;
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Decryption failed (wrong key or corrupted data)".to_string())
+    }
+}
+
+/// Whether `data` was written by `seal`.
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+#[derive(Default)]
+struct AtRestState {
+    cipher: Option<Arc<AtRestCipher>>,
+    /// Seal new writes (the cipher may stay loaded for reading after this is switched off).
+    enabled: bool,
+    /// Why the key is missing although sealed data needs it; blocks all writes.
+    key_unavailable: Option<String>,
+}
+
+static STATE: RwLock<AtRestState> = RwLock::new(AtRestState {
+    cipher: None,
+    enabled: false,
+    key_unavailable: None,
+});
+
+/// Turn sealing of new writes on (with `cipher`) or off. The last cipher stays loaded for
+/// reading already-sealed files.
+pub fn configure(cipher: Option<AtRestCipher>, enabled: bool) {
+    if let Ok(mut state) = STATE.write() {
+        if let Some(cipher) = cipher {
+            state.cipher = Some(Arc::new(cipher));
+        }
+        state.enabled = enabled && state.cipher.is_some();
+    }
+}
+
+pub fn is_enabled() -> bool {
+    STATE.read().map(|s| s.enabled).unwrap_or(false)
+}
+
+/// Record that the key could not be loaded although encryption is on or sealed files exist.
+///
+/// Stores that hit sealed files have loaded empty by then, so until the app restarts with
+/// the key available every write is refused: nothing sealed gets overwritten, and nothing
+/// is written in plaintext instead.
+pub fn set_key_unavailable(reason: String) {
+    if let Ok(mut state) = STATE.write() {
+        if state.key_unavailable.is_none() {
+            log::error!("At-rest encryption key unavailable, refusing writes: {}", reason);
+            state.key_unavailable = Some(reason);
+        }
+    }
+}
+
+/// Why writes are refused this session, if they are (see `set_key_unavailable`).
+pub fn key_unavailable() -> Option<String> {
+    STATE.read().ok().and_then(|s| s.key_unavailable.clone())
+}
+
+/// Whether `data` can be opened: it is plaintext or the key is loaded.
+pub fn can_open(data: &[u8]) -> bool {
+    !is_sealed(data) || active_cipher(false).is_some()
+}
+
+fn active_cipher(for_writing: bool) -> Option<Arc<AtRestCipher>> {
+    let state = STATE.read().ok()?;
+    if for_writing && !state.enabled {
+        return None;
+    }
+    state.cipher.clone()
+}
+
+fn check_writable() -> Result<(), String> {
+    match key_unavailable() {
+        Some(reason) => Err(format!(
+            "Encryption key unavailable ({}); not writing until it loads",
+            reason
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Seal `plaintext` if encryption at rest is on, otherwise return it unchanged.
+///
+/// Fails while the key is unavailable (see `set_key_unavailable`).
+pub fn seal(plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    check_writable()?;
+    match active_cipher(true) {
+        Some(cipher) => cipher.seal(plaintext),
+        None => Ok(plaintext.to_vec()),
+    }
+}
+
+/// Decrypt `data` if it is sealed, otherwise return it unchanged.
+pub fn open(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if !is_sealed(&data) {
+        return Ok(data);
+    }
+    active_cipher(false)
+        .ok_or_else(|| "Data is encrypted but no at-rest key is loaded".to_string())?
+        .open(&data)
+}
+
+/// Whether `line` was written by `seal_line`.
+pub fn is_sealed_line(line: &str) -> bool {
+    line.starts_with(LINE_PREFIX)
+}
+
+/// `seal` for one line of a line-oriented log.
+pub fn seal_line(line: &str) -> Result<String, String> {
+    check_writable()?;
+    match active_cipher(true) {
+        Some(cipher) => {
+            let sealed = cipher.seal(line.as_bytes())?;
+            Ok(format!(
+                "{}{}",
+                LINE_PREFIX,
+                base64::engine::general_purpose::STANDARD.encode(sealed)
+            ))
+        }
+        None => Ok(line.to_string()),
+    }
+}
+
+/// `open` for one line of a line-oriented log.
+pub fn open_line(line: &str) -> Result<String, String> {
+    let Some(encoded) = line.strip_prefix(LINE_PREFIX) else {
+        return Ok(line.to_string());
+    };
+    let sealed = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| e.to_string())?;
+    String::from_utf8(open(sealed)?).map_err(|e| e.to_string())
+}
+
+/// Rewrite a whole-file blob (e.g. a recording) in the current format. Returns whether the
+/// file changed.
+pub fn rewrite_file(path: &Path) -> Result<bool, String> {
+    let raw = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if is_sealed(&raw) == is_enabled() {
+        return Ok(false);
+    }
+
+    let rewritten = seal(&open(raw)?)?;
+    let tmp = path.with_extension("rewrite.tmp");
+    fs::write(&tmp, rewritten).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cipher() -> AtRestCipher {
+        AtRestCipher::from_key(&[7u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let cipher = test_cipher();
+        let sealed = cipher.seal(b"dictated text").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(8).any(|w| w == b"dictated"));
+        assert_eq!(cipher.open(&sealed).unwrap(), b"dictated text");
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_data_is_rejected() {
+        let cipher = test_cipher();
+        let mut sealed = cipher.seal(b"hello").unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(cipher.open(&sealed).is_err());
+
+        let other = AtRestCipher::from_key(&[8u8; 32]).unwrap();
+        assert!(other.open(&cipher.seal(b"hello").unwrap()).is_err());
+
+        assert!(cipher.open(b"{\"plain\":true}").is_err());
+    }
+
+    #[test]
+    fn test_plaintext_passes_through_open() {
+        assert_eq!(open(b"RIFF....WAVE".to_vec()).unwrap(), b"RIFF....WAVE");
+        assert_eq!(open_line("{\"text\":\"hi\"}").unwrap(), "{\"text\":\"hi\"}");
+    }
+}
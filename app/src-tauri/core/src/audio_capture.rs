@@ -0,0 +1,2139 @@
+//! Audio capture module using cpal for cross-platform audio input.
+//!
+//! This module provides functionality to capture audio from the system's
+//! default input device and encode it to WAV format for STT processing.
+//!
+//! Supports optional Voice Activity Detection (VAD) for auto-stop functionality.
+
+use crate::audio_source::{AudioSource, ExternalSource, MicrophoneSource};
+use crate::vad::{NoiseProfile, VadConfig, VadEvent, VadFrameProcessor};
+use std::ops::Range;
+use cpal::traits::{DeviceTrait, HostTrait};
+use hound::{WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex as StdMutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Maximum number of callback chunks queued for the VAD thread.
+///
+/// At typical callback sizes (5-20ms) this is roughly 0.3-1.3s of audio. If the VAD thread
+/// falls further behind than that, the oldest chunks are dropped.
+const VAD_QUEUE_CAPACITY: usize = 64;
+
+fn clamp_u8_0_100(v: u8) -> u8 {
+    v.min(100)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn db_to_amp(db: f32) -> f32 {
+    // db is dBFS; 0 dBFS == full-scale amplitude (1.0).
+    10.0_f32.powf(db / 20.0)
+}
+
+fn amp_to_dbfs(amp: f32) -> f32 {
+    if !amp.is_finite() || amp <= 0.0 {
+        f32::NEG_INFINITY
+    } else {
+        20.0 * amp.log10()
+    }
+}
+
+fn downmix_interleaved_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    let channels = channels.max(1);
+    if channels == 1 {
+        return samples.to_vec();
+    }
+
+    let frames = samples.len() / channels;
+    let mut mono = Vec::with_capacity(frames);
+    for frame_idx in 0..frames {
+        let base = frame_idx * channels;
+        let mut sum = 0.0_f32;
+        for c in 0..channels {
+            sum += samples[base + c];
+        }
+        mono.push(sum / channels as f32);
+    }
+    mono
+}
+
+fn downmix_interleaved_chunk_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    // Same as full downmix, but kept separate for clarity.
+    downmix_interleaved_to_mono(samples, channels)
+}
+
+fn apply_highpass_dc_block(samples: &mut [f32], sample_rate: u32) {
+    // Simple DC-blocking high-pass filter.
+    // Good enough to reduce rumble / DC offset without heavy DSP.
+    let sr = sample_rate.max(1) as f32;
+    // Choose r based on a rough cutoff. Keep stable across SR.
+    // r close to 1.0 => lower cutoff.
+    let cutoff_hz = 80.0_f32;
+    let r = (-2.0 * std::f32::consts::PI * cutoff_hz / sr).exp();
+    let mut y_prev = 0.0_f32;
+    let mut x_prev = 0.0_f32;
+    for x in samples.iter_mut() {
+        let y = *x - x_prev + r * y_prev;
+        x_prev = *x;
+        y_prev = y;
+        *x = y;
+    }
+}
+
+fn apply_agc(samples: &mut [f32]) {
+    // Lightweight gain normalization.
+    // Target a strong peak while capping max gain to avoid crazy amplification.
+    let mut peak = 0.0_f32;
+    let mut sum_sq = 0.0_f64;
+    for &s in samples.iter() {
+        peak = peak.max(s.abs());
+        sum_sq += (s as f64) * (s as f64);
+    }
+    if samples.is_empty() {
+        return;
+    }
+    let rms = (sum_sq / samples.len() as f64).sqrt() as f32;
+
+    // Avoid amplifying true silence.
+    if peak < 1e-6 && rms < 1e-6 {
+        return;
+    }
+
+    let target_peak = 0.90_f32;
+    let target_rms = 0.10_f32; // ~ -20 dBFS
+    let max_gain = 8.0_f32;
+
+    let gain_peak = if peak > 0.0 { target_peak / peak } else { 1.0 };
+    let gain_rms = if rms > 0.0 { target_rms / rms } else { 1.0 };
+    let gain = gain_peak.min(gain_rms).clamp(0.1, max_gain);
+
+    for s in samples.iter_mut() {
+        *s = (*s * gain).clamp(-1.0, 1.0);
+    }
+}
+
+fn apply_light_noise_suppression(samples: &mut [f32], sample_rate: u32) {
+    // Extremely lightweight noise suppression:
+    // estimate a noise floor from the first ~200ms and apply soft subtraction.
+    if samples.is_empty() {
+        return;
+    }
+
+    let sr = sample_rate.max(1) as usize;
+    let window = (sr as f32 * 0.20) as usize; // ~200ms
+    let n = window.clamp(1, samples.len());
+
+    let mut sum_sq = 0.0_f64;
+    for &s in samples.iter().take(n) {
+        sum_sq += (s as f64) * (s as f64);
+    }
+    let floor_rms = (sum_sq / n as f64).sqrt() as f32;
+    if !floor_rms.is_finite() || floor_rms <= 0.0 {
+        return;
+    }
+
+    // Subtract most of the estimated floor; keep some to avoid pumping.
+    let subtract = floor_rms * 0.8;
+    for s in samples.iter_mut() {
+        let a = s.abs();
+        let sign = if *s >= 0.0 { 1.0 } else { -1.0 };
+        let out = (a - subtract).max(0.0);
+        *s = (sign * out).clamp(-1.0, 1.0);
+    }
+}
+
+/// Apply a simple noise gate to interleaved samples.
+///
+/// - `samples` are interleaved f32 in [-1, 1]
+/// - `channels` is the interleaving width
+/// - `threshold_dbfs` is a negative dBFS value (e.g. -60). `None` => bypass.
+///
+/// This is intentionally lightweight and runs at stop-time (offline), not in the
+/// real-time capture callback.
+fn apply_noise_gate_interleaved(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    threshold_dbfs: Option<f32>,
+) -> Vec<f32> {
+    let Some(threshold_dbfs) = threshold_dbfs else {
+        return samples.to_vec();
+    };
+    if samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    // UI-range clamp. Keep conservative.
+    let threshold_dbfs = if threshold_dbfs.is_finite() {
+        threshold_dbfs.clamp(-75.0, -30.0)
+    } else {
+        -60.0
+    };
+
+    let channels_usize = channels.max(1) as usize;
+    let frames = samples.len() / channels_usize;
+
+    let threshold_amp = db_to_amp(threshold_dbfs);
+
+    // Hysteresis reduces "chattering" around the threshold.
+    let close_threshold_amp = threshold_amp * 0.85;
+
+    // Attack/release smoothing on the *gain* to avoid clicks.
+    let fs = sample_rate.max(1) as f32;
+    let attack_s = 0.005_f32;
+    let release_s = 0.120_f32;
+
+    let attack_alpha = (-1.0 / (attack_s * fs)).exp();
+    let release_alpha = (-1.0 / (release_s * fs)).exp();
+
+    let mut out = Vec::with_capacity(samples.len());
+    let mut gate_open = false;
+    let mut gain: f32 = 0.0;
+
+    for frame_idx in 0..frames {
+        let base = frame_idx * channels_usize;
+
+        // Envelope: max abs across channels for this frame.
+        let mut env = 0.0_f32;
+        for c in 0..channels_usize {
+            env = env.max(samples[base + c].abs());
+        }
+
+        if gate_open {
+            if env < close_threshold_amp {
+                gate_open = false;
+            }
+        } else if env > threshold_amp {
+            gate_open = true;
+        }
+
+        let target = if gate_open { 1.0_f32 } else { 0.0_f32 };
+        let alpha = if target > gain { attack_alpha } else { release_alpha };
+        gain = target + alpha * (gain - target);
+
+        for c in 0..channels_usize {
+            out.push(samples[base + c] * gain);
+        }
+    }
+
+    // If there were trailing samples not forming a whole frame, just copy them.
+    // (Shouldn't happen in normal capture, but keep it safe.)
+    let consumed = frames * channels_usize;
+    if consumed < samples.len() {
+        out.extend_from_slice(&samples[consumed..]);
+    }
+
+    out
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AudioEncodeConfig {
+    /// If set, apply a noise gate with the given threshold.
+    pub noise_gate_threshold_dbfs: Option<f32>,
+    /// Convert the captured audio to mono before WAV encoding.
+    pub downmix_to_mono: bool,
+    /// Resample to 16kHz before WAV encoding.
+    pub resample_to_16khz: bool,
+    /// Apply a lightweight high-pass (DC/rumble) filter.
+    pub highpass_enabled: bool,
+    /// Apply a lightweight gain normalization.
+    pub agc_enabled: bool,
+    /// Apply a lightweight noise suppression.
+    pub noise_suppression_enabled: bool,
+    /// If enabled, compute a best-effort speech presence boolean using WebRTC VAD.
+    pub detect_speech_presence: bool,
+    /// Cut leading/trailing silence (found with WebRTC VAD) before encoding. Mono only.
+    pub trim_silence: bool,
+    /// When trimming, shorten pauses between speech segments to at most this many seconds.
+    pub max_internal_pause_secs: Option<f32>,
+}
+
+impl Default for AudioEncodeConfig {
+    fn default() -> Self {
+        Self {
+            noise_gate_threshold_dbfs: None,
+            downmix_to_mono: true,
+            resample_to_16khz: false,
+            highpass_enabled: true,
+            agc_enabled: false,
+            noise_suppression_enabled: false,
+            detect_speech_presence: false,
+            trim_silence: false,
+            max_internal_pause_secs: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AudioCaptureDiagnostics {
+    pub stats: AudioLevelStats,
+    pub speech_detected: Option<bool>,
+    /// VAD sample queue counters for the recording (`None` when realtime VAD was off).
+    #[serde(default)]
+    pub vad_queue: Option<VadQueueStats>,
+    /// Whether the realtime VAD heard speech during the recording (`None` when it was off).
+    #[serde(default)]
+    pub realtime_speech_detected: Option<bool>,
+    /// Seconds of silence removed by `trim_silence` (`None` when trimming was off).
+    #[serde(default)]
+    pub trimmed_silence_secs: Option<f32>,
+}
+
+/// Audio of a stopped recording, taken out of the capture so it can be encoded without
+/// holding the caller's locks.
+#[derive(Debug, Clone)]
+pub struct CapturedAudio {
+    pub buffer: AudioBuffer,
+    pub vad_queue: Option<VadQueueStats>,
+    pub realtime_speech_detected: Option<bool>,
+}
+
+impl CapturedAudio {
+    /// Encode as WAV with `cfg` (see `AudioBuffer::to_wav_bytes_with_config`).
+    pub fn encode(
+        &self,
+        cfg: AudioEncodeConfig,
+    ) -> Result<(Vec<u8>, AudioCaptureDiagnostics), AudioCaptureError> {
+        let (wav_bytes, mut diagnostics) = self.buffer.to_wav_bytes_with_config(cfg)?;
+        diagnostics.vad_queue = self.vad_queue;
+        diagnostics.realtime_speech_detected = self.realtime_speech_detected;
+        Ok((wav_bytes, diagnostics))
+    }
+
+    /// Encode twice:
+    /// - "before": raw, with no preprocessing/gates
+    /// - "after": encoded with `after_cfg`
+    pub fn encode_before_after(
+        &self,
+        after_cfg: AudioEncodeConfig,
+    ) -> Result<(Vec<u8>, Vec<u8>, AudioCaptureDiagnostics), AudioCaptureError> {
+        // "Before": as-captured (no downmix/resample/filters/gates).
+        let (before_wav, _before_diag) = self.buffer.to_wav_bytes_with_config(AudioEncodeConfig {
+            noise_gate_threshold_dbfs: None,
+            downmix_to_mono: false,
+            resample_to_16khz: false,
+            highpass_enabled: false,
+            agc_enabled: false,
+            noise_suppression_enabled: false,
+            detect_speech_presence: false,
+            trim_silence: false,
+            max_internal_pause_secs: None,
+        })?;
+
+        // "After": apply current user settings.
+        let (after_wav, after_diag) = self.encode(after_cfg)?;
+
+        Ok((before_wav, after_wav, after_diag))
+    }
+}
+
+/// Errors that can occur during audio capture
+#[derive(Debug, thiserror::Error)]
+pub enum AudioCaptureError {
+    #[error("No input device available")]
+    NoInputDevice,
+
+    #[error("Failed to get device config: {0}")]
+    DeviceConfig(String),
+
+    #[error("Failed to build audio stream: {0}")]
+    StreamBuild(String),
+
+    #[error("Failed to start audio stream: {0}")]
+    StreamStart(String),
+
+    #[error("Failed to encode audio: {0}")]
+    Encoding(String),
+
+    #[error("Audio capture not active")]
+    NotActive,
+
+    #[error("Pushed audio is {got} Hz but the external source was started at {expected} Hz")]
+    SampleRateMismatch { expected: u32, got: u32 },
+
+    #[error("Failed to read audio file: {0}")]
+    AudioFile(String),
+
+    #[error("Input device '{0}' was disconnected and no replacement device was available")]
+    DeviceLost(String),
+
+    #[error("Capture thread error: {0}")]
+    ThreadError(String),
+}
+
+/// Audio buffer that accumulates samples during recording
+#[derive(Debug, Clone)]
+pub struct AudioBuffer {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: u16,
+    max_duration_secs: f32,
+    /// Keep the first `max_duration_secs` and drop anything after, instead of the last.
+    stop_at_max: bool,
+    /// Samples appended so far, including the ones trimmed since (see `samples_since`).
+    appended: u64,
+}
+
+impl AudioBuffer {
+    /// Create a new audio buffer with the specified parameters
+    pub fn new(sample_rate: u32, channels: u16, max_duration_secs: f32) -> Self {
+        let capacity = (sample_rate as f32 * max_duration_secs * channels as f32) as usize;
+        Self {
+            samples: Vec::with_capacity(capacity),
+            sample_rate,
+            channels,
+            max_duration_secs,
+            stop_at_max: false,
+            appended: 0,
+        }
+    }
+
+    /// Append samples to the buffer
+    ///
+    /// Returns `false` when the buffer is capped (see `set_stop_at_max`) and some of the
+    /// samples didn't fit.
+    pub fn append(&mut self, new_samples: &[f32]) -> bool {
+        let max_samples =
+            (self.sample_rate as f32 * self.max_duration_secs * self.channels as f32) as usize;
+
+        if self.stop_at_max {
+            // Cut on a frame boundary so the kept audio ends exactly at the limit.
+            let channels = self.channels.max(1) as usize;
+            let room = (max_samples / channels * channels).saturating_sub(self.samples.len());
+            let kept = room.min(new_samples.len());
+            self.samples.extend_from_slice(&new_samples[..kept]);
+            self.appended += kept as u64;
+            return kept == new_samples.len();
+        }
+
+        self.samples.extend_from_slice(new_samples);
+        self.appended += new_samples.len() as u64;
+
+        // Trim if exceeds max duration
+        if self.samples.len() > max_samples {
+            let drain_count = self.samples.len() - max_samples;
+            self.samples.drain(0..drain_count);
+        }
+        true
+    }
+
+    /// Change the maximum duration kept in the buffer.
+    ///
+    /// Existing samples are kept (trimming the oldest ones if the new limit is smaller).
+    pub fn set_max_duration_secs(&mut self, max_duration_secs: f32) {
+        self.max_duration_secs = max_duration_secs;
+        if self.stop_at_max {
+            let channels = self.channels.max(1) as usize;
+            let max_samples =
+                (self.sample_rate as f32 * max_duration_secs) as usize * channels;
+            let dropped = self.samples.len().saturating_sub(max_samples);
+            self.samples.truncate(max_samples);
+            self.appended -= dropped as u64;
+        } else {
+            self.append(&[]);
+        }
+    }
+
+    /// Stop taking samples once `max_duration_secs` is reached, rather than dropping the
+    /// oldest ones (a rolling window, used for pre-roll).
+    pub fn set_stop_at_max(&mut self, stop_at_max: bool) {
+        self.stop_at_max = stop_at_max;
+        self.set_max_duration_secs(self.max_duration_secs);
+    }
+
+    /// Samples appended after position `cursor` (a previous return value, or 0) that are
+    /// still in the buffer, and the position to pass next time.
+    pub fn samples_since(&self, cursor: u64) -> (Vec<f32>, u64) {
+        let first = self.appended - self.samples.len() as u64;
+        let start = cursor.clamp(first, self.appended) - first;
+        (self.samples[start as usize..].to_vec(), self.appended)
+    }
+
+    /// Clear all samples from the buffer
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Get the number of samples in the buffer
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Check if the buffer is empty
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Get the duration of audio in the buffer in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / (self.sample_rate as f32 * self.channels as f32)
+    }
+
+    /// Compute simple signal level statistics over the captured samples.
+    ///
+    /// Samples are expected to be normalized floats in [-1.0, 1.0].
+    pub fn level_stats(&self) -> AudioLevelStats {
+        level_stats_of(&self.samples, self.duration_secs())
+    }
+
+    /// Convert the buffer contents to WAV bytes
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn to_wav_bytes(&self) -> Result<Vec<u8>, AudioCaptureError> {
+        self.to_wav_bytes_with_noise_gate(0)
+    }
+
+    /// Convert the buffer contents to WAV bytes, optionally applying an experimental noise gate.
+    ///
+    /// `noise_gate_strength` is 0..=100 where 0 disables the noise gate.
+    pub fn to_wav_bytes_with_noise_gate(
+        &self,
+        noise_gate_strength: u8,
+    ) -> Result<Vec<u8>, AudioCaptureError> {
+        let strength = clamp_u8_0_100(noise_gate_strength);
+        let threshold_dbfs = if strength == 0 {
+            None
+        } else {
+            let t = strength as f32 / 100.0;
+            Some(lerp(-75.0, -30.0, t))
+        };
+
+        let (wav_bytes, _diagnostics) = self.to_wav_bytes_with_config(AudioEncodeConfig {
+            noise_gate_threshold_dbfs: threshold_dbfs,
+            ..Default::default()
+        })?;
+        Ok(wav_bytes)
+    }
+
+    pub fn to_wav_bytes_with_config(
+        &self,
+        cfg: AudioEncodeConfig,
+    ) -> Result<(Vec<u8>, AudioCaptureDiagnostics), AudioCaptureError> {
+        let diagnostics = if cfg.detect_speech_presence {
+            Some(detect_speech_presence(
+                &self.samples,
+                self.sample_rate,
+                self.channels,
+            ))
+        } else {
+            None
+        };
+
+        let mut processed_samples = if cfg.downmix_to_mono {
+            downmix_interleaved_to_mono(&self.samples, self.channels as usize)
+        } else {
+            self.samples.to_vec()
+        };
+
+        let mut out_sample_rate = self.sample_rate;
+        let out_channels: u16 = if cfg.downmix_to_mono { 1 } else { self.channels.max(1) };
+
+        let mut trimmed_silence_secs = None;
+
+        // If we didn't downmix, most processing is skipped (keeps code simple and predictable).
+        if cfg.downmix_to_mono {
+            if cfg.trim_silence {
+                let before = processed_samples.len();
+                let segments = crate::vad::detect_speech_segments(
+                    &processed_samples,
+                    out_sample_rate,
+                    VadConfig::default(),
+                );
+                processed_samples = compact_to_speech_segments(
+                    &processed_samples,
+                    &segments,
+                    out_sample_rate,
+                    cfg.max_internal_pause_secs,
+                );
+                let removed = before - processed_samples.len();
+                trimmed_silence_secs = Some(removed as f32 / out_sample_rate.max(1) as f32);
+            }
+            if cfg.noise_suppression_enabled {
+                apply_light_noise_suppression(&mut processed_samples, out_sample_rate);
+            }
+            if cfg.highpass_enabled {
+                apply_highpass_dc_block(&mut processed_samples, out_sample_rate);
+            }
+            if cfg.agc_enabled {
+                apply_agc(&mut processed_samples);
+            }
+
+            // Optional resample after filtering/gain.
+            if cfg.resample_to_16khz && out_sample_rate != 16000 {
+                processed_samples =
+                    crate::vad::resample_to_16khz_batch(&processed_samples, out_sample_rate);
+                out_sample_rate = 16000;
+            }
+
+            // Noise gate (mono)
+            processed_samples = apply_noise_gate_interleaved(
+                &processed_samples,
+                out_sample_rate,
+                1,
+                cfg.noise_gate_threshold_dbfs,
+            );
+        } else {
+            // Noise gate (interleaved)
+            processed_samples = apply_noise_gate_interleaved(
+                &processed_samples,
+                out_sample_rate,
+                out_channels,
+                cfg.noise_gate_threshold_dbfs,
+            );
+        }
+
+        let spec = WavSpec {
+            channels: out_channels,
+            sample_rate: out_sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = WavWriter::new(&mut cursor, spec)
+                .map_err(|e| AudioCaptureError::Encoding(e.to_string()))?;
+
+            for &sample in &processed_samples {
+                let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                writer
+                    .write_sample(sample_i16)
+                    .map_err(|e| AudioCaptureError::Encoding(e.to_string()))?;
+            }
+
+            writer
+                .finalize()
+                .map_err(|e| AudioCaptureError::Encoding(e.to_string()))?;
+        }
+
+        Ok((
+            cursor.into_inner(),
+            AudioCaptureDiagnostics {
+                stats: self.level_stats(),
+                speech_detected: diagnostics,
+                vad_queue: None,
+                realtime_speech_detected: None,
+                trimmed_silence_secs,
+            },
+        ))
+    }
+
+    /// Get the sample rate
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Get the number of channels
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+/// Level statistics of encoded WAV bytes (integer or float PCM), e.g. a saved recording
+/// that is transcribed again.
+pub fn wav_level_stats(wav: &[u8]) -> Option<AudioLevelStats> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(wav)).ok()?;
+    let spec = reader.spec();
+    if spec.sample_rate == 0 || spec.channels == 0 {
+        return None;
+    }
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample.clamp(1, 32) - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+        }
+    }
+    .ok()?;
+    let duration_secs = samples.len() as f32 / (spec.sample_rate as f32 * spec.channels as f32);
+    Some(level_stats_of(&samples, duration_secs))
+}
+
+/// Sample rate and duration (in seconds) of encoded WAV bytes.
+pub fn wav_sample_rate_and_duration(wav: &[u8]) -> Option<(u32, f32)> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(wav)).ok()?;
+    let sample_rate = reader.spec().sample_rate;
+    (sample_rate > 0).then(|| (sample_rate, reader.duration() as f32 / sample_rate as f32))
+}
+
+/// Level statistics of normalized samples (see `AudioBuffer::level_stats`).
+fn level_stats_of(samples: &[f32], duration_secs: f32) -> AudioLevelStats {
+    let mut peak: f32 = 0.0;
+    let mut sum_sq: f64 = 0.0;
+    let mut n: u64 = 0;
+
+    for &s in samples {
+        let a = s.abs();
+        if a > peak {
+            peak = a;
+        }
+
+        // Promote to f64 for numerical stability on long recordings.
+        sum_sq += (s as f64) * (s as f64);
+        n += 1;
+    }
+
+    let rms = if n == 0 {
+        0.0
+    } else {
+        (sum_sq / n as f64).sqrt() as f32
+    };
+
+    AudioLevelStats {
+        duration_secs,
+        rms,
+        peak,
+    }
+}
+
+/// Basic audio level metrics for gating/diagnostics.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AudioLevelStats {
+    pub duration_secs: f32,
+    /// Root-mean-square amplitude in [0, 1].
+    pub rms: f32,
+    /// Peak (max absolute) amplitude in [0, 1].
+    pub peak: f32,
+}
+
+fn detect_speech_presence(samples: &[f32], sample_rate: u32, channels: u16) -> bool {
+    if samples.is_empty() {
+        return false;
+    }
+
+    let mono = downmix_interleaved_to_mono(samples, channels.max(1) as usize);
+    let mut processor = VadFrameProcessor::new(VadConfig::default(), sample_rate.max(1));
+
+    let mut events = processor.process(&mono);
+    events.extend(processor.finish());
+    events
+        .iter()
+        .any(|event| matches!(event, VadEvent::SpeechStart { .. }))
+}
+
+/// Keep only the speech segments of a mono recording.
+///
+/// Leading and trailing silence is dropped. Pauses between segments are kept as-is unless
+/// `max_pause_secs` is set, in which case longer pauses are shortened to that length
+/// (keeping the edges of the pause so the splice stays quiet). When no speech was found the
+/// samples are returned unchanged and the quiet-audio gate decides what to do with them.
+fn compact_to_speech_segments(
+    samples: &[f32],
+    segments: &[Range<usize>],
+    sample_rate: u32,
+    max_pause_secs: Option<f32>,
+) -> Vec<f32> {
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return samples.to_vec();
+    };
+
+    let max_pause = max_pause_secs
+        .filter(|s| s.is_finite() && *s >= 0.0)
+        .map(|s| (s * sample_rate as f32) as usize);
+
+    let Some(max_pause) = max_pause else {
+        let end = last.end.min(samples.len());
+        return samples[first.start.min(end)..end].to_vec();
+    };
+
+    let mut out = Vec::with_capacity(samples.len());
+    let mut prev_end: Option<usize> = None;
+    for seg in segments {
+        let start = seg.start.min(samples.len());
+        let end = seg.end.min(samples.len());
+        if let Some(prev_end) = prev_end {
+            if start > prev_end {
+                let gap = start - prev_end;
+                if gap > max_pause {
+                    let head = max_pause / 2;
+                    let tail = max_pause - head;
+                    out.extend_from_slice(&samples[prev_end..prev_end + head]);
+                    out.extend_from_slice(&samples[start - tail..start]);
+                } else {
+                    out.extend_from_slice(&samples[prev_end..start]);
+                }
+            }
+        }
+        // Segments may overlap by a few samples after rate conversion.
+        let start = prev_end.map_or(start, |p| start.max(p)).min(end);
+        out.extend_from_slice(&samples[start..end]);
+        prev_end = Some(end.max(prev_end.unwrap_or(0)));
+    }
+    out
+}
+
+/// Realtime-safe snapshot of the most recent input level.
+///
+/// Updated by the CPAL input callback using atomics (no allocations, no event emission).
+#[derive(Debug, Clone, Copy)]
+pub struct AudioLevelSnapshot {
+    pub seq: u64,
+    /// Root-mean-square amplitude in [0, 1] for the most recent callback chunk.
+    pub rms: f32,
+    /// Peak (max abs) amplitude in [0, 1] for the most recent callback chunk.
+    pub peak: f32,
+}
+
+/// Number of min/max buckets sent to the overlay for waveform rendering.
+///
+/// Keep this modest: payload size is 2 * N floats per frame.
+pub const WAVEFORM_BINS: usize = 64;
+
+/// Realtime-safe snapshot of the most recent min/max waveform buckets.
+///
+/// `mins[i]` and `maxes[i]` are in [-1, 1] representing the min/max sample value
+/// for that bucket.
+#[derive(Debug, Clone)]
+pub struct AudioWaveformSnapshot {
+    pub seq: u64,
+    pub mins: Vec<f32>,
+    pub maxes: Vec<f32>,
+}
+
+/// A cheap-to-clone handle for reading realtime waveform buckets without needing
+/// to borrow the full `AudioCapture`.
+#[derive(Clone)]
+pub struct SharedAudioWaveformMeter {
+    inner: Arc<AudioWaveformMeter>,
+}
+
+impl SharedAudioWaveformMeter {
+    pub fn snapshot(&self) -> AudioWaveformSnapshot {
+        self.inner.snapshot()
+    }
+}
+
+/// A cheap-to-clone handle for reading realtime audio levels without needing to
+/// borrow the full `AudioCapture`.
+///
+/// This wrapper avoids exposing the internal `AudioLevelMeter` implementation.
+#[derive(Clone)]
+pub struct SharedAudioLevelMeter {
+    inner: Arc<AudioLevelMeter>,
+}
+
+impl SharedAudioLevelMeter {
+    pub fn snapshot(&self) -> AudioLevelSnapshot {
+        self.inner.snapshot()
+    }
+}
+
+#[derive(Debug)]
+struct AudioWaveformMeter {
+    seq: AtomicU64,
+    min_bits: [AtomicU32; WAVEFORM_BINS],
+    max_bits: [AtomicU32; WAVEFORM_BINS],
+}
+
+impl Default for AudioWaveformMeter {
+    fn default() -> Self {
+        Self {
+            seq: AtomicU64::new(0),
+            min_bits: std::array::from_fn(|_| AtomicU32::new(0f32.to_bits())),
+            max_bits: std::array::from_fn(|_| AtomicU32::new(0f32.to_bits())),
+        }
+    }
+}
+
+impl AudioWaveformMeter {
+    fn snapshot(&self) -> AudioWaveformSnapshot {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let mut mins = Vec::with_capacity(WAVEFORM_BINS);
+        let mut maxes = Vec::with_capacity(WAVEFORM_BINS);
+        for i in 0..WAVEFORM_BINS {
+            mins.push(f32::from_bits(self.min_bits[i].load(Ordering::Relaxed)));
+            maxes.push(f32::from_bits(self.max_bits[i].load(Ordering::Relaxed)));
+        }
+        AudioWaveformSnapshot { seq, mins, maxes }
+    }
+
+    fn update_from_f32_interleaved(&self, data: &[f32], channels: usize) {
+        let channels = channels.max(1);
+        let frames = data.len() / channels;
+        if frames == 0 {
+            return;
+        }
+
+        for bin in 0..WAVEFORM_BINS {
+            let start = (bin * frames) / WAVEFORM_BINS;
+            let end = ((bin + 1) * frames) / WAVEFORM_BINS;
+            if start >= end {
+                self.min_bits[bin].store(0f32.to_bits(), Ordering::Relaxed);
+                self.max_bits[bin].store(0f32.to_bits(), Ordering::Relaxed);
+                continue;
+            }
+
+            let mut min_v: f32 = 1.0;
+            let mut max_v: f32 = -1.0;
+
+            for frame in start..end {
+                let base = frame * channels;
+                let mut acc: f32 = 0.0;
+                for c in 0..channels {
+                    acc += data.get(base + c).copied().unwrap_or(0.0);
+                }
+                let s = (acc / channels as f32).clamp(-1.0, 1.0);
+                if s < min_v {
+                    min_v = s;
+                }
+                if s > max_v {
+                    max_v = s;
+                }
+            }
+
+            self.min_bits[bin].store(min_v.to_bits(), Ordering::Relaxed);
+            self.max_bits[bin].store(max_v.to_bits(), Ordering::Relaxed);
+        }
+
+        self.seq.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default)]
+struct AudioLevelMeter {
+    seq: AtomicU64,
+    rms_bits: AtomicU32,
+    peak_bits: AtomicU32,
+}
+
+impl AudioLevelMeter {
+    fn snapshot(&self) -> AudioLevelSnapshot {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let rms = f32::from_bits(self.rms_bits.load(Ordering::Relaxed));
+        let peak = f32::from_bits(self.peak_bits.load(Ordering::Relaxed));
+        AudioLevelSnapshot { seq, rms, peak }
+    }
+
+    fn update(&self, rms: f32, peak: f32) {
+        // Clamp to sane range and avoid NaNs propagating into the UI.
+        let rms = if rms.is_finite() { rms.clamp(0.0, 1.0) } else { 0.0 };
+        let peak = if peak.is_finite() { peak.clamp(0.0, 1.0) } else { 0.0 };
+
+        self.rms_bits.store(rms.to_bits(), Ordering::Relaxed);
+        self.peak_bits.store(peak.to_bits(), Ordering::Relaxed);
+        self.seq.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counters for the capture -> VAD sample queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VadQueueStats {
+    /// Chunks handed to the queue by the capture callback.
+    pub enqueued: u64,
+    /// Chunks discarded because the queue was full (oldest first).
+    pub dropped: u64,
+    /// Highest number of chunks waiting at once.
+    pub max_depth: u64,
+}
+
+#[derive(Debug, Default)]
+struct VadQueueCounters {
+    enqueued: AtomicU64,
+    dropped: AtomicU64,
+    max_depth: AtomicU64,
+}
+
+impl VadQueueCounters {
+    fn snapshot(&self) -> VadQueueStats {
+        VadQueueStats {
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            max_depth: self.max_depth.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.enqueued.store(0, Ordering::Relaxed);
+        self.dropped.store(0, Ordering::Relaxed);
+        self.max_depth.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Whether the realtime VAD has heard speech during the current recording.
+#[derive(Debug, Default)]
+struct SpeechPresence {
+    /// Speech is in progress (between `SpeechStart` and `SpeechEnd`).
+    speaking: AtomicBool,
+    /// Speech started at some point during the recording.
+    seen: AtomicBool,
+}
+
+impl SpeechPresence {
+    fn observe(&self, event: &AudioCaptureEvent) {
+        match event {
+            AudioCaptureEvent::SpeechStart => {
+                self.speaking.store(true, Ordering::Relaxed);
+                self.seen.store(true, Ordering::Relaxed);
+            }
+            AudioCaptureEvent::SpeechEnd => self.speaking.store(false, Ordering::Relaxed),
+            _ => {}
+        }
+    }
+
+    /// Forget everything (a new stream is starting).
+    fn reset(&self) {
+        self.speaking.store(false, Ordering::Relaxed);
+        self.seen.store(false, Ordering::Relaxed);
+    }
+
+    /// A standby stream became a recording: only speech still in progress is part of it.
+    fn start_recording(&self) {
+        self.seen
+            .store(self.speaking.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    fn seen(&self) -> bool {
+        self.seen.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+struct VadQueueState {
+    chunks: VecDeque<Vec<f32>>,
+    closed: bool,
+}
+
+/// Bounded queue between the capture callback and the VAD thread.
+///
+/// Pushing never blocks: when the queue is full the oldest chunk is dropped, so a stalled
+/// VAD thread costs a bounded amount of memory and only ever sees the most recent audio.
+struct VadSampleQueue {
+    state: StdMutex<VadQueueState>,
+    ready: Condvar,
+    capacity: usize,
+    counters: Arc<VadQueueCounters>,
+}
+
+impl VadSampleQueue {
+    fn new(capacity: usize, counters: Arc<VadQueueCounters>) -> Self {
+        Self {
+            state: StdMutex::new(VadQueueState::default()),
+            ready: Condvar::new(),
+            capacity: capacity.max(1),
+            counters,
+        }
+    }
+
+    fn push(&self, chunk: Vec<f32>) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.closed {
+            return;
+        }
+
+        while state.chunks.len() >= self.capacity {
+            state.chunks.pop_front();
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        state.chunks.push_back(chunk);
+
+        self.counters.enqueued.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .max_depth
+            .fetch_max(state.chunks.len() as u64, Ordering::Relaxed);
+        drop(state);
+        self.ready.notify_one();
+    }
+
+    /// Wait up to `timeout` for the next chunk.
+    ///
+    /// Mirrors `mpsc::Receiver::recv_timeout`: chunks still queued after `close` are
+    /// delivered before `Disconnected` is returned.
+    fn pop_timeout(&self, timeout: Duration) -> Result<Vec<f32>, mpsc::RecvTimeoutError> {
+        let Ok(state) = self.state.lock() else {
+            return Err(mpsc::RecvTimeoutError::Disconnected);
+        };
+        let Ok((mut state, _)) = self
+            .ready
+            .wait_timeout_while(state, timeout, |s| s.chunks.is_empty() && !s.closed)
+        else {
+            return Err(mpsc::RecvTimeoutError::Disconnected);
+        };
+
+        match state.chunks.pop_front() {
+            Some(chunk) => Ok(chunk),
+            None if state.closed => Err(mpsc::RecvTimeoutError::Disconnected),
+            None => Err(mpsc::RecvTimeoutError::Timeout),
+        }
+    }
+
+    fn close(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+        }
+        self.ready.notify_all();
+    }
+}
+
+/// Commands sent to the audio capture thread
+enum CaptureCommand {
+    Stop,
+}
+
+/// VAD events sent from the capture thread
+#[derive(Debug, Clone)]
+pub enum AudioCaptureEvent {
+    /// Speech detected (with pre-roll audio)
+    SpeechStart,
+    /// Speech ended after hangover period
+    SpeechEnd,
+    /// The source has no more audio (a replayed file reached its end). The session stays
+    /// open until it is stopped.
+    SourceEnded,
+    /// The recording reached its maximum duration while stopping at the limit is on (see
+    /// `AudioCapture::set_stop_at_max_duration`); later audio is not recorded.
+    MaxDurationReached,
+    /// The input device stopped delivering audio (unplugged, or the stream died). With
+    /// `switched_to` set, capture continues on that device; otherwise the session has ended
+    /// and stopping it returns `AudioCaptureError::DeviceLost`.
+    DeviceLost {
+        device: String,
+        switched_to: Option<String>,
+    },
+}
+
+/// What decides that speech ended, for auto-stop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endpointing {
+    /// The local VAD (`AudioCaptureEvent::SpeechEnd`).
+    #[default]
+    Local,
+    /// The STT provider's endpointing on audio streamed to it while recording (Deepgram's
+    /// `speech_final`), falling back to the local VAD if the provider has none or the
+    /// stream fails.
+    Provider,
+}
+
+/// Configuration for VAD-based auto-stop
+#[derive(Debug, Clone)]
+pub struct VadAutoStopConfig {
+    /// Enable VAD processing
+    pub enabled: bool,
+    /// Automatically stop recording when speech ends
+    pub auto_stop: bool,
+    /// What detects the end of speech for `auto_stop`
+    pub endpointing: Endpointing,
+    /// VAD configuration
+    pub vad_config: VadConfig,
+}
+
+impl Default for VadAutoStopConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_stop: false,
+            endpointing: Endpointing::Local,
+            vad_config: VadConfig::default(),
+        }
+    }
+}
+
+/// What a running capture stream is being used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureMode {
+    /// A normal recording session.
+    Recording,
+    /// Pre-roll standby: the mic is open and only the last few seconds are kept.
+    Standby,
+}
+
+/// Destination of captured samples: the recording buffer, the UI meters and (when realtime
+/// VAD is on) the VAD queue.
+#[derive(Clone)]
+pub struct CaptureSink {
+    buffer: Arc<StdMutex<AudioBuffer>>,
+    level_meter: Arc<AudioLevelMeter>,
+    waveform_meter: Arc<AudioWaveformMeter>,
+    vad_queue: Option<Arc<VadSampleQueue>>,
+    channels: usize,
+    event_tx: mpsc::Sender<AudioCaptureEvent>,
+    /// Set once `MaxDurationReached` has been sent.
+    max_reached: Arc<AtomicBool>,
+}
+
+impl CaptureSink {
+    /// Append interleaved samples.
+    pub fn write(&self, samples: &[f32]) {
+        if samples.is_empty() {
+            return;
+        }
+
+        let (peak, sum_sq) = samples.iter().fold((0.0f32, 0.0f64), |(peak, sum), &s| {
+            (peak.max(s.abs()), sum + (s as f64) * (s as f64))
+        });
+        self.level_meter
+            .update((sum_sq / samples.len() as f64).sqrt() as f32, peak);
+        self.waveform_meter
+            .update_from_f32_interleaved(samples, self.channels);
+
+        let complete = self.buffer.lock().map(|mut buf| buf.append(samples));
+        if matches!(complete, Ok(false)) && !self.max_reached.swap(true, Ordering::Relaxed) {
+            log::info!("Recording reached its maximum duration");
+            let _ = self.event_tx.send(AudioCaptureEvent::MaxDurationReached);
+        }
+
+        if let Some(queue) = &self.vad_queue {
+            queue.push(if self.channels > 1 {
+                downmix_interleaved_chunk_to_mono(samples, self.channels)
+            } else {
+                samples.to_vec()
+            });
+        }
+    }
+
+    /// Number of `write` calls so far (keeps counting across sessions).
+    pub fn writes(&self) -> u64 {
+        self.level_meter.seq.load(Ordering::Relaxed)
+    }
+}
+
+/// What an `AudioSource` gets on the capture thread: where to write samples, the stop
+/// signal and the event channel.
+pub struct SourceContext {
+    sink: CaptureSink,
+    command_rx: mpsc::Receiver<CaptureCommand>,
+    event_tx: mpsc::Sender<AudioCaptureEvent>,
+}
+
+impl SourceContext {
+    pub fn sink(&self) -> &CaptureSink {
+        &self.sink
+    }
+
+    /// Wait up to `timeout` for the session to be stopped; returns whether it was.
+    pub fn wait_for_stop(&self, timeout: Duration) -> bool {
+        match self.command_rx.recv_timeout(timeout) {
+            Ok(CaptureCommand::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => true,
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+        }
+    }
+
+    /// Report an event to the owner of the session (see `AudioCapture::poll_vad_event`).
+    pub fn emit(&self, event: AudioCaptureEvent) {
+        let _ = self.event_tx.send(event);
+    }
+}
+
+/// Handle to a running audio capture session
+struct CaptureHandle {
+    command_tx: mpsc::Sender<CaptureCommand>,
+    #[cfg_attr(not(test), allow(dead_code))]
+    event_rx: mpsc::Receiver<AudioCaptureEvent>,
+    thread_handle: JoinHandle<Result<(), AudioCaptureError>>,
+    mode: CaptureMode,
+    /// `AudioSource::key` of the source being captured.
+    source_key: String,
+    /// Set when the source takes its samples from `push_frames`.
+    push_sink: Option<CaptureSink>,
+}
+
+/// Thread-safe audio capture manager
+///
+/// This runs audio capture in a separate thread to avoid Send/Sync issues
+/// with cpal::Stream. The captured audio is stored in a shared buffer.
+pub struct AudioCapture {
+    buffer: Arc<StdMutex<AudioBuffer>>,
+    capture_handle: Option<CaptureHandle>,
+    sample_rate: u32,
+    channels: u16,
+    vad_config: VadAutoStopConfig,
+    /// End recordings at their max duration instead of keeping only the latest audio.
+    stop_at_max_duration: bool,
+
+    // Most recent realtime level stats (for UI metering / overlay waveform).
+    level_meter: Arc<AudioLevelMeter>,
+
+    // Most recent realtime waveform buckets (for true waveform rendering).
+    waveform_meter: Arc<AudioWaveformMeter>,
+
+    // Capture -> VAD queue counters for the current stream.
+    vad_queue_counters: Arc<VadQueueCounters>,
+
+    // Speech heard by the realtime VAD during the current recording.
+    speech_presence: Arc<SpeechPresence>,
+
+    // Set while a recording (not a pre-roll standby) is running.
+    recording_flag: Arc<AtomicBool>,
+}
+
+impl AudioCapture {
+    /// Create a new audio capture instance
+    pub fn new() -> Self {
+        Self {
+            buffer: Arc::new(StdMutex::new(AudioBuffer::new(44100, 1, 300.0))),
+            capture_handle: None,
+            sample_rate: 44100,
+            channels: 1,
+            vad_config: VadAutoStopConfig::default(),
+            level_meter: Arc::new(AudioLevelMeter::default()),
+            waveform_meter: Arc::new(AudioWaveformMeter::default()),
+            vad_queue_counters: Arc::new(VadQueueCounters::default()),
+            speech_presence: Arc::new(SpeechPresence::default()),
+            recording_flag: Arc::new(AtomicBool::new(false)),
+            stop_at_max_duration: false,
+        }
+    }
+
+    /// Create a new audio capture instance with VAD configuration
+    pub fn with_vad_config(vad_config: VadAutoStopConfig) -> Self {
+        Self {
+            buffer: Arc::new(StdMutex::new(AudioBuffer::new(44100, 1, 300.0))),
+            capture_handle: None,
+            sample_rate: 44100,
+            channels: 1,
+            vad_config,
+            level_meter: Arc::new(AudioLevelMeter::default()),
+            waveform_meter: Arc::new(AudioWaveformMeter::default()),
+            vad_queue_counters: Arc::new(VadQueueCounters::default()),
+            speech_presence: Arc::new(SpeechPresence::default()),
+            recording_flag: Arc::new(AtomicBool::new(false)),
+            stop_at_max_duration: false,
+        }
+    }
+
+    /// Get the most recent realtime input level snapshot.
+    ///
+    /// This is updated continuously while recording (per CPAL callback). When not recording,
+    /// it returns the last observed values.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn level_snapshot(&self) -> AudioLevelSnapshot {
+        self.level_meter.snapshot()
+    }
+
+    pub fn shared_level_meter(&self) -> SharedAudioLevelMeter {
+        SharedAudioLevelMeter {
+            inner: self.level_meter.clone(),
+        }
+    }
+
+    pub fn shared_waveform_meter(&self) -> SharedAudioWaveformMeter {
+        SharedAudioWaveformMeter {
+            inner: self.waveform_meter.clone(),
+        }
+    }
+
+    /// Update VAD configuration
+    pub fn set_vad_config(&mut self, config: VadAutoStopConfig) {
+        self.vad_config = config;
+    }
+
+    /// Get the current VAD configuration
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn vad_config(&self) -> &VadAutoStopConfig {
+        &self.vad_config
+    }
+
+    /// Whether recordings stop taking audio at `max_duration_secs` (raising
+    /// `AudioCaptureEvent::MaxDurationReached`) rather than dropping their oldest audio.
+    /// Applies from the next recording; pre-roll standby always keeps a rolling window.
+    pub fn set_stop_at_max_duration(&mut self, enabled: bool) {
+        self.stop_at_max_duration = enabled;
+    }
+
+    /// Start recording audio from the default input device.
+    ///
+    /// Prefer `start_with_device_name` when you need to honor a user-selected mic.
+    ///
+    /// # Arguments
+    /// * `max_duration_secs` - Maximum recording duration in seconds (for buffer sizing)
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn start(&mut self, max_duration_secs: f32) -> Result<(), AudioCaptureError> {
+        self.start_with_device_name(max_duration_secs, None)
+    }
+
+    /// Start recording audio from a specific input device (by CPAL device name),
+    /// falling back to the system default if not found.
+    ///
+    /// If a pre-roll standby stream is running on the same device, it is promoted to the
+    /// recording instead of reopening the device, so the buffered pre-roll becomes the start
+    /// of the recording.
+    pub fn start_with_device_name(
+        &mut self,
+        max_duration_secs: f32,
+        input_device_name: Option<&str>,
+    ) -> Result<(), AudioCaptureError> {
+        let requested = normalize_device_name(input_device_name);
+        let key = MicrophoneSource::key_for(requested.as_deref());
+
+        if let Some(handle) = self.capture_handle.as_mut() {
+            if handle.mode == CaptureMode::Standby && handle.source_key == key {
+                // Speech events raised during standby belong to the pre-roll, not this session.
+                while handle.event_rx.try_recv().is_ok() {}
+                handle.mode = CaptureMode::Recording;
+                self.recording_flag.store(true, Ordering::Relaxed);
+                self.vad_queue_counters.reset();
+                self.speech_presence.start_recording();
+
+                let preroll_secs = match self.buffer.lock() {
+                    Ok(mut buf) => {
+                        buf.set_max_duration_secs(max_duration_secs);
+                        buf.set_stop_at_max(self.stop_at_max_duration);
+                        buf.duration_secs()
+                    }
+                    Err(_) => 0.0,
+                };
+                log::info!(
+                    "Audio capture started from standby with {:.2}s of pre-roll",
+                    preroll_secs
+                );
+                return Ok(());
+            }
+        }
+
+        // Release the device (or standby stream) before opening it again.
+        self.stop();
+        let source = MicrophoneSource::open(requested)?;
+        self.open_source(Box::new(source), max_duration_secs, CaptureMode::Recording);
+        log::info!("Audio capture started");
+        Ok(())
+    }
+
+    /// Keep the mic open and continuously buffer the last `preroll_secs` seconds.
+    ///
+    /// The next `start_with_device_name` call for the same device picks up this buffer as
+    /// pre-roll, which covers speech that began just before the hotkey was pressed. Does
+    /// nothing while a recording is in progress.
+    pub fn start_standby(
+        &mut self,
+        preroll_secs: f32,
+        input_device_name: Option<&str>,
+    ) -> Result<(), AudioCaptureError> {
+        let requested = normalize_device_name(input_device_name);
+        let key = MicrophoneSource::key_for(requested.as_deref());
+
+        if let Some(handle) = self.capture_handle.as_ref() {
+            if handle.mode == CaptureMode::Recording {
+                return Ok(());
+            }
+            if handle.source_key == key {
+                if let Ok(mut buf) = self.buffer.lock() {
+                    buf.set_max_duration_secs(preroll_secs);
+                }
+                return Ok(());
+            }
+        }
+
+        self.stop();
+        let source = MicrophoneSource::open(requested)?;
+        self.open_source(Box::new(source), preroll_secs, CaptureMode::Standby);
+        log::info!("Audio capture standby started ({:.1}s pre-roll)", preroll_secs);
+        Ok(())
+    }
+
+    /// Stop the pre-roll standby stream, if one is running. Recordings are left untouched.
+    pub fn stop_standby(&mut self) {
+        if self.is_standby() {
+            self.stop();
+        }
+    }
+
+    /// Check if the mic is open in pre-roll standby mode
+    pub fn is_standby(&self) -> bool {
+        self.capture_handle
+            .as_ref()
+            .is_some_and(|h| h.mode == CaptureMode::Standby)
+    }
+
+    /// Start a recording fed by `push_frames` instead of an OS input device.
+    ///
+    /// This lets integrations (a softphone plugin, a browser extension, ...) act as a
+    /// virtual microphone: pushed audio goes through the same buffer, level meters and
+    /// realtime VAD as captured audio.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn start_external(
+        &mut self,
+        sample_rate: u32,
+        channels: u16,
+        max_duration_secs: f32,
+    ) -> Result<(), AudioCaptureError> {
+        self.start_source(
+            Box::new(ExternalSource::new(sample_rate, channels)?),
+            max_duration_secs,
+        )
+    }
+
+    /// Append interleaved frames to an external recording started with `start_external`.
+    pub fn push_frames(&self, samples: &[f32], sample_rate: u32) -> Result<(), AudioCaptureError> {
+        let Some(sink) = self
+            .capture_handle
+            .as_ref()
+            .and_then(|h| h.push_sink.as_ref())
+        else {
+            return Err(AudioCaptureError::NotActive);
+        };
+        if sample_rate != self.sample_rate {
+            return Err(AudioCaptureError::SampleRateMismatch {
+                expected: self.sample_rate,
+                got: sample_rate,
+            });
+        }
+        sink.write(samples);
+        Ok(())
+    }
+
+    /// Start a recording from any `AudioSource` (stopping the current session first).
+    pub fn start_source(
+        &mut self,
+        source: Box<dyn AudioSource>,
+        max_duration_secs: f32,
+    ) -> Result<(), AudioCaptureError> {
+        self.stop();
+        let key = source.key();
+        self.open_source(source, max_duration_secs, CaptureMode::Recording);
+        log::info!(
+            "Audio capture started from {} ({} Hz, {} channels)",
+            key,
+            self.sample_rate,
+            self.channels
+        );
+        Ok(())
+    }
+
+    /// Set up a fresh buffer for `source` and run it on a new capture thread. Errors from
+    /// the source surface when the session is stopped.
+    fn open_source(&mut self, source: Box<dyn AudioSource>, buffer_secs: f32, mode: CaptureMode) {
+        let format = source.format();
+        self.sample_rate = format.sample_rate;
+        self.channels = format.channels;
+        let mut buffer = AudioBuffer::new(format.sample_rate, format.channels, buffer_secs);
+        buffer.set_stop_at_max(mode == CaptureMode::Recording && self.stop_at_max_duration);
+        self.buffer = Arc::new(StdMutex::new(buffer));
+        self.vad_queue_counters.reset();
+        self.speech_presence.reset();
+        let (command_tx, command_rx) = mpsc::channel();
+        let (event_tx, event_rx) = mpsc::channel();
+
+        let sink = CaptureSink {
+            buffer: self.buffer.clone(),
+            level_meter: self.level_meter.clone(),
+            waveform_meter: self.waveform_meter.clone(),
+            vad_queue: self.vad_config.enabled.then(|| {
+                Arc::new(VadSampleQueue::new(
+                    VAD_QUEUE_CAPACITY,
+                    self.vad_queue_counters.clone(),
+                ))
+            }),
+            channels: format.channels.max(1) as usize,
+            event_tx: event_tx.clone(),
+            max_reached: Arc::new(AtomicBool::new(false)),
+        };
+
+        let source_key = source.key();
+        let push_sink = source.is_pushed().then(|| sink.clone());
+        let vad_config = self.vad_config.vad_config.clone();
+        let speech_presence = self.speech_presence.clone();
+        let thread_handle = thread::spawn(move || {
+            // VAD runs on its own thread since webrtc-vad is not Send.
+            let vad_queue = sink.vad_queue.clone();
+            let vad_handle = vad_queue.clone().map(|queue| {
+                spawn_vad_thread(
+                    vad_config,
+                    format.sample_rate,
+                    queue,
+                    speech_presence,
+                    event_tx.clone(),
+                )
+            });
+
+            let ctx = SourceContext {
+                sink,
+                command_rx,
+                event_tx,
+            };
+            let result = source.run(&ctx);
+            if let Err(e) = &result {
+                log::error!("Audio source failed: {}", e);
+            }
+
+            // Closing the queue lets the VAD thread drain and exit.
+            if let Some(queue) = vad_queue {
+                queue.close();
+            }
+            if let Some(handle) = vad_handle {
+                let _ = handle.join();
+            }
+            result
+        });
+
+        self.recording_flag
+            .store(mode == CaptureMode::Recording, Ordering::Relaxed);
+        self.capture_handle = Some(CaptureHandle {
+            command_tx,
+            event_rx,
+            thread_handle,
+            mode,
+            source_key,
+            push_sink,
+        });
+    }
+
+    /// Stop recording and return the captured audio as WAV bytes
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn stop_and_get_wav(&mut self) -> Result<Vec<u8>, AudioCaptureError> {
+        self.stop_and_get_wav_with_noise_gate(0)
+    }
+
+    /// Stop recording and return the captured audio as WAV bytes, applying an optional noise gate.
+    ///
+    /// `noise_gate_strength` is 0..=100 where 0 disables the noise gate.
+    pub fn stop_and_get_wav_with_noise_gate(
+        &mut self,
+        noise_gate_strength: u8,
+    ) -> Result<Vec<u8>, AudioCaptureError> {
+        let (wav_bytes, _diag) = self.stop_and_get_wav_with_stats_with_noise_gate(noise_gate_strength)?;
+        Ok(wav_bytes)
+    }
+
+    /// Stop recording and return the captured audio as WAV bytes along with level stats.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn stop_and_get_wav_with_stats(
+        &mut self,
+    ) -> Result<(Vec<u8>, AudioLevelStats), AudioCaptureError> {
+        self.stop_and_get_wav_with_stats_with_noise_gate(0)
+    }
+
+    /// Stop recording and return WAV bytes + level stats, optionally applying an experimental noise gate.
+    ///
+    /// Note: stats are computed on the *raw* (pre-gate) samples.
+    pub fn stop_and_get_wav_with_stats_with_noise_gate(
+        &mut self,
+        noise_gate_strength: u8,
+    ) -> Result<(Vec<u8>, AudioLevelStats), AudioCaptureError> {
+        self.finish_session()?;
+
+        let buffer = self
+            .buffer
+            .lock()
+            .map_err(|_| AudioCaptureError::Encoding("Failed to lock buffer".to_string()))?;
+
+        let stats = buffer.level_stats();
+        let (wav_bytes, _diag) = buffer.to_wav_bytes_with_config(AudioEncodeConfig {
+            noise_gate_threshold_dbfs: {
+                let strength = clamp_u8_0_100(noise_gate_strength);
+                if strength == 0 {
+                    None
+                } else {
+                    let t = strength as f32 / 100.0;
+                    Some(lerp(-75.0, -30.0, t))
+                }
+            },
+            ..Default::default()
+        })?;
+
+        log::info!(
+            "Audio capture stopped, {} bytes captured (duration {:.2}s, rms {:.6}, peak {:.6})",
+            wav_bytes.len(),
+            stats.duration_secs,
+            stats.rms,
+            stats.peak
+        );
+
+        Ok((wav_bytes, stats))
+    }
+
+    /// Stop recording and return WAV bytes + diagnostics, applying preprocessing.
+    pub fn stop_and_get_wav_with_diagnostics(
+        &mut self,
+        cfg: AudioEncodeConfig,
+    ) -> Result<(Vec<u8>, AudioCaptureDiagnostics), AudioCaptureError> {
+        self.stop_and_take_audio()?.encode(cfg)
+    }
+
+    /// Stop recording and take the captured samples without encoding them.
+    ///
+    /// Encoding a long recording takes a while; this lets the caller do it after releasing
+    /// its own locks. The next session starts with a fresh buffer either way.
+    pub fn stop_and_take_audio(&mut self) -> Result<CapturedAudio, AudioCaptureError> {
+        self.finish_session()?;
+
+        let mut buffer = self
+            .buffer
+            .lock()
+            .map_err(|_| AudioCaptureError::Encoding("Failed to lock buffer".to_string()))?;
+        let empty = AudioBuffer::new(buffer.sample_rate, buffer.channels, 0.0);
+        Ok(CapturedAudio {
+            buffer: std::mem::replace(&mut *buffer, empty),
+            vad_queue: self.vad_queue_stats(),
+            realtime_speech_detected: self.realtime_speech_detected(),
+        })
+    }
+
+    /// Stop recording and return two WAV encodes of the same captured audio (see
+    /// `CapturedAudio::encode_before_after`).
+    ///
+    /// This is intended for UI A/B testing of audio settings.
+    pub fn stop_and_get_wav_before_after(
+        &mut self,
+        after_cfg: AudioEncodeConfig,
+    ) -> Result<(Vec<u8>, Vec<u8>, AudioCaptureDiagnostics), AudioCaptureError> {
+        self.stop_and_take_audio()?.encode_before_after(after_cfg)
+    }
+
+    /// Stop recording and measure the noise floor of what was captured.
+    ///
+    /// Used to calibrate a microphone from a few seconds of the quiet room.
+    pub fn stop_and_measure_noise(&mut self) -> Result<Option<NoiseProfile>, AudioCaptureError> {
+        self.finish_session()?;
+
+        let buffer = self
+            .buffer
+            .lock()
+            .map_err(|_| AudioCaptureError::Encoding("Failed to lock buffer".to_string()))?;
+
+        let mono = downmix_interleaved_to_mono(&buffer.samples, buffer.channels as usize);
+        Ok(NoiseProfile::measure(&mono, buffer.sample_rate))
+    }
+
+    /// Stop recording without returning audio data
+    pub fn stop(&mut self) {
+        if let Err(e) = self.finish_session() {
+            log::warn!("Audio capture ended with an error: {}", e);
+        }
+    }
+
+    /// Stop the capture thread and return how the source ended (e.g. `DeviceLost`).
+    fn finish_session(&mut self) -> Result<(), AudioCaptureError> {
+        let Some(handle) = self.capture_handle.take() else {
+            return Ok(());
+        };
+        self.recording_flag.store(false, Ordering::Relaxed);
+        log::info!("Stopping audio capture");
+        // Send stop command (ignore error if thread already stopped)
+        let _ = handle.command_tx.send(CaptureCommand::Stop);
+        handle
+            .thread_handle
+            .join()
+            .map_err(|_| AudioCaptureError::ThreadError("capture thread panicked".to_string()))?
+    }
+    /// Flag that mirrors `is_recording`, for work running outside the pipeline lock.
+    pub fn recording_flag(&self) -> Arc<AtomicBool> {
+        self.recording_flag.clone()
+    }
+
+    /// Check if currently recording
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_recording(&self) -> bool {
+        self.capture_handle
+            .as_ref()
+            .is_some_and(|h| h.mode == CaptureMode::Recording)
+    }
+
+    /// Copy of the audio recorded so far; recording continues.
+    pub fn snapshot_buffer(&self) -> Option<AudioBuffer> {
+        self.buffer.lock().ok().map(|buffer| buffer.clone())
+    }
+
+    /// Interleaved samples captured after `cursor`, for streaming them while recording
+    /// (see `AudioBuffer::samples_since`).
+    pub fn samples_since(&self, cursor: u64) -> Option<(Vec<f32>, u64)> {
+        self.buffer
+            .lock()
+            .ok()
+            .map(|buffer| buffer.samples_since(cursor))
+    }
+
+    /// Poll for VAD events (non-blocking)
+    ///
+    /// Returns the next VAD event if one is available, or None if no events are pending.
+    /// This should be called periodically to check for speech start/end events.
+    pub fn poll_vad_event(&self) -> Option<AudioCaptureEvent> {
+        if let Some(ref handle) = self.capture_handle {
+            handle.event_rx.try_recv().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Counters for the capture -> VAD sample queue of the current/last stream.
+    ///
+    /// Returns `None` when realtime VAD is disabled.
+    pub fn vad_queue_stats(&self) -> Option<VadQueueStats> {
+        self.vad_config
+            .enabled
+            .then(|| self.vad_queue_counters.snapshot())
+    }
+
+    /// Whether the realtime VAD heard speech during the current/last recording.
+    ///
+    /// Returns `None` when realtime VAD is disabled.
+    pub fn realtime_speech_detected(&self) -> Option<bool> {
+        self.vad_config
+            .enabled
+            .then(|| self.speech_presence.seen())
+    }
+
+    /// Check if VAD auto-stop is enabled
+    pub fn is_vad_auto_stop_enabled(&self) -> bool {
+        self.vad_config.enabled && self.vad_config.auto_stop
+    }
+
+    /// Get the duration of recorded audio in seconds
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn duration_secs(&self) -> f32 {
+        self.buffer
+            .lock()
+            .map(|b| b.duration_secs())
+            .unwrap_or(0.0)
+    }
+
+    /// Get the sample rate
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Get the number of channels
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+impl Default for AudioCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for AudioCapture {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Treat empty names and "default" as the system default device.
+fn normalize_device_name(name: Option<&str>) -> Option<String> {
+    name.map(str::trim)
+        .filter(|s| !s.is_empty() && *s != "default")
+        .map(str::to_string)
+}
+
+/// Run realtime VAD over queued mono samples until the queue is closed.
+fn spawn_vad_thread(
+    vad_cfg: VadConfig,
+    sample_rate: u32,
+    vad_queue: Arc<VadSampleQueue>,
+    speech_presence: Arc<SpeechPresence>,
+    event_tx: mpsc::Sender<AudioCaptureEvent>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut processor = VadFrameProcessor::new(vad_cfg, sample_rate);
+        log::info!("VAD processor initialized for {} Hz audio in dedicated thread", sample_rate);
+
+        loop {
+            match vad_queue.pop_timeout(Duration::from_millis(100)) {
+                Ok(samples) => {
+                    for event in processor.process(&samples) {
+                        let capture_event = match event {
+                            VadEvent::SpeechStart { .. } => AudioCaptureEvent::SpeechStart,
+                            VadEvent::SpeechEnd => AudioCaptureEvent::SpeechEnd,
+                            VadEvent::None => continue,
+                        };
+                        speech_presence.observe(&capture_event);
+                        let _ = event_tx.send(capture_event);
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    })
+}
+
+/// Get the list of available input devices
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn list_input_devices() -> Vec<String> {
+    let host = cpal::default_host();
+    host.input_devices()
+        .map(|devices| {
+            devices
+                .filter_map(|d| d.name().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Get information about the default input device
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn get_default_input_device_info() -> Option<(String, u32, u16)> {
+    let host = cpal::default_host();
+    let device = host.default_input_device()?;
+    let name = device.name().ok()?;
+    let config = device.default_input_config().ok()?;
+    Some((name, config.sample_rate().0, config.channels()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speech_presence_carries_ongoing_speech_into_recording() {
+        let presence = SpeechPresence::default();
+        presence.observe(&AudioCaptureEvent::SpeechStart);
+        presence.observe(&AudioCaptureEvent::SpeechEnd);
+        // Speech that ended during standby is not part of the recording.
+        presence.start_recording();
+        assert!(!presence.seen());
+
+        presence.observe(&AudioCaptureEvent::SpeechStart);
+        presence.start_recording();
+        assert!(presence.seen());
+
+        presence.reset();
+        assert!(!presence.seen());
+    }
+
+    #[test]
+    fn test_audio_buffer_creation() {
+        let buffer = AudioBuffer::new(16000, 1, 60.0);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.sample_rate(), 16000);
+        assert_eq!(buffer.channels(), 1);
+    }
+
+    #[test]
+    fn test_audio_buffer_append() {
+        let mut buffer = AudioBuffer::new(16000, 1, 60.0);
+        buffer.append(&[0.5, -0.5, 0.0]);
+        assert_eq!(buffer.len(), 3);
+    }
+
+    #[test]
+    fn test_audio_buffer_clear() {
+        let mut buffer = AudioBuffer::new(16000, 1, 60.0);
+        buffer.append(&[0.5, -0.5, 0.0]);
+        buffer.clear();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_audio_buffer_to_wav() {
+        let mut buffer = AudioBuffer::new(16000, 1, 60.0);
+        // Add some test samples
+        buffer.append(&[0.0; 1600]); // 0.1 seconds of silence
+        let wav_bytes = buffer.to_wav_bytes().expect("Failed to encode WAV");
+
+        // WAV header is 44 bytes, plus samples
+        assert!(wav_bytes.len() > 44);
+        // Check WAV magic bytes "RIFF"
+        assert_eq!(&wav_bytes[0..4], b"RIFF");
+    }
+
+    #[test]
+    fn test_audio_buffer_max_duration() {
+        let mut buffer = AudioBuffer::new(1000, 1, 1.0); // 1 second max
+        // Add 2 seconds worth of samples
+        buffer.append(&[0.0; 2000]);
+        // Should be trimmed to 1 second
+        assert_eq!(buffer.len(), 1000);
+    }
+
+    #[test]
+    fn test_audio_buffer_stop_at_max_keeps_first_frames() {
+        let mut buffer = AudioBuffer::new(1000, 2, 1.0);
+        buffer.set_stop_at_max(true);
+        assert!(buffer.append(&[0.1; 1500]));
+        assert!(!buffer.append(&[0.2; 1001]));
+        assert_eq!(buffer.len(), 2000);
+        assert_eq!(buffer.samples[1499], 0.1);
+        assert_eq!(buffer.samples[1999], 0.2);
+        assert!(!buffer.append(&[0.3; 2]));
+        assert_eq!(buffer.duration_secs(), 1.0);
+    }
+
+    #[test]
+    fn test_audio_buffer_grow_keeps_preroll() {
+        let mut buffer = AudioBuffer::new(1000, 1, 0.5);
+        buffer.append(&[0.1; 800]);
+        assert_eq!(buffer.len(), 500);
+
+        // Promoting a standby buffer to a recording keeps the buffered pre-roll.
+        buffer.set_max_duration_secs(2.0);
+        assert_eq!(buffer.len(), 500);
+        buffer.append(&[0.2; 1000]);
+        assert_eq!(buffer.len(), 1500);
+
+        buffer.set_max_duration_secs(1.0);
+        assert_eq!(buffer.len(), 1000);
+    }
+
+    #[test]
+    fn test_audio_buffer_samples_since_follows_the_rolling_window() {
+        let mut buffer = AudioBuffer::new(10, 1, 1.0);
+        buffer.append(&[0.1; 6]);
+        let (samples, cursor) = buffer.samples_since(0);
+        assert_eq!((samples.len(), cursor), (6, 6));
+
+        // 4 new samples, 2 of the old ones rolled out of the window.
+        buffer.append(&[0.2; 6]);
+        let (samples, cursor) = buffer.samples_since(cursor);
+        assert_eq!(samples, vec![0.2; 6]);
+        assert_eq!(cursor, 12);
+        assert!(buffer.samples_since(cursor).0.is_empty());
+
+        // A reader that fell behind gets what is left.
+        buffer.append(&[0.3; 10]);
+        assert_eq!(buffer.samples_since(cursor).0, vec![0.3; 10]);
+    }
+
+    #[test]
+    fn test_vad_queue_drops_oldest_when_full() {
+        let counters = Arc::new(VadQueueCounters::default());
+        let queue = VadSampleQueue::new(2, counters.clone());
+        queue.push(vec![1.0]);
+        queue.push(vec![2.0]);
+        queue.push(vec![3.0]);
+
+        assert_eq!(
+            counters.snapshot(),
+            VadQueueStats {
+                enqueued: 3,
+                dropped: 1,
+                max_depth: 2,
+            }
+        );
+        assert_eq!(queue.pop_timeout(Duration::ZERO), Ok(vec![2.0]));
+        assert_eq!(queue.pop_timeout(Duration::ZERO), Ok(vec![3.0]));
+        assert_eq!(
+            queue.pop_timeout(Duration::ZERO),
+            Err(mpsc::RecvTimeoutError::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_vad_queue_close_drains_then_disconnects() {
+        let queue = VadSampleQueue::new(4, Arc::new(VadQueueCounters::default()));
+        queue.push(vec![1.0]);
+        queue.close();
+        queue.push(vec![2.0]);
+
+        assert_eq!(queue.pop_timeout(Duration::from_millis(10)), Ok(vec![1.0]));
+        assert_eq!(
+            queue.pop_timeout(Duration::from_millis(10)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn test_normalize_device_name() {
+        assert_eq!(normalize_device_name(None), None);
+        assert_eq!(normalize_device_name(Some(" default ")), None);
+        assert_eq!(normalize_device_name(Some("")), None);
+        assert_eq!(normalize_device_name(Some(" USB Mic ")), Some("USB Mic".to_string()));
+    }
+
+    #[test]
+    fn test_compact_to_speech_segments_trims_edges() {
+        let samples: Vec<f32> = (0..100).map(|i| i as f32).collect();
+
+        let trimmed = compact_to_speech_segments(&samples, &[10..20, 60..70], 10, None);
+        assert_eq!(trimmed.len(), 60);
+        assert_eq!(trimmed.first(), Some(&10.0));
+        assert_eq!(trimmed.last(), Some(&69.0));
+
+        // No speech found: leave the audio alone.
+        assert_eq!(compact_to_speech_segments(&samples, &[], 10, None), samples);
+    }
+
+    #[test]
+    fn test_compact_to_speech_segments_shortens_long_pauses() {
+        let samples: Vec<f32> = (0..100).map(|i| i as f32).collect();
+
+        // 40-sample pause at 10 Hz, capped to 1s = 10 samples (5 from each edge).
+        let compacted = compact_to_speech_segments(&samples, &[10..20, 60..70], 10, Some(1.0));
+        assert_eq!(compacted.len(), 30);
+        assert_eq!(&compacted[10..20], &[20.0, 21.0, 22.0, 23.0, 24.0, 55.0, 56.0, 57.0, 58.0, 59.0]);
+
+        // Short pauses are kept intact.
+        let kept = compact_to_speech_segments(&samples, &[10..20, 25..30], 10, Some(1.0));
+        assert_eq!(kept, samples[10..30].to_vec());
+    }
+
+    #[test]
+    fn test_push_frames_requires_external_source() {
+        let mut capture = AudioCapture::new();
+        assert!(matches!(
+            capture.push_frames(&[0.0; 160], 16000),
+            Err(AudioCaptureError::NotActive)
+        ));
+
+        capture.start_external(16000, 2, 10.0).unwrap();
+        assert!(matches!(
+            capture.push_frames(&[0.0; 160], 48000),
+            Err(AudioCaptureError::SampleRateMismatch { expected: 16000, got: 48000 })
+        ));
+        capture.push_frames(&[0.25; 320], 16000).unwrap();
+        assert_eq!(capture.buffer.lock().unwrap().len(), 320);
+
+        capture.stop();
+        assert!(matches!(
+            capture.push_frames(&[0.0; 160], 16000),
+            Err(AudioCaptureError::NotActive)
+        ));
+    }
+
+    /// Loses its device right away, without a replacement.
+    struct UnpluggedSource;
+
+    impl AudioSource for UnpluggedSource {
+        fn key(&self) -> String {
+            "mic:usb".to_string()
+        }
+
+        fn format(&self) -> crate::audio_source::SourceFormat {
+            crate::audio_source::SourceFormat {
+                sample_rate: 16000,
+                channels: 1,
+            }
+        }
+
+        fn run(self: Box<Self>, ctx: &SourceContext) -> Result<(), AudioCaptureError> {
+            ctx.sink().write(&[0.1; 1600]);
+            ctx.emit(AudioCaptureEvent::DeviceLost {
+                device: "usb".to_string(),
+                switched_to: None,
+            });
+            Err(AudioCaptureError::DeviceLost("usb".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_device_lost_fails_the_recording() {
+        let mut capture = AudioCapture::new();
+        capture.start_source(Box::new(UnpluggedSource), 10.0).unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut event = None;
+        while event.is_none() && std::time::Instant::now() < deadline {
+            event = capture.poll_vad_event();
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(matches!(
+            event,
+            Some(AudioCaptureEvent::DeviceLost { switched_to: None, .. })
+        ));
+        assert!(matches!(
+            capture.stop_and_get_wav_with_stats(),
+            Err(AudioCaptureError::DeviceLost(device)) if device == "usb"
+        ));
+    }
+}
@@ -0,0 +1,570 @@
+//! Pluggable capture backends for `AudioCapture`.
+//!
+//! An `AudioSource` produces interleaved f32 samples into a `CaptureSink` on the capture
+//! thread; `AudioCapture` owns everything around it (buffer, meters, realtime VAD, standby
+//! and stop handling). Built-in sources:
+//! - `MicrophoneSource`: an OS input device through a CPAL stream
+//! - `ExternalSource`: frames pushed by an integration via `AudioCapture::push_frames`
+//! - `FileAudioSource`: a WAV file replayed in real time (or faster)
+//!
+//! New sources (loopback, network streams, ...) implement the trait and are started with
+//! `AudioCapture::start_source`; the pipeline doesn't need to know about them.
+
+use crate::audio_capture::{AudioCaptureError, AudioCaptureEvent, CaptureSink, SourceContext};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::SampleFormat;
+use std::io::Cursor;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Sample format delivered by a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// A producer of captured audio.
+pub trait AudioSource: Send {
+    /// Identifies what is being captured (e.g. `mic:<device>`). A pre-roll standby session
+    /// is only reused for a recording with the same key.
+    fn key(&self) -> String;
+
+    /// Format of the samples written by `run` (known before the session starts).
+    fn format(&self) -> SourceFormat;
+
+    /// Whether samples arrive through `AudioCapture::push_frames` instead of from `run`.
+    fn is_pushed(&self) -> bool {
+        false
+    }
+
+    /// Capture on the dedicated capture thread, writing to `ctx.sink()`, until
+    /// `ctx.wait_for_stop` reports a stop request.
+    fn run(self: Box<Self>, ctx: &SourceContext) -> Result<(), AudioCaptureError>;
+}
+
+/// An OS input device, read through a CPAL stream.
+pub struct MicrophoneSource {
+    requested_device: Option<String>,
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
+}
+
+impl MicrophoneSource {
+    /// Key of the microphone source for `requested_device` (`None` = system default).
+    pub fn key_for(requested_device: Option<&str>) -> String {
+        format!("mic:{}", requested_device.unwrap_or("<default>"))
+    }
+
+    /// Resolve the input device named `requested_device`, falling back to the system default
+    /// if it isn't found.
+    pub fn open(requested_device: Option<String>) -> Result<Self, AudioCaptureError> {
+        let host = cpal::default_host();
+
+        let mut selected: Option<cpal::Device> = None;
+        if let Some(name) = requested_device.as_deref() {
+            if let Ok(devices) = host.input_devices() {
+                for d in devices {
+                    let Ok(n) = d.name() else { continue };
+                    if n == name {
+                        selected = Some(d);
+                        break;
+                    }
+                }
+            }
+        }
+
+        let device = match selected {
+            Some(d) => {
+                log::info!(
+                    "Using selected input device: {}",
+                    requested_device.as_deref().unwrap_or("<unknown>")
+                );
+                d
+            }
+            None => {
+                if let Some(name) = requested_device.as_deref() {
+                    log::warn!(
+                        "Selected input device '{}' not found; falling back to default input device",
+                        name
+                    );
+                }
+                host.default_input_device()
+                    .ok_or(AudioCaptureError::NoInputDevice)?
+            }
+        };
+
+        let config = device
+            .default_input_config()
+            .map_err(|e| AudioCaptureError::DeviceConfig(e.to_string()))?;
+
+        log::info!(
+            "Audio config: {} Hz, {} channels, {:?}",
+            config.sample_rate().0,
+            config.channels(),
+            config.sample_format()
+        );
+
+        Ok(Self {
+            requested_device,
+            device,
+            config,
+        })
+    }
+}
+
+impl AudioSource for MicrophoneSource {
+    fn key(&self) -> String {
+        Self::key_for(self.requested_device.as_deref())
+    }
+
+    fn format(&self) -> SourceFormat {
+        SourceFormat {
+            sample_rate: self.config.sample_rate().0,
+            channels: self.config.channels(),
+        }
+    }
+
+    fn run(self: Box<Self>, ctx: &SourceContext) -> Result<(), AudioCaptureError> {
+        let format = self.format();
+        let (error_tx, error_rx) = mpsc::channel();
+        let mut device_name = self.device.name().unwrap_or_else(|_| "<unknown>".to_string());
+        let mut stream = play_input_stream(&self.device, self.config, ctx.sink(), error_tx.clone())?;
+
+        loop {
+            let mut last_writes = ctx.sink().writes();
+            let mut last_progress = Instant::now();
+            loop {
+                if ctx.wait_for_stop(Duration::from_millis(100)) {
+                    // Stream is dropped here, stopping capture
+                    return Ok(());
+                }
+
+                let mut lost = false;
+                while let Ok(err) = error_rx.try_recv() {
+                    match err {
+                        cpal::StreamError::DeviceNotAvailable => lost = true,
+                        other => log::error!("Audio stream error: {}", other),
+                    }
+                }
+
+                // Some backends stop calling back without reporting anything.
+                let writes = ctx.sink().writes();
+                if writes != last_writes {
+                    last_writes = writes;
+                    last_progress = Instant::now();
+                } else if last_progress.elapsed() >= STALL_TIMEOUT {
+                    log::warn!(
+                        "No audio from '{}' for {:.1}s",
+                        device_name,
+                        STALL_TIMEOUT.as_secs_f32()
+                    );
+                    lost = true;
+                }
+
+                if lost {
+                    break;
+                }
+            }
+            drop(stream);
+            log::warn!("Input device '{}' lost", device_name);
+
+            match recover_default_device(ctx, format, error_tx.clone()) {
+                Recovery::Switched(new_stream, name) => {
+                    log::info!("Audio capture switched to '{}'", name);
+                    ctx.emit(AudioCaptureEvent::DeviceLost {
+                        device: device_name,
+                        switched_to: Some(name.clone()),
+                    });
+                    device_name = name;
+                    stream = new_stream;
+                }
+                Recovery::Stopped => return Ok(()),
+                Recovery::Failed => {
+                    ctx.emit(AudioCaptureEvent::DeviceLost {
+                        device: device_name.clone(),
+                        switched_to: None,
+                    });
+                    return Err(AudioCaptureError::DeviceLost(device_name));
+                }
+            }
+        }
+    }
+}
+
+/// How long a stream may go without delivering audio before its device is considered lost.
+const STALL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How many times (`RECOVERY_INTERVAL` apart) to look for a replacement device; the OS may
+/// take a moment to pick a new default after a disconnect.
+const RECOVERY_ATTEMPTS: u32 = 10;
+const RECOVERY_INTERVAL: Duration = Duration::from_millis(200);
+
+enum Recovery {
+    Switched(cpal::Stream, String),
+    Stopped,
+    Failed,
+}
+
+/// Look for a default input device the session can continue on. It must deliver the same
+/// sample rate and channel count, since the recording buffer keeps its format.
+fn recover_default_device(
+    ctx: &SourceContext,
+    format: SourceFormat,
+    error_tx: mpsc::Sender<cpal::StreamError>,
+) -> Recovery {
+    for _ in 0..RECOVERY_ATTEMPTS {
+        if ctx.wait_for_stop(RECOVERY_INTERVAL) {
+            return Recovery::Stopped;
+        }
+
+        let Some(device) = cpal::default_host().default_input_device() else {
+            continue;
+        };
+        let Ok(config) = device.default_input_config() else {
+            continue;
+        };
+        if config.sample_rate().0 != format.sample_rate || config.channels() != format.channels {
+            log::warn!(
+                "Default input device has a different format ({} Hz, {} channels); not switching",
+                config.sample_rate().0,
+                config.channels()
+            );
+            return Recovery::Failed;
+        }
+
+        // Building the stream is the real availability check: right after a disconnect the
+        // lost device can still be reported as the default.
+        match play_input_stream(&device, config, ctx.sink(), error_tx.clone()) {
+            Ok(stream) => {
+                let name = device.name().unwrap_or_else(|_| "<unknown>".to_string());
+                return Recovery::Switched(stream, name);
+            }
+            Err(e) => log::debug!("Replacement input device not ready: {}", e),
+        }
+    }
+    Recovery::Failed
+}
+
+/// Build and start a CPAL input stream writing to `sink`; stream errors go to `error_tx`.
+fn play_input_stream(
+    device: &cpal::Device,
+    config: cpal::SupportedStreamConfig,
+    sink: &CaptureSink,
+    error_tx: mpsc::Sender<cpal::StreamError>,
+) -> Result<cpal::Stream, AudioCaptureError> {
+    use cpal::Sample;
+
+    let err_fn = move |err| {
+        let _ = error_tx.send(err);
+    };
+
+    let sample_format = config.sample_format();
+    let stream_config: cpal::StreamConfig = config.into();
+    let stream = match sample_format {
+        SampleFormat::F32 => {
+            let sink = sink.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| sink.write(data),
+                err_fn,
+                None,
+            )
+        }
+        SampleFormat::I16 => {
+            let sink = sink.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                    let samples: Vec<f32> = data.iter().map(|&s| s.to_float_sample()).collect();
+                    sink.write(&samples);
+                },
+                err_fn,
+                None,
+            )
+        }
+        SampleFormat::U16 => {
+            let sink = sink.clone();
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[u16], _: &cpal::InputCallbackInfo| {
+                    let samples: Vec<f32> = data.iter().map(|&s| s.to_float_sample()).collect();
+                    sink.write(&samples);
+                },
+                err_fn,
+                None,
+            )
+        }
+        _ => {
+            return Err(AudioCaptureError::DeviceConfig(format!(
+                "Unsupported sample format: {:?}",
+                sample_format
+            )));
+        }
+    }
+    .map_err(|e| AudioCaptureError::StreamBuild(e.to_string()))?;
+
+    stream
+        .play()
+        .map_err(|e| AudioCaptureError::StreamStart(e.to_string()))?;
+    Ok(stream)
+}
+
+/// Frames pushed from outside (a softphone plugin, a browser extension, ...) through
+/// `AudioCapture::push_frames`; `run` only waits for the session to end.
+pub struct ExternalSource {
+    format: SourceFormat,
+}
+
+impl ExternalSource {
+    pub fn new(sample_rate: u32, channels: u16) -> Result<Self, AudioCaptureError> {
+        if sample_rate == 0 || channels == 0 {
+            return Err(AudioCaptureError::DeviceConfig(format!(
+                "Invalid external audio format: {} Hz, {} channels",
+                sample_rate, channels
+            )));
+        }
+        Ok(Self {
+            format: SourceFormat {
+                sample_rate,
+                channels,
+            },
+        })
+    }
+}
+
+impl AudioSource for ExternalSource {
+    fn key(&self) -> String {
+        "external".to_string()
+    }
+
+    fn format(&self) -> SourceFormat {
+        self.format
+    }
+
+    fn is_pushed(&self) -> bool {
+        true
+    }
+
+    fn run(self: Box<Self>, ctx: &SourceContext) -> Result<(), AudioCaptureError> {
+        while !ctx.wait_for_stop(Duration::from_millis(100)) {}
+        Ok(())
+    }
+}
+
+/// Fastest accepted `FileAudioSource` replay speed. Faster replay would outrun the VAD
+/// thread, whose queue then drops audio.
+pub const MAX_REPLAY_SPEED: f32 = 20.0;
+
+/// Length of the chunks a replayed file is fed in, similar to a CPAL callback.
+const REPLAY_CHUNK_SECS: f32 = 0.02;
+
+/// A WAV file "played" into a capture session in real time (or faster), standing in for a
+/// microphone: the samples go through the same buffer, meters and realtime VAD, so auto-stop
+/// and streaming can be exercised without audio hardware.
+///
+/// `AudioCaptureEvent::SourceEnded` is raised when the file has been played to the end; the
+/// session then stays open (silent) until it is stopped.
+#[derive(Debug, Clone)]
+pub struct FileAudioSource {
+    /// Interleaved samples in [-1, 1].
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: u16,
+    speed: f32,
+}
+
+impl FileAudioSource {
+    /// Decode a WAV file (integer or float PCM). Replays in real time by default.
+    pub fn from_wav_bytes(wav: &[u8]) -> Result<Self, AudioCaptureError> {
+        let reader = hound::WavReader::new(Cursor::new(wav))
+            .map_err(|e| AudioCaptureError::AudioFile(e.to_string()))?;
+        let spec = reader.spec();
+        if spec.sample_rate == 0 || spec.channels == 0 {
+            return Err(AudioCaptureError::AudioFile(format!(
+                "Invalid WAV format: {} Hz, {} channels",
+                spec.sample_rate, spec.channels
+            )));
+        }
+
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .collect::<Result<Vec<_>, _>>(),
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample.clamp(1, 32) - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / scale))
+                    .collect::<Result<Vec<_>, _>>()
+            }
+        }
+        .map_err(|e| AudioCaptureError::AudioFile(e.to_string()))?;
+
+        Ok(Self {
+            samples,
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            speed: 1.0,
+        })
+    }
+
+    pub fn from_path(path: &std::path::Path) -> Result<Self, AudioCaptureError> {
+        let wav = std::fs::read(path)
+            .map_err(|e| AudioCaptureError::AudioFile(format!("{}: {}", path.display(), e)))?;
+        Self::from_wav_bytes(&wav)
+    }
+
+    /// Replay `speed` times faster than real time (clamped to `(0, MAX_REPLAY_SPEED]`).
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = if speed.is_finite() && speed > 0.0 {
+            speed.min(MAX_REPLAY_SPEED)
+        } else {
+            1.0
+        };
+        self
+    }
+
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / (self.sample_rate as f32 * self.channels as f32)
+    }
+}
+
+impl AudioSource for FileAudioSource {
+    fn key(&self) -> String {
+        "file".to_string()
+    }
+
+    fn format(&self) -> SourceFormat {
+        SourceFormat {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+        }
+    }
+
+    fn run(self: Box<Self>, ctx: &SourceContext) -> Result<(), AudioCaptureError> {
+        log::info!(
+            "Audio file replay: {:.2}s at {}x",
+            self.duration_secs(),
+            self.speed
+        );
+
+        let chunk_frames = ((self.sample_rate as f32 * REPLAY_CHUNK_SECS) as usize).max(1);
+        let interval = Duration::from_secs_f32(REPLAY_CHUNK_SECS / self.speed);
+        let started = Instant::now();
+        for (i, chunk) in self
+            .samples
+            .chunks(chunk_frames * self.channels as usize)
+            .enumerate()
+        {
+            ctx.sink().write(chunk);
+            // Pace against the start time so sleep overshoot doesn't accumulate.
+            let due = started + interval * (i as u32 + 1);
+            if ctx.wait_for_stop(due.saturating_duration_since(Instant::now())) {
+                return Ok(());
+            }
+        }
+
+        log::info!("Audio file replay finished");
+        ctx.emit(AudioCaptureEvent::SourceEnded);
+        while !ctx.wait_for_stop(Duration::from_secs(1)) {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_capture::{AudioBuffer, AudioCapture};
+    use std::thread;
+
+    #[test]
+    fn test_file_source_replays_into_buffer() {
+        let mut recorded = AudioBuffer::new(16000, 1, 10.0);
+        recorded.append(&[0.5; 8000]);
+        let wav = recorded.to_wav_bytes().unwrap();
+
+        let source = FileAudioSource::from_wav_bytes(&wav).unwrap().with_speed(MAX_REPLAY_SPEED);
+        assert!((source.duration_secs() - 0.5).abs() < 1e-6);
+
+        let mut capture = AudioCapture::new();
+        capture.start_source(Box::new(source), 10.0).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut ended = false;
+        while !ended && Instant::now() < deadline {
+            ended = matches!(capture.poll_vad_event(), Some(AudioCaptureEvent::SourceEnded));
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(ended);
+        assert!(capture.is_recording());
+
+        let (_wav, stats) = capture.stop_and_get_wav_with_stats().unwrap();
+        assert!((stats.duration_secs - 0.5).abs() < 1e-3);
+        assert!((stats.peak - 0.5).abs() < 1e-3);
+        assert!(FileAudioSource::from_wav_bytes(b"not a wav").is_err());
+    }
+
+    /// Writes a fixed block of samples, then waits to be stopped.
+    struct BlockSource(Vec<f32>);
+
+    impl AudioSource for BlockSource {
+        fn key(&self) -> String {
+            "block".to_string()
+        }
+
+        fn format(&self) -> SourceFormat {
+            SourceFormat {
+                sample_rate: 8000,
+                channels: 2,
+            }
+        }
+
+        fn run(self: Box<Self>, ctx: &SourceContext) -> Result<(), AudioCaptureError> {
+            ctx.sink().write(&self.0);
+            ctx.emit(AudioCaptureEvent::SourceEnded);
+            while !ctx.wait_for_stop(Duration::from_millis(10)) {}
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_source_feeds_capture() {
+        let mut capture = AudioCapture::new();
+        capture
+            .start_source(Box::new(BlockSource(vec![0.25; 1600])), 10.0)
+            .unwrap();
+        assert_eq!((capture.sample_rate(), capture.channels()), (8000, 2));
+
+        // Pushing is only for pushed sources.
+        assert!(matches!(
+            capture.push_frames(&[0.0; 16], 8000),
+            Err(AudioCaptureError::NotActive)
+        ));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while capture.poll_vad_event().is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        let (_wav, stats) = capture.stop_and_get_wav_with_stats().unwrap();
+        assert!((stats.duration_secs - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_taken_audio_encodes_after_stop() {
+        let mut capture = AudioCapture::new();
+        capture
+            .start_source(Box::new(BlockSource(vec![0.25; 1600])), 10.0)
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while capture.poll_vad_event().is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let audio = capture.stop_and_take_audio().unwrap();
+        assert_eq!(capture.duration_secs(), 0.0);
+
+        let (wav, diagnostics) = audio.encode(Default::default()).unwrap();
+        assert!((diagnostics.stats.duration_secs - 0.1).abs() < 1e-3);
+        assert!(FileAudioSource::from_wav_bytes(&wav).is_ok());
+    }
+}
@@ -0,0 +1,163 @@
+//! Corrections learned from transcripts the user edited before output.
+//!
+//! Each edit in the review window (see `commands::review`) is diffed against the text the
+//! pipeline produced, and every replaced phrase is counted as a `heard -> corrected` pair.
+//! A pair made `LEARN_AFTER` times is applied to later STT output automatically, before
+//! any LLM step. Pairs are kept in settings.json under `learned_corrections`.
+
+use crate::text_diff::{word_diff, DiffOp};
+use serde::{Deserialize, Serialize};
+
+/// Times the same correction has to be made before it is applied automatically.
+pub const LEARN_AFTER: u32 = 2;
+
+/// Longer rewrites are edits of the content, not misrecognitions.
+const MAX_PHRASE_WORDS: usize = 3;
+
+/// Corrections kept; the least used ones are dropped first.
+const MAX_CORRECTIONS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LearnedCorrection {
+    /// What the STT provider produced (matched case-insensitively, on word boundaries).
+    pub heard: String,
+    pub corrected: String,
+    /// How often the user made this correction.
+    pub count: u32,
+}
+
+/// Phrases replaced between `original` and `edited`, as `(heard, corrected)` pairs.
+///
+/// Pure insertions and deletions are skipped, as are changes to punctuation or of more
+/// than a few words.
+pub fn extract(original: &str, edited: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut heard = String::new();
+    let mut corrected = String::new();
+
+    let mut flush = |heard: &mut String, corrected: &mut String| {
+        let h = normalize(heard);
+        let c = normalize(corrected);
+        if is_learnable(&h) && is_learnable(&c) && h != c {
+            pairs.push((h, c));
+        }
+        heard.clear();
+        corrected.clear();
+    };
+
+    for segment in word_diff(original, edited) {
+        match segment.op {
+            // Whitespace in the middle of a replaced phrase still belongs to it.
+            DiffOp::Equal if segment.text.trim().is_empty() => {
+                heard.push_str(&segment.text);
+                corrected.push_str(&segment.text);
+            }
+            DiffOp::Equal => flush(&mut heard, &mut corrected),
+            DiffOp::Delete => heard.push_str(&segment.text),
+            DiffOp::Insert => corrected.push_str(&segment.text),
+        }
+    }
+    flush(&mut heard, &mut corrected);
+    pairs
+}
+
+fn normalize(phrase: &str) -> String {
+    phrase.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A phrase of a few words that starts and ends with a letter or digit.
+fn is_learnable(phrase: &str) -> bool {
+    let edges_alphanumeric = phrase.chars().next().is_some_and(char::is_alphanumeric)
+        && phrase.chars().next_back().is_some_and(char::is_alphanumeric);
+    edges_alphanumeric && phrase.split_whitespace().count() <= MAX_PHRASE_WORDS
+}
+
+/// Count the corrections made when `original` was edited into `edited`.
+///
+/// Returns whether anything was learned.
+pub fn record(corrections: &mut Vec<LearnedCorrection>, original: &str, edited: &str) -> bool {
+    let pairs = extract(original, edited);
+    for (heard, corrected) in &pairs {
+        match corrections
+            .iter_mut()
+            .find(|c| c.heard.eq_ignore_ascii_case(heard) && c.corrected == *corrected)
+        {
+            Some(existing) => existing.count = existing.count.saturating_add(1),
+            None => corrections.push(LearnedCorrection {
+                heard: heard.clone(),
+                corrected: corrected.clone(),
+                count: 1,
+            }),
+        }
+    }
+
+    if corrections.len() > MAX_CORRECTIONS {
+        // Stable sort: among equal counts, the oldest go first.
+        corrections.sort_by_key(|c| std::cmp::Reverse(c.count));
+        corrections.truncate(MAX_CORRECTIONS);
+    }
+    !pairs.is_empty()
+}
+
+/// Apply the corrections made at least `LEARN_AFTER` times to `text`.
+///
+/// When the same phrase was corrected in different ways, the most frequent one wins.
+pub fn apply(text: &str, corrections: &[LearnedCorrection]) -> String {
+    let mut learned: Vec<&LearnedCorrection> =
+        corrections.iter().filter(|c| c.count >= LEARN_AFTER).collect();
+    learned.sort_by_key(|c| std::cmp::Reverse(c.count));
+
+    let mut applied: Vec<String> = Vec::new();
+    let mut text = text.to_string();
+    for correction in learned {
+        let heard = correction.heard.to_lowercase();
+        if applied.contains(&heard) {
+            continue;
+        }
+        let pattern = format!(r"(?i)\b{}\b", regex::escape(&correction.heard));
+        if let Ok(re) = regex::Regex::new(&pattern) {
+            text = re
+                .replace_all(&text, regex::NoExpand(&correction.corrected))
+                .into_owned();
+        }
+        applied.push(heard);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_replaced_phrases() {
+        assert_eq!(
+            extract("I work at ant row pick on cloud.", "I work at Anthropic on Claude."),
+            vec![
+                ("ant row pick".to_string(), "Anthropic".to_string()),
+                ("cloud".to_string(), "Claude".to_string()),
+            ]
+        );
+
+        // Punctuation, insertions and long rewrites are not misrecognitions.
+        assert!(extract("Hello world", "Hello, world!").is_empty());
+        assert!(extract("Send it", "Send it now").is_empty());
+        assert!(extract("one two three four", "five six seven eight").is_empty());
+    }
+
+    #[test]
+    fn test_corrections_apply_once_learned() {
+        let mut corrections = Vec::new();
+        assert!(record(&mut corrections, "ask cloud", "ask Claude"));
+        assert_eq!(apply("Cloud is here", &corrections), "Cloud is here");
+
+        assert!(record(&mut corrections, "cloud said", "Claude said"));
+        assert_eq!(corrections[0].count, 2);
+        assert_eq!(
+            apply("Cloud is here, cloudy skies", &corrections),
+            "Claude is here, cloudy skies"
+        );
+
+        assert!(!record(&mut corrections, "same", "same"));
+    }
+}
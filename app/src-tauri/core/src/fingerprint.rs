@@ -0,0 +1,363 @@
+//! Fast content fingerprints for recorded audio.
+//!
+//! A fingerprint is a 64-bit FNV-1a hash of the PCM payload of a WAV file (the header is
+//! skipped so identical samples always hash the same). It is not cryptographic; it only
+//! needs to be cheap and stable so we can:
+//! - detect the same audio being submitted twice in quick succession, and
+//! - key cached provider responses for identical audio.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+#[cfg(test)]
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Submissions of identical audio within this window are treated as accidental duplicates.
+pub const DUPLICATE_SUBMISSION_WINDOW: Duration = Duration::from_secs(5);
+
+/// Maximum number of STT responses kept in memory.
+const STT_RESPONSE_CACHE_CAPACITY: usize = 32;
+
+/// Maximum number of STT responses kept in the on-disk cache.
+const PERSISTENT_STT_RESPONSE_CACHE_CAPACITY: usize = 500;
+
+/// Changes to the on-disk cache within this window are written together.
+const STT_RESPONSE_CACHE_SAVE_DELAY: Duration = Duration::from_secs(2);
+
+type CacheEntries = VecDeque<(String, String)>;
+
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Return the `data` chunk of a RIFF/WAVE file, or the whole input if it isn't one.
+fn wav_data_payload(wav: &[u8]) -> &[u8] {
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return wav;
+    }
+
+    let mut pos = 12;
+    while pos + 8 <= wav.len() {
+        let id = &wav[pos..pos + 4];
+        let size = u32::from_le_bytes([wav[pos + 4], wav[pos + 5], wav[pos + 6], wav[pos + 7]])
+            as usize;
+        let body_start = pos + 8;
+        if id == b"data" {
+            let end = body_start.saturating_add(size).min(wav.len());
+            return &wav[body_start..end];
+        }
+        // Chunks are padded to an even size.
+        pos = body_start.saturating_add(size).saturating_add(size & 1);
+    }
+
+    wav
+}
+
+/// Compute the fingerprint of a WAV recording as a 16-char lowercase hex string.
+pub fn fingerprint_wav(wav: &[u8]) -> String {
+    format!("{:016x}", fnv1a64(wav_data_payload(wav)))
+}
+
+/// Recently submitted fingerprints, used to catch accidental double submissions.
+#[derive(Debug)]
+pub struct RecentSubmissions {
+    entries: VecDeque<(String, Instant)>,
+    window: Duration,
+}
+
+impl RecentSubmissions {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            window,
+        }
+    }
+
+    /// Record a submission; returns `true` if the same fingerprint was seen within the window.
+    pub fn check_and_record(&mut self, fingerprint: &str, now: Instant) -> bool {
+        let window = self.window;
+        self.entries
+            .retain(|(_, at)| now.saturating_duration_since(*at) <= window);
+
+        let duplicate = self.entries.iter().any(|(fp, _)| fp == fingerprint);
+        self.entries.push_back((fingerprint.to_string(), now));
+        duplicate
+    }
+}
+
+impl Default for RecentSubmissions {
+    fn default() -> Self {
+        Self::new(DUPLICATE_SUBMISSION_WINDOW)
+    }
+}
+
+/// Cache of STT responses keyed by fingerprint + provider + model.
+///
+/// By default it only lives in memory. A cache opened with `load` is also written to disk
+/// after inserts, so re-running a saved recording after a restart (e.g. with only the LLM
+/// prompt changed) still skips the STT call. Writes happen on a background thread, at most
+/// once per `STT_RESPONSE_CACHE_SAVE_DELAY`, so callers holding a lock never wait for the
+/// disk; a change made just before the app exits may not be saved.
+#[derive(Debug)]
+pub struct SttResponseCache {
+    entries: CacheEntries,
+    capacity: usize,
+    path: Option<PathBuf>,
+    writer: Option<CacheWriter>,
+}
+
+impl Default for SttResponseCache {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: STT_RESPONSE_CACHE_CAPACITY,
+            path: None,
+            writer: None,
+        }
+    }
+}
+
+impl SttResponseCache {
+    /// Open the on-disk cache at `path` (empty if it doesn't exist yet).
+    pub fn load(path: PathBuf) -> Self {
+        Self::load_with_save_delay(path, STT_RESPONSE_CACHE_SAVE_DELAY)
+    }
+
+    fn load_with_save_delay(path: PathBuf, save_delay: Duration) -> Self {
+        let entries: CacheEntries = crate::persist::read_with_recovery(&path).unwrap_or_default();
+        let mut cache = Self {
+            entries,
+            capacity: PERSISTENT_STT_RESPONSE_CACHE_CAPACITY,
+            writer: CacheWriter::spawn(path.clone(), save_delay),
+            path: Some(path),
+        };
+        cache.evict();
+        cache
+    }
+
+    /// File the cache is saved to, if it is persistent.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn key(fingerprint: &str, provider_key: &str) -> String {
+        format!("{}::{}", fingerprint, provider_key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, text)| text.clone())
+    }
+
+    pub fn insert(&mut self, key: String, text: String) {
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push_back((key, text));
+        self.evict();
+        self.save();
+    }
+
+    /// Drop all entries (and the on-disk copy).
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    fn save(&self) {
+        if let Some(writer) = &self.writer {
+            writer.queue(self.entries.clone());
+        }
+    }
+
+    /// Write any pending change and stop the writer thread.
+    #[cfg(test)]
+    fn close(mut self) {
+        if let Some(writer) = self.writer.take() {
+            writer.close();
+        }
+    }
+}
+
+/// Background writer of a persistent `SttResponseCache`.
+///
+/// Each change sends a snapshot of the entries; the thread writes the latest snapshot once
+/// the save delay after the first unsaved change has passed. It exits (after a last write)
+/// when the cache is dropped.
+#[derive(Debug)]
+struct CacheWriter {
+    tx: mpsc::Sender<CacheEntries>,
+    /// Only joined by tests; the app leaves the thread to exit on its own.
+    #[cfg(test)]
+    thread: JoinHandle<()>,
+}
+
+impl CacheWriter {
+    fn spawn(path: PathBuf, save_delay: Duration) -> Option<Self> {
+        let (tx, rx) = mpsc::channel::<CacheEntries>();
+        let thread = std::thread::Builder::new()
+            .name("stt-cache-writer".to_string())
+            .spawn(move || {
+                while let Ok(mut latest) = rx.recv() {
+                    let deadline = Instant::now() + save_delay;
+                    loop {
+                        let wait = deadline.saturating_duration_since(Instant::now());
+                        match rx.recv_timeout(wait) {
+                            Ok(newer) => latest = newer,
+                            Err(_) => break,
+                        }
+                    }
+                    write_entries(&path, &latest);
+                }
+            });
+        match thread {
+            #[cfg(test)]
+            Ok(thread) => Some(Self { tx, thread }),
+            #[cfg(not(test))]
+            Ok(_) => Some(Self { tx }),
+            Err(e) => {
+                log::warn!("Failed to start STT cache writer; cache stays in memory: {}", e);
+                None
+            }
+        }
+    }
+
+    fn queue(&self, entries: CacheEntries) {
+        let _ = self.tx.send(entries);
+    }
+
+    #[cfg(test)]
+    fn close(self) {
+        drop(self.tx);
+        let _ = self.thread.join();
+    }
+}
+
+fn write_entries(path: &Path, entries: &CacheEntries) {
+    let result = serde_json::to_vec(entries)
+        .map_err(|e| e.to_string())
+        .and_then(|json| crate::persist::write_atomic(path, &json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("Failed to save STT response cache to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav_with_samples(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+        let data_len = (samples.len() * 2) as u32;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(b"fmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for s in samples {
+            wav.extend_from_slice(&s.to_le_bytes());
+        }
+        wav
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_header() {
+        let a = wav_with_samples(&[1, 2, 3, 4], 16000);
+        let b = wav_with_samples(&[1, 2, 3, 4], 48000);
+        let c = wav_with_samples(&[1, 2, 3, 5], 16000);
+
+        assert_eq!(fingerprint_wav(&a), fingerprint_wav(&b));
+        assert_ne!(fingerprint_wav(&a), fingerprint_wav(&c));
+        assert_eq!(fingerprint_wav(&a).len(), 16);
+    }
+
+    #[test]
+    fn test_recent_submissions_window() {
+        let mut recent = RecentSubmissions::new(Duration::from_secs(5));
+        let t0 = Instant::now();
+
+        assert!(!recent.check_and_record("abc", t0));
+        assert!(recent.check_and_record("abc", t0 + Duration::from_secs(1)));
+        assert!(!recent.check_and_record("def", t0 + Duration::from_secs(2)));
+        assert!(!recent.check_and_record("abc", t0 + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_stt_response_cache_is_bounded() {
+        let mut cache = SttResponseCache::default();
+        for i in 0..(STT_RESPONSE_CACHE_CAPACITY + 1) {
+            cache.insert(SttResponseCache::key(&i.to_string(), "groq::default"), i.to_string());
+        }
+
+        assert!(cache.get(&SttResponseCache::key("0", "groq::default")).is_none());
+        assert_eq!(
+            cache.get(&SttResponseCache::key("1", "groq::default")),
+            Some("1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_persistent_stt_response_cache_survives_reload() {
+        let dir = std::env::temp_dir().join(format!("tangerine-stt-cache-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stt_cache.json");
+        let key = SttResponseCache::key("abc", "groq::whisper-large-v3");
+
+        let mut cache = SttResponseCache::load(path.clone());
+        assert!(cache.get(&key).is_none());
+        cache.insert(key.clone(), "hello world".to_string());
+        cache.close();
+
+        let mut reloaded = SttResponseCache::load(path.clone());
+        assert_eq!(reloaded.get(&key), Some("hello world".to_string()));
+
+        reloaded.clear();
+        reloaded.close();
+        assert!(SttResponseCache::load(path).get(&key).is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_persistent_stt_response_cache_saves_in_background() {
+        let dir = std::env::temp_dir().join(format!("tangerine-stt-cache-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stt_cache.json");
+
+        let mut cache = SttResponseCache::load_with_save_delay(path.clone(), Duration::from_secs(60));
+        for i in 0..3 {
+            cache.insert(SttResponseCache::key(&i.to_string(), "groq::default"), i.to_string());
+        }
+        // Nothing is written until the delay passes or the cache goes away.
+        assert!(!path.exists());
+
+        cache.close();
+        let reloaded = SttResponseCache::load(path);
+        assert_eq!(
+            reloaded.get(&SttResponseCache::key("2", "groq::default")),
+            Some("2".to_string())
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,149 @@
+//! Drop text that Whisper-family models invent for silent or near-silent audio.
+//!
+//! Whisper was trained on subtitled video, so given little or no speech it tends to emit
+//! sign-offs and caption credits ("Thanks for watching!", "Subtitles by the Amara.org
+//! community"). A transcript is only treated as a hallucination when it consists entirely of
+//! such phrases *and* the audio was short or quiet; a user who actually says "thanks for
+//! watching" into the mic keeps their text.
+
+/// Phrases Whisper is known to produce on silence (compared after `normalize`).
+pub const DEFAULT_HALLUCINATION_PHRASES: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thanks for watching and see you next time",
+    "thank you so much for watching",
+    "thank you for listening",
+    "please subscribe",
+    "please like and subscribe",
+    "like and subscribe",
+    "dont forget to like and subscribe",
+    "see you in the next video",
+    "see you next time",
+    "subtitles by the amara org community",
+    "subtitles by",
+    "subtitles made by the amara org community",
+    "transcription by castingwords",
+    "transcribed by",
+    "captions by",
+    "closed captioning by",
+    "you",
+];
+
+/// Recordings shorter than this are treated as silence for filtering purposes.
+pub const HALLUCINATION_MAX_DURATION_SECS: f32 = 2.0;
+
+/// Recordings with an RMS level below this (dBFS) are treated as silence.
+pub const HALLUCINATION_MAX_RMS_DBFS: f32 = -45.0;
+
+/// Lowercase, drop punctuation and collapse whitespace.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() {
+                Some(c.to_ascii_lowercase())
+            } else if c.is_whitespace() || c == '.' || c == '-' {
+                // "Amara.org" -> "amara org"
+                Some(' ')
+            } else {
+                None
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// True if `text` is made up only of (possibly repeated) `phrases`.
+fn consists_of_phrases(text: &str, phrases: &[String]) -> bool {
+    let mut rest = text;
+    while !rest.is_empty() {
+        let next = phrases.iter().filter(|p| !p.is_empty()).find_map(|phrase| {
+            let after = rest.strip_prefix(phrase.as_str())?;
+            // Only match whole words.
+            if after.is_empty() {
+                Some(after)
+            } else {
+                after.strip_prefix(' ')
+            }
+        });
+        match next {
+            Some(after) => rest = after,
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Whether `text` looks like a hallucination for audio of this length and level.
+///
+/// `extra_phrases` are user-configured additions to `DEFAULT_HALLUCINATION_PHRASES`.
+pub fn is_likely_hallucination(
+    text: &str,
+    extra_phrases: &[String],
+    duration_secs: f32,
+    rms_dbfs: f32,
+) -> bool {
+    let sounds_silent =
+        duration_secs < HALLUCINATION_MAX_DURATION_SECS || rms_dbfs < HALLUCINATION_MAX_RMS_DBFS;
+    if !sounds_silent {
+        return false;
+    }
+
+    let text = normalize(text);
+    if text.is_empty() {
+        return false;
+    }
+
+    // Longest first, so "thanks for watching and see you next time" wins over its prefix.
+    let mut phrases: Vec<String> = DEFAULT_HALLUCINATION_PHRASES
+        .iter()
+        .map(|p| p.to_string())
+        .chain(extra_phrases.iter().map(|p| normalize(p)))
+        .collect();
+    phrases.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    consists_of_phrases(&text, &phrases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drops_repeated_phrases_on_quiet_audio() {
+        assert!(is_likely_hallucination(
+            "Thanks for watching! Thanks for watching!",
+            &[],
+            5.0,
+            -60.0
+        ));
+        assert!(is_likely_hallucination(
+            "Subtitles by the Amara.org community",
+            &[],
+            1.0,
+            -20.0
+        ));
+        assert!(is_likely_hallucination(
+            "Bye bye.",
+            &["bye bye".to_string()],
+            1.0,
+            -20.0
+        ));
+    }
+
+    #[test]
+    fn test_keeps_real_speech() {
+        // Loud, long recording: the user really said it.
+        assert!(!is_likely_hallucination("Thanks for watching!", &[], 4.0, -20.0));
+        // Other words around a known phrase.
+        assert!(!is_likely_hallucination(
+            "Thanks for watching the kids tonight",
+            &[],
+            1.0,
+            -60.0
+        ));
+        // "you" must match a whole word, not a prefix.
+        assert!(!is_likely_hallucination("youtube", &[], 1.0, -60.0));
+    }
+}
@@ -0,0 +1,156 @@
+//! Output hook: a user-provided command that transforms the transcript between LLM
+//! formatting and output.
+//!
+//! The command gets a JSON object on stdin and must print one on stdout:
+//!
+//! ```text
+//! stdin:  {"text": "...", "raw_text": "...", "app": "code.exe"}
+//! stdout: {"text": "..."}
+//! ```
+//!
+//! `text` is the formatted transcript, `raw_text` the transcript before LLM formatting and
+//! `app` the executable of the foreground app (or null). Returning an empty `text` drops
+//! the transcript. If the command fails, times out or prints anything else, the
+//! transcript is output unchanged.
+
+use serde::{Deserialize, Serialize};
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// The `output_hook` setting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// Program to run; the hook is off while empty.
+    pub command: String,
+    /// Arguments passed to `command`.
+    pub args: Vec<String>,
+    /// The command is killed (and the transcript kept) after this long.
+    pub timeout_ms: u64,
+}
+
+impl Default for HookConfig {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            args: Vec::new(),
+            timeout_ms: 5000,
+        }
+    }
+}
+
+impl HookConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.command.trim().is_empty()
+    }
+}
+
+/// What the hook receives on stdin.
+#[derive(Debug, Serialize)]
+pub struct HookInput<'a> {
+    pub text: &'a str,
+    pub raw_text: &'a str,
+    pub app: Option<String>,
+}
+
+/// What the hook prints on stdout.
+#[derive(Debug, Deserialize)]
+struct HookOutput {
+    text: String,
+}
+
+/// Run `program` with `input` on stdin and collect its output. The process is killed if
+/// the returned future is dropped (e.g. on timeout).
+pub(crate) async fn exec(program: &str, args: &[String], input: &[u8]) -> Result<Output, String> {
+    let mut command = Command::new(program.trim());
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    {
+        // CREATE_NO_WINDOW: no console flashing up for console programs.
+        command.creation_flags(0x0800_0000);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+    // Written while the output is read, so neither side blocks on a full pipe.
+    let stdin = child.stdin.take();
+    let write = async move {
+        match stdin {
+            Some(mut stdin) => stdin.write_all(input).await,
+            None => Ok(()),
+        }
+    };
+    let (written, output) = tokio::join!(write, child.wait_with_output());
+    let output = output.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    written.map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    Ok(output)
+}
+
+/// Run `config`'s command on `input` and return the transcript it prints.
+pub async fn run(config: &HookConfig, input: &HookInput<'_>) -> Result<String, String> {
+    let stdin_json = serde_json::to_vec(input).map_err(|e| e.to_string())?;
+    let output = tokio::time::timeout(
+        Duration::from_millis(config.timeout_ms.max(1)),
+        exec(&config.command, &config.args, &stdin_json),
+    )
+    .await
+    .map_err(|_| format!("Hook timed out after {} ms", config.timeout_ms))??;
+    serde_json::from_slice::<HookOutput>(&output.stdout)
+        .map(|output| output.text)
+        .map_err(|e| format!("Invalid hook output: {}", e))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> HookConfig {
+        HookConfig {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_run_hook() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let input = HookInput {
+            text: "Hello.",
+            raw_text: "hello",
+            app: None,
+        };
+
+        runtime.block_on(async {
+            assert_eq!(run(&sh("sed s/Hello/Bye/"), &input).await.unwrap(), "Bye.");
+
+            assert!(run(&sh("cat >/dev/null; echo not json"), &input).await.is_err());
+            assert!(run(&sh("cat >/dev/null; exit 3"), &input).await.is_err());
+
+            let slow = HookConfig {
+                timeout_ms: 50,
+                ..sh("sleep 5")
+            };
+            assert!(run(&slow, &input).await.unwrap_err().contains("timed out"));
+        });
+    }
+}
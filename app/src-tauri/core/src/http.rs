@@ -0,0 +1,381 @@
+//! HTTP client shared by the STT and LLM providers, and the network settings applied to it.
+//!
+//! `reqwest::Client` keeps its connection pool behind an `Arc`, so every clone of the shared
+//! client reuses the same keep-alive connections. Providers are rebuilt whenever the
+//! pipeline config changes (`update_config` clears the provider caches); because they all
+//! hold this client, a warm TLS connection to a provider outlives those rebuilds.
+//!
+//! Proxy and certificate settings (`NetworkConfig`) are applied to this client and to every
+//! other client built with `client_builder`. Changing them replaces the shared client, so
+//! providers created afterwards use the new settings. Websocket streams, which don't go
+//! through reqwest, open their connection with `connect_stream` and `tls_connector`.
+//!
+//! Timeouts differ per provider, so they are set on each request rather than here.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// How long an idle pooled connection is kept open.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// TCP keep-alive interval, so NAT/firewall state survives pauses between dictations.
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// Proxy and TLS settings for all outgoing requests (stored as `network_config`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy for all requests, e.g. `http://proxy.corp:8080` (None = `HTTPS_PROXY` etc. from
+    /// the environment)
+    pub proxy_url: Option<String>,
+    /// Hosts that bypass the proxy, comma separated (`NO_PROXY` syntax)
+    pub no_proxy: Option<String>,
+    /// PEM bundle or DER file with extra root certificates to trust
+    pub ca_cert_path: Option<PathBuf>,
+    /// Verify TLS certificates. Only turn off to debug an intercepting proxy.
+    pub tls_verify: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl NetworkConfig {
+    const DEFAULT: Self = Self {
+        proxy_url: None,
+        no_proxy: None,
+        ca_cert_path: None,
+        tls_verify: true,
+    };
+
+    /// Apply these settings to `builder`.
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, String> {
+        if let Some(url) = self.proxy_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+            let no_proxy = self
+                .no_proxy
+                .as_deref()
+                .and_then(reqwest::NoProxy::from_string);
+            let proxy = reqwest::Proxy::all(url)
+                .map_err(|e| format!("Invalid proxy URL '{}': {}", url, e))?
+                .no_proxy(no_proxy);
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_cert_path {
+            for cert in load_certificates(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if !self.tls_verify {
+            log::warn!("TLS certificate verification is disabled");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+
+    /// The proxy to reach `url` through: `proxy_url` (or, when unset, `HTTPS_PROXY` /
+    /// `ALL_PROXY` from the environment) unless the host matches `no_proxy` (or `NO_PROXY`).
+    fn proxy_for(&self, url: &reqwest::Url) -> Option<String> {
+        let from_env = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| std::env::var(name).ok())
+                .filter(|value| !value.trim().is_empty())
+        };
+        let configured = self.proxy_url.as_deref().map(str::trim).filter(|u| !u.is_empty());
+        let (proxy, no_proxy) = match configured {
+            Some(proxy) => (proxy.to_string(), self.no_proxy.clone()),
+            None => (
+                from_env(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"])?,
+                from_env(&["NO_PROXY", "no_proxy"]),
+            ),
+        };
+        let host = url.host_str()?;
+        if no_proxy.is_some_and(|list| no_proxy_matches(&list, host)) {
+            return None;
+        }
+        Some(proxy)
+    }
+
+    /// A TLS connector with the extra root certificates and `tls_verify` applied, for
+    /// connections made outside reqwest.
+    pub fn tls_connector(&self) -> Result<native_tls::TlsConnector, String> {
+        let mut builder = native_tls::TlsConnector::builder();
+        if let Some(path) = &self.ca_cert_path {
+            let bytes = std::fs::read(path)
+                .map_err(|e| format!("Failed to read CA certificate {}: {}", path.display(), e))?;
+            let certs = native_tls::Certificate::stack_from_pem(&bytes)
+                .ok()
+                .filter(|certs| !certs.is_empty())
+                .map_or_else(|| native_tls::Certificate::from_der(&bytes).map(|c| vec![c]), Ok)
+                .map_err(|e| format!("Invalid CA certificate {}: {}", path.display(), e))?;
+            for cert in certs {
+                builder.add_root_certificate(cert);
+            }
+        }
+        if !self.tls_verify {
+            log::warn!("TLS certificate verification is disabled");
+            builder.danger_accept_invalid_certs(true);
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Failed to create TLS connector: {}", e))
+    }
+}
+
+/// Whether `host` is covered by a `NO_PROXY`-style list: `*`, the host itself, or a domain
+/// it is under (`example.com` and `.example.com` both match `api.example.com`).
+fn no_proxy_matches(list: &str, host: &str) -> bool {
+    list.split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host.eq_ignore_ascii_case(entry)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+        })
+}
+
+/// Read root certificates from a PEM bundle, falling back to a single DER certificate.
+fn load_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Failed to read CA certificate {}: {}", path.display(), e))?;
+    let certs = reqwest::Certificate::from_pem_bundle(&bytes)
+        .ok()
+        .filter(|certs| !certs.is_empty())
+        .map_or_else(|| reqwest::Certificate::from_der(&bytes).map(|c| vec![c]), Ok)
+        .map_err(|e| format!("Invalid CA certificate {}: {}", path.display(), e))?;
+    Ok(certs)
+}
+
+struct NetworkState {
+    config: NetworkConfig,
+    /// Built lazily and replaced by `configure`.
+    client: Option<reqwest::Client>,
+}
+
+static NETWORK: Mutex<NetworkState> = Mutex::new(NetworkState {
+    config: NetworkConfig::DEFAULT,
+    client: None,
+});
+
+fn build_shared_client(config: &NetworkConfig) -> Result<reqwest::Client, String> {
+    config
+        .apply(
+            reqwest::Client::builder()
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .tcp_keepalive(TCP_KEEPALIVE),
+        )?
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Switch to new network settings.
+///
+/// On error (bad proxy URL, unreadable certificate) the previous settings stay in effect.
+/// Unchanged settings keep the current client and its warm connections.
+pub fn configure(config: NetworkConfig) -> Result<(), String> {
+    let mut state = NETWORK.lock().unwrap_or_else(|e| e.into_inner());
+    if state.config == config && state.client.is_some() {
+        return Ok(());
+    }
+    let client = build_shared_client(&config)?;
+    state.config = config;
+    state.client = Some(client);
+    Ok(())
+}
+
+/// The process-wide provider client (cheap to clone).
+pub fn shared_client() -> reqwest::Client {
+    let mut state = NETWORK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(client) = &state.client {
+        return client.clone();
+    }
+    let client = build_shared_client(&state.config).unwrap_or_else(|e| {
+        log::error!("Network settings not applied: {}", e);
+        reqwest::Client::new()
+    });
+    state.client = Some(client.clone());
+    client
+}
+
+/// A client builder with the current proxy and certificate settings, for requests that
+/// need their own client (e.g. long downloads with a different timeout).
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let config = NETWORK
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .config
+        .clone();
+    let builder = reqwest::Client::builder();
+    match config.apply(builder) {
+        Ok(builder) => builder,
+        Err(e) => {
+            log::error!("Network settings not applied: {}", e);
+            reqwest::Client::builder()
+        }
+    }
+}
+
+/// The current network settings.
+pub fn network_config() -> NetworkConfig {
+    NETWORK
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .config
+        .clone()
+}
+
+/// Open a TCP connection for a stream to `url` (e.g. a websocket), through the configured
+/// proxy when one applies. Only `http://` proxies can tunnel these connections.
+pub async fn connect_stream(
+    config: &NetworkConfig,
+    url: &reqwest::Url,
+) -> Result<TcpStream, String> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("No host in {}", url))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| format!("No port for {}", url))?;
+    let target = format!("{}:{}", host, port);
+
+    let Some(proxy) = config.proxy_for(url) else {
+        return TcpStream::connect(&target)
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", target, e));
+    };
+    let proxy =
+        reqwest::Url::parse(&proxy).map_err(|e| format!("Invalid proxy URL '{}': {}", proxy, e))?;
+    if proxy.scheme() != "http" {
+        return Err(format!(
+            "Streaming connections need an http:// proxy, not {}://",
+            proxy.scheme()
+        ));
+    }
+    let proxy_addr = format!(
+        "{}:{}",
+        proxy.host_str().unwrap_or_default(),
+        proxy.port_or_known_default().unwrap_or(80)
+    );
+    let mut stream = TcpStream::connect(&proxy_addr)
+        .await
+        .map_err(|e| format!("Failed to connect to proxy {}: {}", proxy_addr, e))?;
+
+    let mut connect = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+    if !proxy.username().is_empty() {
+        let credentials = format!("{}:{}", proxy.username(), proxy.password().unwrap_or_default());
+        connect.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        ));
+    }
+    connect.push_str("\r\n");
+    stream
+        .write_all(connect.as_bytes())
+        .await
+        .map_err(|e| format!("Proxy {} failed: {}", proxy_addr, e))?;
+
+    // Read the response head byte by byte so nothing after it is consumed.
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > 8 * 1024 {
+            return Err(format!("Proxy {} sent an oversized response", proxy_addr));
+        }
+        let byte = stream
+            .read_u8()
+            .await
+            .map_err(|e| format!("Proxy {} closed the connection: {}", proxy_addr, e))?;
+        head.push(byte);
+    }
+    let status_line = String::from_utf8_lossy(&head);
+    let status_line = status_line.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(format!(
+            "Proxy {} refused CONNECT to {}: {}",
+            proxy_addr, target, status_line
+        ));
+    }
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_config_validation() {
+        let proxied = NetworkConfig {
+            proxy_url: Some("http://proxy.corp:8080".to_string()),
+            no_proxy: Some("localhost,127.0.0.1".to_string()),
+            ..Default::default()
+        };
+        assert!(build_shared_client(&proxied).is_ok());
+
+        let missing_ca = NetworkConfig {
+            ca_cert_path: Some(PathBuf::from("/nonexistent/corp-root.pem")),
+            ..Default::default()
+        };
+        assert!(build_shared_client(&missing_ca)
+            .unwrap_err()
+            .contains("corp-root.pem"));
+        assert!(missing_ca.tls_connector().unwrap_err().contains("corp-root.pem"));
+    }
+
+    #[test]
+    fn test_configured_proxy_respects_no_proxy() {
+        let config = NetworkConfig {
+            proxy_url: Some("http://proxy.corp:8080".to_string()),
+            no_proxy: Some("localhost, .internal.corp".to_string()),
+            ..Default::default()
+        };
+        let url = |s: &str| reqwest::Url::parse(s).unwrap();
+        assert_eq!(
+            config.proxy_for(&url("wss://api.deepgram.com/v1/listen")).as_deref(),
+            Some("http://proxy.corp:8080")
+        );
+        assert_eq!(config.proxy_for(&url("ws://localhost:9000/")), None);
+        assert_eq!(config.proxy_for(&url("wss://stt.internal.corp/")), None);
+    }
+
+    #[tokio::test]
+    async fn test_connect_stream_tunnels_through_proxy() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://user:pw@{}", listener.local_addr().unwrap());
+        let proxy = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\ntunnel")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let config = NetworkConfig {
+            proxy_url: Some(proxy_url),
+            ..Default::default()
+        };
+        let url = reqwest::Url::parse("wss://api.deepgram.com/v1/listen").unwrap();
+        let mut stream = connect_stream(&config, &url).await.unwrap();
+        let mut rest = [0u8; 6];
+        stream.read_exact(&mut rest).await.unwrap();
+        assert_eq!(&rest, b"tunnel");
+
+        let request = proxy.await.unwrap();
+        assert!(request.starts_with("CONNECT api.deepgram.com:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwdw==\r\n"));
+    }
+}
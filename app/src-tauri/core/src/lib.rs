@@ -0,0 +1,36 @@
+//! Tauri-independent core of Tangerine: the dictation pipeline with its STT and LLM
+//! providers, microphone capture, pluggable audio sources and voice activity detection.
+//!
+//! The desktop app imports these modules at its crate root; other front ends (a CLI, an
+//! HTTP server) can depend on this crate directly. The pipeline is configured through
+//! `pipeline::PipelineConfig` and reaches back into its host through
+//! `pipeline::PipelineHost`.
+
+pub mod at_rest;
+pub mod audio_capture;
+pub mod audio_source;
+pub mod corrections;
+pub mod fingerprint;
+pub mod hallucination;
+pub mod hooks;
+pub mod http;
+pub mod llm;
+pub mod metrics;
+pub mod mock;
+pub mod network;
+pub mod output_format;
+pub mod output_mode;
+pub mod persist;
+pub mod pipeline;
+pub mod pipeline_metrics;
+pub mod plugins;
+pub mod postprocess;
+pub mod rate_limit;
+pub mod recovery;
+pub mod redaction;
+pub mod request_log;
+pub mod retry;
+pub mod session;
+pub mod stt;
+pub mod text_diff;
+pub mod vad;
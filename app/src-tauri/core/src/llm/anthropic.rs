@@ -0,0 +1,556 @@
+//! Anthropic (Claude) LLM provider for text formatting.
+
+use super::{GenerationParams, LlmError, LlmProvider, DEFAULT_LLM_TIMEOUT};
+use async_trait::async_trait;
+use crate::request_log::RequestLogStore;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+const DEFAULT_MODEL: &str = "claude-3-haiku-20240307";
+const API_VERSION: &str = "2023-06-01";
+/// Tool the model is made to call with the rewrite (structured output).
+const REWRITE_TOOL_NAME: &str = "rewrite_response";
+
+/// Anthropic (Claude) LLM provider using the Messages API
+pub struct AnthropicLlmProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    timeout: Option<Duration>,
+    thinking_budget_tokens: Option<i64>,
+    generation: GenerationParams,
+    structured_outputs: bool,
+    request_log_store: Option<RequestLogStore>,
+}
+
+impl AnthropicLlmProvider {
+    /// Create a new Anthropic provider with the given API key
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: crate::http::shared_client(),
+            api_key,
+            model: DEFAULT_MODEL.to_string(),
+            timeout: Some(DEFAULT_LLM_TIMEOUT),
+            thinking_budget_tokens: None,
+            generation: GenerationParams::default(),
+            structured_outputs: true,
+            request_log_store: None,
+        }
+    }
+
+    /// Create with a specific model
+    pub fn with_model(api_key: String, model: String) -> Self {
+        Self {
+            client: crate::http::shared_client(),
+            api_key,
+            model,
+            timeout: Some(DEFAULT_LLM_TIMEOUT),
+            thinking_budget_tokens: None,
+            generation: GenerationParams::default(),
+            structured_outputs: true,
+            request_log_store: None,
+        }
+    }
+
+    /// Create with custom client and settings
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_client(client: Client, api_key: String, model: Option<String>) -> Self {
+        Self {
+            client,
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            timeout: Some(DEFAULT_LLM_TIMEOUT),
+            thinking_budget_tokens: None,
+            generation: GenerationParams::default(),
+            structured_outputs: true,
+            request_log_store: None,
+        }
+    }
+
+    /// Enable/disable structured output (a forced `rewrite_response` tool call).
+    ///
+    /// Enabled by default, like the OpenAI and Gemini JSON schema modes, so a rewrite comes
+    /// back as the tool's `rewritten_text` argument rather than prose around it. Ad-hoc
+    /// completions (like transcript analysis) should disable it to get free-form text.
+    pub fn with_structured_outputs(mut self, enabled: bool) -> Self {
+        self.structured_outputs = enabled;
+        self
+    }
+
+    pub fn with_request_log_store(mut self, store: Option<RequestLogStore>) -> Self {
+        self.request_log_store = store;
+        self
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Disable request timeouts entirely.
+    ///
+    /// This is primarily intended for the Settings UI "Test" actions.
+    pub fn without_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    pub fn with_thinking_budget(mut self, budget_tokens: Option<i64>) -> Self {
+        self.thinking_budget_tokens = budget_tokens;
+        self
+    }
+
+    /// Set max tokens, temperature and top_p.
+    pub fn with_generation_params(mut self, generation: GenerationParams) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    fn supports_extended_thinking(model: &str) -> bool {
+        let m = model.to_ascii_lowercase();
+
+        // Extended thinking is supported by newer Claude families. Be conservative:
+        // - Claude 3.7 (explicit)
+        // - Claude 4.x / 4.5 families
+        //
+        // (We avoid enabling for older 3.0/3.5 models unless explicitly named.)
+        m.contains("claude-3-7")
+            || m.contains("claude-4")
+            || m.contains("-4-")
+            || m.contains("-4.0")
+            || m.contains("-4-5")
+    }
+
+    fn effective_thinking(&self) -> Option<ThinkingParam> {
+        let budget = self.thinking_budget_tokens?;
+
+        if !Self::supports_extended_thinking(&self.model) {
+            log::warn!(
+                "Unsupported Anthropic extended thinking for model '{}' (budget_tokens={}); ignoring",
+                self.model,
+                budget
+            );
+            return None;
+        }
+
+        // Anthropic cookbook (extended thinking) notes a minimum budget of 1024.
+        if budget < 1024 {
+            log::warn!(
+                "Anthropic thinking budget too small ({}); minimum is 1024. Ignoring.",
+                budget
+            );
+            return None;
+        }
+
+        // Defensive cap to avoid obviously unreasonable values. Actual max may vary by model.
+        if budget > 32768 {
+            log::warn!(
+                "Anthropic thinking budget too large ({}); capping at 32768.",
+                budget
+            );
+        }
+
+        let capped: i64 = budget.min(32768);
+
+        Some(ThinkingParam {
+            thinking_type: "enabled".to_string(),
+            budget_tokens: capped as u32,
+        })
+    }
+
+    fn rewrite_tool() -> ToolDefinition {
+        // Same tiny schema as the OpenAI/Gemini structured outputs.
+        ToolDefinition {
+            name: REWRITE_TOOL_NAME.to_string(),
+            description: "Return the rewritten dictation transcript.".to_string(),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "rewritten_text": {
+                        "type": "string",
+                        "description": "The final rewritten transcript text. This string will be used directly as the output. Preserve meaning, intent, and any required formatting. Do not wrap in markdown or add extra commentary. Return an empty string only if the input transcript is empty."
+                    }
+                },
+                "required": ["rewritten_text"],
+                "additionalProperties": false
+            }),
+        }
+    }
+
+    /// The Messages API request for one completion.
+    fn messages_request(&self, system_prompt: &str, user_message: &str) -> MessagesRequest {
+        let thinking = self.effective_thinking();
+        // `max_tokens` includes the thinking budget, and extended thinking rejects custom
+        // sampling. Newer models also reject temperature and top_p together.
+        let max_tokens =
+            self.generation.max_tokens + thinking.as_ref().map_or(0, |t| t.budget_tokens);
+        let (temperature, top_p) = if thinking.is_some() {
+            (None, None)
+        } else {
+            match (self.generation.temperature, self.generation.top_p) {
+                (Some(temperature), _) => (Some(temperature.min(1.0)), None),
+                (None, top_p) => (None, top_p),
+            }
+        };
+
+        // Extended thinking can't be combined with a forced tool call; the model is then only
+        // asked to use the tool.
+        let (system, tools, tool_choice) = if self.structured_outputs {
+            let tool_choice = if thinking.is_some() {
+                ToolChoice {
+                    choice_type: "auto".to_string(),
+                    name: None,
+                }
+            } else {
+                ToolChoice {
+                    choice_type: "tool".to_string(),
+                    name: Some(REWRITE_TOOL_NAME.to_string()),
+                }
+            };
+            (
+                format!(
+                    "{}\n\nReturn the result by calling the `{}` tool with the rewritten text.",
+                    system_prompt, REWRITE_TOOL_NAME
+                ),
+                Some(vec![Self::rewrite_tool()]),
+                Some(tool_choice),
+            )
+        } else {
+            (system_prompt.to_string(), None, None)
+        };
+
+        MessagesRequest {
+            model: self.model.clone(),
+            max_tokens,
+            system,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: vec![MessageContent {
+                    content_type: "text".to_string(),
+                    text: user_message.to_string(),
+                }],
+            }],
+            temperature,
+            top_p,
+            tools,
+            tool_choice,
+            thinking,
+        }
+    }
+
+    /// The completion text in a Messages API response: the `rewritten_text` argument of the
+    /// rewrite tool call if there is one, otherwise the first text block.
+    fn extract_output(response_json: &serde_json::Value) -> Result<String, LlmError> {
+        let response: MessagesResponse = serde_json::from_value(response_json.clone())
+            .map_err(|e| LlmError::InvalidResponse(format!("Failed to parse response: {}", e)))?;
+
+        if let Some(block) = response.content.iter().find(|block| {
+            block.content_type == "tool_use" && block.name.as_deref() == Some(REWRITE_TOOL_NAME)
+        }) {
+            return block
+                .input
+                .as_ref()
+                .and_then(|input| input.get("rewritten_text"))
+                .and_then(|t| t.as_str())
+                .map(|s| s.to_string())
+                .ok_or_else(|| {
+                    LlmError::InvalidResponse(format!(
+                        "Anthropic tool call missing required field 'rewritten_text' (input: {})",
+                        block.input.clone().unwrap_or_default()
+                    ))
+                });
+        }
+
+        response
+            .content
+            .into_iter()
+            .find(|block| block.content_type == "text")
+            .and_then(|block| block.text)
+            .ok_or_else(|| LlmError::InvalidResponse("No text content in response".to_string()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct MessageContent {
+    #[serde(rename = "type")]
+    content_type: String,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    role: String,
+    content: Vec<MessageContent>,
+}
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest {
+    model: String,
+    max_tokens: u32,
+    system: String,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<ToolDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<ToolChoice>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<ThinkingParam>,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolDefinition {
+    name: String,
+    description: String,
+    input_schema: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolChoice {
+    #[serde(rename = "type")]
+    choice_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ThinkingParam {
+    #[serde(rename = "type")]
+    thinking_type: String,
+    #[serde(rename = "budget_tokens")]
+    budget_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    content_type: String,
+    text: Option<String>,
+    /// Tool name and arguments of a `tool_use` block
+    name: Option<String>,
+    input: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorDetail {
+    message: String,
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicLlmProvider {
+    async fn complete(&self, system_prompt: &str, user_message: &str) -> Result<String, LlmError> {
+        if self.api_key.is_empty() {
+            return Err(LlmError::NoApiKey("anthropic".to_string()));
+        }
+
+        let request = self.messages_request(system_prompt, user_message);
+
+        if let Some(store) = &self.request_log_store {
+            let request_json = serde_json::to_value(&request).unwrap_or_else(|_| {
+                json!({
+                    "provider": "anthropic",
+                    "error": "failed to serialize request",
+                })
+            });
+            store.with_current(|log| {
+                log.llm_request_json = Some(request_json);
+            });
+        }
+
+        let mut req = self
+            .client
+            .post(ANTHROPIC_API_URL)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", API_VERSION)
+            .header("content-type", "application/json")
+            .json(&request);
+        if let Some(timeout) = self.timeout {
+            req = req.timeout(timeout);
+        }
+
+        let response = req.send().await.map_err(|e| {
+            if e.is_timeout() {
+                if let Some(timeout) = self.timeout {
+                    LlmError::Timeout(timeout)
+                } else {
+                    // If we didn't configure a timeout, treat this as a generic network error.
+                    LlmError::Network(e)
+                }
+            } else {
+                LlmError::Network(e)
+            }
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = crate::retry::parse_retry_after(response.headers());
+            let error_text = response.text().await.unwrap_or_default();
+            // Try to parse as error response
+            if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&error_text) {
+                return Err(LlmError::from_status(
+                    status,
+                    retry_after,
+                    format!("Anthropic API error ({}): {}", status, error_response.error.message),
+                ));
+            }
+            return Err(LlmError::from_status(
+                status,
+                retry_after,
+                format!("Anthropic API error ({}): {}", status, error_text),
+            ));
+        }
+
+        let response_json: serde_json::Value = response.json().await.map_err(|e| {
+            LlmError::InvalidResponse(format!("Failed to parse response: {}", e))
+        })?;
+
+        if let Some(store) = &self.request_log_store {
+            let response_for_log = response_json.clone();
+            store.with_current(|log| {
+                log.llm_response_json = Some(response_for_log);
+            });
+        }
+
+        Self::extract_output(&response_json)
+    }
+
+    async fn warm_up(&self) -> Result<(), LlmError> {
+        crate::network::preconnect(&self.client, ANTHROPIC_API_URL).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(ANTHROPIC_API_URL.to_string())
+    }
+
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_name() {
+        let provider = AnthropicLlmProvider::new("test-key".to_string());
+        assert_eq!(provider.name(), "anthropic");
+    }
+
+    #[test]
+    fn test_default_model() {
+        let provider = AnthropicLlmProvider::new("test-key".to_string());
+        assert_eq!(provider.model(), DEFAULT_MODEL);
+    }
+
+    #[test]
+    fn test_custom_model() {
+        let provider = AnthropicLlmProvider::with_model(
+            "test-key".to_string(),
+            "claude-3-opus-20240229".to_string(),
+        );
+        assert_eq!(provider.model(), "claude-3-opus-20240229");
+    }
+
+    #[test]
+    fn test_without_timeout_disables_timeout() {
+        let provider = AnthropicLlmProvider::new("test-key".to_string()).without_timeout();
+        assert!(provider.timeout.is_none());
+    }
+
+    #[test]
+    fn test_messages_request_generation_params() {
+        let generation = GenerationParams {
+            max_tokens: 1000,
+            temperature: Some(0.2),
+            top_p: Some(0.9),
+        };
+        let provider =
+            AnthropicLlmProvider::new("test-key".to_string()).with_generation_params(generation);
+        let request = serde_json::to_value(provider.messages_request("system", "user")).unwrap();
+        assert_eq!(request["max_tokens"], 1000);
+        assert!((request["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!(request.get("top_p").is_none());
+
+        // Extended thinking: the budget comes on top, sampling is left alone.
+        let provider = AnthropicLlmProvider::with_model(
+            "test-key".to_string(),
+            "claude-sonnet-4-5".to_string(),
+        )
+        .with_generation_params(generation)
+        .with_thinking_budget(Some(2048));
+        let request = serde_json::to_value(provider.messages_request("system", "user")).unwrap();
+        assert_eq!(request["max_tokens"], 3048);
+        assert!(request.get("temperature").is_none());
+        assert_eq!(request["thinking"]["budget_tokens"], 2048);
+    }
+
+    #[test]
+    fn test_messages_request_forces_rewrite_tool() {
+        let provider = AnthropicLlmProvider::new("test-key".to_string());
+        let request = serde_json::to_value(provider.messages_request("system", "user")).unwrap();
+        assert_eq!(request["tools"][0]["name"], REWRITE_TOOL_NAME);
+        assert_eq!(
+            request["tool_choice"],
+            json!({ "type": "tool", "name": REWRITE_TOOL_NAME })
+        );
+
+        let provider = provider.with_structured_outputs(false);
+        let request = serde_json::to_value(provider.messages_request("system", "user")).unwrap();
+        assert!(request.get("tools").is_none());
+        assert_eq!(request["system"], "system");
+    }
+
+    #[test]
+    fn test_extract_output() {
+        let tool_call = json!({
+            "content": [
+                { "type": "text", "text": "Here you go:" },
+                {
+                    "type": "tool_use",
+                    "id": "toolu_01",
+                    "name": REWRITE_TOOL_NAME,
+                    "input": { "rewritten_text": "Hello, world." }
+                }
+            ]
+        });
+        assert_eq!(
+            AnthropicLlmProvider::extract_output(&tool_call).unwrap(),
+            "Hello, world."
+        );
+
+        let text = json!({ "content": [{ "type": "text", "text": "Plain reply" }] });
+        assert_eq!(
+            AnthropicLlmProvider::extract_output(&text).unwrap(),
+            "Plain reply"
+        );
+
+        let missing = json!({
+            "content": [{ "type": "tool_use", "name": REWRITE_TOOL_NAME, "input": {} }]
+        });
+        assert!(AnthropicLlmProvider::extract_output(&missing).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 //! Google Gemini (AI Studio / Gemini Developer API) LLM provider for text formatting.
 
-use super::{LlmError, LlmProvider, DEFAULT_LLM_TIMEOUT};
+use super::{GenerationParams, LlmError, LlmProvider, DEFAULT_LLM_TIMEOUT};
 use async_trait::async_trait;
 use crate::request_log::RequestLogStore;
 use reqwest::Client;
@@ -20,32 +20,35 @@ pub struct GeminiLlmProvider {
     thinking_budget: Option<i64>,
     thinking_level: Option<String>,
     structured_outputs: bool,
+    generation: GenerationParams,
     request_log_store: Option<RequestLogStore>,
 }
 
 impl GeminiLlmProvider {
     pub fn new(api_key: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             api_key,
             model: DEFAULT_MODEL.to_string(),
             timeout: Some(DEFAULT_LLM_TIMEOUT),
             thinking_budget: None,
             thinking_level: None,
             structured_outputs: true,
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
 
     pub fn with_model(api_key: String, model: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             api_key,
             model,
             timeout: Some(DEFAULT_LLM_TIMEOUT),
             thinking_budget: None,
             thinking_level: None,
             structured_outputs: true,
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -75,6 +78,12 @@ impl GeminiLlmProvider {
         self
     }
 
+    /// Set max output tokens, temperature and top_p.
+    pub fn with_generation_params(mut self, generation: GenerationParams) -> Self {
+        self.generation = generation;
+        self
+    }
+
     pub fn with_thinking_budget(mut self, budget: Option<i64>) -> Self {
         self.thinking_budget = budget;
         self
@@ -276,6 +285,8 @@ struct GenerationConfig {
     max_output_tokens: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none", rename = "topP")]
+    top_p: Option<f32>,
 
     // Structured outputs
     #[serde(rename = "responseMimeType")]
@@ -329,18 +340,21 @@ impl LlmProvider for GeminiLlmProvider {
         let model = Self::normalize_model_name(&self.model);
         let url = format!("{}/{model}:generateContent", GEMINI_API_ROOT);
 
-        // For deterministic formatting/rewrite.
+        // For deterministic formatting/rewrite, unless a temperature is configured.
         // Gemini docs note that for Gemini 3 models it's recommended to keep temperature at the
         // default value to avoid unexpected behavior. For older models we keep temperature low.
-        let temperature = if self.model.contains("gemini-3") {
-            None
-        } else {
-            Some(0.0)
-        };
+        let temperature = self.generation.temperature.or_else(|| {
+            if self.model.contains("gemini-3") {
+                None
+            } else {
+                Some(0.0)
+            }
+        });
 
         let generation_config = GenerationConfig {
-            max_output_tokens: 4096,
+            max_output_tokens: self.generation.max_tokens,
             temperature,
+            top_p: self.generation.top_p,
             response_mime_type: if self.structured_outputs {
                 "application/json".to_string()
             } else {
@@ -413,17 +427,20 @@ impl LlmProvider for GeminiLlmProvider {
 
         let status = response.status();
         if !status.is_success() {
+            let retry_after = crate::retry::parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
             if let Ok(error_response) = serde_json::from_str::<GeminiErrorResponse>(&error_text) {
-                return Err(LlmError::Api(format!(
-                    "Gemini API error ({}): {}",
-                    status, error_response.error.message
-                )));
+                return Err(LlmError::from_status(
+                    status,
+                    retry_after,
+                    format!("Gemini API error ({}): {}", status, error_response.error.message),
+                ));
             }
-            return Err(LlmError::Api(format!(
-                "Gemini API error ({}): {}",
-                status, error_text
-            )));
+            return Err(LlmError::from_status(
+                status,
+                retry_after,
+                format!("Gemini API error ({}): {}", status, error_text),
+            ));
         }
 
         let response_value: serde_json::Value = response.json().await.map_err(|e| {
@@ -467,6 +484,15 @@ impl LlmProvider for GeminiLlmProvider {
         Ok(rewritten.to_string())
     }
 
+    async fn warm_up(&self) -> Result<(), LlmError> {
+        crate::network::preconnect(&self.client, GEMINI_API_ROOT).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(GEMINI_API_ROOT.to_string())
+    }
+
     fn name(&self) -> &'static str {
         "gemini"
     }
@@ -4,7 +4,7 @@
 //! This provider uses the same request/response shape as OpenAI, but targets
 //! Groq's base URL.
 
-use super::{LlmError, LlmProvider, DEFAULT_LLM_TIMEOUT};
+use super::{GenerationParams, LlmError, LlmProvider, DEFAULT_LLM_TIMEOUT};
 use async_trait::async_trait;
 use crate::request_log::RequestLogStore;
 use reqwest::Client;
@@ -21,6 +21,7 @@ pub struct GroqLlmProvider {
     api_key: String,
     model: String,
     timeout: Option<Duration>,
+    generation: GenerationParams,
     request_log_store: Option<RequestLogStore>,
 }
 
@@ -28,10 +29,11 @@ impl GroqLlmProvider {
     /// Create a new Groq provider with the given API key.
     pub fn new(api_key: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             api_key,
             model: DEFAULT_MODEL.to_string(),
             timeout: Some(DEFAULT_LLM_TIMEOUT),
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -39,10 +41,11 @@ impl GroqLlmProvider {
     /// Create with a specific model.
     pub fn with_model(api_key: String, model: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             api_key,
             model,
             timeout: Some(DEFAULT_LLM_TIMEOUT),
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -65,6 +68,12 @@ impl GroqLlmProvider {
         self.timeout = None;
         self
     }
+
+    /// Set max tokens, temperature and top_p.
+    pub fn with_generation_params(mut self, generation: GenerationParams) -> Self {
+        self.generation = generation;
+        self
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -79,6 +88,8 @@ struct ChatRequest {
     messages: Vec<ChatMessage>,
     max_tokens: u32,
     temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -125,8 +136,9 @@ impl LlmProvider for GroqLlmProvider {
                     content: user_message.to_string(),
                 },
             ],
-            max_tokens: 4096,
-            temperature: 0.3,
+            max_tokens: self.generation.max_tokens,
+            temperature: self.generation.temperature.unwrap_or(0.3),
+            top_p: self.generation.top_p,
         };
 
         if let Some(store) = &self.request_log_store {
@@ -165,17 +177,20 @@ impl LlmProvider for GroqLlmProvider {
 
         let status = response.status();
         if !status.is_success() {
+            let retry_after = crate::retry::parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
             if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&error_text) {
-                return Err(LlmError::Api(format!(
-                    "Groq API error ({}): {}",
-                    status, error_response.error.message
-                )));
+                return Err(LlmError::from_status(
+                    status,
+                    retry_after,
+                    format!("Groq API error ({}): {}", status, error_response.error.message),
+                ));
             }
-            return Err(LlmError::Api(format!(
-                "Groq API error ({}): {}",
-                status, error_text
-            )));
+            return Err(LlmError::from_status(
+                status,
+                retry_after,
+                format!("Groq API error ({}): {}", status, error_text),
+            ));
         }
 
         let response_json: serde_json::Value = response
@@ -201,6 +216,15 @@ impl LlmProvider for GroqLlmProvider {
             .ok_or_else(|| LlmError::InvalidResponse("No response choices returned".to_string()))
     }
 
+    async fn warm_up(&self) -> Result<(), LlmError> {
+        crate::network::preconnect(&self.client, GROQ_API_URL).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(GROQ_API_URL.to_string())
+    }
+
     fn name(&self) -> &'static str {
         "groq"
     }
@@ -0,0 +1,38 @@
+//! Mock LLM provider driven by a `MockScript` (see `crate::mock`).
+
+use super::{LlmError, LlmProvider};
+use crate::mock::{MockScript, MockStep, ScriptedCalls};
+use async_trait::async_trait;
+
+/// Returns canned completions (or scripted failures); without responses it echoes the user
+/// message, i.e. formatting leaves the transcript unchanged.
+pub struct MockLlmProvider {
+    calls: ScriptedCalls,
+}
+
+impl MockLlmProvider {
+    pub fn new(script: MockScript) -> Self {
+        Self {
+            calls: ScriptedCalls::new(script),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockLlmProvider {
+    async fn complete(&self, _system_prompt: &str, user_message: &str) -> Result<String, LlmError> {
+        match self.calls.next().await {
+            MockStep::Respond(text) => Ok(text.unwrap_or_else(|| user_message.to_string())),
+            MockStep::Transient => Err(LlmError::Api("503 Service Unavailable (mock)".to_string())),
+            MockStep::Fatal => Err(LlmError::Api("401 Unauthorized (mock)".to_string())),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn model(&self) -> &str {
+        "mock"
+    }
+}
@@ -0,0 +1,508 @@
+//! LLM providers for text formatting.
+//!
+//! This module provides LLM-based text formatting for dictation transcripts.
+//! It supports multiple providers (OpenAI, Anthropic, Ollama) and uses
+//! configurable prompts to clean up and format transcribed speech.
+
+mod anthropic;
+mod defaults;
+mod gemini;
+mod groq;
+mod mock;
+mod ollama;
+mod openai;
+mod plugin;
+mod prompts;
+
+pub use anthropic::AnthropicLlmProvider;
+pub use gemini::GeminiLlmProvider;
+pub use groq::GroqLlmProvider;
+pub use mock::MockLlmProvider;
+pub use ollama::OllamaLlmProvider;
+pub use openai::OpenAiLlmProvider;
+pub use plugin::PluginLlmProvider;
+pub use defaults::default_llm_model_for_provider;
+pub use prompts::{
+    combine_prompt_sections, PromptSections, ADVANCED_PROMPT_DEFAULT, DICTIONARY_PROMPT_DEFAULT,
+    surrounding_text_section, MAIN_PROMPT_DEFAULT, SESSION_MERGE_PROMPT,
+};
+
+use crate::retry::{is_transient_api_message, retry, RetryConfig, RetryableError};
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default timeout for LLM API requests
+pub const DEFAULT_LLM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of retries for a failed LLM formatting request
+pub const DEFAULT_LLM_MAX_RETRIES: u32 = 1;
+
+/// Default cap on the tokens an LLM may generate per request
+pub const DEFAULT_LLM_MAX_TOKENS: u32 = 4096;
+
+/// Output length and sampling parameters sent with each LLM request.
+///
+/// `None` leaves a parameter to the provider: OpenAI and Gemini then use temperature 0
+/// (Gemini 3 keeps the API default), Groq and Ollama 0.3, and Anthropic the API default.
+/// Parameters a model rejects (e.g. temperature on reasoning models) are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GenerationParams {
+    /// Maximum number of tokens to generate (extended thinking comes on top)
+    pub max_tokens: u32,
+    /// Sampling temperature (0-2)
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass (0-1)
+    pub top_p: Option<f32>,
+}
+
+impl Default for GenerationParams {
+    fn default() -> Self {
+        Self {
+            max_tokens: DEFAULT_LLM_MAX_TOKENS,
+            temperature: None,
+            top_p: None,
+        }
+    }
+}
+
+impl GenerationParams {
+    /// Clamp hand-edited values into ranges the APIs accept.
+    pub fn sanitized(&self) -> Self {
+        let clamp =
+            |v: Option<f32>, max: f32| v.filter(|v| v.is_finite()).map(|v| v.clamp(0.0, max));
+        Self {
+            max_tokens: if self.max_tokens == 0 {
+                DEFAULT_LLM_MAX_TOKENS
+            } else {
+                self.max_tokens
+            },
+            temperature: clamp(self.temperature, 2.0),
+            top_p: clamp(self.top_p, 1.0),
+        }
+    }
+}
+
+/// Errors that can occur during LLM operations
+#[derive(Debug, thiserror::Error)]
+pub enum LlmError {
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    #[error("API error: {0}")]
+    Api(String),
+
+    /// HTTP 429, with the wait the server asked for (`Retry-After`), if any.
+    #[error("API error: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Request timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("No API key configured for provider: {0}")]
+    NoApiKey(String),
+
+    #[error("Provider not available: {0}")]
+    ProviderNotAvailable(String),
+}
+
+impl LlmError {
+    /// Error for a non-success API response.
+    pub fn from_status(
+        status: reqwest::StatusCode,
+        retry_after: Option<Duration>,
+        message: String,
+    ) -> Self {
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            LlmError::RateLimited {
+                message,
+                retry_after,
+            }
+        } else {
+            LlmError::Api(message)
+        }
+    }
+}
+
+/// Trait for LLM providers that can format text
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Complete a prompt and return the response
+    async fn complete(&self, system_prompt: &str, user_message: &str) -> Result<String, LlmError>;
+
+    /// Prepare for an upcoming `complete` call (open the connection, load a local model).
+    async fn warm_up(&self) -> Result<(), LlmError> {
+        Ok(())
+    }
+
+    /// Remote URL this provider sends requests to, probed to detect connectivity (`None`
+    /// for providers that run on this machine or a local server).
+    fn endpoint(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the provider name
+    fn name(&self) -> &'static str;
+
+    /// Get the current model being used
+    fn model(&self) -> &str;
+}
+
+/// Registry of LLM providers by provider id (the LLM counterpart of `SttRegistry`)
+pub struct LlmRegistry {
+    providers: std::collections::HashMap<String, Arc<dyn LlmProvider>>,
+    current: String,
+}
+
+impl LlmRegistry {
+    /// Create a new empty registry
+    pub fn new() -> Self {
+        Self {
+            providers: std::collections::HashMap::new(),
+            current: String::new(),
+        }
+    }
+
+    /// Register a provider with the given name
+    pub fn register(&mut self, name: &str, provider: Arc<dyn LlmProvider>) {
+        self.providers.insert(name.to_string(), provider);
+        // If this is the first provider, set it as current
+        if self.current.is_empty() {
+            self.current = name.to_string();
+        }
+    }
+
+    /// Set the current active provider
+    pub fn set_current(&mut self, name: &str) -> Result<(), String> {
+        if self.providers.contains_key(name) {
+            self.current = name.to_string();
+            Ok(())
+        } else {
+            Err(format!("Provider '{}' not found", name))
+        }
+    }
+
+    /// Get the current active provider
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn get_current(&self) -> Option<Arc<dyn LlmProvider>> {
+        self.providers.get(&self.current).cloned()
+    }
+
+    /// Get a provider by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn LlmProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    /// List all registered provider names
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn list_providers(&self) -> Vec<String> {
+        self.providers.keys().cloned().collect()
+    }
+
+    /// Get the name of the current provider
+    pub fn current_name(&self) -> &str {
+        &self.current
+    }
+}
+
+impl Default for LlmRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Configuration for LLM formatting
+#[derive(Debug, Clone)]
+pub struct LlmConfig {
+    /// Whether LLM formatting is enabled
+    pub enabled: bool,
+    /// The provider to use (openai, anthropic, ollama)
+    pub provider: String,
+    /// API key for cloud providers
+    pub api_key: String,
+    /// Model to use (e.g., "gpt-4o-mini", "claude-3-haiku", "llama3")
+    pub model: Option<String>,
+    /// Base URL for Ollama (default: http://localhost:11434)
+    pub ollama_url: Option<String>,
+
+    /// OpenAI reasoning effort (gpt-5 and o-series models only).
+    /// Examples: "low", "medium", "high".
+    pub openai_reasoning_effort: Option<String>,
+
+    /// Gemini thinking controls.
+    ///
+    /// - For Gemini 2.5 models, set `gemini_thinking_budget` (integer token budget).
+    /// - For Gemini 3 Pro, set `gemini_thinking_level` ("low" or "high").
+    /// - For Gemini 3 Flash, set `gemini_thinking_level` ("minimal", "low", "medium", "high").
+    pub gemini_thinking_budget: Option<i64>,
+    pub gemini_thinking_level: Option<String>,
+
+    /// Anthropic extended thinking controls.
+    ///
+    /// When supported by the selected Claude model, setting a budget enables
+    /// extended thinking mode.
+    pub anthropic_thinking_budget: Option<i64>,
+    /// Max tokens, temperature and top_p
+    pub generation: GenerationParams,
+    /// Prompt sections configuration
+    pub prompts: PromptSections,
+    /// Optional per-program prompt overrides (matched against the foreground executable path)
+    pub program_prompt_profiles: Vec<ProgramPromptProfile>,
+    /// Timeout of a single formatting attempt
+    pub timeout: Duration,
+    /// Retry policy for failed or timed-out formatting attempts
+    pub retry_config: RetryConfig,
+}
+
+/// Per-program prompt override profile.
+///
+/// If the active/foreground executable path matches any entry in `program_paths`, `prompts` is used instead of
+/// the default `LlmConfig.prompts`.
+#[derive(Debug, Clone)]
+pub struct ProgramPromptProfile {
+    pub id: String,
+    pub name: String,
+    pub program_paths: Vec<String>,
+    pub prompts: PromptSections,
+
+    /// Optional per-profile gate for rewrite (falls back to LlmConfig.enabled)
+    pub rewrite_llm_enabled: Option<bool>,
+
+    // Optional per-profile overrides for the pipeline
+    pub stt_provider: Option<String>,
+    pub stt_model: Option<String>,
+    pub stt_timeout_seconds: Option<f64>,
+    pub llm_provider: Option<String>,
+    pub llm_model: Option<String>,
+
+    /// Require a spoken confirmation after a read-back before delivering output
+    pub confirm_by_voice: bool,
+
+    /// Optional per-profile PII redaction gate (falls back to `RedactionConfig.enabled`)
+    pub redact_pii: Option<bool>,
+
+    /// Optional per-profile output transform (falls back to the `output_format` setting)
+    pub output_format: Option<crate::output_format::OutputFormat>,
+
+    /// Optional per-profile webhook endpoint (posted to by the app after output)
+    pub webhook_url: Option<String>,
+
+    /// Optional per-profile output mode (falls back to the `output_mode` setting)
+    pub output_mode: Option<crate::output_mode::OutputMode>,
+
+    /// Optional per-profile VAD config, applied when a recording starts
+    pub vad_config: Option<crate::audio_capture::VadAutoStopConfig>,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: "openai".to_string(),
+            api_key: String::new(),
+            model: None,
+            ollama_url: None,
+            openai_reasoning_effort: None,
+            gemini_thinking_budget: None,
+            gemini_thinking_level: None,
+            anthropic_thinking_budget: None,
+            generation: GenerationParams::default(),
+            prompts: PromptSections::default(),
+            program_prompt_profiles: Vec::new(),
+            timeout: DEFAULT_LLM_TIMEOUT,
+            retry_config: RetryConfig::with_max_retries(DEFAULT_LLM_MAX_RETRIES),
+        }
+    }
+}
+
+/// Format text using an LLM provider
+pub async fn format_text(
+    provider: &dyn LlmProvider,
+    transcript: &str,
+    prompts: &PromptSections,
+) -> Result<String, LlmError> {
+    format_text_with_context(provider, transcript, prompts, None).await
+}
+
+/// Format text like `format_text`. `existing_text` is the text the output will follow in the
+/// target field, so the model can continue it (see `SURROUNDING_TEXT_PROMPT`).
+pub async fn format_text_with_context(
+    provider: &dyn LlmProvider,
+    transcript: &str,
+    prompts: &PromptSections,
+    existing_text: Option<&str>,
+) -> Result<String, LlmError> {
+    if transcript.trim().is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut system_prompt = combine_prompt_sections(prompts);
+    if let Some(existing_text) = existing_text.filter(|t| !t.trim().is_empty()) {
+        system_prompt.push_str("\n\n");
+        system_prompt.push_str(&surrounding_text_section(existing_text));
+    }
+    let result = provider.complete(&system_prompt, transcript).await?;
+
+    Ok(result.trim().to_string())
+}
+
+/// Missing keys or providers won't fix themselves, and client errors (4xx other than
+/// rate limits) will fail the same way again.
+impl RetryableError for LlmError {
+    fn is_retryable(&self, config: &RetryConfig) -> bool {
+        match self {
+            LlmError::Network(_) | LlmError::Timeout(_) | LlmError::InvalidResponse(_) => true,
+            LlmError::Api(msg) => is_transient_api_message(msg, config),
+            LlmError::RateLimited { .. } => config.retry_on_rate_limit,
+            LlmError::NoApiKey(_) | LlmError::ProviderNotAvailable(_) => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            LlmError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Format text like `format_text_with_context`, giving each attempt at most `timeout` and
+/// retrying retryable failures per `retry_config`.
+///
+/// A final timeout is reported as `LlmError::Timeout`.
+pub async fn format_text_with_retry(
+    provider: &dyn LlmProvider,
+    transcript: &str,
+    prompts: &PromptSections,
+    existing_text: Option<&str>,
+    timeout: Duration,
+    retry_config: &RetryConfig,
+) -> Result<String, LlmError> {
+    retry(retry_config, "LLM", || async {
+        tokio::time::timeout(
+            timeout,
+            format_text_with_context(provider, transcript, prompts, existing_text),
+        )
+        .await
+        .unwrap_or(Err(LlmError::Timeout(timeout)))
+    })
+    .await
+}
+
+/// Build the user message for a multi-segment merge request.
+fn session_merge_user_message(segments: &[String]) -> String {
+    segments
+        .iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .enumerate()
+        .map(|(i, s)| format!("[Segment {}]\n{}", i + 1, s))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Merge the segments of a dictation session into one message using an LLM provider.
+///
+/// The regular prompt sections still apply (dictionary, punctuation rules, ...); the
+/// session merge section is appended so the model knows how to treat segment boundaries.
+pub async fn merge_session_segments(
+    provider: &dyn LlmProvider,
+    segments: &[String],
+    prompts: &PromptSections,
+) -> Result<String, LlmError> {
+    let user_message = session_merge_user_message(segments);
+    if user_message.is_empty() {
+        return Ok(String::new());
+    }
+
+    let system_prompt = format!(
+        "{}\n\n{}",
+        combine_prompt_sections(prompts),
+        SESSION_MERGE_PROMPT
+    );
+    let result = provider.complete(&system_prompt, &user_message).await?;
+
+    Ok(result.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_registry() {
+        let mut registry = LlmRegistry::new();
+        assert!(registry.get_current().is_none());
+        assert!(registry.list_providers().is_empty());
+
+        registry.register("first", Arc::new(MockLlmProvider::new(Default::default())));
+        registry.register("second", Arc::new(MockLlmProvider::new(Default::default())));
+        assert_eq!(registry.current_name(), "first");
+        assert!(registry.set_current("missing").is_err());
+        registry.set_current("second").unwrap();
+        assert!(Arc::ptr_eq(
+            &registry.get_current().unwrap(),
+            &registry.get("second").unwrap()
+        ));
+        assert_eq!(registry.list_providers().len(), 2);
+    }
+
+    #[test]
+    fn test_llm_config_default() {
+        let config = LlmConfig::default();
+        assert!(!config.enabled);
+        assert_eq!(config.provider, "openai");
+        assert_eq!(config.timeout, DEFAULT_LLM_TIMEOUT);
+        assert_eq!(config.retry_config.max_retries, DEFAULT_LLM_MAX_RETRIES);
+        assert_eq!(config.generation.max_tokens, DEFAULT_LLM_MAX_TOKENS);
+    }
+
+    #[test]
+    fn test_generation_params_sanitized() {
+        let params: GenerationParams =
+            serde_json::from_value(serde_json::json!({ "temperature": 3.5, "top_p": -1 })).unwrap();
+        assert_eq!(
+            params.sanitized(),
+            GenerationParams {
+                max_tokens: DEFAULT_LLM_MAX_TOKENS,
+                temperature: Some(2.0),
+                top_p: Some(0.0),
+            }
+        );
+        let params = GenerationParams {
+            max_tokens: 0,
+            ..Default::default()
+        };
+        assert_eq!(params.sanitized(), GenerationParams::default());
+    }
+
+    #[test]
+    fn test_llm_error_is_retryable() {
+        let config = RetryConfig::default();
+        assert!(LlmError::Timeout(DEFAULT_LLM_TIMEOUT).is_retryable(&config));
+        assert!(LlmError::Api("503 Service Unavailable".to_string()).is_retryable(&config));
+        assert!(LlmError::Api("429 Too Many Requests".to_string()).is_retryable(&config));
+        assert!(!LlmError::Api("400 Bad Request".to_string()).is_retryable(&config));
+        assert!(!LlmError::NoApiKey("openai".to_string()).is_retryable(&config));
+    }
+
+    #[test]
+    fn test_session_merge_user_message_skips_empty_segments() {
+        let segments = vec![
+            "Hi team, I wanted to follow up".to_string(),
+            "   ".to_string(),
+            "on yesterday's meeting.".to_string(),
+        ];
+        assert_eq!(
+            session_merge_user_message(&segments),
+            "[Segment 1]\nHi team, I wanted to follow up\n\n[Segment 2]\non yesterday's meeting."
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! Ollama LLM provider for local text formatting.
 
-use super::{LlmError, LlmProvider};
+use super::{GenerationParams, LlmError, LlmProvider};
 use async_trait::async_trait;
 use crate::request_log::RequestLogStore;
 use reqwest::Client;
@@ -19,6 +19,7 @@ pub struct OllamaLlmProvider {
     base_url: String,
     model: String,
     timeout: Option<Duration>,
+    generation: GenerationParams,
     request_log_store: Option<RequestLogStore>,
 }
 
@@ -26,10 +27,11 @@ impl OllamaLlmProvider {
     /// Create a new Ollama provider with default settings
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             base_url: DEFAULT_OLLAMA_URL.to_string(),
             model: DEFAULT_MODEL.to_string(),
             timeout: Some(DEFAULT_OLLAMA_TIMEOUT),
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -38,10 +40,11 @@ impl OllamaLlmProvider {
     #[cfg_attr(not(test), allow(dead_code))]
     pub fn with_model(model: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             base_url: DEFAULT_OLLAMA_URL.to_string(),
             model,
             timeout: Some(DEFAULT_OLLAMA_TIMEOUT),
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -49,10 +52,11 @@ impl OllamaLlmProvider {
     /// Create with custom URL and model
     pub fn with_url(base_url: String, model: Option<String>) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             base_url,
             model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
             timeout: Some(DEFAULT_OLLAMA_TIMEOUT),
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -65,6 +69,7 @@ impl OllamaLlmProvider {
             base_url: base_url.unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string()),
             model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
             timeout: Some(DEFAULT_OLLAMA_TIMEOUT),
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -88,6 +93,12 @@ impl OllamaLlmProvider {
         self
     }
 
+    /// Set max tokens (`num_predict`), temperature and top_p.
+    pub fn with_generation_params(mut self, generation: GenerationParams) -> Self {
+        self.generation = generation;
+        self
+    }
+
     /// Check if Ollama is available at the configured URL
     #[cfg_attr(not(test), allow(dead_code))]
     pub async fn is_available(&self) -> bool {
@@ -150,6 +161,8 @@ struct ChatRequest {
 struct ChatOptions {
     temperature: f32,
     num_predict: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -198,8 +211,9 @@ impl LlmProvider for OllamaLlmProvider {
             ],
             stream: false,
             options: Some(ChatOptions {
-                temperature: 0.3,
-                num_predict: 4096,
+                temperature: self.generation.temperature.unwrap_or(0.3),
+                num_predict: i32::try_from(self.generation.max_tokens).unwrap_or(i32::MAX),
+                top_p: self.generation.top_p,
             }),
         };
 
@@ -273,6 +287,19 @@ impl LlmProvider for OllamaLlmProvider {
             .ok_or_else(|| LlmError::InvalidResponse("No message content in response".to_string()))
     }
 
+    /// A generate request without a prompt loads the model into memory, which is most
+    /// of the latency of the first request after Ollama unloaded it.
+    async fn warm_up(&self) -> Result<(), LlmError> {
+        let url = format!("{}/api/generate", self.base_url);
+        self.client
+            .post(&url)
+            .json(&json!({ "model": self.model }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
     fn name(&self) -> &'static str {
         "ollama"
     }
@@ -1,6 +1,6 @@
 //! OpenAI LLM provider for text formatting.
 
-use super::{LlmError, LlmProvider, DEFAULT_LLM_TIMEOUT};
+use super::{GenerationParams, LlmError, LlmProvider, DEFAULT_LLM_TIMEOUT};
 use async_trait::async_trait;
 use crate::request_log::RequestLogStore;
 use reqwest::Client;
@@ -19,6 +19,7 @@ pub struct OpenAiLlmProvider {
     timeout: Option<Duration>,
     reasoning_effort: Option<String>,
     structured_outputs: bool,
+    generation: GenerationParams,
     request_log_store: Option<RequestLogStore>,
 }
 
@@ -26,12 +27,13 @@ impl OpenAiLlmProvider {
     /// Create a new OpenAI provider with the given API key
     pub fn new(api_key: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             api_key,
             model: DEFAULT_MODEL.to_string(),
             timeout: Some(DEFAULT_LLM_TIMEOUT),
             reasoning_effort: None,
             structured_outputs: true,
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -39,12 +41,13 @@ impl OpenAiLlmProvider {
     /// Create with a specific model
     pub fn with_model(api_key: String, model: String) -> Self {
         Self {
-            client: Client::new(),
+            client: crate::http::shared_client(),
             api_key,
             model,
             timeout: Some(DEFAULT_LLM_TIMEOUT),
             reasoning_effort: None,
             structured_outputs: true,
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -59,6 +62,7 @@ impl OpenAiLlmProvider {
             timeout: Some(DEFAULT_LLM_TIMEOUT),
             reasoning_effort: None,
             structured_outputs: true,
+            generation: GenerationParams::default(),
             request_log_store: None,
         }
     }
@@ -102,6 +106,14 @@ impl OpenAiLlmProvider {
         self
     }
 
+    /// Set max output tokens, temperature and top_p.
+    ///
+    /// Temperature and top_p are left out for models that reject them.
+    pub fn with_generation_params(mut self, generation: GenerationParams) -> Self {
+        self.generation = generation;
+        self
+    }
+
     fn supports_structured_outputs(model: &str) -> bool {
         // Structured Outputs (schema adherence) is available in newer models.
         // We keep a conservative allowlist to avoid 400s on unsupported models.
@@ -266,6 +278,8 @@ struct ResponsesRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     reasoning: Option<ReasoningConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     text: Option<TextConfig>,
@@ -339,6 +353,8 @@ impl LlmProvider for OpenAiLlmProvider {
             None
         };
 
+        let sampling = Self::supports_temperature_param(&self.model, reasoning_effort.as_deref());
+
         let request = ResponsesRequest {
             model: self.model.clone(),
             input: vec![
@@ -351,12 +367,12 @@ impl LlmProvider for OpenAiLlmProvider {
                     content: user_message.to_string(),
                 },
             ],
-            max_output_tokens: 4096,
+            max_output_tokens: self.generation.max_tokens,
             reasoning: reasoning_effort
                 .clone()
                 .map(|effort| ReasoningConfig { effort }),
-            temperature: Self::supports_temperature_param(&self.model, reasoning_effort.as_deref())
-                .then_some(0.0),
+            temperature: sampling.then(|| self.generation.temperature.unwrap_or(0.0)),
+            top_p: self.generation.top_p.filter(|_| sampling),
             text: use_structured_outputs.then(|| TextConfig {
                 format: Some(Self::rewrite_response_format()),
             }),
@@ -398,18 +414,21 @@ impl LlmProvider for OpenAiLlmProvider {
 
         let status = response.status();
         if !status.is_success() {
+            let retry_after = crate::retry::parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
             // Try to parse as error response
             if let Ok(error_response) = serde_json::from_str::<ErrorResponse>(&error_text) {
-                return Err(LlmError::Api(format!(
-                    "OpenAI API error ({}): {}",
-                    status, error_response.error.message
-                )));
+                return Err(LlmError::from_status(
+                    status,
+                    retry_after,
+                    format!("OpenAI API error ({}): {}", status, error_response.error.message),
+                ));
             }
-            return Err(LlmError::Api(format!(
-                "OpenAI API error ({}): {}",
-                status, error_text
-            )));
+            return Err(LlmError::from_status(
+                status,
+                retry_after,
+                format!("OpenAI API error ({}): {}", status, error_text),
+            ));
         }
 
         let response_json: serde_json::Value = response.json().await.map_err(|e| {
@@ -449,6 +468,15 @@ impl LlmProvider for OpenAiLlmProvider {
         }
     }
 
+    async fn warm_up(&self) -> Result<(), LlmError> {
+        crate::network::preconnect(&self.client, OPENAI_API_URL).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(OPENAI_API_URL.to_string())
+    }
+
     fn name(&self) -> &'static str {
         "openai"
     }
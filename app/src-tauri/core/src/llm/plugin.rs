@@ -0,0 +1,41 @@
+//! LLM provider backed by an external plugin program (see `crate::plugins`).
+
+use super::{LlmError, LlmProvider};
+use crate::plugins::{PluginError, PluginRequest, ProviderPlugin};
+use async_trait::async_trait;
+
+pub struct PluginLlmProvider {
+    plugin: ProviderPlugin,
+    model: Option<String>,
+}
+
+impl PluginLlmProvider {
+    pub fn new(plugin: ProviderPlugin, model: Option<String>) -> Self {
+        Self { plugin, model }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for PluginLlmProvider {
+    async fn complete(&self, system_prompt: &str, user_message: &str) -> Result<String, LlmError> {
+        let request = PluginRequest::Complete {
+            system_prompt,
+            user_message,
+            model: self.model.as_deref(),
+        };
+        crate::plugins::call(&self.plugin, &request)
+            .await
+            .map_err(|e| match e {
+                PluginError::Failed { .. } => LlmError::ProviderNotAvailable(e.to_string()),
+                PluginError::Provider { .. } => LlmError::Api(e.api_message()),
+            })
+    }
+
+    fn name(&self) -> &'static str {
+        crate::plugins::static_id(&self.plugin.id)
+    }
+
+    fn model(&self) -> &str {
+        self.model.as_deref().unwrap_or("default")
+    }
+}
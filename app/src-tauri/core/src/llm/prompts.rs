@@ -117,6 +117,42 @@ Claude
 Pipecat
 Tauri"#;
 
+/// Session merge section - appended when joining multi-segment dictation sessions.
+///
+/// Not user-editable: it only describes the segment markers used by `merge_session_segments`.
+pub const SESSION_MERGE_PROMPT: &str = r#"## Multi-Segment Dictation
+The input is one message dictated in several consecutive recordings. Each segment is
+preceded by a marker line like "[Segment 2]". Segments have already been cleaned up individually.
+
+- Join the segments, in order, into a single coherent message
+- Fix spacing, capitalization and punctuation where one segment ends and the next begins
+  (e.g. a sentence that was split mid-way should read as one sentence)
+- Start a new paragraph at a boundary only when the content clearly changes topic
+- Do NOT drop, reorder, or summarize content from any segment
+- Do NOT include the segment markers in the output"#;
+
+/// Surrounding text section - appended when the text before the caret was captured.
+///
+/// Not user-editable: it describes the `<existing_text>` block added by
+/// `surrounding_text_section`.
+pub const SURROUNDING_TEXT_PROMPT: &str = r#"## Existing Text
+The output will be inserted right after the text in the <existing_text> block, which is
+already in the document. Use it only as context.
+
+- Continue it naturally: match its tense, person, tone and formatting
+  (e.g. continue a numbered or bulleted list with the next item)
+- Capitalize the first word only if it starts a new sentence
+- Do NOT repeat words or a sentence opener the existing text already ends with
+- Do NOT include any of the existing text in the output"#;
+
+/// The surrounding text section for `existing_text`.
+pub fn surrounding_text_section(existing_text: &str) -> String {
+    format!(
+        "{}\n\n<existing_text>\n{}\n</existing_text>",
+        SURROUNDING_TEXT_PROMPT, existing_text
+    )
+}
+
 /// Configuration for prompt sections
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PromptSections {
@@ -0,0 +1,447 @@
+//! Metrics exporter, off unless the `metrics_exporter` setting selects one.
+//!
+//! Completed request logs are folded into counters and per-stage latency histograms
+//! (total, STT, LLM, typing) so dictation reliability can be graphed (e.g. in Grafana).
+//! They are exported either:
+//!
+//! - `prometheus`: served in the Prometheus text exposition format at
+//!   `http://127.0.0.1:<metrics_port>/metrics`. The listener only binds to loopback and only
+//!   answers `GET /metrics`.
+//! - `otlp`: pushed every `OTLP_PUSH_INTERVAL` as OTLP/HTTP JSON (cumulative temporality)
+//!   to `metrics_otlp_endpoint`, e.g. a local OpenTelemetry Collector.
+//!
+//! The exporter is chosen at startup; changing it takes effect after a restart.
+
+use crate::request_log::{RequestLog, RequestStatus};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Values of the `metrics_exporter` setting.
+pub const METRICS_EXPORTERS: &[&str] = &["off", "prometheus", "otlp"];
+pub const DEFAULT_METRICS_EXPORTER: &str = "off";
+
+/// Default port for the metrics listener (the conventional Prometheus exporter port).
+pub const DEFAULT_METRICS_PORT: u16 = 9464;
+
+/// Default OTLP/HTTP metrics endpoint (a collector on this machine).
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://127.0.0.1:4318/v1/metrics";
+
+const OTLP_PUSH_INTERVAL: Duration = Duration::from_secs(30);
+const OTLP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Latency histogram bucket upper bounds, in seconds.
+const LATENCY_BUCKETS_SECS: &[f64] = &[0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0];
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    /// Non-cumulative counts per bucket; the last slot is `+Inf`.
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; LATENCY_BUCKETS_SECS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let idx = LATENCY_BUCKETS_SECS
+            .iter()
+            .position(|le| value <= *le)
+            .unwrap_or(LATENCY_BUCKETS_SECS.len());
+        self.counts[idx] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (i, le) in LATENCY_BUCKETS_SECS.iter().enumerate() {
+            cumulative += self.counts[i];
+            let _ = writeln!(out, "{}_bucket{{{}le=\"{}\"}} {}", name, labels, le, cumulative);
+        }
+        cumulative += self.counts[LATENCY_BUCKETS_SECS.len()];
+        let _ = writeln!(out, "{}_bucket{{{}le=\"+Inf\"}} {}", name, labels, cumulative);
+
+        let labels = labels.trim_end_matches(',');
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, self.count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    /// (stt provider, status) -> count
+    requests: BTreeMap<(String, &'static str), u64>,
+    /// (stage, provider) -> latency histogram
+    latency: BTreeMap<(&'static str, String), Histogram>,
+    audio_seconds_total: f64,
+}
+
+/// Dictation metrics registry.
+#[derive(Debug)]
+pub struct Metrics {
+    inner: Mutex<MetricsInner>,
+    /// Start of the cumulative series, for OTLP.
+    started_at: SystemTime,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::default(),
+            started_at: SystemTime::now(),
+        }
+    }
+}
+
+/// Process-wide registry fed by the request log store.
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+fn status_label(status: &RequestStatus) -> &'static str {
+    match status {
+        RequestStatus::InProgress => "in_progress",
+        RequestStatus::Success => "success",
+        RequestStatus::Error => "error",
+        RequestStatus::Cancelled => "cancelled",
+    }
+}
+
+/// Escape a label value per the exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl Metrics {
+    /// Fold a completed request log into the metrics.
+    pub fn observe_request(&self, log: &RequestLog) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        *inner
+            .requests
+            .entry((log.stt_provider.clone(), status_label(&log.status)))
+            .or_insert(0) += 1;
+
+        if let Some(secs) = log.audio_duration_secs {
+            if secs.is_finite() && secs > 0.0 {
+                inner.audio_seconds_total += secs as f64;
+            }
+        }
+
+        let mut observe = |stage: &'static str, provider: &str, ms: Option<u64>| {
+            if let Some(ms) = ms {
+                inner
+                    .latency
+                    .entry((stage, provider.to_string()))
+                    .or_insert_with(Histogram::new)
+                    .observe(ms as f64 / 1000.0);
+            }
+        };
+
+        if log.status == RequestStatus::Success {
+            observe("total", &log.stt_provider, log.total_duration_ms);
+        }
+        observe("stt", &log.stt_provider, log.stt_duration_ms);
+        if let Some(llm_provider) = log.llm_provider.as_deref() {
+            observe("llm", llm_provider, log.llm_duration_ms);
+        }
+        observe("typing", "", log.typing_duration_ms);
+    }
+
+    /// Record the time it took to output a transcript whose log was already completed.
+    pub fn observe_typing(&self, elapsed: Duration) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        inner
+            .latency
+            .entry(("typing", String::new()))
+            .or_insert_with(Histogram::new)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let Ok(inner) = self.inner.lock() else {
+            return String::new();
+        };
+        let mut out = String::new();
+
+        out.push_str("# HELP tangerine_requests_total Dictation requests by STT provider and final status.\n");
+        out.push_str("# TYPE tangerine_requests_total counter\n");
+        for ((provider, status), count) in &inner.requests {
+            let _ = writeln!(
+                out,
+                "tangerine_requests_total{{provider=\"{}\",status=\"{}\"}} {}",
+                escape_label(provider),
+                status,
+                count
+            );
+        }
+
+        out.push_str("# HELP tangerine_request_duration_seconds Request latency by stage (total, stt, llm, typing) and provider.\n");
+        out.push_str("# TYPE tangerine_request_duration_seconds histogram\n");
+        for ((stage, provider), histogram) in &inner.latency {
+            let labels = format!("stage=\"{}\",provider=\"{}\",", stage, escape_label(provider));
+            histogram.render(&mut out, "tangerine_request_duration_seconds", &labels);
+        }
+
+        out.push_str("# HELP tangerine_audio_seconds_total Seconds of recorded audio submitted for transcription.\n");
+        out.push_str("# TYPE tangerine_audio_seconds_total counter\n");
+        let _ = writeln!(out, "tangerine_audio_seconds_total {}", inner.audio_seconds_total);
+
+        out
+    }
+
+    /// All metrics as an OTLP `ExportMetricsServiceRequest` in its JSON encoding.
+    pub fn otlp_json(&self) -> Value {
+        let Ok(inner) = self.inner.lock() else {
+            return json!({ "resourceMetrics": [] });
+        };
+        let unix_nanos = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+                .to_string()
+        };
+        let start = unix_nanos(self.started_at);
+        let now = unix_nanos(SystemTime::now());
+        let attributes = |pairs: &[(&str, &str)]| -> Value {
+            pairs
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+                .collect()
+        };
+
+        let requests: Vec<Value> = inner
+            .requests
+            .iter()
+            .map(|((provider, status), count)| {
+                json!({
+                    "attributes": attributes(&[("provider", provider), ("status", status)]),
+                    "startTimeUnixNano": start,
+                    "timeUnixNano": now,
+                    "asInt": count.to_string(),
+                })
+            })
+            .collect();
+        let latency: Vec<Value> = inner
+            .latency
+            .iter()
+            .map(|((stage, provider), histogram)| {
+                json!({
+                    "attributes": attributes(&[("stage", stage), ("provider", provider)]),
+                    "startTimeUnixNano": start,
+                    "timeUnixNano": now,
+                    "count": histogram.count.to_string(),
+                    "sum": histogram.sum,
+                    "bucketCounts": histogram.counts.iter().map(u64::to_string).collect::<Vec<_>>(),
+                    "explicitBounds": LATENCY_BUCKETS_SECS,
+                })
+            })
+            .collect();
+
+        json!({
+            "resourceMetrics": [{
+                "resource": { "attributes": attributes(&[("service.name", "tangerine")]) },
+                "scopeMetrics": [{
+                    "scope": { "name": "tangerine", "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": [
+                        {
+                            "name": "tangerine.requests",
+                            "description": "Dictation requests by STT provider and final status.",
+                            "unit": "{request}",
+                            "sum": { "aggregationTemporality": 2, "isMonotonic": true, "dataPoints": requests },
+                        },
+                        {
+                            "name": "tangerine.request.duration",
+                            "description": "Request latency by stage (total, stt, llm, typing) and provider.",
+                            "unit": "s",
+                            "histogram": { "aggregationTemporality": 2, "dataPoints": latency },
+                        },
+                        {
+                            "name": "tangerine.audio",
+                            "description": "Seconds of recorded audio submitted for transcription.",
+                            "unit": "s",
+                            "sum": {
+                                "aggregationTemporality": 2,
+                                "isMonotonic": true,
+                                "dataPoints": [{
+                                    "startTimeUnixNano": start,
+                                    "timeUnixNano": now,
+                                    "asDouble": inner.audio_seconds_total,
+                                }],
+                            },
+                        },
+                    ],
+                }],
+            }],
+        })
+    }
+}
+
+fn handle_connection(mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf).unwrap_or(0);
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let request_line = request.lines().next().unwrap_or("");
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+
+    let (status, content_type, body) = if method == "GET" && (path == "/metrics" || path.starts_with("/metrics?")) {
+        ("200 OK", "text/plain; version=0.0.4", global().render())
+    } else {
+        ("404 Not Found", "text/plain", "Not found\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Start the metrics listener on `127.0.0.1:<port>` in a background thread.
+pub fn spawn_server(port: u16) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    log::info!("Metrics endpoint listening on http://127.0.0.1:{}/metrics", port);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => handle_connection(stream),
+                Err(e) => log::warn!("Metrics connection failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Push the global metrics to the OTLP/HTTP `endpoint` every `OTLP_PUSH_INTERVAL`, forever.
+pub async fn run_otlp_exporter(endpoint: String) {
+    log::info!("Pushing metrics to {} (OTLP)", endpoint);
+    let mut interval = tokio::time::interval(OTLP_PUSH_INTERVAL);
+    interval.tick().await;
+    // Only the first failure of a streak is logged, so a stopped collector isn't spammy.
+    let mut failing = false;
+    loop {
+        interval.tick().await;
+        let result = crate::http::shared_client()
+            .post(&endpoint)
+            .timeout(OTLP_TIMEOUT)
+            .json(&global().otlp_json())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) if failing => {
+                log::info!("Pushing metrics to {} works again", endpoint);
+                failing = false;
+            }
+            Ok(_) => {}
+            Err(e) if !failing => {
+                log::warn!("Failed to push metrics to {}: {}", endpoint, e);
+                failing = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut h = Histogram::new();
+        h.observe(0.1);
+        h.observe(0.75);
+        h.observe(120.0);
+
+        let mut out = String::new();
+        h.render(&mut out, "x", "stage=\"stt\",");
+        assert!(out.contains("x_bucket{stage=\"stt\",le=\"0.25\"} 1\n"));
+        assert!(out.contains("x_bucket{stage=\"stt\",le=\"1\"} 2\n"));
+        assert!(out.contains("x_bucket{stage=\"stt\",le=\"60\"} 2\n"));
+        assert!(out.contains("x_bucket{stage=\"stt\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("x_count{stage=\"stt\"} 3\n"));
+    }
+
+    #[test]
+    fn test_observe_request_and_render() {
+        let metrics = Metrics::default();
+
+        let mut ok = RequestLog::new("groq".to_string(), None);
+        ok.audio_duration_secs = Some(3.5);
+        ok.stt_duration_ms = Some(400);
+        ok.llm_provider = Some("openai".to_string());
+        ok.llm_duration_ms = Some(900);
+        ok.complete_success();
+        metrics.observe_request(&ok);
+
+        let mut failed = RequestLog::new("groq".to_string(), None);
+        failed.complete_error("timeout");
+        metrics.observe_request(&failed);
+
+        let text = metrics.render();
+        assert!(text.contains("tangerine_requests_total{provider=\"groq\",status=\"success\"} 1\n"));
+        assert!(text.contains("tangerine_requests_total{provider=\"groq\",status=\"error\"} 1\n"));
+        assert!(text.contains("tangerine_request_duration_seconds_count{stage=\"stt\",provider=\"groq\"} 1\n"));
+        assert!(text.contains("tangerine_request_duration_seconds_count{stage=\"llm\",provider=\"openai\"} 1\n"));
+        assert!(text.contains("tangerine_audio_seconds_total 3.5\n"));
+    }
+
+    #[test]
+    fn test_otlp_json_matches_prometheus_series() {
+        let metrics = Metrics::default();
+        let mut ok = RequestLog::new("groq".to_string(), None);
+        ok.stt_duration_ms = Some(400);
+        ok.complete_success();
+        metrics.observe_request(&ok);
+        metrics.observe_typing(Duration::from_millis(1500));
+
+        let body = metrics.otlp_json();
+        let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let requests = &metrics[0]["sum"]["dataPoints"][0];
+        assert_eq!(requests["asInt"], "1");
+        assert_eq!(requests["attributes"][1]["value"]["stringValue"], "success");
+
+        let latency = metrics[1]["histogram"]["dataPoints"].as_array().unwrap();
+        let typing = latency
+            .iter()
+            .find(|point| point["attributes"][0]["value"]["stringValue"] == "typing")
+            .unwrap();
+        assert_eq!(typing["count"], "1");
+        assert_eq!(
+            typing["bucketCounts"].as_array().unwrap().len(),
+            LATENCY_BUCKETS_SECS.len() + 1
+        );
+        assert_eq!(typing["bucketCounts"][3], "1");
+    }
+}
@@ -0,0 +1,123 @@
+//! Scripted behavior for the `mock` STT and LLM providers.
+//!
+//! With `mock_providers_enabled` on (a debug setting), `"mock"` becomes a valid STT and LLM
+//! provider id. Mock providers never touch the network: each call waits `latency_ms` and then
+//! follows the next step of its `MockScript`, so retries, timeouts, fallbacks and output can
+//! be exercised in CI or demos without microphones or API keys.
+//!
+//! Calls are numbered per provider instance; call `n` uses `outcomes[n % len]` and
+//! `responses[n % len]`. Provider instances are cached by the pipeline, so the count carries
+//! over between recordings until the config changes.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+pub const MOCK_PROVIDER_ID: &str = "mock";
+
+/// What a mock call does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MockOutcome {
+    /// Return the next canned response.
+    #[default]
+    Ok,
+    /// Fail with a retryable server error (503).
+    Transient,
+    /// Fail with an error that is not retried (401).
+    Fatal,
+    /// Never answer, so the caller's timeout fires.
+    Hang,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockScript {
+    /// Canned responses, cycled. Empty: a fixed sentence for STT, the input echoed for LLMs.
+    pub responses: Vec<String>,
+    /// Delay before every call completes.
+    pub latency_ms: u64,
+    /// Outcome per call, cycled. Empty: every call succeeds.
+    pub outcomes: Vec<MockOutcome>,
+}
+
+/// The `mock_providers` setting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockProviders {
+    pub stt: MockScript,
+    pub llm: MockScript,
+}
+
+/// Result of one scripted call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockStep {
+    /// Succeed with this response (`None`: the provider's default).
+    Respond(Option<String>),
+    Transient,
+    Fatal,
+}
+
+/// A `MockScript` plus the call counter of one provider instance.
+#[derive(Debug)]
+pub struct ScriptedCalls {
+    script: MockScript,
+    calls: AtomicUsize,
+}
+
+impl ScriptedCalls {
+    pub fn new(script: MockScript) -> Self {
+        Self {
+            script,
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wait out the latency and return the step for the next call (pending forever on `Hang`).
+    pub async fn next(&self) -> MockStep {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.script.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.script.latency_ms)).await;
+        }
+
+        let outcome = cycled(&self.script.outcomes, call).copied().unwrap_or_default();
+        match outcome {
+            MockOutcome::Ok => MockStep::Respond(cycled(&self.script.responses, call).cloned()),
+            MockOutcome::Transient => MockStep::Transient,
+            MockOutcome::Fatal => MockStep::Fatal,
+            MockOutcome::Hang => std::future::pending().await,
+        }
+    }
+}
+
+fn cycled<T>(items: &[T], call: usize) -> Option<&T> {
+    (!items.is_empty()).then(|| &items[call % items.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_script_cycles_outcomes_and_responses() {
+        let calls = ScriptedCalls::new(MockScript {
+            responses: vec!["one".to_string(), "two".to_string()],
+            latency_ms: 0,
+            outcomes: vec![MockOutcome::Transient, MockOutcome::Ok, MockOutcome::Ok],
+        });
+        assert_eq!(calls.next().await, MockStep::Transient);
+        assert_eq!(calls.next().await, MockStep::Respond(Some("two".to_string())));
+        assert_eq!(calls.next().await, MockStep::Respond(Some("one".to_string())));
+        assert_eq!(calls.next().await, MockStep::Transient);
+
+        let hang = ScriptedCalls::new(MockScript {
+            outcomes: vec![MockOutcome::Hang],
+            ..Default::default()
+        });
+        let timed_out = tokio::time::timeout(Duration::from_millis(20), hang.next()).await;
+        assert!(timed_out.is_err());
+
+        let defaults = ScriptedCalls::new(MockScript::default());
+        assert_eq!(defaults.next().await, MockStep::Respond(None));
+    }
+}
@@ -0,0 +1,68 @@
+//! Connectivity helpers shared by the providers and the app's offline-mode monitor.
+
+use std::time::Duration;
+
+/// Timeout per probed endpoint.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Timeout for a provider warm-up request (see `preconnect`).
+const PRECONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Open a pooled connection to `url` ahead of the real request.
+///
+/// Any HTTP response, including an error status, means DNS, TCP and TLS are done and the
+/// connection is kept in `client`'s pool for the next request to the same host.
+pub async fn preconnect(client: &reqwest::Client, url: &str) -> Result<(), reqwest::Error> {
+    client
+        .head(url)
+        .timeout(PRECONNECT_TIMEOUT)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+/// Whether any of `urls` (the configured providers' endpoints, see
+/// `SharedPipeline::connectivity_probe_urls`) answers, going through the shared client so the
+/// proxy and certificate settings apply. With nothing to probe, there is nothing to be
+/// offline from.
+pub async fn probe_connectivity(urls: &[String]) -> bool {
+    if urls.is_empty() {
+        return true;
+    }
+    let client = crate::http::shared_client();
+    for url in urls {
+        let probe = client.head(url).timeout(PROBE_TIMEOUT).send().await;
+        if probe.is_ok() {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_probe_connectivity_uses_provider_endpoints() {
+        assert!(probe_connectivity(&[]).await);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/listen", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\n\r\n")
+                .await;
+        });
+        assert!(probe_connectivity(&[url]).await);
+
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let unreachable = format!("http://{}/", closed.local_addr().unwrap());
+        drop(closed);
+        assert!(!probe_connectivity(&[unreachable]).await);
+    }
+}
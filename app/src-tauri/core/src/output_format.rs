@@ -0,0 +1,334 @@
+//! Output transforms for LLM-formatted (markdown) text.
+//!
+//! Rewrite prompts often produce markdown: fine for chat apps and editors that render it,
+//! noise in a plain-text field. `OutputFormat` (the `output_format` setting, overridable per
+//! profile) picks what reaches the destination:
+//! - `AsIs`: the text unchanged
+//! - `PlainText`: markdown syntax removed (headings, emphasis, code fences, link syntax)
+//! - `Html`: rendered to HTML and put on the clipboard as `text/html` (with the plain-text
+//!   rendering as the fallback flavor) so rich editors paste formatted text
+//!
+//! Only the common subset of markdown is understood: ATX headings, emphasis, inline code,
+//! fenced code blocks, links/images, lists, block quotes and horizontal rules.
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    AsIs,
+    PlainText,
+    Html,
+}
+
+/// One line of markdown, classified.
+#[derive(Debug, PartialEq)]
+enum Line<'a> {
+    Blank,
+    Code(&'a str),
+    Heading(usize, &'a str),
+    /// `ordered` carries the original number marker (e.g. `"2."`).
+    Item {
+        indent: &'a str,
+        ordered: Option<&'a str>,
+        body: &'a str,
+    },
+    Quote(&'a str),
+    Rule,
+    Text(&'a str),
+}
+
+fn is_fence(trimmed: &str) -> bool {
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+fn is_rule(trimmed: &str) -> bool {
+    let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&marker| compact.chars().all(|c| c == marker))
+}
+
+fn classify(text: &str) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if is_fence(trimmed) {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(Line::Code(line));
+            continue;
+        }
+        if trimmed.is_empty() {
+            lines.push(Line::Blank);
+            continue;
+        }
+        if is_rule(trimmed) {
+            lines.push(Line::Rule);
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            lines.push(Line::Heading(hashes, trimmed[hashes..].trim()));
+            continue;
+        }
+        if let Some(quoted) = trimmed.strip_prefix('>') {
+            lines.push(Line::Quote(quoted.trim_start()));
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(body) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        {
+            lines.push(Line::Item {
+                indent,
+                ordered: None,
+                body: body.trim_start(),
+            });
+            continue;
+        }
+        let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && trimmed[digits..].starts_with(". ") {
+            lines.push(Line::Item {
+                indent,
+                ordered: Some(&trimmed[..=digits]),
+                body: trimmed[digits + 2..].trim_start(),
+            });
+            continue;
+        }
+
+        lines.push(Line::Text(line.trim()));
+    }
+    lines
+}
+
+struct InlinePatterns {
+    image: Regex,
+    link: Regex,
+    strong: Regex,
+    emphasis_star: Regex,
+    emphasis_underscore: Regex,
+}
+
+fn patterns() -> &'static InlinePatterns {
+    static PATTERNS: OnceLock<InlinePatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let re = |pattern: &str| Regex::new(pattern).expect("built-in markdown pattern");
+        InlinePatterns {
+            image: re(r"!\[([^\]]*)\]\(([^)\s]+)\)"),
+            link: re(r"\[([^\]]+)\]\(([^)\s]+)\)"),
+            strong: re(r"\*\*(\S(?:.*?\S)?)\*\*|__(\S(?:.*?\S)?)__"),
+            emphasis_star: re(r"\*(\S(?:[^*]*?\S)?)\*"),
+            // `_` only counts at word boundaries, so snake_case survives.
+            emphasis_underscore: re(r"(^|[^\w])_(\S(?:[^_]*?\S)?)_($|[^\w])"),
+        }
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Apply inline markdown to `text` (outside code spans), as HTML or as plain text.
+fn inline(text: &str, html: bool) -> String {
+    let p = patterns();
+    let mut out = String::new();
+    // Odd segments are code spans.
+    for (i, segment) in text.split('`').enumerate() {
+        if i % 2 == 1 {
+            if html {
+                out.push_str(&format!("<code>{}</code>", escape_html(segment)));
+            } else {
+                out.push_str(segment);
+            }
+            continue;
+        }
+
+        let segment = if html {
+            escape_html(segment)
+        } else {
+            segment.to_string()
+        };
+        let segment = p.image.replace_all(&segment, |c: &Captures| {
+            if html {
+                format!("<img src=\"{}\" alt=\"{}\">", &c[2], &c[1])
+            } else {
+                c[1].to_string()
+            }
+        });
+        let segment = p.link.replace_all(&segment, |c: &Captures| {
+            if html {
+                format!("<a href=\"{}\">{}</a>", &c[2], &c[1])
+            } else if c[1] == c[2] {
+                c[1].to_string()
+            } else {
+                format!("{} ({})", &c[1], &c[2])
+            }
+        });
+        let (strong, em) = if html {
+            ("<strong>$1$2</strong>", "<em>$1</em>")
+        } else {
+            ("$1$2", "$1")
+        };
+        let segment = p.strong.replace_all(&segment, strong);
+        let segment = p.emphasis_star.replace_all(&segment, em);
+        let em_underscore = if html { "$1<em>$2</em>$3" } else { "$1$2$3" };
+        let segment = p.emphasis_underscore.replace_all(&segment, em_underscore);
+        out.push_str(&segment);
+    }
+    out
+}
+
+/// Remove markdown syntax, keeping line structure (list items stay as `- ` / `1. ` lines).
+pub fn strip_markdown(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for line in classify(text) {
+        match line {
+            Line::Blank => out.push(String::new()),
+            Line::Code(code) => out.push(code.to_string()),
+            Line::Heading(_, body) | Line::Quote(body) | Line::Text(body) => {
+                out.push(inline(body, false))
+            }
+            Line::Item {
+                indent,
+                ordered,
+                body,
+            } => out.push(format!(
+                "{}{} {}",
+                indent,
+                ordered.unwrap_or("-"),
+                inline(body, false)
+            )),
+            Line::Rule => {}
+        }
+    }
+    out.join("\n").trim().to_string()
+}
+
+/// Render markdown to an HTML fragment.
+///
+/// Consecutive text lines form one paragraph with `<br>` between them (dictated line
+/// breaks are intentional), and nested list indentation is flattened.
+pub fn markdown_to_html(text: &str) -> String {
+    #[derive(PartialEq)]
+    enum Open {
+        None,
+        Paragraph,
+        Quote,
+        List(&'static str),
+        Code,
+    }
+
+    fn close(html: &mut String, open: &mut Open) {
+        match open {
+            Open::None => {}
+            Open::Paragraph => html.push_str("</p>"),
+            Open::Quote => html.push_str("</p></blockquote>"),
+            Open::List(tag) => html.push_str(&format!("</{}>", tag)),
+            Open::Code => html.push_str("</code></pre>"),
+        }
+        *open = Open::None;
+    }
+
+    let mut html = String::new();
+    let mut open = Open::None;
+    for line in classify(text) {
+        match line {
+            Line::Blank => close(&mut html, &mut open),
+            Line::Code(code) => {
+                if open == Open::Code {
+                    html.push('\n');
+                } else {
+                    close(&mut html, &mut open);
+                    html.push_str("<pre><code>");
+                    open = Open::Code;
+                }
+                html.push_str(&escape_html(code));
+            }
+            Line::Heading(level, body) => {
+                close(&mut html, &mut open);
+                html.push_str(&format!("<h{0}>{1}</h{0}>", level, inline(body, true)));
+            }
+            Line::Item { ordered, body, .. } => {
+                let tag = if ordered.is_some() { "ol" } else { "ul" };
+                if open != Open::List(tag) {
+                    close(&mut html, &mut open);
+                    html.push_str(&format!("<{}>", tag));
+                    open = Open::List(tag);
+                }
+                html.push_str(&format!("<li>{}</li>", inline(body, true)));
+            }
+            Line::Quote(body) => {
+                if open == Open::Quote {
+                    html.push_str("<br>");
+                } else {
+                    close(&mut html, &mut open);
+                    html.push_str("<blockquote><p>");
+                    open = Open::Quote;
+                }
+                html.push_str(&inline(body, true));
+            }
+            Line::Rule => {
+                close(&mut html, &mut open);
+                html.push_str("<hr>");
+            }
+            Line::Text(body) => {
+                if open == Open::Paragraph {
+                    html.push_str("<br>");
+                } else {
+                    close(&mut html, &mut open);
+                    html.push_str("<p>");
+                    open = Open::Paragraph;
+                }
+                html.push_str(&inline(body, true));
+            }
+        }
+    }
+    close(&mut html, &mut open);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# Notes\n\nHello **world**, see [docs](https://x.io) and `a_b`.\n\n- one\n- *two*\n\n1. first\n\n```\nlet x = 1;\n```\n---\n> quoted";
+
+    #[test]
+    fn test_strip_markdown() {
+        assert_eq!(
+            strip_markdown(SAMPLE),
+            "Notes\n\nHello world, see docs (https://x.io) and a_b.\n\n- one\n- two\n\n1. first\n\nlet x = 1;\nquoted"
+        );
+        assert_eq!(strip_markdown("keep snake_case_names"), "keep snake_case_names");
+        assert_eq!(strip_markdown("an _emphasized_ word"), "an emphasized word");
+    }
+
+    #[test]
+    fn test_markdown_to_html() {
+        assert_eq!(
+            markdown_to_html(SAMPLE),
+            "<h1>Notes</h1>\
+             <p>Hello <strong>world</strong>, see <a href=\"https://x.io\">docs</a> and <code>a_b</code>.</p>\
+             <ul><li>one</li><li><em>two</em></li></ul>\
+             <ol><li>first</li></ol>\
+             <pre><code>let x = 1;</code></pre>\
+             <hr>\
+             <blockquote><p>quoted</p></blockquote>"
+        );
+        assert_eq!(markdown_to_html("a < b\nnext"), "<p>a &lt; b<br>next</p>");
+    }
+}
@@ -0,0 +1,57 @@
+//! How a transcript reaches its destination (the `output_mode` setting, overridable per
+//! profile). Delivery itself is up to the front end.
+
+use serde::{Deserialize, Serialize};
+
+/// Output mode for transcribed text
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Copy to clipboard and simulate Ctrl+V/Cmd+V, then restore clipboard
+    #[default]
+    Paste,
+    /// Paste and keep in clipboard (no restore)
+    PasteAndClipboard,
+    /// Just copy to clipboard (no paste)
+    Clipboard,
+    /// Insert into the focused field through the accessibility API (clipboard untouched),
+    /// falling back to `Paste` when the field doesn't support it
+    Accessibility,
+    /// Type partial transcripts while recording and correct them once the final one is in.
+    /// Anything output after the fact is pasted like `Paste`.
+    LiveTyping,
+    /// Don't type anything; the transcript is POSTed to the configured webhook instead.
+    Webhook,
+    // NOTE: Keystrokes mode was removed/disabled due to reliability issues across targets.
+}
+
+impl OutputMode {
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "paste" => OutputMode::Paste,
+            "paste_and_clipboard" => OutputMode::PasteAndClipboard,
+            "clipboard" => OutputMode::Clipboard,
+            "accessibility" => OutputMode::Accessibility,
+            "live_typing" => OutputMode::LiveTyping,
+            "webhook" => OutputMode::Webhook,
+            // Legacy/disabled values: map to paste so existing settings.json doesn't break.
+            "keystrokes" => OutputMode::Paste,
+            "keystrokes_and_clipboard" => OutputMode::Paste,
+            // Handle legacy value
+            "auto_paste" => OutputMode::Paste,
+            _ => OutputMode::Paste,
+        }
+    }
+
+    /// Settings value for this mode (inverse of `from_str`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutputMode::Paste => "paste",
+            OutputMode::PasteAndClipboard => "paste_and_clipboard",
+            OutputMode::Clipboard => "clipboard",
+            OutputMode::Accessibility => "accessibility",
+            OutputMode::LiveTyping => "live_typing",
+            OutputMode::Webhook => "webhook",
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Crash-safe persistence for the JSON files in the app data directory.
+//!
+//! Writes go to a temporary file that is fsynced and then renamed over the target, so a
+//! crash mid-write never leaves a truncated file behind. Each file starts with a header line
+//! holding the SHA-256 of the rest, so data and checksum land in the same rename; the
+//! previous good version is kept as `<name>.bak`. Files from older versions kept the
+//! checksum next to them (`<name>.sha256`); those are still read and lose the sidecar on
+//! their next write.
+//!
+//! On load, a file that fails to parse or does not match its checksum is moved aside as
+//! `<name>.corrupt` and the backup is restored in its place.
+//!
+//! Contents are sealed by `crate::at_rest` when encryption at rest is on; the checksum
+//! covers the bytes as stored. A sealed file that can't be opened because the key isn't
+//! loaded is left alone: it isn't corrupted, and `crate::at_rest` then refuses writes.
+
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// `path` with `suffix` appended to the file name (`history.json` -> `history.json.bak`).
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".sha256")
+}
+
+fn checksum(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Start of the checksum header line.
+const CHECKSUM_HEADER: &[u8] = b"tangerine-sha256:";
+
+/// `contents` prefixed with its checksum header.
+fn with_checksum(contents: &[u8]) -> Vec<u8> {
+    let mut out = CHECKSUM_HEADER.to_vec();
+    out.extend_from_slice(checksum(contents).as_bytes());
+    out.push(b'\n');
+    out.extend_from_slice(contents);
+    out
+}
+
+/// Split a checksum header off `raw`: `(Some(checksum), contents)`, or `(None, raw)` for
+/// files without one.
+fn split_checksum(raw: &[u8]) -> (Option<&str>, &[u8]) {
+    let Some(rest) = raw.strip_prefix(CHECKSUM_HEADER) else {
+        return (None, raw);
+    };
+    match rest.iter().position(|&b| b == b'\n') {
+        Some(end) => (std::str::from_utf8(&rest[..end]).ok(), &rest[end + 1..]),
+        None => (Some(""), &[]),
+    }
+}
+
+/// Write `contents` to `path` via a synced temp file and rename, without a checksum,
+/// backup or sealing (for files read by other programs).
+pub fn write_replace(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = with_suffix(path, ".tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)
+}
+
+/// Why `read_verified` failed.
+#[derive(Debug)]
+enum ReadError {
+    /// The file is sealed and the at-rest key isn't loaded.
+    KeyUnavailable,
+    /// Unreadable, corrupted or not a `T`.
+    Invalid(String),
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KeyUnavailable => f.write_str("encrypted, and the at-rest key is not loaded"),
+            Self::Invalid(e) => f.write_str(e),
+        }
+    }
+}
+
+/// Read `path` and check it parses as `T` and matches its checksum (when one exists).
+fn read_verified<T: DeserializeOwned>(path: &Path) -> Result<T, ReadError> {
+    let raw = fs::read(path).map_err(|e| ReadError::Invalid(e.to_string()))?;
+
+    let (expected, contents) = match split_checksum(&raw) {
+        (Some(expected), contents) => (Some(expected.to_string()), contents),
+        // Older files keep the checksum in a sidecar.
+        (None, contents) => (fs::read_to_string(checksum_path(path)).ok(), contents),
+    };
+    if let Some(expected) = expected {
+        if expected.trim() != checksum(contents) {
+            return Err(ReadError::Invalid("checksum mismatch".to_string()));
+        }
+    }
+
+    if !crate::at_rest::can_open(contents) {
+        return Err(ReadError::KeyUnavailable);
+    }
+    let contents = crate::at_rest::open(contents.to_vec()).map_err(ReadError::Invalid)?;
+    serde_json::from_slice(&contents).map_err(|e| ReadError::Invalid(e.to_string()))
+}
+
+/// Block writes for the session: `path` was sealed, so its store loaded empty.
+fn key_unavailable_for(path: &Path) {
+    crate::at_rest::set_key_unavailable(format!("{} is encrypted", path.display()));
+}
+
+/// Atomically replace `path` with `contents`, keeping the current file as the backup.
+///
+/// The current file only becomes the backup if it still verifies, so a corrupted file
+/// never overwrites a good backup.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_with_backup(path, contents, true)
+}
+
+/// Like `write_atomic`, but stores `contents` as-is: no checksum header and no sealing.
+/// Used for files that another reader has to parse, such as the settings store.
+pub fn write_plain(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_with_backup(path, contents, false)
+}
+
+fn write_with_backup(path: &Path, contents: &[u8], own_format: bool) -> io::Result<()> {
+    if let Some(reason) = crate::at_rest::key_unavailable().filter(|_| own_format) {
+        return Err(io::Error::other(format!(
+            "Not writing {}: encryption key unavailable ({})",
+            path.display(),
+            reason
+        )));
+    }
+    if path.exists() {
+        refresh_backup(path)?;
+    }
+
+    if !own_format {
+        return write_replace(path, contents);
+    }
+    let contents = crate::at_rest::seal(contents).map_err(io::Error::other)?;
+    write_replace(path, &with_checksum(&contents))?;
+    // The header supersedes a sidecar left by an older version.
+    remove_if_exists(&checksum_path(path))
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Rewrite `path` and its backup in the current at-rest format (after encryption at rest
+/// was switched on or off). Returns whether anything changed.
+pub fn rewrite(path: &Path) -> io::Result<bool> {
+    let raw = match fs::read(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let (_, stored) = split_checksum(&raw);
+    if crate::at_rest::is_sealed(stored) == crate::at_rest::is_enabled() {
+        return Ok(false);
+    }
+
+    let plaintext = crate::at_rest::open(stored.to_vec()).map_err(io::Error::other)?;
+    write_atomic(path, &plaintext)?;
+    // The backup still holds the old format; replace it with the file just written.
+    refresh_backup(path)?;
+    Ok(true)
+}
+
+/// Copy the current file to its backup if it verifies (used for files written by others,
+/// such as the settings store).
+pub fn refresh_backup(path: &Path) -> io::Result<()> {
+    if read_verified::<serde_json::Value>(path).is_err() {
+        return Ok(());
+    }
+    let contents = fs::read(path)?;
+    let backup = backup_path(path);
+    // Drop the old sidecar first: a stale one would fail the new backup.
+    remove_if_exists(&checksum_path(&backup))?;
+    if split_checksum(&contents).0.is_none() && checksum_path(path).exists() {
+        // A file from an older version: give the copy its checksum inline.
+        return write_replace(&backup, &with_checksum(&contents));
+    }
+    write_replace(&backup, &contents)
+}
+
+/// Load `path` as `T`, restoring the backup if the file is corrupted.
+///
+/// Returns `None` when neither the file nor its backup exists or can be read.
+pub fn read_with_recovery<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    if !path.exists() {
+        return None;
+    }
+
+    let err = match read_verified::<T>(path) {
+        Ok(value) => return Some(value),
+        Err(ReadError::KeyUnavailable) => {
+            key_unavailable_for(path);
+            return None;
+        }
+        Err(ReadError::Invalid(e)) => e,
+    };
+
+    log::error!("{} is corrupted ({}); trying backup", path.display(), err);
+
+    // Keep the broken file around for inspection instead of overwriting it.
+    let _ = fs::rename(path, with_suffix(path, ".corrupt"));
+    let _ = fs::remove_file(checksum_path(path));
+
+    let backup = backup_path(path);
+    match read_verified::<T>(&backup) {
+        Ok(value) => {
+            // The backup is in the file's own format (with or without a checksum header).
+            if let Err(e) = fs::read(&backup).and_then(|raw| write_replace(path, &raw)) {
+                log::warn!("Failed to restore {} from backup: {}", path.display(), e);
+            } else {
+                log::warn!("Restored {} from backup", path.display());
+            }
+            Some(value)
+        }
+        Err(ReadError::KeyUnavailable) => {
+            key_unavailable_for(&backup);
+            None
+        }
+        Err(e) => {
+            log::error!("No usable backup for {} ({})", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tangerine-persist-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_backup_and_checksum() {
+        let dir = temp_dir();
+        let path = dir.join("history.json");
+
+        write_atomic(&path, br#"{"v":1}"#).unwrap();
+        write_atomic(&path, br#"{"v":2}"#).unwrap();
+
+        let current: serde_json::Value = read_with_recovery(&path).unwrap();
+        assert_eq!(current["v"], 2);
+        let backup: serde_json::Value = read_verified(&backup_path(&path)).unwrap();
+        assert_eq!(backup["v"], 1);
+        assert!(!with_suffix(&path, ".tmp").exists());
+        assert!(!checksum_path(&path).exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_truncated_file_is_restored_from_backup() {
+        let dir = temp_dir();
+        let path = dir.join("settings.json");
+
+        write_atomic(&path, br#"{"v":1}"#).unwrap();
+        write_atomic(&path, br#"{"v":2}"#).unwrap();
+        fs::write(&path, br#"{"v":"#).unwrap();
+
+        let restored: serde_json::Value = read_with_recovery(&path).unwrap();
+        assert_eq!(restored["v"], 1);
+        assert!(with_suffix(&path, ".corrupt").exists());
+
+        // The restored file verifies on the next load.
+        let again: serde_json::Value = read_with_recovery(&path).unwrap();
+        assert_eq!(again["v"], 1);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_store_owned_file_restore_has_no_checksum() {
+        let dir = temp_dir();
+        let path = dir.join("settings.json");
+
+        fs::write(&path, br#"{"v":1}"#).unwrap();
+        refresh_backup(&path).unwrap();
+        fs::write(&path, b"").unwrap();
+
+        let restored: serde_json::Value = read_with_recovery(&path).unwrap();
+        assert_eq!(restored["v"], 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"v":1}"#);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_write_plain_keeps_contents_as_is() {
+        let dir = temp_dir();
+        let path = dir.join("settings.json");
+
+        write_plain(&path, br#"{"v":1}"#).unwrap();
+        write_plain(&path, br#"{"v":2}"#).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"v":2}"#);
+        assert_eq!(
+            fs::read_to_string(backup_path(&path)).unwrap(),
+            r#"{"v":1}"#
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_stale_sidecar_does_not_fail_new_file() {
+        let dir = temp_dir();
+        let path = dir.join("history.json");
+
+        // A file written by an older version, with its checksum in a sidecar.
+        fs::write(&path, br#"{"v":1}"#).unwrap();
+        fs::write(checksum_path(&path), checksum(br#"{"v":1}"#)).unwrap();
+        let legacy: serde_json::Value = read_with_recovery(&path).unwrap();
+        assert_eq!(legacy["v"], 1);
+
+        write_atomic(&path, br#"{"v":2}"#).unwrap();
+        // As if the process died before the sidecar was removed.
+        fs::write(checksum_path(&path), checksum(br#"{"v":1}"#)).unwrap();
+
+        let current: serde_json::Value = read_with_recovery(&path).unwrap();
+        assert_eq!(current["v"], 2);
+        assert!(!with_suffix(&path, ".corrupt").exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_sealed_file_without_key_is_not_corrupted() {
+        let dir = temp_dir();
+        let path = dir.join("history.json");
+
+        let sealed = crate::at_rest::AtRestCipher::from_key(&[7u8; 32])
+            .unwrap()
+            .seal(br#"{"v":1}"#)
+            .unwrap();
+        fs::write(&path, sealed).unwrap();
+
+        assert!(matches!(
+            read_verified::<serde_json::Value>(&path),
+            Err(ReadError::KeyUnavailable)
+        ));
+        fs::write(&path, br#"{"v":"#).unwrap();
+        assert!(matches!(
+            read_verified::<serde_json::Value>(&path),
+            Err(ReadError::Invalid(_))
+        ));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_detected() {
+        let dir = temp_dir();
+        let path = dir.join("api_tokens.json");
+
+        write_atomic(&path, br#"{"v":1}"#).unwrap();
+        write_atomic(&path, br#"{"v":2}"#).unwrap();
+        // Still valid JSON, but not what we wrote.
+        let raw = fs::read_to_string(&path).unwrap();
+        fs::write(&path, raw.replace(r#""v":2"#, r#""v":3"#)).unwrap();
+
+        let restored: serde_json::Value = read_with_recovery(&path).unwrap();
+        assert_eq!(restored["v"], 1);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,4937 @@
+//! Recording pipeline module that orchestrates audio capture → STT → LLM formatting → typing.
+//!
+//! This module provides the core pipeline for voice dictation, managing the
+//! flow from audio recording through transcription to text output.
+//!
+//! ## Pipeline Hardening (Phase 5)
+//! - Cancellation tokens for aborting in-flight tasks
+//! - Timeouts on STT requests
+//! - Bounded buffer sizes
+//! - Proper error recovery (failures don't wedge the pipeline)
+//! - Explicit state machine with guards
+//!
+//! ## LLM Formatting (Phase 6)
+//! - Optional LLM-based text formatting after STT
+//! - Multiple provider support (OpenAI, Anthropic, Ollama)
+//! - Configurable prompts for dictation cleanup
+
+use crate::audio_capture::{AudioCapture, AudioCaptureDiagnostics, AudioCaptureError, AudioCaptureEvent, AudioEncodeConfig, CapturedAudio, AudioLevelSnapshot, AudioLevelStats, Endpointing, VadAutoStopConfig};
+use crate::audio_source::{AudioSource, ExternalSource};
+use crate::llm::{
+    format_text_with_retry, merge_session_segments, AnthropicLlmProvider, GeminiLlmProvider, GroqLlmProvider,
+    LlmConfig, LlmError, LlmProvider, LlmRegistry, MockLlmProvider, OllamaLlmProvider, OpenAiLlmProvider, PromptSections,
+};
+use crate::fingerprint::{fingerprint_wav, RecentSubmissions, SttResponseCache};
+use crate::mock::{MockProviders, MOCK_PROVIDER_ID};
+use crate::plugins::PluginKind;
+use crate::redaction::{Redaction, RedactionConfig, Redactor};
+use crate::request_log::RequestLogStore;
+use crate::retry::{retry_cancellable, RetryConfig, RetryProfile};
+use crate::stt::{
+    is_retryable_error, AudioFormat, SttError, SttProvider, SttRegistry, Transcript,
+    TranscriptSegment,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+fn normalize_program_path(path: &str) -> String {
+    // Windows comparisons are case-insensitive, and we want to treat / and \ equivalently.
+    path.replace('/', "\\").to_lowercase()
+}
+
+fn select_profile_for_foreground_app(
+    llm_config: &LlmConfig,
+    host: &dyn PipelineHost,
+) -> Option<crate::llm::ProgramPromptProfile> {
+    let foreground = host.foreground_app_path();
+    let Some(foreground) = foreground else {
+        return None;
+    };
+
+    let foreground_norm = normalize_program_path(&foreground);
+
+    for profile in &llm_config.program_prompt_profiles {
+        if profile
+            .program_paths
+            .iter()
+            .any(|p| normalize_program_path(p) == foreground_norm)
+        {
+            log::debug!(
+                "Pipeline: Using profile '{}' for foreground app {}",
+                profile.name,
+                foreground
+            );
+            return Some(profile.clone());
+        }
+    }
+
+    None
+}
+
+/// Mask PII in `text` before it goes to `llm`, unless the LLM runs locally.
+fn redact_for_llm(redactor: Option<&Redactor>, llm: &dyn LlmProvider, text: &str) -> Option<Redaction> {
+    if llm.name() == "ollama" {
+        return None;
+    }
+    Some(redactor?.redact(text)).filter(|r| !r.is_empty())
+}
+
+/// Put the values masked by `redact_for_llm` back into the LLM's output.
+fn restore_pii(redaction: Option<&Redaction>, text: String) -> String {
+    match redaction {
+        Some(r) => r.restore(&text),
+        None => text,
+    }
+}
+
+/// Whether an STT failure should be retried on the fallback provider: timeouts, and
+/// transient errors that survived every retry. Config/audio/4xx errors would fail again.
+fn should_fall_back(error: &PipelineError) -> bool {
+    match error {
+        PipelineError::Timeout(_) => true,
+        PipelineError::Stt(e) => is_retryable_error(e),
+        _ => false,
+    }
+}
+
+/// Run STT on `wav_bytes` with `retry_config`.
+///
+/// Cancelling `cancel` drops the request in flight (aborting the upload) and skips any
+/// pending retries.
+async fn transcribe_with_retry(
+    provider: &Arc<dyn SttProvider>,
+    wav_bytes: &Bytes,
+    context: Option<&str>,
+    retry_config: &RetryConfig,
+    cancel: &CancellationToken,
+) -> Result<Transcript, PipelineError> {
+    let format = AudioFormat::default();
+    let result = retry_cancellable(retry_config, "STT", cancel, || {
+        provider.transcribe_detailed(wav_bytes.clone(), &format, context)
+    })
+    .await;
+    match result {
+        Some(result) => result.map_err(PipelineError::from),
+        None => Err(PipelineError::Cancelled),
+    }
+}
+
+fn canonicalize_stt_provider_id(id: &str) -> String {
+    match id {
+        // Historical UI value
+        "whisper" => "local-whisper".to_string(),
+        "vosk" => "local-vosk".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Providers that transcribe on this machine (and keep working offline).
+fn is_local_stt_provider(id: &str) -> bool {
+    matches!(id, "local-whisper" | "local-vosk")
+}
+
+/// Normalize STT output text.
+///
+/// Some providers (notably Whisper-based APIs) may include a leading space as a
+/// tokenization artifact (many vocabularies encode " space+word" as a single token).
+/// We trim only *leading* whitespace to avoid changing internal formatting.
+fn normalize_stt_text(text: String) -> String {
+    match text.chars().next() {
+        Some(c) if c.is_whitespace() => text.trim_start().to_string(),
+        _ => text,
+    }
+}
+
+/// STT and LLM providers to warm up when a recording starts.
+type WarmUpTargets = (Option<Arc<dyn SttProvider>>, Option<Arc<dyn LlmProvider>>);
+
+/// Longest allowed `stt_context_secs`.
+pub const MAX_STT_CONTEXT_SECS: u32 = 600;
+
+/// Upper bound on context text kept for STT prompts; providers clamp it further.
+const STT_CONTEXT_MAX_CHARS: usize = 1000;
+
+/// The last `max_chars` characters of `text`.
+fn tail_chars(text: &str, max_chars: usize) -> String {
+    let skip = text.chars().count().saturating_sub(max_chars);
+    text.chars().skip(skip).collect()
+}
+
+/// Recently output transcripts, used as STT context for the next dictation.
+#[derive(Debug, Default)]
+struct RecentTranscripts {
+    entries: VecDeque<(Instant, String)>,
+}
+
+impl RecentTranscripts {
+    fn push(&mut self, at: Instant, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        self.entries.push_back((at, text.to_string()));
+        // Older entries can't fit in the context anyway.
+        let mut total = 0;
+        let keep = self
+            .entries
+            .iter()
+            .rev()
+            .take_while(|(_, t)| {
+                let fits = total < STT_CONTEXT_MAX_CHARS;
+                total += t.len() + 1;
+                fits
+            })
+            .count();
+        let drop = self.entries.len() - keep;
+        self.entries.drain(..drop);
+    }
+
+    /// Text dictated within `window` before `now`, oldest first.
+    fn context(&mut self, now: Instant, window: Duration) -> Option<String> {
+        self.entries
+            .retain(|(at, _)| now.saturating_duration_since(*at) <= window);
+        let context = self
+            .entries
+            .iter()
+            .map(|(_, text)| text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        (!context.is_empty()).then_some(context)
+    }
+}
+
+/// Recordings whose audio is kept by request id (see `SharedPipeline::audio_for_request`).
+/// Covers the transcriptions that can still be running while newer recordings start.
+const REQUEST_AUDIO_KEPT: usize = 8;
+
+/// Finished jobs kept for `SharedPipeline::jobs`.
+const FINISHED_JOBS_KEPT: usize = 20;
+
+/// Status of a transcription job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting for earlier jobs to finish.
+    Queued,
+    Running,
+    Done,
+    /// Failed or cancelled (see `error`).
+    Failed,
+}
+
+/// A transcription job as listed by `SharedPipeline::jobs`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionJobInfo {
+    pub id: u64,
+    pub status: JobStatus,
+    /// Request log id of the recording, when request logging is enabled.
+    pub request_id: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    pub error: Option<String>,
+}
+
+/// Counters that move while the pipeline is making progress, so a watchdog can tell a
+/// stalled pipeline from a busy one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineProgress {
+    pub state: PipelineState,
+    /// Audio callbacks seen by the level meter (moves while the microphone delivers audio).
+    pub audio_seq: u64,
+    /// Jobs queued, moved to the next stage or finished.
+    pub job_progress: u64,
+}
+
+/// A transcription running in the background.
+///
+/// Stopping a recording hands its audio to a job and frees the recorder, so the next
+/// recording can start while earlier ones are still being transcribed and formatted.
+/// Jobs run one at a time, in the order their recordings stopped.
+#[derive(Debug)]
+struct TranscriptionJob {
+    info: TranscriptionJobInfo,
+    /// `Transcribing` or `Rewriting`
+    stage: PipelineState,
+    cancel_token: CancellationToken,
+    /// Audio kept on disk until the job ends (see `crate::recovery`).
+    recovery_id: Option<String>,
+}
+
+/// Build an (uncached) API-backed STT provider from `config`.
+fn create_remote_stt_provider(
+    config: &PipelineConfig,
+    provider_id: &str,
+    api_key: String,
+    model: Option<String>,
+    request_log_store: Option<RequestLogStore>,
+) -> Result<Arc<dyn SttProvider>, PipelineError> {
+    // A whisper.cpp server has no authentication.
+    if api_key.is_empty() && provider_id != "whisper-server" {
+        return Err(PipelineError::Config(format!(
+            "STT provider '{}' requires an API key",
+            provider_id
+        )));
+    }
+
+    let vocabulary = stt_vocabulary(config);
+    let extra_params = config
+        .stt_extra_params
+        .get(provider_id)
+        .cloned()
+        .unwrap_or_default();
+    let prompt = crate::stt::prompt_with_vocabulary(
+        config.stt_transcription_prompt.as_deref(),
+        &vocabulary,
+    );
+    let provider: Arc<dyn SttProvider> = match provider_id {
+        "openai" => Arc::new(
+            crate::stt::OpenAiSttProvider::new(api_key, model, prompt)
+                .with_language(config.stt_language.clone())
+                .with_temperature(config.stt_temperature)
+                .with_verbose_json(config.stt_verbose_json)
+                .with_extra_params(extra_params)
+                .with_request_log_store(request_log_store),
+        ),
+        "groq" => Arc::new(
+            crate::stt::GroqSttProvider::new(api_key, model, prompt)
+                .with_language(config.stt_language.clone())
+                .with_temperature(config.stt_temperature)
+                .with_verbose_json(config.stt_verbose_json)
+                .with_extra_params(extra_params)
+                .with_request_log_store(request_log_store),
+        ),
+        "deepgram" => Arc::new(
+            crate::stt::DeepgramSttProvider::new(api_key, model)
+                .with_keywords(vocabulary)
+                .with_language(config.stt_language.clone())
+                .with_extra_params(extra_params)
+                .with_request_log_store(request_log_store),
+        ),
+        "speechmatics" => Arc::new(
+            crate::stt::SpeechmaticsSttProvider::new(
+                api_key,
+                model,
+                config.stt_language.clone(),
+            )
+            .with_vocabulary(stt_vocabulary_entries(config))
+            .with_extra_params(extra_params)
+            .with_request_log_store(request_log_store),
+        ),
+        "whisper-server" => Arc::new(
+            crate::stt::WhisperServerSttProvider::new(
+                config.whisper_server_url.clone(),
+                prompt,
+                config
+                    .stt_language
+                    .as_deref()
+                    .and_then(crate::stt::iso639_1_language),
+            )
+            .with_extra_params(extra_params)
+            .with_request_log_store(request_log_store),
+        ),
+        other => {
+            return Err(PipelineError::Config(format!(
+                "Unknown STT provider: {}",
+                other
+            )))
+        }
+    };
+    Ok(provider)
+}
+
+/// Cache key ("provider::model") of the STT provider `provider_id` (canonical) with `model`.
+fn stt_provider_cache_key(provider_id: &str, model: Option<&str>) -> String {
+    format!("{}::{}", provider_id, model.unwrap_or("<default>"))
+}
+
+/// What it takes to build an STT provider, taken under the pipeline lock so that the
+/// provider itself (loading a local model can take seconds) can be built without it.
+enum SttProviderRecipe {
+    Mock(crate::mock::MockScript),
+    Plugin(crate::plugins::ProviderPlugin, Option<String>),
+    #[cfg(feature = "local-whisper")]
+    LocalWhisper(crate::stt::LocalWhisperConfig),
+    #[cfg(feature = "local-vosk")]
+    LocalVosk(std::path::PathBuf),
+    Remote {
+        provider_id: String,
+        api_key: String,
+        model: Option<String>,
+        config: Box<PipelineConfig>,
+    },
+}
+
+impl SttProviderRecipe {
+    fn build(self) -> Result<Arc<dyn SttProvider>, PipelineError> {
+        match self {
+            Self::Mock(script) => Ok(Arc::new(crate::stt::MockSttProvider::new(script))),
+            Self::Plugin(plugin, model) => {
+                Ok(Arc::new(crate::stt::PluginSttProvider::new(plugin, model)))
+            }
+            #[cfg(feature = "local-whisper")]
+            Self::LocalWhisper(config) => {
+                let provider = crate::stt::LocalWhisperProvider::with_config(config).map_err(|e| {
+                    PipelineError::Config(format!("Local Whisper init failed: {}", e))
+                })?;
+                Ok(Arc::new(provider))
+            }
+            #[cfg(feature = "local-vosk")]
+            Self::LocalVosk(model_path) => {
+                let provider = crate::stt::LocalVoskProvider::new(&model_path)
+                    .map_err(|e| PipelineError::Config(format!("Local Vosk init failed: {}", e)))?;
+                Ok(Arc::new(provider))
+            }
+            Self::Remote {
+                provider_id,
+                api_key,
+                model,
+                config,
+            } => {
+                let request_log_store = config.request_log_store.clone();
+                let provider = create_remote_stt_provider(
+                    &config,
+                    &provider_id,
+                    api_key,
+                    model,
+                    request_log_store,
+                )?;
+                Ok(crate::rate_limit::RateLimitedSttProvider::wrap(provider))
+            }
+        }
+    }
+}
+
+/// STT providers missing from the cache (see `PipelineInner::pending_stt_providers`).
+struct PendingSttProviders {
+    /// `PipelineInner::stt_provider_generation` when the recipes were taken.
+    generation: u64,
+    /// Recipes by cache key.
+    recipes: Vec<(String, SttProviderRecipe)>,
+}
+
+impl PendingSttProviders {
+    /// Build the providers, leaving out the ones that fail.
+    fn build(self) -> Vec<(String, Arc<dyn SttProvider>)> {
+        self.recipes
+            .into_iter()
+            .filter_map(|(cache_key, recipe)| match recipe.build() {
+                Ok(provider) => Some((cache_key, provider)),
+                Err(e) => {
+                    log::debug!("Pipeline: Could not prepare STT provider '{}': {}", cache_key, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Build the STT providers `wanted` picks (id, model) that aren't cached yet on a blocking
+/// thread, without holding the pipeline lock, and add them to the cache. The lookups under
+/// the lock that follow then find them instead of building them there; a provider that
+/// fails to build is left for that lookup to report.
+async fn prepare_stt_providers(
+    inner: &Mutex<PipelineInner>,
+    wanted: impl FnOnce(&PipelineInner) -> Vec<(String, Option<String>)>,
+) {
+    let pending = match inner.lock() {
+        Ok(inner) => inner.pending_stt_providers(wanted(&inner)),
+        Err(_) => return,
+    };
+    if pending.recipes.is_empty() {
+        return;
+    }
+
+    let generation = pending.generation;
+    let Ok(built) = tokio::task::spawn_blocking(move || pending.build()).await else {
+        return;
+    };
+    if let Ok(mut inner) = inner.lock() {
+        inner.add_stt_providers(generation, built);
+    }
+}
+
+/// One second of a quiet 16 kHz tone, used to check STT provider connectivity.
+fn connectivity_test_wav() -> Result<Bytes, PipelineError> {
+    const SAMPLE_RATE: u32 = 16_000;
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut cursor, spec)
+        .map_err(|e| PipelineError::Config(format!("Failed to encode test audio: {}", e)))?;
+    for i in 0..SAMPLE_RATE {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let sample = (t * 440.0 * std::f32::consts::TAU).sin() * 0.05;
+        writer
+            .write_sample((sample * i16::MAX as f32) as i16)
+            .map_err(|e| PipelineError::Config(format!("Failed to encode test audio: {}", e)))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| PipelineError::Config(format!("Failed to encode test audio: {}", e)))?;
+
+    Ok(Bytes::from(cursor.into_inner()))
+}
+
+fn seconds_to_duration_or(seconds: f64, fallback: Duration) -> Duration {
+    // Guard against invalid values.
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return fallback;
+    }
+    Duration::from_secs_f64(seconds)
+}
+
+/// Default timeout for STT transcription requests
+const DEFAULT_TRANSCRIPTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest surrounding text passed to the LLM; the end (next to the caret) is kept.
+const SURROUNDING_TEXT_MAX_CHARS: usize = 500;
+
+/// Shortest recording worth a partial transcription.
+const MIN_PARTIAL_AUDIO_SECS: f32 = 1.0;
+
+/// Maximum WAV file size in bytes (50MB) to prevent memory issues
+const MAX_WAV_SIZE_BYTES: usize = 50 * 1024 * 1024;
+
+/// Upper bound for the pre-roll buffer (seconds).
+pub const MAX_PREROLL_SECS: f32 = 30.0;
+
+/// Default values for the quiet-audio gate.
+///
+/// Thresholds are in dBFS (decibels relative to full scale, where 0 dBFS is max amplitude).
+const DEFAULT_QUIET_AUDIO_MIN_DURATION_SECS: f32 = 0.15;
+const DEFAULT_QUIET_AUDIO_RMS_DBFS_THRESHOLD: f32 = -60.0;
+const DEFAULT_QUIET_AUDIO_PEAK_DBFS_THRESHOLD: f32 = -50.0;
+
+fn amp_to_dbfs(amp: f32) -> f32 {
+    if !amp.is_finite() || amp <= 0.0 {
+        f32::NEG_INFINITY
+    } else {
+        20.0 * amp.log10()
+    }
+}
+
+fn is_effectively_quiet(
+    stats: AudioLevelStats,
+    min_duration_secs: f32,
+    rms_dbfs_threshold: f32,
+    peak_dbfs_threshold: f32,
+) -> bool {
+    // Very short recordings are usually accidental taps; treat as quiet.
+    if stats.duration_secs < min_duration_secs {
+        return true;
+    }
+
+    let rms_dbfs = amp_to_dbfs(stats.rms);
+    let peak_dbfs = amp_to_dbfs(stats.peak);
+
+    rms_dbfs < rms_dbfs_threshold && peak_dbfs < peak_dbfs_threshold
+}
+
+/// Why the quiet-audio gate skips STT for a recording with `diagnostics`, if it does.
+fn quiet_audio_skip_reason(
+    config: &PipelineConfig,
+    diagnostics: &AudioCaptureDiagnostics,
+) -> Option<SkipReason> {
+    if !config.quiet_audio_gate_enabled {
+        return None;
+    }
+
+    // Hallucination protection: if VAD heard no speech at all, skip STT. The realtime
+    // VAD is consulted whenever it ran; the offline scan is opt-in.
+    let no_speech = diagnostics.realtime_speech_detected == Some(false)
+        || (config.quiet_audio_require_speech && diagnostics.speech_detected == Some(false));
+    if no_speech {
+        return Some(SkipReason::EmptyRecording);
+    }
+
+    is_effectively_quiet(
+        diagnostics.stats,
+        config.quiet_audio_min_duration_secs,
+        config.quiet_audio_rms_dbfs_threshold,
+        config.quiet_audio_peak_dbfs_threshold,
+    )
+    .then_some(SkipReason::Quiet)
+}
+
+/// What a short test recording tells about the microphone (see
+/// `commands::audio::run_audio_diagnostics`).
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDiagnostics {
+    /// Input device recorded from (`None`: the system default).
+    pub device_name: Option<String>,
+    /// Format the device delivered.
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_secs: f32,
+    /// Levels in dBFS (`null` for digital silence).
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    /// Whether the VAD heard speech (the realtime VAD when it is on, else an offline scan).
+    pub speech_detected: bool,
+    /// Why a dictation with this audio would not be transcribed, if it wouldn't.
+    pub skip_reason: Option<SkipReason>,
+}
+
+impl AudioDiagnostics {
+    /// Report on a test recording with `diagnostics`, judged by `config`'s quiet-audio gate.
+    pub fn new(
+        config: &PipelineConfig,
+        device_name: Option<String>,
+        sample_rate: u32,
+        channels: u16,
+        diagnostics: &AudioCaptureDiagnostics,
+    ) -> Self {
+        Self {
+            device_name,
+            sample_rate,
+            channels,
+            duration_secs: diagnostics.stats.duration_secs,
+            peak_dbfs: amp_to_dbfs(diagnostics.stats.peak),
+            rms_dbfs: amp_to_dbfs(diagnostics.stats.rms),
+            speech_detected: diagnostics
+                .realtime_speech_detected
+                .or(diagnostics.speech_detected)
+                .unwrap_or(false),
+            skip_reason: quiet_audio_skip_reason(config, diagnostics),
+        }
+    }
+}
+
+/// Errors that can occur in the recording pipeline
+#[derive(Debug, thiserror::Error)]
+pub enum PipelineError {
+    #[error("Audio capture error: {0}")]
+    AudioCapture(#[from] AudioCaptureError),
+
+    #[error("STT error: {0}")]
+    Stt(#[from] SttError),
+
+    #[error("LLM error: {0}")]
+    Llm(#[from] LlmError),
+
+    #[error("No STT provider configured")]
+    NoProvider,
+
+    #[error("Pipeline is already recording")]
+    AlreadyRecording,
+
+    #[error("Pipeline is not recording")]
+    NotRecording,
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("Lock error: {0}")]
+    Lock(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("Transcription timeout after {0:?}")]
+    Timeout(Duration),
+
+    #[error("Recording too large: {0} bytes exceeds limit of {1} bytes")]
+    RecordingTooLarge(usize, usize),
+}
+
+/// Pipeline state machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineState {
+    /// Pipeline is idle, ready to start recording
+    Idle,
+    /// Pipeline is actively recording audio
+    Recording,
+    /// Pipeline is transcribing recorded audio
+    Transcribing,
+    /// Pipeline is rewriting/formatting text via an LLM (optional step)
+    Rewriting,
+    /// Pipeline encountered an error (recoverable - can start new recording)
+    Error,
+}
+
+impl PipelineState {
+    /// Check if this state allows starting a new recording
+    ///
+    /// Transcriptions run in the background, so only another recording prevents it.
+    pub fn can_start_recording(&self) -> bool {
+        !matches!(self, PipelineState::Recording)
+    }
+
+    /// Check if this state allows stopping a recording
+    pub fn can_stop_recording(&self) -> bool {
+        matches!(self, PipelineState::Recording)
+    }
+
+    /// Check if this state allows cancellation
+    pub fn can_cancel(&self) -> bool {
+        matches!(
+            self,
+            PipelineState::Recording | PipelineState::Transcribing | PipelineState::Rewriting
+        )
+    }
+}
+
+/// Events emitted by the pipeline
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub enum PipelineEvent {
+    /// Recording has started
+    RecordingStarted,
+    /// Recording has stopped
+    RecordingStopped,
+    /// Transcription is in progress
+    TranscriptionStarted,
+    /// Final transcript received
+    TranscriptReady(String),
+    /// Interim transcript of the recording so far, from a streaming provider; replaced by
+    /// later ones and by the final transcript.
+    PartialTranscript(String),
+    /// An error occurred
+    Error(String),
+    /// The recording should be stopped and transcribed now (see
+    /// `SharedPipeline::spawn_capture_watcher`).
+    AutoStop(AutoStopReason),
+    /// The input device was lost mid-recording and capture continues on another one.
+    InputDeviceSwitched { lost: String, switched_to: String },
+}
+
+impl PipelineEvent {
+    /// What a capture event means for the recording, if anything. `SpeechEnd` only ends it
+    /// with VAD auto-stop on.
+    fn from_capture_event(event: AudioCaptureEvent, vad_auto_stop: bool) -> Option<Self> {
+        match event {
+            AudioCaptureEvent::SpeechEnd if vad_auto_stop => {
+                Some(Self::AutoStop(AutoStopReason::SpeechEnd))
+            }
+            AudioCaptureEvent::MaxDurationReached => {
+                Some(Self::AutoStop(AutoStopReason::MaxDuration))
+            }
+            AudioCaptureEvent::DeviceLost {
+                device,
+                switched_to: None,
+            } => Some(Self::AutoStop(AutoStopReason::DeviceLost(device))),
+            AudioCaptureEvent::DeviceLost {
+                device,
+                switched_to: Some(switched_to),
+            } => Some(Self::InputDeviceSwitched {
+                lost: device,
+                switched_to,
+            }),
+            AudioCaptureEvent::SpeechStart
+            | AudioCaptureEvent::SpeechEnd
+            | AudioCaptureEvent::SourceEnded => None,
+        }
+    }
+}
+
+/// Why a recording ended without the user stopping it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoStopReason {
+    /// VAD auto-stop is on and the speaker stopped talking.
+    SpeechEnd,
+    /// `stop_at_max_duration` is on and the recording is full.
+    MaxDuration,
+    /// The input device went away with no replacement; stopping the recording reports
+    /// `AudioCaptureError::DeviceLost`.
+    DeviceLost(String),
+}
+
+/// How often the capture watcher polls for capture events while recording.
+const CAPTURE_WATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Outcome of the optional LLM formatting step.
+#[derive(Debug, Clone)]
+pub enum LlmOutcome {
+    /// LLM step was not attempted (not configured or disabled).
+    NotAttempted,
+    /// LLM step completed successfully and returned formatted text.
+    Succeeded,
+    /// LLM step timed out and the pipeline fell back to the raw STT transcript.
+    TimedOut,
+    /// LLM step failed and the pipeline fell back to the raw STT transcript.
+    Failed(String),
+}
+
+/// Why a recording was finished without being sent to STT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// No speech was detected anywhere in the recording.
+    EmptyRecording,
+    /// The recording was too short or too quiet.
+    Quiet,
+    /// The same audio was submitted moments ago.
+    Duplicate,
+}
+
+/// Detailed result for a transcription request.
+///
+/// This separates the raw STT transcript from the final output (which may
+/// include LLM formatting and/or fallbacks).
+#[derive(Debug, Clone)]
+pub struct TranscriptionResult {
+    /// Raw transcript as returned from the STT provider (before any LLM formatting).
+    pub stt_text: String,
+    /// Final output text returned by the pipeline.
+    /// If LLM formatting was disabled, this will match `stt_text`.
+    /// If LLM formatting failed/timed out, this falls back to `stt_text`.
+    pub final_text: String,
+    /// Duration of the STT phase (including retries), in milliseconds.
+    pub stt_duration_ms: u64,
+    /// Duration of the LLM phase (including timeout/fallback), in milliseconds.
+    pub llm_duration_ms: Option<u64>,
+    /// LLM provider id actually used for this transcription (if the LLM step was attempted).
+    ///
+    /// This is sourced from the concrete provider instance (including any default/fallback
+    /// model selection performed by the provider implementation).
+    pub llm_provider_used: Option<String>,
+    /// LLM model actually used for this transcription (if the LLM step was attempted).
+    ///
+    /// This is sourced from the concrete provider instance. If the configured model is None,
+    /// this will still be populated with the provider's internal default model.
+    pub llm_model_used: Option<String>,
+    /// Outcome of the LLM phase.
+    pub llm_outcome: LlmOutcome,
+    /// Set when formatting failed and should be retried in the background.
+    pub deferred_formatting: Option<DeferredFormatting>,
+    /// Set when STT was skipped; the texts are then empty.
+    pub skipped: Option<SkipReason>,
+    /// Language the STT provider detected (verbose responses only).
+    pub stt_language: Option<String>,
+    /// Timed segments of the raw transcript (verbose responses only).
+    pub stt_segments: Vec<TranscriptSegment>,
+    /// Length of the transcribed audio, in seconds.
+    pub audio_duration_secs: Option<f32>,
+}
+
+/// An LLM formatting step that failed during transcription, to be retried later.
+#[derive(Clone)]
+pub struct DeferredFormatting {
+    pub provider: Arc<dyn LlmProvider>,
+    pub prompts: PromptSections,
+    /// Per-attempt timeout.
+    pub timeout: Duration,
+    pub retry_config: RetryConfig,
+    /// Transcript to format (the text that was output unformatted).
+    pub raw_text: String,
+    /// `raw_text` with PII masked, when it goes to a cloud provider with redaction on.
+    pub redaction: Option<Redaction>,
+}
+
+impl std::fmt::Debug for DeferredFormatting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeferredFormatting")
+            .field("provider", &self.provider.name())
+            .field("model", &self.provider.model())
+            .field("timeout", &self.timeout)
+            .field("raw_text_len", &self.raw_text.len())
+            .field("redacted", &self.redaction.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TranscriptionResult {
+    /// Result for a recording that was not sent to STT.
+    fn skipped(reason: SkipReason) -> Self {
+        Self {
+            stt_text: String::new(),
+            final_text: String::new(),
+            stt_duration_ms: 0,
+            llm_duration_ms: None,
+            llm_provider_used: None,
+            llm_model_used: None,
+            llm_outcome: LlmOutcome::NotAttempted,
+            deferred_formatting: None,
+            skipped: Some(reason),
+            stt_language: None,
+            stt_segments: Vec::new(),
+            audio_duration_secs: None,
+        }
+    }
+
+    pub fn llm_attempted(&self) -> bool {
+        !matches!(self.llm_outcome, LlmOutcome::NotAttempted)
+    }
+}
+
+/// Configuration for the recording pipeline
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Optional backend input device name (CPAL device name).
+    ///
+    /// When set, recording will attempt to use the first input device whose name
+    /// matches exactly, falling back to the system default if not found.
+    pub input_device_name: Option<String>,
+    /// Maximum recording duration in seconds
+    pub max_duration_secs: f32,
+    /// End the recording when `max_duration_secs` is reached (it is then stopped and
+    /// transcribed). When off, only the most recent `max_duration_secs` are kept.
+    pub stop_at_max_duration: bool,
+    /// Seconds of audio to keep buffered while idle and prepend to the next recording.
+    ///
+    /// When > 0 the mic stays open between recordings (pre-roll standby). 0 disables it.
+    pub preroll_secs: f32,
+    /// STT provider to use
+    pub stt_provider: String,
+    /// API key for the STT provider
+    pub stt_api_key: String,
+    /// API keys for all configured STT providers (provider id -> key)
+    pub stt_api_keys: HashMap<String, String>,
+    /// Optional model override for STT
+    pub stt_model: Option<String>,
+    /// Language of the dictation (e.g. "en"), sent to every cloud STT provider as a hint.
+    /// `None` lets the provider detect it.
+    pub stt_language: Option<String>,
+    /// URL of the whisper.cpp server used by the "whisper-server" provider (`None` for
+    /// localhost).
+    pub whisper_server_url: Option<String>,
+    /// Extra request parameters per STT provider id (`stt_extra_params` setting), for
+    /// provider features without a setting of their own.
+    pub stt_extra_params: HashMap<String, crate::stt::ExtraParams>,
+    /// Sampling temperature for the OpenAI/Groq Whisper endpoints (`None` = provider default).
+    pub stt_temperature: Option<f32>,
+    /// Ask the OpenAI/Groq Whisper endpoints for `verbose_json`, which adds the detected
+    /// language and timed segments to `TranscriptionResult`.
+    pub stt_verbose_json: bool,
+
+    /// Optional global transcription prompt.
+    ///
+    /// Applied by STT providers that support prompting (currently OpenAI transcription endpoint models).
+    pub stt_transcription_prompt: Option<String>,
+    /// Pass personal dictionary terms to the STT provider (Deepgram keywords, Whisper
+    /// prompt) so names are recognized correctly before any LLM cleanup.
+    pub stt_vocabulary_boost: bool,
+    /// Pass text dictated within this many seconds to the STT provider as context
+    /// (Whisper `prompt` / initial tokens). 0 disables it.
+    pub stt_context_secs: u32,
+    /// Secondary STT provider used when the primary one exhausts its retries or times out
+    /// (e.g. Groq -> local Whisper). `None` disables the fallback.
+    pub fallback_stt_provider: Option<String>,
+    /// Retry configuration for STT requests
+    pub retry_config: RetryConfig,
+    /// VAD auto-stop configuration
+    pub vad_config: VadAutoStopConfig,
+    /// Timeout for transcription requests
+    pub transcription_timeout: Duration,
+    /// Maximum recording size in bytes (0 = no limit beyond default)
+    pub max_recording_bytes: usize,
+
+    /// Enable a quiet-audio gate to avoid silent-audio hallucinations.
+    pub quiet_audio_gate_enabled: bool,
+    /// Treat recordings shorter than this as effectively quiet.
+    pub quiet_audio_min_duration_secs: f32,
+    /// RMS threshold (in dBFS) below which the audio is considered quiet.
+    pub quiet_audio_rms_dbfs_threshold: f32,
+    /// Peak threshold (in dBFS) below which the audio is considered quiet.
+    pub quiet_audio_peak_dbfs_threshold: f32,
+
+    /// Optional noise gate threshold (dBFS), applied at stop-time before WAV encoding.
+    ///
+    /// Recommended range: -75..-30. `None` disables the noise gate.
+    pub noise_gate_threshold_dbfs: Option<f32>,
+
+    // ------------------------------------------------------------------------
+    // Voice pickup (preprocessing) options
+    // ------------------------------------------------------------------------
+    /// Convert captured audio to mono before WAV encoding.
+    pub audio_downmix_to_mono: bool,
+    /// Resample to 16kHz before WAV encoding.
+    pub audio_resample_to_16khz: bool,
+    /// Apply a lightweight high-pass (DC/rumble) filter.
+    pub audio_highpass_enabled: bool,
+    /// Apply a lightweight auto-gain/normalization.
+    pub audio_agc_enabled: bool,
+    /// Apply a lightweight noise suppression.
+    pub audio_noise_suppression_enabled: bool,
+    /// Strip leading/trailing silence (via VAD) before upload.
+    pub audio_trim_silence: bool,
+    /// When trimming, shorten internal pauses longer than this many seconds. `None` keeps them.
+    pub audio_max_pause_secs: Option<f32>,
+
+    /// Rewrite spoken identifier commands ("camel case user name" -> `userName`) in the
+    /// transcript before any LLM step (see `crate::postprocess`).
+    pub identifier_casing_enabled: bool,
+
+    /// Corrections learned from reviewed transcripts, applied before identifier casing
+    /// (see `crate::corrections`).
+    pub learned_corrections: Vec<crate::corrections::LearnedCorrection>,
+
+    // ------------------------------------------------------------------------
+    // Extra hallucination protection
+    // ------------------------------------------------------------------------
+    /// If enabled, run an offline VAD scan at stop-time and skip STT when no speech is detected.
+    pub quiet_audio_require_speech: bool,
+    /// Drop transcripts that consist only of known Whisper hallucinations ("Thanks for
+    /// watching!") when the audio was short or quiet (see `crate::hallucination`).
+    pub hallucination_filter_enabled: bool,
+    /// Extra phrases for the hallucination filter, on top of the built-in list.
+    pub hallucination_filter_phrases: Vec<String>,
+    /// LLM formatting configuration
+    pub llm_config: LlmConfig,
+    /// API keys for all configured LLM providers (provider id -> key)
+    pub llm_api_keys: HashMap<String, String>,
+    /// When LLM formatting fails or times out, keep the raw transcript and retry formatting
+    /// in the background (the app queues the retry).
+    pub deferred_llm_formatting: bool,
+
+    /// PII masking for text sent to cloud LLMs and kept in request logs (see
+    /// `crate::redaction`); profiles can switch it on or off with `redact_pii`.
+    pub redaction: RedactionConfig,
+
+    /// External command run on the final transcript before it is output (see
+    /// `crate::hooks`).
+    pub output_hook: crate::hooks::HookConfig,
+
+    /// Optional request log store for capturing provider request/response payloads.
+    pub request_log_store: Option<RequestLogStore>,
+    /// Scripts for the `mock` STT and LLM providers (see `crate::mock`). `None` (the default)
+    /// keeps the `mock` provider id unavailable.
+    pub mock_providers: Option<MockProviders>,
+    /// STT and LLM providers implemented by external programs (see `crate::plugins`).
+    pub provider_plugins: Vec<crate::plugins::ProviderPlugin>,
+    /// Path to local Whisper model (for local-whisper feature)
+    #[cfg(feature = "local-whisper")]
+    pub whisper_model_path: Option<std::path::PathBuf>,
+    /// Decoding parameters for local Whisper (beam size, temperature fallback, ...)
+    #[cfg(feature = "local-whisper")]
+    pub whisper_decoding: crate::stt::WhisperDecodingParams,
+    /// Thread count, priority and pausing for local Whisper inference
+    #[cfg(feature = "local-whisper")]
+    pub whisper_threading: crate::stt::WhisperThreading,
+    /// Directory of the local Vosk model (for local-vosk feature)
+    #[cfg(feature = "local-vosk")]
+    pub vosk_model_path: Option<std::path::PathBuf>,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            input_device_name: None,
+            max_duration_secs: 300.0, // 5 minutes max
+            stop_at_max_duration: false,
+            preroll_secs: 0.0,
+            stt_provider: "groq".to_string(),
+            stt_api_key: String::new(),
+            stt_api_keys: HashMap::new(),
+            stt_model: None,
+            stt_transcription_prompt: None,
+            stt_vocabulary_boost: true,
+            stt_context_secs: 0,
+            stt_language: None,
+            whisper_server_url: None,
+            stt_extra_params: HashMap::new(),
+            stt_temperature: None,
+            stt_verbose_json: false,
+            fallback_stt_provider: None,
+            retry_config: RetryConfig::default(),
+            vad_config: VadAutoStopConfig::default(),
+            transcription_timeout: DEFAULT_TRANSCRIPTION_TIMEOUT,
+            max_recording_bytes: MAX_WAV_SIZE_BYTES,
+
+            quiet_audio_gate_enabled: true,
+            quiet_audio_min_duration_secs: DEFAULT_QUIET_AUDIO_MIN_DURATION_SECS,
+            quiet_audio_rms_dbfs_threshold: DEFAULT_QUIET_AUDIO_RMS_DBFS_THRESHOLD,
+            quiet_audio_peak_dbfs_threshold: DEFAULT_QUIET_AUDIO_PEAK_DBFS_THRESHOLD,
+
+            noise_gate_threshold_dbfs: None,
+
+            audio_downmix_to_mono: true,
+            audio_resample_to_16khz: true,
+            audio_highpass_enabled: true,
+            audio_agc_enabled: false,
+            audio_noise_suppression_enabled: false,
+            audio_trim_silence: false,
+            audio_max_pause_secs: None,
+            identifier_casing_enabled: false,
+            learned_corrections: Vec::new(),
+
+            quiet_audio_require_speech: false,
+            hallucination_filter_enabled: true,
+            hallucination_filter_phrases: Vec::new(),
+
+            llm_config: LlmConfig::default(),
+            deferred_llm_formatting: true,
+            llm_api_keys: HashMap::new(),
+            redaction: RedactionConfig::default(),
+            output_hook: crate::hooks::HookConfig::default(),
+            request_log_store: None,
+            mock_providers: None,
+            provider_plugins: Vec::new(),
+            #[cfg(feature = "local-whisper")]
+            whisper_model_path: None,
+            #[cfg(feature = "local-whisper")]
+            whisper_decoding: crate::stt::WhisperDecodingParams::default(),
+            #[cfg(feature = "local-whisper")]
+            whisper_threading: crate::stt::WhisperThreading::default(),
+            #[cfg(feature = "local-vosk")]
+            vosk_model_path: None,
+        }
+    }
+}
+
+/// Internal state for the recording pipeline
+struct PipelineInner {
+    audio_capture: AudioCapture,
+    stt_registry: SttRegistry,
+    llm_registry: LlmRegistry,
+    stt_provider_cache: HashMap<String, Arc<dyn SttProvider>>,
+    /// Bumped whenever cached STT providers are evicted, so providers built without the
+    /// lock from older settings are not added (see `prepare_stt_providers`).
+    stt_provider_generation: u64,
+    llm_provider_cache: HashMap<String, Arc<dyn LlmProvider>>,
+    state: PipelineState,
+    config: PipelineConfig,
+    /// Cancellation token for the current operation
+    cancel_token: Option<CancellationToken>,
+
+    /// Last captured audio (WAV bytes). Used for debugging/testing.
+    last_wav_bytes: Option<Bytes>,
+
+    /// Last recording diagnostics (raw stats + optional speech detection).
+    last_recording_diagnostics: Option<AudioCaptureDiagnostics>,
+
+    /// Fingerprints of recently submitted recordings (double-submission guard).
+    recent_submissions: RecentSubmissions,
+
+    /// STT responses for recently transcribed audio, keyed by fingerprint + provider + model.
+    stt_response_cache: SttResponseCache,
+
+    /// Recent output, passed to STT as context (see `PipelineConfig::stt_context_secs`).
+    recent_transcripts: RecentTranscripts,
+
+    /// Text before the caret in the target field, captured when the recording started
+    /// (see `SharedPipeline::set_surrounding_text`).
+    surrounding_text: Option<String>,
+
+    /// No network connectivity (see `SharedPipeline::set_offline`): prefer local providers.
+    offline: bool,
+
+    /// Profile bound to the hotkey that started the current (or last) recording; it
+    /// applies instead of the foreground app's profile until the next recording starts.
+    hotkey_profile_id: Option<String>,
+
+    /// Background transcriptions by job id, oldest first. `state` only describes the
+    /// recorder; see `effective_state`.
+    jobs: BTreeMap<u64, TranscriptionJob>,
+    next_job_id: u64,
+    /// Most recent finished jobs, oldest first.
+    finished_jobs: VecDeque<TranscriptionJobInfo>,
+    /// Notified whenever a job leaves `jobs`, so the next queued one can run.
+    job_turns: Arc<Notify>,
+    /// Bumped whenever a job is queued, changes stage or ends (see `PipelineProgress`).
+    job_progress: u64,
+
+    /// Audio of recent recordings by request log id, newest last.
+    request_audio: VecDeque<(String, Bytes)>,
+
+    /// Deepgram stream of the current recording when the end of speech comes from the
+    /// provider (`Endpointing::Provider`).
+    provider_endpointing: Option<Arc<crate::stt::DeepgramEndpointing>>,
+
+    host: Arc<dyn PipelineHost>,
+}
+
+impl PipelineInner {
+    fn new(config: PipelineConfig, host: Arc<dyn PipelineHost>) -> Self {
+        let mut audio_capture = AudioCapture::with_vad_config(config.vad_config.clone());
+        audio_capture.set_stop_at_max_duration(config.stop_at_max_duration);
+        let mut inner = Self {
+            audio_capture,
+            stt_registry: SttRegistry::new(),
+            llm_registry: LlmRegistry::new(),
+            stt_provider_cache: HashMap::new(),
+            stt_provider_generation: 0,
+            llm_provider_cache: HashMap::new(),
+            state: PipelineState::Idle,
+            config: config.clone(),
+            cancel_token: None,
+            last_wav_bytes: None,
+            last_recording_diagnostics: None,
+            recent_submissions: RecentSubmissions::default(),
+            stt_response_cache: SttResponseCache::default(),
+            recent_transcripts: RecentTranscripts::default(),
+            surrounding_text: None,
+            offline: false,
+            hotkey_profile_id: None,
+            jobs: BTreeMap::new(),
+            next_job_id: 0,
+            finished_jobs: VecDeque::new(),
+            job_turns: Arc::new(Notify::new()),
+            job_progress: 0,
+            request_audio: VecDeque::new(),
+            provider_endpointing: None,
+            host,
+        };
+        inner.initialize_providers(&config);
+        inner.sync_preroll_standby();
+        inner
+    }
+
+    /// Profile in effect: the hotkey's (see `SharedPipeline::start_recording_for_profile`),
+    /// else the one matching the foreground app.
+    fn active_profile(&self) -> Option<crate::llm::ProgramPromptProfile> {
+        let llm_config = &self.config.llm_config;
+        if let Some(id) = &self.hotkey_profile_id {
+            match llm_config.program_prompt_profiles.iter().find(|p| &p.id == id) {
+                Some(profile) => return Some(profile.clone()),
+                None => log::warn!("Pipeline: Hotkey profile '{}' no longer exists", id),
+            }
+        }
+        select_profile_for_foreground_app(llm_config, self.host.as_ref())
+    }
+
+    fn get_or_create_stt_provider(
+        &mut self,
+        provider_id: &str,
+        model: Option<String>,
+    ) -> Result<Arc<dyn SttProvider>, PipelineError> {
+        let provider_id = canonicalize_stt_provider_id(provider_id);
+        let cache_key = stt_provider_cache_key(&provider_id, model.as_deref());
+
+        if let Some(p) = self.stt_provider_cache.get(&cache_key) {
+            return Ok(p.clone());
+        }
+
+        let provider = self.stt_provider_recipe(&provider_id, model)?.build()?;
+        self.stt_provider_cache.insert(cache_key, provider.clone());
+        Ok(provider)
+    }
+
+    /// What it takes to build the STT provider `provider_id` (canonical) with `model`.
+    fn stt_provider_recipe(
+        &self,
+        provider_id: &str,
+        model: Option<String>,
+    ) -> Result<SttProviderRecipe, PipelineError> {
+        if provider_id == MOCK_PROVIDER_ID {
+            return Ok(SttProviderRecipe::Mock(self.mock_providers()?.stt.clone()));
+        }
+
+        if let Some(plugin) =
+            crate::plugins::find(&self.config.provider_plugins, PluginKind::Stt, provider_id)
+        {
+            return Ok(SttProviderRecipe::Plugin(plugin.clone(), model));
+        }
+
+        #[cfg(feature = "local-whisper")]
+        if provider_id == "local-whisper" {
+            if let Some(model_path) = &self.config.whisper_model_path {
+                let threading = &self.config.whisper_threading;
+                return Ok(SttProviderRecipe::LocalWhisper(crate::stt::LocalWhisperConfig {
+                    model_path: model_path.clone(),
+                    n_threads: threading.n_threads,
+                    priority: threading.priority,
+                    pause_signal: threading
+                        .pause_while_recording
+                        .then(|| self.audio_capture.recording_flag()),
+                    decoding: self.config.whisper_decoding.clone(),
+                    ..Default::default()
+                }));
+            }
+
+            return Err(PipelineError::Config(
+                "Local Whisper selected but no model path configured".to_string(),
+            ));
+        }
+
+        #[cfg(feature = "local-vosk")]
+        if provider_id == "local-vosk" {
+            let model_path = self.config.vosk_model_path.clone().ok_or_else(|| {
+                PipelineError::Config("Local Vosk selected but no model is downloaded".to_string())
+            })?;
+            return Ok(SttProviderRecipe::LocalVosk(model_path));
+        }
+
+        let api_key = self
+            .config
+            .stt_api_keys
+            .get(provider_id)
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(SttProviderRecipe::Remote {
+            provider_id: provider_id.to_string(),
+            api_key,
+            model,
+            config: Box::new(self.config.clone()),
+        })
+    }
+
+    /// Recipes for the providers in `wanted` (id, model) that aren't cached yet. Providers
+    /// that can't be built are left out; the lookup under the lock reports why.
+    fn pending_stt_providers(&self, wanted: Vec<(String, Option<String>)>) -> PendingSttProviders {
+        let mut recipes: Vec<(String, SttProviderRecipe)> = Vec::new();
+        for (provider_id, model) in wanted {
+            let provider_id = canonicalize_stt_provider_id(&provider_id);
+            let cache_key = stt_provider_cache_key(&provider_id, model.as_deref());
+            if self.stt_provider_cache.contains_key(&cache_key)
+                || recipes.iter().any(|(key, _)| *key == cache_key)
+            {
+                continue;
+            }
+            if let Ok(recipe) = self.stt_provider_recipe(&provider_id, model) {
+                recipes.push((cache_key, recipe));
+            }
+        }
+        PendingSttProviders {
+            generation: self.stt_provider_generation,
+            recipes,
+        }
+    }
+
+    /// Cache providers built from `pending_stt_providers` at `generation`, unless the
+    /// settings they were built from changed in the meantime.
+    fn add_stt_providers(&mut self, generation: u64, built: Vec<(String, Arc<dyn SttProvider>)>) {
+        if generation != self.stt_provider_generation {
+            return;
+        }
+        for (cache_key, provider) in built {
+            self.stt_provider_cache.entry(cache_key).or_insert(provider);
+        }
+    }
+
+    /// STT providers a transcription with `profile` may use: its own and, while offline,
+    /// the local one it is swapped for (see `offline_stt_provider`). With `with_fallback`,
+    /// also the configured fallback (see `fallback_stt_provider_for`).
+    fn stt_providers_wanted(
+        &self,
+        profile: Option<&crate::llm::ProgramPromptProfile>,
+        with_fallback: bool,
+    ) -> Vec<(String, Option<String>)> {
+        let (provider_id, model) = self.stt_provider_choice(profile);
+        let offline_local = self
+            .local_stt_provider_id()
+            .filter(|_| self.offline && !is_local_stt_provider(&provider_id));
+        let mut wanted = vec![(provider_id, model)];
+        wanted.extend(offline_local.map(|id| (id.to_string(), None)));
+        if with_fallback {
+            wanted.extend(self.fallback_stt_provider_id().map(|id| (id, None)));
+        }
+        wanted
+    }
+
+    fn mock_providers(&self) -> Result<&MockProviders, PipelineError> {
+        self.config.mock_providers.as_ref().ok_or_else(|| {
+            PipelineError::Config(
+                "The mock provider is only available with mock providers enabled".to_string(),
+            )
+        })
+    }
+
+    /// Cache key ("provider::model") of a provider returned by `get_or_create_stt_provider`.
+    fn stt_provider_key(&self, provider: &Arc<dyn SttProvider>) -> String {
+        self.stt_provider_cache
+            .iter()
+            .find(|(_, p)| Arc::ptr_eq(p, provider))
+            .map(|(k, _)| k.clone())
+            .unwrap_or_else(|| provider.name().to_string())
+    }
+
+    /// Local provider to fall back to offline: local Whisper if its model is present on
+    /// disk, else local Vosk.
+    fn local_stt_provider_id(&self) -> Option<&'static str> {
+        #[cfg(feature = "local-whisper")]
+        if self
+            .config
+            .whisper_model_path
+            .as_ref()
+            .is_some_and(|path| path.exists())
+        {
+            return Some("local-whisper");
+        }
+        #[cfg(feature = "local-vosk")]
+        if self
+            .config
+            .vosk_model_path
+            .as_ref()
+            .is_some_and(|path| path.is_dir())
+        {
+            return Some("local-vosk");
+        }
+        None
+    }
+
+    /// Whether a local model is configured and present on disk.
+    fn local_stt_available(&self) -> bool {
+        self.local_stt_provider_id().is_some()
+    }
+
+    /// While offline, swap a cloud STT provider for a local one when one is installed.
+    fn offline_stt_provider(&mut self, provider: Arc<dyn SttProvider>) -> Arc<dyn SttProvider> {
+        if !self.offline || is_local_stt_provider(provider.name()) {
+            return provider;
+        }
+        let Some(local_id) = self.local_stt_provider_id() else {
+            return provider;
+        };
+
+        match self.get_or_create_stt_provider(local_id, None) {
+            Ok(local) => {
+                log::info!(
+                    "Pipeline: Offline, using '{}' instead of '{}'",
+                    local_id,
+                    provider.name()
+                );
+                if let Some(store) = &self.config.request_log_store {
+                    store.with_current(|log| {
+                        log.warn(format!(
+                            "Offline: using '{}' instead of '{}'",
+                            local_id,
+                            provider.name()
+                        ));
+                        log.stt_provider = local.name().to_string();
+                        log.stt_model = None;
+                    });
+                }
+                local
+            }
+            Err(e) => {
+                log::warn!("Pipeline: Offline but '{}' is unavailable ({})", local_id, e);
+                provider
+            }
+        }
+    }
+
+    /// While offline, skip cloud LLM formatting (a local Ollama server still runs).
+    fn offline_llm_provider(
+        &self,
+        provider: Option<Arc<dyn LlmProvider>>,
+    ) -> Option<Arc<dyn LlmProvider>> {
+        let provider = provider?;
+        if !self.offline || provider.name() == "ollama" {
+            return Some(provider);
+        }
+
+        log::info!("Pipeline: Offline, skipping '{}' LLM formatting", provider.name());
+        if let Some(store) = &self.config.request_log_store {
+            store.with_current(|log| {
+                log.warn(format!("Offline: skipped '{}' LLM formatting", provider.name()));
+            });
+        }
+        None
+    }
+
+    /// PII redactor for text of `profile` (its `redact_pii` if set, else the global setting).
+    fn redactor_for(&self, profile: Option<&crate::llm::ProgramPromptProfile>) -> Option<Redactor> {
+        let config = &self.config.redaction;
+        let enabled = profile.and_then(|p| p.redact_pii).unwrap_or(config.enabled);
+        enabled.then(|| Redactor::new(config))
+    }
+
+    /// `redactor_for` for a transcription request; also marks the current request log so it
+    /// is scrubbed before it's stored.
+    fn request_redactor(&self, profile: Option<&crate::llm::ProgramPromptProfile>) -> Option<Redactor> {
+        let redactor = self.redactor_for(profile)?;
+        if let Some(store) = &self.config.request_log_store {
+            store.with_current(|log| log.redactor = Some(redactor.clone()));
+        }
+        Some(redactor)
+    }
+
+    /// Id of the configured fallback STT provider, unless it can't be used offline.
+    fn fallback_stt_provider_id(&self) -> Option<String> {
+        let fallback_id = canonicalize_stt_provider_id(self.config.fallback_stt_provider.as_deref()?);
+        (!self.offline || is_local_stt_provider(&fallback_id)).then_some(fallback_id)
+    }
+
+    /// Resolve the configured fallback STT provider, unless it is the primary provider itself.
+    fn fallback_stt_provider_for(
+        &mut self,
+        primary: &Arc<dyn SttProvider>,
+    ) -> Option<Arc<dyn SttProvider>> {
+        let fallback_id = self.fallback_stt_provider_id()?;
+        match self.get_or_create_stt_provider(&fallback_id, None) {
+            Ok(p) if p.name() != primary.name() => Some(p),
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!(
+                    "Pipeline: Fallback STT provider '{}' unavailable ({})",
+                    fallback_id,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Record the fingerprint of audio about to be transcribed on the current request log.
+    fn record_audio_fingerprint(&self, fingerprint: &str) {
+        if let Some(store) = &self.config.request_log_store {
+            store.with_current(|log| {
+                log.audio_fingerprint = Some(fingerprint.to_string());
+            });
+        }
+    }
+
+    /// Why audio with `diagnostics` should skip STT, if it should: the quiet-audio gate, then
+    /// (with `check_duplicate`) the double-submission check. Shared by fresh recordings and
+    /// retries of saved audio; retries skip the duplicate check because they resubmit the
+    /// same audio on purpose.
+    fn screen_audio(
+        &mut self,
+        fingerprint: &str,
+        diagnostics: &AudioCaptureDiagnostics,
+        check_duplicate: bool,
+    ) -> Option<SkipReason> {
+        let stats = diagnostics.stats;
+        if let Some(reason) = quiet_audio_skip_reason(&self.config, diagnostics) {
+            let why = match reason {
+                SkipReason::EmptyRecording => "no speech was detected by VAD",
+                _ => "recording is quiet",
+            };
+            tracing::info!(
+                reason = ?reason,
+                duration_secs = stats.duration_secs,
+                rms_dbfs = amp_to_dbfs(stats.rms),
+                peak_dbfs = amp_to_dbfs(stats.peak),
+                "Pipeline: Skipping STT because {}",
+                why
+            );
+            return Some(reason);
+        }
+
+        // Identical audio submitted again right away is an accidental double submission
+        // (e.g. a double hotkey press); don't transcribe and output it twice.
+        if check_duplicate
+            && self
+                .recent_submissions
+                .check_and_record(fingerprint, std::time::Instant::now())
+        {
+            log::warn!("Pipeline: Skipping duplicate submission of audio {}", fingerprint);
+            if let Some(store) = &self.config.request_log_store {
+                store.with_current(|log| {
+                    log.warn(format!("Duplicate submission of audio {} skipped", fingerprint));
+                });
+            }
+            return Some(SkipReason::Duplicate);
+        }
+
+        None
+    }
+
+    /// Keep `wav` under the current request id, for callers that look it up after a newer
+    /// recording has replaced `last_wav_bytes`.
+    fn remember_request_audio(&mut self, wav: &Bytes) {
+        let Some(id) = self
+            .config
+            .request_log_store
+            .as_ref()
+            .and_then(|store| store.with_current(|log| log.id.clone()))
+        else {
+            return;
+        };
+        self.request_audio.retain(|(existing, _)| *existing != id);
+        self.request_audio.push_back((id, wav.clone()));
+        while self.request_audio.len() > REQUEST_AUDIO_KEPT {
+            self.request_audio.pop_front();
+        }
+    }
+
+    fn get_or_create_llm_provider(
+        &mut self,
+        provider_id: &str,
+        model: Option<String>,
+        timeout: Duration,
+        ollama_url: Option<String>,
+    ) -> Result<Arc<dyn LlmProvider>, PipelineError> {
+        let model_key = model.clone().unwrap_or_else(|| "<default>".to_string());
+        let url_key = ollama_url
+            .clone()
+            .unwrap_or_else(|| "<default-url>".to_string());
+        let cache_key = format!(
+            "{}::{}::{}::{}",
+            provider_id,
+            model_key,
+            timeout.as_secs_f64(),
+            url_key
+        );
+
+        // Registered providers (the global one, or one injected by tests) are used as they
+        // are, unless another model is asked for.
+        if let Some(p) = self
+            .llm_registry
+            .get(provider_id)
+            .filter(|p| model.as_deref().is_none_or(|m| p.model() == m))
+        {
+            return Ok(p);
+        }
+
+        if let Some(p) = self.llm_provider_cache.get(&cache_key) {
+            return Ok(p.clone());
+        }
+
+        if provider_id == MOCK_PROVIDER_ID {
+            let script = self.mock_providers()?.llm.clone();
+            let provider: Arc<dyn LlmProvider> = Arc::new(MockLlmProvider::new(script));
+            self.llm_provider_cache.insert(cache_key, provider.clone());
+            return Ok(provider);
+        }
+
+        if let Some(plugin) =
+            crate::plugins::find(&self.config.provider_plugins, PluginKind::Llm, provider_id)
+        {
+            let provider: Arc<dyn LlmProvider> =
+                Arc::new(crate::llm::PluginLlmProvider::new(plugin.clone(), model));
+            self.llm_provider_cache.insert(cache_key, provider.clone());
+            return Ok(provider);
+        }
+
+        let api_key = if provider_id == "ollama" {
+            String::new()
+        } else {
+            self.config
+                .llm_api_keys
+                .get(provider_id)
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        if provider_id != "ollama" && api_key.is_empty() {
+            return Err(PipelineError::Config(format!(
+                "LLM provider '{}' requires an API key",
+                provider_id
+            )));
+        }
+
+        // Preserve global LLM config (including provider-specific knobs) but override the
+        // effective provider/model/timeout for this transcription.
+        let mut cfg = self.config.llm_config.clone();
+        cfg.enabled = true;
+        cfg.provider = provider_id.to_string();
+        cfg.api_key = api_key;
+        cfg.model = model;
+        cfg.ollama_url = ollama_url;
+        cfg.timeout = timeout;
+
+        let mut provider = create_llm_provider(&cfg, self.config.request_log_store.clone());
+        if provider_id != "ollama" {
+            provider = crate::rate_limit::RateLimitedLlmProvider::wrap(provider);
+        }
+        self.llm_provider_cache.insert(cache_key, provider.clone());
+        Ok(provider)
+    }
+
+    /// Drop the cached providers that `config` would build differently, so a config update
+    /// keeps the others (their warm connections, a loaded Whisper model).
+    fn evict_stale_providers(&mut self, config: &PipelineConfig) {
+        let changes = ProviderChanges::between(&self.config, config);
+        if changes == ProviderChanges::default() {
+            return;
+        }
+        log::debug!("Pipeline: Rebuilding providers: {:?}", changes);
+        self.stt_provider_generation += 1;
+
+        let plugin_ids: HashSet<&str> = self
+            .config
+            .provider_plugins
+            .iter()
+            .chain(&config.provider_plugins)
+            .map(|p| p.id.as_str())
+            .collect();
+        let provider_id = |key: &str| key.split("::").next().unwrap_or_default().to_string();
+        let is_stale = |id: &str, builtin_changed: bool| {
+            if id == MOCK_PROVIDER_ID {
+                changes.mock
+            } else {
+                (changes.plugins && plugin_ids.contains(id)) || builtin_changed
+            }
+        };
+
+        self.stt_provider_cache.retain(|key, _| {
+            let id = provider_id(key);
+            let builtin_changed = match id.as_str() {
+                "local-whisper" => changes.local_whisper,
+                "local-vosk" => changes.local_vosk,
+                _ => changes.remote_stt,
+            };
+            !is_stale(&id, builtin_changed)
+        });
+        self.llm_provider_cache
+            .retain(|key, _| !is_stale(&provider_id(key), changes.llm));
+    }
+
+    /// (Re)build the registries of the global STT and LLM providers, reusing cached ones.
+    fn initialize_providers(&mut self, config: &PipelineConfig) {
+        // Initialize STT providers
+        self.stt_registry = SttRegistry::new();
+        let canonical = canonicalize_stt_provider_id(&config.stt_provider);
+        match self.get_or_create_stt_provider(&canonical, config.stt_model.clone()) {
+            Ok(provider) => {
+                self.stt_registry.register(&canonical, provider);
+                let _ = self.stt_registry.set_current(&canonical);
+            }
+            Err(e) => {
+                log::warn!(
+                    "Pipeline: Default STT provider '{}' not initialized: {}",
+                    canonical,
+                    e
+                );
+            }
+        }
+
+        // Initialize the global LLM provider. Profiles that pick another provider or model
+        // get theirs on demand.
+        self.llm_registry = LlmRegistry::new();
+        let llm_config = &config.llm_config;
+        if llm_config.enabled {
+            match self.get_or_create_llm_provider(
+                &llm_config.provider,
+                llm_config.model.clone(),
+                llm_config.timeout,
+                llm_config.ollama_url.clone(),
+            ) {
+                Ok(provider) => {
+                    self.llm_registry.register(&llm_config.provider, provider);
+                    let _ = self.llm_registry.set_current(&llm_config.provider);
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Pipeline: Default LLM provider '{}' not initialized: {}",
+                        llm_config.provider,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// STT provider configured for `profile`, or the global one.
+    fn stt_provider_for(
+        &mut self,
+        profile: Option<&crate::llm::ProgramPromptProfile>,
+    ) -> Result<Arc<dyn SttProvider>, PipelineError> {
+        let (provider_id, model) = self.stt_provider_choice(profile);
+        self.get_or_create_stt_provider(&provider_id, model)
+    }
+
+    /// Id and model of the STT provider configured for `profile`, or the global one.
+    fn stt_provider_choice(
+        &self,
+        profile: Option<&crate::llm::ProgramPromptProfile>,
+    ) -> (String, Option<String>) {
+        let provider_id = canonicalize_stt_provider_id(
+            profile
+                .and_then(|p| p.stt_provider.as_deref())
+                .unwrap_or(self.config.stt_provider.as_str()),
+        );
+        let model = profile
+            .and_then(|p| p.stt_model.clone())
+            .or_else(|| self.config.stt_model.clone());
+        (provider_id, model)
+    }
+
+    /// Providers the next transcription will most likely use: the foreground app's profile
+    /// or the global settings. Nothing is warmed up while offline.
+    fn warm_up_targets(&mut self) -> WarmUpTargets {
+        if self.offline {
+            return (None, None);
+        }
+        self.configured_providers()
+    }
+
+    /// Providers the settings select for the next transcription, online or not (see
+    /// `warm_up_targets`).
+    fn configured_providers(&mut self) -> WarmUpTargets {
+        let llm_config = self.config.llm_config.clone();
+        let profile = self.active_profile();
+        let stt = self.stt_provider_for(profile.as_ref()).ok();
+
+        let llm_enabled = profile
+            .as_ref()
+            .and_then(|p| p.rewrite_llm_enabled)
+            .unwrap_or(llm_config.enabled);
+        let llm = if llm_enabled {
+            let provider_id = profile
+                .as_ref()
+                .and_then(|p| p.llm_provider.clone())
+                .unwrap_or_else(|| llm_config.provider.clone());
+            let model = profile
+                .as_ref()
+                .and_then(|p| p.llm_model.clone())
+                .or_else(|| llm_config.model.clone());
+            self.get_or_create_llm_provider(
+                &provider_id,
+                model,
+                llm_config.timeout,
+                llm_config.ollama_url.clone(),
+            )
+            .ok()
+        } else {
+            None
+        };
+
+        (stt, llm)
+    }
+
+    /// Queue a background transcription; returns its id and cancellation token.
+    fn begin_job(&mut self) -> (u64, CancellationToken) {
+        let id = self.next_job_id;
+        self.next_job_id += 1;
+        self.job_progress += 1;
+        let cancel_token = CancellationToken::new();
+        let request_id = self
+            .config
+            .request_log_store
+            .as_ref()
+            .and_then(|store| store.with_current(|log| log.id.clone()));
+        self.jobs.insert(
+            id,
+            TranscriptionJob {
+                info: TranscriptionJobInfo {
+                    id,
+                    status: JobStatus::Queued,
+                    request_id,
+                    queued_at: Utc::now(),
+                    error: None,
+                },
+                stage: PipelineState::Transcribing,
+                cancel_token: cancel_token.clone(),
+                recovery_id: None,
+            },
+        );
+        (id, cancel_token)
+    }
+
+    /// Mark job `id` running if every earlier job is finished. `None` if the job is gone
+    /// (cancelled).
+    fn start_job_if_turn(&mut self, id: u64) -> Option<bool> {
+        let oldest = *self.jobs.keys().next()?;
+        let job = self.jobs.get_mut(&id)?;
+        if oldest != id {
+            return Some(false);
+        }
+        job.info.status = JobStatus::Running;
+        Some(true)
+    }
+
+    /// Stop tracking job `id` and keep it in the finished list.
+    fn retire_job(&mut self, id: u64, error: Option<String>) -> Option<CancellationToken> {
+        let job = self.jobs.remove(&id)?;
+        self.job_progress += 1;
+        if let Some(recovery_id) = &job.recovery_id {
+            crate::recovery::global().finish(recovery_id);
+        }
+        let mut info = job.info;
+        info.status = if error.is_some() { JobStatus::Failed } else { JobStatus::Done };
+        info.error = error;
+        self.finished_jobs.push_back(info);
+        while self.finished_jobs.len() > FINISHED_JOBS_KEPT {
+            self.finished_jobs.pop_front();
+        }
+        self.job_turns.notify_waiters();
+        Some(job.cancel_token)
+    }
+
+    fn set_job_stage(&mut self, id: u64, stage: PipelineState) {
+        if let Some(job) = self.jobs.get_mut(&id) {
+            job.stage = stage;
+            self.job_progress += 1;
+        }
+    }
+
+    /// Finish job `id` with `result`. Errors other than cancellation are reported as the
+    /// pipeline error, unless a newer recording is running (whose state is left alone).
+    fn finish_job(&mut self, id: u64, result: Result<(), &PipelineError>) {
+        let error = result.as_ref().err().map(|e| e.to_string());
+        self.retire_job(id, error);
+        match result {
+            Err(PipelineError::Cancelled) => {
+                if self.state != PipelineState::Recording {
+                    self.reset_to_idle();
+                }
+            }
+            Err(e) if self.state == PipelineState::Recording => {
+                log::error!("Pipeline error (background transcription): {}", e);
+            }
+            Err(e) => self.set_error(&e.to_string()),
+            Ok(()) if self.state == PipelineState::Recording => {}
+            Ok(()) => self.reset_to_idle(),
+        }
+    }
+
+    /// Cancel job `id` if it is queued or running.
+    fn cancel_job(&mut self, id: u64) -> bool {
+        let token = self.retire_job(id, Some(PipelineError::Cancelled.to_string()));
+        token.map(|token| token.cancel()).is_some()
+    }
+
+    /// Cancel all queued and running transcriptions.
+    fn cancel_jobs(&mut self) {
+        let ids: Vec<u64> = self.jobs.keys().copied().collect();
+        for id in ids {
+            self.cancel_job(id);
+        }
+    }
+
+    /// State as seen from outside: recording wins, then the running (oldest) background
+    /// transcription, then the recorder's idle/error state.
+    fn effective_state(&self) -> PipelineState {
+        if self.state == PipelineState::Recording {
+            return PipelineState::Recording;
+        }
+        self.jobs
+            .values()
+            .next()
+            .map_or(self.state, |job| job.stage)
+    }
+
+    /// Reset to idle state, clearing any error condition
+    fn reset_to_idle(&mut self) {
+        self.state = PipelineState::Idle;
+        self.cancel_token = None;
+        self.sync_preroll_standby();
+    }
+
+    /// Transition to error state
+    fn set_error(&mut self, msg: &str) {
+        log::error!("Pipeline error: {}", msg);
+        self.state = PipelineState::Error;
+        self.cancel_token = None;
+        self.sync_preroll_standby();
+    }
+
+    /// Settle the state after a stopped recording was handled or dropped, leaving a
+    /// recording started in the meantime alone (as `finish_job` does).
+    fn settle_stopped(&mut self, error: Option<&str>) {
+        match error {
+            _ if self.state == PipelineState::Recording => {
+                if let Some(msg) = error {
+                    log::error!("Pipeline error (stopped recording): {}", msg);
+                }
+            }
+            Some(msg) => self.set_error(msg),
+            None => self.reset_to_idle(),
+        }
+    }
+
+    /// Preprocessing applied when encoding a recording.
+    fn audio_encode_config(&self) -> AudioEncodeConfig {
+        AudioEncodeConfig {
+            noise_gate_threshold_dbfs: self.config.noise_gate_threshold_dbfs,
+            downmix_to_mono: self.config.audio_downmix_to_mono,
+            resample_to_16khz: self.config.audio_resample_to_16khz,
+            highpass_enabled: self.config.audio_highpass_enabled,
+            agc_enabled: self.config.audio_agc_enabled,
+            noise_suppression_enabled: self.config.audio_noise_suppression_enabled,
+            detect_speech_presence: self.config.quiet_audio_require_speech,
+            trim_silence: self.config.audio_trim_silence,
+            max_internal_pause_secs: self.config.audio_max_pause_secs,
+        }
+    }
+
+    /// Open or close the pre-roll standby stream to match the current config.
+    ///
+    /// Standby is never started while recording; a running recording is left alone.
+    fn sync_preroll_standby(&mut self) {
+        let preroll_secs = self.config.preroll_secs;
+        if !(preroll_secs.is_finite() && preroll_secs > 0.0) {
+            self.audio_capture.stop_standby();
+            return;
+        }
+        if self.state == PipelineState::Recording {
+            return;
+        }
+
+        let input_device_name = self.config.input_device_name.clone();
+        if let Err(e) = self
+            .audio_capture
+            .start_standby(preroll_secs, input_device_name.as_deref())
+        {
+            log::warn!("Failed to start pre-roll standby capture: {}", e);
+        }
+    }
+}
+
+/// Personal dictionary terms of `config` to pass to STT providers as recognition hints.
+fn stt_vocabulary(config: &PipelineConfig) -> Vec<String> {
+    let prompts = &config.llm_config.prompts;
+    if !config.stt_vocabulary_boost || !prompts.dictionary_enabled {
+        return Vec::new();
+    }
+    crate::stt::dictionary_terms(prompts.dictionary_prompt())
+}
+
+/// `stt_vocabulary` with the spoken forms of the dictionary's mappings.
+fn stt_vocabulary_entries(config: &PipelineConfig) -> Vec<crate::stt::VocabularyEntry> {
+    let prompts = &config.llm_config.prompts;
+    if !config.stt_vocabulary_boost || !prompts.dictionary_enabled {
+        return Vec::new();
+    }
+    crate::stt::dictionary_entries(prompts.dictionary_prompt())
+}
+
+/// Which kinds of cached providers a config update invalidates.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ProviderChanges {
+    /// API-backed STT providers: keys, transcription prompt, language, vocabulary.
+    remote_stt: bool,
+    /// Local Whisper: model, decoding and threading.
+    local_whisper: bool,
+    /// Local Vosk: model.
+    local_vosk: bool,
+    /// LLM providers: keys, endpoint, timeout and provider-specific knobs (not prompts,
+    /// which are passed per request).
+    llm: bool,
+    mock: bool,
+    plugins: bool,
+}
+
+impl ProviderChanges {
+    fn between(old: &PipelineConfig, new: &PipelineConfig) -> Self {
+        let (old_llm, new_llm) = (&old.llm_config, &new.llm_config);
+        Self {
+            remote_stt: old.stt_api_keys != new.stt_api_keys
+                || old.stt_transcription_prompt != new.stt_transcription_prompt
+                || old.stt_language != new.stt_language
+                || old.whisper_server_url != new.whisper_server_url
+                || old.stt_extra_params != new.stt_extra_params
+                || old.stt_temperature != new.stt_temperature
+                || old.stt_verbose_json != new.stt_verbose_json
+                || stt_vocabulary_entries(old) != stt_vocabulary_entries(new),
+            #[cfg(feature = "local-whisper")]
+            local_whisper: old.whisper_model_path != new.whisper_model_path
+                || old.whisper_decoding != new.whisper_decoding
+                || old.whisper_threading != new.whisper_threading,
+            #[cfg(not(feature = "local-whisper"))]
+            local_whisper: false,
+            #[cfg(feature = "local-vosk")]
+            local_vosk: old.vosk_model_path != new.vosk_model_path,
+            #[cfg(not(feature = "local-vosk"))]
+            local_vosk: false,
+            llm: old.llm_api_keys != new.llm_api_keys
+                || old_llm.provider != new_llm.provider
+                || old_llm.api_key != new_llm.api_key
+                || old_llm.model != new_llm.model
+                || old_llm.ollama_url != new_llm.ollama_url
+                || old_llm.timeout != new_llm.timeout
+                || old_llm.openai_reasoning_effort != new_llm.openai_reasoning_effort
+                || old_llm.gemini_thinking_budget != new_llm.gemini_thinking_budget
+                || old_llm.gemini_thinking_level != new_llm.gemini_thinking_level
+                || old_llm.anthropic_thinking_budget != new_llm.anthropic_thinking_budget
+                || old_llm.generation != new_llm.generation,
+            mock: old.mock_providers != new.mock_providers,
+            plugins: old.provider_plugins != new.provider_plugins,
+        }
+    }
+}
+
+/// Create an LLM provider based on configuration
+fn create_llm_provider(
+    config: &LlmConfig,
+    request_log_store: Option<RequestLogStore>,
+) -> Arc<dyn LlmProvider> {
+    match config.provider.as_str() {
+        "anthropic" => {
+            let provider = if let Some(model) = &config.model {
+                AnthropicLlmProvider::with_model(config.api_key.clone(), model.clone())
+            } else {
+                AnthropicLlmProvider::new(config.api_key.clone())
+            };
+            Arc::new(
+                provider
+                    .with_timeout(config.timeout)
+                    .with_generation_params(config.generation)
+                    .with_request_log_store(request_log_store.clone())
+                    .with_thinking_budget(config.anthropic_thinking_budget),
+            )
+        }
+        "groq" => {
+            let provider = if let Some(model) = &config.model {
+                GroqLlmProvider::with_model(config.api_key.clone(), model.clone())
+            } else {
+                GroqLlmProvider::new(config.api_key.clone())
+            };
+            Arc::new(
+                provider
+                    .with_timeout(config.timeout)
+                    .with_generation_params(config.generation)
+                    .with_request_log_store(request_log_store.clone()),
+            )
+        }
+        "gemini" => {
+            let provider = if let Some(model) = &config.model {
+                GeminiLlmProvider::with_model(config.api_key.clone(), model.clone())
+            } else {
+                GeminiLlmProvider::new(config.api_key.clone())
+            };
+
+            Arc::new(
+                provider
+                    .with_timeout(config.timeout)
+                    .with_generation_params(config.generation)
+                    .with_request_log_store(request_log_store.clone())
+                    .with_thinking_budget(config.gemini_thinking_budget)
+                    .with_thinking_level(config.gemini_thinking_level.clone()),
+            )
+        }
+        "ollama" => {
+            let provider = OllamaLlmProvider::with_url(
+                config
+                    .ollama_url
+                    .clone()
+                    .unwrap_or_else(|| "http://localhost:11434".to_string()),
+                config.model.clone(),
+            );
+            Arc::new(
+                provider
+                    .with_timeout(config.timeout)
+                    .with_generation_params(config.generation)
+                    .with_request_log_store(request_log_store.clone()),
+            )
+        }
+        _ => {
+            // Default to OpenAI
+            let provider = if let Some(model) = &config.model {
+                OpenAiLlmProvider::with_model(config.api_key.clone(), model.clone())
+            } else {
+                OpenAiLlmProvider::new(config.api_key.clone())
+            };
+            Arc::new(
+                provider
+                    .with_timeout(config.timeout)
+                    .with_generation_params(config.generation)
+                    .with_request_log_store(request_log_store.clone())
+                    .with_reasoning_effort(config.openai_reasoning_effort.clone()),
+            )
+        }
+    }
+}
+
+/// What the pipeline needs from the program embedding it.
+pub trait PipelineHost: Send + Sync {
+    /// Runtime for background tasks (provider warm-up, endpointing, the capture watcher).
+    fn runtime(&self) -> tokio::runtime::Handle;
+
+    /// Executable path of the foreground app, to pick its prompt profile and to tell the
+    /// output hook where the text goes.
+    fn foreground_app_path(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Host for embedders without a runtime of their own: uses the current Tokio runtime, or
+/// a shared one started on first use, and knows no foreground app.
+pub struct DefaultPipelineHost;
+
+impl PipelineHost for DefaultPipelineHost {
+    fn runtime(&self) -> tokio::runtime::Handle {
+        static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();
+        tokio::runtime::Handle::try_current().unwrap_or_else(|_| {
+            RUNTIME
+                .get_or_init(|| {
+                    tokio::runtime::Builder::new_multi_thread()
+                        .enable_all()
+                        .build()
+                        .expect("failed to start the pipeline runtime")
+                })
+                .handle()
+                .clone()
+        })
+    }
+}
+
+/// Thread-safe wrapper for the recording pipeline
+///
+/// `PipelineInner` sits behind a standard Mutex, which makes this Send + Sync for Tauri
+/// state management. The lock is only held to read or update state, never across an
+/// `.await` (the guard isn't Send, so async commands wouldn't compile), and is also taken
+/// from synchronous shortcut handlers, which an async mutex couldn't serve. For the same
+/// reason `PipelineInner` is not owned by an actor task: synchronous callers such as
+/// `stop_recording` would have to block on its replies. Slow work (encoding a recording,
+/// building an STT provider, writing recovery audio) runs between two short critical
+/// sections instead.
+/// Provides robust error handling and cancellation support.
+pub struct SharedPipeline {
+    inner: Arc<Mutex<PipelineInner>>,
+    level_meter: crate::audio_capture::SharedAudioLevelMeter,
+    waveform_meter: crate::audio_capture::SharedAudioWaveformMeter,
+    host: Arc<dyn PipelineHost>,
+}
+
+impl SharedPipeline {
+    /// Create a new shared pipeline with `DefaultPipelineHost`
+    pub fn new(config: PipelineConfig) -> Self {
+        Self::with_host(config, Arc::new(DefaultPipelineHost))
+    }
+
+    /// Create a new shared pipeline that runs its background tasks on `host`
+    pub fn with_host(config: PipelineConfig, host: Arc<dyn PipelineHost>) -> Self {
+        let inner = PipelineInner::new(config, host.clone());
+        let level_meter = inner.audio_capture.shared_level_meter();
+        let waveform_meter = inner.audio_capture.shared_waveform_meter();
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            level_meter,
+            waveform_meter,
+            host,
+        }
+    }
+
+    /// Try to read the current state without blocking.
+    ///
+    /// This is useful for UI publishers that should not stall the runtime when
+    /// the pipeline mutex is briefly held (e.g., during start-up).
+    pub fn try_state(&self) -> Option<PipelineState> {
+        self.inner.try_lock().ok().map(|inner| inner.effective_state())
+    }
+
+    /// Get the most recent realtime audio input level snapshot without locking
+    /// the pipeline mutex.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn audio_level_snapshot_fast(&self) -> AudioLevelSnapshot {
+        self.level_meter.snapshot()
+    }
+
+    /// Get the most recent realtime waveform min/max buckets without locking the
+    /// pipeline mutex.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn audio_waveform_snapshot_fast(&self) -> crate::audio_capture::AudioWaveformSnapshot {
+        self.waveform_meter.snapshot()
+    }
+
+    /// Start recording
+    ///
+    /// Creates a new cancellation token for this recording session.
+    pub fn start_recording(&self) -> Result<(), PipelineError> {
+        self.start_recording_for_profile(None)
+    }
+
+    /// Start recording with the profile `profile_id` (bound to a hotkey) instead of the
+    /// foreground app's: its VAD settings, providers, prompts and output mode apply to
+    /// this recording.
+    pub fn start_recording_for_profile(&self, profile_id: Option<String>) -> Result<(), PipelineError> {
+        let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+        // State guard: only allow starting from Idle or Error states
+        if !inner.state.can_start_recording() {
+            return Err(PipelineError::AlreadyRecording);
+        }
+
+        // Create a new cancellation token for this session
+        let cancel_token = CancellationToken::new();
+        inner.cancel_token = Some(cancel_token);
+        inner.surrounding_text = None;
+        inner.hotkey_profile_id = profile_id;
+        let vad_config = inner
+            .active_profile()
+            .and_then(|p| p.vad_config)
+            .unwrap_or_else(|| inner.config.vad_config.clone());
+        inner.audio_capture.set_vad_config(vad_config);
+
+        let max_duration = inner.config.max_duration_secs;
+        // Clone out of the config to avoid borrowing `inner` immutably while calling into
+        // `audio_capture` mutably.
+        let input_device_name = inner.config.input_device_name.clone();
+        match inner
+            .audio_capture
+            .start_with_device_name(max_duration, input_device_name.as_deref())
+        {
+            Ok(()) => {
+                inner.state = PipelineState::Recording;
+                tracing::info!(
+                    profile = inner.hotkey_profile_id.as_deref(),
+                    device = input_device_name.as_deref(),
+                    "Pipeline: Recording started"
+                );
+                self.spawn_provider_warm_up();
+                self.start_provider_endpointing(&mut inner);
+                Ok(())
+            }
+            Err(e) => {
+                inner.set_error(&format!("Failed to start recording: {}", e));
+                Err(PipelineError::AudioCapture(e))
+            }
+        }
+    }
+
+    /// Warm up the STT and LLM providers in the background while the user is speaking, so
+    /// connection setup (or loading a local model) is done by the time the recording stops.
+    /// A provider that isn't cached yet is built first, without the lock.
+    fn spawn_provider_warm_up(&self) {
+        let inner = self.inner.clone();
+        self.host.runtime().spawn(async move {
+            prepare_stt_providers(&inner, |inner| {
+                if inner.offline {
+                    return Vec::new();
+                }
+                vec![inner.stt_provider_choice(inner.active_profile().as_ref())]
+            })
+            .await;
+            let (stt, llm) = match inner.lock() {
+                Ok(mut inner) => inner.warm_up_targets(),
+                Err(_) => return,
+            };
+
+            let stt_warm_up = async {
+                if let Some(provider) = &stt {
+                    if let Err(e) = provider.warm_up().await {
+                        log::debug!("Pipeline: STT warm-up for '{}' failed: {}", provider.name(), e);
+                    }
+                }
+            };
+            let llm_warm_up = async {
+                if let Some(provider) = &llm {
+                    if let Err(e) = provider.warm_up().await {
+                        log::debug!("Pipeline: LLM warm-up for '{}' failed: {}", provider.name(), e);
+                    }
+                }
+            };
+            tokio::join!(stt_warm_up, llm_warm_up);
+        });
+    }
+
+    /// With auto-stop on `Endpointing::Provider` and Deepgram as the STT provider, stream the
+    /// recording to Deepgram so its endpointing ends it. Other providers have no streaming
+    /// API here and keep the local VAD.
+    fn start_provider_endpointing(&self, inner: &mut PipelineInner) {
+        inner.provider_endpointing = None;
+        let vad_config = inner.audio_capture.vad_config().clone();
+        if !inner.audio_capture.is_vad_auto_stop_enabled()
+            || vad_config.endpointing != Endpointing::Provider
+            || inner.offline
+        {
+            return;
+        }
+
+        let profile = inner.active_profile();
+        let provider_id = canonicalize_stt_provider_id(
+            profile
+                .as_ref()
+                .and_then(|p| p.stt_provider.as_deref())
+                .unwrap_or(inner.config.stt_provider.as_str()),
+        );
+        if provider_id != "deepgram" {
+            log::debug!(
+                "Pipeline: '{}' has no streaming endpointing, using local VAD",
+                provider_id
+            );
+            return;
+        }
+        let api_key = inner
+            .config
+            .stt_api_keys
+            .get(&provider_id)
+            .cloned()
+            .unwrap_or_default();
+        if api_key.is_empty() {
+            return;
+        }
+
+        let config = crate::stt::LiveStreamConfig {
+            api_key,
+            model: profile
+                .and_then(|p| p.stt_model)
+                .or_else(|| inner.config.stt_model.clone()),
+            sample_rate: inner.audio_capture.sample_rate(),
+            channels: inner.audio_capture.channels(),
+            endpointing_ms: vad_config.vad_config.hangover_frames
+                * vad_config.vad_config.frame_duration_ms,
+        };
+        let endpointing = crate::stt::DeepgramEndpointing::new();
+        inner.provider_endpointing = Some(endpointing.clone());
+
+        let pipeline = self.clone();
+        self.host.runtime().spawn(async move {
+            endpointing
+                .run(config, |cursor| pipeline.recording_samples_since(cursor))
+                .await;
+        });
+    }
+
+    /// Samples of the current recording captured after `cursor` (see
+    /// `AudioCapture::samples_since`); `None` once it is no longer recording.
+    fn recording_samples_since(&self, cursor: u64) -> Option<(Vec<f32>, u64)> {
+        let inner = self.inner.lock().ok()?;
+        if inner.state != PipelineState::Recording {
+            return None;
+        }
+        inner.audio_capture.samples_since(cursor)
+    }
+
+    /// The provider's endpointing stream, while it is open.
+    fn active_provider_endpointing(&self) -> Option<Arc<crate::stt::DeepgramEndpointing>> {
+        let inner = self.inner.lock().ok()?;
+        inner
+            .provider_endpointing
+            .clone()
+            .filter(|endpointing| endpointing.is_active())
+    }
+
+    /// Start a recording fed by `push_audio_frames` instead of the OS microphone.
+    ///
+    /// Stopping and transcribing work exactly as for a microphone recording.
+    pub fn start_external_recording(&self, sample_rate: u32, channels: u16) -> Result<(), PipelineError> {
+        let source = ExternalSource::new(sample_rate, channels).map_err(PipelineError::AudioCapture)?;
+        self.start_source_recording(Box::new(source))
+    }
+
+    /// Start a recording from any `AudioSource` other than the configured microphone (a
+    /// replayed WAV file, pushed frames, ...).
+    pub fn start_source_recording(&self, source: Box<dyn AudioSource>) -> Result<(), PipelineError> {
+        let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+        if !inner.state.can_start_recording() {
+            return Err(PipelineError::AlreadyRecording);
+        }
+
+        inner.cancel_token = Some(CancellationToken::new());
+        inner.hotkey_profile_id = None;
+        let vad_config = inner.config.vad_config.clone();
+        inner.audio_capture.set_vad_config(vad_config);
+
+        let max_duration = inner.config.max_duration_secs;
+        let key = source.key();
+        match inner.audio_capture.start_source(source, max_duration) {
+            Ok(()) => {
+                inner.state = PipelineState::Recording;
+                tracing::info!(source = %key, "Pipeline: Recording started");
+                self.spawn_provider_warm_up();
+                Ok(())
+            }
+            Err(e) => {
+                inner.set_error(&format!("Failed to start recording from {}: {}", key, e));
+                Err(PipelineError::AudioCapture(e))
+            }
+        }
+    }
+
+    /// Feed interleaved audio into an external recording (see `start_external_recording`).
+    pub fn push_audio_frames(&self, samples: &[f32], sample_rate: u32) -> Result<(), PipelineError> {
+        let inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+        if inner.state != PipelineState::Recording {
+            return Err(PipelineError::NotRecording);
+        }
+
+        inner
+            .audio_capture
+            .push_frames(samples, sample_rate)
+            .map_err(PipelineError::AudioCapture)
+    }
+
+    /// Stop the recording and take its audio, with the encoding settings and the session's
+    /// cancellation token (holds the lock briefly). The state stays `Transcribing` until the
+    /// caller settles it (see `PipelineInner::settle_stopped`), after encoding the audio
+    /// without the lock.
+    fn take_recorded_audio(
+        &self,
+    ) -> Result<(CapturedAudio, AudioEncodeConfig, Option<CancellationToken>), PipelineError> {
+        let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+        if !inner.state.can_stop_recording() {
+            return Err(PipelineError::NotRecording);
+        }
+
+        let encode_cfg = inner.audio_encode_config();
+        let audio = match inner.audio_capture.stop_and_take_audio() {
+            Ok(audio) => audio,
+            Err(e) => {
+                inner.set_error(&format!("Failed to stop recording: {}", e));
+                return Err(PipelineError::AudioCapture(e));
+            }
+        };
+        inner.state = PipelineState::Transcribing;
+        Ok((audio, encode_cfg, inner.cancel_token.clone()))
+    }
+
+    /// Stop recording and return the raw WAV audio
+    pub fn stop_recording(&self) -> Result<Bytes, PipelineError> {
+        let (audio, cfg, _) = self.take_recorded_audio()?;
+        let encoded = audio.encode(cfg);
+
+        let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+        match encoded {
+            Ok((wav_bytes, diagnostics)) => {
+                let wav_bytes = Bytes::from(wav_bytes);
+                // Keep a copy for STT testing/debugging UI.
+                inner.last_wav_bytes = Some(wav_bytes.clone());
+                inner.last_recording_diagnostics = Some(diagnostics);
+
+                // Check size limit
+                let max_bytes = inner.config.max_recording_bytes;
+                if max_bytes > 0 && wav_bytes.len() > max_bytes {
+                    inner.settle_stopped(Some(&format!(
+                        "Recording too large: {} bytes",
+                        wav_bytes.len()
+                    )));
+                    return Err(PipelineError::RecordingTooLarge(wav_bytes.len(), max_bytes));
+                }
+
+                inner.settle_stopped(None);
+                log::info!(
+                    "Pipeline: Recording stopped, {} bytes captured",
+                    wav_bytes.len()
+                );
+                Ok(wav_bytes)
+            }
+            Err(e) => {
+                inner.settle_stopped(Some(&format!("Failed to stop recording: {}", e)));
+                Err(PipelineError::AudioCapture(e))
+            }
+        }
+    }
+
+    /// Stop recording and return a before/after pair of WAV bytes.
+    ///
+    /// - before: raw capture with no preprocessing/gates
+    /// - after: capture encoded with the current audio settings
+    ///
+    /// Intended for settings UI A/B testing.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn stop_recording_before_after(&self) -> Result<(Bytes, Bytes), PipelineError> {
+        let (audio, after_cfg, _) = self.take_recorded_audio()?;
+        let encoded = audio.encode_before_after(after_cfg);
+
+        let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+        match encoded {
+            Ok((before_wav, after_wav, diagnostics)) => {
+                let (before_wav, after_wav) = (Bytes::from(before_wav), Bytes::from(after_wav));
+                // Keep a copy of the processed output for STT test + debugging.
+                inner.last_wav_bytes = Some(after_wav.clone());
+                inner.last_recording_diagnostics = Some(diagnostics);
+
+                // Check size limit (both, to avoid surprising huge payloads)
+                let max_bytes = inner.config.max_recording_bytes;
+                if max_bytes > 0 {
+                    if before_wav.len() > max_bytes {
+                        inner.settle_stopped(Some(&format!(
+                            "Recording too large: {} bytes",
+                            before_wav.len()
+                        )));
+                        return Err(PipelineError::RecordingTooLarge(before_wav.len(), max_bytes));
+                    }
+                    if after_wav.len() > max_bytes {
+                        inner.settle_stopped(Some(&format!(
+                            "Recording too large: {} bytes",
+                            after_wav.len()
+                        )));
+                        return Err(PipelineError::RecordingTooLarge(after_wav.len(), max_bytes));
+                    }
+                }
+
+                inner.settle_stopped(None);
+                Ok((before_wav, after_wav))
+            }
+            Err(e) => {
+                inner.settle_stopped(Some(&format!("Failed to stop recording: {}", e)));
+                Err(PipelineError::AudioCapture(e))
+            }
+        }
+    }
+
+    /// Transcribe a short generated sample with `provider_id` to check that its key and
+    /// model work, returning the transcript.
+    ///
+    /// `api_key` overrides the configured key so settings can be validated before saving.
+    /// Nothing is written to the request log and no pipeline state changes: API-backed
+    /// providers are built fresh without a request log store rather than taken from the
+    /// provider cache.
+    pub async fn test_stt_provider(
+        &self,
+        provider_id: &str,
+        api_key: Option<String>,
+        model: Option<String>,
+    ) -> Result<String, PipelineError> {
+        let provider_id = canonicalize_stt_provider_id(provider_id);
+        let (remote, timeout) = {
+            let inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+            // Local, mock and plugin providers never log requests (and are costly to load).
+            let reuses_cached = is_local_stt_provider(&provider_id)
+                || provider_id == MOCK_PROVIDER_ID
+                || crate::plugins::find(&inner.config.provider_plugins, PluginKind::Stt, &provider_id)
+                    .is_some();
+            let remote = (!reuses_cached).then(|| {
+                let key = api_key
+                    .filter(|k| !k.trim().is_empty())
+                    .or_else(|| inner.config.stt_api_keys.get(&provider_id).cloned())
+                    .unwrap_or_default();
+                (key, inner.config.clone())
+            });
+            (remote, inner.config.transcription_timeout)
+        };
+        let provider = match remote {
+            Some((key, config)) => create_remote_stt_provider(&config, &provider_id, key, model, None)?,
+            None => {
+                let wanted = (provider_id.clone(), model.clone());
+                prepare_stt_providers(&self.inner, |_| vec![wanted]).await;
+                let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+                inner.get_or_create_stt_provider(&provider_id, model)?
+            }
+        };
+
+        let wav_bytes = connectivity_test_wav()?;
+        match tokio::time::timeout(
+            timeout,
+            provider.transcribe(wav_bytes, &AudioFormat::default()),
+        )
+        .await
+        {
+            Ok(result) => Ok(normalize_stt_text(result?)),
+            Err(_) => Err(PipelineError::Timeout(timeout)),
+        }
+    }
+
+    /// Transcribe WAV bytes with the configured STT provider only: no LLM formatting, no
+    /// request log or history entry.
+    pub async fn transcribe_wav_bytes_raw(&self, wav_bytes: Bytes) -> Result<String, PipelineError> {
+        let (provider_id, model) = {
+            let inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+            (inner.config.stt_provider.clone(), inner.config.stt_model.clone())
+        };
+        self.transcribe_wav_bytes_with(&provider_id, model, wav_bytes)
+            .await
+    }
+
+    /// Like `transcribe_wav_bytes_raw`, with an explicit provider and model (`None` for the
+    /// provider's default) using the configured API keys.
+    pub async fn transcribe_wav_bytes_with(
+        &self,
+        provider_id: &str,
+        model: Option<String>,
+        wav_bytes: Bytes,
+    ) -> Result<String, PipelineError> {
+        let provider_id = canonicalize_stt_provider_id(provider_id);
+        let wanted = (provider_id.clone(), model.clone());
+        prepare_stt_providers(&self.inner, |_| vec![wanted]).await;
+        let (provider, timeout) = {
+            let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+            (
+                inner.get_or_create_stt_provider(&provider_id, model)?,
+                inner.config.transcription_timeout,
+            )
+        };
+
+        match tokio::time::timeout(
+            timeout,
+            provider.transcribe(wav_bytes, &AudioFormat::default()),
+        )
+        .await
+        {
+            Ok(result) => Ok(normalize_stt_text(result?)),
+            Err(_) => Err(PipelineError::Timeout(timeout)),
+        }
+    }
+
+    /// Transcribe the last captured audio (WAV bytes) using the current effective STT settings.
+    ///
+    /// This is intended for settings UI testing and debugging.
+    pub async fn transcribe_last_audio_for_profile(
+        &self,
+        profile_id: Option<&str>,
+    ) -> Result<String, PipelineError> {
+        // Resolve per-profile overrides. Note: program prompt profiles live under llm_config.
+        let find_profile = |inner: &PipelineInner| {
+            profile_id
+                .and_then(|id| {
+                    if id == "default" {
+                        None
+                    } else {
+                        Some(id)
+                    }
+                })
+                .and_then(|id| {
+                    inner
+                        .config
+                        .llm_config
+                        .program_prompt_profiles
+                        .iter()
+                        .find(|p| p.id == id)
+                        .cloned()
+                })
+        };
+        prepare_stt_providers(&self.inner, |inner| {
+            inner.stt_providers_wanted(find_profile(inner).as_ref(), false)
+        })
+        .await;
+
+        let (wav_bytes, stt_provider, retry_config, cancel_token) = {
+            let mut inner = self
+                .inner
+                .lock()
+                .map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+            let wav_bytes = inner
+                .last_wav_bytes
+                .clone()
+                .ok_or_else(|| {
+                    PipelineError::Config(
+                        "No audio captured yet. Record once to create test audio.".to_string(),
+                    )
+                })?;
+
+            let config = inner.config.clone();
+            let profile = find_profile(&inner);
+
+            let desired_stt_provider = canonicalize_stt_provider_id(
+                profile
+                    .as_ref()
+                    .and_then(|p| p.stt_provider.as_deref())
+                    .unwrap_or(config.stt_provider.as_str()),
+            );
+            let desired_stt_model = profile
+                .as_ref()
+                .and_then(|p| p.stt_model.clone())
+                .or_else(|| config.stt_model.clone());
+
+            let stt_provider = match inner.get_or_create_stt_provider(
+                &desired_stt_provider,
+                desired_stt_model.clone(),
+            ) {
+                Ok(p) => p,
+                Err(e) => {
+                    // If the profile specified an override provider, fall back to global provider.
+                    let global_provider = canonicalize_stt_provider_id(&config.stt_provider);
+                    if global_provider != desired_stt_provider {
+                        log::warn!(
+                            "Pipeline: Profile STT provider '{}' unavailable ({}), falling back to '{}'",
+                            desired_stt_provider,
+                            e,
+                            global_provider
+                        );
+
+                        let global_model = config.stt_model.clone();
+                        inner
+                            .get_or_create_stt_provider(&global_provider, global_model)
+                            .map_err(|err| {
+                                inner.set_error(&format!(
+                                    "No STT provider configured: {}",
+                                    err
+                                ));
+                                PipelineError::NoProvider
+                            })?
+                    } else {
+                        inner.set_error(&format!("No STT provider configured: {}", e));
+                        return Err(PipelineError::NoProvider);
+                    }
+                }
+            };
+            let stt_provider = inner.offline_stt_provider(stt_provider);
+
+            let cancel_token = inner
+                .cancel_token
+                .clone()
+                .unwrap_or_else(CancellationToken::new);
+
+            (
+                wav_bytes,
+                stt_provider,
+                RetryProfile::Interactive.apply(&config.retry_config),
+                cancel_token,
+            )
+        };
+
+        let transcription_future =
+            transcribe_with_retry(&stt_provider, &wav_bytes, None, &retry_config, &cancel_token);
+
+        // Cancellation protection (test endpoint intentionally does NOT enforce timeout)
+        tokio::select! {
+            biased;
+
+            _ = cancel_token.cancelled() => {
+                Err(PipelineError::Cancelled)
+            }
+
+            result = transcription_future => result.map(|t| normalize_stt_text(t.text)),
+        }
+    }
+
+    /// Re-run STT on `fallback` after the primary provider failed with `primary_error`.
+    ///
+    /// The fallback gets the same retry policy, timeout and cancellation as the primary
+    /// attempt, and the switch is recorded on the current request log.
+    #[allow(clippy::too_many_arguments)]
+    async fn transcribe_with_fallback(
+        &self,
+        fallback: Arc<dyn SttProvider>,
+        primary_name: &str,
+        primary_error: &PipelineError,
+        wav_bytes: Bytes,
+        retry_config: &RetryConfig,
+        timeout: Duration,
+        cancel_token: &CancellationToken,
+    ) -> Result<Transcript, PipelineError> {
+        log::warn!(
+            "Pipeline: STT provider '{}' failed ({}), falling back to '{}'",
+            primary_name,
+            primary_error,
+            fallback.name()
+        );
+        let request_log_store = self
+            .inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.config.request_log_store.clone());
+        if let Some(store) = &request_log_store {
+            store.with_current(|log| {
+                log.warn(format!(
+                    "STT provider '{}' failed ({}); falling back to '{}'",
+                    primary_name,
+                    primary_error,
+                    fallback.name()
+                ));
+                log.stt_fallback_from = Some(log.stt_provider.clone());
+                log.stt_provider = fallback.name().to_string();
+                // The fallback runs with its provider default model.
+                log.stt_model = None;
+            });
+        }
+
+        tokio::select! {
+            biased;
+
+            _ = cancel_token.cancelled() => Err(PipelineError::Cancelled),
+
+            _ = tokio::time::sleep(timeout) => {
+                log::warn!("Pipeline: Fallback transcription timed out after {:?}", timeout);
+                Err(PipelineError::Timeout(timeout))
+            }
+
+            result = transcribe_with_retry(
+                &fallback,
+                &wav_bytes,
+                None,
+                retry_config,
+                cancel_token,
+            ) => result,
+        }
+    }
+
+    /// Apply the configured deterministic transcript rewrites (see `crate::postprocess`).
+    fn postprocess_stt_text(&self, text: String) -> String {
+        let (enabled, text) = match self.inner.lock() {
+            Ok(inner) => (
+                inner.config.identifier_casing_enabled,
+                crate::corrections::apply(&text, &inner.config.learned_corrections),
+            ),
+            Err(_) => (false, text),
+        };
+        if enabled {
+            crate::postprocess::apply_identifier_casing(&text)
+        } else {
+            text
+        }
+    }
+
+    /// Pass the final transcript through the output hook, if one is configured. A failing
+    /// hook leaves the transcript as it is.
+    async fn apply_output_hook(&self, text: String, raw_text: &str) -> String {
+        let (hook, request_log_store) = match self.inner.lock() {
+            Ok(inner) => (
+                inner.config.output_hook.clone(),
+                inner.config.request_log_store.clone(),
+            ),
+            Err(_) => return text,
+        };
+        if !hook.is_enabled() || text.trim().is_empty() {
+            return text;
+        }
+
+        let input = crate::hooks::HookInput {
+            text: &text,
+            raw_text,
+            app: self.host.foreground_app_path().and_then(|path| {
+                std::path::Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            }),
+        };
+        match crate::hooks::run(&hook, &input).await {
+            Ok(hooked) => {
+                log::info!("Pipeline: Output hook {} -> {} chars", text.len(), hooked.len());
+                hooked
+            }
+            Err(e) => {
+                log::warn!("Pipeline: Output hook failed ({}), keeping transcript", e);
+                if let Some(store) = request_log_store {
+                    store.with_current(|log| log.warn(format!("Output hook failed: {}", e)));
+                }
+                text
+            }
+        }
+    }
+
+    /// Steps shared by every transcription after STT returns: cache the response under
+    /// `cache_key`, then drop it if it looks like a hallucination for audio with `stats`.
+    fn finish_stt_text(&self, text: String, cache_key: String, stats: AudioLevelStats) -> String {
+        self.cache_stt_response(cache_key, &text);
+        self.filter_hallucination(text, stats)
+    }
+
+    /// Replace a likely silence hallucination with an empty transcript. The dropped text is
+    /// kept in the request log.
+    fn filter_hallucination(&self, text: String, stats: AudioLevelStats) -> String {
+        let Ok(inner) = self.inner.lock() else {
+            return text;
+        };
+        if !inner.config.hallucination_filter_enabled
+            || !crate::hallucination::is_likely_hallucination(
+                &text,
+                &inner.config.hallucination_filter_phrases,
+                stats.duration_secs,
+                amp_to_dbfs(stats.rms),
+            )
+        {
+            return text;
+        }
+
+        log::info!(
+            "Pipeline: Dropping likely hallucination {:?} (duration {:.2}s, rms {:.1} dBFS)",
+            text,
+            stats.duration_secs,
+            amp_to_dbfs(stats.rms)
+        );
+        if let Some(store) = &inner.config.request_log_store {
+            store.with_current(|log| {
+                log.warn(format!("Dropped likely hallucination: {:?}", text));
+            });
+        }
+        String::new()
+    }
+
+    /// Package a failed/timed-out LLM step for background retry, if enabled.
+    fn defer_formatting(
+        &self,
+        outcome: &LlmOutcome,
+        provider: &Arc<dyn LlmProvider>,
+        prompts: &PromptSections,
+        timeout: Duration,
+        raw_text: &str,
+        redaction: Option<Redaction>,
+    ) -> Option<DeferredFormatting> {
+        if !matches!(outcome, LlmOutcome::TimedOut | LlmOutcome::Failed(_)) || raw_text.trim().is_empty() {
+            return None;
+        }
+        let inner = self.inner.lock().ok()?;
+        inner.config.deferred_llm_formatting.then(|| DeferredFormatting {
+            provider: provider.clone(),
+            prompts: prompts.clone(),
+            timeout,
+            // Nobody is waiting on the result, so use the full backoff.
+            retry_config: RetryProfile::Background.apply(&inner.config.llm_config.retry_config),
+            raw_text: raw_text.to_string(),
+            redaction,
+        })
+    }
+
+    /// Remember a successful STT response for identical audio (best-effort).
+    fn cache_stt_response(&self, key: String, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.stt_response_cache.insert(key, text.to_string());
+        }
+    }
+
+    /// Keep STT responses in the on-disk cache at `path` (next to the saved recordings), so
+    /// re-running a recording after a restart doesn't call the STT provider again. `None`
+    /// keeps them in memory only (nothing may be written to disk).
+    pub fn set_stt_cache_path(&self, path: Option<std::path::PathBuf>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.stt_response_cache.path() == path.as_deref() {
+                return;
+            }
+            inner.stt_response_cache = match path {
+                Some(path) => SttResponseCache::load(path),
+                None => SttResponseCache::default(),
+            };
+        }
+    }
+
+    /// Forget all cached STT responses.
+    pub fn clear_stt_cache(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.stt_response_cache.clear();
+        }
+    }
+
+    /// Stop recording and transcribe the audio, returning a detailed result.
+    ///
+    /// This is the main end-to-end function for voice dictation.
+    /// Includes:
+    /// - Automatic retry with exponential backoff on transient failures
+    /// - Timeout protection
+    /// - Cancellation support
+    /// - Proper error recovery
+    /// - Optional LLM formatting
+    pub async fn stop_and_transcribe_detailed(
+        &self,
+    ) -> Result<TranscriptionResult, PipelineError> {
+        // Phase 1: Stop recording and take the captured samples (holds lock briefly)
+        let (audio, encode_cfg, session_token) = self.take_recorded_audio()?;
+
+        // Encoding (filters, resampling, VAD trimming) takes a while for long recordings, so
+        // it runs on a blocking thread without the lock, as does building any STT provider
+        // that isn't cached yet.
+        let encoding = tokio::task::spawn_blocking(move || audio.encode(encode_cfg));
+        let preparing = prepare_stt_providers(&self.inner, |inner| {
+            inner.stt_providers_wanted(inner.active_profile().as_ref(), true)
+        });
+        let (encoded, ()) = tokio::join!(encoding, preparing);
+        let encoded = encoded.unwrap_or_else(|e| Err(AudioCaptureError::Encoding(e.to_string())));
+
+        // Phase 1b: Prepare for transcription (synchronous, holds lock briefly)
+        let (job_id, wav_bytes, stats, stt_context, stt_cache_key, cached_stt_text, stt_provider, fallback_stt_provider, llm_provider, llm_prompts, existing_text, llm_timeout, llm_retry_config, redactor, retry_config, timeout, cancel_token) = {
+            let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+            // Cancelled while encoding: `cancel` already reset the state.
+            if session_token.is_some_and(|token| token.is_cancelled()) {
+                return Err(PipelineError::Cancelled);
+            }
+
+            let (wav_bytes, diagnostics) = match encoded {
+                Ok((wav_bytes, diagnostics)) => (Bytes::from(wav_bytes), diagnostics),
+                Err(e) => {
+                    inner.settle_stopped(Some(&format!("Failed to encode recording: {}", e)));
+                    return Err(PipelineError::AudioCapture(e));
+                }
+            };
+
+            let stats = diagnostics.stats;
+
+            // Persist diagnostics for UI readout.
+            inner.last_recording_diagnostics = Some(diagnostics);
+
+            // Keep a copy for STT testing/debugging UI.
+            inner.last_wav_bytes = Some(wav_bytes.clone());
+            inner.remember_request_audio(&wav_bytes);
+
+            let fingerprint = fingerprint_wav(&wav_bytes);
+            inner.record_audio_fingerprint(&fingerprint);
+
+            if let Some(reason) = inner.screen_audio(&fingerprint, &diagnostics, true) {
+                inner.settle_stopped(None);
+                return Ok(TranscriptionResult::skipped(reason));
+            }
+
+            // Check size limit
+            let max_bytes = inner.config.max_recording_bytes;
+            if max_bytes > 0 && wav_bytes.len() > max_bytes {
+                inner.settle_stopped(Some(&format!("Recording too large: {} bytes", wav_bytes.len())));
+                return Err(PipelineError::RecordingTooLarge(wav_bytes.len(), max_bytes));
+            }
+
+            // Free the recorder (and re-arm pre-roll) right away: the next recording can
+            // start while this one is transcribed in the background.
+            inner.settle_stopped(None);
+
+            let llm_config = inner.config.llm_config.clone();
+            let active_profile = inner.active_profile();
+            let llm_prompts = active_profile
+                .as_ref()
+                .map(|p| p.prompts.clone())
+                .unwrap_or_else(|| llm_config.prompts.clone());
+
+            // Resolve effective STT settings (profile overrides -> global defaults, with safe fallback)
+            let desired_stt_provider = canonicalize_stt_provider_id(
+                active_profile
+                    .as_ref()
+                    .and_then(|p| p.stt_provider.as_deref())
+                    .unwrap_or(inner.config.stt_provider.as_str()),
+            );
+            let desired_stt_model = active_profile
+                .as_ref()
+                .and_then(|p| p.stt_model.clone())
+                .or_else(|| inner.config.stt_model.clone());
+            let desired_timeout = active_profile
+                .as_ref()
+                .and_then(|p| p.stt_timeout_seconds)
+                .map(|s| seconds_to_duration_or(s, inner.config.transcription_timeout))
+                .unwrap_or(inner.config.transcription_timeout);
+
+            let stt_provider = match inner.get_or_create_stt_provider(&desired_stt_provider, desired_stt_model.clone()) {
+                Ok(p) => p,
+                Err(e) => {
+                    // If the profile specified an override provider, fall back to global provider.
+                    let global_provider = canonicalize_stt_provider_id(&inner.config.stt_provider);
+                    if global_provider != desired_stt_provider {
+                        log::warn!(
+                            "Pipeline: Profile STT provider '{}' unavailable ({}), falling back to '{}'",
+                            desired_stt_provider,
+                            e,
+                            global_provider
+                        );
+                        let global_model = inner.config.stt_model.clone();
+                        inner.get_or_create_stt_provider(&global_provider, global_model)
+                            .map_err(|err| {
+                                inner.settle_stopped(Some(&format!("No STT provider configured: {}", err)));
+                                PipelineError::NoProvider
+                            })?
+                    } else {
+                        inner.settle_stopped(Some(&format!("No STT provider configured: {}", e)));
+                        return Err(PipelineError::NoProvider);
+                    }
+                }
+            };
+
+            // Resolve effective LLM provider/model (profile overrides -> global defaults), gated by
+            // the active profile's enable flag (falls back to the global enable).
+            let llm_timeout = llm_config.timeout;
+            let effective_llm_enabled = active_profile
+                .as_ref()
+                .and_then(|p| p.rewrite_llm_enabled)
+                .unwrap_or(inner.config.llm_config.enabled);
+
+            let llm_provider = if effective_llm_enabled {
+                let desired_llm_provider = active_profile
+                    .as_ref()
+                    .and_then(|p| p.llm_provider.clone())
+                    .unwrap_or_else(|| llm_config.provider.clone());
+                let desired_llm_model = active_profile
+                    .as_ref()
+                    .and_then(|p| p.llm_model.clone())
+                    .or_else(|| llm_config.model.clone());
+
+                match inner.get_or_create_llm_provider(
+                    desired_llm_provider.as_str(),
+                    desired_llm_model.clone(),
+                    llm_timeout,
+                    llm_config.ollama_url.clone(),
+                ) {
+                    Ok(p) => Some(p),
+                    Err(e) => {
+                        // Fallback to global provider if profile requested a different one.
+                        if active_profile
+                            .as_ref()
+                            .and_then(|p| p.llm_provider.as_ref())
+                            .is_some()
+                            && desired_llm_provider != llm_config.provider
+                        {
+                            log::warn!(
+                                "Pipeline: Profile LLM provider '{}' unavailable ({}), falling back to '{}'",
+                                desired_llm_provider,
+                                e,
+                                llm_config.provider
+                            );
+                            inner
+                                .get_or_create_llm_provider(
+                                    llm_config.provider.as_str(),
+                                    llm_config.model.clone(),
+                                    llm_timeout,
+                                    llm_config.ollama_url.clone(),
+                                )
+                                .ok()
+                        } else {
+                            log::warn!(
+                                "Pipeline: LLM disabled for this transcription ({}).",
+                                e
+                            );
+                            None
+                        }
+                    }
+                }
+            } else {
+                None
+            };
+
+            let stt_provider = inner.offline_stt_provider(stt_provider);
+            let llm_provider = inner.offline_llm_provider(llm_provider);
+
+            // Live dictation: the user is waiting to paste, so fail fast.
+            let retry_config = RetryProfile::Interactive.apply(&inner.config.retry_config);
+            let llm_retry_config = RetryProfile::Interactive.apply(&llm_config.retry_config);
+            let redactor = inner.request_redactor(active_profile.as_ref());
+
+            let stt_cache_key =
+                SttResponseCache::key(&fingerprint, &inner.stt_provider_key(&stt_provider));
+            let cached_stt_text = inner.stt_response_cache.get(&stt_cache_key);
+            let fallback_stt_provider = inner.fallback_stt_provider_for(&stt_provider);
+            let stt_context = match inner.config.stt_context_secs {
+                0 => None,
+                secs => inner
+                    .recent_transcripts
+                    .context(Instant::now(), Duration::from_secs(secs as u64)),
+            };
+            // Field content is not redacted, so it isn't sent along when redaction is on.
+            let existing_text = inner.surrounding_text.take().filter(|_| redactor.is_none());
+            let (job_id, cancel_token) = inner.begin_job();
+
+            (
+                job_id,
+                wav_bytes,
+                stats,
+                stt_context,
+                stt_cache_key,
+                cached_stt_text,
+                stt_provider,
+                fallback_stt_provider,
+                llm_provider,
+                llm_prompts,
+                existing_text,
+                llm_timeout,
+                llm_retry_config,
+                redactor,
+                retry_config,
+                desired_timeout,
+                cancel_token,
+            )
+        };
+
+        self.persist_job_audio(job_id, stt_provider.name(), stats.duration_secs, wav_bytes.clone())
+            .await;
+
+        if !self.wait_for_job_turn(job_id, &cancel_token).await? {
+            return Err(PipelineError::Cancelled);
+        }
+
+        log::info!(
+            "Pipeline: Starting transcription ({} bytes, timeout {:?})",
+            wav_bytes.len(),
+            timeout
+        );
+
+        // Phase 2: Transcribe with retry logic (async, outside the lock)
+        // Wrap the transcription in a timeout and cancellation
+        let transcription_future = async {
+            if let Some(text) = cached_stt_text {
+                log::info!("Pipeline: Reusing cached STT response for identical audio");
+                return Ok(Transcript::from_text(text));
+            }
+
+            transcribe_with_retry(
+                &stt_provider,
+                &wav_bytes,
+                stt_context.as_deref(),
+                &retry_config,
+                &cancel_token,
+            )
+            .await
+        };
+
+        // Race between transcription, timeout, and cancellation
+        let stt_start = std::time::Instant::now();
+        let stt_result = tokio::select! {
+            biased;
+
+            // Cancellation takes priority
+            _ = cancel_token.cancelled() => {
+                log::info!("Pipeline: Transcription cancelled");
+                Err(PipelineError::Cancelled)
+            }
+
+            // Timeout
+            _ = tokio::time::sleep(timeout) => {
+                log::warn!("Pipeline: Transcription timed out after {:?}", timeout);
+                Err(PipelineError::Timeout(timeout))
+            }
+
+            // Actual transcription
+            result = transcription_future => result,
+        };
+        let stt_result = match (stt_result, fallback_stt_provider) {
+            (Err(e), Some(fallback)) if should_fall_back(&e) => {
+                self.transcribe_with_fallback(
+                    fallback,
+                    stt_provider.name(),
+                    &e,
+                    wav_bytes.clone(),
+                    &retry_config,
+                    timeout,
+                    &cancel_token,
+                )
+                .await
+            }
+            (result, _) => result,
+        };
+
+        let (stt_text, stt_language, stt_segments) = match stt_result {
+            Ok(t) => (
+                self.postprocess_stt_text(normalize_stt_text(t.text)),
+                t.language,
+                t.segments,
+            ),
+            Err(e) => {
+                let mut inner = self
+                    .inner
+                    .lock()
+                    .map_err(|err| PipelineError::Lock(err.to_string()))?;
+                inner.finish_job(job_id, Err(&e));
+                return Err(e);
+            }
+        };
+        let stt_duration_ms = stt_start.elapsed().as_millis() as u64;
+        log::info!("Pipeline: STT complete, {} chars", stt_text.len());
+        let stt_text = self.finish_stt_text(stt_text, stt_cache_key, stats);
+
+        // Phase 3: Optional LLM formatting
+        let mut llm_duration_ms: Option<u64> = None;
+        let mut llm_outcome: LlmOutcome = LlmOutcome::NotAttempted;
+
+        // Capture the *actual* provider/model that will be used (including provider defaults)
+        // before we move `llm_provider` into the formatting block.
+        let llm_provider_used: Option<String> = llm_provider.as_ref().map(|p| p.name().to_string());
+        let llm_model_used: Option<String> = llm_provider.as_ref().map(|p| p.model().to_string());
+
+        let mut deferred_formatting: Option<DeferredFormatting> = None;
+
+        let final_text = if let Some(llm) = llm_provider {
+            // Expose the optional LLM step as a distinct phase for UI.
+            {
+                let mut inner = self
+                    .inner
+                    .lock()
+                    .map_err(|e| PipelineError::Lock(e.to_string()))?;
+                inner.set_job_stage(job_id, PipelineState::Rewriting);
+            }
+
+            log::info!("Pipeline: Applying LLM formatting");
+
+            llm_outcome = LlmOutcome::Succeeded; // may be overwritten by fallback paths
+            let llm_start = std::time::Instant::now();
+            let redaction = redact_for_llm(redactor.as_ref(), llm.as_ref(), &stt_text);
+            let llm_input = redaction.as_ref().map_or(stt_text.as_str(), |r| r.text.as_str());
+
+            // Apply LLM formatting with a per-attempt timeout and retries
+            let llm_result = tokio::select! {
+                biased;
+
+                _ = cancel_token.cancelled() => {
+                    log::info!("Pipeline: LLM formatting cancelled");
+                    Err(PipelineError::Cancelled)
+                }
+
+                result = format_text_with_retry(llm.as_ref(), llm_input, &llm_prompts, existing_text.as_deref(), llm_timeout, &llm_retry_config) => {
+                    match result {
+                        Ok(formatted) => {
+                            log::info!("Pipeline: LLM formatted {} -> {} chars", stt_text.len(), formatted.len());
+                            Ok(restore_pii(redaction.as_ref(), formatted))
+                        }
+                        Err(LlmError::Timeout(_)) => {
+                            log::warn!("Pipeline: LLM formatting timed out, using raw transcript");
+                            // On timeout, fall back to raw transcript instead of failing
+                            llm_outcome = LlmOutcome::TimedOut;
+                            Ok(stt_text.clone())
+                        }
+                        Err(e) => {
+                            log::warn!("Pipeline: LLM formatting failed ({}), using raw transcript", e);
+                            // On error, fall back to raw transcript instead of failing
+                            llm_outcome = LlmOutcome::Failed(e.to_string());
+                            Ok(stt_text.clone())
+                        }
+                    }
+                }
+            };
+
+            llm_duration_ms = Some(llm_start.elapsed().as_millis() as u64);
+            deferred_formatting = self.defer_formatting(
+                &llm_outcome,
+                &llm,
+                &llm_prompts,
+                llm_timeout,
+                &stt_text,
+                redaction,
+            );
+
+            match llm_result {
+                Ok(text) => text,
+                Err(PipelineError::Cancelled) => {
+                    let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+                    inner.finish_job(job_id, Err(&PipelineError::Cancelled));
+                    return Err(PipelineError::Cancelled);
+                }
+                Err(_) => stt_text.clone(), // Fallback on other errors
+            }
+        } else {
+            stt_text.clone()
+        };
+        let final_text = self.apply_output_hook(final_text, &stt_text).await;
+
+        // Phase 4: Update state to idle
+        {
+            let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+            inner.finish_job(job_id, Ok(()));
+            inner.recent_transcripts.push(Instant::now(), &final_text);
+            log::info!("Pipeline: Complete, {} chars output", final_text.len());
+        }
+
+        Ok(TranscriptionResult {
+            stt_text,
+            final_text,
+            stt_duration_ms,
+            llm_duration_ms,
+            llm_provider_used,
+            llm_model_used,
+            llm_outcome,
+            deferred_formatting,
+            skipped: None,
+            stt_language,
+            stt_segments,
+            audio_duration_secs: Some(stats.duration_secs),
+        })
+    }
+
+    /// Transcribe provided WAV bytes using the same STT + optional LLM logic as the main pipeline.
+    ///
+    /// This is used for retrying failed requests from persisted audio. `retry_profile`
+    /// selects how hard STT is retried (see `RetryProfile`).
+    pub async fn transcribe_wav_bytes_detailed(
+        &self,
+        wav_bytes: Bytes,
+        retry_profile: RetryProfile,
+    ) -> Result<TranscriptionResult, PipelineError> {
+        // Decoding the audio for its levels and building STT providers run without the lock.
+        let wav = wav_bytes.clone();
+        let measuring =
+            tokio::task::spawn_blocking(move || crate::audio_capture::wav_level_stats(&wav));
+        let preparing = prepare_stt_providers(&self.inner, |inner| {
+            inner.stt_providers_wanted(inner.active_profile().as_ref(), true)
+        });
+        let (stats, ()) = tokio::join!(measuring, preparing);
+        let stats = stats.ok().flatten();
+        let audio_duration_secs = stats.map(|stats| stats.duration_secs);
+
+        // Phase 1: Resolve providers/config under lock.
+        let (job_id, stt_cache_key, cached_stt_text, stt_provider, fallback_stt_provider, llm_provider, llm_prompts, llm_timeout, llm_retry_config, redactor, retry_config, timeout, cancel_token) = {
+            let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+            // Guard: don't run a retry while actively recording.
+            if inner.state == PipelineState::Recording {
+                return Err(PipelineError::AlreadyRecording);
+            }
+
+            // Keep a copy for STT testing/debugging UI.
+            inner.last_wav_bytes = Some(wav_bytes.clone());
+            inner.remember_request_audio(&wav_bytes);
+
+            let fingerprint = fingerprint_wav(&wav_bytes);
+            inner.record_audio_fingerprint(&fingerprint);
+
+            // Saved audio gets the same quiet-audio gate as a live recording. VAD results
+            // aren't saved with it, so only the levels are checked.
+            if let Some(stats) = stats {
+                let diagnostics = AudioCaptureDiagnostics {
+                    stats,
+                    speech_detected: None,
+                    vad_queue: None,
+                    realtime_speech_detected: None,
+                    trimmed_silence_secs: None,
+                };
+                if let Some(reason) = inner.screen_audio(&fingerprint, &diagnostics, false) {
+                    return Ok(TranscriptionResult::skipped(reason));
+                }
+            }
+
+            // Check size limit
+            let max_bytes = inner.config.max_recording_bytes;
+            if max_bytes > 0 && wav_bytes.len() > max_bytes {
+                inner.set_error(&format!("Recording too large: {} bytes", wav_bytes.len()));
+                return Err(PipelineError::RecordingTooLarge(wav_bytes.len(), max_bytes));
+            }
+
+            let llm_config = inner.config.llm_config.clone();
+            let active_profile = inner.active_profile();
+            let llm_prompts = active_profile
+                .as_ref()
+                .map(|p| p.prompts.clone())
+                .unwrap_or_else(|| llm_config.prompts.clone());
+
+            // Resolve effective STT settings (profile overrides -> global defaults, with safe fallback)
+            let desired_stt_provider = canonicalize_stt_provider_id(
+                active_profile
+                    .as_ref()
+                    .and_then(|p| p.stt_provider.as_deref())
+                    .unwrap_or(inner.config.stt_provider.as_str()),
+            );
+            let desired_stt_model = active_profile
+                .as_ref()
+                .and_then(|p| p.stt_model.clone())
+                .or_else(|| inner.config.stt_model.clone());
+            let desired_timeout = active_profile
+                .as_ref()
+                .and_then(|p| p.stt_timeout_seconds)
+                .map(|s| seconds_to_duration_or(s, inner.config.transcription_timeout))
+                .unwrap_or(inner.config.transcription_timeout);
+
+            let stt_provider = match inner.get_or_create_stt_provider(&desired_stt_provider, desired_stt_model.clone()) {
+                Ok(p) => p,
+                Err(e) => {
+                    // If the profile specified an override provider, fall back to global provider.
+                    let global_provider = canonicalize_stt_provider_id(&inner.config.stt_provider);
+                    if global_provider != desired_stt_provider {
+                        log::warn!(
+                            "Pipeline: Profile STT provider '{}' unavailable ({}), falling back to '{}'",
+                            desired_stt_provider,
+                            e,
+                            global_provider
+                        );
+                        let global_model = inner.config.stt_model.clone();
+                        inner.get_or_create_stt_provider(&global_provider, global_model)
+                            .map_err(|err| {
+                                inner.set_error(&format!("No STT provider configured: {}", err));
+                                PipelineError::NoProvider
+                            })?
+                    } else {
+                        inner.set_error(&format!("No STT provider configured: {}", e));
+                        return Err(PipelineError::NoProvider);
+                    }
+                }
+            };
+
+            // Resolve effective LLM provider/model (profile overrides -> global defaults)
+            let llm_timeout = llm_config.timeout;
+            let effective_llm_enabled = active_profile
+                .as_ref()
+                .and_then(|p| p.rewrite_llm_enabled)
+                .unwrap_or(inner.config.llm_config.enabled);
+
+            let llm_provider = if effective_llm_enabled {
+                let desired_llm_provider = active_profile
+                    .as_ref()
+                    .and_then(|p| p.llm_provider.clone())
+                    .unwrap_or_else(|| llm_config.provider.clone());
+                let desired_llm_model = active_profile
+                    .as_ref()
+                    .and_then(|p| p.llm_model.clone())
+                    .or_else(|| llm_config.model.clone());
+
+                match inner.get_or_create_llm_provider(
+                    desired_llm_provider.as_str(),
+                    desired_llm_model.clone(),
+                    llm_timeout,
+                    llm_config.ollama_url.clone(),
+                ) {
+                    Ok(p) => Some(p),
+                    Err(e) => {
+                        // Fallback to global provider if profile requested a different one.
+                        if active_profile
+                            .as_ref()
+                            .and_then(|p| p.llm_provider.as_ref())
+                            .is_some()
+                            && desired_llm_provider != llm_config.provider
+                        {
+                            log::warn!(
+                                "Pipeline: Profile LLM provider '{}' unavailable ({}), falling back to '{}'",
+                                desired_llm_provider,
+                                e,
+                                llm_config.provider
+                            );
+                            inner
+                                .get_or_create_llm_provider(
+                                    llm_config.provider.as_str(),
+                                    llm_config.model.clone(),
+                                    llm_timeout,
+                                    llm_config.ollama_url.clone(),
+                                )
+                                .ok()
+                        } else {
+                            log::warn!("Pipeline: LLM disabled for this transcription ({})", e);
+                            None
+                        }
+                    }
+                }
+            } else {
+                None
+            };
+
+            let stt_provider = inner.offline_stt_provider(stt_provider);
+            let llm_provider = inner.offline_llm_provider(llm_provider);
+
+            let retry_config = retry_profile.apply(&inner.config.retry_config);
+            let llm_retry_config = retry_profile.apply(&llm_config.retry_config);
+            let redactor = inner.request_redactor(active_profile.as_ref());
+
+            let stt_cache_key =
+                SttResponseCache::key(&fingerprint, &inner.stt_provider_key(&stt_provider));
+            let cached_stt_text = inner.stt_response_cache.get(&stt_cache_key);
+            let fallback_stt_provider = inner.fallback_stt_provider_for(&stt_provider);
+            let (job_id, cancel_token) = inner.begin_job();
+
+            (
+                job_id,
+                stt_cache_key,
+                cached_stt_text,
+                stt_provider,
+                fallback_stt_provider,
+                llm_provider,
+                llm_prompts,
+                llm_timeout,
+                llm_retry_config,
+                redactor,
+                retry_config,
+                desired_timeout,
+                cancel_token,
+            )
+        };
+
+        if !self.wait_for_job_turn(job_id, &cancel_token).await? {
+            return Err(PipelineError::Cancelled);
+        }
+
+        log::info!(
+            "Pipeline: Starting retry transcription ({} bytes, timeout {:?})",
+            wav_bytes.len(),
+            timeout
+        );
+
+        // Phase 2: STT transcription
+        let transcription_future = async {
+            if let Some(text) = cached_stt_text {
+                log::info!("Pipeline: Reusing cached STT response for identical audio");
+                return Ok(Transcript::from_text(text));
+            }
+
+            transcribe_with_retry(&stt_provider, &wav_bytes, None, &retry_config, &cancel_token)
+                .await
+        };
+
+        let stt_start = std::time::Instant::now();
+        let stt_result = tokio::select! {
+            biased;
+
+            _ = cancel_token.cancelled() => {
+                log::info!("Pipeline: Retry transcription cancelled");
+                Err(PipelineError::Cancelled)
+            }
+
+            _ = tokio::time::sleep(timeout) => {
+                log::warn!("Pipeline: Retry transcription timed out after {:?}", timeout);
+                Err(PipelineError::Timeout(timeout))
+            }
+
+            result = transcription_future => result,
+        };
+        let stt_result = match (stt_result, fallback_stt_provider) {
+            (Err(e), Some(fallback)) if should_fall_back(&e) => {
+                self.transcribe_with_fallback(
+                    fallback,
+                    stt_provider.name(),
+                    &e,
+                    wav_bytes.clone(),
+                    &retry_config,
+                    timeout,
+                    &cancel_token,
+                )
+                .await
+            }
+            (result, _) => result,
+        };
+
+        let (stt_text, stt_language, stt_segments) = match stt_result {
+            Ok(t) => (
+                self.postprocess_stt_text(normalize_stt_text(t.text)),
+                t.language,
+                t.segments,
+            ),
+            Err(e) => {
+                let mut inner = self
+                    .inner
+                    .lock()
+                    .map_err(|err| PipelineError::Lock(err.to_string()))?;
+                inner.finish_job(job_id, Err(&e));
+                return Err(e);
+            }
+        };
+
+        let stt_duration_ms = stt_start.elapsed().as_millis() as u64;
+        log::info!("Pipeline: Retry STT complete, {} chars", stt_text.len());
+        let stt_text = match stats {
+            Some(stats) => self.finish_stt_text(stt_text, stt_cache_key, stats),
+            None => {
+                self.cache_stt_response(stt_cache_key, &stt_text);
+                stt_text
+            }
+        };
+
+        // Phase 3: Optional LLM formatting
+        let mut llm_duration_ms: Option<u64> = None;
+        let mut llm_outcome: LlmOutcome = LlmOutcome::NotAttempted;
+
+        let llm_provider_used: Option<String> = llm_provider.as_ref().map(|p| p.name().to_string());
+        let llm_model_used: Option<String> = llm_provider.as_ref().map(|p| p.model().to_string());
+
+        let mut deferred_formatting: Option<DeferredFormatting> = None;
+
+        let final_text = if let Some(llm) = llm_provider {
+            // Expose the optional LLM step as a distinct phase for UI.
+            {
+                let mut inner = self
+                    .inner
+                    .lock()
+                    .map_err(|e| PipelineError::Lock(e.to_string()))?;
+                inner.set_job_stage(job_id, PipelineState::Rewriting);
+            }
+
+            log::info!("Pipeline: Applying LLM formatting (retry)");
+            llm_outcome = LlmOutcome::Succeeded;
+            let llm_start = std::time::Instant::now();
+            let redaction = redact_for_llm(redactor.as_ref(), llm.as_ref(), &stt_text);
+            let llm_input = redaction.as_ref().map_or(stt_text.as_str(), |r| r.text.as_str());
+
+            let llm_result = tokio::select! {
+                biased;
+
+                _ = cancel_token.cancelled() => {
+                    log::info!("Pipeline: Retry LLM formatting cancelled");
+                    Err(PipelineError::Cancelled)
+                }
+
+                result = format_text_with_retry(llm.as_ref(), llm_input, &llm_prompts, None, llm_timeout, &llm_retry_config) => {
+                    match result {
+                        Ok(formatted) => {
+                            log::info!("Pipeline: Retry LLM formatted {} -> {} chars", stt_text.len(), formatted.len());
+                            Ok(restore_pii(redaction.as_ref(), formatted))
+                        }
+                        Err(LlmError::Timeout(_)) => {
+                            log::warn!("Pipeline: Retry LLM formatting timed out, using raw transcript");
+                            llm_outcome = LlmOutcome::TimedOut;
+                            Ok(stt_text.clone())
+                        }
+                        Err(e) => {
+                            log::warn!("Pipeline: Retry LLM formatting failed ({}), using raw transcript", e);
+                            llm_outcome = LlmOutcome::Failed(e.to_string());
+                            Ok(stt_text.clone())
+                        }
+                    }
+                }
+            };
+
+            llm_duration_ms = Some(llm_start.elapsed().as_millis() as u64);
+            deferred_formatting = self.defer_formatting(
+                &llm_outcome,
+                &llm,
+                &llm_prompts,
+                llm_timeout,
+                &stt_text,
+                redaction,
+            );
+
+            match llm_result {
+                Ok(text) => text,
+                Err(PipelineError::Cancelled) => {
+                    let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+                    inner.finish_job(job_id, Err(&PipelineError::Cancelled));
+                    return Err(PipelineError::Cancelled);
+                }
+                Err(_) => stt_text.clone(),
+            }
+        } else {
+            stt_text.clone()
+        };
+        let final_text = self.apply_output_hook(final_text, &stt_text).await;
+
+        // Phase 4: Reset to idle
+        {
+            let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+            inner.finish_job(job_id, Ok(()));
+            log::info!("Pipeline: Retry complete, {} chars output", final_text.len());
+        }
+
+        Ok(TranscriptionResult {
+            stt_text,
+            final_text,
+            stt_duration_ms,
+            llm_duration_ms,
+            llm_provider_used,
+            llm_model_used,
+            llm_outcome,
+            deferred_formatting,
+            skipped: None,
+            stt_language,
+            stt_segments,
+            audio_duration_secs,
+        })
+    }
+
+    /// Stop recording and transcribe the audio.
+    ///
+    /// Kept for backwards compatibility. Prefer `stop_and_transcribe_detailed`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub async fn stop_and_transcribe(&self) -> Result<String, PipelineError> {
+        self.stop_and_transcribe_detailed()
+            .await
+            .map(|r| r.final_text)
+    }
+
+    /// The profile in effect: the one bound to the hotkey that started the recording,
+    /// else the one matching the foreground app.
+    ///
+    /// Both change with the next recording, so callers that output a transcript later
+    /// resolve it when the recording stops and keep the result.
+    pub fn active_profile(&self) -> Option<crate::llm::ProgramPromptProfile> {
+        self.inner.lock().ok()?.active_profile()
+    }
+
+    /// Output mode override of the active profile, if any.
+    pub fn output_mode_for_active_profile(&self) -> Option<crate::output_mode::OutputMode> {
+        self.active_profile().and_then(|p| p.output_mode)
+    }
+
+    /// Record a short spoken reply for `listen` and return its transcript (STT only).
+    ///
+    /// Cancelling the pipeline while listening returns `PipelineError::Cancelled`.
+    pub async fn listen_for_reply(&self, listen: Duration) -> Result<String, PipelineError> {
+        // The reply belongs to the dictation being confirmed; keep its profile.
+        let profile_id = self
+            .inner
+            .lock()
+            .map_err(|e| PipelineError::Lock(e.to_string()))?
+            .hotkey_profile_id
+            .clone();
+        self.start_recording_for_profile(profile_id)?;
+        let cancel_token = self.get_cancel_token().unwrap_or_default();
+
+        tokio::select! {
+            _ = cancel_token.cancelled() => return Err(PipelineError::Cancelled),
+            _ = tokio::time::sleep(listen) => {}
+        }
+
+        self.stop_recording()?;
+        self.transcribe_last_audio_for_profile(None).await
+    }
+
+    /// Merge the segments of a dictation session into a single message.
+    ///
+    /// Uses the same LLM provider resolution as transcription (active profile first, then the
+    /// global rewrite settings). When the LLM is disabled, unavailable, times out or fails, the
+    /// segments are joined locally with `crate::session::join_segments`.
+    pub async fn merge_session_segments(
+        &self,
+        segments: &[String],
+    ) -> Result<(String, LlmOutcome), PipelineError> {
+        let fallback = crate::session::join_segments(segments);
+        if segments.len() < 2 {
+            return Ok((fallback, LlmOutcome::NotAttempted));
+        }
+
+        let (llm_provider, llm_prompts, llm_timeout, redactor) = {
+            let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+            let llm_config = inner.config.llm_config.clone();
+            let active_profile = inner.active_profile();
+            let llm_prompts = active_profile
+                .as_ref()
+                .map(|p| p.prompts.clone())
+                .unwrap_or_else(|| llm_config.prompts.clone());
+            let effective_llm_enabled = active_profile
+                .as_ref()
+                .and_then(|p| p.rewrite_llm_enabled)
+                .unwrap_or(llm_config.enabled);
+
+            let llm_provider = if effective_llm_enabled {
+                let desired_llm_provider = active_profile
+                    .as_ref()
+                    .and_then(|p| p.llm_provider.clone())
+                    .unwrap_or_else(|| llm_config.provider.clone());
+                let desired_llm_model = active_profile
+                    .as_ref()
+                    .and_then(|p| p.llm_model.clone())
+                    .or_else(|| llm_config.model.clone());
+
+                inner
+                    .get_or_create_llm_provider(
+                        desired_llm_provider.as_str(),
+                        desired_llm_model,
+                        llm_config.timeout,
+                        llm_config.ollama_url.clone(),
+                    )
+                    .map_err(|e| log::warn!("Pipeline: LLM unavailable for session merge ({}).", e))
+                    .ok()
+            } else {
+                None
+            };
+
+            let redactor = inner.redactor_for(active_profile.as_ref());
+            (llm_provider, llm_prompts, llm_config.timeout, redactor)
+        };
+
+        let Some(llm) = llm_provider else {
+            return Ok((fallback, LlmOutcome::NotAttempted));
+        };
+
+        log::info!("Pipeline: Merging {} session segments with LLM", segments.len());
+
+        let mut redaction = Redaction::default();
+        let segments: Vec<String> = match redactor.as_ref().filter(|_| llm.name() != "ollama") {
+            Some(redactor) => segments
+                .iter()
+                .map(|s| redactor.redact_with(s, &mut redaction))
+                .collect(),
+            None => segments.to_vec(),
+        };
+
+        tokio::select! {
+            _ = tokio::time::sleep(llm_timeout) => {
+                log::warn!("Pipeline: Session merge timed out, joining segments locally");
+                Ok((fallback, LlmOutcome::TimedOut))
+            }
+
+            result = merge_session_segments(llm.as_ref(), &segments, &llm_prompts) => {
+                match result {
+                    Ok(merged) if !merged.is_empty() => {
+                        Ok((redaction.restore(&merged), LlmOutcome::Succeeded))
+                    }
+                    Ok(_) => Ok((fallback, LlmOutcome::Failed("Empty response".to_string()))),
+                    Err(e) => {
+                        log::warn!("Pipeline: Session merge failed ({}), joining segments locally", e);
+                        Ok((fallback, LlmOutcome::Failed(e.to_string())))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Update configuration
+    ///
+    /// Only providers whose settings changed are rebuilt; e.g. editing a prompt keeps the
+    /// loaded local Whisper model and the LLM provider.
+    ///
+    /// Note: This will not affect an in-progress recording.
+    pub fn update_config(&self, config: PipelineConfig) -> Result<(), PipelineError> {
+        let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+
+        // Don't update config while recording - could cause issues
+        if inner.state == PipelineState::Recording {
+            log::warn!("Pipeline: Config update requested while recording, will take effect after current session");
+        }
+
+        inner.evict_stale_providers(&config);
+        inner.config = config.clone();
+        inner.initialize_providers(&config);
+        // Update VAD config on audio capture
+        inner.audio_capture.set_vad_config(config.vad_config);
+        inner
+            .audio_capture
+            .set_stop_at_max_duration(config.stop_at_max_duration);
+        inner.sync_preroll_standby();
+        log::info!("Pipeline configuration updated");
+        Ok(())
+    }
+
+    /// Check if recording
+    pub fn is_recording(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.state == PipelineState::Recording)
+            .unwrap_or(false)
+    }
+
+    /// Get a clone of the last captured WAV bytes, if present.
+    ///
+    /// This is a cheap reference-counted clone; the audio itself is not copied.
+    pub fn clone_last_wav_bytes(&self) -> Option<Bytes> {
+        self.inner.lock().ok().and_then(|inner| inner.last_wav_bytes.clone())
+    }
+
+    /// Queued, running and recently finished transcription jobs, oldest first.
+    pub fn jobs(&self) -> Vec<TranscriptionJobInfo> {
+        let Ok(inner) = self.inner.lock() else {
+            return Vec::new();
+        };
+        let mut jobs: Vec<TranscriptionJobInfo> = inner.finished_jobs.iter().cloned().collect();
+        jobs.extend(inner.jobs.values().map(|job| job.info.clone()));
+        jobs.sort_by_key(|job| job.id);
+        jobs
+    }
+
+    /// Cancel one queued or running transcription job. Returns false if it already
+    /// finished (or never existed).
+    pub fn cancel_job(&self, id: u64) -> bool {
+        self.inner.lock().map(|mut inner| inner.cancel_job(id)).unwrap_or(false)
+    }
+
+    /// Save the audio of job `id` so it can be recovered if the app exits before the job
+    /// finishes. Sealing and writing the audio happen on a blocking thread, without the
+    /// pipeline lock.
+    async fn persist_job_audio(&self, id: u64, stt_provider: &str, duration_secs: f32, wav: Bytes) {
+        let request_id = match self.inner.lock() {
+            Ok(inner) => match inner.jobs.get(&id) {
+                Some(job) => job.info.request_id.clone(),
+                None => return,
+            },
+            Err(_) => return,
+        };
+        let stt_provider = stt_provider.to_string();
+        let recovery_id = tokio::task::spawn_blocking(move || {
+            crate::recovery::global().begin(request_id, &stt_provider, duration_secs, &wav)
+        })
+        .await
+        .ok()
+        .flatten();
+        let Some(recovery_id) = recovery_id else {
+            return;
+        };
+
+        let attached = self.inner.lock().ok().is_some_and(|mut inner| {
+            inner
+                .jobs
+                .get_mut(&id)
+                .map(|job| job.recovery_id = Some(recovery_id.clone()))
+                .is_some()
+        });
+        if !attached {
+            // The job ended (e.g. was cancelled) while its audio was being written.
+            crate::recovery::global().finish(&recovery_id);
+        }
+    }
+
+    /// Wait until every job queued before `id` has finished, then mark `id` running.
+    ///
+    /// Returns `Ok(false)` if the job is cancelled while waiting; it is then finished.
+    async fn wait_for_job_turn(
+        &self,
+        id: u64,
+        cancel_token: &CancellationToken,
+    ) -> Result<bool, PipelineError> {
+        let turns = self
+            .inner
+            .lock()
+            .map_err(|e| PipelineError::Lock(e.to_string()))?
+            .job_turns
+            .clone();
+        loop {
+            // Created before checking, so a job finishing in between still wakes us.
+            let notified = turns.notified();
+            let turn = {
+                let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+                let turn = inner.start_job_if_turn(id);
+                if turn.is_none() || cancel_token.is_cancelled() {
+                    inner.finish_job(id, Err(&PipelineError::Cancelled));
+                    return Ok(false);
+                }
+                turn == Some(true)
+            };
+            if turn {
+                return Ok(true);
+            }
+            log::debug!("Pipeline: Job {} queued behind earlier transcriptions", id);
+            tokio::select! {
+                _ = notified => {}
+                _ = cancel_token.cancelled() => {}
+            }
+        }
+    }
+
+    /// Audio of the recording logged as request `request_id`.
+    ///
+    /// Unlike `clone_last_wav_bytes`, this stays right for a transcription that finishes
+    /// after a newer recording was made.
+    pub fn audio_for_request(&self, request_id: &str) -> Option<Bytes> {
+        self.inner.lock().ok().and_then(|inner| {
+            inner
+                .request_audio
+                .iter()
+                .find(|(id, _)| id == request_id)
+                .map(|(_, wav)| wav.clone())
+        })
+    }
+
+    /// Give the LLM the text before the caret in the target field, for the recording in
+    /// progress. Ignored when not recording; only the last `SURROUNDING_TEXT_MAX_CHARS`
+    /// characters are kept.
+    pub fn set_surrounding_text(&self, text: String) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.state == PipelineState::Recording {
+                inner.surrounding_text = Some(tail_chars(&text, SURROUNDING_TEXT_MAX_CHARS));
+            }
+        }
+    }
+
+    /// Transcribe the audio recorded so far, for live partial results.
+    ///
+    /// Returns `Ok(None)` while not recording, offline, or with less than
+    /// `MIN_PARTIAL_AUDIO_SECS` of audio. Partials skip retries, fallbacks and the request
+    /// log; the final transcript supersedes them anyway.
+    pub async fn transcribe_partial(&self) -> Result<Option<String>, PipelineError> {
+        prepare_stt_providers(&self.inner, |inner| {
+            if inner.state != PipelineState::Recording || inner.offline {
+                return Vec::new();
+            }
+            vec![inner.stt_provider_choice(inner.active_profile().as_ref())]
+        })
+        .await;
+
+        let (buffer, provider) = {
+            let mut inner = self.inner.lock().map_err(|e| PipelineError::Lock(e.to_string()))?;
+            if inner.state != PipelineState::Recording || inner.offline {
+                return Ok(None);
+            }
+            let Some(buffer) = inner.audio_capture.snapshot_buffer() else {
+                return Ok(None);
+            };
+            if buffer.duration_secs() < MIN_PARTIAL_AUDIO_SECS {
+                return Ok(None);
+            }
+            let profile = inner.active_profile();
+            (buffer, inner.stt_provider_for(profile.as_ref())?)
+        };
+
+        let (wav, _) = tokio::task::spawn_blocking(move || {
+            buffer.to_wav_bytes_with_config(AudioEncodeConfig {
+                downmix_to_mono: true,
+                resample_to_16khz: true,
+                ..Default::default()
+            })
+        })
+        .await
+        .unwrap_or_else(|e| Err(AudioCaptureError::Encoding(e.to_string())))?;
+        let text = tokio::time::timeout(
+            DEFAULT_TRANSCRIPTION_TIMEOUT,
+            provider.transcribe(Bytes::from(wav), &AudioFormat::default()),
+        )
+        .await
+        .map_err(|_| PipelineError::Timeout(DEFAULT_TRANSCRIPTION_TIMEOUT))??;
+        Ok(Some(self.postprocess_stt_text(normalize_stt_text(text))))
+    }
+
+    /// Get a copy of the last recording diagnostics (raw stats + optional speech detection).
+    pub fn last_recording_diagnostics(&self) -> Option<AudioCaptureDiagnostics> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.last_recording_diagnostics)
+    }
+
+    /// Watch capture events while recording and pass on the ones that matter as
+    /// `PipelineEvent`s.
+    ///
+    /// On `PipelineEvent::AutoStop` the owner stops and transcribes the recording the same way
+    /// a user-initiated stop would (output, history, ...); further events of that recording
+    /// are dropped.
+    pub fn spawn_capture_watcher<F>(&self, on_event: F) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(PipelineEvent) + Send + 'static,
+    {
+        let pipeline = self.clone();
+        self.host.runtime().spawn(async move {
+            let mut stopping = false;
+            loop {
+                tokio::time::sleep(CAPTURE_WATCH_INTERVAL).await;
+
+                match pipeline.try_state() {
+                    Some(PipelineState::Recording) => {}
+                    Some(_) => {
+                        stopping = false;
+                        continue;
+                    }
+                    None => continue,
+                }
+                if stopping {
+                    continue;
+                }
+
+                let vad_auto_stop = pipeline.is_vad_auto_stop_enabled();
+                // Deepgram decides when speech ended while its stream is open.
+                let provider_endpointing = pipeline.active_provider_endpointing();
+                if let Some(endpointing) = &provider_endpointing {
+                    if let Some(partial) = endpointing.take_partial() {
+                        on_event(PipelineEvent::PartialTranscript(partial));
+                    }
+                    if vad_auto_stop && endpointing.take_speech_final() {
+                        let reason = AutoStopReason::SpeechEnd;
+                        log::info!("Pipeline: Auto-stopping recording ({:?}, Deepgram)", reason);
+                        stopping = true;
+                        on_event(PipelineEvent::AutoStop(reason));
+                        continue;
+                    }
+                }
+                let local_speech_end = vad_auto_stop && provider_endpointing.is_none();
+                while let Some(event) = pipeline.poll_vad_event() {
+                    let Some(event) = PipelineEvent::from_capture_event(event, local_speech_end) else {
+                        continue;
+                    };
+                    if let PipelineEvent::AutoStop(reason) = &event {
+                        log::info!("Pipeline: Auto-stopping recording ({:?})", reason);
+                        stopping = true;
+                    }
+                    on_event(event);
+                    if stopping {
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Poll for VAD events (non-blocking)
+    ///
+    /// Returns the next VAD event if one is available, or None if no events are pending.
+    pub fn poll_vad_event(&self) -> Option<AudioCaptureEvent> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.audio_capture.poll_vad_event())
+    }
+
+    /// Check if VAD auto-stop is enabled
+    pub fn is_vad_auto_stop_enabled(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.audio_capture.is_vad_auto_stop_enabled())
+            .unwrap_or(false)
+    }
+
+    /// Cancel current operation
+    ///
+    /// This will:
+    /// - Stop any ongoing recording
+    /// - Signal cancellation to all in-flight transcriptions
+    /// - Reset the pipeline to Idle state
+    pub fn cancel(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            let state = inner.effective_state();
+            if !state.can_cancel() {
+                log::debug!("Pipeline: Cancel requested but nothing to cancel (state: {:?})", state);
+                return;
+            }
+
+            // Signal cancellation to any async tasks
+            if let Some(token) = inner.cancel_token.take() {
+                token.cancel();
+            }
+            inner.cancel_jobs();
+
+            // Stop audio capture if recording
+            if inner.state == PipelineState::Recording {
+                inner.audio_capture.stop();
+            }
+
+            inner.reset_to_idle();
+            log::info!("Pipeline: Cancelled and reset to idle");
+        }
+    }
+
+    /// Force reset the pipeline to idle state
+    ///
+    /// Use this to recover from stuck states. Cancels any in-progress operations.
+    pub fn force_reset(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            // Cancel any async tasks
+            if let Some(token) = inner.cancel_token.take() {
+                token.cancel();
+            }
+            inner.cancel_jobs();
+
+            // Force stop audio capture
+            inner.audio_capture.stop();
+
+            // Reset state
+            inner.reset_to_idle();
+            log::warn!("Pipeline: Force reset to idle");
+        }
+    }
+
+    /// State plus the counters that show whether it is making progress.
+    pub fn progress(&self) -> PipelineProgress {
+        match self.inner.lock() {
+            Ok(inner) => PipelineProgress {
+                state: inner.effective_state(),
+                audio_seq: inner.audio_capture.level_snapshot().seq,
+                job_progress: inner.job_progress,
+            },
+            Err(_) => PipelineProgress {
+                state: PipelineState::Error,
+                audio_seq: 0,
+                job_progress: 0,
+            },
+        }
+    }
+
+    /// Get current state
+    pub fn state(&self) -> PipelineState {
+        self.inner
+            .lock()
+            .map(|inner| inner.effective_state())
+            .unwrap_or(PipelineState::Error)
+    }
+
+    /// Get the most recent realtime audio input level snapshot.
+    ///
+    /// This is cheap and intended for UI metering (e.g., overlay waveform). The snapshot is
+    /// updated from the CPAL input callback while recording.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn audio_level_snapshot(&self) -> AudioLevelSnapshot {
+        self.inner
+            .lock()
+            .map(|inner| inner.audio_capture.level_snapshot())
+            .unwrap_or(AudioLevelSnapshot {
+                seq: 0,
+                rms: 0.0,
+                peak: 0.0,
+            })
+    }
+
+    /// Get the name of the current STT provider
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn current_provider_name(&self) -> String {
+        self.inner
+            .lock()
+            .map(|inner| inner.stt_registry.current_name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Get the name of the current (global) LLM provider; empty while LLM formatting is off
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn current_llm_provider_name(&self) -> String {
+        self.inner
+            .lock()
+            .map(|inner| inner.llm_registry.current_name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Use `provider` whenever the LLM provider `name` is requested, instead of building one
+    /// (e.g. a mock in tests). Replaced on the next config update.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn register_llm_provider(&self, name: &str, provider: Arc<dyn LlmProvider>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.llm_registry.register(name, provider);
+        }
+    }
+
+    /// Enter or leave offline mode (local Whisper instead of cloud STT, no cloud LLM).
+    pub fn set_offline(&self, offline: bool) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.offline = offline;
+        }
+    }
+
+    /// Remote endpoints of the configured STT and LLM providers, to probe for connectivity
+    /// (empty when they all run locally).
+    pub fn connectivity_probe_urls(&self) -> Vec<String> {
+        let Ok(mut inner) = self.inner.lock() else {
+            return Vec::new();
+        };
+        let (stt, llm) = inner.configured_providers();
+        let mut urls: Vec<String> = stt
+            .and_then(|p| p.endpoint())
+            .into_iter()
+            .chain(llm.and_then(|p| p.endpoint()))
+            .collect();
+        urls.dedup();
+        urls
+    }
+
+    /// Whether the pipeline is in offline mode.
+    pub fn is_offline(&self) -> bool {
+        self.inner.lock().map(|inner| inner.offline).unwrap_or(false)
+    }
+
+    /// Whether a local model is available for offline transcription.
+    pub fn local_stt_available(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.local_stt_available())
+            .unwrap_or(false)
+    }
+
+    /// Get a clone of the current pipeline configuration
+    pub fn config(&self) -> PipelineConfig {
+        self.inner
+            .lock()
+            .map(|inner| inner.config.clone())
+            .unwrap_or_default()
+    }
+
+    /// Check if the pipeline is in an error state
+    pub fn is_error(&self) -> bool {
+        self.inner
+            .lock()
+            .map(|inner| inner.state == PipelineState::Error)
+            .unwrap_or(true)
+    }
+
+    /// Whether there is a previously captured audio buffer available for testing.
+    pub fn has_last_audio(&self) -> bool {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.last_wav_bytes.as_ref().map(|b| !b.is_empty()))
+            .unwrap_or(false)
+    }
+
+    /// Get the cancellation token for external use (e.g., for coordinating with other async tasks)
+    pub fn get_cancel_token(&self) -> Option<CancellationToken> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|inner| inner.cancel_token.clone())
+    }
+}
+
+impl Default for SharedPipeline {
+    fn default() -> Self {
+        Self::new(PipelineConfig::default())
+    }
+}
+
+impl Clone for SharedPipeline {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            level_meter: self.level_meter.clone(),
+            waveform_meter: self.waveform_meter.clone(),
+            host: self.host.clone(),
+        }
+    }
+}
+
+// Tauri state must be Send + Sync. That holds without any `unsafe impl`: the cpal stream
+// (the only non-Send piece) never leaves its capture thread, which `AudioCapture` drives
+// through a command channel. Fail the build here if a new field breaks this.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedPipeline>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_config_default() {
+        let config = PipelineConfig::default();
+        assert_eq!(config.max_duration_secs, 300.0);
+        assert_eq!(config.stt_provider, "groq");
+        assert_eq!(config.transcription_timeout, DEFAULT_TRANSCRIPTION_TIMEOUT);
+        assert_eq!(config.max_recording_bytes, MAX_WAV_SIZE_BYTES);
+    }
+
+    #[test]
+    fn test_shared_pipeline_creation() {
+        let config = PipelineConfig {
+            stt_api_key: "test-key".to_string(),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+        assert_eq!(pipeline.state(), PipelineState::Idle);
+        assert!(!pipeline.is_error());
+    }
+
+    #[test]
+    fn test_state_guards() {
+        assert!(PipelineState::Idle.can_start_recording());
+        assert!(PipelineState::Error.can_start_recording());
+        assert!(!PipelineState::Recording.can_start_recording());
+        assert!(PipelineState::Transcribing.can_start_recording());
+
+        assert!(PipelineState::Recording.can_stop_recording());
+        assert!(!PipelineState::Idle.can_stop_recording());
+
+        assert!(PipelineState::Recording.can_cancel());
+        assert!(PipelineState::Transcribing.can_cancel());
+        assert!(!PipelineState::Idle.can_cancel());
+    }
+
+    #[test]
+    fn test_force_reset() {
+        let config = PipelineConfig {
+            stt_api_key: "test-key".to_string(),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+
+        // Force reset should always work
+        pipeline.force_reset();
+        assert_eq!(pipeline.state(), PipelineState::Idle);
+    }
+
+    #[test]
+    fn test_background_jobs_do_not_block_recording_state() {
+        let mut inner = PipelineInner::new(PipelineConfig::default(), Arc::new(DefaultPipelineHost));
+        let (first, first_token) = inner.begin_job();
+        let (second, _) = inner.begin_job();
+        inner.set_job_stage(first, PipelineState::Rewriting);
+        assert_eq!(inner.effective_state(), PipelineState::Rewriting);
+
+        // A new recording takes precedence, and jobs finishing meanwhile leave it alone.
+        inner.state = PipelineState::Recording;
+        inner.finish_job(second, Err(&PipelineError::Timeout(Duration::from_secs(1))));
+        assert_eq!(inner.effective_state(), PipelineState::Recording);
+
+        inner.state = PipelineState::Idle;
+        assert_eq!(inner.effective_state(), PipelineState::Rewriting);
+        inner.cancel_jobs();
+        assert!(first_token.is_cancelled());
+        assert_eq!(inner.effective_state(), PipelineState::Idle);
+        inner.finish_job(first, Err(&PipelineError::Cancelled));
+        assert_eq!(inner.effective_state(), PipelineState::Idle);
+    }
+
+    #[test]
+    fn test_jobs_run_in_order() {
+        let pipeline = SharedPipeline::new(PipelineConfig::default());
+        let (first, second, third) = {
+            let mut inner = pipeline.inner.lock().unwrap();
+            (inner.begin_job().0, inner.begin_job().0, inner.begin_job().0)
+        };
+
+        {
+            let mut inner = pipeline.inner.lock().unwrap();
+            assert_eq!(inner.start_job_if_turn(second), Some(false));
+            assert_eq!(inner.start_job_if_turn(first), Some(true));
+        }
+        assert!(pipeline.cancel_job(second));
+        assert!(!pipeline.cancel_job(second));
+
+        {
+            let mut inner = pipeline.inner.lock().unwrap();
+            assert_eq!(inner.start_job_if_turn(second), None);
+            assert_eq!(inner.start_job_if_turn(third), Some(false));
+            inner.finish_job(first, Ok(()));
+            assert_eq!(inner.start_job_if_turn(third), Some(true));
+        }
+
+        let statuses: Vec<(u64, JobStatus)> =
+            pipeline.jobs().iter().map(|job| (job.id, job.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (first, JobStatus::Done),
+                (second, JobStatus::Failed),
+                (third, JobStatus::Running),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fallback_stt_provider_resolution() {
+        let config = PipelineConfig {
+            stt_api_keys: HashMap::from([
+                ("groq".to_string(), "groq-key".to_string()),
+                ("openai".to_string(), "openai-key".to_string()),
+            ]),
+            fallback_stt_provider: Some("openai".to_string()),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+        let mut inner = pipeline.inner.lock().unwrap();
+
+        let primary = inner.get_or_create_stt_provider("groq", None).unwrap();
+        let fallback = inner.fallback_stt_provider_for(&primary).unwrap();
+        assert_eq!(fallback.name(), "openai");
+
+        // A fallback pointing at the primary provider is ignored.
+        let openai = inner.get_or_create_stt_provider("openai", None).unwrap();
+        assert!(inner.fallback_stt_provider_for(&openai).is_none());
+    }
+
+    #[test]
+    fn test_should_fall_back() {
+        assert!(should_fall_back(&PipelineError::Timeout(Duration::from_secs(10))));
+        assert!(should_fall_back(&PipelineError::Stt(SttError::Api(
+            "503 Service Unavailable".to_string()
+        ))));
+        assert!(!should_fall_back(&PipelineError::Stt(SttError::Config(
+            "Invalid API key".to_string()
+        ))));
+        assert!(!should_fall_back(&PipelineError::Cancelled));
+    }
+
+    #[test]
+    fn test_connectivity_test_wav_is_one_second_of_16khz_audio() {
+        let wav = connectivity_test_wav().unwrap();
+        let reader = hound::WavReader::new(std::io::Cursor::new(wav.to_vec())).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16_000);
+        assert_eq!(reader.spec().channels, 1);
+        assert_eq!(reader.duration(), 16_000);
+    }
+
+    #[tokio::test]
+    async fn test_mock_stt_provider_requires_debug_setting() {
+        let wav = connectivity_test_wav().unwrap();
+        let pipeline = SharedPipeline::new(PipelineConfig::default());
+        assert!(pipeline
+            .transcribe_wav_bytes_with(MOCK_PROVIDER_ID, None, wav.clone())
+            .await
+            .is_err());
+
+        let config = PipelineConfig {
+            mock_providers: Some(MockProviders {
+                stt: crate::mock::MockScript {
+                    responses: vec!["  scripted text".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+        let text = pipeline
+            .transcribe_wav_bytes_with(MOCK_PROVIDER_ID, None, wav)
+            .await
+            .unwrap();
+        assert_eq!(text, "scripted text");
+    }
+
+    #[tokio::test]
+    async fn test_retried_audio_is_screened_like_a_recording() {
+        let config = PipelineConfig {
+            stt_provider: MOCK_PROVIDER_ID.to_string(),
+            mock_providers: Some(MockProviders {
+                stt: crate::mock::MockScript {
+                    responses: vec!["Thanks for watching!".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+
+        // One second of audio is short enough for the hallucination filter.
+        let wav = connectivity_test_wav().unwrap();
+        let result = pipeline
+            .transcribe_wav_bytes_detailed(wav, RetryProfile::Background)
+            .await
+            .unwrap();
+        assert_eq!(result.skipped, None);
+        assert_eq!(result.final_text, "");
+
+        // Silence never reaches STT.
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for _ in 0..16_000 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        let result = pipeline
+            .transcribe_wav_bytes_detailed(Bytes::from(cursor.into_inner()), RetryProfile::Background)
+            .await
+            .unwrap();
+        assert_eq!(result.skipped, Some(SkipReason::Quiet));
+    }
+
+    #[tokio::test]
+    async fn test_stt_provider_test_stays_out_of_request_log() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    let complete = |request: &[u8]| {
+                        let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+                            return false;
+                        };
+                        let head = String::from_utf8_lossy(&request[..end]).to_lowercase();
+                        let length = head
+                            .lines()
+                            .find_map(|line| line.strip_prefix("content-length:"))
+                            .and_then(|v| v.trim().parse::<usize>().ok())
+                            .unwrap_or(0);
+                        request.len() >= end + 4 + length
+                    };
+                    while !complete(&request) {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let body = br#"{"text":" hello"}"#;
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                         content-length: {}\r\nconnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = socket.write_all(head.as_bytes()).await;
+                    let _ = socket.write_all(body).await;
+                });
+            }
+        });
+
+        let store = RequestLogStore::new();
+        let config = PipelineConfig {
+            stt_provider: "whisper-server".to_string(),
+            whisper_server_url: Some(url),
+            request_log_store: Some(store.clone()),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+
+        // A dictation in progress: the test must not write into its log.
+        store.start_request("whisper-server".to_string(), None);
+        for _ in 0..2 {
+            let text = pipeline.test_stt_provider("whisper-server", None, None).await.unwrap();
+            assert_eq!(text, "hello");
+        }
+        let logs = store.get_logs(None);
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].stt_request_json.is_none());
+        assert!(logs[0].stt_response_json.is_none());
+    }
+
+    #[test]
+    fn test_llm_registry_holds_global_and_injected_providers() {
+        let config = PipelineConfig {
+            llm_config: LlmConfig {
+                enabled: true,
+                provider: MOCK_PROVIDER_ID.to_string(),
+                ..Default::default()
+            },
+            mock_providers: Some(MockProviders::default()),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+        assert_eq!(pipeline.current_llm_provider_name(), MOCK_PROVIDER_ID);
+
+        let injected: Arc<dyn LlmProvider> = Arc::new(MockLlmProvider::new(Default::default()));
+        pipeline.register_llm_provider("custom", injected.clone());
+        let mut inner = pipeline.inner.lock().unwrap();
+        let timeout = Duration::from_secs(5);
+        let resolved = inner.get_or_create_llm_provider("custom", None, timeout, None).unwrap();
+        assert!(Arc::ptr_eq(&resolved, &injected));
+        // Unregistered providers are still built from settings (here: no API key).
+        assert!(inner.get_or_create_llm_provider("openai", None, timeout, None).is_err());
+    }
+
+    #[test]
+    fn test_update_config_keeps_unaffected_providers() {
+        let config = PipelineConfig {
+            stt_api_keys: HashMap::from([("groq".to_string(), "groq-key".to_string())]),
+            llm_config: LlmConfig {
+                enabled: true,
+                provider: MOCK_PROVIDER_ID.to_string(),
+                ..Default::default()
+            },
+            mock_providers: Some(MockProviders::default()),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config.clone());
+        let cached = |pipeline: &SharedPipeline| {
+            let inner = pipeline.inner.lock().unwrap();
+            (
+                inner.stt_registry.get_current().unwrap(),
+                inner.llm_registry.get_current().unwrap(),
+            )
+        };
+        let (stt, llm) = cached(&pipeline);
+
+        let mut prompt_edit = config.clone();
+        prompt_edit.hallucination_filter_phrases = vec!["thanks for watching".to_string()];
+        pipeline.update_config(prompt_edit.clone()).unwrap();
+        let (same_stt, same_llm) = cached(&pipeline);
+        assert!(Arc::ptr_eq(&stt, &same_stt));
+        assert!(Arc::ptr_eq(&llm, &same_llm));
+
+        let mut key_change = prompt_edit;
+        key_change.stt_api_keys.insert("groq".to_string(), "new-key".to_string());
+        pipeline.update_config(key_change).unwrap();
+        let (new_stt, same_llm) = cached(&pipeline);
+        assert!(!Arc::ptr_eq(&stt, &new_stt));
+        assert!(Arc::ptr_eq(&llm, &same_llm));
+    }
+
+    #[test]
+    fn test_audio_diagnostics_reports_quiet_audio_gate() {
+        let recording = |rms: f32, realtime_speech: Option<bool>| AudioCaptureDiagnostics {
+            stats: AudioLevelStats {
+                duration_secs: 3.0,
+                rms,
+                peak: rms * 4.0,
+            },
+            speech_detected: Some(false),
+            vad_queue: None,
+            realtime_speech_detected: realtime_speech,
+            trimmed_silence_secs: None,
+        };
+        let mut config = PipelineConfig::default();
+
+        let speech = AudioDiagnostics::new(&config, None, 48000, 2, &recording(0.05, Some(true)));
+        assert!(speech.speech_detected);
+        assert_eq!(speech.skip_reason, None);
+        assert!((speech.rms_dbfs + 26.0).abs() < 0.1);
+
+        let silent_room = recording(0.05, Some(false));
+        assert_eq!(
+            AudioDiagnostics::new(&config, None, 48000, 2, &silent_room).skip_reason,
+            Some(SkipReason::EmptyRecording)
+        );
+        // Without realtime VAD only the levels count, unless speech is required.
+        let muted = recording(0.0001, None);
+        assert_eq!(
+            AudioDiagnostics::new(&config, None, 48000, 2, &muted).skip_reason,
+            Some(SkipReason::Quiet)
+        );
+
+        config.quiet_audio_gate_enabled = false;
+        assert_eq!(AudioDiagnostics::new(&config, None, 48000, 2, &silent_room).skip_reason, None);
+    }
+
+    #[test]
+    fn test_hotkey_profile_overrides_foreground_profile() {
+        let profile = crate::llm::ProgramPromptProfile {
+            id: "long-form".to_string(),
+            name: "Long-form".to_string(),
+            program_paths: Vec::new(),
+            prompts: PromptSections::default(),
+            rewrite_llm_enabled: None,
+            stt_provider: None,
+            stt_model: None,
+            stt_timeout_seconds: None,
+            llm_provider: None,
+            llm_model: None,
+            confirm_by_voice: false,
+            redact_pii: None,
+            output_format: None,
+            webhook_url: None,
+            output_mode: Some(crate::output_mode::OutputMode::Clipboard),
+            vad_config: None,
+        };
+        let pipeline = SharedPipeline::new(PipelineConfig {
+            llm_config: LlmConfig {
+                program_prompt_profiles: vec![profile],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        assert!(pipeline.active_profile().is_none());
+
+        pipeline.inner.lock().unwrap().hotkey_profile_id = Some("long-form".to_string());
+        assert_eq!(
+            pipeline.output_mode_for_active_profile(),
+            Some(crate::output_mode::OutputMode::Clipboard)
+        );
+
+        // A profile deleted since the hotkey was pressed no longer applies.
+        pipeline.inner.lock().unwrap().hotkey_profile_id = Some("deleted".to_string());
+        assert!(pipeline.active_profile().is_none());
+    }
+
+    #[test]
+    fn test_offline_mode_drops_cloud_fallback() {
+        let config = PipelineConfig {
+            stt_api_keys: HashMap::from([
+                ("groq".to_string(), "groq-key".to_string()),
+                ("openai".to_string(), "openai-key".to_string()),
+            ]),
+            fallback_stt_provider: Some("openai".to_string()),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+        pipeline.set_offline(true);
+        assert!(pipeline.is_offline());
+
+        let mut inner = pipeline.inner.lock().unwrap();
+        let primary = inner.get_or_create_stt_provider("groq", None).unwrap();
+        assert!(inner.fallback_stt_provider_for(&primary).is_none());
+
+        // Without a local model the cloud provider is kept (and will fail on its own).
+        let kept = inner.offline_stt_provider(primary.clone());
+        assert!(Arc::ptr_eq(&kept, &primary));
+    }
+
+    #[tokio::test]
+    async fn test_prepared_stt_providers_skip_changed_settings() {
+        let config = PipelineConfig {
+            stt_api_keys: HashMap::from([
+                ("openai".to_string(), "openai-key".to_string()),
+                ("deepgram".to_string(), "deepgram-key".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+
+        prepare_stt_providers(&pipeline.inner, |_| vec![("openai".to_string(), None)]).await;
+        let mut inner = pipeline.inner.lock().unwrap();
+        assert!(inner.stt_provider_cache.contains_key("openai::<default>"));
+        assert!(inner
+            .pending_stt_providers(vec![("openai".to_string(), None)])
+            .recipes
+            .is_empty());
+
+        // Providers built from settings that changed in the meantime are dropped.
+        let pending = inner.pending_stt_providers(vec![("deepgram".to_string(), None)]);
+        let generation = pending.generation;
+        inner.stt_provider_generation += 1;
+        inner.add_stt_providers(generation, pending.build());
+        assert!(!inner.stt_provider_cache.contains_key("deepgram::<default>"));
+    }
+
+    #[test]
+    fn test_warm_up_targets_follow_settings() {
+        let config = PipelineConfig {
+            stt_api_keys: HashMap::from([("groq".to_string(), "groq-key".to_string())]),
+            ..Default::default()
+        };
+        let pipeline = SharedPipeline::new(config);
+        let mut inner = pipeline.inner.lock().unwrap();
+
+        let (stt, llm) = inner.warm_up_targets();
+        assert_eq!(stt.map(|p| p.name()), Some("groq"));
+        // LLM formatting is off by default.
+        assert!(llm.is_none());
+
+        inner.offline = true;
+        let (stt, _) = inner.warm_up_targets();
+        assert!(stt.is_none());
+    }
+
+    #[test]
+    fn test_tail_chars_keeps_text_next_to_caret() {
+        assert_eq!(tail_chars("Dear Anna, über", 6), ", über");
+        assert_eq!(tail_chars("short", 500), "short");
+    }
+
+    #[test]
+    fn test_recent_transcripts_context_window() {
+        let start = Instant::now();
+        let mut recent = RecentTranscripts::default();
+        recent.push(start, "Meet Siobhan at noon.");
+        recent.push(start + Duration::from_secs(50), "  ");
+        recent.push(start + Duration::from_secs(60), "Bring the Tauri notes.");
+
+        let now = start + Duration::from_secs(90);
+        assert_eq!(
+            recent.context(now, Duration::from_secs(120)).as_deref(),
+            Some("Meet Siobhan at noon. Bring the Tauri notes.")
+        );
+        assert_eq!(
+            recent.context(now, Duration::from_secs(60)).as_deref(),
+            Some("Bring the Tauri notes.")
+        );
+        assert_eq!(recent.context(now + Duration::from_secs(600), Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn test_capture_events_map_to_auto_stop() {
+        let map = PipelineEvent::from_capture_event;
+        assert!(map(AudioCaptureEvent::SpeechEnd, false).is_none());
+        assert!(matches!(
+            map(AudioCaptureEvent::SpeechEnd, true),
+            Some(PipelineEvent::AutoStop(AutoStopReason::SpeechEnd))
+        ));
+        assert!(matches!(
+            map(AudioCaptureEvent::MaxDurationReached, false),
+            Some(PipelineEvent::AutoStop(AutoStopReason::MaxDuration))
+        ));
+        assert!(matches!(
+            map(
+                AudioCaptureEvent::DeviceLost {
+                    device: "usb".to_string(),
+                    switched_to: None,
+                },
+                false
+            ),
+            Some(PipelineEvent::AutoStop(AutoStopReason::DeviceLost(device))) if device == "usb"
+        ));
+        assert!(matches!(
+            map(
+                AudioCaptureEvent::DeviceLost {
+                    device: "usb".to_string(),
+                    switched_to: Some("built-in".to_string()),
+                },
+                true
+            ),
+            Some(PipelineEvent::InputDeviceSwitched { .. })
+        ));
+        assert!(map(AudioCaptureEvent::SpeechStart, true).is_none());
+    }
+}
@@ -0,0 +1,327 @@
+//! Per-stage latency breakdown of completed dictations.
+//!
+//! Every stored request log is turned into a `PipelineSample` (capture duration, WAV size,
+//! STT/LLM latency, total time). Typing time comes from the log when the text was output
+//! before the log was completed, and is added afterwards via `record_typing` otherwise.
+//! The last `MAX_SAMPLES` samples are kept in `pipeline_metrics.json` so providers can be
+//! compared across restarts, and `get_pipeline_metrics` aggregates them into p50/p95 per
+//! stage, overall and per provider.
+
+use crate::request_log::{RequestLog, RequestStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Samples kept (oldest are dropped first).
+const MAX_SAMPLES: usize = 1000;
+
+/// Stage timings of one request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSample {
+    pub request_id: String,
+    pub completed_at: DateTime<Utc>,
+    pub success: bool,
+    pub stt_provider: String,
+    #[serde(default)]
+    pub stt_model: Option<String>,
+    #[serde(default)]
+    pub llm_provider: Option<String>,
+    #[serde(default)]
+    pub llm_model: Option<String>,
+    #[serde(default)]
+    pub capture_ms: Option<u64>,
+    #[serde(default)]
+    pub wav_bytes: Option<u64>,
+    #[serde(default)]
+    pub stt_ms: Option<u64>,
+    #[serde(default)]
+    pub llm_ms: Option<u64>,
+    #[serde(default)]
+    pub typing_ms: Option<u64>,
+    #[serde(default)]
+    pub total_ms: Option<u64>,
+}
+
+impl PipelineSample {
+    /// `None` for requests that are still running or were cancelled.
+    fn from_log(log: &RequestLog) -> Option<Self> {
+        let success = match log.status {
+            RequestStatus::Success => true,
+            RequestStatus::Error => false,
+            RequestStatus::InProgress | RequestStatus::Cancelled => return None,
+        };
+        Some(Self {
+            request_id: log.id.clone(),
+            completed_at: log.completed_at.unwrap_or_else(Utc::now),
+            success,
+            stt_provider: log.stt_provider.clone(),
+            stt_model: log.stt_model.clone(),
+            llm_provider: log.llm_provider.clone(),
+            llm_model: log.llm_model.clone(),
+            capture_ms: log
+                .audio_duration_secs
+                .filter(|secs| secs.is_finite() && *secs > 0.0)
+                .map(|secs| (secs as f64 * 1000.0).round() as u64),
+            wav_bytes: log.audio_size_bytes.map(|b| b as u64),
+            stt_ms: log.stt_duration_ms,
+            llm_ms: log.llm_duration_ms,
+            typing_ms: log.typing_duration_ms,
+            total_ms: log.total_duration_ms,
+        })
+    }
+}
+
+/// p50/p95 of one stage.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub count: usize,
+    pub p50: u64,
+    pub p95: u64,
+    pub mean: f64,
+}
+
+impl Percentiles {
+    fn of(mut values: Vec<u64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_unstable();
+        // Nearest-rank percentile.
+        let rank = |p: f64| values[((p * values.len() as f64).ceil() as usize).clamp(1, values.len()) - 1];
+        Some(Self {
+            count: values.len(),
+            p50: rank(0.5),
+            p95: rank(0.95),
+            mean: values.iter().sum::<u64>() as f64 / values.len() as f64,
+        })
+    }
+}
+
+/// Per-stage aggregates over a set of samples.
+#[derive(Debug, Clone, Serialize)]
+pub struct StageBreakdown {
+    pub requests: usize,
+    pub errors: usize,
+    pub capture_ms: Option<Percentiles>,
+    pub wav_bytes: Option<Percentiles>,
+    pub stt_ms: Option<Percentiles>,
+    pub llm_ms: Option<Percentiles>,
+    pub typing_ms: Option<Percentiles>,
+    pub total_ms: Option<Percentiles>,
+}
+
+impl StageBreakdown {
+    fn of<'a>(samples: impl Iterator<Item = &'a PipelineSample> + Clone) -> Self {
+        let stage = |f: fn(&PipelineSample) -> Option<u64>| {
+            Percentiles::of(samples.clone().filter_map(f).collect())
+        };
+        Self {
+            requests: samples.clone().count(),
+            errors: samples.clone().filter(|s| !s.success).count(),
+            capture_ms: stage(|s| s.capture_ms),
+            wav_bytes: stage(|s| s.wav_bytes),
+            stt_ms: stage(|s| s.stt_ms),
+            llm_ms: stage(|s| s.llm_ms),
+            typing_ms: stage(|s| s.typing_ms),
+            // Failed requests stop early, so their total would skew the distribution.
+            total_ms: stage(|s| s.total_ms.filter(|_| s.success)),
+        }
+    }
+}
+
+/// Response of `get_pipeline_metrics`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineMetrics {
+    pub since: Option<DateTime<Utc>>,
+    pub overall: StageBreakdown,
+    /// Keyed by `provider` or `provider/model` (see `group_by_model`).
+    pub by_stt_provider: BTreeMap<String, StageBreakdown>,
+    pub by_llm_provider: BTreeMap<String, StageBreakdown>,
+}
+
+/// Recorded samples, persisted to disk when a path is set.
+#[derive(Debug, Default)]
+pub struct PipelineMetricsStore {
+    samples: Mutex<VecDeque<PipelineSample>>,
+    file_path: Mutex<Option<PathBuf>>,
+}
+
+/// Process-wide store fed by the request log store.
+pub fn global() -> &'static PipelineMetricsStore {
+    static STORE: OnceLock<PipelineMetricsStore> = OnceLock::new();
+    STORE.get_or_init(PipelineMetricsStore::default)
+}
+
+impl PipelineMetricsStore {
+    /// Load samples from (and persist future ones to) `<app_data_dir>/pipeline_metrics.json`.
+    pub fn load(&self, app_data_dir: PathBuf) {
+        let path = app_data_dir.join("pipeline_metrics.json");
+        let loaded: VecDeque<PipelineSample> =
+            crate::persist::read_with_recovery(&path).unwrap_or_default();
+        if let Ok(mut samples) = self.samples.lock() {
+            // Keep anything recorded before the file was loaded.
+            let recorded = std::mem::replace(&mut *samples, loaded);
+            samples.extend(recorded);
+            while samples.len() > MAX_SAMPLES {
+                samples.pop_front();
+            }
+        }
+        if let Ok(mut file_path) = self.file_path.lock() {
+            *file_path = Some(path);
+        }
+    }
+
+    fn save(&self, samples: &VecDeque<PipelineSample>) {
+        let Some(path) = self.file_path.lock().ok().and_then(|p| p.clone()) else {
+            return;
+        };
+        let result = serde_json::to_vec(samples)
+            .map_err(|e| e.to_string())
+            .and_then(|json| crate::persist::write_atomic(&path, &json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to save pipeline metrics: {}", e);
+        }
+    }
+
+    /// Record a stored request log.
+    pub fn observe_request(&self, log: &RequestLog) {
+        let Some(sample) = PipelineSample::from_log(log) else {
+            return;
+        };
+        let Ok(mut samples) = self.samples.lock() else {
+            return;
+        };
+        samples.push_back(sample);
+        while samples.len() > MAX_SAMPLES {
+            samples.pop_front();
+        }
+        self.save(&samples);
+    }
+
+    /// Add the time it took to output (type/paste) the text of `request_id`.
+    pub fn record_typing(&self, request_id: &str, elapsed: Duration) {
+        let Ok(mut samples) = self.samples.lock() else {
+            return;
+        };
+        let Some(sample) = samples.iter_mut().rev().find(|s| s.request_id == request_id) else {
+            return;
+        };
+        sample.typing_ms = Some(elapsed.as_millis() as u64);
+        self.save(&samples);
+    }
+
+    /// Aggregate samples completed at or after `since` (all when `None`).
+    pub fn summary(&self, since: Option<DateTime<Utc>>, group_by_model: bool) -> PipelineMetrics {
+        let samples: Vec<PipelineSample> = self
+            .samples
+            .lock()
+            .map(|samples| {
+                samples
+                    .iter()
+                    .filter(|s| since.is_none_or(|since| s.completed_at >= since))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let key = |provider: &str, model: &Option<String>| match model {
+            Some(model) if group_by_model => format!("{}/{}", provider, model),
+            _ => provider.to_string(),
+        };
+        let mut by_stt: BTreeMap<String, Vec<&PipelineSample>> = BTreeMap::new();
+        let mut by_llm: BTreeMap<String, Vec<&PipelineSample>> = BTreeMap::new();
+        for sample in &samples {
+            by_stt
+                .entry(key(&sample.stt_provider, &sample.stt_model))
+                .or_default()
+                .push(sample);
+            if let Some(provider) = &sample.llm_provider {
+                by_llm
+                    .entry(key(provider, &sample.llm_model))
+                    .or_default()
+                    .push(sample);
+            }
+        }
+        let breakdown = |groups: BTreeMap<String, Vec<&PipelineSample>>| {
+            groups
+                .into_iter()
+                .map(|(k, group)| (k, StageBreakdown::of(group.into_iter())))
+                .collect()
+        };
+
+        PipelineMetrics {
+            since,
+            overall: StageBreakdown::of(samples.iter()),
+            by_stt_provider: breakdown(by_stt),
+            by_llm_provider: breakdown(by_llm),
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut samples) = self.samples.lock() {
+            samples.clear();
+            self.save(&samples);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completed_log(stt_ms: u64, llm: Option<(&str, u64)>) -> RequestLog {
+        let mut log = RequestLog::new("groq".to_string(), Some("whisper-large-v3".to_string()));
+        log.audio_duration_secs = Some(2.5);
+        log.audio_size_bytes = Some(80_000);
+        log.stt_duration_ms = Some(stt_ms);
+        if let Some((provider, ms)) = llm {
+            log.llm_provider = Some(provider.to_string());
+            log.llm_duration_ms = Some(ms);
+        }
+        log.complete_success();
+        log
+    }
+
+    #[test]
+    fn test_percentiles_nearest_rank() {
+        let p = Percentiles::of((1..=100).collect()).unwrap();
+        assert_eq!((p.count, p.p50, p.p95), (100, 50, 95));
+        assert_eq!(p.mean, 50.5);
+
+        let single = Percentiles::of(vec![7]).unwrap();
+        assert_eq!((single.p50, single.p95), (7, 7));
+        assert!(Percentiles::of(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_summary_groups_by_provider_and_adds_typing() {
+        let store = PipelineMetricsStore::default();
+        for ms in [300, 500, 700] {
+            store.observe_request(&completed_log(ms, Some(("openai", ms * 2))));
+        }
+        let last = completed_log(900, None);
+        store.observe_request(&last);
+        store.record_typing(&last.id, Duration::from_millis(42));
+
+        let mut cancelled = RequestLog::new("groq".to_string(), None);
+        cancelled.complete_cancelled();
+        store.observe_request(&cancelled);
+
+        let metrics = store.summary(None, false);
+        assert_eq!(metrics.overall.requests, 4);
+        assert_eq!(metrics.overall.stt_ms.as_ref().unwrap().p50, 500);
+        assert_eq!(metrics.overall.capture_ms.as_ref().unwrap().p95, 2500);
+        assert_eq!(metrics.overall.typing_ms.as_ref().unwrap().count, 1);
+        assert_eq!(metrics.by_llm_provider["openai"].llm_ms.as_ref().unwrap().p95, 1400);
+        assert_eq!(metrics.by_stt_provider["groq"].requests, 4);
+
+        let by_model = store.summary(None, true);
+        assert!(by_model.by_stt_provider.contains_key("groq/whisper-large-v3"));
+
+        let future = store.summary(Some(Utc::now() + chrono::Duration::hours(1)), false);
+        assert_eq!(future.overall.requests, 0);
+    }
+}
@@ -0,0 +1,214 @@
+//! Provider plugins: STT and LLM providers implemented by external programs, so niche
+//! providers can be added without changing the app.
+//!
+//! Each entry of the `provider_plugins` setting makes its `id` a valid STT or LLM provider
+//! id (listed under its `label`). A plugin takes precedence over a built-in provider with
+//! the same id.
+//!
+//! The plugin program is started for every call. It reads one JSON request from stdin:
+//!
+//! ```text
+//! {"type": "transcribe", "audio_base64": "...", "mime_type": "audio/wav", "context": null, "model": null}
+//! {"type": "complete", "system_prompt": "...", "user_message": "...", "model": null}
+//! ```
+//!
+//! and prints one JSON response to stdout: `{"text": "..."}`, or `{"error": "...",
+//! "status": 503}` on failure. `status` is optional and HTTP-like: 429 and 5xx failures are
+//! retried like API errors. Timeouts are the pipeline's; a plugin that takes too long is
+//! killed.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+/// Which kind of provider a plugin implements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    Stt,
+    Llm,
+}
+
+/// An entry of the `provider_plugins` setting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderPlugin {
+    /// Provider id used in settings and profiles.
+    pub id: String,
+    /// Name shown in provider pickers (defaults to the id).
+    #[serde(default)]
+    pub label: Option<String>,
+    pub kind: PluginKind,
+    /// Program to run.
+    pub command: String,
+    /// Arguments passed to `command`.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl ProviderPlugin {
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// Plugins of the `provider_plugins` setting. Invalid entries are skipped.
+pub fn from_setting(value: Option<serde_json::Value>) -> Vec<ProviderPlugin> {
+    let Some(serde_json::Value::Array(entries)) = value else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .filter_map(|entry| match serde_json::from_value::<ProviderPlugin>(entry) {
+            Ok(plugin) if !plugin.id.trim().is_empty() && !plugin.command.trim().is_empty() => {
+                Some(plugin)
+            }
+            Ok(plugin) => {
+                log::warn!("Provider plugin {:?} has no id or command, skipped", plugin.id);
+                None
+            }
+            Err(e) => {
+                log::warn!("Invalid provider plugin, skipped: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The plugin of `kind` registered as `id`, if any.
+pub fn find<'a>(plugins: &'a [ProviderPlugin], kind: PluginKind, id: &str) -> Option<&'a ProviderPlugin> {
+    plugins.iter().find(|p| p.kind == kind && p.id == id)
+}
+
+/// `id` as a `&'static str`, for `SttProvider::name` / `LlmProvider::name`. Each distinct
+/// id is leaked once.
+pub fn static_id(id: &str) -> &'static str {
+    static IDS: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut ids = IDS.get_or_init(Default::default).lock().unwrap();
+    if let Some(existing) = ids.get(id) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(id.to_string().into_boxed_str());
+    ids.insert(leaked);
+    leaked
+}
+
+/// A request sent to a plugin.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PluginRequest<'a> {
+    Transcribe {
+        audio_base64: String,
+        mime_type: &'a str,
+        context: Option<&'a str>,
+        model: Option<&'a str>,
+    },
+    Complete {
+        system_prompt: &'a str,
+        user_message: &'a str,
+        model: Option<&'a str>,
+    },
+}
+
+impl<'a> PluginRequest<'a> {
+    pub fn transcribe(audio: &[u8], context: Option<&'a str>, model: Option<&'a str>) -> Self {
+        PluginRequest::Transcribe {
+            audio_base64: base64::engine::general_purpose::STANDARD.encode(audio),
+            mime_type: "audio/wav",
+            context,
+            model,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginResponse {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    status: Option<u16>,
+}
+
+/// Why a plugin call failed.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PluginError {
+    /// The program could not be run or did not answer with a response.
+    #[error("Plugin '{plugin}' failed: {message}")]
+    Failed { plugin: String, message: String },
+    /// The plugin answered with an error.
+    #[error("{message}")]
+    Provider { message: String, status: Option<u16> },
+}
+
+impl PluginError {
+    /// Message for an API error, starting with the status code (if any) so transient
+    /// failures are recognized by the retry policy.
+    pub fn api_message(&self) -> String {
+        match self {
+            PluginError::Provider {
+                message,
+                status: Some(status),
+            } => format!("{} {}", status, message),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Run `plugin` on `request` and return the text it answers with.
+pub async fn call(plugin: &ProviderPlugin, request: &PluginRequest<'_>) -> Result<String, PluginError> {
+    let failed = |message: String| PluginError::Failed {
+        plugin: plugin.id.clone(),
+        message,
+    };
+    let input = serde_json::to_vec(request).map_err(|e| failed(e.to_string()))?;
+    let output = crate::hooks::exec(&plugin.command, &plugin.args, &input)
+        .await
+        .map_err(failed)?;
+    parse_response(&plugin.id, &output.stdout)
+}
+
+fn parse_response(plugin: &str, stdout: &[u8]) -> Result<String, PluginError> {
+    let failed = |message: String| PluginError::Failed {
+        plugin: plugin.to_string(),
+        message,
+    };
+    let response: PluginResponse = serde_json::from_slice(stdout)
+        .map_err(|e| failed(format!("Invalid response: {}", e)))?;
+    match (response.text, response.error) {
+        (_, Some(message)) => Err(PluginError::Provider {
+            message,
+            status: response.status,
+        }),
+        (Some(text), None) => Ok(text),
+        (None, None) => Err(failed("Response has neither text nor error".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(parse_response("p", br#"{"text":"Hello."}"#), Ok("Hello.".to_string()));
+
+        let error = parse_response("p", br#"{"error":"overloaded","status":503}"#).unwrap_err();
+        assert_eq!(error.api_message(), "503 overloaded");
+        assert!(matches!(parse_response("p", b"{}"), Err(PluginError::Failed { .. })));
+        assert!(matches!(parse_response("p", b"nope"), Err(PluginError::Failed { .. })));
+
+        let plugins = from_setting(Some(serde_json::json!([
+            {"id": "my-stt", "kind": "stt", "command": "my-stt-plugin"},
+            {"id": "broken", "kind": "tts", "command": "x"},
+            {"id": "", "kind": "llm", "command": "x"},
+        ])));
+        assert_eq!(plugins.len(), 1);
+        assert!(find(&plugins, PluginKind::Stt, "my-stt").is_some());
+        assert!(find(&plugins, PluginKind::Llm, "my-stt").is_none());
+
+        let id = String::from("my-stt");
+        assert!(std::ptr::eq(static_id("my-stt"), static_id(&id)));
+    }
+}
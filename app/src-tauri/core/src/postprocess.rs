@@ -0,0 +1,335 @@
+//! Deterministic post-processing of STT transcripts.
+//!
+//! Spoken identifier commands are rewritten into code identifiers:
+//!
+//! - "camel case user name" -> `userName`
+//! - "pascal case user name" -> `UserName`
+//! - "snake case max retry count" -> `max_retry_count`
+//! - "constant case api key" (or "screaming snake case ...") -> `API_KEY`
+//! - "kebab case main window" -> `main-window`
+//!
+//! An identifier runs until the spoken terminator "end case", the next punctuation mark or
+//! line break, or `MAX_IDENTIFIER_WORDS` words, whichever comes first (longer identifiers
+//! need "end case"). A trigger that ends a sentence ("... in camel case.") is left as prose.
+//! Everything outside the rewritten spans, including line breaks, is kept as it was.
+//!
+//! LLM rewriting is unreliable at this, so it happens here, before any LLM step.
+
+/// Spoken words an identifier takes without an "end case" terminator.
+const MAX_IDENTIFIER_WORDS: usize = 4;
+
+/// How a dictated identifier is cased and joined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentifierCase {
+    Camel,
+    Pascal,
+    Snake,
+    Constant,
+    Kebab,
+}
+
+impl IdentifierCase {
+    fn from_keyword(word: &str) -> Option<Self> {
+        match word {
+            "camel" => Some(Self::Camel),
+            "pascal" => Some(Self::Pascal),
+            "snake" => Some(Self::Snake),
+            "constant" => Some(Self::Constant),
+            "kebab" => Some(Self::Kebab),
+            _ => None,
+        }
+    }
+
+    /// Join lowercase `words` into an identifier.
+    pub fn format(self, words: &[String]) -> String {
+        match self {
+            Self::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+                .collect(),
+            Self::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+            Self::Snake => words.join("_"),
+            Self::Constant => words.join("_").to_uppercase(),
+            Self::Kebab => words.join("-"),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Punctuation that ends a sentence.
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?')
+}
+
+/// A whitespace-separated token split into leading punctuation, word and trailing punctuation.
+struct Token<'a> {
+    leading: &'a str,
+    core: &'a str,
+    trailing: &'a str,
+}
+
+impl<'a> Token<'a> {
+    fn parse(raw: &'a str) -> Self {
+        let start = raw
+            .find(|c: char| c.is_alphanumeric())
+            .unwrap_or(raw.len());
+        let end = raw
+            .rfind(|c: char| c.is_alphanumeric())
+            .map(|i| i + raw[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(start);
+        Self {
+            leading: &raw[..start],
+            core: &raw[start..end],
+            trailing: &raw[end..],
+        }
+    }
+
+    fn keyword(&self) -> String {
+        self.core.to_lowercase()
+    }
+
+    /// Lowercase word parts ("user-name" -> ["user", "name"]).
+    fn words(&self) -> impl Iterator<Item = String> + 'a {
+        self.core
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+    }
+
+    fn ends_identifier(&self) -> bool {
+        !self.trailing.is_empty()
+    }
+
+    fn ends_sentence(&self) -> bool {
+        self.trailing.chars().any(is_sentence_end)
+    }
+}
+
+/// Match an identifier command at `tokens[0..]`, returning its case and the number of
+/// tokens the trigger phrase spans.
+fn match_trigger(tokens: &[Token]) -> Option<(IdentifierCase, usize)> {
+    let (style, len) = match_trigger_words(tokens)?;
+    // STT often puts a comma after the trigger; a full stop means it was prose.
+    (!tokens[len - 1].ends_sentence()).then_some((style, len))
+}
+
+fn match_trigger_words(tokens: &[Token]) -> Option<(IdentifierCase, usize)> {
+    let first = tokens.first()?.keyword();
+    let keyword_at = |i: usize| tokens.get(i).map(Token::keyword);
+
+    // Single-token forms: "camelcase", "camel-case", "snake_case".
+    let compact: String = first.chars().filter(|c| c.is_alphanumeric()).collect();
+    if let Some(style) = compact.strip_suffix("case").and_then(IdentifierCase::from_keyword) {
+        return Some((style, 1));
+    }
+
+    if first == "screaming"
+        && keyword_at(1).as_deref() == Some("snake")
+        && keyword_at(2).as_deref() == Some("case")
+    {
+        return Some((IdentifierCase::Constant, 3));
+    }
+
+    let style = IdentifierCase::from_keyword(&first)?;
+    (keyword_at(1).as_deref() == Some("case")).then_some((style, 2))
+}
+
+/// Rewrite spoken identifier commands in `text` (see the module docs).
+///
+/// Text without identifier commands is returned unchanged.
+pub fn apply_identifier_casing(text: &str) -> String {
+    // Whitespace-separated tokens with their byte offsets in `text`.
+    let spans: Vec<(usize, &str)> = text
+        .split_whitespace()
+        .map(|raw| (raw.as_ptr() as usize - text.as_ptr() as usize, raw))
+        .collect();
+    let tokens: Vec<Token> = spans.iter().map(|(_, raw)| Token::parse(raw)).collect();
+    let span_end = |k: usize| spans[k].0 + spans[k].1.len();
+    let line_break_before = |k: usize| text[span_end(k - 1)..spans[k].0].contains('\n');
+
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let Some((style, trigger_len)) = match_trigger(&tokens[i..]) else {
+            i += 1;
+            continue;
+        };
+
+        // Scan to "end case", punctuation or the end of the line; `capped` is where the
+        // identifier ends if the scan found no terminator and ran past the word cap.
+        let mut words = Vec::new();
+        let mut trailing = "";
+        let mut terminated = false;
+        let mut capped = None;
+        let mut j = i + trigger_len;
+        while j < tokens.len() && !line_break_before(j) {
+            let token = &tokens[j];
+            if token.keyword() == "end"
+                && tokens.get(j + 1).map(Token::keyword).as_deref() == Some("case")
+                && !line_break_before(j + 1)
+            {
+                trailing = tokens[j + 1].trailing;
+                terminated = true;
+                j += 2;
+                break;
+            }
+            if j - (i + trigger_len) == MAX_IDENTIFIER_WORDS && capped.is_none() {
+                capped = Some((j, words.len()));
+            }
+            words.extend(token.words());
+            j += 1;
+            if token.ends_identifier() {
+                trailing = token.trailing;
+                break;
+            }
+        }
+        if let (false, Some((cap, word_count))) = (terminated, capped) {
+            j = cap;
+            words.truncate(word_count);
+            trailing = "";
+        }
+
+        if words.is_empty() {
+            // A bare "camel case" is just prose; leave it alone.
+            i += 1;
+            continue;
+        }
+
+        out.push_str(&text[copied..spans[i].0]);
+        out.push_str(tokens[i].leading);
+        out.push_str(&style.format(&words));
+        out.push_str(trailing);
+        copied = span_end(j - 1);
+        i = j;
+    }
+
+    if copied == 0 {
+        return text.to_string();
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_examples() {
+        assert_eq!(apply_identifier_casing("camel case user name"), "userName");
+        assert_eq!(apply_identifier_casing("snake case max retry count"), "max_retry_count");
+        assert_eq!(apply_identifier_casing("constant case api key"), "API_KEY");
+    }
+
+    #[test]
+    fn test_all_styles() {
+        let words: Vec<String> = ["http", "request", "id"].iter().map(|w| w.to_string()).collect();
+        assert_eq!(IdentifierCase::Camel.format(&words), "httpRequestId");
+        assert_eq!(IdentifierCase::Pascal.format(&words), "HttpRequestId");
+        assert_eq!(IdentifierCase::Snake.format(&words), "http_request_id");
+        assert_eq!(IdentifierCase::Constant.format(&words), "HTTP_REQUEST_ID");
+        assert_eq!(IdentifierCase::Kebab.format(&words), "http-request-id");
+        assert_eq!(apply_identifier_casing("pascal case main window"), "MainWindow");
+        assert_eq!(apply_identifier_casing("kebab case main window"), "main-window");
+        assert_eq!(apply_identifier_casing("screaming snake case max size"), "MAX_SIZE");
+    }
+
+    #[test]
+    fn test_stt_capitalization_and_trigger_punctuation() {
+        assert_eq!(apply_identifier_casing("Camel case, User Name."), "userName.");
+        assert_eq!(apply_identifier_casing("Snake-case retry count"), "retry_count");
+        assert_eq!(apply_identifier_casing("CamelCase user name"), "userName");
+        assert_eq!(apply_identifier_casing("snake_case user id"), "user_id");
+    }
+
+    #[test]
+    fn test_identifier_ends_at_punctuation() {
+        assert_eq!(
+            apply_identifier_casing("Set camel case user name, then save."),
+            "Set userName, then save."
+        );
+        assert_eq!(
+            apply_identifier_casing("Is snake case retry count? Yes."),
+            "Is retry_count? Yes."
+        );
+    }
+
+    #[test]
+    fn test_end_case_terminator() {
+        assert_eq!(
+            apply_identifier_casing("rename camel case user name end case to the new one"),
+            "rename userName to the new one"
+        );
+        assert_eq!(
+            apply_identifier_casing("snake case user id end case."),
+            "user_id."
+        );
+    }
+
+    #[test]
+    fn test_multiple_identifiers_and_leading_punctuation() {
+        assert_eq!(
+            apply_identifier_casing("map (camel case user id) to constant case user key."),
+            "map (userId) to USER_KEY."
+        );
+    }
+
+    #[test]
+    fn test_words_are_split_and_digits_kept() {
+        assert_eq!(apply_identifier_casing("camel case user-name 2"), "userName2");
+        assert_eq!(apply_identifier_casing("snake case base64 encoder"), "base64_encoder");
+    }
+
+    #[test]
+    fn test_text_without_commands_is_unchanged() {
+        let text = "In this case,  the camel  was fine.";
+        assert_eq!(apply_identifier_casing(text), text);
+        assert_eq!(apply_identifier_casing(""), "");
+    }
+
+    #[test]
+    fn test_bare_trigger_is_left_alone() {
+        assert_eq!(apply_identifier_casing("use camel case."), "use camel case.");
+        assert_eq!(
+            apply_identifier_casing("Write it in snake case. Then commit."),
+            "Write it in snake case. Then commit."
+        );
+        assert_eq!(apply_identifier_casing("snake case"), "snake case");
+        assert_eq!(apply_identifier_casing("snake case end case"), "snake case end case");
+    }
+
+    #[test]
+    fn test_identifier_stops_at_line_break_and_keeps_layout() {
+        assert_eq!(
+            apply_identifier_casing("camel case user name\nnext line"),
+            "userName\nnext line"
+        );
+        let text = "Intro  text.\n\n- snake case retry count\n- camel case user id end case\n  done";
+        assert_eq!(
+            apply_identifier_casing(text),
+            "Intro  text.\n\n- retry_count\n- userId\n  done"
+        );
+    }
+
+    #[test]
+    fn test_identifier_without_terminator_is_capped() {
+        assert_eq!(
+            apply_identifier_casing("camel case max retry count per user and then we stop"),
+            "maxRetryCountPer user and then we stop"
+        );
+        assert_eq!(
+            apply_identifier_casing("camel case max retry count per user end case is set"),
+            "maxRetryCountPerUser is set"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Client-side rate limiting for STT and LLM provider calls.
+//!
+//! Each provider gets a token bucket (`requests_per_minute`, refilled continuously, up to
+//! `burst` requests at once), and a global semaphore caps requests in flight across all
+//! providers. Rapid-fire dictations and batch jobs queue here instead of tripping the
+//! provider's own limits.
+//!
+//! When a provider still answers with a rate-limit error, its bucket is blocked for as long
+//! as the server's `Retry-After` asks (or a default cool-down), so the retry (see
+//! `crate::retry`) and any concurrent requests wait for it instead of hammering the API.
+//!
+//! Providers are wrapped by the pipeline (`RateLimitedSttProvider`, `RateLimitedLlmProvider`);
+//! local and mock providers are not limited.
+
+use crate::llm::{LlmError, LlmProvider};
+use crate::retry::is_rate_limit_message;
+use crate::stt::{AudioFormat, SttError, SttProvider, Transcript};
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How long a provider is left alone after a rate limit without `Retry-After`.
+pub const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// Limits for provider calls (stored as `rate_limit`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Sustained requests per minute to each provider (0 = unlimited)
+    pub requests_per_minute: u32,
+    /// Requests allowed back to back before the per-minute rate applies
+    pub burst: u32,
+    /// Requests in flight across all providers (0 = unlimited)
+    pub max_in_flight: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl RateLimitConfig {
+    const DEFAULT: Self = Self {
+        requests_per_minute: 60,
+        burst: 10,
+        max_in_flight: 4,
+    };
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+    /// Set after a rate-limit response; nothing is sent before it.
+    blocked_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(config: &RateLimitConfig, now: Instant) -> Self {
+        Self {
+            tokens: config.burst.max(1) as f64,
+            last_refill: now,
+            blocked_until: None,
+        }
+    }
+
+    /// Take a token, or return how long to wait before trying again.
+    fn try_take(&mut self, config: &RateLimitConfig, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.blocked_until {
+            if now < until {
+                return Some(until - now);
+            }
+            self.blocked_until = None;
+        }
+        if config.requests_per_minute == 0 {
+            return None;
+        }
+
+        let per_sec = config.requests_per_minute as f64 / 60.0;
+        let capacity = config.burst.max(1) as f64;
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec).min(capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / per_sec))
+        }
+    }
+
+    fn block_for(&mut self, duration: Duration, now: Instant) {
+        let until = now + duration;
+        let until = self.blocked_until.map_or(until, |current| current.max(until));
+        self.blocked_until = Some(until);
+        // Refill from zero once the cool-down is over.
+        self.tokens = 0.0;
+        self.last_refill = until;
+    }
+}
+
+struct LimiterState {
+    config: RateLimitConfig,
+    buckets: BTreeMap<String, TokenBucket>,
+    /// Created lazily and replaced by `configure` (requests holding the old one finish
+    /// normally).
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+static LIMITER: Mutex<LimiterState> = Mutex::new(LimiterState {
+    config: RateLimitConfig::DEFAULT,
+    buckets: BTreeMap::new(),
+    in_flight: None,
+});
+
+fn state() -> std::sync::MutexGuard<'static, LimiterState> {
+    LIMITER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Switch to new limits. Existing buckets keep their cool-downs.
+pub fn configure(config: RateLimitConfig) {
+    let mut state = state();
+    if state.config != config {
+        state.config = config;
+        state.in_flight = None;
+    }
+}
+
+/// Held for the duration of a provider request.
+pub struct RatePermit {
+    _in_flight: OwnedSemaphorePermit,
+}
+
+/// Wait until a request to `key` is allowed.
+pub async fn acquire(key: &str) -> RatePermit {
+    loop {
+        let wait = {
+            let mut state = state();
+            let now = Instant::now();
+            let config = state.config;
+            state
+                .buckets
+                .entry(key.to_string())
+                .or_insert_with(|| TokenBucket::new(&config, now))
+                .try_take(&config, now)
+        };
+        match wait {
+            None => break,
+            Some(delay) => {
+                log::debug!("Rate limit: waiting {:?} before calling {}", delay, key);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+
+    // Only take an in-flight slot once this provider may be called, so a throttled
+    // provider doesn't hold up the others.
+    let semaphore = {
+        let mut state = state();
+        let max = match state.config.max_in_flight {
+            0 => Semaphore::MAX_PERMITS,
+            n => n as usize,
+        };
+        state
+            .in_flight
+            .get_or_insert_with(|| Arc::new(Semaphore::new(max)))
+            .clone()
+    };
+    let in_flight = semaphore
+        .acquire_owned()
+        .await
+        .expect("rate limit semaphore is never closed");
+
+    RatePermit {
+        _in_flight: in_flight,
+    }
+}
+
+/// Hold requests to `key` for `duration` after the provider reported a rate limit.
+pub fn throttle(key: &str, duration: Duration) {
+    let mut state = state();
+    let now = Instant::now();
+    let config = state.config;
+    log::warn!("Rate limit: {} is throttled for {:?}", key, duration);
+    state
+        .buckets
+        .entry(key.to_string())
+        .or_insert_with(|| TokenBucket::new(&config, now))
+        .block_for(duration, now);
+}
+
+/// STT provider whose requests go through the rate limiter.
+pub struct RateLimitedSttProvider {
+    inner: Arc<dyn SttProvider>,
+    key: String,
+}
+
+impl RateLimitedSttProvider {
+    pub fn wrap(inner: Arc<dyn SttProvider>) -> Arc<dyn SttProvider> {
+        let key = format!("stt:{}", inner.name());
+        Arc::new(Self { inner, key })
+    }
+
+    fn observe<T>(&self, result: Result<T, SttError>) -> Result<T, SttError> {
+        match &result {
+            Err(SttError::RateLimited { retry_after, .. }) => {
+                throttle(&self.key, retry_after.unwrap_or(RATE_LIMIT_COOLDOWN));
+            }
+            Err(SttError::Api(msg)) if is_rate_limit_message(msg) => {
+                throttle(&self.key, RATE_LIMIT_COOLDOWN);
+            }
+            _ => {}
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl SttProvider for RateLimitedSttProvider {
+    async fn transcribe(&self, audio: Bytes, format: &AudioFormat) -> Result<String, SttError> {
+        let _permit = acquire(&self.key).await;
+        self.observe(self.inner.transcribe(audio, format).await)
+    }
+
+    async fn transcribe_with_context(
+        &self,
+        audio: Bytes,
+        format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<String, SttError> {
+        let _permit = acquire(&self.key).await;
+        self.observe(self.inner.transcribe_with_context(audio, format, context).await)
+    }
+
+    async fn transcribe_detailed(
+        &self,
+        audio: Bytes,
+        format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<Transcript, SttError> {
+        let _permit = acquire(&self.key).await;
+        self.observe(self.inner.transcribe_detailed(audio, format, context).await)
+    }
+
+    async fn warm_up(&self) -> Result<(), SttError> {
+        // Not an API call, so it doesn't count against the limits.
+        self.inner.warm_up().await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+/// LLM provider whose requests go through the rate limiter.
+pub struct RateLimitedLlmProvider {
+    inner: Arc<dyn LlmProvider>,
+    key: String,
+}
+
+impl RateLimitedLlmProvider {
+    pub fn wrap(inner: Arc<dyn LlmProvider>) -> Arc<dyn LlmProvider> {
+        let key = format!("llm:{}", inner.name());
+        Arc::new(Self { inner, key })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RateLimitedLlmProvider {
+    async fn complete(&self, system_prompt: &str, user_message: &str) -> Result<String, LlmError> {
+        let _permit = acquire(&self.key).await;
+        let result = self.inner.complete(system_prompt, user_message).await;
+        match &result {
+            Err(LlmError::RateLimited { retry_after, .. }) => {
+                throttle(&self.key, retry_after.unwrap_or(RATE_LIMIT_COOLDOWN));
+            }
+            Err(LlmError::Api(msg)) if is_rate_limit_message(msg) => {
+                throttle(&self.key, RATE_LIMIT_COOLDOWN);
+            }
+            _ => {}
+        }
+        result
+    }
+
+    async fn warm_up(&self) -> Result<(), LlmError> {
+        self.inner.warm_up().await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_burst_then_rate() {
+        let config = RateLimitConfig {
+            requests_per_minute: 60,
+            burst: 2,
+            max_in_flight: 0,
+        };
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(&config, start);
+
+        assert_eq!(bucket.try_take(&config, start), None);
+        assert_eq!(bucket.try_take(&config, start), None);
+        // Burst used up: one request per second from here.
+        assert_eq!(bucket.try_take(&config, start), Some(Duration::from_secs(1)));
+        assert_eq!(bucket.try_take(&config, start + Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn test_throttled_bucket_waits_for_cooldown() {
+        let config = RateLimitConfig::default();
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(&config, start);
+
+        bucket.block_for(Duration::from_secs(5), start);
+        assert_eq!(
+            bucket.try_take(&config, start + Duration::from_secs(2)),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(bucket.try_take(&config, start + Duration::from_secs(6)), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! Crash recovery for recordings that were being transcribed.
+//!
+//! When a transcription starts, its audio is written to `<app data>/recovery/<id>.wav`
+//! followed by a `<id>.json` marker, and both are deleted when the transcription ends
+//! (successfully or not; failed recordings are kept by `RecordingStore` as usual). Markers
+//! still on disk at the next launch belong to transcriptions the app never finished, and the
+//! UI offers to transcribe them again into history or to discard them.
+//!
+//! Nothing is written while the store is disabled (`set_enabled(false)`); the app disables it
+//! when the user chose to never keep recordings on disk.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+const RECOVERY_DIR: &str = "recovery";
+
+/// A transcription that was in flight, as recorded in its marker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrphanedRecording {
+    pub id: String,
+    /// Request log of the lost transcription (the log itself was only kept in memory).
+    pub request_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub duration_secs: f32,
+    pub stt_provider: String,
+}
+
+#[derive(Debug, Default)]
+struct RecoveryState {
+    dir: Option<PathBuf>,
+    enabled: bool,
+    /// Ids whose markers were left by a previous run.
+    orphans: HashSet<String>,
+}
+
+/// In-flight markers and the audio they point at.
+#[derive(Debug, Default)]
+pub struct RecoveryStore {
+    state: Mutex<RecoveryState>,
+}
+
+/// Process-wide store used by the pipeline.
+pub fn global() -> &'static RecoveryStore {
+    static STORE: OnceLock<RecoveryStore> = OnceLock::new();
+    STORE.get_or_init(RecoveryStore::default)
+}
+
+fn is_safe_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn marker_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+fn wav_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.wav", id))
+}
+
+impl RecoveryStore {
+    /// Use `<app_data_dir>/recovery`; markers already there are orphans from a previous run.
+    pub fn load(&self, app_data_dir: &Path, enabled: bool) {
+        let dir = app_data_dir.join(RECOVERY_DIR);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            log::warn!("Failed to create {}: {}", dir.display(), e);
+        }
+        let orphans: HashSet<String> = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect();
+        // Audio whose marker was never written (the app exited in between).
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let stem = path.file_stem().and_then(|stem| stem.to_str());
+            if path.extension().and_then(|ext| ext.to_str()) == Some("wav")
+                && !stem.is_some_and(|stem| orphans.contains(stem))
+            {
+                let _ = fs::remove_file(&path);
+            }
+        }
+        if !orphans.is_empty() {
+            log::warn!(
+                "{} transcription(s) were interrupted by the last exit and can be recovered",
+                orphans.len()
+            );
+        }
+        if let Ok(mut state) = self.state.lock() {
+            state.dir = Some(dir);
+            state.enabled = enabled;
+            state.orphans = orphans;
+        }
+    }
+
+    /// Turn writing on or off (off when recordings must never be written to disk).
+    pub fn set_enabled(&self, enabled: bool) {
+        if let Ok(mut state) = self.state.lock() {
+            state.enabled = enabled;
+        }
+    }
+
+    fn dir(&self) -> Option<PathBuf> {
+        self.state.lock().ok()?.dir.clone()
+    }
+
+    /// Persist `wav` and the marker of a transcription that is starting. Returns the id to
+    /// pass to `finish`, or `None` if nothing was written.
+    pub fn begin(
+        &self,
+        request_id: Option<String>,
+        stt_provider: &str,
+        duration_secs: f32,
+        wav: &[u8],
+    ) -> Option<String> {
+        let dir = {
+            let state = self.state.lock().ok()?;
+            if !state.enabled {
+                return None;
+            }
+            state.dir.clone()?
+        };
+        let marker = OrphanedRecording {
+            id: uuid::Uuid::new_v4().to_string(),
+            request_id,
+            started_at: Utc::now(),
+            duration_secs,
+            stt_provider: stt_provider.to_string(),
+        };
+        // The marker goes last, so every marker has its complete audio.
+        let result = crate::at_rest::seal(wav)
+            .and_then(|sealed| {
+                fs::write(wav_path(&dir, &marker.id), sealed).map_err(|e| e.to_string())
+            })
+            .and_then(|()| serde_json::to_vec(&marker).map_err(|e| e.to_string()))
+            .and_then(|json| {
+                fs::write(marker_path(&dir, &marker.id), json).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => Some(marker.id),
+            Err(e) => {
+                log::warn!("Failed to save in-flight recording for recovery: {}", e);
+                let _ = fs::remove_file(wav_path(&dir, &marker.id));
+                None
+            }
+        }
+    }
+
+    /// Delete the marker and audio of `id` (a finished transcription, or a recovered or
+    /// discarded orphan).
+    pub fn finish(&self, id: &str) {
+        let Some(dir) = self.dir() else {
+            return;
+        };
+        if !is_safe_id(id) {
+            return;
+        }
+        let _ = fs::remove_file(marker_path(&dir, id));
+        let _ = fs::remove_file(wav_path(&dir, id));
+        if let Ok(mut state) = self.state.lock() {
+            state.orphans.remove(id);
+        }
+    }
+
+    /// Transcriptions interrupted by the previous exit, oldest first. Orphans whose audio
+    /// is missing or unreadable are dropped.
+    pub fn orphans(&self) -> Vec<OrphanedRecording> {
+        let Some(dir) = self.dir() else {
+            return Vec::new();
+        };
+        let ids: Vec<String> = self
+            .state
+            .lock()
+            .map(|state| state.orphans.iter().cloned().collect())
+            .unwrap_or_default();
+
+        let mut orphans = Vec::new();
+        for id in ids {
+            let marker = fs::read(marker_path(&dir, &id))
+                .ok()
+                .and_then(|json| serde_json::from_slice::<OrphanedRecording>(&json).ok())
+                .filter(|marker| marker.id == id && wav_path(&dir, &id).exists());
+            match marker {
+                Some(marker) => orphans.push(marker),
+                None => {
+                    log::warn!("Dropping unreadable in-flight recording {}", id);
+                    self.finish(&id);
+                }
+            }
+        }
+        orphans.sort_by_key(|orphan| orphan.started_at);
+        orphans
+    }
+
+    /// Audio of orphan `id`.
+    pub fn load_wav(&self, id: &str) -> Result<Vec<u8>, String> {
+        let is_orphan = self
+            .state
+            .lock()
+            .map(|state| state.orphans.contains(id))
+            .unwrap_or(false);
+        if !is_orphan {
+            return Err(format!("No interrupted recording {}", id));
+        }
+        let dir = self.dir().ok_or("Recovery store is not loaded")?;
+        let path = wav_path(&dir, id);
+        let contents = fs::read(&path)
+            .map_err(|e| format!("Failed to read recording {}: {}", path.display(), e))?;
+        crate::at_rest::open(contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfinished_markers_become_orphans_on_next_load() {
+        let app_data =
+            std::env::temp_dir().join(format!("tangerine-recovery-{}", uuid::Uuid::new_v4()));
+
+        let first_run = RecoveryStore::default();
+        first_run.load(&app_data, true);
+        let finished = first_run.begin(None, "groq", 1.0, b"RIFF-a").unwrap();
+        let interrupted = first_run
+            .begin(Some("req-1".to_string()), "groq", 2.5, b"RIFF-b")
+            .unwrap();
+        first_run.finish(&finished);
+        // A transcription of this run is not an orphan.
+        assert!(first_run.orphans().is_empty());
+
+        let second_run = RecoveryStore::default();
+        second_run.load(&app_data, true);
+        let orphans = second_run.orphans();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].id, interrupted);
+        assert_eq!(orphans[0].request_id.as_deref(), Some("req-1"));
+        assert_eq!(second_run.load_wav(&interrupted).unwrap(), b"RIFF-b");
+        assert!(second_run.load_wav(&finished).is_err());
+
+        second_run.finish(&interrupted);
+        assert!(second_run.orphans().is_empty());
+        second_run.set_enabled(false);
+        assert_eq!(second_run.begin(None, "groq", 1.0, b"RIFF"), None);
+
+        let _ = fs::remove_dir_all(app_data);
+    }
+}
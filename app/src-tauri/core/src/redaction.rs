@@ -0,0 +1,317 @@
+//! PII redaction for transcripts leaving the machine.
+//!
+//! Before a transcript is sent to a cloud LLM, emails, phone numbers, card numbers (and,
+//! optionally, person names and user-defined patterns) are replaced with numbered
+//! placeholders such as `[EMAIL_1]`. The formatted text coming back has the placeholders
+//! swapped for the original values again, so the user still gets their own text pasted.
+//! Request logs of redacted requests are scrubbed the same way before they are stored.
+//!
+//! Name detection is a small local heuristic (cue phrases like "my name is" and honorifics
+//! followed by capitalized words), not a statistical NER model, so it is off by default.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// The `pii_redaction` setting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Global default; profiles can override it with `redact_pii`.
+    pub enabled: bool,
+    pub emails: bool,
+    pub phone_numbers: bool,
+    pub card_numbers: bool,
+    /// Heuristic person-name detection.
+    pub person_names: bool,
+    /// Extra regular expressions to mask (matches become `[REDACTED_n]`).
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emails: true,
+            phone_numbers: true,
+            card_numbers: true,
+            person_names: false,
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PiiKind {
+    Card,
+    Email,
+    Phone,
+    Name,
+    Custom,
+}
+
+impl PiiKind {
+    fn label(self) -> &'static str {
+        match self {
+            PiiKind::Card => "CARD",
+            PiiKind::Email => "EMAIL",
+            PiiKind::Phone => "PHONE",
+            PiiKind::Name => "NAME",
+            PiiKind::Custom => "REDACTED",
+        }
+    }
+}
+
+/// Compiled detectors for one `RedactionConfig`.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    /// In priority order: earlier detectors win overlapping matches.
+    detectors: Vec<(PiiKind, Regex)>,
+}
+
+/// A redacted text plus what is needed to undo it.
+#[derive(Debug, Clone, Default)]
+pub struct Redaction {
+    pub text: String,
+    /// `(placeholder, original)` pairs.
+    replacements: Vec<(String, String)>,
+}
+
+impl Redaction {
+    pub fn is_empty(&self) -> bool {
+        self.replacements.is_empty()
+    }
+
+    /// Put the original values back into `text` (e.g. the LLM's formatted output).
+    pub fn restore(&self, text: &str) -> String {
+        self.replacements
+            .iter()
+            .fold(text.to_string(), |acc, (placeholder, original)| {
+                acc.replace(placeholder.as_str(), original)
+            })
+    }
+}
+
+impl Redactor {
+    pub fn new(config: &RedactionConfig) -> Self {
+        let builtin = |pattern: &str| Regex::new(pattern).expect("built-in PII pattern");
+        let mut detectors = Vec::new();
+
+        if config.card_numbers {
+            detectors.push((PiiKind::Card, builtin(r"\b\d(?:[ -]?\d){12,18}\b")));
+        }
+        if config.emails {
+            detectors.push((
+                PiiKind::Email,
+                builtin(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b"),
+            ));
+        }
+        if config.phone_numbers {
+            detectors.push((
+                PiiKind::Phone,
+                builtin(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)[ .-]?)?\d{2,4}(?:[ .-]?\d{2,4}){1,4}\b"),
+            ));
+        }
+        if config.person_names {
+            detectors.push((
+                PiiKind::Name,
+                builtin(
+                    r"(?:\b(?:[Mm]y name is|[Tt]his is|[Cc]all|[Aa]sk|[Tt]ell)\s+|\b(?:Mr|Mrs|Ms|Dr|Prof)\.?\s+)([A-Z][a-z]+(?:\s+[A-Z][a-z]+){0,2})",
+                ),
+            ));
+        }
+        for pattern in &config.custom_patterns {
+            match Regex::new(pattern) {
+                Ok(re) => detectors.push((PiiKind::Custom, re)),
+                Err(e) => log::warn!("Ignoring invalid redaction pattern '{}': {}", pattern, e),
+            }
+        }
+
+        Self { detectors }
+    }
+
+    /// Spans to mask, sorted and non-overlapping.
+    fn find_spans(&self, text: &str) -> Vec<(usize, usize, PiiKind)> {
+        let mut spans: Vec<(usize, usize, PiiKind)> = Vec::new();
+
+        for (kind, re) in &self.detectors {
+            for caps in re.captures_iter(text) {
+                // Name patterns capture just the name, not the cue phrase.
+                let Some(m) = caps.get(1).or_else(|| caps.get(0)) else {
+                    continue;
+                };
+                if !is_valid_match(*kind, m.as_str()) {
+                    continue;
+                }
+                let overlaps = spans
+                    .iter()
+                    .any(|&(start, end, _)| m.start() < end && start < m.end());
+                if !overlaps {
+                    spans.push((m.start(), m.end(), *kind));
+                }
+            }
+        }
+
+        spans.sort_by_key(|&(start, _, _)| start);
+        spans
+    }
+
+    /// Replace PII in `text` with numbered placeholders; the same value always gets the
+    /// same placeholder.
+    pub fn redact(&self, text: &str) -> Redaction {
+        let mut redaction = Redaction::default();
+        redaction.text = self.redact_with(text, &mut redaction);
+        redaction
+    }
+
+    /// Like `redact`, but reuses and extends the placeholders of `redaction`, so several
+    /// texts (e.g. session segments) can be restored with one `Redaction`.
+    pub fn redact_with(&self, text: &str, redaction: &mut Redaction) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+
+        for (start, end, kind) in self.find_spans(text) {
+            let original = &text[start..end];
+            let existing = redaction
+                .replacements
+                .iter()
+                .find(|(_, o)| o == original)
+                .map(|(placeholder, _)| placeholder.clone());
+            let placeholder = existing.unwrap_or_else(|| {
+                let prefix = format!("[{}_", kind.label());
+                let n = redaction
+                    .replacements
+                    .iter()
+                    .filter(|(p, _)| p.starts_with(&prefix))
+                    .count()
+                    + 1;
+                let placeholder = format!("{}{}]", prefix, n);
+                redaction
+                    .replacements
+                    .push((placeholder.clone(), original.to_string()));
+                placeholder
+            });
+
+            out.push_str(&text[last..start]);
+            out.push_str(&placeholder);
+            last = end;
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    /// `redact` without keeping the originals (for logs).
+    pub fn scrub(&self, text: &str) -> String {
+        self.redact(text).text
+    }
+
+    /// Scrub every string inside a JSON value.
+    pub fn scrub_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) => *s = self.scrub(s),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.scrub_json(v)),
+            serde_json::Value::Object(map) => map.values_mut().for_each(|v| self.scrub_json(v)),
+            _ => {}
+        }
+    }
+}
+
+fn is_valid_match(kind: PiiKind, matched: &str) -> bool {
+    let digits: Vec<u32> = matched.chars().filter_map(|c| c.to_digit(10)).collect();
+    match kind {
+        PiiKind::Card => luhn_valid(&digits),
+        // Shorter runs are usually times, amounts or version numbers.
+        PiiKind::Phone => (7..=15).contains(&digits.len()) && !looks_like_date(matched),
+        _ => true,
+    }
+}
+
+fn looks_like_date(matched: &str) -> bool {
+    let parts: Vec<&str> = matched.split(['-', '.', ' ']).collect();
+    parts.len() == 3 && parts[0].len() == 4 && parts[1].len() <= 2 && parts[2].len() <= 2
+}
+
+fn luhn_valid(digits: &[u32]) -> bool {
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_detectors() -> Redactor {
+        Redactor::new(&RedactionConfig {
+            enabled: true,
+            person_names: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_masks_and_restores_pii() {
+        let redactor = all_detectors();
+        let text = "Email jane.doe@example.com or call +1 415-555-0132, card 4111 1111 1111 1111.";
+        let redaction = redactor.redact(text);
+
+        assert_eq!(
+            redaction.text,
+            "Email [EMAIL_1] or call [PHONE_1], card [CARD_1]."
+        );
+        assert_eq!(redaction.restore(&redaction.text), text);
+    }
+
+    #[test]
+    fn test_leaves_ordinary_numbers_alone() {
+        let redactor = all_detectors();
+        for text in [
+            "Meet at 10:30 on 2024-05-01",
+            "It costs 1,250 dollars",
+            "Version 2.4.1 is out",
+        ] {
+            assert_eq!(redactor.scrub(text), text);
+        }
+    }
+
+    #[test]
+    fn test_names_and_repeated_values() {
+        let redactor = all_detectors();
+        let redaction =
+            redactor.redact("Hi, my name is John Smith. Mail bob@x.io, then bob@x.io again.");
+        assert_eq!(
+            redaction.text,
+            "Hi, my name is [NAME_1]. Mail [EMAIL_1], then [EMAIL_1] again."
+        );
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let redactor = Redactor::new(&RedactionConfig {
+            emails: false,
+            custom_patterns: vec![r"\bACME-\d+\b".to_string(), "(".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            redactor.scrub("Ticket ACME-4411 from a@b.co"),
+            "Ticket [REDACTED_1] from a@b.co"
+        );
+    }
+}
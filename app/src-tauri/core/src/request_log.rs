@@ -7,16 +7,31 @@
 //! - Timing information
 //! - Errors if any
 
+use crate::redaction::Redactor;
+use crate::stt::TranscriptSegment;
 use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::VecDeque;
+use std::future::Future;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 /// Default number of request logs to keep (matches UI default)
 const DEFAULT_MAX_LOGS: usize = 10;
 
+/// Maximum number of control API audit entries kept in memory.
+const MAX_API_AUDIT_ENTRIES: usize = 500;
+
+/// In-progress requests whose transcription runs in the background (see
+/// `RequestLogStore::scope_current`). Older ones are finalized as cancelled.
+const MAX_DETACHED_LOGS: usize = 8;
+
+tokio::task_local! {
+    /// Request the running task logs to, instead of the store's current request.
+    static SCOPED_REQUEST: String;
+}
+
 /// Defensive hard cap for request logs kept in memory.
 ///
 /// Even when using time-based retention, we don't want unbounded growth.
@@ -82,6 +97,9 @@ pub struct RequestLog {
     pub stt_provider: String,
     /// STT model used
     pub stt_model: Option<String>,
+    /// Primary STT provider that failed before `stt_provider` took over as the fallback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stt_fallback_from: Option<String>,
     /// LLM provider used (if formatting enabled)
     pub llm_provider: Option<String>,
     /// LLM model used
@@ -92,6 +110,9 @@ pub struct RequestLog {
     pub audio_size_bytes: Option<usize>,
     /// Sample rate of the audio
     pub sample_rate: Option<u32>,
+    /// Fingerprint of the submitted audio (see `crate::fingerprint`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_fingerprint: Option<String>,
     /// Raw transcript from STT
     pub raw_transcript: Option<String>,
     /// Formatted transcript from LLM (if used)
@@ -107,6 +128,12 @@ pub struct RequestLog {
     /// JSON response received from STT provider (if available).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stt_response_json: Option<JsonValue>,
+    /// Language the STT provider detected (verbose responses only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stt_language: Option<String>,
+    /// Timed segments of the raw transcript (verbose responses only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stt_segments: Vec<TranscriptSegment>,
 
     /// Payload sent to LLM provider (if LLM rewrite attempted).
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -126,6 +153,12 @@ pub struct RequestLog {
     pub stt_duration_ms: Option<u64>,
     /// LLM duration in milliseconds
     pub llm_duration_ms: Option<u64>,
+    /// Time spent typing/pasting the output, when it happened before the log was completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typing_duration_ms: Option<u64>,
+    /// Set when PII redaction applies to this request; the log is scrubbed before it's stored.
+    #[serde(skip)]
+    pub redactor: Option<Redactor>,
 }
 
 /// Status of a request
@@ -151,15 +184,19 @@ impl RequestLog {
             completed_at: None,
             stt_provider,
             stt_model,
+            stt_fallback_from: None,
             llm_provider: None,
             llm_model: None,
             audio_duration_secs: None,
             audio_size_bytes: None,
             sample_rate: None,
+            audio_fingerprint: None,
             raw_transcript: None,
             formatted_transcript: None,
             stt_request_json: None,
             stt_response_json: None,
+            stt_language: None,
+            stt_segments: Vec::new(),
             llm_request_json: None,
             llm_response_json: None,
             status: RequestStatus::InProgress,
@@ -168,6 +205,8 @@ impl RequestLog {
             total_duration_ms: None,
             stt_duration_ms: None,
             llm_duration_ms: None,
+            typing_duration_ms: None,
+            redactor: None,
         }
     }
 
@@ -208,6 +247,58 @@ impl RequestLog {
         self.log(LogLevel::Info, message, Some(details.into()));
     }
 
+    /// Fill the audio size, sample rate and duration from the WAV submitted for transcription.
+    pub fn set_audio(&mut self, wav: &[u8]) {
+        self.audio_size_bytes = Some(wav.len());
+        if let Some((sample_rate, secs)) = crate::audio_capture::wav_sample_rate_and_duration(wav) {
+            self.sample_rate = Some(sample_rate);
+            self.audio_duration_secs = Some(secs);
+        }
+    }
+
+    /// Mask PII in the transcripts, payloads and messages of this log (see `crate::redaction`).
+    pub fn scrub_pii(&mut self, redactor: &Redactor) {
+        for text in [
+            &mut self.raw_transcript,
+            &mut self.formatted_transcript,
+            &mut self.error_message,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *text = redactor.scrub(text);
+        }
+        for segment in &mut self.stt_segments {
+            segment.text = redactor.scrub(&segment.text);
+        }
+        for json in [
+            &mut self.stt_request_json,
+            &mut self.stt_response_json,
+            &mut self.llm_request_json,
+            &mut self.llm_response_json,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            redactor.scrub_json(json);
+        }
+        for entry in &mut self.entries {
+            entry.message = redactor.scrub(&entry.message);
+            if let Some(details) = &mut entry.details {
+                *details = redactor.scrub(details);
+            }
+        }
+    }
+
+    /// Copy of this log that is safe to hand out (scrubbed if redaction applies).
+    fn scrubbed(&self) -> RequestLog {
+        let mut log = self.clone();
+        if let Some(redactor) = log.redactor.take() {
+            log.scrub_pii(&redactor);
+        }
+        log
+    }
+
     /// Mark request as complete with success
     pub fn complete_success(&mut self) {
         self.completed_at = Some(Utc::now());
@@ -240,12 +331,30 @@ impl RequestLog {
     }
 }
 
+/// Audit record for a single control API request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiAuditEntry {
+    pub timestamp: DateTime<Utc>,
+    /// ID of the token used (None if the token was missing/unknown)
+    pub token_id: Option<String>,
+    /// Name of the token used (None if the token was missing/unknown)
+    pub token_name: Option<String>,
+    /// Action requested, e.g. "start_recording" or "get_history"
+    pub action: String,
+    pub allowed: bool,
+    /// Reason for a denial
+    pub error: Option<String>,
+}
+
 /// Thread-safe request log store
 #[derive(Debug, Clone)]
 pub struct RequestLogStore {
     logs: Arc<Mutex<VecDeque<RequestLog>>>,
     current: Arc<Mutex<Option<RequestLog>>>,
+    /// Requests detached from `current` while their transcription finishes.
+    detached: Arc<Mutex<VecDeque<RequestLog>>>,
     retention: Arc<Mutex<RequestLogsRetentionConfig>>,
+    api_audit: Arc<Mutex<VecDeque<ApiAuditEntry>>>,
 }
 
 impl Default for RequestLogStore {
@@ -270,7 +379,9 @@ impl RequestLogStore {
         Self {
             logs: Arc::new(Mutex::new(VecDeque::with_capacity(initial_capacity))),
             current: Arc::new(Mutex::new(None)),
+            detached: Arc::new(Mutex::new(VecDeque::new())),
             retention: Arc::new(Mutex::new(retention)),
+            api_audit: Arc::new(Mutex::new(VecDeque::new())),
         }
     }
 
@@ -334,24 +445,89 @@ impl RequestLogStore {
     }
 
     /// Get the current request log for modification
+    ///
+    /// Inside `scope_current`, this is the scoped request rather than the newest one.
     pub fn with_current<F, R>(&self, f: F) -> Option<R>
     where
         F: FnOnce(&mut RequestLog) -> R,
     {
+        if let Ok(id) = SCOPED_REQUEST.try_with(String::clone) {
+            let mut detached = self.detached.lock().unwrap();
+            return detached.iter_mut().find(|log| log.id == id).map(f);
+        }
         let mut current = self.current.lock().unwrap();
         current.as_mut().map(f)
     }
 
     /// Complete the current request and store it
     pub fn complete_current(&self) {
+        if let Ok(id) = SCOPED_REQUEST.try_with(String::clone) {
+            let log = {
+                let mut detached = self.detached.lock().unwrap();
+                let index = detached.iter().position(|log| log.id == id);
+                index.and_then(|i| detached.remove(i))
+            };
+            if let Some(log) = log {
+                self.store_log(log);
+            }
+            return;
+        }
         let mut current = self.current.lock().unwrap();
         if let Some(log) = current.take() {
             self.store_log(log);
         }
     }
 
+    /// Run `fut` against the current request, detached from the store.
+    ///
+    /// The request stops being current right away, so a new recording can start its own
+    /// while `fut` is still transcribing; `with_current`/`complete_current` inside `fut`
+    /// keep reaching this request. Anything `fut` leaves unfinished is finalized like an
+    /// abandoned request.
+    pub async fn scope_current<F: Future>(&self, fut: F) -> F::Output {
+        let Some(id) = self.detach_current() else {
+            return fut.await;
+        };
+        let output = SCOPED_REQUEST.scope(id.clone(), fut).await;
+        SCOPED_REQUEST
+            .scope(id, async {
+                self.with_current(|log| {
+                    if log.status == RequestStatus::InProgress {
+                        log.complete_cancelled();
+                    }
+                });
+                self.complete_current();
+            })
+            .await;
+        output
+    }
+
+    /// Move the current request to `detached`; returns its id.
+    fn detach_current(&self) -> Option<String> {
+        let log = self.current.lock().unwrap().take()?;
+        let id = log.id.clone();
+        let overflow = {
+            let mut detached = self.detached.lock().unwrap();
+            detached.push_back(log);
+            let excess = detached.len().saturating_sub(MAX_DETACHED_LOGS);
+            detached.drain(..excess).collect::<Vec<_>>()
+        };
+        for mut log in overflow {
+            log.complete_cancelled();
+            self.store_log(log);
+        }
+        Some(id)
+    }
+
     /// Store a completed log
-    fn store_log(&self, log: RequestLog) {
+    fn store_log(&self, mut log: RequestLog) {
+        if let Some(redactor) = log.redactor.take() {
+            log.scrub_pii(&redactor);
+        }
+
+        crate::metrics::global().observe_request(&log);
+        crate::pipeline_metrics::global().observe_request(&log);
+
         let mut logs = self.logs.lock().unwrap();
         logs.push_back(log);
 
@@ -364,13 +540,15 @@ impl RequestLogStore {
         self.prune();
 
         let logs = self.logs.lock().unwrap();
+        let detached = self.detached.lock().unwrap();
         let current = self.current.lock().unwrap();
 
         let mut result: Vec<RequestLog> = logs.iter().cloned().collect();
+        result.extend(detached.iter().map(RequestLog::scrubbed));
 
         // Add current request if exists
         if let Some(ref c) = *current {
-            result.push(c.clone());
+            result.push(c.scrubbed());
         }
 
         // Reverse to get most recent first
@@ -388,6 +566,38 @@ impl RequestLogStore {
         let mut logs = self.logs.lock().unwrap();
         logs.clear();
     }
+
+    /// Record a control API request in the audit trail.
+    ///
+    /// Allowed requests are also noted on the in-progress request log (if any), so a
+    /// recording triggered through the API shows who triggered it.
+    pub fn record_api_access(&self, entry: ApiAuditEntry) {
+        if entry.allowed {
+            self.with_current(|log| {
+                log.info(format!(
+                    "Control API: {} (token '{}')",
+                    entry.action,
+                    entry.token_name.as_deref().unwrap_or("unknown")
+                ));
+            });
+        }
+
+        let mut audit = self.api_audit.lock().unwrap();
+        audit.push_back(entry);
+        while audit.len() > MAX_API_AUDIT_ENTRIES {
+            audit.pop_front();
+        }
+    }
+
+    /// Get control API audit entries (most recent first)
+    pub fn get_api_audit(&self, limit: Option<usize>) -> Vec<ApiAuditEntry> {
+        let audit = self.api_audit.lock().unwrap();
+        let mut result: Vec<ApiAuditEntry> = audit.iter().rev().cloned().collect();
+        if let Some(limit) = limit {
+            result.truncate(limit);
+        }
+        result
+    }
 }
 
 #[cfg(test)]
@@ -439,4 +649,102 @@ mod tests {
         assert_eq!(logs[0].id, id2); // Most recent first
         assert_eq!(logs[1].id, id1);
     }
+
+    #[tokio::test]
+    async fn test_scoped_request_outlives_a_newer_request() {
+        let store = RequestLogStore::new();
+        let first = store.start_request("groq".to_string(), None);
+
+        store
+            .scope_current(async {
+                // A new recording starts while the first one is still transcribing.
+                let second = store.start_request("groq".to_string(), None);
+                assert_ne!(second, first);
+
+                store.with_current(|log| {
+                    log.info("STT completed");
+                    log.complete_success();
+                });
+                store.complete_current();
+            })
+            .await;
+
+        let logs = store.get_logs(None);
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].status, RequestStatus::InProgress);
+        assert_eq!(logs[1].id, first);
+        assert_eq!(logs[1].status, RequestStatus::Success);
+    }
+
+    #[tokio::test]
+    async fn test_scope_finalizes_an_unfinished_request() {
+        let store = RequestLogStore::new();
+        store.start_request("groq".to_string(), None);
+
+        store.scope_current(async {}).await;
+
+        let logs = store.get_logs(None);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].status, RequestStatus::Cancelled);
+        assert!(store.with_current(|_| ()).is_none());
+    }
+
+    #[test]
+    fn test_redacted_request_is_scrubbed_when_stored() {
+        let store = RequestLogStore::new();
+        store.start_request("groq".to_string(), None);
+        store.with_current(|log| {
+            log.redactor = Some(Redactor::new(&crate::redaction::RedactionConfig::default()));
+            log.raw_transcript = Some("mail me at jane@example.com".to_string());
+            log.stt_response_json =
+                Some(serde_json::json!({ "text": "mail me at jane@example.com" }));
+            log.complete_success();
+        });
+
+        // The in-flight log is scrubbed when handed out too.
+        assert_eq!(
+            store.get_logs(None)[0].raw_transcript.as_deref(),
+            Some("mail me at [EMAIL_1]")
+        );
+
+        store.complete_current();
+        let log = &store.get_logs(None)[0];
+        assert_eq!(log.raw_transcript.as_deref(), Some("mail me at [EMAIL_1]"));
+        assert_eq!(
+            log.stt_response_json,
+            Some(serde_json::json!({ "text": "mail me at [EMAIL_1]" }))
+        );
+    }
+
+    #[test]
+    fn test_api_audit_is_recorded_on_current_request() {
+        let store = RequestLogStore::new();
+        store.start_request("groq".to_string(), None);
+
+        store.record_api_access(ApiAuditEntry {
+            timestamp: Utc::now(),
+            token_id: Some("t1".to_string()),
+            token_name: Some("Stream Deck".to_string()),
+            action: "start_recording".to_string(),
+            allowed: true,
+            error: None,
+        });
+        store.record_api_access(ApiAuditEntry {
+            timestamp: Utc::now(),
+            token_id: Some("t1".to_string()),
+            token_name: Some("Stream Deck".to_string()),
+            action: "get_history".to_string(),
+            allowed: false,
+            error: Some("missing scope".to_string()),
+        });
+
+        let audit = store.get_api_audit(None);
+        assert_eq!(audit.len(), 2);
+        assert_eq!(audit[0].action, "get_history");
+        assert!(!audit[0].allowed);
+
+        let current = store.get_logs(Some(1)).remove(0);
+        assert_eq!(current.entries.len(), 1);
+        assert!(current.entries[0].message.contains("start_recording"));
+    }
 }
@@ -0,0 +1,355 @@
+//! Retry policy with exponential backoff, shared by STT and LLM requests and model downloads.
+//!
+//! Error types opt in by implementing `RetryableError`, which decides whether a failure is
+//! worth repeating; `RetryConfig` decides how often and how long to wait. When the server
+//! says how long to wait (`Retry-After`), that wins over the backoff.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Configuration for retry behavior
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Maximum number of retry attempts
+    pub max_retries: u32,
+    /// Initial delay before first retry (doubles with each attempt)
+    pub initial_delay: Duration,
+    /// Maximum delay between retries
+    pub max_delay: Duration,
+    /// Whether to retry on rate limit errors
+    pub retry_on_rate_limit: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            retry_on_rate_limit: true,
+        }
+    }
+}
+
+/// How long a caller is willing to wait for a transcription.
+///
+/// Selected by the pipeline entry point and applied on top of the configured `RetryConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetryProfile {
+    /// The user is waiting to paste: at most one quick retry, then fail fast.
+    #[default]
+    Interactive,
+    /// Nobody is waiting (batch/file transcription, retries of saved audio):
+    /// keep the full exponential backoff.
+    Background,
+}
+
+/// Longest server-requested wait that is still worth retrying after. A provider asking for
+/// more (e.g. a daily quota reset) fails immediately instead.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Upper bound for the single retry delay of interactive requests.
+const INTERACTIVE_MAX_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+impl RetryProfile {
+    /// Derive the effective retry config for this profile from the configured one.
+    pub fn apply(self, base: &RetryConfig) -> RetryConfig {
+        match self {
+            RetryProfile::Interactive => RetryConfig {
+                max_retries: base.max_retries.min(1),
+                initial_delay: base.initial_delay.min(INTERACTIVE_MAX_RETRY_DELAY),
+                max_delay: base.max_delay.min(INTERACTIVE_MAX_RETRY_DELAY),
+                // Waiting out a rate limit is never "fast".
+                retry_on_rate_limit: false,
+            },
+            RetryProfile::Background => base.clone(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Create a new retry config with custom max retries
+    pub fn with_max_retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Default::default()
+        }
+    }
+
+    /// Calculate the delay for a given attempt number (0-indexed)
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let delay = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt));
+        std::cmp::min(delay, self.max_delay)
+    }
+
+    /// Delay before retrying attempt `attempt`: the server's `retry_after` if it sent one,
+    /// otherwise the backoff. `None` if the server wants us to wait longer than is worth it.
+    pub fn next_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        match retry_after {
+            Some(wait) if wait > MAX_RETRY_AFTER => None,
+            Some(wait) => Some(wait),
+            None => Some(self.delay_for_attempt(attempt)),
+        }
+    }
+}
+
+/// An error that knows whether the failed operation is worth repeating.
+pub trait RetryableError: std::fmt::Display {
+    /// Whether another attempt could succeed where this one failed, under `config`.
+    fn is_retryable(&self, config: &RetryConfig) -> bool;
+
+    /// How long the server asked us to wait before the next attempt, if it said.
+    fn retry_after(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Parse the wait a response asks for: `retry-after-ms` (OpenAI), or `Retry-After` as
+/// seconds or an HTTP date.
+pub fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        if ms.is_finite() && ms >= 0.0 {
+            return Some(Duration::from_secs_f64(ms / 1000.0));
+        }
+    }
+
+    let value = header("retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Whether an API error message describes a rate limit (HTTP 429).
+pub fn is_rate_limit_message(msg: &str) -> bool {
+    let lower = msg.to_lowercase();
+    msg.contains("429") || lower.contains("rate limit") || lower.contains("too many requests")
+}
+
+/// Whether an API error message describes a transient server-side failure (5xx) or, if
+/// `config` allows it, a rate limit.
+pub fn is_transient_api_message(msg: &str, config: &RetryConfig) -> bool {
+    let server_error = ["500", "502", "503", "504"].iter().any(|code| msg.contains(code))
+        || msg.to_lowercase().contains("overloaded");
+    server_error || (config.retry_on_rate_limit && is_rate_limit_message(msg))
+}
+
+/// Run `operation`, retrying retryable errors up to `config.max_retries` times with
+/// exponential backoff, or after the server-requested delay. `label` names the request in
+/// logs.
+pub async fn retry<F, Fut, T, E>(config: &RetryConfig, label: &str, operation: F) -> Result<T, E>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: RetryableError,
+{
+    retry_cancellable(config, label, &CancellationToken::new(), operation)
+        .await
+        .unwrap_or_else(|| unreachable!("uncancelled token"))
+}
+
+/// Like `retry`, but gives up as soon as `cancel` fires: the attempt in progress is dropped
+/// (which aborts its HTTP request) and no further attempts are made.
+///
+/// Returns `None` if cancelled.
+pub async fn retry_cancellable<F, Fut, T, E>(
+    config: &RetryConfig,
+    label: &str,
+    cancel: &CancellationToken,
+    operation: F,
+) -> Option<Result<T, E>>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: RetryableError,
+{
+    let mut attempt = 0;
+    loop {
+        let result = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => None,
+            result = operation() => Some(result),
+        };
+        let Some(result) = result else {
+            log::info!("{} request cancelled", label);
+            return None;
+        };
+
+        match result {
+            Ok(result) => return Some(Ok(result)),
+            Err(e) => {
+                if !e.is_retryable(config) || attempt >= config.max_retries {
+                    return Some(Err(e));
+                }
+
+                let Some(delay) = config.next_delay(attempt, e.retry_after()) else {
+                    log::warn!(
+                        "{} request failed, not retrying (server asked to wait {:?}): {}",
+                        label,
+                        e.retry_after().unwrap_or_default(),
+                        e
+                    );
+                    return Some(Err(e));
+                };
+                log::warn!(
+                    "{} request failed (attempt {}/{}), retrying in {:?}: {}",
+                    label,
+                    attempt + 1,
+                    config.max_retries + 1,
+                    delay,
+                    e
+                );
+
+                tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => {
+                        log::info!("{} request cancelled, skipping retry", label);
+                        return None;
+                    }
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_calculation() {
+        let config = RetryConfig::default();
+
+        // Initial delay: 500ms
+        assert_eq!(config.delay_for_attempt(0), Duration::from_millis(500));
+        // Second attempt: 1000ms
+        assert_eq!(config.delay_for_attempt(1), Duration::from_millis(1000));
+        // Third attempt: 2000ms
+        assert_eq!(config.delay_for_attempt(2), Duration::from_millis(2000));
+        // Fourth attempt: 4000ms
+        assert_eq!(config.delay_for_attempt(3), Duration::from_millis(4000));
+    }
+
+    #[test]
+    fn test_max_delay_capping() {
+        let config = RetryConfig {
+            max_delay: Duration::from_secs(2),
+            ..Default::default()
+        };
+
+        // Should cap at max_delay
+        assert_eq!(config.delay_for_attempt(10), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retry_profiles() {
+        let base = RetryConfig::default();
+
+        let interactive = RetryProfile::Interactive.apply(&base);
+        assert_eq!(interactive.max_retries, 1);
+        assert_eq!(interactive.delay_for_attempt(0), Duration::from_millis(250));
+        assert!(!interactive.retry_on_rate_limit);
+
+        let background = RetryProfile::Background.apply(&base);
+        assert_eq!(background.max_retries, base.max_retries);
+        assert_eq!(background.delay_for_attempt(3), Duration::from_secs(4));
+
+        // Never *more* retries than configured.
+        let none = RetryProfile::Interactive.apply(&RetryConfig::with_max_retries(0));
+        assert_eq!(none.max_retries, 0);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+
+        // The millisecond header is more precise, so it wins.
+        headers.insert("retry-after-ms", HeaderValue::from_static("1500"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_millis(1500)));
+
+        let mut dated = HeaderMap::new();
+        dated.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&dated), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_retry_after_overrides_backoff() {
+        let config = RetryConfig::default();
+        assert_eq!(config.next_delay(0, None), Some(Duration::from_millis(500)));
+        // The server's wait is used as is, even above `max_delay`...
+        assert_eq!(
+            config.next_delay(0, Some(Duration::from_secs(20))),
+            Some(Duration::from_secs(20))
+        );
+        // ...unless it is too long to be worth waiting for.
+        assert_eq!(config.next_delay(0, Some(Duration::from_secs(3600))), None);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_skips_pending_retries() {
+        #[derive(Debug)]
+        struct Unavailable;
+
+        impl std::fmt::Display for Unavailable {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "503 Service Unavailable")
+            }
+        }
+
+        impl RetryableError for Unavailable {
+            fn is_retryable(&self, _config: &RetryConfig) -> bool {
+                true
+            }
+        }
+
+        let config = RetryConfig {
+            initial_delay: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let cancel = CancellationToken::new();
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        // The first attempt fails and the user cancels: no 60s wait, no second attempt.
+        let result: Option<Result<(), Unavailable>> =
+            retry_cancellable(&config, "test", &cancel, || async {
+                attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                cancel.cancel();
+                Err(Unavailable)
+            })
+            .await;
+        assert!(result.is_none());
+        assert_eq!(attempts.into_inner(), 1);
+    }
+
+    #[test]
+    fn test_is_transient_api_message() {
+        let config = RetryConfig::default();
+        assert!(is_transient_api_message("503 Service Unavailable", &config));
+        assert!(is_transient_api_message("429 Rate limit exceeded", &config));
+        assert!(!is_transient_api_message("401 Unauthorized", &config));
+
+        let no_rate_limit = RetryConfig {
+            retry_on_rate_limit: false,
+            ..Default::default()
+        };
+        assert!(!is_transient_api_message("Too many requests", &no_rate_limit));
+    }
+}
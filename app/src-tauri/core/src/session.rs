@@ -0,0 +1,136 @@
+//! Multi-segment dictation sessions ("append mode").
+//!
+//! When enabled, recordings that start within a short window after the previous one are
+//! collected into a single session instead of being output one by one. Once the window
+//! passes without a new recording, the segments are merged (by the rewrite LLM when it is
+//! available, otherwise with `join_segments`) and output as one message.
+
+use std::sync::Mutex;
+
+/// Default time window (seconds) in which a new recording continues the current session.
+pub const DEFAULT_SESSION_WINDOW_SECS: f64 = 10.0;
+
+#[derive(Default)]
+struct SessionInner {
+    segments: Vec<String>,
+    /// Bumped on every append so that only the most recent flush timer owns the session.
+    generation: u64,
+}
+
+/// Pending segments of the current dictation session.
+#[derive(Default)]
+pub struct DictationSession {
+    inner: Mutex<SessionInner>,
+}
+
+impl DictationSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a transcribed segment and return the session generation it belongs to.
+    pub fn append(&self, text: String) -> u64 {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.segments.push(text);
+        inner.generation = inner.generation.wrapping_add(1);
+        inner.generation
+    }
+
+    /// Number of segments collected so far.
+    pub fn segment_count(&self) -> usize {
+        self.inner
+            .lock()
+            .map(|inner| inner.segments.len())
+            .unwrap_or(0)
+    }
+
+    /// Take all pending segments, but only if nothing was appended since `generation`.
+    ///
+    /// Returns `None` when a newer segment exists (its own timer will flush the session)
+    /// or when the session is empty.
+    pub fn take_if_current(&self, generation: u64) -> Option<Vec<String>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.generation != generation || inner.segments.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut inner.segments))
+    }
+}
+
+/// Join session segments without an LLM.
+///
+/// Segments are trimmed and separated by a single space; a segment that ended with an
+/// explicit line break keeps it so dictated "new line"/"new paragraph" survive the join.
+pub fn join_segments(segments: &[String]) -> String {
+    let mut out = String::new();
+
+    for segment in segments {
+        let trimmed = segment.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push(' ');
+        }
+        out.push_str(trimmed);
+
+        // Preserve trailing line breaks (up to a paragraph break).
+        let trailing_newlines = segment
+            .trim_end_matches([' ', '\t'])
+            .chars()
+            .rev()
+            .take_while(|c| *c == '\n' || *c == '\r')
+            .filter(|c| *c == '\n')
+            .count();
+        for _ in 0..trailing_newlines.min(2) {
+            out.push('\n');
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_if_current_only_for_latest_generation() {
+        let session = DictationSession::new();
+        let first = session.append("Hello there,".to_string());
+        let second = session.append("how are you?".to_string());
+
+        assert_eq!(session.segment_count(), 2);
+        assert!(session.take_if_current(first).is_none());
+        assert_eq!(
+            session.take_if_current(second),
+            Some(vec!["Hello there,".to_string(), "how are you?".to_string()])
+        );
+        assert_eq!(session.segment_count(), 0);
+        assert!(session.take_if_current(second).is_none());
+    }
+
+    #[test]
+    fn test_join_segments_spacing() {
+        let segments = vec![
+            " Hi team, ".to_string(),
+            String::new(),
+            "the release is ready.".to_string(),
+        ];
+        assert_eq!(join_segments(&segments), "Hi team, the release is ready.");
+    }
+
+    #[test]
+    fn test_join_segments_keeps_line_breaks() {
+        let segments = vec![
+            "Dear Sam,\n\n".to_string(),
+            "Thanks for the update.\n".to_string(),
+            "Best".to_string(),
+        ];
+        assert_eq!(
+            join_segments(&segments),
+            "Dear Sam,\n\nThanks for the update.\nBest"
+        );
+    }
+}
@@ -1,7 +1,8 @@
 //! Deepgram STT provider implementation.
 
-use super::{AudioFormat, SttError, SttProvider};
+use super::{iso639_1_language, with_extra_params, AudioFormat, ExtraParams, SttError, SttProvider};
 use async_trait::async_trait;
+use bytes::Bytes;
 use crate::request_log::RequestLogStore;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use reqwest::Url;
@@ -13,23 +14,47 @@ pub struct DeepgramSttProvider {
     client: reqwest::Client,
     api_key: String,
     model: String,
+    /// Terms to boost (see `with_keywords`).
+    keywords: Vec<String>,
+    /// Language code (`language` parameter); `None` for Deepgram's default (English)
+    language: Option<String>,
+    /// Extra query parameters (see `with_extra_params`).
+    extra_params: ExtraParams,
     request_log_store: Option<RequestLogStore>,
 }
 
 impl DeepgramSttProvider {
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
     /// Build the Deepgram /v1/listen URL with required query parameters.
     ///
     /// We always enable `smart_format=true` for all Deepgram calls to improve
     /// readability (e.g., numerals/date formatting), and we keep `punctuate=true`
-    /// enabled for clean transcripts.
+    /// enabled for clean transcripts, unless the extra params say otherwise.
     fn listen_url(&self) -> Result<Url, SttError> {
         let mut url = Url::parse("https://api.deepgram.com/v1/listen")
             .map_err(|e| SttError::Config(format!("Invalid Deepgram base URL: {}", e)))?;
 
+        let mut params = vec![
+            ("model", self.model.clone()),
+            ("smart_format", "true".to_string()),
+            ("punctuate", "true".to_string()),
+        ];
+        if let Some(language) = &self.language {
+            params.push(("language", language.clone()));
+        }
         url.query_pairs_mut()
-            .append_pair("model", &self.model)
-            .append_pair("smart_format", "true")
-            .append_pair("punctuate", "true");
+            .extend_pairs(with_extra_params(params, &self.extra_params));
+
+        // Nova-3 replaced `keywords` with `keyterm` prompting.
+        let keyword_param = if self.model.starts_with("nova-3") {
+            "keyterm"
+        } else {
+            "keywords"
+        };
+        for keyword in &self.keywords {
+            url.query_pairs_mut().append_pair(keyword_param, keyword);
+        }
 
         Ok(url)
     }
@@ -40,15 +65,13 @@ impl DeepgramSttProvider {
     /// * `api_key` - Deepgram API key
     /// * `model` - Model to use (e.g., "nova-2")
     pub fn new(api_key: String, model: Option<String>) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(60))
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
-            client,
+            client: crate::http::shared_client(),
             api_key,
             model: model.unwrap_or_else(|| "nova-2".to_string()),
+            keywords: Vec::new(),
+            language: None,
+            extra_params: ExtraParams::new(),
             request_log_store: None,
         }
     }
@@ -60,6 +83,9 @@ impl DeepgramSttProvider {
             client,
             api_key,
             model: model.unwrap_or_else(|| "nova-2".to_string()),
+            keywords: Vec::new(),
+            language: None,
+            extra_params: ExtraParams::new(),
             request_log_store: None,
         }
     }
@@ -68,11 +94,30 @@ impl DeepgramSttProvider {
         self.request_log_store = store;
         self
     }
+
+    /// Boost recognition of these terms (e.g. names from the personal dictionary).
+    pub fn with_keywords(mut self, keywords: Vec<String>) -> Self {
+        self.keywords = keywords;
+        self
+    }
+
+    /// Dictation language (e.g. "de"); `None` for Deepgram's default.
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language.as_deref().and_then(iso639_1_language);
+        self
+    }
+
+    /// Extra query parameters (e.g. `diarize=true`); these replace the built-in
+    /// `smart_format`/`punctuate` defaults when they share a name.
+    pub fn with_extra_params(mut self, extra_params: ExtraParams) -> Self {
+        self.extra_params = extra_params;
+        self
+    }
 }
 
 #[async_trait]
 impl SttProvider for DeepgramSttProvider {
-    async fn transcribe(&self, audio: &[u8], _format: &AudioFormat) -> Result<String, SttError> {
+    async fn transcribe(&self, audio: Bytes, _format: &AudioFormat) -> Result<String, SttError> {
         if let Some(store) = &self.request_log_store {
             let url = self.listen_url()?;
             let request_json = json!({
@@ -110,21 +155,24 @@ impl SttProvider for DeepgramSttProvider {
             .client
             .post(url)
             .headers(headers)
-            .body(audio.to_vec())
+            .timeout(Self::REQUEST_TIMEOUT)
+            .body(audio)
             .send()
             .await
             .map_err(|e| if e.is_timeout() { SttError::Timeout } else { SttError::Network(e) })?;
 
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = crate::retry::parse_retry_after(response.headers());
             let error_text = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(SttError::Api(format!(
-                "Deepgram API error ({}): {}",
-                status, error_text
-            )));
+            return Err(SttError::from_status(
+                status,
+                retry_after,
+                format!("Deepgram API error ({}): {}", status, error_text),
+            ));
         }
 
         let result: serde_json::Value = response.json().await?;
@@ -148,6 +196,16 @@ impl SttProvider for DeepgramSttProvider {
         Ok(text)
     }
 
+    async fn warm_up(&self) -> Result<(), SttError> {
+        let url = self.listen_url()?;
+        crate::network::preconnect(&self.client, url.as_str()).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        self.listen_url().ok().map(String::from)
+    }
+
     fn name(&self) -> &'static str {
         "deepgram"
     }
@@ -169,4 +227,42 @@ mod tests {
         let provider = DeepgramSttProvider::new("test-key".to_string(), Some("nova-2-general".to_string()));
         assert_eq!(provider.model, "nova-2-general");
     }
+
+    #[test]
+    fn test_keywords_in_listen_url() {
+        let keywords = vec!["Tauri".to_string(), "Pipe cat".to_string()];
+        let provider = DeepgramSttProvider::new("test-key".to_string(), None)
+            .with_keywords(keywords.clone());
+        let url = provider.listen_url().unwrap();
+        assert!(url.as_str().ends_with("&keywords=Tauri&keywords=Pipe+cat"));
+
+        let provider = DeepgramSttProvider::new("test-key".to_string(), Some("nova-3".to_string()))
+            .with_keywords(keywords);
+        assert!(provider.listen_url().unwrap().as_str().contains("keyterm=Tauri"));
+    }
+
+    #[test]
+    fn test_language_in_listen_url() {
+        let provider = DeepgramSttProvider::new("test-key".to_string(), None)
+            .with_language(Some("cmn".to_string()));
+        assert!(provider.listen_url().unwrap().as_str().contains("&language=zh"));
+
+        let provider = DeepgramSttProvider::new("test-key".to_string(), None);
+        assert!(!provider.listen_url().unwrap().as_str().contains("language="));
+    }
+
+    #[test]
+    fn test_extra_params_in_listen_url() {
+        let provider = DeepgramSttProvider::new("test-key".to_string(), None).with_extra_params(
+            ExtraParams::from([
+                ("smart_format".to_string(), "false".to_string()),
+                ("diarize".to_string(), "true".to_string()),
+            ]),
+        );
+        let url = provider.listen_url().unwrap();
+        assert_eq!(
+            url.query(),
+            Some("model=nova-2&punctuate=true&diarize=true&smart_format=false")
+        );
+    }
 }
@@ -0,0 +1,316 @@
+//! Deepgram live endpointing (`Endpointing::Provider`).
+//!
+//! While recording, the captured audio is streamed to Deepgram's websocket API with interim
+//! results on, and the recording auto-stops on the first `speech_final` result (Deepgram
+//! detected the end of an utterance) instead of the local VAD's `SpeechEnd`. The recording
+//! itself is still transcribed by the configured provider once it stops. The interim
+//! transcript is kept for live captions (`take_partial`).
+//!
+//! If the stream can't be opened or fails midway, `is_active` turns false and the local VAD
+//! takes over again.
+
+use super::SttError;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::Connector;
+
+const LISTEN_URL: &str = "wss://api.deepgram.com/v1/listen";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often captured audio is sent.
+const SEND_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The part of a `Results` message endpointing needs.
+#[derive(Debug, Deserialize)]
+struct LiveResult {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    is_final: bool,
+    #[serde(default)]
+    speech_final: bool,
+    #[serde(default)]
+    channel: Option<LiveChannel>,
+}
+
+impl LiveResult {
+    fn transcript(&self) -> &str {
+        self.channel
+            .as_ref()
+            .and_then(|channel| channel.alternatives.first())
+            .map_or("", |alternative| alternative.transcript.trim())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LiveChannel {
+    #[serde(default)]
+    alternatives: Vec<LiveAlternative>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LiveAlternative {
+    #[serde(default)]
+    transcript: String,
+}
+
+/// Whether a websocket text message ends the utterance: a `speech_final` result with some
+/// speech in it (Deepgram also finalizes stretches of silence).
+fn is_speech_final(message: &str) -> bool {
+    let Ok(result) = serde_json::from_str::<LiveResult>(message) else {
+        return false;
+    };
+    result.kind == "Results" && result.speech_final && !result.transcript().is_empty()
+}
+
+/// Transcript of the stream so far: finalized segments plus the latest interim one, which
+/// Deepgram revises until it marks it `is_final`.
+#[derive(Debug, Default)]
+struct LiveTranscript {
+    finalized: String,
+    interim: String,
+    changed: bool,
+}
+
+impl LiveTranscript {
+    fn apply(&mut self, message: &str) {
+        let Ok(result) = serde_json::from_str::<LiveResult>(message) else {
+            return;
+        };
+        if result.kind != "Results" {
+            return;
+        }
+        let text = result.transcript();
+        if result.is_final {
+            if !text.is_empty() {
+                if !self.finalized.is_empty() {
+                    self.finalized.push(' ');
+                }
+                self.finalized.push_str(text);
+            }
+            self.interim.clear();
+        } else {
+            self.interim = text.to_string();
+        }
+        self.changed = true;
+    }
+
+    fn take(&mut self) -> Option<String> {
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+        Some(format!("{} {}", self.finalized, self.interim).trim().to_string())
+    }
+}
+
+/// Interleaved float samples as 16-bit little-endian PCM (`encoding=linear16`).
+fn to_linear16(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|&s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+/// Audio format and model of a live stream.
+#[derive(Debug, Clone)]
+pub struct LiveStreamConfig {
+    pub api_key: String,
+    /// `None` for Deepgram's default (as `DeepgramSttProvider`)
+    pub model: Option<String>,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Silence (ms) after which Deepgram ends an utterance
+    pub endpointing_ms: u32,
+}
+
+impl LiveStreamConfig {
+    fn url(&self) -> Result<reqwest::Url, SttError> {
+        let mut url = reqwest::Url::parse(LISTEN_URL)
+            .map_err(|e| SttError::Config(format!("Invalid Deepgram base URL: {}", e)))?;
+        url.query_pairs_mut()
+            .append_pair("model", self.model.as_deref().unwrap_or("nova-2"))
+            .append_pair("encoding", "linear16")
+            .append_pair("sample_rate", &self.sample_rate.to_string())
+            .append_pair("channels", &self.channels.to_string())
+            .append_pair("interim_results", "true")
+            .append_pair("endpointing", &self.endpointing_ms.to_string());
+        Ok(url)
+    }
+}
+
+/// Endpointing state of one recording, shared with the capture watcher.
+#[derive(Debug, Default)]
+pub struct DeepgramEndpointing {
+    active: AtomicBool,
+    speech_final: AtomicBool,
+    transcript: Mutex<LiveTranscript>,
+}
+
+impl DeepgramEndpointing {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// The stream is open, so the end of speech comes from Deepgram.
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Whether Deepgram reported the end of speech since the last call.
+    pub fn take_speech_final(&self) -> bool {
+        self.speech_final.swap(false, Ordering::Relaxed)
+    }
+
+    /// The interim transcript, if it changed since the last call.
+    pub fn take_partial(&self) -> Option<String> {
+        self.transcript.lock().ok()?.take()
+    }
+
+    /// Stream audio from `next_audio` (samples after a cursor, and the next cursor; `None`
+    /// once the recording ended) until the recording ends or the stream fails.
+    pub async fn run<F>(&self, config: LiveStreamConfig, next_audio: F)
+    where
+        F: FnMut(u64) -> Option<(Vec<f32>, u64)>,
+    {
+        if let Err(e) = self.stream(&config, next_audio).await {
+            log::warn!(
+                "Deepgram endpointing stream failed, using local VAD instead: {}",
+                e
+            );
+        }
+        self.active.store(false, Ordering::Relaxed);
+    }
+
+    async fn stream<F>(&self, config: &LiveStreamConfig, mut next_audio: F) -> Result<(), SttError>
+    where
+        F: FnMut(u64) -> Option<(Vec<f32>, u64)>,
+    {
+        let url = config.url()?;
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| SttError::Config(e.to_string()))?;
+        request.headers_mut().insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Token {}", config.api_key))
+                .map_err(|e| SttError::Config(format!("Invalid API key format: {}", e)))?,
+        );
+
+        // Same proxy and certificates as the HTTP providers.
+        let network = crate::http::network_config();
+        let connector = network.tls_connector().map_err(SttError::Config)?;
+        let connect = async {
+            let tcp = crate::http::connect_stream(&network, &url)
+                .await
+                .map_err(SttError::Api)?;
+            tokio_tungstenite::client_async_tls_with_config(
+                request,
+                tcp,
+                None,
+                Some(Connector::NativeTls(connector)),
+            )
+            .await
+            .map_err(|e| SttError::Api(e.to_string()))
+        };
+        let (socket, _) = tokio::time::timeout(CONNECT_TIMEOUT, connect)
+            .await
+            .map_err(|_| SttError::Timeout)??;
+        let (mut sink, mut stream) = socket.split();
+        self.active.store(true, Ordering::Relaxed);
+        log::info!("Deepgram endpointing stream opened");
+
+        let mut cursor = 0;
+        let mut send_tick = tokio::time::interval(SEND_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = send_tick.tick() => {
+                    let Some((samples, next)) = next_audio(cursor) else {
+                        break;
+                    };
+                    cursor = next;
+                    if !samples.is_empty() {
+                        sink.send(Message::Binary(to_linear16(&samples).into()))
+                            .await
+                            .map_err(|e| SttError::Api(e.to_string()))?;
+                    }
+                }
+                message = stream.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Ok(mut transcript) = self.transcript.lock() {
+                            transcript.apply(&text);
+                        }
+                        if is_speech_final(&text) {
+                            self.speech_final.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => {
+                        return Err(SttError::Api("closed by Deepgram".to_string()));
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(SttError::Api(e.to_string())),
+                },
+            }
+        }
+
+        let close_stream = Message::Text(r#"{"type":"CloseStream"}"#.into());
+        let _ = sink.send(close_stream).await;
+        let _ = sink.close().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(transcript: &str, is_final: bool, speech_final: bool) -> String {
+        serde_json::json!({
+            "type": "Results",
+            "is_final": is_final,
+            "speech_final": speech_final,
+            "channel": { "alternatives": [{ "transcript": transcript, "confidence": 0.9 }] },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_only_speech_final_with_words_ends_the_utterance() {
+        assert!(is_speech_final(&result("hello there", true, true)));
+        // Interim and final-but-not-endpointed results.
+        assert!(!is_speech_final(&result("hello", false, false)));
+        assert!(!is_speech_final(&result("hello there", true, false)));
+        // Silence finalized before anyone spoke.
+        assert!(!is_speech_final(&result(" ", true, true)));
+        assert!(!is_speech_final(r#"{"type":"Metadata","request_id":"x"}"#));
+        assert!(!is_speech_final("not json"));
+
+        assert_eq!(
+            to_linear16(&[0.0, 1.0, -2.0]),
+            [0, 0, 0xff, 0x7f, 0x01, 0x80]
+        );
+    }
+
+    #[test]
+    fn test_partial_joins_final_segments_and_latest_interim() {
+        let mut transcript = LiveTranscript::default();
+        assert_eq!(transcript.take(), None);
+
+        transcript.apply(&result("hello", false, false));
+        assert_eq!(transcript.take().as_deref(), Some("hello"));
+        assert_eq!(transcript.take(), None);
+
+        transcript.apply(&result("hello there", true, false));
+        transcript.apply(&result("how", false, false));
+        assert_eq!(transcript.take().as_deref(), Some("hello there how"));
+
+        // The interim segment is revised, then finalized.
+        transcript.apply(&result("how are you", true, true));
+        transcript.apply(r#"{"type":"Metadata","request_id":"x"}"#);
+        assert_eq!(transcript.take().as_deref(), Some("hello there how are you"));
+    }
+}
@@ -0,0 +1,285 @@
+//! Groq Whisper API STT provider implementation.
+
+use super::{
+    fields_json, iso639_1_language, prompt_with_context, with_extra_params, AudioFormat,
+    ExtraParams, SttError, SttProvider, Transcript,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use crate::request_log::RequestLogStore;
+use reqwest::multipart;
+use serde_json::json;
+use std::time::Duration;
+
+const TRANSCRIPTIONS_URL: &str = "https://api.groq.com/openai/v1/audio/transcriptions";
+
+/// Groq Whisper API provider for speech-to-text
+pub struct GroqSttProvider {
+    client: reqwest::Client,
+    api_key: String,
+    model: String,
+    default_prompt: Option<String>,
+    /// ISO 639-1 language code (`language` field); `None` lets Whisper detect it
+    language: Option<String>,
+    /// Sampling temperature; `None` for Groq's default (0)
+    temperature: Option<f32>,
+    /// Ask for `verbose_json` (detected language and segments) instead of `json`
+    verbose_json: bool,
+    extra_params: ExtraParams,
+    request_log_store: Option<RequestLogStore>,
+}
+
+impl GroqSttProvider {
+    const PROMPT_MAX_CHARS: usize = 224;
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Create a new Groq STT provider
+    ///
+    /// # Arguments
+    /// * `api_key` - Groq API key
+    /// * `model` - Model to use (e.g., "whisper-large-v3-turbo")
+    /// * `default_prompt` - Optional transcription prompt (OpenAI-compatible `prompt` field)
+    pub fn new(api_key: String, model: Option<String>, default_prompt: Option<String>) -> Self {
+        Self {
+            client: crate::http::shared_client(),
+            api_key,
+            model: model.unwrap_or_else(|| "whisper-large-v3-turbo".to_string()),
+            default_prompt,
+            language: None,
+            temperature: None,
+            verbose_json: false,
+            extra_params: ExtraParams::new(),
+            request_log_store: None,
+        }
+    }
+
+    /// Create a new provider with a custom HTTP client
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_client(
+        client: reqwest::Client,
+        api_key: String,
+        model: Option<String>,
+        default_prompt: Option<String>,
+    ) -> Self {
+        Self {
+            client,
+            api_key,
+            model: model.unwrap_or_else(|| "whisper-large-v3-turbo".to_string()),
+            default_prompt,
+            language: None,
+            temperature: None,
+            verbose_json: false,
+            extra_params: ExtraParams::new(),
+            request_log_store: None,
+        }
+    }
+
+    /// Dictation language (e.g. "en"); `None` to detect it.
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language.as_deref().and_then(iso639_1_language);
+        self
+    }
+
+    /// Sampling temperature (0-1); `None` for the default.
+    pub fn with_temperature(mut self, temperature: Option<f32>) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Request `verbose_json`, which adds the detected language and timed segments.
+    pub fn with_verbose_json(mut self, verbose_json: bool) -> Self {
+        self.verbose_json = verbose_json;
+        self
+    }
+
+    /// Extra multipart fields, sent as is.
+    pub fn with_extra_params(mut self, extra_params: ExtraParams) -> Self {
+        self.extra_params = extra_params;
+        self
+    }
+
+    /// Multipart text fields of a request.
+    fn form_fields(&self, prompt: Option<String>) -> Vec<(String, String)> {
+        let mut fields = vec![("model", self.model.clone())];
+        if let Some(prompt) = prompt {
+            fields.push(("prompt", prompt));
+        }
+        if let Some(language) = &self.language {
+            fields.push(("language", language.clone()));
+        }
+        if let Some(temperature) = self.temperature {
+            fields.push(("temperature", temperature.to_string()));
+        }
+        if self.verbose_json {
+            fields.push(("response_format", "verbose_json".to_string()));
+        }
+        with_extra_params(fields, &self.extra_params)
+    }
+
+    pub fn with_request_log_store(mut self, store: Option<RequestLogStore>) -> Self {
+        self.request_log_store = store;
+        self
+    }
+
+    fn clamp_prompt(prompt: &str) -> Option<String> {
+        let trimmed = prompt.trim();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        let clamped: String = trimmed.chars().take(Self::PROMPT_MAX_CHARS).collect();
+        Some(clamped)
+    }
+}
+
+#[async_trait]
+impl SttProvider for GroqSttProvider {
+    async fn transcribe(&self, audio: Bytes, format: &AudioFormat) -> Result<String, SttError> {
+        self.transcribe_with_context(audio, format, None).await
+    }
+
+    async fn transcribe_with_context(
+        &self,
+        audio: Bytes,
+        format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<String, SttError> {
+        Ok(self.transcribe_detailed(audio, format, context).await?.text)
+    }
+
+    async fn transcribe_detailed(
+        &self,
+        audio: Bytes,
+        _format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<Transcript, SttError> {
+        let prompt =
+            prompt_with_context(self.default_prompt.as_deref(), context, Self::PROMPT_MAX_CHARS)
+                .as_deref()
+                .and_then(Self::clamp_prompt);
+        let fields = self.form_fields(prompt);
+
+        if let Some(store) = &self.request_log_store {
+            let request_json = json!({
+                "provider": "groq",
+                "endpoint": TRANSCRIPTIONS_URL,
+                "content_type": "multipart/form-data",
+                "fields": fields_json(&fields),
+                "file": {
+                    "name": "audio.wav",
+                    "mime": "audio/wav",
+                    "bytes": audio.len(),
+                    "data": "<binary audio omitted>",
+                }
+            });
+
+            store.with_current(|log| {
+                log.stt_request_json = Some(request_json);
+            });
+        }
+
+        let audio_len = audio.len() as u64;
+        let part = multipart::Part::stream_with_length(audio, audio_len)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| SttError::Audio(format!("Failed to create multipart: {}", e)))?;
+
+        let form = fields
+            .into_iter()
+            .fold(multipart::Form::new().part("file", part), |form, (name, value)| {
+                form.text(name, value)
+            });
+
+        let response = self
+            .client
+            .post(TRANSCRIPTIONS_URL)
+            .bearer_auth(&self.api_key)
+            .timeout(Self::REQUEST_TIMEOUT)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| if e.is_timeout() { SttError::Timeout } else { SttError::Network(e) })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let retry_after = crate::retry::parse_retry_after(response.headers());
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(SttError::from_status(
+                status,
+                retry_after,
+                format!("Groq API error ({}): {}", status, error_text),
+            ));
+        }
+
+        let result: serde_json::Value = response.json().await?;
+
+        if let Some(store) = &self.request_log_store {
+            let result_for_log = result.clone();
+            store.with_current(|log| {
+                log.stt_response_json = Some(result_for_log);
+            });
+        }
+        Ok(Transcript::from_whisper_json(&result))
+    }
+
+    async fn warm_up(&self) -> Result<(), SttError> {
+        crate::network::preconnect(&self.client, TRANSCRIPTIONS_URL).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(TRANSCRIPTIONS_URL.to_string())
+    }
+
+    fn name(&self) -> &'static str {
+        "groq"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_creation() {
+        let provider = GroqSttProvider::new("test-key".to_string(), None, None);
+        assert_eq!(provider.name(), "groq");
+        assert_eq!(provider.model, "whisper-large-v3-turbo");
+    }
+
+    #[test]
+    fn test_provider_with_custom_model() {
+        let provider = GroqSttProvider::new(
+            "test-key".to_string(),
+            Some("whisper-large-v3-turbo".to_string()),
+            None,
+        );
+        assert_eq!(provider.model, "whisper-large-v3-turbo");
+    }
+
+    #[test]
+    fn test_verbose_json_and_temperature_fields() {
+        let provider = GroqSttProvider::new("test-key".to_string(), None, None)
+            .with_temperature(Some(0.2))
+            .with_verbose_json(true);
+        let fields = provider.form_fields(None);
+        assert!(fields.contains(&("temperature".to_string(), "0.2".to_string())));
+        assert!(fields.contains(&("response_format".to_string(), "verbose_json".to_string())));
+
+        let provider = GroqSttProvider::new("test-key".to_string(), None, None);
+        assert_eq!(
+            provider.form_fields(None),
+            vec![("model".to_string(), "whisper-large-v3-turbo".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_prompt_clamping() {
+        let long = "x".repeat(GroqSttProvider::PROMPT_MAX_CHARS + 10);
+        let clamped = GroqSttProvider::clamp_prompt(&long).unwrap();
+        assert_eq!(clamped.len(), GroqSttProvider::PROMPT_MAX_CHARS);
+    }
+}
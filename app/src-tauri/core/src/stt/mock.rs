@@ -0,0 +1,39 @@
+//! Mock STT provider driven by a `MockScript` (see `crate::mock`).
+
+use super::{AudioFormat, SttError, SttProvider};
+use crate::mock::{MockScript, MockStep, ScriptedCalls};
+use async_trait::async_trait;
+use bytes::Bytes;
+
+const DEFAULT_TRANSCRIPT: &str = "This is a mock transcription.";
+
+/// Returns canned transcripts (or scripted failures) without sending the audio anywhere.
+pub struct MockSttProvider {
+    calls: ScriptedCalls,
+}
+
+impl MockSttProvider {
+    pub fn new(script: MockScript) -> Self {
+        Self {
+            calls: ScriptedCalls::new(script),
+        }
+    }
+}
+
+#[async_trait]
+impl SttProvider for MockSttProvider {
+    async fn transcribe(&self, audio: Bytes, _format: &AudioFormat) -> Result<String, SttError> {
+        if audio.is_empty() {
+            return Err(SttError::Audio("Empty audio".to_string()));
+        }
+        match self.calls.next().await {
+            MockStep::Respond(text) => Ok(text.unwrap_or_else(|| DEFAULT_TRANSCRIPT.to_string())),
+            MockStep::Transient => Err(SttError::Api("503 Service Unavailable (mock)".to_string())),
+            MockStep::Fatal => Err(SttError::Api("401 Unauthorized (mock)".to_string())),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+}
@@ -0,0 +1,494 @@
+//! Speech-to-Text (STT) provider abstraction and implementations.
+//!
+//! This module provides a trait-based abstraction for STT providers,
+//! allowing easy switching between different speech recognition services.
+
+mod deepgram;
+mod deepgram_live;
+mod groq;
+mod mock;
+mod openai;
+mod plugin;
+mod retry;
+mod speechmatics;
+mod vocabulary;
+mod whisper_server;
+
+#[cfg(feature = "local-vosk")]
+mod vosk;
+#[cfg(feature = "local-whisper")]
+mod whisper;
+
+pub use deepgram::DeepgramSttProvider;
+pub use deepgram_live::{DeepgramEndpointing, LiveStreamConfig};
+pub use groq::GroqSttProvider;
+pub use mock::MockSttProvider;
+pub use openai::OpenAiSttProvider;
+pub use plugin::PluginSttProvider;
+pub use retry::is_retryable_error;
+pub use speechmatics::SpeechmaticsSttProvider;
+pub use vocabulary::{dictionary_entries, dictionary_terms, prompt_with_vocabulary, VocabularyEntry};
+pub use whisper_server::WhisperServerSttProvider;
+
+#[cfg(feature = "local-vosk")]
+pub use vosk::{LocalVoskProvider, VoskModel};
+#[cfg(feature = "local-whisper")]
+pub use whisper::{
+    InferencePriority, LocalWhisperConfig, LocalWhisperProvider, WhisperDecodingParams,
+    WhisperModel, WhisperThreading,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Audio format information for STT processing
+#[derive(Debug, Clone)]
+pub struct AudioFormat {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub sample_rate: u32,
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub channels: u8,
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub encoding: AudioEncoding,
+}
+
+impl Default for AudioFormat {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            channels: 1,
+            encoding: AudioEncoding::Wav,
+        }
+    }
+}
+
+/// Supported audio encoding formats
+#[derive(Debug, Clone, Copy)]
+pub enum AudioEncoding {
+    Wav,
+    #[cfg_attr(not(test), allow(dead_code))]
+    Pcm16,
+}
+
+/// Errors that can occur during STT operations
+#[derive(Debug, thiserror::Error)]
+pub enum SttError {
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    #[error("API error: {0}")]
+    Api(String),
+
+    /// HTTP 429, with the wait the server asked for (`Retry-After`), if any.
+    #[error("API error: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Audio processing error: {0}")]
+    Audio(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("Timeout: transcription took too long")]
+    Timeout,
+}
+
+impl SttError {
+    /// Error for a non-success API response.
+    pub fn from_status(
+        status: reqwest::StatusCode,
+        retry_after: Option<Duration>,
+        message: String,
+    ) -> Self {
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            SttError::RateLimited {
+                message,
+                retry_after,
+            }
+        } else {
+            SttError::Api(message)
+        }
+    }
+}
+
+/// A transcript with the details verbose responses (`response_format=verbose_json`) add.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transcript {
+    pub text: String,
+    /// Language the provider detected (Whisper reports names, e.g. "english")
+    pub language: Option<String>,
+    /// Timed segments, when the provider returned them
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// A timed stretch of a transcript.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TranscriptSegment {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+}
+
+impl Transcript {
+    pub fn from_text(text: String) -> Self {
+        Self {
+            text,
+            ..Default::default()
+        }
+    }
+
+    /// Parse an OpenAI-style transcription response, `json` (text only) or `verbose_json`.
+    pub(crate) fn from_whisper_json(value: &serde_json::Value) -> Self {
+        let segments = value["segments"]
+            .as_array()
+            .map(|segments| {
+                segments
+                    .iter()
+                    .filter_map(|segment| {
+                        Some(TranscriptSegment {
+                            start_secs: segment["start"].as_f64()?,
+                            end_secs: segment["end"].as_f64()?,
+                            text: segment["text"].as_str()?.trim().to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            text: value["text"].as_str().unwrap_or("").to_string(),
+            language: value["language"]
+                .as_str()
+                .filter(|language| !language.is_empty())
+                .map(str::to_string),
+            segments,
+        }
+    }
+}
+
+/// Trait for Speech-to-Text providers
+#[async_trait]
+pub trait SttProvider: Send + Sync {
+    /// Transcribe audio data to text
+    ///
+    /// # Arguments
+    /// * `audio` - Raw audio bytes (typically WAV format). `Bytes` is reference-counted,
+    ///   so retries and request bodies share one buffer instead of copying it.
+    /// * `format` - Information about the audio format
+    ///
+    /// # Returns
+    /// The transcribed text, or an error if transcription fails
+    async fn transcribe(&self, audio: Bytes, format: &AudioFormat) -> Result<String, SttError>;
+
+    /// Transcribe audio that continues earlier dictation.
+    ///
+    /// `context` is text dictated just before this recording. Whisper-style models take it
+    /// as their prompt, which keeps names, casing and terminology consistent across
+    /// consecutive dictations. Providers without prompting ignore it.
+    async fn transcribe_with_context(
+        &self,
+        audio: Bytes,
+        format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<String, SttError> {
+        let _ = context;
+        self.transcribe(audio, format).await
+    }
+
+    /// `transcribe_with_context`, with the detected language and timed segments when the
+    /// provider returns them.
+    async fn transcribe_detailed(
+        &self,
+        audio: Bytes,
+        format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<Transcript, SttError> {
+        self.transcribe_with_context(audio, format, context)
+            .await
+            .map(Transcript::from_text)
+    }
+
+    /// Prepare for an upcoming `transcribe` call (e.g. open the HTTPS connection).
+    ///
+    /// Called when a recording starts so the setup overlaps with the user speaking.
+    async fn warm_up(&self) -> Result<(), SttError> {
+        Ok(())
+    }
+
+    /// Remote URL this provider sends audio to, probed to detect connectivity (`None` for
+    /// providers that run on this machine or a local server).
+    fn endpoint(&self) -> Option<String> {
+        None
+    }
+
+    /// Get the name of this provider
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn name(&self) -> &'static str;
+}
+
+/// Registry for managing multiple STT providers
+pub struct SttRegistry {
+    providers: std::collections::HashMap<String, Arc<dyn SttProvider>>,
+    current: String,
+}
+
+impl SttRegistry {
+    /// Create a new empty registry
+    pub fn new() -> Self {
+        Self {
+            providers: std::collections::HashMap::new(),
+            current: String::new(),
+        }
+    }
+
+    /// Register a provider with the given name
+    pub fn register(&mut self, name: &str, provider: Arc<dyn SttProvider>) {
+        self.providers.insert(name.to_string(), provider);
+        // If this is the first provider, set it as current
+        if self.current.is_empty() {
+            self.current = name.to_string();
+        }
+    }
+
+    /// Set the current active provider
+    pub fn set_current(&mut self, name: &str) -> Result<(), String> {
+        if self.providers.contains_key(name) {
+            self.current = name.to_string();
+            Ok(())
+        } else {
+            Err(format!("Provider '{}' not found", name))
+        }
+    }
+
+    /// Get the current active provider
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn get_current(&self) -> Option<Arc<dyn SttProvider>> {
+        self.providers.get(&self.current).cloned()
+    }
+
+    /// Get a provider by name
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn get(&self, name: &str) -> Option<Arc<dyn SttProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    /// List all registered provider names
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn list_providers(&self) -> Vec<String> {
+        self.providers.keys().cloned().collect()
+    }
+
+    /// Get the name of the current provider
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn current_name(&self) -> &str {
+        &self.current
+    }
+}
+
+impl Default for SttRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Combine a configured prompt with dictation `context` into one prompt of at most
+/// `max_chars` characters.
+///
+/// The configured prompt is kept whole; the context fills the remaining space with its
+/// most recent text, since the end of the prompt is what Whisper treats as "just said".
+pub fn prompt_with_context(
+    prompt: Option<&str>,
+    context: Option<&str>,
+    max_chars: usize,
+) -> Option<String> {
+    let prompt = prompt.map(str::trim).filter(|p| !p.is_empty());
+    let context = context.map(str::trim).filter(|c| !c.is_empty());
+    let Some(context) = context else {
+        return prompt.map(str::to_string);
+    };
+
+    let used = prompt.map_or(0, |p| p.chars().count() + 1);
+    let budget = max_chars.saturating_sub(used);
+    let chars: Vec<char> = context.chars().collect();
+    let tail: String = if chars.len() > budget {
+        let start = chars.len() - budget;
+        let cut: String = chars[start..].iter().collect();
+        // Start at a word boundary so the prompt doesn't open with half a word.
+        match cut.split_once(' ') {
+            Some((_, rest)) if !chars[start - 1].is_whitespace() => rest.to_string(),
+            _ => cut,
+        }
+    } else {
+        context.to_string()
+    };
+    if tail.trim().is_empty() {
+        return prompt.map(str::to_string);
+    }
+
+    Some(match prompt {
+        Some(prompt) => format!("{} {}", prompt, tail.trim()),
+        None => tail.trim().to_string(),
+    })
+}
+
+/// The ISO 639-1 code Whisper-style APIs take for the `stt_language` setting: the region
+/// is dropped ("en-US" -> "en") and Mandarin ("cmn", as Speechmatics names it) is "zh".
+pub fn iso639_1_language(language: &str) -> Option<String> {
+    let primary = language.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+    match primary.as_str() {
+        "" | "auto" => None,
+        "cmn" => Some("zh".to_string()),
+        _ => Some(primary),
+    }
+}
+
+/// Request parameters from the `stt_extra_params` setting, for provider features without
+/// a setting of their own (e.g. Deepgram `diarize`).
+pub type ExtraParams = HashMap<String, String>;
+
+/// `fields` followed by `extra`; an extra parameter replaces the field of the same name.
+pub(crate) fn with_extra_params(
+    fields: Vec<(&'static str, String)>,
+    extra: &ExtraParams,
+) -> Vec<(String, String)> {
+    let mut merged: Vec<(String, String)> = fields
+        .into_iter()
+        .filter(|(name, _)| !extra.contains_key(*name))
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+    let mut extra: Vec<(String, String)> =
+        extra.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    extra.sort();
+    merged.extend(extra);
+    merged
+}
+
+/// Multipart text fields as the request log shows them.
+pub(crate) fn fields_json(fields: &[(String, String)]) -> serde_json::Value {
+    serde_json::Value::Object(
+        fields
+            .iter()
+            .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+            .collect(),
+    )
+}
+
+/// An extra parameter for a JSON body: JSON if it parses (`0.2`, `true`, `["a"]`),
+/// else a string.
+pub(crate) fn extra_param_json(value: &str) -> serde_json::Value {
+    serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_extra_params_overrides_and_appends() {
+        let extra = ExtraParams::from([
+            ("smart_format".to_string(), "false".to_string()),
+            ("diarize".to_string(), "true".to_string()),
+        ]);
+        let fields = vec![
+            ("model", "nova-2".to_string()),
+            ("smart_format", "true".to_string()),
+        ];
+        assert_eq!(
+            with_extra_params(fields, &extra),
+            vec![
+                ("model".to_string(), "nova-2".to_string()),
+                ("diarize".to_string(), "true".to_string()),
+                ("smart_format".to_string(), "false".to_string()),
+            ]
+        );
+        assert_eq!(extra_param_json("0.2"), serde_json::json!(0.2));
+        assert_eq!(extra_param_json("en"), serde_json::json!("en"));
+    }
+
+    #[test]
+    fn test_transcript_from_whisper_json() {
+        let verbose = serde_json::json!({
+            "task": "transcribe",
+            "language": "german",
+            "duration": 2.5,
+            "text": "Hallo Welt. Wie geht's?",
+            "segments": [
+                { "id": 0, "start": 0.0, "end": 1.2, "text": " Hallo Welt." },
+                { "id": 1, "start": 1.2, "end": 2.5, "text": " Wie geht's?" },
+            ],
+        });
+        let transcript = Transcript::from_whisper_json(&verbose);
+        assert_eq!(transcript.text, "Hallo Welt. Wie geht's?");
+        assert_eq!(transcript.language.as_deref(), Some("german"));
+        assert_eq!(
+            transcript.segments[1],
+            TranscriptSegment {
+                start_secs: 1.2,
+                end_secs: 2.5,
+                text: "Wie geht's?".to_string(),
+            }
+        );
+
+        let plain = Transcript::from_whisper_json(&serde_json::json!({ "text": "hi" }));
+        assert_eq!(plain, Transcript::from_text("hi".to_string()));
+    }
+
+    #[test]
+    fn test_iso639_1_language() {
+        assert_eq!(iso639_1_language("de").as_deref(), Some("de"));
+        assert_eq!(iso639_1_language("en-US").as_deref(), Some("en"));
+        assert_eq!(iso639_1_language("cmn").as_deref(), Some("zh"));
+        assert_eq!(iso639_1_language("auto"), None);
+        assert_eq!(iso639_1_language(" "), None);
+    }
+
+    struct MockProvider;
+
+    #[async_trait]
+    impl SttProvider for MockProvider {
+        async fn transcribe(&self, _audio: Bytes, _format: &AudioFormat) -> Result<String, SttError> {
+            Ok("test transcript".to_string())
+        }
+
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+    }
+
+    #[test]
+    fn test_registry_register_and_get() {
+        let mut registry = SttRegistry::new();
+        registry.register("mock", Arc::new(MockProvider));
+
+        assert!(registry.get("mock").is_some());
+        assert!(registry.get("nonexistent").is_none());
+    }
+
+    #[test]
+    fn test_prompt_with_context_keeps_recent_text() {
+        let prompt = prompt_with_context(
+            Some("Vocabulary: Tauri."),
+            Some("We shipped the first build. Next we test the overlay"),
+            40,
+        );
+        assert_eq!(prompt.as_deref(), Some("Vocabulary: Tauri. we test the overlay"));
+        assert_eq!(
+            prompt_with_context(None, Some("  "), 40).as_deref(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_registry_set_current() {
+        let mut registry = SttRegistry::new();
+        registry.register("mock", Arc::new(MockProvider));
+
+        assert!(registry.set_current("mock").is_ok());
+        assert!(registry.set_current("nonexistent").is_err());
+    }
+}
@@ -4,24 +4,39 @@
 //! - Legacy Whisper API (whisper-1) - uses /v1/audio/transcriptions
 //! - Audio chat models (e.g., gpt-4o-audio-preview) - uses /v1/responses with audio input
 
-use super::{AudioFormat, SttError, SttProvider};
+use super::{
+    extra_param_json, fields_json, iso639_1_language, prompt_with_context, with_extra_params,
+    AudioFormat, ExtraParams, SttError, SttProvider, Transcript,
+};
 use async_trait::async_trait;
+use bytes::Bytes;
 use crate::request_log::RequestLogStore;
 use reqwest::multipart;
 use serde_json::json;
 use std::time::Duration;
 
+const TRANSCRIPTIONS_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+
 /// OpenAI STT provider for speech-to-text
 pub struct OpenAiSttProvider {
     client: reqwest::Client,
     api_key: String,
     model: String,
     default_prompt: Option<String>,
+    /// ISO 639-1 language code; `None` lets the model detect it
+    language: Option<String>,
+    /// Sampling temperature for the transcription endpoint; `None` for OpenAI's default
+    temperature: Option<f32>,
+    /// Ask whisper-1 for `verbose_json` (detected language and segments)
+    verbose_json: bool,
+    extra_params: ExtraParams,
     request_log_store: Option<RequestLogStore>,
 }
 
 impl OpenAiSttProvider {
     const WHISPER_PROMPT_MAX_CHARS: usize = 224;
+    /// Longer than the other providers because GPT-4o audio is slow.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
 
     /// Create a new OpenAI STT provider
     ///
@@ -32,13 +47,8 @@ impl OpenAiSttProvider {
     ///   - "gpt-4o-mini-audio-preview" - Smaller/faster GPT-4o audio
     ///   - "whisper-1" - Legacy Whisper API
     pub fn new(api_key: String, model: Option<String>, default_prompt: Option<String>) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(120)) // Longer timeout for GPT-4o
-            .build()
-            .expect("Failed to create HTTP client");
-
         Self {
-            client,
+            client: crate::http::shared_client(),
             api_key,
             model: model.unwrap_or_else(|| "gpt-4o-audio-preview".to_string()),
             default_prompt: default_prompt
@@ -46,6 +56,10 @@ impl OpenAiSttProvider {
                 .map(str::trim)
                 .filter(|s| !s.is_empty())
                 .map(|s| s.to_string()),
+            language: None,
+            temperature: None,
+            verbose_json: false,
+            extra_params: ExtraParams::new(),
             request_log_store: None,
         }
     }
@@ -67,10 +81,40 @@ impl OpenAiSttProvider {
                 .map(str::trim)
                 .filter(|s| !s.is_empty())
                 .map(|s| s.to_string()),
+            language: None,
+            temperature: None,
+            verbose_json: false,
+            extra_params: ExtraParams::new(),
             request_log_store: None,
         }
     }
 
+    /// Dictation language (e.g. "en"); `None` to detect it.
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language.as_deref().and_then(iso639_1_language);
+        self
+    }
+
+    /// Sampling temperature (0-1) for the transcription endpoint; `None` for the default.
+    pub fn with_temperature(mut self, temperature: Option<f32>) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Request `verbose_json`, which adds the detected language and timed segments. Only
+    /// whisper-1 supports it; the other transcription models always answer `json`.
+    pub fn with_verbose_json(mut self, verbose_json: bool) -> Self {
+        self.verbose_json = verbose_json;
+        self
+    }
+
+    /// Extra request parameters: multipart fields for the transcription endpoint, top-level
+    /// body keys for the Responses API.
+    pub fn with_extra_params(mut self, extra_params: ExtraParams) -> Self {
+        self.extra_params = extra_params;
+        self
+    }
+
     pub fn with_request_log_store(mut self, store: Option<RequestLogStore>) -> Self {
         self.request_log_store = store;
         self
@@ -107,22 +151,38 @@ impl OpenAiSttProvider {
         Some(prompt.to_string())
     }
 
+    /// Multipart text fields of a transcription endpoint request.
+    fn form_fields(&self, prompt: Option<&str>) -> Vec<(String, String)> {
+        let mut fields = vec![("model", self.model.clone())];
+        if let Some(prompt) = self.clamp_prompt_for_model(prompt) {
+            fields.push(("prompt", prompt));
+        }
+        if let Some(language) = &self.language {
+            fields.push(("language", language.clone()));
+        }
+        if let Some(temperature) = self.temperature {
+            fields.push(("temperature", temperature.to_string()));
+        }
+        if self.verbose_json && self.model == "whisper-1" {
+            fields.push(("response_format", "verbose_json".to_string()));
+        }
+        with_extra_params(fields, &self.extra_params)
+    }
+
     /// Transcribe using the dedicated OpenAI transcription endpoint.
     async fn transcribe_audio_transcriptions(
         &self,
-        audio: &[u8],
+        audio: Bytes,
         prompt: Option<&str>,
-    ) -> Result<String, SttError> {
+    ) -> Result<Transcript, SttError> {
+        let fields = self.form_fields(prompt);
+
         if let Some(store) = &self.request_log_store {
-            let prompt = self.clamp_prompt_for_model(prompt);
             let request_json = json!({
                 "provider": "openai",
-                "endpoint": "https://api.openai.com/v1/audio/transcriptions",
+                "endpoint": TRANSCRIPTIONS_URL,
                 "content_type": "multipart/form-data",
-                "fields": {
-                    "model": self.model,
-                    "prompt": prompt,
-                },
+                "fields": fields_json(&fields),
                 "file": {
                     "name": "audio.wav",
                     "mime": "audio/wav",
@@ -136,23 +196,23 @@ impl OpenAiSttProvider {
             });
         }
 
-        let part = multipart::Part::bytes(audio.to_vec())
+        let audio_len = audio.len() as u64;
+        let part = multipart::Part::stream_with_length(audio, audio_len)
             .file_name("audio.wav")
             .mime_str("audio/wav")
             .map_err(|e| SttError::Audio(format!("Failed to create multipart: {}", e)))?;
 
-        let mut form = multipart::Form::new()
-            .part("file", part)
-            .text("model", self.model.clone());
-
-        if let Some(prompt) = self.clamp_prompt_for_model(prompt) {
-            form = form.text("prompt", prompt);
-        }
+        let form = fields
+            .into_iter()
+            .fold(multipart::Form::new().part("file", part), |form, (name, value)| {
+                form.text(name, value)
+            });
 
         let response = self
             .client
-            .post("https://api.openai.com/v1/audio/transcriptions")
+            .post(TRANSCRIPTIONS_URL)
             .bearer_auth(&self.api_key)
+            .timeout(Self::REQUEST_TIMEOUT)
             .multipart(form)
             .send()
             .await
@@ -160,14 +220,16 @@ impl OpenAiSttProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = crate::retry::parse_retry_after(response.headers());
             let error_text = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(SttError::Api(format!(
-                "OpenAI Whisper API error ({}): {}",
-                status, error_text
-            )));
+            return Err(SttError::from_status(
+                status,
+                retry_after,
+                format!("OpenAI Whisper API error ({}): {}", status, error_text),
+            ));
         }
 
         let result: serde_json::Value = response.json().await?;
@@ -179,9 +241,7 @@ impl OpenAiSttProvider {
             });
         }
 
-        let text = result["text"].as_str().unwrap_or("").to_string();
-
-        Ok(text)
+        Ok(Transcript::from_whisper_json(&result))
     }
 
     fn extract_responses_output_text(value: &serde_json::Value) -> Result<String, SttError> {
@@ -228,6 +288,13 @@ impl OpenAiSttProvider {
         ))
     }
 
+    /// Add the extra params to a Responses API body.
+    fn apply_extra_params(&self, body: &mut serde_json::Value) {
+        for (name, value) in &self.extra_params {
+            body[name.as_str()] = extra_param_json(value);
+        }
+    }
+
     /// Transcribe using the Responses API with audio input.
     async fn transcribe_responses_audio(
         &self,
@@ -240,12 +307,16 @@ impl OpenAiSttProvider {
         let audio_base64 = STANDARD.encode(audio);
 
         let mut instruction = "Transcribe this audio. Output only the transcribed text, nothing else.".to_string();
+        // The Responses API has no language parameter.
+        if let Some(language) = &self.language {
+            instruction.push_str(&format!("\nThe speech is in language '{}'.", language));
+        }
         if let Some(prompt) = self.clamp_prompt_for_model(prompt) {
             instruction.push_str("\n\nContext/prompt: ");
             instruction.push_str(&prompt);
         }
 
-        let request_body = json!({
+        let mut request_body = json!({
             "model": self.model,
             "input": [
                 {
@@ -269,9 +340,10 @@ impl OpenAiSttProvider {
                 "format": {"type": "text"}
             }
         });
+        self.apply_extra_params(&mut request_body);
 
         if let Some(store) = &self.request_log_store {
-            let request_json = json!({
+            let mut request_json = json!({
                 "provider": "openai",
                 "endpoint": "https://api.openai.com/v1/responses",
                 "body": {
@@ -301,6 +373,7 @@ impl OpenAiSttProvider {
                     }
                 }
             });
+            self.apply_extra_params(&mut request_json["body"]);
 
             store.with_current(|log| {
                 log.stt_request_json = Some(request_json);
@@ -311,6 +384,7 @@ impl OpenAiSttProvider {
             .client
             .post("https://api.openai.com/v1/responses")
             .bearer_auth(&self.api_key)
+            .timeout(Self::REQUEST_TIMEOUT)
             .json(&request_body)
             .send()
             .await
@@ -318,14 +392,16 @@ impl OpenAiSttProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = crate::retry::parse_retry_after(response.headers());
             let error_text = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(SttError::Api(format!(
-                "OpenAI GPT-4o API error ({}): {}",
-                status, error_text
-            )));
+            return Err(SttError::from_status(
+                status,
+                retry_after,
+                format!("OpenAI GPT-4o API error ({}): {}", status, error_text),
+            ));
         }
 
         let result: serde_json::Value = response.json().await?;
@@ -345,25 +421,75 @@ impl OpenAiSttProvider {
     /// This is primarily used by the Settings "Test transcription" UI.
     pub async fn transcribe_with_prompt(
         &self,
-        audio: &[u8],
+        audio: Bytes,
         _format: &AudioFormat,
         prompt: Option<&str>,
     ) -> Result<String, SttError> {
+        Ok(self.transcribe_prompted(audio, prompt).await?.text)
+    }
+
+    async fn transcribe_prompted(
+        &self,
+        audio: Bytes,
+        prompt: Option<&str>,
+    ) -> Result<Transcript, SttError> {
         if self.uses_transcriptions_endpoint() {
             self.transcribe_audio_transcriptions(audio, prompt).await
         } else {
-            self.transcribe_responses_audio(audio, prompt).await
+            self.transcribe_responses_audio(&audio, prompt)
+                .await
+                .map(Transcript::from_text)
         }
     }
+
+    /// The prompt for a request continuing `context`.
+    fn prompt_with_context(&self, context: Option<&str>) -> Option<String> {
+        let max_chars = if self.model == "whisper-1" {
+            Self::WHISPER_PROMPT_MAX_CHARS
+        } else {
+            usize::MAX
+        };
+        prompt_with_context(self.default_prompt.as_deref(), context, max_chars)
+    }
 }
 
 #[async_trait]
 impl SttProvider for OpenAiSttProvider {
-    async fn transcribe(&self, audio: &[u8], _format: &AudioFormat) -> Result<String, SttError> {
+    async fn transcribe(&self, audio: Bytes, _format: &AudioFormat) -> Result<String, SttError> {
         self.transcribe_with_prompt(audio, _format, self.default_prompt.as_deref())
             .await
     }
 
+    async fn transcribe_with_context(
+        &self,
+        audio: Bytes,
+        format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<String, SttError> {
+        let prompt = self.prompt_with_context(context);
+        self.transcribe_with_prompt(audio, format, prompt.as_deref())
+            .await
+    }
+
+    async fn transcribe_detailed(
+        &self,
+        audio: Bytes,
+        _format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<Transcript, SttError> {
+        let prompt = self.prompt_with_context(context);
+        self.transcribe_prompted(audio, prompt.as_deref()).await
+    }
+
+    async fn warm_up(&self) -> Result<(), SttError> {
+        crate::network::preconnect(&self.client, TRANSCRIPTIONS_URL).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(TRANSCRIPTIONS_URL.to_string())
+    }
+
     fn name(&self) -> &'static str {
         "openai"
     }
@@ -0,0 +1,46 @@
+//! STT provider backed by an external plugin program (see `crate::plugins`).
+
+use super::{AudioFormat, SttError, SttProvider};
+use crate::plugins::{PluginError, PluginRequest, ProviderPlugin};
+use async_trait::async_trait;
+use bytes::Bytes;
+
+pub struct PluginSttProvider {
+    plugin: ProviderPlugin,
+    model: Option<String>,
+}
+
+impl PluginSttProvider {
+    pub fn new(plugin: ProviderPlugin, model: Option<String>) -> Self {
+        Self { plugin, model }
+    }
+}
+
+#[async_trait]
+impl SttProvider for PluginSttProvider {
+    async fn transcribe(&self, audio: Bytes, format: &AudioFormat) -> Result<String, SttError> {
+        self.transcribe_with_context(audio, format, None).await
+    }
+
+    async fn transcribe_with_context(
+        &self,
+        audio: Bytes,
+        _format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<String, SttError> {
+        if audio.is_empty() {
+            return Err(SttError::Audio("Empty audio".to_string()));
+        }
+        let request = PluginRequest::transcribe(&audio, context, self.model.as_deref());
+        crate::plugins::call(&self.plugin, &request)
+            .await
+            .map_err(|e| match e {
+                PluginError::Failed { .. } => SttError::Config(e.to_string()),
+                PluginError::Provider { .. } => SttError::Api(e.api_message()),
+            })
+    }
+
+    fn name(&self) -> &'static str {
+        crate::plugins::static_id(&self.plugin.id)
+    }
+}
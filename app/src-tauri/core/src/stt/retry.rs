@@ -0,0 +1,60 @@
+//! Retry classification for STT errors (the policy itself lives in `crate::retry`).
+
+use crate::retry::{is_transient_api_message, RetryConfig, RetryableError};
+use crate::stt::SttError;
+use std::time::Duration;
+
+impl RetryableError for SttError {
+    fn is_retryable(&self, config: &RetryConfig) -> bool {
+        match self {
+            SttError::Network(_) => true,
+            SttError::Timeout => true,
+            // Retry on server errors (5xx) or rate limits (429)
+            SttError::Api(msg) => is_transient_api_message(msg, config),
+            SttError::RateLimited { .. } => config.retry_on_rate_limit,
+            SttError::Audio(_) => false, // Don't retry audio errors
+            SttError::Config(_) => false, // Don't retry config errors
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            SttError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Determines if an error is retryable.
+///
+/// Note: this uses a default policy (including retrying rate-limit errors).
+/// If you need to respect a specific `RetryConfig`, use `RetryableError::is_retryable`.
+pub fn is_retryable_error(error: &SttError) -> bool {
+    error.is_retryable(&RetryConfig::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_retryable_error() {
+        assert!(is_retryable_error(&SttError::Timeout));
+        assert!(is_retryable_error(&SttError::Api("500 Internal Server Error".to_string())));
+        assert!(is_retryable_error(&SttError::Api("429 Rate limit exceeded".to_string())));
+        assert!(!is_retryable_error(&SttError::Config("Invalid API key".to_string())));
+        assert!(!is_retryable_error(&SttError::Audio("Invalid audio format".to_string())));
+
+        let limited = SttError::from_status(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(3)),
+            "Groq API error (429 Too Many Requests)".to_string(),
+        );
+        assert!(is_retryable_error(&limited));
+        assert_eq!(limited.retry_after(), Some(Duration::from_secs(3)));
+        assert!(!limited.is_retryable(&RetryConfig {
+            retry_on_rate_limit: false,
+            ..Default::default()
+        }));
+    }
+}
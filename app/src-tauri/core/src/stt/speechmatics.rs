@@ -0,0 +1,297 @@
+//! Speechmatics batch STT provider implementation.
+//!
+//! Speechmatics transcribes asynchronously: the audio is submitted as a job, which is polled
+//! until it is done and then deleted once its transcript is fetched.
+
+use super::{extra_param_json, AudioFormat, ExtraParams, SttError, SttProvider, VocabularyEntry};
+use crate::request_log::RequestLogStore;
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::multipart;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+const JOBS_URL: &str = "https://asr.api.speechmatics.com/v2/jobs";
+
+/// Speechmatics batch API provider for speech-to-text
+pub struct SpeechmaticsSttProvider {
+    client: reqwest::Client,
+    api_key: String,
+    /// Operating point: "enhanced" or "standard"
+    model: String,
+    /// Language code, or "auto" to let Speechmatics identify it
+    language: String,
+    /// Custom dictionary (`additional_vocab`).
+    vocabulary: Vec<VocabularyEntry>,
+    /// Extra `transcription_config` keys.
+    extra_params: ExtraParams,
+    request_log_store: Option<RequestLogStore>,
+}
+
+impl SpeechmaticsSttProvider {
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+    /// Longest a job may take, queueing included.
+    const JOB_TIMEOUT: Duration = Duration::from_secs(120);
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Create a new Speechmatics STT provider
+    ///
+    /// # Arguments
+    /// * `api_key` - Speechmatics API key
+    /// * `model` - Operating point ("enhanced" or "standard")
+    /// * `language` - Language code (e.g. "en"); `None` for automatic identification
+    pub fn new(api_key: String, model: Option<String>, language: Option<String>) -> Self {
+        Self {
+            client: crate::http::shared_client(),
+            api_key,
+            model: model.unwrap_or_else(|| "enhanced".to_string()),
+            language: language.unwrap_or_else(|| "auto".to_string()),
+            vocabulary: Vec::new(),
+            extra_params: ExtraParams::new(),
+            request_log_store: None,
+        }
+    }
+
+    pub fn with_request_log_store(mut self, store: Option<RequestLogStore>) -> Self {
+        self.request_log_store = store;
+        self
+    }
+
+    /// Boost recognition of these entries (the personal dictionary).
+    pub fn with_vocabulary(mut self, vocabulary: Vec<VocabularyEntry>) -> Self {
+        self.vocabulary = vocabulary;
+        self
+    }
+
+    /// Extra `transcription_config` keys (e.g. `enable_entities=true`).
+    pub fn with_extra_params(mut self, extra_params: ExtraParams) -> Self {
+        self.extra_params = extra_params;
+        self
+    }
+
+    /// The job's `config` part.
+    fn job_config(&self) -> serde_json::Value {
+        let mut transcription_config = json!({
+            "language": self.language,
+            "operating_point": self.model,
+        });
+        if !self.vocabulary.is_empty() {
+            let additional_vocab: Vec<serde_json::Value> = self
+                .vocabulary
+                .iter()
+                .map(|entry| match &entry.sounds_like {
+                    Some(spoken) => json!({ "content": entry.term, "sounds_like": [spoken] }),
+                    None => json!({ "content": entry.term }),
+                })
+                .collect();
+            transcription_config["additional_vocab"] = json!(additional_vocab);
+        }
+        for (name, value) in &self.extra_params {
+            transcription_config[name.as_str()] = extra_param_json(value);
+        }
+        json!({
+            "type": "transcription",
+            "transcription_config": transcription_config,
+        })
+    }
+
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response, SttError> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let retry_after = crate::retry::parse_retry_after(response.headers());
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        Err(SttError::from_status(
+            status,
+            retry_after,
+            format!("Speechmatics API error ({}): {}", status, error_text),
+        ))
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, SttError> {
+        let response = request
+            .bearer_auth(&self.api_key)
+            .timeout(Self::REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    SttError::Timeout
+                } else {
+                    SttError::Network(e)
+                }
+            })?;
+        Self::check(response).await
+    }
+
+    /// Wait for job `id` to finish.
+    async fn wait_for_job(&self, id: &str) -> Result<(), SttError> {
+        let started = Instant::now();
+        loop {
+            let response = self
+                .send(self.client.get(format!("{}/{}", JOBS_URL, id)))
+                .await?;
+            let details: serde_json::Value = response.json().await?;
+            match details["job"]["status"].as_str() {
+                Some("done") => return Ok(()),
+                Some("running") => {}
+                other => {
+                    return Err(SttError::Api(format!(
+                        "Speechmatics job {} failed ({}): {}",
+                        id,
+                        other.unwrap_or("unknown status"),
+                        details["job"]["errors"]
+                    )))
+                }
+            }
+            if started.elapsed() > Self::JOB_TIMEOUT {
+                return Err(SttError::Timeout);
+            }
+            tokio::time::sleep(Self::POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[async_trait]
+impl SttProvider for SpeechmaticsSttProvider {
+    async fn transcribe(&self, audio: Bytes, _format: &AudioFormat) -> Result<String, SttError> {
+        let config = self.job_config();
+
+        if let Some(store) = &self.request_log_store {
+            let request_json = json!({
+                "provider": "speechmatics",
+                "endpoint": JOBS_URL,
+                "content_type": "multipart/form-data",
+                "fields": {
+                    "config": config,
+                },
+                "file": {
+                    "name": "audio.wav",
+                    "mime": "audio/wav",
+                    "bytes": audio.len(),
+                    "data": "<binary audio omitted>",
+                }
+            });
+
+            store.with_current(|log| {
+                log.stt_request_json = Some(request_json);
+            });
+        }
+
+        let audio_len = audio.len() as u64;
+        let part = multipart::Part::stream_with_length(audio, audio_len)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| SttError::Audio(format!("Failed to create multipart: {}", e)))?;
+        let form = multipart::Form::new()
+            .text("config", config.to_string())
+            .part("data_file", part);
+
+        let response = self
+            .send(self.client.post(JOBS_URL).multipart(form))
+            .await?;
+        let created: serde_json::Value = response.json().await?;
+        let id = created["id"]
+            .as_str()
+            .ok_or_else(|| SttError::Api("Speechmatics returned no job id".to_string()))?
+            .to_string();
+
+        let result = async {
+            self.wait_for_job(&id).await?;
+            let response = self
+                .send(
+                    self.client
+                        .get(format!("{}/{}/transcript", JOBS_URL, id))
+                        .query(&[("format", "txt")]),
+                )
+                .await?;
+            Ok::<String, SttError>(response.text().await?)
+        }
+        .await;
+
+        // Don't leave the audio on their servers.
+        if let Err(e) = self
+            .send(self.client.delete(format!("{}/{}", JOBS_URL, id)))
+            .await
+        {
+            log::debug!("Failed to delete Speechmatics job {}: {}", id, e);
+        }
+
+        let text = result?;
+        if let Some(store) = &self.request_log_store {
+            let result_for_log = json!({ "job_id": id, "transcript": text });
+            store.with_current(|log| {
+                log.stt_response_json = Some(result_for_log);
+            });
+        }
+        Ok(text.trim().to_string())
+    }
+
+    async fn warm_up(&self) -> Result<(), SttError> {
+        crate::network::preconnect(&self.client, JOBS_URL).await?;
+        Ok(())
+    }
+
+    fn endpoint(&self) -> Option<String> {
+        Some(JOBS_URL.to_string())
+    }
+
+    fn name(&self) -> &'static str {
+        "speechmatics"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_creation() {
+        let provider = SpeechmaticsSttProvider::new("test-key".to_string(), None, None);
+        assert_eq!(provider.name(), "speechmatics");
+        assert_eq!(provider.model, "enhanced");
+        assert_eq!(provider.language, "auto");
+    }
+
+    #[test]
+    fn test_job_config_has_language_and_additional_vocab() {
+        let provider = SpeechmaticsSttProvider::new(
+            "test-key".to_string(),
+            Some("standard".to_string()),
+            Some("de".to_string()),
+        )
+        .with_vocabulary(vec![
+            VocabularyEntry {
+                term: "Tauri".to_string(),
+                sounds_like: None,
+            },
+            VocabularyEntry {
+                term: "Anthropic".to_string(),
+                sounds_like: Some("ant row pick".to_string()),
+            },
+        ]);
+        assert_eq!(
+            provider.job_config(),
+            json!({
+                "type": "transcription",
+                "transcription_config": {
+                    "language": "de",
+                    "operating_point": "standard",
+                    "additional_vocab": [
+                        { "content": "Tauri" },
+                        { "content": "Anthropic", "sounds_like": ["ant row pick"] },
+                    ],
+                },
+            })
+        );
+
+        let provider = SpeechmaticsSttProvider::new("test-key".to_string(), None, None);
+        assert!(provider.job_config()["transcription_config"]
+            .get("additional_vocab")
+            .is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Turn the personal dictionary into STT vocabulary hints.
+//!
+//! The dictionary is free text written for the LLM cleanup step (see
+//! `DICTIONARY_PROMPT_DEFAULT`). Its entry lines are either plain terms ("Tauri") or
+//! mappings ("ant row pick = Anthropic"); both name a word the STT model should expect.
+//! Longer natural-language instructions can't be expressed as hints and are skipped.
+
+/// Entries with more words than this are instructions, not terms.
+const MAX_TERM_WORDS: usize = 4;
+
+/// Upper bound on hints sent to a provider (Deepgram rejects very long query strings,
+/// and Whisper only reads the first ~224 tokens of a prompt).
+pub const MAX_VOCABULARY_TERMS: usize = 50;
+
+/// A dictionary entry as a recognition hint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VocabularyEntry {
+    /// The written form, what should be recognized
+    pub term: String,
+    /// How it is pronounced, for "spoken form = Written form" entries
+    pub sounds_like: Option<String>,
+}
+
+/// Extract the terms from a dictionary prompt section.
+///
+/// When the section has an "Entries:" heading only the lines after it are read, so the
+/// explanatory preamble of the default section is ignored.
+pub fn dictionary_terms(dictionary: &str) -> Vec<String> {
+    dictionary_entries(dictionary)
+        .into_iter()
+        .map(|entry| entry.term)
+        .collect()
+}
+
+/// Like `dictionary_terms`, keeping the spoken form of mappings (for providers that take
+/// pronunciations, e.g. Speechmatics `sounds_like`).
+pub fn dictionary_entries(dictionary: &str) -> Vec<VocabularyEntry> {
+    let entries = dictionary
+        .lines()
+        .position(|line| {
+            line.trim_start_matches('#')
+                .trim()
+                .eq_ignore_ascii_case("entries:")
+        })
+        .map_or_else(
+            || dictionary.lines().collect::<Vec<_>>(),
+            |idx| dictionary.lines().skip(idx + 1).collect(),
+        );
+
+    let mut vocabulary: Vec<VocabularyEntry> = Vec::new();
+    for line in entries {
+        let line = line.trim().trim_start_matches(['-', '*', '•']).trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let unquote = |s: &str| s.trim().trim_matches(['"', '\'']).to_string();
+        // "spoken form = Written form": the written form is what should be recognized.
+        let (term, sounds_like) = match line.rsplit_once('=') {
+            Some((spoken, written)) => (unquote(written), Some(unquote(spoken))),
+            None => (unquote(line), None),
+        };
+        if term.is_empty() || term.split_whitespace().count() > MAX_TERM_WORDS {
+            continue;
+        }
+        if !vocabulary.iter().any(|e| e.term.eq_ignore_ascii_case(&term)) {
+            vocabulary.push(VocabularyEntry {
+                term,
+                sounds_like: sounds_like.filter(|spoken| !spoken.is_empty()),
+            });
+        }
+        if vocabulary.len() == MAX_VOCABULARY_TERMS {
+            break;
+        }
+    }
+    vocabulary
+}
+
+/// Build a Whisper-style `prompt` from the user's transcription prompt plus `terms`.
+///
+/// Whisper imitates the spelling it sees in the prompt, so listing the terms is enough.
+pub fn prompt_with_vocabulary(prompt: Option<&str>, terms: &[String]) -> Option<String> {
+    let prompt = prompt.map(str::trim).filter(|p| !p.is_empty());
+    if terms.is_empty() {
+        return prompt.map(str::to_string);
+    }
+    let vocabulary = format!("Vocabulary: {}.", terms.join(", "));
+    Some(match prompt {
+        Some(prompt) => format!("{} {}", prompt, vocabulary),
+        None => vocabulary,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::DICTIONARY_PROMPT_DEFAULT;
+
+    #[test]
+    fn test_default_dictionary_terms() {
+        assert_eq!(
+            dictionary_terms(DICTIONARY_PROMPT_DEFAULT),
+            vec!["Tangerine", "LLM", "Anthropic", "Claude", "Pipecat", "Tauri"]
+        );
+    }
+
+    #[test]
+    fn test_skips_instructions_and_duplicates() {
+        let terms = dictionary_terms(
+            "- Kubernetes\n- kubernetes\n- The name 'Claude' should always be capitalized\n- jay son = JSON",
+        );
+        assert_eq!(terms, vec!["Kubernetes", "JSON"]);
+    }
+
+    #[test]
+    fn test_dictionary_entries_keep_spoken_forms() {
+        let entries = dictionary_entries("- Tauri\n- ant row pick = Anthropic");
+        assert_eq!(
+            entries,
+            vec![
+                VocabularyEntry {
+                    term: "Tauri".to_string(),
+                    sounds_like: None,
+                },
+                VocabularyEntry {
+                    term: "Anthropic".to_string(),
+                    sounds_like: Some("ant row pick".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prompt_with_vocabulary() {
+        let terms = vec!["Tauri".to_string(), "Pipecat".to_string()];
+        assert_eq!(
+            prompt_with_vocabulary(Some("Technical dictation."), &terms).as_deref(),
+            Some("Technical dictation. Vocabulary: Tauri, Pipecat.")
+        );
+        assert_eq!(prompt_with_vocabulary(Some("  "), &[]), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! Local Vosk (Kaldi) STT provider implementation.
+//!
+//! A lightweight offline alternative to local Whisper: the small Vosk models are ~40MB and
+//! transcribe faster than real time on old laptops, at lower accuracy. Models are
+//! directories (unpacked from the zip archives alphacephei.com serves; see
+//! `commands::vosk`).
+
+use super::{AudioFormat, SttError, SttProvider};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Downloadable Vosk models
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoskModel {
+    #[default]
+    SmallEnUs,
+    EnUsLgraph,
+    SmallDe,
+    SmallFr,
+    SmallEs,
+    SmallCn,
+}
+
+impl VoskModel {
+    /// Name of the archive and of the directory it unpacks to
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            Self::SmallEnUs => "vosk-model-small-en-us-0.15",
+            Self::EnUsLgraph => "vosk-model-en-us-0.22-lgraph",
+            Self::SmallDe => "vosk-model-small-de-0.15",
+            Self::SmallFr => "vosk-model-small-fr-0.22",
+            Self::SmallEs => "vosk-model-small-es-0.42",
+            Self::SmallCn => "vosk-model-small-cn-0.22",
+        }
+    }
+
+    /// Get the download URL (a zip archive)
+    pub fn download_url(&self) -> String {
+        format!(
+            "https://alphacephei.com/vosk/models/{}.zip",
+            self.dir_name()
+        )
+    }
+
+    /// Get approximate archive size in bytes
+    pub fn size_bytes(&self) -> u64 {
+        match self {
+            Self::SmallEnUs => 40_000_000,
+            Self::EnUsLgraph => 128_000_000,
+            Self::SmallDe => 45_000_000,
+            Self::SmallFr => 41_000_000,
+            Self::SmallEs => 39_000_000,
+            Self::SmallCn => 42_000_000,
+        }
+    }
+
+    /// Get human-readable model name
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::SmallEnUs => "Small English (40MB)",
+            Self::EnUsLgraph => "English (128MB)",
+            Self::SmallDe => "Small German (45MB)",
+            Self::SmallFr => "Small French (41MB)",
+            Self::SmallEs => "Small Spanish (39MB)",
+            Self::SmallCn => "Small Chinese (42MB)",
+        }
+    }
+
+    /// List all available models
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::SmallEnUs,
+            Self::EnUsLgraph,
+            Self::SmallDe,
+            Self::SmallFr,
+            Self::SmallEs,
+            Self::SmallCn,
+        ]
+    }
+}
+
+/// Local Vosk provider for speech-to-text
+pub struct LocalVoskProvider {
+    model: Arc<vosk::Model>,
+}
+
+impl LocalVoskProvider {
+    /// Load the model unpacked in `model_dir`.
+    pub fn new(model_dir: &Path) -> Result<Self, SttError> {
+        let path = model_dir
+            .to_str()
+            .ok_or_else(|| SttError::Config(format!("Invalid model path {:?}", model_dir)))?;
+        let model = vosk::Model::new(path).ok_or_else(|| {
+            SttError::Config(format!(
+                "Failed to load Vosk model from {}",
+                model_dir.display()
+            ))
+        })?;
+        log::info!("Loaded Vosk model from {}", model_dir.display());
+        Ok(Self {
+            model: Arc::new(model),
+        })
+    }
+}
+
+#[async_trait]
+impl SttProvider for LocalVoskProvider {
+    async fn transcribe(&self, audio: Bytes, _format: &AudioFormat) -> Result<String, SttError> {
+        let model = self.model.clone();
+        tokio::task::spawn_blocking(move || recognize(&model, &audio))
+            .await
+            .map_err(|e| SttError::Audio(format!("Vosk task failed: {}", e)))?
+    }
+
+    fn name(&self) -> &'static str {
+        "local-vosk"
+    }
+}
+
+fn recognize(model: &vosk::Model, wav_bytes: &[u8]) -> Result<String, SttError> {
+    let (samples, sample_rate) = decode_wav_to_i16_mono(wav_bytes)?;
+    // Vosk resamples to the model's rate itself.
+    let mut recognizer = vosk::Recognizer::new(model, sample_rate as f32)
+        .ok_or_else(|| SttError::Audio("Failed to create Vosk recognizer".to_string()))?;
+    recognizer
+        .accept_waveform(&samples)
+        .map_err(|e| SttError::Audio(format!("Vosk rejected the audio: {:?}", e)))?;
+    Ok(recognizer
+        .final_result()
+        .single()
+        .map(|result| result.text.to_string())
+        .unwrap_or_default())
+}
+
+/// Decode WAV bytes to 16-bit mono samples and their sample rate.
+fn decode_wav_to_i16_mono(wav_bytes: &[u8]) -> Result<(Vec<i16>, u32), SttError> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(wav_bytes))
+        .map_err(|e| SttError::Audio(format!("Failed to read WAV: {}", e)))?;
+    let spec = reader.spec();
+    let channels = spec.channels.max(1) as usize;
+
+    let interleaved: Vec<i16> = match spec.sample_format {
+        hound::SampleFormat::Int if spec.bits_per_sample == 16 => reader
+            .into_samples::<i16>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| SttError::Audio(format!("Failed to read samples: {}", e)))?,
+        hound::SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .map(|s| s.map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16))
+            .collect::<Result<_, _>>()
+            .map_err(|e| SttError::Audio(format!("Failed to read samples: {}", e)))?,
+        hound::SampleFormat::Int => {
+            return Err(SttError::Audio(format!(
+                "Unsupported WAV bit depth: {}",
+                spec.bits_per_sample
+            )))
+        }
+    };
+
+    let mono = interleaved
+        .chunks(channels)
+        .map(|frame| (frame.iter().map(|&s| s as i32).sum::<i32>() / frame.len() as i32) as i16)
+        .collect();
+    Ok((mono, spec.sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_wav_downmixes_to_mono() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 44_100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut wav = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+        for sample in [100i16, 300, -50, -150] {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (samples, sample_rate) = decode_wav_to_i16_mono(wav.get_ref()).unwrap();
+        assert_eq!(samples, vec![200, -100]);
+        assert_eq!(sample_rate, 44_100);
+    }
+
+    #[test]
+    fn test_model_ids_and_urls() {
+        assert_eq!(
+            serde_json::to_string(&VoskModel::SmallEnUs).unwrap(),
+            "\"small_en_us\""
+        );
+        assert_eq!(
+            VoskModel::default().download_url(),
+            "https://alphacephei.com/vosk/models/vosk-model-small-en-us-0.15.zip"
+        );
+    }
+}
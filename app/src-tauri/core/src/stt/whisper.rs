@@ -0,0 +1,634 @@
+//! Local Whisper STT provider using whisper.cpp bindings.
+//!
+//! This module provides offline speech-to-text using the whisper-rs library
+//! (Rust bindings for whisper.cpp). It's an optional feature that requires
+//! the `local-whisper` feature flag to be enabled.
+//!
+//! ## Requirements
+//! - whisper.cpp model files (downloaded separately)
+//! - Feature flag: `--features local-whisper`
+//!
+//! ## Model Sizes
+//! - tiny: ~75MB, fastest, lower accuracy
+//! - base: ~142MB, good balance
+//! - small: ~466MB, better accuracy
+//! - medium: ~1.5GB, high accuracy
+//! - large: ~2.9GB, highest accuracy
+
+use super::{AudioFormat, SttError, SttProvider};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// Available Whisper model sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum WhisperModel {
+    Tiny,
+    TinyEn,
+    Base,
+    BaseEn,
+    Small,
+    SmallEn,
+    Medium,
+    MediumEn,
+    LargeV1,
+    LargeV2,
+    LargeV3,
+    LargeV3Turbo,
+}
+
+impl WhisperModel {
+    /// Get the model filename
+    pub fn filename(&self) -> &'static str {
+        match self {
+            Self::Tiny => "ggml-tiny.bin",
+            Self::TinyEn => "ggml-tiny.en.bin",
+            Self::Base => "ggml-base.bin",
+            Self::BaseEn => "ggml-base.en.bin",
+            Self::Small => "ggml-small.bin",
+            Self::SmallEn => "ggml-small.en.bin",
+            Self::Medium => "ggml-medium.bin",
+            Self::MediumEn => "ggml-medium.en.bin",
+            Self::LargeV1 => "ggml-large-v1.bin",
+            Self::LargeV2 => "ggml-large-v2.bin",
+            Self::LargeV3 => "ggml-large-v3.bin",
+            Self::LargeV3Turbo => "ggml-large-v3-turbo.bin",
+        }
+    }
+
+    /// Get the Hugging Face download URL
+    pub fn download_url(&self) -> String {
+        format!(
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/{}",
+            self.filename()
+        )
+    }
+
+    /// Get approximate model size in bytes
+    pub fn size_bytes(&self) -> u64 {
+        match self {
+            Self::Tiny | Self::TinyEn => 75_000_000,
+            Self::Base | Self::BaseEn => 142_000_000,
+            Self::Small | Self::SmallEn => 466_000_000,
+            Self::Medium | Self::MediumEn => 1_500_000_000,
+            Self::LargeV1 | Self::LargeV2 | Self::LargeV3 => 2_900_000_000,
+            Self::LargeV3Turbo => 1_600_000_000,
+        }
+    }
+
+    /// Get human-readable model name
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Tiny => "Tiny (75MB)",
+            Self::TinyEn => "Tiny English (75MB)",
+            Self::Base => "Base (142MB)",
+            Self::BaseEn => "Base English (142MB)",
+            Self::Small => "Small (466MB)",
+            Self::SmallEn => "Small English (466MB)",
+            Self::Medium => "Medium (1.5GB)",
+            Self::MediumEn => "Medium English (1.5GB)",
+            Self::LargeV1 => "Large v1 (2.9GB)",
+            Self::LargeV2 => "Large v2 (2.9GB)",
+            Self::LargeV3 => "Large v3 (2.9GB)",
+            Self::LargeV3Turbo => "Large v3 Turbo (1.6GB)",
+        }
+    }
+
+    /// Check if this is an English-only model
+    pub fn is_english_only(&self) -> bool {
+        matches!(
+            self,
+            Self::TinyEn | Self::BaseEn | Self::SmallEn | Self::MediumEn
+        )
+    }
+
+    /// List all available models
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Tiny,
+            Self::TinyEn,
+            Self::Base,
+            Self::BaseEn,
+            Self::Small,
+            Self::SmallEn,
+            Self::Medium,
+            Self::MediumEn,
+            Self::LargeV1,
+            Self::LargeV2,
+            Self::LargeV3,
+            Self::LargeV3Turbo,
+        ]
+    }
+}
+
+impl Default for WhisperModel {
+    fn default() -> Self {
+        Self::Base // Good balance of speed and accuracy
+    }
+}
+
+/// Largest beam whisper.cpp supports (`WHISPER_MAX_DECODERS`).
+const MAX_BEAM_SIZE: u32 = 8;
+
+/// Decoding parameters passed to whisper.cpp.
+///
+/// The defaults match what the provider used before these were configurable: greedy
+/// decoding with whisper.cpp's temperature fallback. A larger beam is more accurate and
+/// proportionally slower.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WhisperDecodingParams {
+    /// Beam search width (0 or 1 = greedy decoding)
+    pub beam_size: u32,
+    /// Initial sampling temperature
+    pub temperature: f32,
+    /// Step added to the temperature each time a segment fails the quality checks
+    /// (0 = no fallback)
+    pub temperature_inc: f32,
+    /// Probability above which a segment is treated as silence
+    pub no_speech_threshold: f32,
+    /// Maximum segment length in characters (0 = unlimited)
+    pub max_segment_len: u32,
+}
+
+impl Default for WhisperDecodingParams {
+    fn default() -> Self {
+        Self {
+            beam_size: 0,
+            temperature: 0.0,
+            temperature_inc: 0.2,
+            no_speech_threshold: 0.6,
+            max_segment_len: 0,
+        }
+    }
+}
+
+impl WhisperDecodingParams {
+    /// Clamp hand-edited values into ranges whisper.cpp accepts.
+    pub fn sanitized(&self) -> Self {
+        let unit = |v: f32, fallback: f32| if v.is_finite() { v.clamp(0.0, 1.0) } else { fallback };
+        let defaults = Self::default();
+        Self {
+            beam_size: self.beam_size.min(MAX_BEAM_SIZE),
+            temperature: unit(self.temperature, defaults.temperature),
+            temperature_inc: unit(self.temperature_inc, defaults.temperature_inc),
+            no_speech_threshold: unit(self.no_speech_threshold, defaults.no_speech_threshold),
+            max_segment_len: self.max_segment_len,
+        }
+    }
+
+    fn sampling_strategy(&self) -> SamplingStrategy {
+        if self.beam_size > 1 {
+            SamplingStrategy::BeamSearch {
+                beam_size: self.beam_size as i32,
+                // Ignored by whisper.cpp.
+                patience: -1.0,
+            }
+        } else {
+            SamplingStrategy::Greedy { best_of: 1 }
+        }
+    }
+
+    fn apply(&self, params: &mut FullParams) {
+        params.set_temperature(self.temperature);
+        params.set_temperature_inc(self.temperature_inc);
+        params.set_no_speech_thold(self.no_speech_threshold);
+        if self.max_segment_len > 0 {
+            // whisper.cpp only splits segments when token timestamps are on.
+            params.set_token_timestamps(true);
+            params.set_split_on_word(true);
+            params.set_max_len(self.max_segment_len as i32);
+        }
+    }
+}
+
+/// Cores left free for the UI and audio capture when the thread count is auto-detected.
+const RESERVED_CORES: usize = 2;
+
+/// How often a paused inference checks whether the recording has ended.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Scheduling priority of the inference thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InferencePriority {
+    Normal,
+    /// Below normal, so the UI and audio thread win when cores are contended.
+    #[default]
+    Low,
+    /// Only runs on otherwise idle cores.
+    Idle,
+}
+
+/// Thread-pool settings for local inference (stored as `whisper_threading`).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WhisperThreading {
+    /// Worker threads (0 = all cores but two)
+    pub n_threads: u32,
+    pub priority: InferencePriority,
+    /// Hold inference while a new recording is running.
+    pub pause_while_recording: bool,
+}
+
+impl Default for WhisperThreading {
+    fn default() -> Self {
+        Self {
+            n_threads: 0,
+            priority: InferencePriority::default(),
+            pause_while_recording: true,
+        }
+    }
+}
+
+/// Threads to use for `n_threads` (0 = auto) on a machine with `cores` cores.
+fn resolve_thread_count(n_threads: u32, cores: usize) -> u32 {
+    if n_threads > 0 {
+        return n_threads;
+    }
+    cores.saturating_sub(RESERVED_CORES).max(1) as u32
+}
+
+/// Apply `priority` to the calling thread.
+///
+/// On Linux the nice value is per thread and inherited by whisper.cpp's workers; on
+/// macOS and Windows only the calling thread is affected. Lowering the priority can't be
+/// undone without privileges, so this is only called on a dedicated thread.
+fn set_current_thread_priority(priority: InferencePriority) {
+    if priority == InferencePriority::Normal {
+        return;
+    }
+
+    #[cfg(target_os = "linux")]
+    let ok = {
+        let nice = match priority {
+            InferencePriority::Idle => 19,
+            _ => 10,
+        };
+        unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) == 0 }
+    };
+    #[cfg(target_os = "macos")]
+    let ok = unsafe { libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG) == 0 };
+    #[cfg(target_os = "windows")]
+    let ok = {
+        use windows::Win32::System::Threading::{
+            GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_BELOW_NORMAL, THREAD_PRIORITY_IDLE,
+        };
+        let level = match priority {
+            InferencePriority::Idle => THREAD_PRIORITY_IDLE,
+            _ => THREAD_PRIORITY_BELOW_NORMAL,
+        };
+        unsafe { SetThreadPriority(GetCurrentThread(), level).is_ok() }
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let ok = false;
+
+    if !ok {
+        log::warn!("Local Whisper: could not set {:?} thread priority", priority);
+    }
+}
+
+/// Block while `signal` is set (a recording is in progress).
+fn wait_while_recording(signal: &AtomicBool) {
+    if !signal.load(Ordering::Relaxed) {
+        return;
+    }
+    log::info!("Local Whisper: paused until the current recording ends");
+    while signal.load(Ordering::Relaxed) {
+        std::thread::sleep(PAUSE_POLL_INTERVAL);
+    }
+}
+
+/// Configuration for the local Whisper provider
+#[derive(Debug, Clone)]
+pub struct LocalWhisperConfig {
+    /// Path to the model file
+    pub model_path: PathBuf,
+    /// Language to use (None for auto-detect)
+    pub language: Option<String>,
+    /// Whether to translate to English
+    pub translate: bool,
+    /// Number of threads to use (0 = all cores but two)
+    pub n_threads: u32,
+    /// Scheduling priority of the inference thread
+    pub priority: InferencePriority,
+    /// Inference waits while this is set; the pipeline sets it during recordings.
+    pub pause_signal: Option<Arc<AtomicBool>>,
+    /// Beam size, temperature schedule and segmenting
+    pub decoding: WhisperDecodingParams,
+}
+
+impl Default for LocalWhisperConfig {
+    fn default() -> Self {
+        Self {
+            model_path: PathBuf::new(),
+            language: Some("en".to_string()),
+            translate: false,
+            n_threads: 0, // Auto-detect
+            priority: InferencePriority::default(),
+            pause_signal: None,
+            decoding: WhisperDecodingParams::default(),
+        }
+    }
+}
+
+/// Local Whisper STT provider
+pub struct LocalWhisperProvider {
+    ctx: Arc<WhisperContext>,
+    config: LocalWhisperConfig,
+}
+
+impl LocalWhisperProvider {
+    /// Create a new provider with the given model path
+    pub fn new(model_path: PathBuf) -> Result<Self, SttError> {
+        Self::with_config(LocalWhisperConfig {
+            model_path,
+            ..Default::default()
+        })
+    }
+
+    /// Create a new provider with custom configuration
+    pub fn with_config(config: LocalWhisperConfig) -> Result<Self, SttError> {
+        if !config.model_path.exists() {
+            return Err(SttError::Audio(format!(
+                "Model file not found: {}",
+                config.model_path.display()
+            )));
+        }
+
+        let ctx_params = WhisperContextParameters::default();
+
+        let ctx = WhisperContext::new_with_params(
+            config.model_path.to_str().ok_or_else(|| {
+                SttError::Audio("Invalid model path encoding".to_string())
+            })?,
+            ctx_params,
+        )
+        .map_err(|e| SttError::Audio(format!("Failed to load Whisper model: {}", e)))?;
+
+        Ok(Self {
+            ctx: Arc::new(ctx),
+            config,
+        })
+    }
+
+    /// Check if a model file exists at the given path
+    pub fn model_exists(model_path: &PathBuf) -> bool {
+        model_path.exists() && model_path.is_file()
+    }
+
+    /// Get the default models directory
+    pub fn default_models_dir() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|d| {
+            d.join("tangerine-voice").join("models")
+        })
+    }
+}
+
+#[async_trait]
+impl SttProvider for LocalWhisperProvider {
+    async fn transcribe(&self, audio: Bytes, format: &AudioFormat) -> Result<String, SttError> {
+        self.transcribe_with_context(audio, format, None).await
+    }
+
+    async fn transcribe_with_context(
+        &self,
+        audio: Bytes,
+        _format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<String, SttError> {
+        // Decode WAV to f32 samples
+        let samples = decode_wav_to_f32_mono_16khz(&audio)?;
+
+        if samples.is_empty() {
+            return Ok(String::new());
+        }
+
+        // Clone what we need for the inference thread
+        let ctx = self.ctx.clone();
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let config = LocalWhisperConfig {
+            n_threads: resolve_thread_count(self.config.n_threads, cores),
+            decoding: self.config.decoding.sanitized(),
+            ..self.config.clone()
+        };
+        // Whisper reads at most ~224 prompt tokens; NUL bytes can't cross the C boundary.
+        let initial_prompt = super::prompt_with_context(None, context, 224)
+            .map(|prompt| prompt.replace('\0', ""));
+
+        // whisper-rs is synchronous. It runs on its own thread rather than the blocking
+        // pool because a lowered priority can't be raised again afterwards.
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        let abort_on_drop = AbortOnDrop::default();
+        let abort_signal = abort_on_drop.0.clone();
+        std::thread::Builder::new()
+            .name("local-whisper".to_string())
+            .spawn(move || {
+                set_current_thread_priority(config.priority);
+                if let Some(signal) = &config.pause_signal {
+                    wait_while_recording(signal);
+                }
+                let result = run_inference(&ctx, &samples, config, initial_prompt, abort_signal);
+                let _ = result_tx.send(result);
+            })
+            .map_err(|e| SttError::Audio(format!("Failed to start Whisper thread: {}", e)))?;
+
+        result_rx
+            .await
+            .map_err(|_| SttError::Audio("Whisper thread exited without a result".to_string()))?
+    }
+
+    fn name(&self) -> &'static str {
+        "local-whisper"
+    }
+}
+
+/// Sets its flag when dropped. Held by the transcription future, so dropping that future
+/// (cancelled, timed out) tells the inference thread to stop; once inference is done the
+/// flag no longer matters.
+#[derive(Default)]
+struct AbortOnDrop(Arc<AtomicBool>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Run whisper.cpp over `samples` (blocking). Stops early once `abort` is set.
+fn run_inference(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    config: LocalWhisperConfig,
+    initial_prompt: Option<String>,
+    abort: Arc<AtomicBool>,
+) -> Result<String, SttError> {
+    let LocalWhisperConfig {
+        language,
+        translate,
+        n_threads,
+        pause_signal,
+        decoding,
+        ..
+    } = config;
+
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| SttError::Audio(format!("Failed to create Whisper state: {}", e)))?;
+
+    let mut params = FullParams::new(decoding.sampling_strategy());
+    decoding.apply(&mut params);
+
+    // Set language
+    if let Some(lang) = &language {
+        params.set_language(Some(lang));
+    }
+
+    // Set translate mode
+    params.set_translate(translate);
+
+    // Earlier dictation as initial tokens, for consistent names and casing
+    if let Some(prompt) = &initial_prompt {
+        params.set_initial_prompt(prompt);
+    }
+
+    params.set_n_threads(n_threads as i32);
+
+    // whisper.cpp reports progress between 30s windows; a long file being
+    // transcribed yields there when the user starts dictating.
+    if let Some(signal) = pause_signal {
+        params.set_progress_callback_safe(move |_progress: i32| {
+            wait_while_recording(&signal)
+        });
+    }
+
+    // Nobody is waiting for the result anymore: stop instead of finishing the file.
+    params.set_abort_callback_safe(move || abort.load(Ordering::Relaxed));
+
+    // Disable printing to reduce noise
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+
+    // Run inference
+    state
+        .full(params, samples)
+        .map_err(|e| SttError::Audio(format!("Whisper inference failed: {}", e)))?;
+
+    // Collect results
+    let num_segments = state.full_n_segments().map_err(|e| {
+        SttError::Audio(format!("Failed to get segment count: {}", e))
+    })?;
+
+    let mut text = String::new();
+    for i in 0..num_segments {
+        if let Ok(segment_text) = state.full_get_segment_text(i) {
+            text.push_str(&segment_text);
+        }
+    }
+
+    Ok(text.trim().to_string())
+}
+
+/// Decode WAV audio to f32 samples, converting to mono 16kHz if needed
+fn decode_wav_to_f32_mono_16khz(wav_bytes: &[u8]) -> Result<Vec<f32>, SttError> {
+    use std::io::Cursor;
+
+    let cursor = Cursor::new(wav_bytes);
+    let mut reader = hound::WavReader::new(cursor)
+        .map_err(|e| SttError::Audio(format!("Failed to read WAV: {}", e)))?;
+
+    let spec = reader.spec();
+    let sample_rate = spec.sample_rate;
+    let channels = spec.channels as usize;
+
+    // Read samples based on format
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|s| s.unwrap_or(0.0))
+            .collect(),
+        hound::SampleFormat::Int => {
+            let bits = spec.bits_per_sample;
+            let max_val = (1 << (bits - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.unwrap_or(0) as f32 / max_val)
+                .collect()
+        }
+    };
+
+    // Convert to mono if stereo
+    let mono_samples: Vec<f32> = if channels > 1 {
+        samples
+            .chunks(channels)
+            .map(|chunk| chunk.iter().sum::<f32>() / channels as f32)
+            .collect()
+    } else {
+        samples
+    };
+
+    // Resample to 16kHz if needed
+    if sample_rate != 16000 {
+        Ok(crate::vad::resample_to_16khz_batch(&mono_samples, sample_rate))
+    } else {
+        Ok(mono_samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_filenames() {
+        assert_eq!(WhisperModel::Tiny.filename(), "ggml-tiny.bin");
+        assert_eq!(WhisperModel::BaseEn.filename(), "ggml-base.en.bin");
+        assert_eq!(WhisperModel::LargeV3.filename(), "ggml-large-v3.bin");
+    }
+
+    #[test]
+    fn test_model_urls() {
+        let url = WhisperModel::Base.download_url();
+        assert!(url.contains("huggingface.co"));
+        assert!(url.contains("ggml-base.bin"));
+    }
+
+    #[test]
+    fn test_english_only_models() {
+        assert!(WhisperModel::TinyEn.is_english_only());
+        assert!(WhisperModel::BaseEn.is_english_only());
+        assert!(!WhisperModel::Tiny.is_english_only());
+        assert!(!WhisperModel::LargeV3.is_english_only());
+    }
+
+    #[test]
+    fn test_decoding_params_sanitized() {
+        let params: WhisperDecodingParams =
+            serde_json::from_value(serde_json::json!({ "beam_size": 32, "temperature": 1.5 }))
+                .unwrap();
+        let params = params.sanitized();
+        assert_eq!(params.beam_size, MAX_BEAM_SIZE);
+        assert_eq!(params.temperature, 1.0);
+        // Missing keys keep their defaults.
+        assert_eq!(params.no_speech_threshold, 0.6);
+    }
+
+    #[test]
+    fn test_auto_thread_count_leaves_cores_free() {
+        assert_eq!(resolve_thread_count(0, 8), 6);
+        assert_eq!(resolve_thread_count(0, 2), 1);
+        assert_eq!(resolve_thread_count(3, 8), 3);
+    }
+
+    #[test]
+    fn test_all_models() {
+        let models = WhisperModel::all();
+        assert!(models.len() >= 10);
+    }
+}
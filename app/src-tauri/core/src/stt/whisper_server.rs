@@ -0,0 +1,203 @@
+//! whisper.cpp server STT provider implementation.
+//!
+//! Posts the recording to the `/inference` endpoint of a `whisper-server` (from whisper.cpp)
+//! running elsewhere, e.g. a GPU box on the LAN. The server needs no API key.
+
+use super::{
+    fields_json, prompt_with_context, with_extra_params, AudioFormat, ExtraParams, SttError,
+    SttProvider,
+};
+use crate::request_log::RequestLogStore;
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::multipart;
+use serde_json::json;
+use std::time::Duration;
+
+/// Where `whisper-server` listens by default.
+pub const DEFAULT_WHISPER_SERVER_URL: &str = "http://127.0.0.1:8080";
+
+/// whisper.cpp server provider for speech-to-text
+pub struct WhisperServerSttProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    default_prompt: Option<String>,
+    /// Language code; `None` lets the server use its own setting
+    language: Option<String>,
+    extra_params: ExtraParams,
+    request_log_store: Option<RequestLogStore>,
+}
+
+impl WhisperServerSttProvider {
+    const PROMPT_MAX_CHARS: usize = 896;
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+    /// Create a new whisper.cpp server provider
+    ///
+    /// # Arguments
+    /// * `url` - Server URL (e.g. "http://192.168.1.20:8080"); `/inference` is appended
+    ///   unless already there. `None` for `DEFAULT_WHISPER_SERVER_URL`.
+    /// * `default_prompt` - Optional initial prompt
+    /// * `language` - Language code (e.g. "en")
+    pub fn new(
+        url: Option<String>,
+        default_prompt: Option<String>,
+        language: Option<String>,
+    ) -> Self {
+        Self {
+            client: crate::http::shared_client(),
+            endpoint: inference_endpoint(url.as_deref().unwrap_or(DEFAULT_WHISPER_SERVER_URL)),
+            default_prompt,
+            language,
+            extra_params: ExtraParams::new(),
+            request_log_store: None,
+        }
+    }
+
+    /// Extra multipart fields (e.g. `beam_size`), sent as is.
+    pub fn with_extra_params(mut self, extra_params: ExtraParams) -> Self {
+        self.extra_params = extra_params;
+        self
+    }
+
+    pub fn with_request_log_store(mut self, store: Option<RequestLogStore>) -> Self {
+        self.request_log_store = store;
+        self
+    }
+}
+
+/// The `/inference` URL of a server.
+fn inference_endpoint(url: &str) -> String {
+    let base = url.trim().trim_end_matches('/');
+    if base.ends_with("/inference") {
+        base.to_string()
+    } else {
+        format!("{}/inference", base)
+    }
+}
+
+#[async_trait]
+impl SttProvider for WhisperServerSttProvider {
+    async fn transcribe(&self, audio: Bytes, format: &AudioFormat) -> Result<String, SttError> {
+        self.transcribe_with_context(audio, format, None).await
+    }
+
+    async fn transcribe_with_context(
+        &self,
+        audio: Bytes,
+        _format: &AudioFormat,
+        context: Option<&str>,
+    ) -> Result<String, SttError> {
+        let prompt =
+            prompt_with_context(self.default_prompt.as_deref(), context, Self::PROMPT_MAX_CHARS);
+        let mut fields = vec![("response_format", "json".to_string())];
+        if let Some(prompt) = prompt {
+            fields.push(("prompt", prompt));
+        }
+        if let Some(language) = &self.language {
+            fields.push(("language", language.clone()));
+        }
+        let fields = with_extra_params(fields, &self.extra_params);
+
+        if let Some(store) = &self.request_log_store {
+            let request_json = json!({
+                "provider": "whisper-server",
+                "endpoint": self.endpoint,
+                "content_type": "multipart/form-data",
+                "fields": fields_json(&fields),
+                "file": {
+                    "name": "audio.wav",
+                    "mime": "audio/wav",
+                    "bytes": audio.len(),
+                    "data": "<binary audio omitted>",
+                }
+            });
+
+            store.with_current(|log| {
+                log.stt_request_json = Some(request_json);
+            });
+        }
+
+        let audio_len = audio.len() as u64;
+        let part = multipart::Part::stream_with_length(audio, audio_len)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| SttError::Audio(format!("Failed to create multipart: {}", e)))?;
+
+        let form = fields
+            .into_iter()
+            .fold(multipart::Form::new().part("file", part), |form, (name, value)| {
+                form.text(name, value)
+            });
+
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .timeout(Self::REQUEST_TIMEOUT)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| if e.is_timeout() { SttError::Timeout } else { SttError::Network(e) })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let retry_after = crate::retry::parse_retry_after(response.headers());
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(SttError::from_status(
+                status,
+                retry_after,
+                format!("Whisper server error ({}): {}", status, error_text),
+            ));
+        }
+
+        let result: serde_json::Value = response.json().await?;
+
+        if let Some(store) = &self.request_log_store {
+            let result_for_log = result.clone();
+            store.with_current(|log| {
+                log.stt_response_json = Some(result_for_log);
+            });
+        }
+        if let Some(error) = result["error"].as_str() {
+            return Err(SttError::Api(format!("Whisper server error: {}", error)));
+        }
+
+        Ok(result["text"].as_str().unwrap_or("").trim().to_string())
+    }
+
+    async fn warm_up(&self) -> Result<(), SttError> {
+        crate::network::preconnect(&self.client, &self.endpoint).await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "whisper-server"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_creation() {
+        let provider = WhisperServerSttProvider::new(None, None, None);
+        assert_eq!(provider.name(), "whisper-server");
+        assert_eq!(provider.endpoint, "http://127.0.0.1:8080/inference");
+    }
+
+    #[test]
+    fn test_inference_endpoint() {
+        assert_eq!(
+            inference_endpoint(" http://10.0.0.5:8080/ "),
+            "http://10.0.0.5:8080/inference"
+        );
+        assert_eq!(
+            inference_endpoint("http://gpu.lan/whisper/inference"),
+            "http://gpu.lan/whisper/inference"
+        );
+    }
+}
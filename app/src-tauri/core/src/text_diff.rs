@@ -0,0 +1,148 @@
+//! Word-level text diff (used to compare rewrite outputs in `compare_prompts`).
+//!
+//! Text is split into words, whitespace runs and single punctuation characters, and the
+//! segments come from a longest-common-subsequence walk over those tokens. Concatenating
+//! the `Equal` + `Delete` segments gives the old text back, `Equal` + `Insert` the new one.
+
+use serde::Serialize;
+
+/// Token pairs above which the LCS table gets too large; the texts are then reported as
+/// replaced wholesale.
+const MAX_TABLE_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffSegment {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+#[derive(PartialEq)]
+enum TokenKind {
+    Word,
+    Space,
+    Other,
+}
+
+fn kind(c: char) -> TokenKind {
+    if c.is_alphanumeric() || c == '\'' {
+        TokenKind::Word
+    } else if c.is_whitespace() {
+        TokenKind::Space
+    } else {
+        TokenKind::Other
+    }
+}
+
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let current = kind(c);
+        let continues = chars
+            .peek()
+            .is_some_and(|&(_, next)| current != TokenKind::Other && kind(next) == current);
+        if !continues {
+            let end = i + c.len_utf8();
+            tokens.push(&text[start..end]);
+            start = end;
+        }
+    }
+    tokens
+}
+
+fn push(segments: &mut Vec<DiffSegment>, op: DiffOp, text: &str) {
+    match segments.last_mut() {
+        Some(last) if last.op == op => last.text.push_str(text),
+        _ => segments.push(DiffSegment {
+            op,
+            text: text.to_string(),
+        }),
+    }
+}
+
+/// Diff `old` against `new`; removals come before insertions at each change.
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffSegment> {
+    let a = tokenize(old);
+    let b = tokenize(new);
+    let mut segments = Vec::new();
+
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_TABLE_CELLS {
+        if !old.is_empty() {
+            push(&mut segments, DiffOp::Delete, old);
+        }
+        if !new.is_empty() {
+            push(&mut segments, DiffOp::Insert, new);
+        }
+        return segments;
+    }
+
+    // lcs[i][j]: length of the LCS of a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            push(&mut segments, DiffOp::Equal, a[i]);
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            push(&mut segments, DiffOp::Delete, a[i]);
+            i += 1;
+        } else {
+            push(&mut segments, DiffOp::Insert, b[j]);
+            j += 1;
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(op: DiffOp, text: &str) -> DiffSegment {
+        DiffSegment {
+            op,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_word_diff() {
+        assert_eq!(
+            word_diff("The quick fox, jumps.", "The slow fox jumps!"),
+            vec![
+                seg(DiffOp::Equal, "The "),
+                seg(DiffOp::Delete, "quick"),
+                seg(DiffOp::Insert, "slow"),
+                seg(DiffOp::Equal, " fox"),
+                seg(DiffOp::Delete, ","),
+                seg(DiffOp::Equal, " jumps"),
+                seg(DiffOp::Delete, "."),
+                seg(DiffOp::Insert, "!"),
+            ]
+        );
+        assert_eq!(word_diff("same", "same"), vec![seg(DiffOp::Equal, "same")]);
+        assert_eq!(word_diff("", "new"), vec![seg(DiffOp::Insert, "new")]);
+        assert!(word_diff("", "").is_empty());
+    }
+}
@@ -0,0 +1,933 @@
+//! Voice Activity Detection (VAD) module.
+//!
+//! This module provides voice activity detection to automatically detect
+//! when speech starts and stops. It uses the webrtc-vad crate and includes
+//! proper handling of pre-roll buffering and hangover periods.
+
+use rubato::Resampler;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ops::Range;
+use webrtc_vad::{Vad, VadMode};
+
+/// VAD aggressiveness level (maps to webrtc-vad modes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VadAggressiveness {
+    /// Quality mode - less aggressive, fewer false negatives
+    Quality,
+    /// Low bitrate mode
+    LowBitrate,
+    /// Aggressive mode
+    Aggressive,
+    /// Very aggressive mode - more aggressive, more false negatives
+    VeryAggressive,
+}
+
+impl VadAggressiveness {
+    fn to_vad_mode(self) -> VadMode {
+        match self {
+            VadAggressiveness::Quality => VadMode::Quality,
+            VadAggressiveness::LowBitrate => VadMode::LowBitrate,
+            VadAggressiveness::Aggressive => VadMode::Aggressive,
+            VadAggressiveness::VeryAggressive => VadMode::VeryAggressive,
+        }
+    }
+}
+
+impl Default for VadAggressiveness {
+    fn default() -> Self {
+        VadAggressiveness::Aggressive
+    }
+}
+
+/// Configuration for the VAD
+#[derive(Debug, Clone)]
+pub struct VadConfig {
+    /// VAD aggressiveness mode (higher = more aggressive filtering)
+    pub aggressiveness: VadAggressiveness,
+    /// Number of consecutive speech frames required to trigger speech start
+    pub speech_frames_threshold: u32,
+    /// Number of consecutive silence frames required to trigger speech end (hangover)
+    pub hangover_frames: u32,
+    /// Pre-roll duration in milliseconds (audio to keep before speech start)
+    pub pre_roll_ms: u32,
+    /// Frame duration in milliseconds (10, 20, or 30ms supported by webrtc-vad)
+    pub frame_duration_ms: u32,
+    /// Sample rate to use for VAD (must be 8000, 16000, 32000, or 48000)
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub sample_rate: u32,
+    /// Frames quieter than this (RMS, dBFS) are never speech. `None` disables the gate.
+    pub energy_threshold_dbfs: Option<f32>,
+    /// Measure the ambient noise over this many milliseconds at the start of the stream and
+    /// adapt to it (see `NoiseProfile::apply`). 0 disables calibration.
+    pub calibration_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            aggressiveness: VadAggressiveness::Aggressive,
+            speech_frames_threshold: 3,
+            hangover_frames: 30, // ~300ms at 10ms frames
+            pre_roll_ms: 300,
+            frame_duration_ms: 10,
+            sample_rate: 16000,
+            energy_threshold_dbfs: None,
+            calibration_ms: 0,
+        }
+    }
+}
+
+/// How far above the noise floor a frame must be to count as speech.
+const NOISE_MARGIN_DB: f32 = 10.0;
+
+/// Noise floors above which webrtc-vad is made more aggressive, so steady background noise
+/// (fans, traffic) isn't taken for speech.
+const NOISY_FLOOR_DBFS: f32 = -55.0;
+const VERY_NOISY_FLOOR_DBFS: f32 = -45.0;
+
+/// Share of calibration frames assumed to be noise. Using a low percentile of the frame
+/// levels keeps a few words spoken during calibration from raising the noise floor.
+const NOISE_FLOOR_PERCENTILE: f32 = 0.2;
+
+/// RMS level of a frame in dBFS (-120 for digital silence).
+fn frame_level_dbfs(samples: &[i16]) -> f32 {
+    if samples.is_empty() {
+        return -120.0;
+    }
+    let sum_sq: f64 = samples
+        .iter()
+        .map(|&s| {
+            let f = s as f64 / i16::MAX as f64;
+            f * f
+        })
+        .sum();
+    let rms = (sum_sq / samples.len() as f64).sqrt() as f32;
+    if rms > 0.0 {
+        (20.0 * rms.log10()).max(-120.0)
+    } else {
+        -120.0
+    }
+}
+
+/// Ambient noise level of a microphone.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NoiseProfile {
+    /// RMS level of the background noise, in dBFS.
+    pub noise_floor_dbfs: f32,
+}
+
+impl NoiseProfile {
+    /// Estimate the noise floor from per-frame levels (dBFS).
+    pub fn from_frame_levels(levels_dbfs: &[f32]) -> Option<Self> {
+        if levels_dbfs.is_empty() {
+            return None;
+        }
+        let mut sorted = levels_dbfs.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let idx = ((sorted.len() - 1) as f32 * NOISE_FLOOR_PERCENTILE) as usize;
+        Some(Self {
+            noise_floor_dbfs: sorted[idx],
+        })
+    }
+
+    /// Measure mono `samples` (e.g. a recording of the room with nobody talking).
+    pub fn measure(samples: &[f32], sample_rate: u32) -> Option<Self> {
+        let resampled = resample_to_16khz_batch(samples, sample_rate);
+        // 30ms frames, as used by the realtime VAD.
+        let levels: Vec<f32> = resampled
+            .chunks_exact(480)
+            .map(|frame| frame_level_dbfs(&f32_to_i16(frame)))
+            .collect();
+        Self::from_frame_levels(&levels)
+    }
+
+    /// Adapt `config` to this noise level: gate out frames that aren't clearly above the
+    /// floor, and raise the webrtc-vad aggressiveness in noisy rooms (never lower it).
+    pub fn apply(&self, config: &mut VadConfig) {
+        config.energy_threshold_dbfs = Some(self.noise_floor_dbfs + NOISE_MARGIN_DB);
+        let for_noise = if self.noise_floor_dbfs >= VERY_NOISY_FLOOR_DBFS {
+            VadAggressiveness::VeryAggressive
+        } else if self.noise_floor_dbfs >= NOISY_FLOOR_DBFS {
+            VadAggressiveness::Aggressive
+        } else {
+            config.aggressiveness
+        };
+        config.aggressiveness = config.aggressiveness.max(for_noise);
+    }
+}
+
+/// Frame levels collected while calibrating.
+struct Calibration {
+    frames_left: u32,
+    levels: Vec<f32>,
+}
+
+/// Events emitted by the VAD
+#[derive(Debug, Clone)]
+pub enum VadEvent {
+    /// No significant event
+    None,
+    /// Speech has started, includes pre-roll audio
+    SpeechStart {
+        /// Pre-roll audio samples (before speech was detected)
+        #[cfg_attr(not(test), allow(dead_code))]
+        pre_roll: Vec<i16>,
+    },
+    /// Speech has ended
+    SpeechEnd,
+}
+
+/// Voice Activity Detector with pre-roll buffering and hangover
+pub struct VoiceActivityDetector {
+    vad: Vad,
+    config: VadConfig,
+    /// Whether we're currently in a speech segment
+    is_speaking: bool,
+    /// Count of consecutive silence frames
+    silence_frames: u32,
+    /// Count of consecutive speech frames
+    speech_frames: u32,
+    /// Pre-roll ring buffer storing recent audio frames
+    pre_roll_buffer: VecDeque<Vec<i16>>,
+    /// Maximum number of frames to keep in pre-roll buffer
+    pre_roll_max_frames: usize,
+    /// Ambient noise measurement in progress (see `VadConfig::calibration_ms`)
+    calibration: Option<Calibration>,
+}
+
+impl VoiceActivityDetector {
+    /// Create a new VAD with the given configuration
+    pub fn new(config: VadConfig) -> Self {
+        let mut vad = Vad::new();
+        vad.set_mode(config.aggressiveness.to_vad_mode());
+        vad.set_sample_rate(webrtc_vad::SampleRate::Rate16kHz);
+
+        // Calculate pre-roll buffer size in frames
+        let pre_roll_max_frames =
+            (config.pre_roll_ms / config.frame_duration_ms) as usize;
+
+        let calibration_frames = config.calibration_ms / config.frame_duration_ms.max(1);
+        let calibration = (calibration_frames > 0).then(|| Calibration {
+            frames_left: calibration_frames,
+            levels: Vec::with_capacity(calibration_frames as usize),
+        });
+
+        Self {
+            vad,
+            config,
+            is_speaking: false,
+            silence_frames: 0,
+            speech_frames: 0,
+            pre_roll_buffer: VecDeque::with_capacity(pre_roll_max_frames + 1),
+            pre_roll_max_frames,
+            calibration,
+        }
+    }
+
+    /// Process a frame of audio samples and return any VAD events
+    ///
+    /// # Arguments
+    /// * `samples` - PCM16 audio samples at 16kHz. Frame must be exactly
+    ///   the size expected for the configured frame duration:
+    ///   - 10ms: 160 samples
+    ///   - 20ms: 320 samples
+    ///   - 30ms: 480 samples
+    ///
+    /// # Returns
+    /// A VAD event indicating speech start, speech end, or no event
+    pub fn process_frame(&mut self, samples: &[i16]) -> VadEvent {
+        // Always maintain pre-roll buffer (even during speech for potential restart)
+        self.pre_roll_buffer.push_back(samples.to_vec());
+        if self.pre_roll_buffer.len() > self.pre_roll_max_frames {
+            self.pre_roll_buffer.pop_front();
+        }
+
+        let level_dbfs = frame_level_dbfs(samples);
+        self.calibrate(level_dbfs);
+
+        // Run VAD on the frame
+        let is_speech = self
+            .vad
+            .is_voice_segment(samples)
+            .unwrap_or(false)
+            && self
+                .config
+                .energy_threshold_dbfs
+                .is_none_or(|threshold| level_dbfs >= threshold);
+
+        if is_speech {
+            self.speech_frames += 1;
+            self.silence_frames = 0;
+
+            // Detect speech start after threshold frames of consecutive speech
+            if !self.is_speaking && self.speech_frames >= self.config.speech_frames_threshold {
+                self.is_speaking = true;
+
+                // Collect pre-roll audio
+                let pre_roll: Vec<i16> = self
+                    .pre_roll_buffer
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect();
+
+                log::debug!(
+                    "VAD: Speech started (pre-roll: {} samples, {} frames)",
+                    pre_roll.len(),
+                    self.pre_roll_buffer.len()
+                );
+
+                return VadEvent::SpeechStart { pre_roll };
+            }
+        } else {
+            self.silence_frames += 1;
+            self.speech_frames = 0;
+
+            // Detect speech end after hangover period
+            if self.is_speaking && self.silence_frames >= self.config.hangover_frames {
+                self.is_speaking = false;
+
+                log::debug!(
+                    "VAD: Speech ended (after {} silence frames)",
+                    self.silence_frames
+                );
+
+                return VadEvent::SpeechEnd;
+            }
+        }
+
+        VadEvent::None
+    }
+
+    /// Record a frame level while calibrating; adapt the config once enough were seen.
+    fn calibrate(&mut self, level_dbfs: f32) {
+        let Some(calibration) = self.calibration.as_mut() else {
+            return;
+        };
+        calibration.levels.push(level_dbfs);
+        calibration.frames_left -= 1;
+        if calibration.frames_left > 0 {
+            return;
+        }
+
+        if let Some(profile) = NoiseProfile::from_frame_levels(&calibration.levels) {
+            profile.apply(&mut self.config);
+            self.vad.set_mode(self.config.aggressiveness.to_vad_mode());
+            log::info!(
+                "VAD: Calibrated to a {:.1} dBFS noise floor ({:?}, energy gate {:.1} dBFS)",
+                profile.noise_floor_dbfs,
+                self.config.aggressiveness,
+                profile.noise_floor_dbfs + NOISE_MARGIN_DB
+            );
+        }
+        self.calibration = None;
+    }
+
+    /// Reset the VAD state (call when starting a new recording session)
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn reset(&mut self) {
+        self.is_speaking = false;
+        self.silence_frames = 0;
+        self.speech_frames = 0;
+        self.pre_roll_buffer.clear();
+    }
+
+    /// Check if currently detecting speech
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_speaking(&self) -> bool {
+        self.is_speaking
+    }
+
+    /// Get the expected frame size in samples for the configured duration
+    pub fn frame_size(&self) -> usize {
+        // At 16kHz: 10ms = 160, 20ms = 320, 30ms = 480
+        (16000 * self.config.frame_duration_ms / 1000) as usize
+    }
+
+    /// Get the VAD configuration
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn config(&self) -> &VadConfig {
+        &self.config
+    }
+}
+
+impl Default for VoiceActivityDetector {
+    fn default() -> Self {
+        Self::new(VadConfig::default())
+    }
+}
+
+/// Resample audio from source sample rate to 16kHz for VAD processing
+///
+/// Uses the rubato library for high-quality resampling.
+pub fn resample_to_16khz(samples: &[f32], source_sample_rate: u32) -> Vec<f32> {
+    use rubato::{
+        SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+    };
+
+    if source_sample_rate == 16000 {
+        return samples.to_vec();
+    }
+
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let params = SincInterpolationParameters {
+        sinc_len: 256,
+        f_cutoff: 0.95,
+        interpolation: SincInterpolationType::Linear,
+        oversampling_factor: 256,
+        window: WindowFunction::BlackmanHarris2,
+    };
+
+    let resample_ratio = 16000.0 / source_sample_rate as f64;
+
+    // Create resampler - chunk_size needs to be reasonable
+    let chunk_size = samples.len().max(1024);
+    let mut resampler = match SincFixedIn::<f32>::new(
+        resample_ratio,
+        2.0, // max relative ratio (for variable rate)
+        params,
+        chunk_size,
+        1, // mono
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            log::error!("Failed to create resampler: {}", e);
+            return samples.to_vec();
+        }
+    };
+
+    // Process - rubato expects Vec<Vec<f32>> for channels
+    let waves_in = vec![samples.to_vec()];
+    match resampler.process(&waves_in, None) {
+        Ok(waves_out) => waves_out.into_iter().next().unwrap_or_default(),
+        Err(e) => {
+            log::error!("Resampling failed: {}", e);
+            samples.to_vec()
+        }
+    }
+}
+
+/// Input chunk size for [`resample_to_16khz_batch`].
+const BATCH_RESAMPLE_CHUNK: usize = 1024;
+
+/// Resample a complete mono recording to 16kHz (e.g. before upload).
+///
+/// Unlike [`resample_to_16khz`], which is sized for short VAD frames, this feeds the
+/// resampler in fixed chunks and flushes the samples held back by the filter at the end, so
+/// the tail isn't cut off and the output has the expected length.
+pub fn resample_to_16khz_batch(samples: &[f32], source_sample_rate: u32) -> Vec<f32> {
+    use rubato::{
+        SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+    };
+
+    if source_sample_rate == 16000 || source_sample_rate == 0 {
+        return samples.to_vec();
+    }
+
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let params = SincInterpolationParameters {
+        sinc_len: 256,
+        f_cutoff: 0.95,
+        interpolation: SincInterpolationType::Linear,
+        oversampling_factor: 256,
+        window: WindowFunction::BlackmanHarris2,
+    };
+
+    let resample_ratio = 16000.0 / source_sample_rate as f64;
+    let expected_len = (samples.len() as f64 * resample_ratio).round() as usize;
+
+    let mut resampler =
+        match SincFixedIn::<f32>::new(resample_ratio, 1.0, params, BATCH_RESAMPLE_CHUNK, 1) {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("Failed to create batch resampler: {}", e);
+                return resample_to_16khz(samples, source_sample_rate);
+            }
+        };
+
+    let mut out: Vec<f32> = Vec::with_capacity(expected_len + BATCH_RESAMPLE_CHUNK);
+
+    let result = (|| -> Result<(), rubato::ResampleError> {
+        let mut pos = 0;
+        while samples.len() - pos >= resampler.input_frames_next() {
+            let next = pos + resampler.input_frames_next();
+            let chunk = resampler.process(&[&samples[pos..next]], None)?;
+            out.extend_from_slice(&chunk[0]);
+            pos = next;
+        }
+        if pos < samples.len() {
+            let chunk = resampler.process_partial(Some(&[&samples[pos..]]), None)?;
+            out.extend_from_slice(&chunk[0]);
+        }
+        // Flush the samples still held back by the filter.
+        while out.len() < expected_len {
+            let chunk = resampler.process_partial::<&[f32]>(None, None)?;
+            if chunk[0].is_empty() {
+                break;
+            }
+            out.extend_from_slice(&chunk[0]);
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        log::error!("Batch resampling failed: {}", e);
+        return resample_to_16khz(samples, source_sample_rate);
+    }
+
+    out.truncate(expected_len);
+    out
+}
+
+/// Locate speech in a mono recording, returning sample ranges at `sample_rate`.
+///
+/// Each range starts at the detector's pre-roll and ends after its hangover, so word
+/// onsets and tails are kept. A segment still open at the end of the input is closed at
+/// the last sample.
+pub fn detect_speech_segments(
+    samples: &[f32],
+    sample_rate: u32,
+    config: VadConfig,
+) -> Vec<Range<usize>> {
+    if samples.is_empty() || sample_rate == 0 {
+        return Vec::new();
+    }
+
+    let mut vad = VoiceActivityDetector::new(config);
+    let frame_size = vad.frame_size();
+    let resampled = resample_to_16khz_batch(samples, sample_rate);
+
+    // Map a 16kHz sample index back to the source rate.
+    let to_source = |idx_16k: usize| -> usize {
+        ((idx_16k as u64 * sample_rate as u64) / 16000).min(samples.len() as u64) as usize
+    };
+
+    let mut segments = Vec::new();
+    let mut open_start: Option<usize> = None;
+
+    for (i, frame) in resampled.chunks_exact(frame_size).enumerate() {
+        let frame_end = (i + 1) * frame_size;
+        match vad.process_frame(&f32_to_i16(frame)) {
+            VadEvent::SpeechStart { pre_roll } => {
+                open_start = Some(frame_end.saturating_sub(pre_roll.len()));
+            }
+            VadEvent::SpeechEnd => {
+                if let Some(start) = open_start.take() {
+                    segments.push(to_source(start)..to_source(frame_end));
+                }
+            }
+            VadEvent::None => {}
+        }
+    }
+
+    if let Some(start) = open_start {
+        segments.push(to_source(start)..samples.len());
+    }
+
+    segments
+}
+
+/// Streaming resampler from a fixed source rate to 16kHz.
+///
+/// Keeps one rubato resampler (and its filter state) for the whole stream, so audio can be
+/// pushed in arbitrarily sized callback chunks without per-call setup cost or artifacts at
+/// chunk boundaries. Call [`flush`](Self::flush) at the end of the stream for the tail.
+pub struct StreamingResampler {
+    /// `None` when the source is already 16kHz (or the resampler could not be built).
+    resampler: Option<rubato::SincFixedIn<f32>>,
+    /// Input waiting for a complete resampler chunk.
+    pending: Vec<f32>,
+    ratio: f64,
+    /// Samples pushed in / produced since the last flush or reset.
+    frames_in: u64,
+    frames_out: u64,
+}
+
+impl StreamingResampler {
+    /// Input chunk length: 10ms at the source rate.
+    fn chunk_size(source_sample_rate: u32) -> usize {
+        (source_sample_rate as usize / 100).max(1)
+    }
+
+    pub fn new(source_sample_rate: u32) -> Self {
+        use rubato::{SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction};
+
+        if source_sample_rate == 16000 || source_sample_rate == 0 {
+            return Self { resampler: None, pending: Vec::new(), ratio: 1.0, frames_in: 0, frames_out: 0 };
+        }
+
+        // Shorter filter than the offline path: VAD only needs the speech band, and this
+        // runs for every captured chunk.
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.9,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 128,
+            window: WindowFunction::BlackmanHarris2,
+        };
+
+        let ratio = 16000.0 / source_sample_rate as f64;
+        let resampler = match SincFixedIn::<f32>::new(
+            ratio,
+            1.0,
+            params,
+            Self::chunk_size(source_sample_rate),
+            1,
+        ) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                log::error!("Failed to create streaming resampler: {}", e);
+                None
+            }
+        };
+
+        Self { resampler, pending: Vec::new(), ratio, frames_in: 0, frames_out: 0 }
+    }
+
+    /// Resample `samples`, appending whatever 16kHz output is ready to `out`.
+    pub fn process(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        let Some(resampler) = self.resampler.as_mut() else {
+            out.extend_from_slice(samples);
+            return;
+        };
+
+        self.pending.extend_from_slice(samples);
+        self.frames_in += samples.len() as u64;
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= resampler.input_frames_next() {
+            let end = consumed + resampler.input_frames_next();
+            match resampler.process(&[&self.pending[consumed..end]], None) {
+                Ok(chunk) => {
+                    self.frames_out += chunk[0].len() as u64;
+                    out.extend_from_slice(&chunk[0]);
+                }
+                Err(e) => log::error!("Streaming resample failed: {}", e),
+            }
+            consumed = end;
+        }
+        self.pending.drain(..consumed);
+    }
+
+    /// Push out the remaining input and the samples held back by the filter.
+    pub fn flush(&mut self, out: &mut Vec<f32>) {
+        let Some(resampler) = self.resampler.as_mut() else {
+            return;
+        };
+
+        let expected = (self.frames_in as f64 * self.ratio).round() as u64;
+        let mut pending = std::mem::take(&mut self.pending);
+
+        // The filter holds back a few samples, so the tail can take more than one call.
+        for _ in 0..3 {
+            if self.frames_out >= expected {
+                break;
+            }
+            let input = if pending.is_empty() { None } else { Some([pending.as_slice()]) };
+            match resampler.process_partial(input.as_ref().map(|i| &i[..]), None) {
+                Ok(chunk) => {
+                    let take = chunk[0].len().min((expected - self.frames_out) as usize);
+                    out.extend_from_slice(&chunk[0][..take]);
+                    self.frames_out += take as u64;
+                }
+                Err(e) => {
+                    log::error!("Streaming resample flush failed: {}", e);
+                    break;
+                }
+            }
+            pending.clear();
+        }
+
+        self.reset();
+    }
+
+    /// Drop buffered input and filter state.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.frames_in = 0;
+        self.frames_out = 0;
+        if let Some(resampler) = self.resampler.as_mut() {
+            resampler.reset();
+        }
+    }
+}
+
+/// Convert f32 samples to i16 for webrtc-vad
+pub fn f32_to_i16(samples: &[f32]) -> Vec<i16> {
+    samples
+        .iter()
+        .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect()
+}
+
+/// Convert i16 samples back to f32
+#[allow(dead_code)]
+pub fn i16_to_f32(samples: &[i16]) -> Vec<f32> {
+    samples
+        .iter()
+        .map(|&s| s as f32 / i16::MAX as f32)
+        .collect()
+}
+
+/// Audio frame processor that handles resampling and frame splitting for VAD
+pub struct VadFrameProcessor {
+    vad: VoiceActivityDetector,
+    /// Persistent resampler from the source rate to 16kHz
+    resampler: StreamingResampler,
+    /// Resampled samples waiting for a complete VAD frame
+    resampled_buffer: Vec<f32>,
+}
+
+impl VadFrameProcessor {
+    /// Create a new frame processor
+    pub fn new(config: VadConfig, source_sample_rate: u32) -> Self {
+        Self {
+            vad: VoiceActivityDetector::new(config),
+            resampler: StreamingResampler::new(source_sample_rate),
+            resampled_buffer: Vec::new(),
+        }
+    }
+
+    /// Process incoming audio samples and emit VAD events
+    ///
+    /// This handles:
+    /// - Resampling to 16kHz (with state carried across calls)
+    /// - Splitting into the correct frame size for webrtc-vad
+    ///
+    /// # Returns
+    /// A vector of VAD events (may be empty, one, or multiple)
+    pub fn process(&mut self, samples: &[f32]) -> Vec<VadEvent> {
+        self.resampler.process(samples, &mut self.resampled_buffer);
+        self.drain_frames()
+    }
+
+    /// Flush the resampler at the end of a stream and process the remaining audio.
+    ///
+    /// A trailing partial VAD frame is discarded.
+    pub fn finish(&mut self) -> Vec<VadEvent> {
+        self.resampler.flush(&mut self.resampled_buffer);
+        let events = self.drain_frames();
+        self.resampled_buffer.clear();
+        events
+    }
+
+    fn drain_frames(&mut self) -> Vec<VadEvent> {
+        let mut events = Vec::new();
+        let frame_size = self.vad.frame_size();
+
+        let mut consumed = 0;
+        while self.resampled_buffer.len() - consumed >= frame_size {
+            let vad_frame = f32_to_i16(&self.resampled_buffer[consumed..consumed + frame_size]);
+            consumed += frame_size;
+
+            let event = self.vad.process_frame(&vad_frame);
+            if !matches!(event, VadEvent::None) {
+                events.push(event);
+            }
+        }
+        self.resampled_buffer.drain(..consumed);
+
+        events
+    }
+
+    /// Reset the processor state
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn reset(&mut self) {
+        self.vad.reset();
+        self.resampler.reset();
+        self.resampled_buffer.clear();
+    }
+
+    /// Check if currently detecting speech
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_speaking(&self) -> bool {
+        self.vad.is_speaking()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vad_config_default() {
+        let config = VadConfig::default();
+        assert_eq!(config.frame_duration_ms, 10);
+        assert_eq!(config.hangover_frames, 30);
+        assert_eq!(config.pre_roll_ms, 300);
+    }
+
+    #[test]
+    fn test_vad_creation() {
+        let vad = VoiceActivityDetector::new(VadConfig::default());
+        assert!(!vad.is_speaking());
+        assert_eq!(vad.frame_size(), 160); // 10ms at 16kHz
+    }
+
+    #[test]
+    fn test_vad_frame_size() {
+        let config = VadConfig {
+            frame_duration_ms: 20,
+            ..Default::default()
+        };
+        let vad = VoiceActivityDetector::new(config);
+        assert_eq!(vad.frame_size(), 320); // 20ms at 16kHz
+    }
+
+    #[test]
+    fn test_vad_reset() {
+        let mut vad = VoiceActivityDetector::new(VadConfig::default());
+        // Process some frames to change state
+        let silence = vec![0i16; 160];
+        for _ in 0..10 {
+            vad.process_frame(&silence);
+        }
+        vad.reset();
+        assert!(!vad.is_speaking());
+    }
+
+    #[test]
+    fn test_f32_to_i16_conversion() {
+        let samples = vec![0.0, 0.5, -0.5, 1.0, -1.0];
+        let converted = f32_to_i16(&samples);
+        assert_eq!(converted[0], 0);
+        assert!(converted[1] > 0);
+        assert!(converted[2] < 0);
+        assert_eq!(converted[3], i16::MAX);
+        assert_eq!(converted[4], -i16::MAX);
+    }
+
+    #[test]
+    fn test_i16_to_f32_conversion() {
+        let samples = vec![0, i16::MAX / 2, -i16::MAX / 2];
+        let converted = i16_to_f32(&samples);
+        assert!((converted[0] - 0.0).abs() < 0.001);
+        assert!((converted[1] - 0.5).abs() < 0.01);
+        assert!((converted[2] - (-0.5)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_resample_to_16khz_batch_length_and_alignment() {
+        // 1.5s of a 440Hz tone at 44.1kHz, with a click marking t = 1s.
+        let mut samples: Vec<f32> = (0..66150)
+            .map(|i| 0.1 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44100.0).sin())
+            .collect();
+        samples[44100] = 1.0;
+
+        let out = resample_to_16khz_batch(&samples, 44100);
+        assert_eq!(out.len(), 24000);
+
+        // The click should land at (about) t = 1s in the output too.
+        let peak = out
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .map(|(i, _)| i)
+            .unwrap();
+        assert!((peak as i64 - 16000).abs() <= 2, "peak at {}", peak);
+        // The tail is flushed rather than cut off.
+        assert!(out[23990..].iter().any(|s| s.abs() > 0.05));
+
+        assert_eq!(resample_to_16khz_batch(&samples[..10], 16000).len(), 10);
+        assert!(resample_to_16khz_batch(&[], 48000).is_empty());
+    }
+
+    #[test]
+    fn test_detect_speech_segments_in_silence() {
+        let silence = vec![0.0f32; 48000];
+        assert!(detect_speech_segments(&silence, 48000, VadConfig::default()).is_empty());
+        assert!(detect_speech_segments(&[], 48000, VadConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_streaming_resampler_matches_expected_length() {
+        // 1s at 48kHz, pushed in uneven callback-sized chunks.
+        let samples: Vec<f32> = (0..48000)
+            .map(|i| 0.1 * (2.0 * std::f32::consts::PI * 300.0 * i as f32 / 48000.0).sin())
+            .collect();
+
+        let mut resampler = StreamingResampler::new(48000);
+        let mut out = Vec::new();
+        for chunk in samples.chunks(441) {
+            resampler.process(chunk, &mut out);
+        }
+        let before_flush = out.len();
+        resampler.flush(&mut out);
+
+        assert!(before_flush < 16000);
+        assert_eq!(out.len(), 16000);
+
+        // No discontinuities at chunk boundaries: a 300Hz tone changes slowly at 16kHz.
+        let max_step = out
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_step < 0.02, "max step {}", max_step);
+    }
+
+    #[test]
+    fn test_streaming_resampler_passthrough_at_16khz() {
+        let mut resampler = StreamingResampler::new(16000);
+        let mut out = Vec::new();
+        resampler.process(&[0.1, 0.2, 0.3], &mut out);
+        resampler.flush(&mut out);
+        assert_eq!(out, vec![0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    fn test_frame_processor_creation() {
+        let processor = VadFrameProcessor::new(VadConfig::default(), 44100);
+        assert!(!processor.is_speaking());
+    }
+
+    #[test]
+    fn test_noise_profile_ignores_speech_and_adapts_config() {
+        // Mostly a -50 dBFS room with a burst of speech in it.
+        let mut levels = vec![-50.0f32; 40];
+        levels.extend([-12.0f32; 10]);
+        let profile = NoiseProfile::from_frame_levels(&levels).unwrap();
+        assert_eq!(profile.noise_floor_dbfs, -50.0);
+
+        let mut config = VadConfig {
+            aggressiveness: VadAggressiveness::Quality,
+            ..Default::default()
+        };
+        profile.apply(&mut config);
+        assert_eq!(config.aggressiveness, VadAggressiveness::Aggressive);
+        assert_eq!(config.energy_threshold_dbfs, Some(-40.0));
+
+        // A quiet room never lowers the configured aggressiveness.
+        let mut config = VadConfig {
+            aggressiveness: VadAggressiveness::VeryAggressive,
+            ..Default::default()
+        };
+        NoiseProfile { noise_floor_dbfs: -80.0 }.apply(&mut config);
+        assert_eq!(config.aggressiveness, VadAggressiveness::VeryAggressive);
+    }
+
+    #[test]
+    fn test_calibration_sets_energy_gate_after_calibration_window() {
+        let config = VadConfig {
+            frame_duration_ms: 30,
+            calibration_ms: 300,
+            ..Default::default()
+        };
+        let mut vad = VoiceActivityDetector::new(config);
+        let noise: Vec<i16> = (0..480).map(|i| if i % 2 == 0 { 100 } else { -100 }).collect();
+
+        for _ in 0..9 {
+            vad.process_frame(&noise);
+        }
+        assert_eq!(vad.config.energy_threshold_dbfs, None);
+
+        vad.process_frame(&noise);
+        let threshold = vad.config.energy_threshold_dbfs.expect("calibrated");
+        assert!((threshold - (frame_level_dbfs(&noise) + NOISE_MARGIN_DB)).abs() < 0.01);
+    }
+}
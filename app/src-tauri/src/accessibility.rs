@@ -0,0 +1,325 @@
+//! Text insertion through the platform accessibility APIs (`OutputMode::Accessibility`).
+//!
+//! Instead of putting the text on the clipboard and faking Ctrl+V, the text is written into
+//! the focused text field directly:
+//! - macOS: sets `AXSelectedText` on the focused `AXUIElement`, which replaces the selection
+//!   (or inserts at the caret). Needs the Accessibility permission.
+//! - Windows: rebuilds the focused element's UI Automation `ValuePattern` value with the text
+//!   spliced in at the selection (located through `TextPattern` when the control has one,
+//!   otherwise the text is appended).
+//!
+//! Fields that don't expose these (terminals, many custom-drawn editors) return an error and
+//! the caller falls back to pasting. Other platforms always return an error.
+//!
+//! The same APIs read the text before the caret (`text_before_caret`), which is given to the
+//! LLM as context when `capture_surrounding_text` is on.
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::c_void;
+
+    type CFTypeRef = *const c_void;
+    type CFStringRef = *const c_void;
+    type AXUIElementRef = *const c_void;
+    type AXError = i32;
+    type CFTypeID = usize;
+
+    const AX_ERROR_SUCCESS: AXError = 0;
+    const AX_VALUE_CF_RANGE_TYPE: u32 = 4;
+    const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CFRange {
+        location: isize,
+        length: isize,
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+        fn AXUIElementCreateSystemWide() -> AXUIElementRef;
+        fn AXUIElementCopyAttributeValue(
+            element: AXUIElementRef,
+            attribute: CFStringRef,
+            value: *mut CFTypeRef,
+        ) -> AXError;
+        fn AXUIElementSetAttributeValue(
+            element: AXUIElementRef,
+            attribute: CFStringRef,
+            value: CFTypeRef,
+        ) -> AXError;
+        fn AXValueGetValue(value: CFTypeRef, value_type: u32, out: *mut c_void) -> bool;
+        fn AXValueGetTypeID() -> CFTypeID;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithBytes(
+            alloc: *const c_void,
+            bytes: *const u8,
+            num_bytes: isize,
+            encoding: u32,
+            is_external_representation: u8,
+        ) -> CFStringRef;
+        fn CFStringGetLength(string: CFStringRef) -> isize;
+        fn CFStringGetCharacters(string: CFStringRef, range: CFRange, buffer: *mut u16);
+        fn CFRelease(cf: CFTypeRef);
+        fn CFGetTypeID(cf: CFTypeRef) -> CFTypeID;
+        fn CFStringGetTypeID() -> CFTypeID;
+    }
+
+    /// An owned Core Foundation object, released on drop.
+    struct Owned(CFTypeRef);
+
+    impl Owned {
+        fn new(ptr: CFTypeRef, what: &str) -> Result<Self, String> {
+            if ptr.is_null() {
+                Err(format!("Failed to create {}", what))
+            } else {
+                Ok(Self(ptr))
+            }
+        }
+
+        fn string(s: &str) -> Result<Self, String> {
+            // SAFETY: the bytes are valid UTF-8 for the given length.
+            let ptr = unsafe {
+                CFStringCreateWithBytes(
+                    std::ptr::null(),
+                    s.as_ptr(),
+                    s.len() as isize,
+                    CF_STRING_ENCODING_UTF8,
+                    0,
+                )
+            };
+            Self::new(ptr, "CFString")
+        }
+    }
+
+    impl Drop for Owned {
+        fn drop(&mut self) {
+            // SAFETY: we own one reference to a non-null CF object.
+            unsafe { CFRelease(self.0) }
+        }
+    }
+
+    /// The focused accessibility element.
+    unsafe fn focused_element() -> Result<Owned, String> {
+        if !AXIsProcessTrusted() {
+            return Err("Accessibility permission not granted".to_string());
+        }
+
+        let system = Owned::new(AXUIElementCreateSystemWide(), "system-wide AX element")?;
+        let focused_attr = Owned::string("AXFocusedUIElement")?;
+        let mut focused: CFTypeRef = std::ptr::null();
+        let err = AXUIElementCopyAttributeValue(system.0, focused_attr.0, &mut focused);
+        if err != AX_ERROR_SUCCESS || focused.is_null() {
+            return Err(format!("No focused accessibility element (AXError {})", err));
+        }
+        Ok(Owned(focused))
+    }
+
+    /// Copy attribute `name` of `element`.
+    unsafe fn copy_attribute(element: &Owned, name: &str) -> Result<Owned, String> {
+        let attr = Owned::string(name)?;
+        let mut value: CFTypeRef = std::ptr::null();
+        let err = AXUIElementCopyAttributeValue(element.0, attr.0, &mut value);
+        if err != AX_ERROR_SUCCESS || value.is_null() {
+            return Err(format!("Focused element has no {} (AXError {})", name, err));
+        }
+        Ok(Owned(value))
+    }
+
+    pub fn text_before_caret() -> Result<String, String> {
+        // SAFETY: plain C calls; every returned object is owned by an `Owned` guard, its type
+        // is checked before type-specific calls, and the character buffer is sized from the
+        // string's own length.
+        unsafe {
+            let focused = focused_element()?;
+            // AXValue is whatever the control holds (a number for sliders, etc.), not
+            // necessarily text.
+            let value = copy_attribute(&focused, "AXValue")?;
+            if CFGetTypeID(value.0) != CFStringGetTypeID() {
+                return Err("Focused element's value is not text".to_string());
+            }
+            let range_value = copy_attribute(&focused, "AXSelectedTextRange")?;
+            if CFGetTypeID(range_value.0) != AXValueGetTypeID() {
+                return Err("Focused element has no caret position".to_string());
+            }
+            let mut range = CFRange::default();
+            if !AXValueGetValue(
+                range_value.0,
+                AX_VALUE_CF_RANGE_TYPE,
+                &mut range as *mut CFRange as *mut c_void,
+            ) {
+                return Err("Focused element has no caret position".to_string());
+            }
+
+            let len = CFStringGetLength(value.0);
+            let end = range.location.clamp(0, len.max(0));
+            let mut utf16 = vec![0u16; end as usize];
+            CFStringGetCharacters(
+                value.0,
+                CFRange {
+                    location: 0,
+                    length: end,
+                },
+                utf16.as_mut_ptr(),
+            );
+            Ok(String::from_utf16_lossy(&utf16))
+        }
+    }
+
+    pub fn insert_text(text: &str) -> Result<(), String> {
+        // SAFETY: plain C calls; every returned object is owned by an `Owned` guard.
+        unsafe {
+            let focused = focused_element()?;
+
+            let selected_text_attr = Owned::string("AXSelectedText")?;
+            let value = Owned::string(text)?;
+            let err = AXUIElementSetAttributeValue(focused.0, selected_text_attr.0, value.0);
+            if err != AX_ERROR_SUCCESS {
+                return Err(format!(
+                    "Focused element does not accept text insertion (AXError {})",
+                    err
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use windows::core::BSTR;
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{
+        CUIAutomation, IUIAutomation, IUIAutomationElement, IUIAutomationTextPattern, IUIAutomationValuePattern,
+        TextPatternRangeEndpoint_End, TextPatternRangeEndpoint_Start, UIA_TextPatternId,
+        UIA_ValuePatternId,
+    };
+
+    /// UTF-16 offsets of the selection (start, end) in the element's text.
+    unsafe fn selection_range(
+        pattern: &IUIAutomationTextPattern,
+    ) -> windows::core::Result<Option<(usize, usize)>> {
+        let selections = pattern.GetSelection()?;
+        if selections.Length()? < 1 {
+            return Ok(None);
+        }
+        let selection = selections.GetElement(0)?;
+        let before = pattern.DocumentRange()?;
+        before.MoveEndpointByRange(
+            TextPatternRangeEndpoint_End,
+            &selection,
+            TextPatternRangeEndpoint_Start,
+        )?;
+        let start = before.GetText(-1)?.len();
+        let end = start + selection.GetText(-1)?.len();
+        Ok(Some((start, end)))
+    }
+
+    /// The focused UI Automation element.
+    unsafe fn focused_element() -> Result<IUIAutomationElement, String> {
+        // Ignore the error if COM is already initialized on this thread.
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+        let automation: IUIAutomation =
+            CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER)
+                .map_err(|e| format!("Failed to create UI Automation: {}", e))?;
+        automation
+            .GetFocusedElement()
+            .map_err(|e| format!("No focused element: {}", e))
+    }
+
+    pub fn text_before_caret() -> Result<String, String> {
+        // SAFETY: COM calls on interfaces obtained from UI Automation in this function.
+        unsafe {
+            let element = focused_element()?;
+            let pattern: IUIAutomationTextPattern = element
+                .GetCurrentPatternAs(UIA_TextPatternId)
+                .map_err(|e| format!("Focused element has no text pattern: {}", e))?;
+            let selections = pattern
+                .GetSelection()
+                .map_err(|e| format!("Failed to read the selection: {}", e))?;
+            if selections.Length().unwrap_or(0) < 1 {
+                return Err("Focused element has no caret".to_string());
+            }
+            let selection = selections
+                .GetElement(0)
+                .map_err(|e| format!("Failed to read the selection: {}", e))?;
+            let before = pattern
+                .DocumentRange()
+                .map_err(|e| format!("Failed to read the document: {}", e))?;
+            before
+                .MoveEndpointByRange(
+                    TextPatternRangeEndpoint_End,
+                    &selection,
+                    TextPatternRangeEndpoint_Start,
+                )
+                .map_err(|e| format!("Failed to locate the caret: {}", e))?;
+            let text = before
+                .GetText(-1)
+                .map_err(|e| format!("Failed to read the text: {}", e))?;
+            Ok(text.to_string())
+        }
+    }
+
+    pub fn insert_text(text: &str) -> Result<(), String> {
+        // SAFETY: COM calls on interfaces obtained from UI Automation in this function.
+        unsafe {
+            let element = focused_element()?;
+            let value: IUIAutomationValuePattern = element
+                .GetCurrentPatternAs(UIA_ValuePatternId)
+                .map_err(|e| format!("Focused element has no value pattern: {}", e))?;
+            if value.CurrentIsReadOnly().map(|b| b.as_bool()).unwrap_or(true) {
+                return Err("Focused element is read-only".to_string());
+            }
+
+            let current: Vec<u16> = value
+                .CurrentValue()
+                .map_err(|e| format!("Failed to read focused element value: {}", e))?
+                .as_wide()
+                .to_vec();
+            let (start, end) = element
+                .GetCurrentPatternAs::<IUIAutomationTextPattern>(UIA_TextPatternId)
+                .ok()
+                .and_then(|pattern| selection_range(&pattern).ok().flatten())
+                .filter(|&(start, end)| start <= end && end <= current.len())
+                .unwrap_or((current.len(), current.len()));
+
+            let mut updated = Vec::with_capacity(current.len() + text.len());
+            updated.extend_from_slice(&current[..start]);
+            updated.extend(text.encode_utf16());
+            updated.extend_from_slice(&current[end..]);
+
+            value
+                .SetValue(&BSTR::from_wide(&updated))
+                .map_err(|e| format!("Focused element rejected the text: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod imp {
+    pub fn insert_text(_text: &str) -> Result<(), String> {
+        Err("Accessibility insertion is not supported on this platform".to_string())
+    }
+
+    pub fn text_before_caret() -> Result<String, String> {
+        Err("Reading text through accessibility is not supported on this platform".to_string())
+    }
+}
+
+/// Write `text` into the focused text field through the accessibility API.
+pub fn insert_text(text: &str) -> Result<(), String> {
+    imp::insert_text(text)
+}
+
+/// Text of the focused field before the caret (or before the selection).
+pub fn text_before_caret() -> Result<String, String> {
+    imp::text_before_caret()
+}
@@ -0,0 +1,329 @@
+//! Scoped access tokens for the local control API.
+//!
+//! Each token carries a set of scopes, so an external controller (e.g. a Stream Deck)
+//! can be given a token that may only start/stop recordings without being able to read
+//! history or transcripts. Only a SHA-256 hash of each secret is persisted; the secret
+//! itself is returned once, when the token is created.
+//!
+//! Every authorization decision is recorded in the request log audit trail
+//! (see `RequestLogStore::record_api_access`).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Prefix for token secrets, so they are easy to recognize (and to scan for if leaked).
+const TOKEN_PREFIX: &str = "tgr_";
+
+/// What a token is allowed to do.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+    /// Start/stop/cancel recordings and query pipeline state.
+    Control,
+    /// Read dictation history entries.
+    ReadHistory,
+    /// Read transcripts and request logs of individual requests.
+    ReadTranscripts,
+    /// Read and change settings (including managing tokens).
+    Admin,
+}
+
+/// Errors returned when a request is not authorized.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ApiAuthError {
+    #[error("Missing or unknown API token")]
+    InvalidToken,
+
+    #[error("API token '{0}' has been revoked")]
+    Revoked(String),
+
+    #[error("API token '{token}' lacks the '{scope:?}' scope")]
+    MissingScope { token: String, scope: ApiScope },
+}
+
+/// A stored API token (without its secret).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub revoked: bool,
+    /// Hex SHA-256 of the secret.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    secret_hash: String,
+}
+
+impl ApiToken {
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        // Admin implies every other scope.
+        self.scopes.contains(&scope) || self.scopes.contains(&ApiScope::Admin)
+    }
+
+    /// Copy without the secret hash, for returning to the UI.
+    fn redacted(&self) -> ApiToken {
+        ApiToken {
+            secret_hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A freshly created token. `secret` is only ever returned here.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedApiToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct ApiTokenData {
+    tokens: Vec<ApiToken>,
+}
+
+fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.trim().as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn generate_secret() -> String {
+    format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Manages loading, saving and checking API tokens
+pub struct ApiTokenStore {
+    data: RwLock<ApiTokenData>,
+    file_path: Option<PathBuf>,
+}
+
+impl ApiTokenStore {
+    /// Create a token store persisted in the given app data directory
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        let file_path = app_data_dir.join("api_tokens.json");
+
+        if let Some(parent) = file_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        let data = crate::persist::read_with_recovery(&file_path).unwrap_or_default();
+
+        Self {
+            data: RwLock::new(data),
+            file_path: Some(file_path),
+        }
+    }
+
+    #[cfg(test)]
+    fn in_memory() -> Self {
+        Self {
+            data: RwLock::new(ApiTokenData::default()),
+            file_path: None,
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.file_path else {
+            return Ok(());
+        };
+        let data = self
+            .data
+            .read()
+            .map_err(|e| format!("Failed to read API tokens: {}", e))?;
+
+        let content = serde_json::to_string_pretty(&*data)
+            .map_err(|e| format!("Failed to serialize API tokens: {}", e))?;
+
+        crate::persist::write_atomic(path, content.as_bytes())
+            .map_err(|e| format!("Failed to write API tokens file: {}", e))
+    }
+
+    /// List all tokens (secrets are never included)
+    pub fn list(&self) -> Result<Vec<ApiToken>, String> {
+        let data = self
+            .data
+            .read()
+            .map_err(|e| format!("Failed to read API tokens: {}", e))?;
+        Ok(data.tokens.iter().map(ApiToken::redacted).collect())
+    }
+
+    /// Create a new token with the given scopes
+    pub fn create(&self, name: String, scopes: Vec<ApiScope>) -> Result<CreatedApiToken, String> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err("Token name must not be empty".to_string());
+        }
+        if scopes.is_empty() {
+            return Err("Token must have at least one scope".to_string());
+        }
+
+        let mut scopes = scopes;
+        scopes.sort_by_key(|s| *s as u8);
+        scopes.dedup();
+
+        let secret = generate_secret();
+        let token = ApiToken {
+            id: Uuid::new_v4().to_string(),
+            name,
+            scopes,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked: false,
+            secret_hash: hash_secret(&secret),
+        };
+
+        {
+            let mut data = self
+                .data
+                .write()
+                .map_err(|e| format!("Failed to write API tokens: {}", e))?;
+            data.tokens.push(token.clone());
+        }
+        self.save()?;
+
+        Ok(CreatedApiToken {
+            token: token.redacted(),
+            secret,
+        })
+    }
+
+    /// Revoke a token by ID. Revoked tokens are kept so the audit trail stays readable.
+    pub fn revoke(&self, id: &str) -> Result<bool, String> {
+        let found = {
+            let mut data = self
+                .data
+                .write()
+                .map_err(|e| format!("Failed to write API tokens: {}", e))?;
+            match data.tokens.iter_mut().find(|t| t.id == id) {
+                Some(token) => {
+                    token.revoked = true;
+                    true
+                }
+                None => false,
+            }
+        };
+
+        if found {
+            self.save()?;
+        }
+        Ok(found)
+    }
+
+    /// Delete a token by ID
+    pub fn delete(&self, id: &str) -> Result<bool, String> {
+        let removed = {
+            let mut data = self
+                .data
+                .write()
+                .map_err(|e| format!("Failed to write API tokens: {}", e))?;
+            let before = data.tokens.len();
+            data.tokens.retain(|t| t.id != id);
+            data.tokens.len() != before
+        };
+
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Check that `secret` belongs to an active token with `scope`.
+    ///
+    /// On success the token's `last_used_at` is updated (in memory; persisted with the
+    /// next change to the store).
+    pub fn authorize(&self, secret: &str, scope: ApiScope) -> Result<ApiToken, ApiAuthError> {
+        let hash = hash_secret(secret);
+        let mut data = self.data.write().map_err(|_| ApiAuthError::InvalidToken)?;
+
+        let token = data
+            .tokens
+            .iter_mut()
+            .find(|t| !t.secret_hash.is_empty() && t.secret_hash == hash)
+            .ok_or(ApiAuthError::InvalidToken)?;
+
+        if token.revoked {
+            return Err(ApiAuthError::Revoked(token.name.clone()));
+        }
+        if !token.has_scope(scope) {
+            return Err(ApiAuthError::MissingScope {
+                token: token.name.clone(),
+                scope,
+            });
+        }
+
+        token.last_used_at = Some(Utc::now());
+        Ok(token.redacted())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoped_token_authorization() {
+        let store = ApiTokenStore::in_memory();
+        let created = store
+            .create("Stream Deck".to_string(), vec![ApiScope::Control])
+            .unwrap();
+        assert!(created.secret.starts_with(TOKEN_PREFIX));
+
+        let token = store.authorize(&created.secret, ApiScope::Control).unwrap();
+        assert_eq!(token.name, "Stream Deck");
+        assert!(token.last_used_at.is_some());
+
+        assert_eq!(
+            store.authorize(&created.secret, ApiScope::ReadHistory).unwrap_err(),
+            ApiAuthError::MissingScope {
+                token: "Stream Deck".to_string(),
+                scope: ApiScope::ReadHistory,
+            }
+        );
+        assert_eq!(
+            store.authorize("tgr_wrong", ApiScope::Control).unwrap_err(),
+            ApiAuthError::InvalidToken
+        );
+
+        assert!(store.revoke(&created.token.id).unwrap());
+        assert_eq!(
+            store.authorize(&created.secret, ApiScope::Control).unwrap_err(),
+            ApiAuthError::Revoked("Stream Deck".to_string())
+        );
+    }
+
+    #[test]
+    fn test_admin_scope_implies_others_and_secrets_are_not_listed() {
+        let store = ApiTokenStore::in_memory();
+        let created = store
+            .create("Admin".to_string(), vec![ApiScope::Admin, ApiScope::Admin])
+            .unwrap();
+        assert_eq!(created.token.scopes, vec![ApiScope::Admin]);
+        assert!(store.authorize(&created.secret, ApiScope::ReadTranscripts).is_ok());
+
+        let listed = store.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].secret_hash.is_empty());
+        assert!(!serde_json::to_string(&listed[0]).unwrap().contains("secret_hash"));
+    }
+
+    #[test]
+    fn test_create_rejects_empty_name_or_scopes() {
+        let store = ApiTokenStore::in_memory();
+        assert!(store.create("  ".to_string(), vec![ApiScope::Control]).is_err());
+        assert!(store.create("Deck".to_string(), Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Application log: everything logged through `tracing` (and `log`, which is forwarded to
+//! it) goes to stderr and to JSON-lines files in `<app data>/logs`, one per day
+//! (`tangerine.YYYY-MM-DD.jsonl`), keeping the last `MAX_LOG_FILES`. Per-dictation request
+//! logs (`crate::request_log`) are separate.
+//!
+//! The `log_level` setting applies to both outputs, except when `RUST_LOG` is set: its
+//! filter (e.g. `debug` or `info,tangerine_core::stt=trace`) then wins over the setting for
+//! the whole run. Until settings are loaded the level is `info`.
+
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing_appender::rolling::{RollingFileAppender, RollingWriter, Rotation};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::fmt::writer::{MakeWriter, OptionalWriter};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+/// Values of the `log_level` setting.
+pub const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+const LOG_DIR: &str = "logs";
+const FILE_PREFIX: &str = "tangerine";
+const FILE_SUFFIX: &str = "jsonl";
+const MAX_LOG_FILES: usize = 7;
+
+/// Most lines `tail` returns.
+pub const MAX_TAIL_LINES: usize = 5000;
+
+static LOG_FILE: OnceLock<RollingFileAppender> = OnceLock::new();
+static LEVEL: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Set at `init` when the filter came from `RUST_LOG`; `set_level` then leaves it alone.
+static FILTER_FROM_ENV: OnceLock<bool> = OnceLock::new();
+
+/// Writes to the log file once `open_files` ran; drops lines logged before that.
+struct LogFile;
+
+impl<'a> MakeWriter<'a> for LogFile {
+    type Writer = OptionalWriter<RollingWriter<'static>>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match LOG_FILE.get() {
+            Some(file) => OptionalWriter::some(file.make_writer()),
+            None => OptionalWriter::none(),
+        }
+    }
+}
+
+/// Install the global logger. Call once, first thing.
+pub fn init() {
+    let (filter, from_env) = match EnvFilter::try_from_default_env() {
+        Ok(filter) => (filter, true),
+        Err(e) => {
+            if std::env::var_os(EnvFilter::DEFAULT_ENV).is_some() {
+                eprintln!("Ignoring invalid RUST_LOG: {}", e);
+            }
+            (EnvFilter::new(DEFAULT_LOG_LEVEL), false)
+        }
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(
+            fmt::layer()
+                .with_ansi(false)
+                .json()
+                .flatten_event(true)
+                .with_writer(LogFile),
+        )
+        .try_init();
+    match result {
+        Ok(()) => {
+            let _ = LEVEL.set(handle);
+            let _ = FILTER_FROM_ENV.set(from_env);
+        }
+        Err(e) => eprintln!("Logger already initialized: {}", e),
+    }
+}
+
+/// Directory of the log files.
+pub fn log_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(LOG_DIR)
+}
+
+/// Start writing the log files in `app_data_dir`.
+pub fn open_files(app_data_dir: &Path) -> Result<(), String> {
+    let dir = log_dir(app_data_dir);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix(FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)
+        .map_err(|e| format!("Failed to open log files: {}", e))?;
+    LOG_FILE
+        .set(appender)
+        .map_err(|_| "Log files are already open".to_string())
+}
+
+/// Apply the `log_level` setting (one of `LOG_LEVELS`). A no-op while `RUST_LOG` is set.
+pub fn set_level(level: &str) -> Result<(), String> {
+    if !LOG_LEVELS.contains(&level) {
+        return Err(format!("Unknown log level {:?}", level));
+    }
+    let handle = LEVEL.get().ok_or("Logger is not initialized")?;
+    if FILTER_FROM_ENV.get().copied().unwrap_or(false) {
+        log::debug!("RUST_LOG is set; not applying log level {:?}", level);
+        return Ok(());
+    }
+    let filter: LevelFilter = level
+        .parse()
+        .map_err(|_| format!("Unknown log level {:?}", level))?;
+    handle
+        .modify(|current| *current = EnvFilter::default().add_directive(filter.into()))
+        .map_err(|e| e.to_string())?;
+    // `log` records are dropped above the `log` crate's own max level, set once at `init`.
+    log::set_max_level(level.parse().unwrap_or(log::LevelFilter::Info));
+    Ok(())
+}
+
+/// The last `lines` lines (oldest first) of the log files in `dir`, reading back into
+/// older files as needed.
+pub fn tail(dir: &Path, lines: usize) -> std::io::Result<Vec<String>> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX)
+                    })
+            })
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    // Dated names sort chronologically; newest first.
+    files.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut tail: Vec<String> = Vec::new();
+    for file in files {
+        if tail.len() >= lines {
+            break;
+        }
+        let file_lines: Vec<String> = BufReader::new(std::fs::File::open(&file)?)
+            .lines()
+            .collect::<Result<_, _>>()?;
+        let wanted = lines - tail.len();
+        let start = file_lines.len().saturating_sub(wanted);
+        tail.splice(0..0, file_lines.into_iter().skip(start));
+    }
+    Ok(tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_reads_back_across_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("tangerine-logs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tangerine.2026-01-01.jsonl"), "a\nb\nc\n").unwrap();
+        std::fs::write(dir.join("tangerine.2026-01-02.jsonl"), "d\ne\n").unwrap();
+        std::fs::write(dir.join("other.txt"), "x\n").unwrap();
+
+        assert_eq!(tail(&dir, 1).unwrap(), ["e"]);
+        assert_eq!(tail(&dir, 3).unwrap(), ["c", "d", "e"]);
+        assert_eq!(tail(&dir, 10).unwrap(), ["a", "b", "c", "d", "e"]);
+        assert!(tail(&dir.join("missing"), 10).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,597 @@
+//! Typed schema of `settings.json`.
+//!
+//! Most of the app reads single keys with `get_setting_from_store`. `AppSettings` describes
+//! the core keys in one place so they can be validated (types, ranges, enum variants) and
+//! edited as a whole through the `get_settings` / `update_settings` commands. Keys it
+//! doesn't model are carried along untouched in `other`. A `null` value counts as unset.
+//!
+//! `settings_version` records how many of `MIGRATIONS` have been applied to the file. They
+//! run at startup, before missing defaults are seeded.
+
+use crate::commands::text::{FocusGuard, OutputMode};
+use crate::output_format::OutputFormat;
+use crate::recordings::RecordingStorageMode;
+use crate::settings::{HotkeyConfig, VadSettings};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Key holding the number of migrations applied to `settings.json`.
+pub const SETTINGS_VERSION_KEY: &str = "settings_version";
+
+/// One migration: rewrites the settings of the previous version in place.
+type Migration = fn(&mut Map<String, Value>);
+
+/// Migrations in order; `MIGRATIONS[n]` upgrades version `n` to `n + 1`.
+const MIGRATIONS: &[Migration] = &[migrate_restore_focus, migrate_auto_mute_audio];
+
+/// Version written by this build.
+pub const CURRENT_SETTINGS_VERSION: u32 = MIGRATIONS.len() as u32;
+
+const OVERLAY_MODES: &[&str] = &["always", "never", "recording_only"];
+const WIDGET_POSITIONS: &[&str] = &[
+    "center",
+    "top-left",
+    "top-center",
+    "top-right",
+    "bottom-left",
+    "bottom-center",
+    "bottom-right",
+];
+const PLAYING_AUDIO_HANDLINGS: &[&str] = &["none", "mute", "pause", "mute_and_pause"];
+
+/// Retention of the in-memory request logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestLogsRetentionMode {
+    /// Keep the last `request_logs_retention_amount` logs.
+    #[default]
+    Amount,
+    /// Keep logs for `request_logs_retention_days` days.
+    Time,
+}
+
+/// Unit of `transcription_retention_value`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionRetentionUnit {
+    #[default]
+    Days,
+    Hours,
+}
+
+/// The core keys of `settings.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub settings_version: u32,
+
+    pub stt_provider: String,
+    pub fallback_stt_provider: Option<String>,
+    pub stt_timeout_seconds: f64,
+    pub llm_timeout_secs: u64,
+    pub llm_max_retries: u32,
+
+    pub output_mode: OutputMode,
+    pub output_hit_enter: bool,
+    pub output_format: OutputFormat,
+    pub focus_guard: FocusGuard,
+    pub review_before_output: bool,
+
+    pub overlay_mode: String,
+    pub widget_position: String,
+    pub playing_audio_handling: String,
+    pub sound_enabled: bool,
+
+    pub toggle_hotkey: HotkeyConfig,
+    pub hold_hotkey: HotkeyConfig,
+    pub paste_last_hotkey: HotkeyConfig,
+    pub vad_settings: VadSettings,
+    pub preroll_secs: f64,
+    pub readback_listen_secs: f64,
+    pub dictation_session_window_secs: f64,
+
+    pub recording_storage_mode: RecordingStorageMode,
+    pub max_saved_recordings: u32,
+    pub request_logs_retention_mode: RequestLogsRetentionMode,
+    pub request_logs_retention_amount: u32,
+    pub request_logs_retention_days: u32,
+    pub log_level: String,
+    pub metrics_exporter: String,
+    pub transcription_retention_unit: TranscriptionRetentionUnit,
+    pub transcription_retention_value: f64,
+
+    pub captions_font_size_px: u32,
+    pub captions_max_lines: u32,
+
+    /// Every other key, as stored.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        let pipeline = crate::pipeline::PipelineConfig::default();
+        let captions = crate::commands::captions::CaptionsStyle::default();
+        Self {
+            settings_version: CURRENT_SETTINGS_VERSION,
+            stt_provider: "groq".to_string(),
+            fallback_stt_provider: None,
+            stt_timeout_seconds: 10.0,
+            llm_timeout_secs: pipeline.llm_config.timeout.as_secs(),
+            llm_max_retries: pipeline.llm_config.retry_config.max_retries,
+            output_mode: OutputMode::default(),
+            output_hit_enter: false,
+            output_format: OutputFormat::default(),
+            focus_guard: FocusGuard::default(),
+            review_before_output: false,
+            overlay_mode: "recording_only".to_string(),
+            widget_position: "bottom-center".to_string(),
+            playing_audio_handling: "mute".to_string(),
+            sound_enabled: true,
+            toggle_hotkey: HotkeyConfig::default_toggle(),
+            hold_hotkey: HotkeyConfig::default_hold(),
+            paste_last_hotkey: HotkeyConfig::default_paste_last(),
+            vad_settings: VadSettings::default(),
+            preroll_secs: 0.0,
+            readback_listen_secs: crate::readback::DEFAULT_READBACK_LISTEN_SECS,
+            dictation_session_window_secs: crate::session::DEFAULT_SESSION_WINDOW_SECS,
+            recording_storage_mode: RecordingStorageMode::default(),
+            max_saved_recordings: 1000,
+            request_logs_retention_mode: RequestLogsRetentionMode::default(),
+            request_logs_retention_amount: 10,
+            request_logs_retention_days: 7,
+            log_level: crate::app_log::DEFAULT_LOG_LEVEL.to_string(),
+            metrics_exporter: crate::metrics::DEFAULT_METRICS_EXPORTER.to_string(),
+            transcription_retention_unit: TranscriptionRetentionUnit::default(),
+            transcription_retention_value: 0.0,
+            captions_font_size_px: captions.font_size_px,
+            captions_max_lines: captions.max_lines,
+            other: Map::new(),
+        }
+    }
+}
+
+/// A setting that doesn't fit the schema.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SettingsValidationError {
+    /// Settings key (`vad_settings.aggressiveness` for nested fields).
+    pub field: String,
+    pub message: String,
+}
+
+impl SettingsValidationError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Top-level settings key the error is about.
+    pub fn key(&self) -> &str {
+        self.field.split('.').next().unwrap_or(&self.field)
+    }
+}
+
+impl std::fmt::Display for SettingsValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(
+    errors: &mut Vec<SettingsValidationError>,
+    field: &str,
+    value: T,
+    min: T,
+    max: T,
+) {
+    if !(value >= min && value <= max) {
+        errors.push(SettingsValidationError::new(
+            field,
+            format!("must be between {} and {} (got {})", min, max, value),
+        ));
+    }
+}
+
+fn check_one_of(
+    errors: &mut Vec<SettingsValidationError>,
+    field: &str,
+    value: &str,
+    allowed: &[&str],
+) {
+    if !allowed.contains(&value) {
+        errors.push(SettingsValidationError::new(
+            field,
+            format!("must be one of {} (got {:?})", allowed.join(", "), value),
+        ));
+    }
+}
+
+fn defaults_map() -> Map<String, Value> {
+    match serde_json::to_value(AppSettings::default()) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+impl AppSettings {
+    /// Read settings from the stored `values`. Modelled keys whose value has the wrong
+    /// type (or an unknown variant) fall back to their default and are reported.
+    pub fn from_values(mut values: Map<String, Value>) -> (Self, Vec<SettingsValidationError>) {
+        values.retain(|_, value| !value.is_null());
+        if let Ok(settings) = serde_json::from_value(Value::Object(values.clone())) {
+            return (settings, Vec::new());
+        }
+
+        // Find the offending keys by parsing each one on its own.
+        let defaults = defaults_map();
+        let mut errors = Vec::new();
+        for key in defaults.keys() {
+            let Some(value) = values.get(key) else {
+                continue;
+            };
+            let mut single = defaults.clone();
+            single.insert(key.clone(), value.clone());
+            if let Err(e) = serde_json::from_value::<AppSettings>(Value::Object(single)) {
+                errors.push(SettingsValidationError::new(key, e.to_string()));
+                values.remove(key);
+            }
+        }
+        let settings = serde_json::from_value(Value::Object(values)).unwrap_or_default();
+        (settings, errors)
+    }
+
+    /// Range and variant checks the types alone don't express.
+    pub fn validate(&self) -> Vec<SettingsValidationError> {
+        let mut errors = Vec::new();
+        let e = &mut errors;
+
+        if self.stt_provider.trim().is_empty() {
+            e.push(SettingsValidationError::new(
+                "stt_provider",
+                "must not be empty",
+            ));
+        }
+        check_range(
+            e,
+            "stt_timeout_seconds",
+            self.stt_timeout_seconds,
+            0.5,
+            600.0,
+        );
+        check_range(e, "llm_timeout_secs", self.llm_timeout_secs, 1, 3600);
+        check_range(e, "llm_max_retries", self.llm_max_retries, 0, 10);
+
+        check_one_of(e, "overlay_mode", &self.overlay_mode, OVERLAY_MODES);
+        check_one_of(e, "log_level", &self.log_level, crate::app_log::LOG_LEVELS);
+        check_one_of(
+            e,
+            "metrics_exporter",
+            &self.metrics_exporter,
+            crate::metrics::METRICS_EXPORTERS,
+        );
+        check_one_of(
+            e,
+            "widget_position",
+            &self.widget_position,
+            WIDGET_POSITIONS,
+        );
+        check_one_of(
+            e,
+            "playing_audio_handling",
+            &self.playing_audio_handling,
+            PLAYING_AUDIO_HANDLINGS,
+        );
+
+        check_range(
+            e,
+            "vad_settings.aggressiveness",
+            self.vad_settings.aggressiveness,
+            0,
+            3,
+        );
+        check_range(
+            e,
+            "vad_settings.speech_frames_threshold",
+            self.vad_settings.speech_frames_threshold,
+            1,
+            100,
+        );
+        check_range(
+            e,
+            "vad_settings.hangover_frames",
+            self.vad_settings.hangover_frames,
+            1,
+            1000,
+        );
+        check_range(
+            e,
+            "vad_settings.pre_roll_ms",
+            self.vad_settings.pre_roll_ms,
+            0,
+            5000,
+        );
+        check_range(
+            e,
+            "preroll_secs",
+            self.preroll_secs,
+            0.0,
+            f64::from(crate::pipeline::MAX_PREROLL_SECS),
+        );
+        check_range(
+            e,
+            "readback_listen_secs",
+            self.readback_listen_secs,
+            0.5,
+            30.0,
+        );
+        check_range(
+            e,
+            "dictation_session_window_secs",
+            self.dictation_session_window_secs,
+            1.0,
+            600.0,
+        );
+
+        check_range(
+            e,
+            "max_saved_recordings",
+            self.max_saved_recordings,
+            1,
+            100_000,
+        );
+        check_range(
+            e,
+            "request_logs_retention_amount",
+            self.request_logs_retention_amount,
+            1,
+            1000,
+        );
+        check_range(
+            e,
+            "request_logs_retention_days",
+            self.request_logs_retention_days,
+            0,
+            36_500,
+        );
+        check_range(
+            e,
+            "transcription_retention_value",
+            self.transcription_retention_value,
+            0.0,
+            876_000.0,
+        );
+
+        use crate::commands::captions::{MAX_FONT_SIZE_PX, MAX_LINES, MIN_FONT_SIZE_PX};
+        check_range(
+            e,
+            "captions_font_size_px",
+            self.captions_font_size_px,
+            MIN_FONT_SIZE_PX,
+            MAX_FONT_SIZE_PX,
+        );
+        check_range(
+            e,
+            "captions_max_lines",
+            self.captions_max_lines,
+            1,
+            MAX_LINES,
+        );
+
+        errors
+    }
+
+    /// These settings with `patch` applied (a `null` value resets the key). Errors only
+    /// concern the patched keys, so a bad value stored elsewhere doesn't block an update.
+    pub fn apply_patch(
+        &self,
+        patch: &Map<String, Value>,
+    ) -> Result<Self, Vec<SettingsValidationError>> {
+        if patch.contains_key(SETTINGS_VERSION_KEY) {
+            return Err(vec![SettingsValidationError::new(
+                SETTINGS_VERSION_KEY,
+                "is managed by the app",
+            )]);
+        }
+        let mut values = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        for (key, value) in patch {
+            values.insert(key.clone(), value.clone());
+        }
+
+        let (settings, mut errors) = Self::from_values(values);
+        errors.extend(
+            settings
+                .validate()
+                .into_iter()
+                .filter(|error| patch.contains_key(error.key())),
+        );
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// `restore_focus: true` became `focus_guard: "refocus"`.
+fn migrate_restore_focus(values: &mut Map<String, Value>) {
+    if let Some(restore_focus) = values.remove("restore_focus") {
+        if values.get("focus_guard").is_none_or(Value::is_null) {
+            let guard = if restore_focus.as_bool() == Some(true) {
+                "refocus"
+            } else {
+                "off"
+            };
+            values.insert("focus_guard".to_string(), Value::from(guard));
+        }
+    }
+}
+
+/// The `auto_mute_audio` toggle became `playing_audio_handling`.
+fn migrate_auto_mute_audio(values: &mut Map<String, Value>) {
+    if let Some(auto_mute) = values.remove("auto_mute_audio") {
+        if values
+            .get("playing_audio_handling")
+            .is_none_or(Value::is_null)
+        {
+            let handling = if auto_mute.as_bool() == Some(false) {
+                "none"
+            } else {
+                "mute"
+            };
+            values.insert("playing_audio_handling".to_string(), Value::from(handling));
+        }
+    }
+}
+
+/// Apply the migrations `values` hasn't had yet. Returns whether anything changed.
+pub fn migrate(values: &mut Map<String, Value>) -> bool {
+    let version = values
+        .get(SETTINGS_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as usize;
+    if version >= MIGRATIONS.len() {
+        return false;
+    }
+    for migration in &MIGRATIONS[version..] {
+        migration(values);
+    }
+    values.insert(
+        SETTINGS_VERSION_KEY.to_string(),
+        Value::from(CURRENT_SETTINGS_VERSION),
+    );
+    log::info!(
+        "Migrated settings from version {} to {}",
+        version,
+        CURRENT_SETTINGS_VERSION
+    );
+    true
+}
+
+/// All values of `settings.json`.
+#[cfg(desktop)]
+pub fn store_values(store: &tauri_plugin_store::Store<tauri::Wry>) -> Map<String, Value> {
+    store.entries().into_iter().collect()
+}
+
+/// Open `settings.json` for the session with auto-save off. The store plugin writes the
+/// file in place, so every change is saved through `save_store` instead.
+///
+/// Must run before anything else opens the store: the plugin hands out the first instance
+/// for a path and ignores the options of later ones.
+#[cfg(desktop)]
+pub fn open_store(app: &tauri::AppHandle) -> Result<(), String> {
+    tauri_plugin_store::StoreBuilder::new(app, "settings.json")
+        .disable_auto_save()
+        .build()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Write `settings.json` atomically, keeping the previous version as its backup.
+#[cfg(desktop)]
+pub fn save_store(
+    app: &tauri::AppHandle,
+    store: &tauri_plugin_store::Store<tauri::Wry>,
+) -> Result<(), String> {
+    let path =
+        tauri_plugin_store::resolve_store_path(app, "settings.json").map_err(|e| e.to_string())?;
+    let contents = serde_json::to_vec_pretty(&store_values(store)).map_err(|e| e.to_string())?;
+    crate::persist::write_plain(&path, &contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Save `settings.json` one last time and take it away from the store plugin, whose exit
+/// hook would otherwise write it again in place.
+#[cfg(desktop)]
+pub fn close_store(app: &tauri::AppHandle) {
+    use tauri_plugin_store::StoreExt;
+
+    let Some(store) = app.get_store("settings.json") else {
+        return;
+    };
+    if let Err(e) = save_store(app, &store) {
+        log::warn!("Failed to save settings on exit: {}", e);
+    }
+    store.close_resource();
+}
+
+/// Run `migrate` on the store, writing back what changed (not saved).
+#[cfg(desktop)]
+pub fn migrate_store(store: &tauri_plugin_store::Store<tauri::Wry>) -> bool {
+    let before = store_values(store);
+    let mut after = before.clone();
+    if !migrate(&mut after) {
+        return false;
+    }
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        store.delete(key);
+    }
+    for (key, value) in after {
+        if before.get(&key) != Some(&value) {
+            store.set(key, value);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_migrate_legacy_keys() {
+        let mut values = map(json!({ "restore_focus": true, "auto_mute_audio": false }));
+        assert!(migrate(&mut values));
+        assert_eq!(values["focus_guard"], "refocus");
+        assert_eq!(values["playing_audio_handling"], "none");
+        assert!(!values.contains_key("restore_focus"));
+        assert_eq!(values[SETTINGS_VERSION_KEY], CURRENT_SETTINGS_VERSION);
+        assert!(!migrate(&mut values));
+    }
+
+    #[test]
+    fn test_from_values_and_patch_report_fields() {
+        let (settings, errors) = AppSettings::from_values(map(json!({
+            "output_mode": "telepathy",
+            "max_saved_recordings": 50,
+            "stt_transcription_prompt": null,
+            "accent_color": "#ff8800",
+        })));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "output_mode");
+        assert_eq!(settings.output_mode, OutputMode::Paste);
+        assert_eq!(settings.max_saved_recordings, 50);
+        assert_eq!(settings.other["accent_color"], "#ff8800");
+
+        let errors = settings
+            .apply_patch(&map(json!({
+                "vad_settings": { "aggressiveness": 7, "enabled": true, "auto_stop": false,
+                    "speech_frames_threshold": 3, "hangover_frames": 30, "pre_roll_ms": 300 },
+                "overlay_mode": "sometimes",
+            })))
+            .unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, ["overlay_mode", "vad_settings.aggressiveness"]);
+
+        let updated = settings
+            .apply_patch(&map(
+                json!({ "output_mode": "clipboard", "max_saved_recordings": null }),
+            ))
+            .unwrap();
+        assert_eq!(updated.output_mode, OutputMode::Clipboard);
+        assert_eq!(updated.max_saved_recordings, 1000);
+    }
+}
@@ -0,0 +1,59 @@
+//! Encryption at rest, on top of `tangerine_core::at_rest`: the key lives in the OS
+//! keychain, and switching `encrypt_at_rest` rewrites the app's files in the new format.
+
+pub use tangerine_core::at_rest::*;
+
+use crate::history::HistoryStorage;
+use crate::output_audit::OutputAuditLog;
+use crate::recordings::RecordingStore;
+use base64::Engine;
+use tauri::{AppHandle, Manager};
+
+const KEYRING_SERVICE: &str = "tangerine-voice";
+const KEYRING_USER: &str = "at-rest-encryption-key";
+
+/// Load the key from the OS keychain, generating and storing one on first use.
+pub fn load_or_create_cipher() -> Result<AtRestCipher, String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| format!("Keychain unavailable: {}", e))?;
+
+    match entry.get_password() {
+        Ok(encoded) => {
+            let key = engine
+                .decode(encoded.trim())
+                .map_err(|e| format!("Stored at-rest key is invalid: {}", e))?;
+            AtRestCipher::from_key(&key)
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key = AtRestCipher::generate_key();
+            entry
+                .set_password(&engine.encode(&key))
+                .map_err(|e| format!("Failed to store at-rest key in keychain: {}", e))?;
+            log::info!("Generated a new at-rest encryption key");
+            AtRestCipher::from_key(&key)
+        }
+        Err(e) => Err(format!("Failed to read at-rest key from keychain: {}", e)),
+    }
+}
+
+/// Rewrite recordings, the STT cache, history and the output audit log in the current
+/// format. Returns how many files changed.
+pub fn migrate(app: &AppHandle) -> Result<usize, String> {
+    let mut changed = 0;
+    if let Some(recordings) = app.try_state::<RecordingStore>() {
+        changed += recordings.rewrite_all()?;
+    }
+    if let Some(history) = app.try_state::<HistoryStorage>() {
+        changed += usize::from(history.rewrite()?);
+    }
+    if let Some(audit) = app.try_state::<OutputAuditLog>() {
+        changed += usize::from(audit.rewrite()?);
+    }
+    log::info!(
+        "At-rest migration ({}) rewrote {} file(s)",
+        if is_enabled() { "encrypt" } else { "decrypt" },
+        changed
+    );
+    Ok(changed)
+}
@@ -0,0 +1,84 @@
+//! Tauri commands for managing scoped control API tokens.
+
+use crate::error::AppError;
+use crate::api_tokens::{ApiAuthError, ApiScope, ApiToken, ApiTokenStore, CreatedApiToken};
+use crate::request_log::{ApiAuditEntry, RequestLogStore};
+use chrono::Utc;
+use tauri::{AppHandle, Manager, State};
+
+/// List all control API tokens (secrets are never returned)
+#[tauri::command]
+pub fn list_api_tokens(tokens: State<'_, ApiTokenStore>) -> Result<Vec<ApiToken>, AppError> {
+    tokens.list().map_err(AppError::from)
+}
+
+/// Create a control API token.
+///
+/// The returned `secret` is shown once and cannot be retrieved later.
+#[tauri::command]
+pub fn create_api_token(
+    name: String,
+    scopes: Vec<ApiScope>,
+    tokens: State<'_, ApiTokenStore>,
+) -> Result<CreatedApiToken, AppError> {
+    tokens.create(name, scopes).map_err(AppError::from)
+}
+
+/// Revoke a control API token (it stays listed for the audit trail)
+#[tauri::command]
+pub fn revoke_api_token(id: String, tokens: State<'_, ApiTokenStore>) -> Result<bool, AppError> {
+    tokens.revoke(&id).map_err(AppError::from)
+}
+
+/// Permanently delete a control API token
+#[tauri::command]
+pub fn delete_api_token(id: String, tokens: State<'_, ApiTokenStore>) -> Result<bool, AppError> {
+    tokens.delete(&id).map_err(AppError::from)
+}
+
+/// Get the control API audit trail (most recent first)
+#[tauri::command]
+pub fn get_api_audit_log(app: AppHandle, limit: Option<usize>) -> Vec<ApiAuditEntry> {
+    app.try_state::<RequestLogStore>()
+        .map(|store| store.get_api_audit(limit))
+        .unwrap_or_default()
+}
+
+/// Authorize a control API request and record it in the audit trail.
+///
+/// This is the single entry point API handlers (such as the IPC output socket) should use
+/// before acting on a request.
+pub fn authorize_api_request(
+    app: &AppHandle,
+    secret: &str,
+    scope: ApiScope,
+    action: &str,
+) -> Result<ApiToken, ApiAuthError> {
+    let result = match app.try_state::<ApiTokenStore>() {
+        Some(tokens) => tokens.authorize(secret, scope),
+        None => Err(ApiAuthError::InvalidToken),
+    };
+
+    if let Some(log_store) = app.try_state::<RequestLogStore>() {
+        let (token_id, token_name) = match &result {
+            Ok(token) => (Some(token.id.clone()), Some(token.name.clone())),
+            Err(ApiAuthError::Revoked(name)) | Err(ApiAuthError::MissingScope { token: name, .. }) => {
+                (None, Some(name.clone()))
+            }
+            Err(ApiAuthError::InvalidToken) => (None, None),
+        };
+        log_store.record_api_access(ApiAuditEntry {
+            timestamp: Utc::now(),
+            token_id,
+            token_name,
+            action: action.to_string(),
+            allowed: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+    }
+
+    if let Err(e) = &result {
+        log::warn!("Control API request '{}' denied: {}", action, e);
+    }
+    result
+}
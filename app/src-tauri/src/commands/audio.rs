@@ -1,13 +1,21 @@
+use crate::error::AppError;
 use crate::audio::{self, AudioCue, SoundType};
-use crate::audio_capture;
+use crate::audio_capture::{self, AudioCapture, AudioCaptureError, AudioEncodeConfig};
+use crate::pipeline::{AudioDiagnostics, SharedPipeline};
+use base64::Engine;
+use serde::Serialize;
 use std::thread;
 use std::time::Duration;
+use tauri::State;
+
+/// Length of the `run_audio_diagnostics` test recording.
+const DIAGNOSTICS_RECORDING: Duration = Duration::from_secs(3);
 
 /// Play the selected cue once as a short preview.
 ///
 /// Frontend passes the cue string (e.g. "tangerine"). Unknown values fall back to Tangerine.
 #[tauri::command]
-pub async fn play_audio_cue_preview(cue: String) -> Result<(), String> {
+pub async fn play_audio_cue_preview(cue: String) -> Result<(), AppError> {
     let cue = AudioCue::from_str(&cue);
 
     // Preview both sounds so it's obvious which pair will be used during real recording.
@@ -44,3 +52,68 @@ pub fn list_audio_input_devices() -> Vec<String> {
 pub fn get_default_audio_input_device_name() -> Option<String> {
     audio_capture::get_default_input_device_info().map(|(name, _sr, _ch)| name)
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDiagnosticsResponse {
+    #[serde(flatten)]
+    pub diagnostics: AudioDiagnostics,
+    /// The test recording (mono, 16 kHz WAV) for playback.
+    pub wav_base64: String,
+}
+
+/// Record 3 seconds from `device_name` (default: the configured microphone) and report its
+/// format, levels and whether the VAD heard speech, to find out why dictations come back empty.
+#[tauri::command]
+pub async fn run_audio_diagnostics(
+    pipeline: State<'_, SharedPipeline>,
+    device_name: Option<String>,
+) -> Result<AudioDiagnosticsResponse, AppError> {
+    if pipeline.is_recording() {
+        return Err(AppError::AlreadyRecording);
+    }
+
+    let config = pipeline.config();
+    let device_name = match device_name.map(|s| s.trim().to_string()) {
+        Some(name) => Some(name).filter(|s| !s.is_empty() && s != "default"),
+        None => config.input_device_name.clone(),
+    };
+
+    let vad_config = config.vad_config.clone();
+    let capture_device = device_name.clone();
+    let (wav, diagnostics, sample_rate, channels) = tauri::async_runtime::spawn_blocking(move || {
+        let mut capture = AudioCapture::with_vad_config(vad_config);
+        capture.start_with_device_name(
+            DIAGNOSTICS_RECORDING.as_secs_f32() + 1.0,
+            capture_device.as_deref(),
+        )?;
+        thread::sleep(DIAGNOSTICS_RECORDING);
+        let (sample_rate, channels) = (capture.sample_rate(), capture.channels());
+        let (wav, diagnostics) = capture.stop_and_get_wav_with_diagnostics(AudioEncodeConfig {
+            downmix_to_mono: true,
+            resample_to_16khz: true,
+            highpass_enabled: false,
+            detect_speech_presence: true,
+            ..Default::default()
+        })?;
+        Ok::<_, AudioCaptureError>((wav, diagnostics, sample_rate, channels))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Audio diagnostics task failed: {}", e)))??;
+
+    let diagnostics = AudioDiagnostics::new(&config, device_name, sample_rate, channels, &diagnostics);
+    tracing::info!(
+        device = diagnostics.device_name.as_deref().unwrap_or("<default>"),
+        sample_rate = diagnostics.sample_rate,
+        channels = diagnostics.channels,
+        peak_dbfs = diagnostics.peak_dbfs,
+        rms_dbfs = diagnostics.rms_dbfs,
+        speech_detected = diagnostics.speech_detected,
+        skip_reason = ?diagnostics.skip_reason,
+        "Audio diagnostics finished"
+    );
+
+    Ok(AudioDiagnosticsResponse {
+        diagnostics,
+        wav_base64: base64::engine::general_purpose::STANDARD.encode(wav),
+    })
+}
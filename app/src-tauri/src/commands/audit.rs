@@ -0,0 +1,28 @@
+use crate::error::AppError;
+use crate::output_audit::{
+    OutputAuditEntry, OutputAuditExportFormat, OutputAuditLog, OutputAuditQuery,
+};
+use std::path::PathBuf;
+use tauri::State;
+
+/// Query the output audit log (newest first)
+#[tauri::command]
+pub async fn query_output_audit(
+    filter: Option<OutputAuditQuery>,
+    audit: State<'_, OutputAuditLog>,
+) -> Result<Vec<OutputAuditEntry>, AppError> {
+    audit.query(&filter.unwrap_or_default()).map_err(AppError::from)
+}
+
+/// Export matching output audit entries to a file, returning how many were written
+#[tauri::command]
+pub async fn export_output_audit(
+    path: String,
+    format: OutputAuditExportFormat,
+    filter: Option<OutputAuditQuery>,
+    audit: State<'_, OutputAuditLog>,
+) -> Result<usize, AppError> {
+    audit
+        .export(&PathBuf::from(path), format, &filter.unwrap_or_default())
+        .map_err(AppError::from)
+}
@@ -0,0 +1,217 @@
+//! Large-text captions window for accessibility.
+//!
+//! The captions window is created and positioned by the backend, stays on top, and shows
+//! transcription text as it becomes available. It is fed directly from the pipeline
+//! (`publish_caption`), independent of whether the text is also typed/pasted anywhere.
+//!
+//! Font size, contrast and the number of visible lines come from the `captions_*` settings.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+#[cfg(desktop)]
+use tauri_plugin_store::StoreExt;
+
+/// Window label of the captions window.
+pub const CAPTIONS_WINDOW_LABEL: &str = "captions";
+
+pub(crate) const MIN_FONT_SIZE_PX: u32 = 16;
+pub(crate) const MAX_FONT_SIZE_PX: u32 = 160;
+pub(crate) const MAX_LINES: u32 = 10;
+
+/// Presentation settings for the captions window.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CaptionsStyle {
+    pub font_size_px: u32,
+    /// White-on-black text instead of the translucent theme.
+    pub high_contrast: bool,
+    /// How many lines of recent text stay visible.
+    pub max_lines: u32,
+}
+
+impl Default for CaptionsStyle {
+    fn default() -> Self {
+        Self {
+            font_size_px: 40,
+            high_contrast: true,
+            max_lines: 3,
+        }
+    }
+}
+
+impl CaptionsStyle {
+    fn clamped(self) -> Self {
+        Self {
+            font_size_px: self.font_size_px.clamp(MIN_FONT_SIZE_PX, MAX_FONT_SIZE_PX),
+            high_contrast: self.high_contrast,
+            max_lines: self.max_lines.clamp(1, MAX_LINES),
+        }
+    }
+}
+
+/// Payload of the `caption-update` event sent to the captions window.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptionUpdate {
+    pub text: String,
+    /// `false` while the text may still change (live partials), `true` once settled.
+    pub is_final: bool,
+}
+
+#[cfg(desktop)]
+fn get_setting_from_store<T: serde::de::DeserializeOwned>(app: &AppHandle, key: &str, default: T) -> T {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(key))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default)
+}
+
+#[cfg(desktop)]
+fn load_style(app: &AppHandle) -> CaptionsStyle {
+    let defaults = CaptionsStyle::default();
+    CaptionsStyle {
+        font_size_px: get_setting_from_store(app, "captions_font_size_px", defaults.font_size_px),
+        high_contrast: get_setting_from_store(app, "captions_high_contrast", defaults.high_contrast),
+        max_lines: get_setting_from_store(app, "captions_max_lines", defaults.max_lines),
+    }
+    .clamped()
+}
+
+#[cfg(not(desktop))]
+fn load_style(_app: &AppHandle) -> CaptionsStyle {
+    CaptionsStyle::default()
+}
+
+/// Create the captions window if needed and show it along the bottom of the screen.
+pub fn show_captions_window_impl(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(CAPTIONS_WINDOW_LABEL) {
+        return window.show().map_err(|e| e.to_string());
+    }
+
+    let style = load_style(app);
+    let height = (style.font_size_px as f64 * 1.4) * style.max_lines as f64 + 32.0;
+
+    let window = tauri::WebviewWindowBuilder::new(
+        app,
+        CAPTIONS_WINDOW_LABEL,
+        tauri::WebviewUrl::App("captions.html".into()),
+    )
+    .title("Tangerine Captions")
+    .inner_size(960.0, height)
+    .min_inner_size(320.0, 80.0)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .focused(false)
+    .visible_on_all_workspaces(true)
+    .build()
+    .map_err(|e| e.to_string())?;
+
+    // Bottom-center of the monitor the user is working on, like broadcast captions.
+    if let Some(display) = crate::placement::active_display(app) {
+        let bounds = display.bounds;
+        let width = 960.0 * display.scale;
+        let x = bounds.x + ((bounds.width - width) / 2.0).max(0.0);
+        let y = bounds.y + (bounds.height - (height + 80.0) * display.scale).max(0.0);
+        let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
+            x: x.round() as i32,
+            y: y.round() as i32,
+        }));
+    }
+
+    log::info!("Captions window opened");
+    Ok(())
+}
+
+/// Send caption text to the captions window (no-op while it is closed).
+///
+/// Partials (`is_final == false`) replace each other until a final caption settles the
+/// line. Empty text settles nothing and only clears the partial shown.
+pub fn publish_caption(app: &AppHandle, text: &str, is_final: bool) {
+    if app.get_webview_window(CAPTIONS_WINDOW_LABEL).is_none() {
+        return;
+    }
+    let is_final = is_final && !text.trim().is_empty();
+    let _ = app.emit_to(
+        CAPTIONS_WINDOW_LABEL,
+        "caption-update",
+        CaptionUpdate {
+            text: text.to_string(),
+            is_final,
+        },
+    );
+}
+
+/// Drop the partial caption of a recording that produced no transcript.
+pub fn clear_partial_caption(app: &AppHandle) {
+    publish_caption(app, "", false);
+}
+
+/// Open (or focus-less show) the captions window.
+#[tauri::command]
+pub fn show_captions_window(app: AppHandle) -> Result<(), AppError> {
+    show_captions_window_impl(&app).map_err(AppError::from)
+}
+
+/// Close the captions window.
+#[tauri::command]
+pub fn hide_captions_window(app: AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app.get_webview_window(CAPTIONS_WINDOW_LABEL) {
+        window.close().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Get the current captions presentation settings.
+#[tauri::command]
+pub fn get_captions_style(app: AppHandle) -> CaptionsStyle {
+    load_style(&app)
+}
+
+/// Save captions presentation settings and apply them to an open captions window.
+#[cfg(desktop)]
+#[tauri::command]
+pub fn set_captions_style(app: AppHandle, style: CaptionsStyle) -> Result<CaptionsStyle, AppError> {
+    let style = style.clamped();
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    store.set("captions_font_size_px", serde_json::json!(style.font_size_px));
+    store.set("captions_high_contrast", serde_json::json!(style.high_contrast));
+    store.set("captions_max_lines", serde_json::json!(style.max_lines));
+    crate::app_settings::save_store(&app, &store)?;
+
+    let _ = app.emit_to(CAPTIONS_WINDOW_LABEL, "captions-style-changed", style.clone());
+    Ok(style)
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub fn set_captions_style(_app: AppHandle, style: CaptionsStyle) -> Result<CaptionsStyle, AppError> {
+    Ok(style.clamped())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_is_clamped() {
+        let style = CaptionsStyle {
+            font_size_px: 4,
+            high_contrast: false,
+            max_lines: 0,
+        }
+        .clamped();
+        assert_eq!(style.font_size_px, MIN_FONT_SIZE_PX);
+        assert_eq!(style.max_lines, 1);
+        assert!(!style.high_contrast);
+
+        let style = CaptionsStyle {
+            font_size_px: 1000,
+            high_contrast: true,
+            max_lines: 99,
+        }
+        .clamped();
+        assert_eq!(style.font_size_px, MAX_FONT_SIZE_PX);
+        assert_eq!(style.max_lines, MAX_LINES);
+    }
+}
@@ -3,6 +3,7 @@
 //! This module provides commands that replace the Python server's config API,
 //! including default prompt sections and available providers.
 
+use crate::error::AppError;
 use serde::Serialize;
 use tauri::AppHandle;
 
@@ -170,7 +171,10 @@ const STT_PROVIDERS: &[(&str, &str, bool)] = &[
     ("openai", "OpenAI", false),
     ("groq", "Groq", false),
     ("deepgram", "Deepgram", false),
+    ("speechmatics", "Speechmatics", false),
     ("whisper", "Local Whisper", true),
+    ("vosk", "Local Vosk", true),
+    ("whisper-server", "Whisper server", true),
 ];
 
 /// LLM provider definitions
@@ -201,6 +205,9 @@ pub fn get_available_providers(app: AppHandle) -> AvailableProvidersResponse {
 
     // Check which STT providers have API keys
     for (id, label, is_local) in STT_PROVIDERS {
+        if *id == "vosk" && !cfg!(feature = "local-vosk") {
+            continue;
+        }
         let key_name = format!("{}_api_key", id);
         // Local providers don't need API keys, remote ones do
         if *is_local || has_api_key(&app, &key_name) {
@@ -225,6 +232,42 @@ pub fn get_available_providers(app: AppHandle) -> AvailableProvidersResponse {
         }
     }
 
+    // Scripted providers for testing and demos (debug setting)
+    let mock_providers_enabled = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("mock_providers_enabled"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if mock_providers_enabled {
+        for providers in [&mut stt_providers, &mut llm_providers] {
+            providers.push(ProviderInfo {
+                value: crate::mock::MOCK_PROVIDER_ID.to_string(),
+                label: "Mock (debug)".to_string(),
+                is_local: true,
+            });
+        }
+    }
+
+    // Providers implemented by external programs
+    let plugins = crate::plugins::from_setting(
+        app.store("settings.json")
+            .ok()
+            .and_then(|store| store.get("provider_plugins")),
+    );
+    for plugin in plugins {
+        let providers = match plugin.kind {
+            crate::plugins::PluginKind::Stt => &mut stt_providers,
+            crate::plugins::PluginKind::Llm => &mut llm_providers,
+        };
+        providers.retain(|p| p.value != plugin.id);
+        providers.push(ProviderInfo {
+            value: plugin.id.clone(),
+            label: plugin.label().to_string(),
+            is_local: true,
+        });
+    }
+
     AvailableProvidersResponse {
         stt: stt_providers,
         llm: llm_providers,
@@ -249,11 +292,37 @@ pub fn get_available_providers(_app: AppHandle) -> AvailableProvidersResponse {
 /// This re-initializes the STT provider based on current settings
 #[cfg(desktop)]
 #[tauri::command]
-pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
-    use crate::pipeline::{PipelineConfig, SharedPipeline};
-    use crate::stt::RetryConfig;
+pub fn sync_pipeline_config(app: AppHandle) -> Result<(), AppError> {
+    use crate::pipeline::{PipelineConfig, SharedPipeline, MAX_PREROLL_SECS};
+    use crate::retry::RetryConfig;
     use tauri::Manager;
 
+    // Apply network settings before the pipeline rebuilds its providers. An invalid config
+    // keeps the previous one; the error is reported once the rest of the settings are synced.
+    let network_config: crate::http::NetworkConfig = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("network_config"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    let network_result = crate::http::configure(network_config);
+
+    let rate_limit: crate::rate_limit::RateLimitConfig = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("rate_limit"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    crate::rate_limit::configure(rate_limit);
+
+    let watchdog: crate::watchdog::WatchdogConfig = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("pipeline_watchdog"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    crate::watchdog::configure(watchdog);
+
     // Read STT settings from store
     let stt_provider: String = app
         .store("settings.json")
@@ -276,6 +345,88 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         .and_then(|store| store.get("stt_transcription_prompt"))
         .and_then(|v| serde_json::from_value(v).ok());
 
+    let stt_vocabulary_boost: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("stt_vocabulary_boost"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(true);
+
+    let stt_context_secs: u32 = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("stt_context_secs"))
+        .and_then(|v| serde_json::from_value::<u32>(v).ok())
+        .unwrap_or(0)
+        .min(crate::pipeline::MAX_STT_CONTEXT_SECS);
+
+    let stt_language: Option<String> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("stt_language"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .flatten();
+
+    let whisper_server_url: Option<String> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("whisper_server_url"))
+        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
+        .filter(|url| !url.is_empty());
+
+    let stt_extra_params: std::collections::HashMap<String, crate::stt::ExtraParams> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("stt_extra_params"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    let stt_temperature: Option<f32> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("stt_temperature"))
+        .and_then(|v| v.as_f64())
+        .map(|t| (t as f32).clamp(0.0, 1.0));
+
+    let stt_verbose_json: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("stt_verbose_json"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    #[cfg(feature = "local-whisper")]
+    let whisper_decoding: crate::stt::WhisperDecodingParams = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("whisper_decoding"))
+        .and_then(|v| serde_json::from_value::<crate::stt::WhisperDecodingParams>(v).ok())
+        .unwrap_or_default()
+        .sanitized();
+
+    #[cfg(feature = "local-whisper")]
+    let whisper_threading: crate::stt::WhisperThreading = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("whisper_threading"))
+        .and_then(|v| serde_json::from_value::<crate::stt::WhisperThreading>(v).ok())
+        .unwrap_or_default();
+
+    #[cfg(feature = "local-vosk")]
+    let vosk_model: Option<String> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("vosk_model"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .flatten();
+
+    let fallback_stt_provider: Option<String> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("fallback_stt_provider"))
+        .and_then(|v| serde_json::from_value::<String>(v).ok())
+        .filter(|p| !p.trim().is_empty());
+
     // Get the appropriate API key based on provider
     let stt_api_key: String = {
         let key_name = format!("{}_api_key", stt_provider);
@@ -289,7 +440,7 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
     // Read all available STT API keys (for per-profile provider overrides at runtime)
     let mut stt_api_keys: std::collections::HashMap<String, String> =
         std::collections::HashMap::new();
-    for provider in ["openai", "groq", "deepgram"] {
+    for provider in ["openai", "groq", "deepgram", "speechmatics"] {
         let key_name = format!("{}_api_key", provider);
         let key: String = app
             .store("settings.json")
@@ -366,6 +517,14 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         .and_then(|store| store.get("anthropic_thinking_budget"))
         .and_then(|v| serde_json::from_value(v).ok());
 
+    let llm_generation: crate::llm::GenerationParams = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("llm_generation"))
+        .and_then(|v| serde_json::from_value::<crate::llm::GenerationParams>(v).ok())
+        .unwrap_or_default()
+        .sanitized();
+
     // If the user never explicitly selected a model, treat "default" as the provider's
     // concrete default model so request logs can display the exact model used.
     let llm_provider_effective = llm_provider_setting
@@ -430,27 +589,12 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         .and_then(|store| store.get("rewrite_program_prompt_profiles"))
         .and_then(|v| serde_json::from_value(v).ok())
         .unwrap_or_default();
-
-    let program_prompt_profiles: Vec<crate::llm::ProgramPromptProfile> =
-        rewrite_program_prompt_profiles
-            .into_iter()
-            .map(|p| crate::llm::ProgramPromptProfile {
-                id: p.id,
-                name: p.name,
-                program_paths: p.program_paths,
-                prompts: p
-                    .cleanup_prompt_sections
-                    .as_ref()
-                    .map(|o| o.apply_to(&base_prompts))
-                    .unwrap_or_else(|| base_prompts.clone()),
-                rewrite_llm_enabled: p.rewrite_llm_enabled,
-                stt_provider: p.stt_provider,
-                stt_model: p.stt_model,
-                stt_timeout_seconds: p.stt_timeout_seconds,
-                llm_provider: p.llm_provider,
-                llm_model: p.llm_model,
-            })
-            .collect();
+    let quality_tiers: crate::settings::QualityTiers = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("quality_tiers"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
 
     // Read VAD settings from store
     let vad_settings: VadSettings = app
@@ -473,6 +617,30 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
             if t.is_empty() || t == "default" { None } else { Some(t) }
         });
 
+    let stop_at_max_duration: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("stop_at_max_duration"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(false);
+
+    // Per-device noise profiles measured by `calibrate_microphone`.
+    let noise_profiles: std::collections::HashMap<String, crate::vad::NoiseProfile> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("noise_profiles"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    // Pre-roll buffer length (0 = disabled)
+    let preroll_secs: f32 = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("preroll_secs"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .and_then(|v: f32| if v.is_finite() { Some(v.clamp(0.0, MAX_PREROLL_SECS)) } else { None })
+        .unwrap_or(0.0);
+
     // Read quiet-audio gate settings from store
     let default_pipeline_config = PipelineConfig::default();
     let quiet_audio_gate_enabled: bool = app
@@ -559,6 +727,80 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         .and_then(|store| store.get("audio_noise_suppression_enabled"))
         .and_then(|v| serde_json::from_value(v).ok())
         .unwrap_or(default_pipeline_config.audio_noise_suppression_enabled);
+    let audio_trim_silence: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("audio_trim_silence"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default_pipeline_config.audio_trim_silence);
+    let audio_max_pause_secs: Option<f32> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("audio_max_pause_secs"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .filter(|v: &f32| v.is_finite() && *v > 0.0);
+    let identifier_casing_enabled: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("identifier_casing_enabled"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default_pipeline_config.identifier_casing_enabled);
+    let learned_corrections: Vec<crate::corrections::LearnedCorrection> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("learned_corrections"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    let deferred_llm_formatting: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("deferred_llm_formatting"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default_pipeline_config.deferred_llm_formatting);
+    let llm_timeout_secs: u64 = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("llm_timeout_secs"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default_pipeline_config.llm_config.timeout.as_secs());
+    let redaction: crate::redaction::RedactionConfig = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("pii_redaction"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_else(|| default_pipeline_config.redaction.clone());
+    let output_hook: crate::hooks::HookConfig = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("output_hook"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_else(|| default_pipeline_config.output_hook.clone());
+    let llm_max_retries: u32 = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("llm_max_retries"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default_pipeline_config.llm_config.retry_config.max_retries);
+
+    // Debug: scripted `mock` STT/LLM providers.
+    let mock_providers_enabled: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("mock_providers_enabled"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(false);
+    let mock_providers: Option<crate::mock::MockProviders> = mock_providers_enabled.then(|| {
+        app.store("settings.json")
+            .ok()
+            .and_then(|store| store.get("mock_providers"))
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    });
+    let provider_plugins = crate::plugins::from_setting(
+        app.store("settings.json")
+            .ok()
+            .and_then(|store| store.get("provider_plugins")),
+    );
 
     // Extra hallucination protection
     let quiet_audio_require_speech: bool = app
@@ -567,6 +809,55 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         .and_then(|store| store.get("quiet_audio_require_speech"))
         .and_then(|v| serde_json::from_value(v).ok())
         .unwrap_or(default_pipeline_config.quiet_audio_require_speech);
+    let hallucination_filter_enabled: bool = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("hallucination_filter_enabled"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default_pipeline_config.hallucination_filter_enabled);
+    let hallucination_filter_phrases: Vec<String> = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("hallucination_filter_phrases"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    let noise_profile =
+        noise_profiles.get(crate::settings::noise_profile_key(input_device_name.as_deref()));
+    let vad_config = vad_settings.to_vad_auto_stop_config(noise_profile);
+
+    let program_prompt_profiles: Vec<crate::llm::ProgramPromptProfile> =
+        rewrite_program_prompt_profiles
+            .into_iter()
+            .map(|p| {
+                let models = p.resolved_models(&quality_tiers);
+                crate::llm::ProgramPromptProfile {
+                    id: p.id,
+                    name: p.name,
+                    program_paths: p.program_paths,
+                    prompts: p
+                        .cleanup_prompt_sections
+                        .as_ref()
+                        .map(|o| o.apply_to(&base_prompts))
+                        .unwrap_or_else(|| base_prompts.clone()),
+                    rewrite_llm_enabled: p.rewrite_llm_enabled,
+                    stt_provider: models.stt_provider,
+                    stt_model: models.stt_model,
+                    stt_timeout_seconds: p.stt_timeout_seconds,
+                    llm_provider: models.llm_provider,
+                    llm_model: models.llm_model,
+                    confirm_by_voice: p.confirm_by_voice.unwrap_or(false),
+                    redact_pii: p.redact_pii,
+                    output_format: p.output_format,
+                    webhook_url: p.webhook_url,
+                    output_mode: p.output_mode,
+                    vad_config: p
+                        .vad_settings
+                        .as_ref()
+                        .map(|v| v.to_vad_auto_stop_config(noise_profile)),
+                }
+            })
+            .collect();
 
     let config = PipelineConfig {
         input_device_name,
@@ -575,9 +866,19 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         stt_api_keys,
         stt_model: stt_model.clone(),
         stt_transcription_prompt,
+        stt_vocabulary_boost,
+        stt_context_secs,
+        stt_language,
+        whisper_server_url,
+        stt_extra_params,
+        stt_temperature,
+        stt_verbose_json,
+        fallback_stt_provider,
         max_duration_secs: 300.0,
+        stop_at_max_duration,
+        preroll_secs,
         retry_config: RetryConfig::default(),
-        vad_config: vad_settings.to_vad_auto_stop_config(),
+        vad_config,
         transcription_timeout: std::time::Duration::from_secs_f64(stt_timeout_seconds),
         max_recording_bytes: 50 * 1024 * 1024, // 50MB
 
@@ -593,8 +894,14 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         audio_highpass_enabled,
         audio_agc_enabled,
         audio_noise_suppression_enabled,
+        audio_trim_silence,
+        audio_max_pause_secs,
+        identifier_casing_enabled,
+        learned_corrections,
 
         quiet_audio_require_speech,
+        hallucination_filter_enabled,
+        hallucination_filter_phrases,
 
         llm_config: crate::llm::LlmConfig {
             enabled: llm_enabled,
@@ -605,14 +912,33 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
             gemini_thinking_budget,
             gemini_thinking_level,
             anthropic_thinking_budget,
+            generation: llm_generation,
             prompts: base_prompts,
             program_prompt_profiles,
+            timeout: std::time::Duration::from_secs(llm_timeout_secs.max(1)),
+            retry_config: RetryConfig {
+                max_retries: llm_max_retries,
+                ..default_pipeline_config.llm_config.retry_config.clone()
+            },
             ..Default::default()
         },
         llm_api_keys,
+        deferred_llm_formatting,
+        redaction,
+        output_hook,
 
         // Preserve provider payload logging across config sync.
         request_log_store: app.try_state::<RequestLogStore>().map(|s| s.inner().clone()),
+        mock_providers,
+        provider_plugins,
+        #[cfg(feature = "local-whisper")]
+        whisper_model_path: None,
+        #[cfg(feature = "local-whisper")]
+        whisper_decoding,
+        #[cfg(feature = "local-whisper")]
+        whisper_threading,
+        #[cfg(feature = "local-vosk")]
+        vosk_model_path: crate::commands::vosk::selected_model_path(&app, vosk_model.as_deref()),
     };
 
     // Update the pipeline
@@ -630,13 +956,13 @@ pub fn sync_pipeline_config(app: AppHandle) -> Result<(), String> {
         );
     }
 
-    Ok(())
+    network_result.map_err(|e| AppError::Config(format!("Invalid network settings: {}", e)))
 }
 
 /// Stub for non-desktop platforms
 #[cfg(not(desktop))]
 #[tauri::command]
-pub fn sync_pipeline_config(_app: AppHandle) -> Result<(), String> {
+pub fn sync_pipeline_config(_app: AppHandle) -> Result<(), AppError> {
     Ok(())
 }
 
@@ -667,7 +993,7 @@ pub fn get_vad_settings(_app: AppHandle) -> VadSettings {
 /// Save VAD settings to the store
 #[cfg(desktop)]
 #[tauri::command]
-pub fn set_vad_settings(app: AppHandle, settings: VadSettings) -> Result<(), String> {
+pub fn set_vad_settings(app: AppHandle, settings: VadSettings) -> Result<(), AppError> {
     let store = app
         .store("settings.json")
         .map_err(|e| format!("Failed to get store: {}", e))?;
@@ -678,8 +1004,7 @@ pub fn set_vad_settings(app: AppHandle, settings: VadSettings) -> Result<(), Str
             serde_json::to_value(&settings).map_err(|e| format!("Failed to serialize: {}", e))?,
         );
 
-    store
-        .save()
+    crate::app_settings::save_store(&app, &store)
         .map_err(|e| format!("Failed to save store: {}", e))?;
 
     log::info!("VAD settings updated: enabled={}, auto_stop={}", settings.enabled, settings.auto_stop);
@@ -689,10 +1014,108 @@ pub fn set_vad_settings(app: AppHandle, settings: VadSettings) -> Result<(), Str
 /// Stub for non-desktop platforms
 #[cfg(not(desktop))]
 #[tauri::command]
-pub fn set_vad_settings(_app: AppHandle, _settings: VadSettings) -> Result<(), String> {
+pub fn set_vad_settings(_app: AppHandle, _settings: VadSettings) -> Result<(), AppError> {
     Ok(())
 }
 
+// ============================================================================
+// Microphone calibration
+// ============================================================================
+
+use crate::vad::NoiseProfile;
+
+/// Default length of the ambient noise sample taken by `calibrate_microphone`
+#[cfg(desktop)]
+const DEFAULT_CALIBRATION_MS: u32 = 1500;
+
+/// Calibration length bounds (ms)
+#[cfg(desktop)]
+const MIN_CALIBRATION_MS: u32 = 500;
+#[cfg(desktop)]
+const MAX_CALIBRATION_MS: u32 = 10_000;
+
+/// Record the room for a moment and store the input device's noise profile.
+///
+/// The user should stay quiet while this runs. Afterwards the VAD uses the stored
+/// profile for this device instead of calibrating at the start of every recording.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn calibrate_microphone(
+    app: AppHandle,
+    pipeline: tauri::State<'_, crate::pipeline::SharedPipeline>,
+    device_name: Option<String>,
+    duration_ms: Option<u32>,
+) -> Result<NoiseProfile, AppError> {
+    use crate::audio_capture::AudioCapture;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    if pipeline.is_recording() {
+        return Err(AppError::AlreadyRecording);
+    }
+
+    let device_name = device_name
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty() && s != "default");
+    let duration = Duration::from_millis(
+        duration_ms
+            .unwrap_or(DEFAULT_CALIBRATION_MS)
+            .clamp(MIN_CALIBRATION_MS, MAX_CALIBRATION_MS) as u64,
+    );
+
+    let capture_device = device_name.clone();
+    let profile = tauri::async_runtime::spawn_blocking(move || {
+        let mut capture = AudioCapture::new();
+        capture.start_with_device_name(duration.as_secs_f32() + 1.0, capture_device.as_deref())?;
+        std::thread::sleep(duration);
+        capture.stop_and_measure_noise()
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Calibration task failed: {}", e)))??
+    .ok_or_else(|| AppError::Audio("No audio was captured during calibration".to_string()))?;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("Failed to get store: {}", e))?;
+
+    let mut profiles: HashMap<String, NoiseProfile> = store
+        .get("noise_profiles")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    profiles.insert(
+        crate::settings::noise_profile_key(device_name.as_deref()).to_string(),
+        profile,
+    );
+    store.set(
+        "noise_profiles",
+        serde_json::to_value(&profiles).map_err(|e| format!("Failed to serialize: {}", e))?,
+    );
+    crate::app_settings::save_store(&app, &store)
+        .map_err(|e| format!("Failed to save store: {}", e))?;
+
+    log::info!(
+        "Calibrated microphone {:?}: noise floor {:.1} dBFS",
+        device_name.as_deref().unwrap_or("<default>"),
+        profile.noise_floor_dbfs
+    );
+
+    sync_pipeline_config(app)?;
+    Ok(profile)
+}
+
+/// Stub for non-desktop platforms
+#[cfg(not(desktop))]
+#[tauri::command]
+pub async fn calibrate_microphone(
+    _app: AppHandle,
+    _device_name: Option<String>,
+    _duration_ms: Option<u32>,
+) -> Result<NoiseProfile, AppError> {
+    Err(AppError::Config(
+        "Microphone calibration is not supported on this platform".to_string(),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,177 @@
+//! Export of a single request as a zip for bug reports.
+//!
+//! The bundle holds the recording, the request log, the history entry, app/OS info and the
+//! settings with every secret masked, so a failure can be reproduced from one attachment.
+
+use crate::error::AppError;
+use crate::history::HistoryStorage;
+use crate::recordings::RecordingStore;
+use crate::request_log::RequestLogStore;
+use serde_json::{json, Value as JsonValue};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+
+/// Setting keys containing any of these are masked in the bundle.
+const SECRET_KEY_MARKERS: &[&str] = &["api_key", "token", "secret", "password"];
+
+/// Whether a setting key (at any depth) names a secret.
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_MARKERS.iter().any(|marker| key.contains(marker))
+}
+
+/// Mask secret values in a settings object (recursively, e.g. per-provider key maps).
+fn redact_settings(key: &str, value: &mut JsonValue) {
+    if is_secret_key(key) {
+        if value.as_str().is_some_and(|s| !s.is_empty()) || value.is_object() {
+            *value = json!("<redacted>");
+        }
+        return;
+    }
+    if let JsonValue::Object(map) = value {
+        for (k, v) in map.iter_mut() {
+            redact_settings(k, v);
+        }
+    }
+}
+
+#[cfg(desktop)]
+fn redacted_settings(app: &AppHandle) -> JsonValue {
+    use tauri_plugin_store::StoreExt;
+
+    let Ok(store) = app.store("settings.json") else {
+        return JsonValue::Null;
+    };
+    let mut settings = serde_json::Map::new();
+    for (key, mut value) in store.entries() {
+        redact_settings(&key, &mut value);
+        settings.insert(key, value);
+    }
+    JsonValue::Object(settings)
+}
+
+#[cfg(not(desktop))]
+fn redacted_settings(_app: &AppHandle) -> JsonValue {
+    JsonValue::Null
+}
+
+fn add_json(
+    zip: &mut zip::ZipWriter<std::fs::File>,
+    name: &str,
+    value: &JsonValue,
+) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&bytes).map_err(|e| e.to_string())
+}
+
+/// Write a debug bundle for `request_id` and return its path.
+///
+/// Defaults to `<app_data_dir>/debug-bundles/tangerine-debug-<request_id>.zip` when no
+/// `destination` is given. Parts that no longer exist (e.g. a pruned recording) are skipped.
+#[tauri::command]
+pub fn export_debug_bundle(
+    app: AppHandle,
+    request_id: String,
+    destination: Option<String>,
+) -> Result<String, AppError> {
+    let recording = app
+        .try_state::<RecordingStore>()
+        .map(|store| -> Result<Option<Vec<u8>>, String> {
+            match store.wav_path_if_exists(&request_id)? {
+                Some(_) => store.load_wav(&request_id).map(Some),
+                None => Ok(None),
+            }
+        })
+        .transpose()?
+        .flatten();
+    let request_log = app.try_state::<RequestLogStore>().and_then(|store| {
+        store
+            .get_logs(None)
+            .into_iter()
+            .find(|log| log.id == request_id)
+    });
+    let history_entry = app
+        .try_state::<HistoryStorage>()
+        .and_then(|history| history.get_all(None).ok())
+        .and_then(|entries| entries.into_iter().find(|e| e.id == request_id));
+
+    if recording.is_none() && request_log.is_none() && history_entry.is_none() {
+        return Err(format!("Nothing recorded for request {}", request_id).into());
+    }
+
+    let path = match destination {
+        Some(destination) => PathBuf::from(destination),
+        None => {
+            let dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| e.to_string())?
+                .join("debug-bundles");
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            dir.join(format!("tangerine-debug-{}.zip", request_id))
+        }
+    };
+
+    let file = std::fs::File::create(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+
+    let package = app.package_info();
+    add_json(
+        &mut zip,
+        "app.json",
+        &json!({
+            "name": package.name,
+            "version": package.version.to_string(),
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "request_id": request_id,
+            "exported_at": chrono::Utc::now(),
+        }),
+    )?;
+    add_json(&mut zip, "settings.json", &redacted_settings(&app))?;
+    if let Some(log) = &request_log {
+        add_json(&mut zip, "request_log.json", &json!(log))?;
+    }
+    if let Some(entry) = &history_entry {
+        add_json(&mut zip, "history_entry.json", &json!(entry))?;
+    }
+    if let Some(wav) = &recording {
+        zip.start_file("recording.wav", SimpleFileOptions::default())
+            .map_err(|e| e.to_string())?;
+        zip.write_all(wav).map_err(|e| e.to_string())?;
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+    log::info!("Exported debug bundle for request {} to {}", request_id, path.display());
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_masked() {
+        let mut settings = json!({
+            "groq_api_key": "gsk_live",
+            "stt_provider": "groq",
+            "empty_api_key": "",
+            "webhook": { "url": "https://example.com", "auth_token": "abc" }
+        });
+        redact_settings("", &mut settings);
+        assert_eq!(
+            settings,
+            json!({
+                "groq_api_key": "<redacted>",
+                "stt_provider": "groq",
+                "empty_api_key": "",
+                "webhook": { "url": "https://example.com", "auth_token": "<redacted>" }
+            })
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Daily Markdown digest export (see `crate::digest`).
+
+use crate::digest::{self, DigestConfig};
+use crate::error::AppError;
+use crate::history::HistoryStorage;
+use chrono::NaiveDate;
+use tauri::{AppHandle, Manager};
+
+/// Read the `daily_digest` setting.
+pub fn digest_config(app: &AppHandle) -> DigestConfig {
+    #[cfg(desktop)]
+    {
+        use tauri_plugin_store::StoreExt;
+        if let Some(config) = app
+            .store("settings.json")
+            .ok()
+            .and_then(|store| store.get("daily_digest"))
+            .and_then(|v| serde_json::from_value(v).ok())
+        {
+            return config;
+        }
+    }
+
+    #[cfg(not(desktop))]
+    let _ = app;
+
+    DigestConfig::default()
+}
+
+/// Append a finished dictation to today's digest file when `auto_append` is on
+/// (in the background).
+pub fn append_after_dictation(app: &AppHandle, request_id: &str) {
+    let config = digest_config(app);
+    if !config.auto_append || config.folder.is_none() {
+        return;
+    }
+    let app = app.clone();
+    let request_id = request_id.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(history) = app.try_state::<HistoryStorage>() else {
+            return;
+        };
+        let result = history.get_all(None).and_then(|entries| {
+            let entry: Vec<_> = entries.into_iter().filter(|e| e.id == request_id).collect();
+            digest::append_entries(&config, &entry)
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to append to the daily digest: {}", e);
+        }
+    });
+}
+
+/// Write the transcripts of `date` (today by default) to its digest file; transcripts
+/// already in the file are skipped. Returns how many were added.
+#[tauri::command]
+pub async fn export_daily_digest(
+    app: AppHandle,
+    date: Option<NaiveDate>,
+) -> Result<usize, AppError> {
+    let config = digest_config(&app);
+    let date = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+    let history = app
+        .try_state::<HistoryStorage>()
+        .ok_or_else(|| AppError::from("History not available".to_string()))?;
+    let entries = digest::entries_on(&history.get_all(None)?, date);
+    tauri::async_runtime::spawn_blocking(move || digest::append_entries(&config, &entries))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(AppError::from)
+}
@@ -0,0 +1,51 @@
+use crate::error::AppError;
+use tauri::AppHandle;
+
+/// Whether recordings, history and logs are currently written encrypted.
+#[tauri::command]
+pub fn get_encryption_at_rest() -> bool {
+    crate::at_rest::is_enabled()
+}
+
+/// Why the at-rest key could not be loaded, if it couldn't. Nothing is saved until the app
+/// restarts with the key available.
+#[tauri::command]
+pub fn get_encryption_key_error() -> Option<String> {
+    crate::at_rest::key_unavailable()
+}
+
+/// Switch encryption at rest on or off and migrate existing files to match.
+///
+/// Returns how many files were rewritten.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn set_encryption_at_rest(app: AppHandle, enabled: bool) -> Result<usize, AppError> {
+    use tauri_plugin_store::StoreExt;
+
+    // Stores that found sealed files loaded empty; saving them now would lose data.
+    if let Some(reason) = crate::at_rest::key_unavailable() {
+        return Err(AppError::from(format!(
+            "Encryption key was unavailable at startup ({}); restart Tangerine once the keychain is unlocked",
+            reason
+        )));
+    }
+
+    // Even when switching off, the key is needed to decrypt what was sealed so far.
+    let cipher = crate::at_rest::load_or_create_cipher()?;
+    crate::at_rest::configure(Some(cipher), enabled);
+
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    store.set("encrypt_at_rest", serde_json::json!(enabled));
+    crate::app_settings::save_store(&app, &store)?;
+
+    tauri::async_runtime::spawn_blocking(move || crate::at_rest::migrate(&app))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(AppError::from)
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub async fn set_encryption_at_rest(_app: AppHandle, _enabled: bool) -> Result<usize, AppError> {
+    Err("Encryption at rest is only available on desktop".to_string())
+}
@@ -0,0 +1,117 @@
+//! STT evaluation runs over a folder of reference recordings (see `crate::eval`).
+
+use crate::error::AppError;
+use crate::eval::{discover_cases, CaseResult, EvalProvider, EvalReport, ProviderReport};
+use crate::pipeline::SharedPipeline;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, State};
+
+/// Payload of the `stt-eval-progress` event, emitted after each transcription.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalProgress {
+    pub provider: String,
+    pub model: Option<String>,
+    pub case_name: String,
+    /// Transcriptions finished so far, across all providers.
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Transcribe every `<name>.wav` / `<name>.txt` pair in `folder` with each provider and
+/// score the results.
+///
+/// `providers` defaults to the configured STT provider and model. Cases run one at a time
+/// so latencies are comparable. With `write_report`, `eval-report.md` and
+/// `eval-report.json` are written into the folder as well.
+#[tauri::command]
+pub async fn run_stt_eval(
+    app: AppHandle,
+    pipeline: State<'_, SharedPipeline>,
+    folder: String,
+    providers: Option<Vec<EvalProvider>>,
+    write_report: Option<bool>,
+) -> Result<EvalReport, AppError> {
+    let folder = PathBuf::from(folder);
+    let cases = discover_cases(&folder)?;
+    if cases.is_empty() {
+        return Err(AppError::from(
+            "No test cases found: add <name>.wav files with matching <name>.txt transcripts",
+        ));
+    }
+
+    let providers = match providers.filter(|p| !p.is_empty()) {
+        Some(providers) => providers,
+        None => {
+            let config = pipeline.config();
+            vec![EvalProvider {
+                provider: config.stt_provider,
+                model: config.stt_model,
+            }]
+        }
+    };
+
+    let started_at = chrono::Utc::now();
+    let total = cases.len() * providers.len();
+    let mut done = 0;
+    let mut reports = Vec::with_capacity(providers.len());
+    for provider in &providers {
+        let mut results = Vec::with_capacity(cases.len());
+        for case in &cases {
+            let started = Instant::now();
+            let result = match std::fs::read(&case.audio_path) {
+                Ok(wav) => pipeline
+                    .transcribe_wav_bytes_with(
+                        &provider.provider,
+                        provider.model.clone(),
+                        bytes::Bytes::from(wav),
+                    )
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(format!(
+                    "Failed to read {}: {}",
+                    case.audio_path.display(),
+                    e
+                )),
+            };
+            let latency_ms = started.elapsed().as_millis() as u64;
+            results.push(match result {
+                Ok(hypothesis) => CaseResult::scored(case, hypothesis, latency_ms),
+                Err(error) => {
+                    log::warn!(
+                        "Eval: {} failed on {}: {}",
+                        provider.provider,
+                        case.name,
+                        error
+                    );
+                    CaseResult::failed(case, error, latency_ms)
+                }
+            });
+
+            done += 1;
+            let _ = app.emit(
+                "stt-eval-progress",
+                EvalProgress {
+                    provider: provider.provider.clone(),
+                    model: provider.model.clone(),
+                    case_name: case.name.clone(),
+                    done,
+                    total,
+                },
+            );
+        }
+        reports.push(ProviderReport::new(provider, results));
+    }
+
+    let report = EvalReport::new(folder.clone(), started_at, cases.len(), reports);
+    if write_report.unwrap_or(false) {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize eval report: {}", e))?;
+        std::fs::write(folder.join("eval-report.json"), json)
+            .map_err(|e| format!("Failed to write eval-report.json: {}", e))?;
+        std::fs::write(folder.join("eval-report.md"), report.to_markdown())
+            .map_err(|e| format!("Failed to write eval-report.md: {}", e))?;
+    }
+    Ok(report)
+}
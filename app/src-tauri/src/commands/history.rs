@@ -1,5 +1,9 @@
-use crate::history::{HistoryEntry, HistoryStorage};
-use tauri::{AppHandle, State};
+use crate::error::AppError;
+use crate::history::{HistoryEntry, HistoryQuery, HistoryStorage};
+use crate::request_log::RequestLogStore;
+use crate::search_index::{SearchIndex, TranscriptHit};
+use crate::stats::{DictationStats, StatsRange, DEFAULT_TYPING_WPM};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[cfg(desktop)]
 use tauri_plugin_store::StoreExt;
@@ -31,9 +35,9 @@ pub async fn add_history_entry(
     app: AppHandle,
     text: String,
     history: State<'_, HistoryStorage>,
-) -> Result<HistoryEntry, String> {
+) -> Result<HistoryEntry, AppError> {
     let max = get_max_saved_recordings(&app);
-    history.add_entry(text, max)
+    history.add_entry(text, max).map_err(AppError::from)
 }
 
 /// Get dictation history entries
@@ -41,8 +45,8 @@ pub async fn add_history_entry(
 pub async fn get_history(
     limit: Option<usize>,
     history: State<'_, HistoryStorage>,
-) -> Result<Vec<HistoryEntry>, String> {
-    history.get_all(limit)
+) -> Result<Vec<HistoryEntry>, AppError> {
+    history.get_all(limit).map_err(AppError::from)
 }
 
 /// Delete a history entry by ID
@@ -50,12 +54,118 @@ pub async fn get_history(
 pub async fn delete_history_entry(
     id: String,
     history: State<'_, HistoryStorage>,
-) -> Result<bool, String> {
-    history.delete(&id)
+) -> Result<bool, AppError> {
+    history.delete(&id).map_err(AppError::from)
+}
+
+/// Time range for `search_history`; either end may be open.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct DateRange {
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Search history entries (newest first) by text or notes, tags (all must match) and
+/// time range.
+#[tauri::command]
+pub async fn search_history(
+    query: Option<String>,
+    tags: Option<Vec<String>>,
+    date_range: Option<DateRange>,
+    limit: Option<usize>,
+    history: State<'_, HistoryStorage>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let date_range = date_range.unwrap_or_default();
+    let query = HistoryQuery {
+        query,
+        tags: tags.unwrap_or_default(),
+        from: date_range.from,
+        to: date_range.to,
+    };
+    history.search(&query, limit).map_err(AppError::from)
+}
+
+/// Set the tags and notes of a history entry. Returns the updated entry.
+#[tauri::command]
+pub async fn annotate_history_entry(
+    app: AppHandle,
+    id: String,
+    tags: Vec<String>,
+    notes: Option<String>,
+    history: State<'_, HistoryStorage>,
+) -> Result<HistoryEntry, AppError> {
+    let entry = history
+        .annotate(&id, tags, notes)?
+        .ok_or_else(|| AppError::from(format!("No history entry with id {}", id)))?;
+    let _ = app.emit("history-changed", ());
+    Ok(entry)
+}
+
+/// Results returned by `search_transcripts` unless a limit is given.
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Bring the transcript search index up to date with history (in the background).
+pub fn sync_search_index(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let (Some(index), Some(history)) =
+            (app.try_state::<SearchIndex>(), app.try_state::<HistoryStorage>())
+        else {
+            return;
+        };
+        let result = history.get_all(None).and_then(|entries| index.sync_history(&entries));
+        if let Err(e) = result {
+            log::warn!("Failed to update the transcript search index: {}", e);
+        }
+    });
+}
+
+/// Full-text search over history and request log transcripts, best matches first, with
+/// snippets.
+#[tauri::command]
+pub async fn search_transcripts(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<TranscriptHit>, AppError> {
+    let index = app
+        .try_state::<SearchIndex>()
+        .ok_or_else(|| AppError::from("Transcript search is not available".to_string()))?;
+    if let Some(logs) = app.try_state::<RequestLogStore>() {
+        index.index_request_logs(&logs.get_logs(None))?;
+    }
+    index
+        .search(&query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .map_err(AppError::from)
+}
+
+/// All tags used in history, sorted.
+#[tauri::command]
+pub async fn get_history_tags(history: State<'_, HistoryStorage>) -> Result<Vec<String>, AppError> {
+    history.tags().map_err(AppError::from)
 }
 
 /// Clear all history entries
 #[tauri::command]
-pub async fn clear_history(history: State<'_, HistoryStorage>) -> Result<(), String> {
-    history.clear()
+pub async fn clear_history(history: State<'_, HistoryStorage>) -> Result<(), AppError> {
+    history.clear().map_err(AppError::from)
+}
+
+/// Dictation statistics for `range`: words per day, average WPM and time saved vs typing
+/// at `typing_wpm` (40 WPM by default).
+#[tauri::command]
+pub async fn get_dictation_stats(
+    range: StatsRange,
+    typing_wpm: Option<f64>,
+    history: State<'_, HistoryStorage>,
+) -> Result<DictationStats, AppError> {
+    let entries = history.get_all(None)?;
+    Ok(crate::stats::compute(
+        &entries,
+        range,
+        typing_wpm.unwrap_or(DEFAULT_TYPING_WPM),
+        chrono::Utc::now(),
+        &chrono::Local,
+    ))
 }
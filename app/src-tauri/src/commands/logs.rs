@@ -1,9 +1,11 @@
-//! Tauri commands for request logging.
+//! Tauri commands for request logging and the application log.
 
+use crate::error::AppError;
+use crate::pipeline_metrics::PipelineMetrics;
 use crate::request_log::{
     RequestLog, RequestLogStore, RequestLogsRetentionConfig, RequestLogsRetentionMode,
 };
-use chrono::Duration as ChronoDuration;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use tauri::{AppHandle, Manager};
 
 #[cfg(desktop)]
@@ -68,3 +70,56 @@ pub fn clear_request_logs(app: AppHandle) {
         store.clear();
     }
 }
+
+/// Per-stage latency percentiles (p50/p95) of completed requests, overall and per provider.
+///
+/// `since` limits the window; `group_by_model` splits providers into `provider/model`.
+#[tauri::command]
+pub fn get_pipeline_metrics(
+    since: Option<DateTime<Utc>>,
+    group_by_model: Option<bool>,
+) -> PipelineMetrics {
+    crate::pipeline_metrics::global().summary(since, group_by_model.unwrap_or(false))
+}
+
+/// Forget all recorded pipeline metrics samples
+#[tauri::command]
+pub fn clear_pipeline_metrics() {
+    crate::pipeline_metrics::global().clear();
+}
+
+/// The last `lines` entries of the application log (default 200), oldest first.
+#[tauri::command]
+pub fn get_app_log_tail(
+    app: AppHandle,
+    lines: Option<usize>,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::from(format!("Failed to get app data dir: {}", e)))?;
+    let lines = lines.unwrap_or(200).min(crate::app_log::MAX_TAIL_LINES);
+    let tail = crate::app_log::tail(&crate::app_log::log_dir(&app_data_dir), lines)
+        .map_err(|e| AppError::from(format!("Failed to read log files: {}", e)))?;
+    // A line still being written doesn't parse yet; skip it.
+    Ok(tail
+        .iter()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Set the application log level (`error`, `warn`, `info`, `debug` or `trace`) and save it.
+#[tauri::command]
+pub fn set_log_level(app: AppHandle, level: String) -> Result<(), AppError> {
+    crate::app_log::set_level(&level)?;
+    #[cfg(desktop)]
+    {
+        use tauri_plugin_store::StoreExt;
+        let store = app.store("settings.json").map_err(|e| e.to_string())?;
+        store.set("log_level", serde_json::json!(level));
+        crate::app_settings::save_store(&app, &store)?;
+    }
+    #[cfg(not(desktop))]
+    let _ = app;
+    Ok(())
+}
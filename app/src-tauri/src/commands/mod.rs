@@ -1,11 +1,22 @@
+pub mod api_tokens;
 pub mod audio;
+pub mod audit;
+pub mod captions;
 pub mod config;
+pub mod debug_bundle;
+pub mod digest;
+pub mod encryption;
+pub mod eval;
 pub mod history;
 pub mod llm;
 pub mod logs;
+pub mod models;
 pub mod overlay;
+pub mod permissions;
 pub mod recording;
+pub mod review;
 pub mod settings;
 pub mod text;
+pub mod vosk;
 pub mod windows;
 pub mod whisper;
@@ -0,0 +1,41 @@
+use crate::error::AppError;
+use crate::model_catalog::{CatalogCredentials, ModelCatalog, ModelKind, ProviderModels};
+use crate::pipeline::SharedPipeline;
+use tauri::{AppHandle, State};
+
+#[cfg(desktop)]
+fn stored_api_key(app: &AppHandle, provider: &str) -> Option<String> {
+    use tauri_plugin_store::StoreExt;
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(format!("{}_api_key", provider)))
+        .and_then(|v| v.as_str().map(str::to_string))
+        .filter(|key| !key.trim().is_empty())
+}
+
+#[cfg(not(desktop))]
+fn stored_api_key(_app: &AppHandle, _provider: &str) -> Option<String> {
+    None
+}
+
+/// List the models a provider offers, for the settings dropdowns.
+///
+/// Uses the cached list while it's fresh unless `refresh` is set; falls back to the last
+/// cached or a built-in list when the provider can't be reached.
+#[tauri::command]
+pub async fn list_provider_models(
+    app: AppHandle,
+    provider: String,
+    kind: ModelKind,
+    refresh: Option<bool>,
+    catalog: State<'_, ModelCatalog>,
+    pipeline: State<'_, SharedPipeline>,
+) -> Result<ProviderModels, AppError> {
+    let credentials = CatalogCredentials {
+        api_key: stored_api_key(&app, &provider),
+        ollama_url: pipeline.config().llm_config.ollama_url,
+    };
+    Ok(catalog
+        .models(&provider, kind, &credentials, refresh.unwrap_or(false))
+        .await)
+}
@@ -1,3 +1,5 @@
+use crate::error::AppError;
+use crate::placement::{self, OverlayPlacement, PlacementPrefs};
 use tauri::{AppHandle, Emitter, Manager};
 
 #[cfg(desktop)]
@@ -12,76 +14,58 @@ fn get_setting_from_store<T: serde::de::DeserializeOwned>(app: &AppHandle, key:
         .unwrap_or(default)
 }
 
-fn set_widget_position_impl(app: &AppHandle, position: &str) -> Result<(), String> {
+/// Move the overlay to `preset` (or the saved placement when `None`) on the right monitor.
+fn set_widget_position_impl(app: &AppHandle, preset: Option<&str>) -> Result<(), String> {
     let Some(window) = app.get_webview_window("overlay") else {
         return Err("Overlay window not found".to_string());
     };
 
-    let monitor = window
-        .current_monitor()
-        .map_err(|e| e.to_string())?
-        .ok_or("No monitor found")?;
-
-    let screen_size = monitor.size();
-    let scale = monitor.scale_factor();
-    let screen_width = screen_size.width as f64 / scale;
-    let screen_height = screen_size.height as f64 / scale;
+    if let Some(preset) = preset {
+        if !placement::WIDGET_POSITIONS.contains(&preset) {
+            return Err(format!("Invalid widget position: {}", preset));
+        }
+    }
 
-    // Get current window size
     let window_size = window.outer_size().map_err(|e| e.to_string())?;
-    let window_width = window_size.width as f64 / scale;
-    let window_height = window_size.height as f64 / scale;
-
-    // Calculate margins (pixels from edge)
-    let margin = 50.0;
-
-    let (x, y) = match position {
-        "top-left" => (margin, margin),
-        "top-center" => ((screen_width - window_width) / 2.0, margin),
-        "top-right" => (screen_width - window_width - margin, margin),
-        "center" => (
-            (screen_width - window_width) / 2.0,
-            (screen_height - window_height) / 2.0,
-        ),
-        "bottom-left" => (margin, screen_height - window_height - margin),
-        "bottom-center" => (
-            (screen_width - window_width) / 2.0,
-            screen_height - window_height - margin,
-        ),
-        "bottom-right" => (
-            screen_width - window_width - margin,
-            screen_height - window_height - margin,
-        ),
-        _ => return Err(format!("Invalid widget position: {}", position)),
-    };
+    let position = placement::window_position(
+        app,
+        (window_size.width as f64, window_size.height as f64),
+        preset,
+    )
+    .ok_or("No monitor found")?;
 
     window
-        .set_position(tauri::Position::Logical(tauri::LogicalPosition { x, y }))
+        .set_position(tauri::Position::Physical(position))
         .map_err(|e| e.to_string())?;
 
-    log::info!("Widget position set to {} at ({}, {})", position, x, y);
+    log::info!(
+        "Widget position set to {} at ({}, {})",
+        preset.unwrap_or("saved placement"),
+        position.x,
+        position.y
+    );
     Ok(())
 }
 
-/// Best-effort: snap the overlay window back to the saved preset position.
+/// Best-effort: snap the overlay window back to the saved placement (preset position, or
+/// near the caret / active window).
 ///
 /// Intended for cases where the overlay is not always visible (recording-only/never) and
 /// the user may have dragged it away since the last time it was shown.
 #[cfg(desktop)]
 pub fn snap_overlay_to_saved_position(app: &AppHandle) -> Result<(), String> {
-    let position: String =
-        get_setting_from_store(app, "widget_position", "bottom-center".to_string());
-    set_widget_position_impl(app, position.as_str())
+    set_widget_position_impl(app, None)
 }
 
-/// Show the overlay window and, if the current mode is not "always", reset the window
-/// back to the saved preset position.
+/// Show the overlay window and, if the current mode is not "always" or the overlay follows
+/// the caret / active window, reset the window to the saved placement.
 #[cfg(desktop)]
 pub fn show_overlay_with_reset_if_not_always(app: &AppHandle) -> Result<(), String> {
     let overlay_mode: String =
         get_setting_from_store(app, "overlay_mode", "recording_only".to_string());
+    let follows_focus = PlacementPrefs::load(app).placement != OverlayPlacement::Preset;
 
-    if overlay_mode != "always" {
+    if overlay_mode != "always" || follows_focus {
         if let Err(e) = snap_overlay_to_saved_position(app) {
             log::warn!("Failed to snap overlay position on show: {}", e);
         }
@@ -95,7 +79,7 @@ pub fn show_overlay_with_reset_if_not_always(app: &AppHandle) -> Result<(), Stri
 }
 
 #[tauri::command]
-pub async fn resize_overlay(app: AppHandle, width: f64, height: f64) -> Result<(), String> {
+pub async fn resize_overlay(app: AppHandle, width: f64, height: f64) -> Result<(), AppError> {
     // Enforce minimum dimensions to prevent invisible window
     let min_size = 48.0;
     let width = width.max(min_size);
@@ -162,15 +146,20 @@ pub async fn resize_overlay(app: AppHandle, width: f64, height: f64) -> Result<(
                 if let Ok(Some(monitor)) = window.current_monitor() {
                     let screen_size = monitor.size();
                     let scale = monitor.scale_factor();
+                    // Secondary monitors don't start at (0, 0).
+                    let screen_x = monitor.position().x as f64 / scale;
+                    let screen_y = monitor.position().y as f64 / scale;
                     let screen_width = screen_size.width as f64 / scale;
                     let screen_height = screen_size.height as f64 / scale;
 
                     let margin = 12.0;
-                    let max_x = (screen_width - new_outer_w - margin).max(margin);
-                    let max_y = (screen_height - new_outer_h - margin).max(margin);
+                    let min_x = screen_x + margin;
+                    let min_y = screen_y + margin;
+                    let max_x = (screen_x + screen_width - new_outer_w - margin).max(min_x);
+                    let max_y = (screen_y + screen_height - new_outer_h - margin).max(min_y);
 
-                    x = x.clamp(margin, max_x);
-                    y = y.clamp(margin, max_y);
+                    x = x.clamp(min_x, max_x);
+                    y = y.clamp(min_y, max_y);
                 }
             }
 
@@ -183,10 +172,10 @@ pub async fn resize_overlay(app: AppHandle, width: f64, height: f64) -> Result<(
 }
 
 #[tauri::command]
-pub async fn show_overlay(app: AppHandle) -> Result<(), String> {
+pub async fn show_overlay(app: AppHandle) -> Result<(), AppError> {
     #[cfg(desktop)]
     {
-        return show_overlay_with_reset_if_not_always(&app);
+        return show_overlay_with_reset_if_not_always(&app).map_err(AppError::from);
     }
 
     #[cfg(not(desktop))]
@@ -199,7 +188,7 @@ pub async fn show_overlay(app: AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn hide_overlay(app: AppHandle) -> Result<(), String> {
+pub async fn hide_overlay(app: AppHandle) -> Result<(), AppError> {
     if let Some(window) = app.get_webview_window("overlay") {
         window.hide().map_err(|e| e.to_string())?;
     }
@@ -208,7 +197,7 @@ pub async fn hide_overlay(app: AppHandle) -> Result<(), String> {
 
 /// Set overlay mode: "always", "never", or "recording_only"
 #[tauri::command]
-pub async fn set_overlay_mode(app: AppHandle, mode: String) -> Result<(), String> {
+pub async fn set_overlay_mode(app: AppHandle, mode: String) -> Result<(), AppError> {
     if let Some(window) = app.get_webview_window("overlay") {
         match mode.as_str() {
             "always" => {
@@ -251,7 +240,7 @@ pub async fn set_overlay_mode(app: AppHandle, mode: String) -> Result<(), String
                 });
             }
             _ => {
-                return Err(format!("Invalid overlay mode: {}", mode));
+                return Err(format!("Invalid overlay mode: {}", mode).into());
             }
         }
     }
@@ -260,6 +249,6 @@ pub async fn set_overlay_mode(app: AppHandle, mode: String) -> Result<(), String
 
 /// Set overlay widget position on screen
 #[tauri::command]
-pub async fn set_widget_position(app: AppHandle, position: String) -> Result<(), String> {
-    set_widget_position_impl(&app, position.as_str())
+pub async fn set_widget_position(app: AppHandle, position: String) -> Result<(), AppError> {
+    set_widget_position_impl(&app, Some(position.as_str())).map_err(AppError::from)
 }
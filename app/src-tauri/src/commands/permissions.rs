@@ -0,0 +1,30 @@
+use crate::error::AppError;
+use crate::permissions::{PermissionKind, PermissionStatus};
+
+/// Microphone, accessibility, input monitoring and notification permissions of the app
+#[tauri::command]
+pub fn get_permission_status() -> PermissionStatus {
+    PermissionStatus::current()
+}
+
+/// Open the system settings page where `kind` is granted
+#[cfg(desktop)]
+#[tauri::command]
+pub fn open_permission_settings(kind: PermissionKind) -> Result<(), AppError> {
+    let url = crate::permissions::settings_url(kind).ok_or_else(|| {
+        AppError::from(format!(
+            "{:?} permission has no settings page on this OS",
+            kind
+        ))
+    })?;
+    open::that(url).map_err(|e| AppError::from(format!("Failed to open {}: {}", url, e)))
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub fn open_permission_settings(kind: PermissionKind) -> Result<(), AppError> {
+    Err(AppError::from(format!(
+        "{:?} permission settings can't be opened on this platform",
+        kind
+    )))
+}
@@ -4,10 +4,19 @@
 //! enabling voice dictation directly from the Tauri app.
 
 use crate::audio_capture::{AudioCaptureDiagnostics, VadAutoStopConfig};
-use crate::pipeline::{LlmOutcome, PipelineConfig, PipelineError, PipelineState, SharedPipeline};
-use crate::recordings::{RecordingStore, RecordingsStats};
+use crate::audio_source::FileAudioSource;
+use crate::error::AppError;
+use crate::pipeline::{
+    LlmOutcome, PipelineConfig, PipelineError, PipelineState, SharedPipeline, SkipReason,
+    TranscriptionJobInfo, TranscriptionResult,
+};
+use crate::recordings::{
+    RecordingOutcome, RecordingStorageMode, RecordingStore, RecordingsStats, RetentionPolicy,
+};
 use crate::request_log::RequestLogStore;
+use crate::retry::RetryProfile;
 use crate::history::{HistoryStorage, RequestModelInfo};
+use crate::network::NetworkStatus;
 use chrono::{Duration as ChronoDuration, Utc};
 use serde::Serialize;
 use std::time::{Duration, Instant};
@@ -154,6 +163,20 @@ fn get_transcription_retention_delete_recordings(app: &AppHandle) -> bool {
     }
 }
 
+/// Tell the UI (and the request log) that a recording was not transcribed because it held
+/// no speech, so it can say so instead of showing an empty result.
+pub(crate) fn notify_if_empty_recording(app: &AppHandle, result: &TranscriptionResult) {
+    if result.skipped != Some(SkipReason::EmptyRecording) {
+        return;
+    }
+    if let Some(log_store) = app.try_state::<RequestLogStore>() {
+        log_store.with_current(|log| {
+            log.info("No speech detected; skipped transcription");
+        });
+    }
+    let _ = app.emit("pipeline-empty-recording", ());
+}
+
 pub(crate) fn apply_transcription_retention(app: &AppHandle) {
     let Some(retention) = get_transcription_retention_duration(app) else {
         return;
@@ -190,57 +213,85 @@ pub(crate) fn apply_transcription_retention(app: &AppHandle) {
     let _ = app.emit("history-changed", ());
 }
 
-/// Tauri-compatible error type for commands
-#[derive(Debug, serde::Serialize)]
-pub struct CommandError {
-    pub message: String,
-    pub error_type: String,
-}
+/// How often saved recordings are checked against the retention policy, besides after
+/// each save (so age limits apply even when nothing is being recorded).
+const RECORDING_RETENTION_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
-impl From<PipelineError> for CommandError {
-    fn from(err: PipelineError) -> Self {
-        let error_type = match &err {
-            PipelineError::AudioCapture(_) => "audio",
-            PipelineError::Stt(_) => "stt",
-            PipelineError::Llm(_) => "llm",
-            PipelineError::NoProvider => "config",
-            PipelineError::AlreadyRecording => "state",
-            PipelineError::NotRecording => "state",
-            PipelineError::Config(_) => "config",
-            PipelineError::Lock(_) => "internal",
-            PipelineError::Cancelled => "cancelled",
-            PipelineError::Timeout(_) => "timeout",
-            PipelineError::RecordingTooLarge(_, _) => "size",
+fn get_recording_retention_policy(app: &AppHandle) -> RetentionPolicy {
+    #[cfg(desktop)]
+    {
+        let store = app.store("settings.json").ok();
+        let get_u64 = |key: &str| {
+            store
+                .as_ref()
+                .and_then(|s| s.get(key))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0)
+        };
+
+        // 0 means no limit for the size and age caps.
+        let max_total_mb = get_u64("recordings_max_total_mb");
+        let max_age_days = get_u64("recordings_max_age_days").min(36_500);
+
+        return RetentionPolicy {
+            max_files: Some(get_max_saved_recordings(app)),
+            max_total_bytes: (max_total_mb > 0).then(|| max_total_mb.saturating_mul(1024 * 1024)),
+            max_age: (max_age_days > 0).then(|| Duration::from_secs(max_age_days * 24 * 60 * 60)),
         };
-        Self {
-            message: err.to_string(),
-            error_type: error_type.to_string(),
+    }
+
+    #[cfg(not(desktop))]
+    {
+        RetentionPolicy {
+            max_files: Some(get_max_saved_recordings(app)),
+            ..Default::default()
         }
     }
 }
 
-impl From<String> for CommandError {
-    fn from(message: String) -> Self {
-        Self {
-            message,
-            error_type: "unknown".to_string(),
-        }
+/// Delete saved recordings beyond the configured count/size/age limits (best-effort).
+pub(crate) fn apply_recording_retention(app: &AppHandle) {
+    let Some(store) = app.try_state::<RecordingStore>() else {
+        return;
+    };
+    match store.enforce_retention(&get_recording_retention_policy(app)) {
+        Ok(0) => {}
+        Ok(deleted) => log::info!("Recording retention: deleted {} old recording(s)", deleted),
+        Err(e) => log::warn!("Failed to apply recording retention: {}", e),
     }
 }
 
+/// Apply the recording retention policy now and then every hour in the background.
+pub(crate) fn spawn_recording_retention_sweep(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let app = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || apply_recording_retention(&app)).await;
+            tokio::time::sleep(RECORDING_RETENTION_SWEEP_INTERVAL).await;
+        }
+    });
+}
+
 /// Get the absolute path to a saved WAV recording for a given request id.
 ///
-/// Returns `null` when the recording doesn't exist.
+/// Returns `null` when the recording doesn't exist. Fails while encryption at rest is on,
+/// since the file on disk isn't playable; use `recording_get_wav_base64` instead.
 #[tauri::command]
 pub fn recording_get_wav_path(
     app: AppHandle,
     request_id: String,
-) -> Result<Option<String>, CommandError> {
+) -> Result<Option<String>, AppError> {
     let store = app
         .try_state::<RecordingStore>()
-        .ok_or_else(|| CommandError::from("Recording store not available".to_string()))?;
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
+
+    if crate::at_rest::is_enabled() {
+        return Err(AppError::from(
+            "Recordings are encrypted at rest; use recording_get_wav_base64".to_string(),
+        ));
+    }
 
-    let path = store.wav_path_if_exists(&request_id).map_err(CommandError::from)?;
+    let path = store.wav_path_if_exists(&request_id).map_err(AppError::from)?;
     Ok(path.map(|p| p.to_string_lossy().to_string()))
 }
 
@@ -254,53 +305,95 @@ pub fn recording_get_wav_path(
 pub fn recording_get_wav_base64(
     app: AppHandle,
     request_id: String,
-) -> Result<Option<String>, CommandError> {
+) -> Result<Option<String>, AppError> {
     use base64::Engine;
 
     let store = app
         .try_state::<RecordingStore>()
-        .ok_or_else(|| CommandError::from("Recording store not available".to_string()))?;
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
 
     // Reuse the same validation / existence semantics.
-    let path = store.wav_path_if_exists(&request_id).map_err(CommandError::from)?;
+    let path = store.wav_path_if_exists(&request_id).map_err(AppError::from)?;
     let Some(_) = path else {
         return Ok(None);
     };
 
-    let wav = store.load_wav(&request_id).map_err(CommandError::from)?;
+    let wav = store.load_wav(&request_id).map_err(AppError::from)?;
     let encoded = base64::engine::general_purpose::STANDARD.encode(wav);
     Ok(Some(encoded))
 }
 
 /// Open the recordings folder in the OS file manager.
 #[tauri::command]
-pub fn recordings_open_folder(app: AppHandle) -> Result<(), CommandError> {
+pub fn recordings_open_folder(app: AppHandle) -> Result<(), AppError> {
     let store = app
         .try_state::<RecordingStore>()
-        .ok_or_else(|| CommandError::from("Recording store not available".to_string()))?;
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
 
     open::that(store.directory())
-        .map_err(|e| CommandError::from(format!("Failed to open recordings folder: {}", e)))
+        .map_err(|e| AppError::from(format!("Failed to open recordings folder: {}", e)))
 }
 
 /// Total bytes used by saved recordings on disk.
 #[tauri::command]
-pub fn recordings_get_storage_bytes(app: AppHandle) -> Result<u64, CommandError> {
+pub fn recordings_get_storage_bytes(app: AppHandle) -> Result<u64, AppError> {
     let store = app
         .try_state::<RecordingStore>()
-        .ok_or_else(|| CommandError::from("Recording store not available".to_string()))?;
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
 
-    store.total_size_bytes().map_err(CommandError::from)
+    store.total_size_bytes().map_err(AppError::from)
 }
 
 /// Stats about saved recordings (count + total bytes).
 #[tauri::command]
-pub fn recordings_get_stats(app: AppHandle) -> Result<RecordingsStats, CommandError> {
+pub fn recordings_get_stats(app: AppHandle) -> Result<RecordingsStats, AppError> {
+    let store = app
+        .try_state::<RecordingStore>()
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
+
+    store.stats().map_err(AppError::from)
+}
+
+/// When recordings are written to disk.
+#[tauri::command]
+pub fn recordings_get_storage_mode(app: AppHandle) -> Result<RecordingStorageMode, AppError> {
+    let store = app
+        .try_state::<RecordingStore>()
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
+
+    Ok(store.storage_mode())
+}
+
+/// Change when recordings are written to disk (applies to the next save) and persist it.
+///
+/// Recordings saved earlier are left in place. With `Never`, cached STT responses are kept in
+/// memory only as well.
+#[tauri::command]
+pub fn recordings_set_storage_mode(
+    app: AppHandle,
+    mode: RecordingStorageMode,
+) -> Result<(), AppError> {
     let store = app
         .try_state::<RecordingStore>()
-        .ok_or_else(|| CommandError::from("Recording store not available".to_string()))?;
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
+    store.set_storage_mode(mode);
+    crate::recovery::global().set_enabled(mode != RecordingStorageMode::Never);
+    if let Some(pipeline) = app.try_state::<SharedPipeline>() {
+        pipeline.set_stt_cache_path(
+            (mode != RecordingStorageMode::Never).then(|| store.stt_cache_path()),
+        );
+    }
 
-    store.stats().map_err(CommandError::from)
+    #[cfg(desktop)]
+    {
+        let settings = app
+            .store("settings.json")
+            .map_err(|e| AppError::from(e.to_string()))?;
+        settings.set("recording_storage_mode", serde_json::json!(mode));
+        crate::app_settings::save_store(&app, &settings)?;
+    }
+
+    Ok(())
 }
 
 /// Start recording audio using the pipeline
@@ -308,7 +401,66 @@ pub fn recordings_get_stats(app: AppHandle) -> Result<RecordingsStats, CommandEr
 pub fn pipeline_start_recording(
     app: AppHandle,
     pipeline: State<'_, SharedPipeline>,
-) -> Result<(), CommandError> {
+) -> Result<(), AppError> {
+    begin_recording(&app, &pipeline, "Recording started", || pipeline.start_recording())
+}
+
+/// Start a recording fed by `pipeline_push_audio_frames` instead of the microphone
+/// (e.g. a softphone plugin or browser extension acting as a virtual microphone).
+#[tauri::command]
+pub fn pipeline_start_external_recording(
+    app: AppHandle,
+    pipeline: State<'_, SharedPipeline>,
+    sample_rate: u32,
+    channels: Option<u16>,
+) -> Result<(), AppError> {
+    let channels = channels.unwrap_or(1);
+    begin_recording(
+        &app,
+        &pipeline,
+        &format!("External recording started ({} Hz, {} ch)", sample_rate, channels),
+        || pipeline.start_external_recording(sample_rate, channels),
+    )
+}
+
+/// Start a recording that replays the WAV file at `path` as if it were spoken into the
+/// microphone, `speed` times faster than real time (default 1).
+#[tauri::command]
+pub fn pipeline_start_file_recording(
+    app: AppHandle,
+    pipeline: State<'_, SharedPipeline>,
+    path: String,
+    speed: Option<f32>,
+) -> Result<(), AppError> {
+    let source = FileAudioSource::from_path(std::path::Path::new(&path))?
+        .with_speed(speed.unwrap_or(1.0));
+    begin_recording(
+        &app,
+        &pipeline,
+        &format!("File replay recording started ({})", path),
+        || pipeline.start_source_recording(Box::new(source)),
+    )
+}
+
+/// Push interleaved f32 samples into the active external recording
+#[tauri::command]
+pub fn pipeline_push_audio_frames(
+    pipeline: State<'_, SharedPipeline>,
+    samples: Vec<f32>,
+    sample_rate: u32,
+) -> Result<(), AppError> {
+    pipeline
+        .push_audio_frames(&samples, sample_rate)
+        .map_err(AppError::from)
+}
+
+/// Open a request log, start the pipeline with `start`, and announce the recording.
+fn begin_recording(
+    app: &AppHandle,
+    pipeline: &SharedPipeline,
+    message: &str,
+    start: impl FnOnce() -> Result<(), PipelineError>,
+) -> Result<(), AppError> {
     // Start request logging
     if let Some(log_store) = app.try_state::<RequestLogStore>() {
         let config = pipeline.config();
@@ -323,11 +475,11 @@ pub fn pipeline_start_recording(
                 None
             };
             log.llm_model = config.llm_config.model.clone();
-            log.info("Recording started");
+            log.info(message);
         });
     }
 
-    pipeline.start_recording().map_err(|e| {
+    start().map_err(|e| {
         if let Some(log_store) = app.try_state::<RequestLogStore>() {
             log_store.with_current(|log| {
                 log.error(format!("Failed to start recording: {}", e));
@@ -335,12 +487,12 @@ pub fn pipeline_start_recording(
             });
             log_store.complete_current();
         }
-        CommandError::from(e)
+        AppError::from(e)
     })?;
 
     // While recording/transcribing, allow Escape to cancel without triggering transcription.
     #[cfg(desktop)]
-    crate::set_escape_cancel_shortcut_enabled(&app, true);
+    crate::set_escape_cancel_shortcut_enabled(app, true);
 
     // Emit event to frontend
     let _ = app.emit("pipeline-recording-started", ());
@@ -353,7 +505,16 @@ pub fn pipeline_start_recording(
 pub async fn pipeline_stop_and_transcribe(
     app: AppHandle,
     pipeline: State<'_, SharedPipeline>,
-) -> Result<String, CommandError> {
+) -> Result<String, AppError> {
+    // Keep logging to this request even if another recording starts meanwhile.
+    let transcription = stop_and_transcribe_request(app.clone(), pipeline.inner().clone());
+    match app.try_state::<RequestLogStore>().map(|store| (*store).clone()) {
+        Some(store) => store.scope_current(transcription).await,
+        None => transcription.await,
+    }
+}
+
+async fn stop_and_transcribe_request(app: AppHandle, pipeline: SharedPipeline) -> Result<String, AppError> {
     let max_saved_recordings = get_max_saved_recordings(&app);
 
     // Ensure Escape-to-cancel is available during the transcription phase.
@@ -399,7 +560,7 @@ pub async fn pipeline_stop_and_transcribe(
     // quiet-audio gate (hallucination protection) decides to skip STT.
     {
         let app_clone = app.clone();
-        let pipeline_clone = pipeline.inner().clone();
+        let pipeline_clone = pipeline.clone();
         tauri::async_runtime::spawn(async move {
             let start = Instant::now();
             loop {
@@ -434,7 +595,18 @@ pub async fn pipeline_stop_and_transcribe(
         });
     }
 
-    let result = match pipeline.stop_and_transcribe_detailed().await {
+    let outcome = pipeline.stop_and_transcribe_detailed().await;
+    // This recording's audio, even if a newer one has been made since.
+    let request_audio = active_request_id
+        .as_deref()
+        .and_then(|id| pipeline.audio_for_request(id))
+        .or_else(|| pipeline.clone_last_wav_bytes());
+
+    if outcome.is_err() {
+        // A partial caption of this recording won't be followed by a final one.
+        crate::commands::captions::clear_partial_caption(&app);
+    }
+    let result = match outcome {
         Ok(r) => r,
         Err(PipelineError::Cancelled) => {
             // User cancelled (Escape / cancel button). Treat as a normal outcome.
@@ -481,9 +653,9 @@ pub async fn pipeline_stop_and_transcribe(
                 active_request_id.as_deref(),
                 app.try_state::<RecordingStore>(),
             ) {
-                if let Some(wav) = pipeline.clone_last_wav_bytes() {
-                    if store.save_wav(req_id, &wav).is_ok() {
-                        let _ = store.prune_to_max_files(max_saved_recordings);
+                if let Some(ref wav) = request_audio {
+                    if let Ok(true) = store.save_wav(req_id, wav, RecordingOutcome::Failed) {
+                        apply_recording_retention(&app);
                     }
                 }
             }
@@ -495,19 +667,25 @@ pub async fn pipeline_stop_and_transcribe(
             });
             let _ = app.emit("pipeline-error", payload);
 
-            return Err(CommandError::from(e));
+            return Err(AppError::from(e));
         }
     };
 
+    notify_if_empty_recording(&app, &result);
     let final_text = result.final_text.clone();
 
     // Log success
     if let Some(log_store) = app.try_state::<RequestLogStore>() {
         log_store.with_current(|log| {
             log.raw_transcript = Some(result.stt_text.clone());
+            log.stt_language = result.stt_language.clone();
+            log.stt_segments = result.stt_segments.clone();
             log.formatted_transcript = Some(result.final_text.clone());
             log.stt_duration_ms = Some(result.stt_duration_ms);
             log.llm_duration_ms = result.llm_duration_ms;
+            if let Some(ref wav) = request_audio {
+                log.set_audio(wav);
+            }
 
             // Use the provider instance's model (includes provider defaults) so the UI can show
             // the real model used even if no explicit model override was configured.
@@ -566,9 +744,9 @@ pub async fn pipeline_stop_and_transcribe(
         active_request_id.as_deref(),
         app.try_state::<RecordingStore>(),
     ) {
-        if let Some(wav) = pipeline.clone_last_wav_bytes() {
-            if store.save_wav(req_id, &wav).is_ok() {
-                let _ = store.prune_to_max_files(max_saved_recordings);
+        if let Some(ref wav) = request_audio {
+            if let Ok(true) = store.save_wav(req_id, wav, RecordingOutcome::Succeeded) {
+                apply_recording_retention(&app);
             }
         }
     }
@@ -576,16 +754,27 @@ pub async fn pipeline_stop_and_transcribe(
     // Update history entry with success text
     if let Some(req_id) = active_request_id.as_deref() {
         if let Some(history) = app.try_state::<HistoryStorage>() {
-            let _ = history.complete_request_success(req_id, final_text.clone());
+            if history
+                .complete_request_success(
+                    req_id,
+                    final_text.clone(),
+                    result.audio_duration_secs,
+                )
+                .is_ok()
+            {
+                crate::commands::digest::append_after_dictation(&app, req_id);
+            }
             let _ = app.emit("history-changed", ());
         }
     }
+    crate::deferred_format::enqueue_if_needed(&app, active_request_id.as_deref(), &result);
 
     // Time-based retention (best-effort). Runs only after a transcription attempt.
     apply_transcription_retention(&app);
 
     // Emit transcript ready event
     let _ = app.emit("pipeline-transcript-ready", &final_text);
+    crate::commands::captions::publish_caption(&app, &final_text, true);
 
     // Done transcribing - stop stealing Escape.
     #[cfg(desktop)]
@@ -603,20 +792,61 @@ pub async fn pipeline_retry_transcription(
     app: AppHandle,
     pipeline: State<'_, SharedPipeline>,
     request_id: String,
-) -> Result<String, CommandError> {
-    let max_saved_recordings = get_max_saved_recordings(&app);
+) -> Result<String, AppError> {
+    let wav = app
+        .try_state::<RecordingStore>()
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?
+        .load_wav(&request_id)
+        .map_err(AppError::from)?;
+    let text = transcribe_saved_audio(&app, &pipeline, bytes::Bytes::from(wav)).await?;
+    Ok(text.unwrap_or_default())
+}
+
+/// Transcriptions the app was running when it last exited, which can be recovered.
+#[tauri::command]
+pub fn get_orphaned_recordings() -> Vec<crate::recovery::OrphanedRecording> {
+    crate::recovery::global().orphans()
+}
+
+/// Transcribe an interrupted recording into history, like a retry. The recording is
+/// discarded once it was transcribed; returns `None` if the transcription was cancelled.
+#[tauri::command]
+pub async fn recover_orphaned_recording(
+    app: AppHandle,
+    pipeline: State<'_, SharedPipeline>,
+    id: String,
+) -> Result<Option<String>, AppError> {
+    let recovery = crate::recovery::global();
+    let wav = recovery.load_wav(&id).map_err(AppError::from)?;
+    let text = transcribe_saved_audio(&app, &pipeline, bytes::Bytes::from(wav)).await?;
+    if text.is_some() {
+        recovery.finish(&id);
+    }
+    Ok(text)
+}
+
+/// Delete an interrupted recording without transcribing it.
+#[tauri::command]
+pub fn discard_orphaned_recording(id: String) {
+    crate::recovery::global().finish(&id);
+}
+
+/// Run STT (+ optional LLM) on saved audio as a new request with its own log and history
+/// entry. `None` if it was cancelled.
+async fn transcribe_saved_audio(
+    app: &AppHandle,
+    pipeline: &SharedPipeline,
+    wav: bytes::Bytes,
+) -> Result<Option<String>, AppError> {
+    let max_saved_recordings = get_max_saved_recordings(app);
 
     // Allow Escape-to-cancel while the retry transcription is running.
     #[cfg(desktop)]
-    crate::set_escape_cancel_shortcut_enabled(&app, true);
+    crate::set_escape_cancel_shortcut_enabled(app, true);
 
     let recording_store = app
         .try_state::<RecordingStore>()
-        .ok_or_else(|| CommandError::from("Recording store not available".to_string()))?;
-
-    let wav = recording_store
-        .load_wav(&request_id)
-        .map_err(CommandError::from)?;
+        .ok_or_else(|| AppError::from("Recording store not available".to_string()))?;
 
     // Start a *new* request log for the retry attempt.
     let config = pipeline.config();
@@ -651,17 +881,21 @@ pub async fn pipeline_retry_transcription(
     let _ = app.emit("pipeline-transcription-started", ());
 
     // Run the retry transcription (STT + optional LLM)
-    let result = match pipeline.transcribe_wav_bytes_detailed(wav.clone()).await {
+    // The request already failed or was interrupted once; give the saved audio the full backoff.
+    let result = match pipeline
+        .transcribe_wav_bytes_detailed(wav.clone(), RetryProfile::Background)
+        .await
+    {
         Ok(r) => r,
         Err(PipelineError::Cancelled) => {
             #[cfg(desktop)]
-            crate::set_escape_cancel_shortcut_enabled(&app, false);
+            crate::set_escape_cancel_shortcut_enabled(app, false);
             let _ = app.emit("pipeline-cancelled", ());
-            return Ok(String::new());
+            return Ok(None);
         }
         Err(e) => {
             #[cfg(desktop)]
-            crate::set_escape_cancel_shortcut_enabled(&app, false);
+            crate::set_escape_cancel_shortcut_enabled(app, false);
 
             if let Some(log_store) = app.try_state::<RequestLogStore>() {
                 log_store.with_current(|log| {
@@ -685,14 +919,14 @@ pub async fn pipeline_retry_transcription(
             });
             let _ = app.emit("pipeline-error", payload);
 
-            return Err(CommandError::from(e));
+            return Err(AppError::from(e));
         }
     };
 
     // Persist audio under the *new* request id (best-effort)
     if let Some(req_id) = new_request_id.as_deref() {
-        if recording_store.save_wav(req_id, &wav).is_ok() {
-            let _ = recording_store.prune_to_max_files(max_saved_recordings);
+        if let Ok(true) = recording_store.save_wav(req_id, &wav, RecordingOutcome::Succeeded) {
+            apply_recording_retention(app);
         }
     }
 
@@ -702,9 +936,12 @@ pub async fn pipeline_retry_transcription(
     if let Some(log_store) = app.try_state::<RequestLogStore>() {
         log_store.with_current(|log| {
             log.raw_transcript = Some(result.stt_text.clone());
+            log.stt_language = result.stt_language.clone();
+            log.stt_segments = result.stt_segments.clone();
             log.formatted_transcript = Some(result.final_text.clone());
             log.stt_duration_ms = Some(result.stt_duration_ms);
             log.llm_duration_ms = result.llm_duration_ms;
+            log.set_audio(&wav);
 
             if result.llm_attempted() {
                 log.llm_provider = result.llm_provider_used.clone();
@@ -724,18 +961,25 @@ pub async fn pipeline_retry_transcription(
     // Update history on success
     if let Some(req_id) = new_request_id.as_deref() {
         if let Some(history) = app.try_state::<HistoryStorage>() {
-            let _ = history.complete_request_success(req_id, final_text.clone());
+            if history
+                .complete_request_success(req_id, final_text.clone(), result.audio_duration_secs)
+                .is_ok()
+            {
+                crate::commands::digest::append_after_dictation(app, req_id);
+            }
             let _ = app.emit("history-changed", ());
         }
     }
+    crate::deferred_format::enqueue_if_needed(app, new_request_id.as_deref(), &result);
 
     // Emit transcript ready event
     let _ = app.emit("pipeline-transcript-ready", &final_text);
+    crate::commands::captions::publish_caption(app, &final_text, true);
 
     #[cfg(desktop)]
-    crate::set_escape_cancel_shortcut_enabled(&app, false);
+    crate::set_escape_cancel_shortcut_enabled(app, false);
 
-    Ok(final_text)
+    Ok(Some(final_text))
 }
 
 /// Cancel the current recording/transcription
@@ -743,7 +987,7 @@ pub async fn pipeline_retry_transcription(
 pub fn pipeline_cancel(
     app: AppHandle,
     pipeline: State<'_, SharedPipeline>,
-) -> Result<(), CommandError> {
+) -> Result<(), AppError> {
     // `pipeline` is kept for API stability; on desktop we delegate to a helper that
     // re-acquires the shared pipeline from app state.
     let _ = pipeline;
@@ -779,7 +1023,7 @@ pub fn pipeline_cancel(
 #[tauri::command]
 pub fn pipeline_get_state(
     pipeline: State<'_, SharedPipeline>,
-) -> Result<String, CommandError> {
+) -> Result<String, AppError> {
     let state = pipeline.state();
     let state_str = match state {
         PipelineState::Idle => "idle",
@@ -791,14 +1035,43 @@ pub fn pipeline_get_state(
     Ok(state_str.to_string())
 }
 
+/// List queued, running and recently finished transcription jobs (oldest first)
+#[tauri::command]
+pub fn pipeline_list_jobs(pipeline: State<'_, SharedPipeline>) -> Vec<TranscriptionJobInfo> {
+    pipeline.jobs()
+}
+
+/// Cancel a single queued or running transcription job.
+///
+/// Returns false if the job had already finished.
+#[tauri::command]
+pub fn pipeline_cancel_job(pipeline: State<'_, SharedPipeline>, id: u64) -> bool {
+    pipeline.cancel_job(id)
+}
+
 /// Check if the pipeline is currently recording
 #[tauri::command]
 pub fn pipeline_is_recording(
     pipeline: State<'_, SharedPipeline>,
-) -> Result<bool, CommandError> {
+) -> Result<bool, AppError> {
     Ok(pipeline.is_recording())
 }
 
+/// Get the current network status (offline mode) of the pipeline
+#[tauri::command]
+pub fn pipeline_get_network_status(pipeline: State<'_, SharedPipeline>) -> NetworkStatus {
+    NetworkStatus {
+        online: !pipeline.is_offline(),
+        local_stt_available: pipeline.local_stt_available(),
+    }
+}
+
+/// Drop all cached STT responses, forcing the next transcription of any audio to hit the provider
+#[tauri::command]
+pub fn pipeline_clear_stt_cache(pipeline: State<'_, SharedPipeline>) {
+    pipeline.clear_stt_cache();
+}
+
 /// Configuration payload for updating the pipeline
 #[derive(Debug, serde::Deserialize)]
 pub struct PipelineConfigPayload {
@@ -820,10 +1093,10 @@ pub struct PipelineConfigPayload {
 pub fn pipeline_update_config(
     pipeline: State<'_, SharedPipeline>,
     config: PipelineConfigPayload,
-) -> Result<(), CommandError> {
+) -> Result<(), AppError> {
     use std::collections::HashMap;
 
-    let mut retry_config = crate::stt::RetryConfig::default();
+    let mut retry_config = crate::retry::RetryConfig::default();
     if let Some(max_retries) = config.max_retries {
         retry_config.max_retries = max_retries;
     }
@@ -851,7 +1124,7 @@ pub fn pipeline_update_config(
     new_config.llm_config = crate::llm::LlmConfig::default();
     new_config.llm_api_keys = HashMap::new();
 
-    pipeline.update_config(new_config).map_err(CommandError::from)?;
+    pipeline.update_config(new_config).map_err(AppError::from)?;
     log::info!("Pipeline configuration updated");
 
     Ok(())
@@ -863,7 +1136,16 @@ pub fn pipeline_update_config(
 pub async fn pipeline_dictate(
     app: AppHandle,
     pipeline: State<'_, SharedPipeline>,
-) -> Result<String, CommandError> {
+) -> Result<String, AppError> {
+    // Scoped like `pipeline_stop_and_transcribe`.
+    let transcription = dictate_request(app.clone(), pipeline.inner().clone());
+    match app.try_state::<RequestLogStore>().map(|store| (*store).clone()) {
+        Some(store) => store.scope_current(transcription).await,
+        None => transcription.await,
+    }
+}
+
+async fn dictate_request(app: AppHandle, pipeline: SharedPipeline) -> Result<String, AppError> {
     // Ensure Escape-to-cancel remains available while we transcribe.
     #[cfg(desktop)]
     crate::set_escape_cancel_shortcut_enabled(&app, true);
@@ -879,7 +1161,7 @@ pub async fn pipeline_dictate(
     // Transcribing state (avoid flashing "TRANSCRIBING..." on quiet-audio skips).
     {
         let app_clone = app.clone();
-        let pipeline_clone = pipeline.inner().clone();
+        let pipeline_clone = pipeline.clone();
         tauri::async_runtime::spawn(async move {
             let start = Instant::now();
             loop {
@@ -902,7 +1184,19 @@ pub async fn pipeline_dictate(
         });
     }
 
-    let result = match pipeline.stop_and_transcribe_detailed().await {
+    let outcome = pipeline.stop_and_transcribe_detailed().await;
+    // This recording's audio, even if a newer one has been made since.
+    let request_audio = app
+        .try_state::<RequestLogStore>()
+        .and_then(|store| store.with_current(|log| log.id.clone()))
+        .and_then(|id| pipeline.audio_for_request(&id))
+        .or_else(|| pipeline.clone_last_wav_bytes());
+
+    if outcome.is_err() {
+        // A partial caption of this recording won't be followed by a final one.
+        crate::commands::captions::clear_partial_caption(&app);
+    }
+    let result = match outcome {
         Ok(r) => r,
         Err(PipelineError::Cancelled) => {
             #[cfg(desktop)]
@@ -921,14 +1215,16 @@ pub async fn pipeline_dictate(
                 });
                 log_store.complete_current();
             }
-            return Err(CommandError::from(e));
+            return Err(AppError::from(e));
         }
     };
 
+    notify_if_empty_recording(&app, &result);
     let final_text = result.final_text.clone();
 
     // Emit transcript ready event
     let _ = app.emit("pipeline-transcript-ready", &final_text);
+    crate::commands::captions::publish_caption(&app, &final_text, true);
 
     // Type the transcript
     if !final_text.is_empty() {
@@ -938,25 +1234,35 @@ pub async fn pipeline_dictate(
             });
         }
 
-        crate::commands::text::type_text(app.clone(), final_text.clone())
-            .await
-            .map_err(|e| {
-                if let Some(log_store) = app.try_state::<RequestLogStore>() {
-                    log_store.with_current(|log| {
-                        log.error(format!("Failed to type text: {}", e));
-                    });
-                }
-                CommandError::from(e)
-            })?;
+        let typing_started = Instant::now();
+        let typed = crate::commands::text::type_text(app.clone(), final_text.clone()).await;
+        if let Some(log_store) = app.try_state::<RequestLogStore>() {
+            log_store.with_current(|log| {
+                log.typing_duration_ms = Some(typing_started.elapsed().as_millis() as u64);
+            });
+        }
+        typed.map_err(|e| {
+            if let Some(log_store) = app.try_state::<RequestLogStore>() {
+                log_store.with_current(|log| {
+                    log.error(format!("Failed to type text: {}", e));
+                });
+            }
+            e
+        })?;
     }
 
     // Log success
     if let Some(log_store) = app.try_state::<RequestLogStore>() {
         log_store.with_current(|log| {
             log.raw_transcript = Some(result.stt_text.clone());
+            log.stt_language = result.stt_language.clone();
+            log.stt_segments = result.stt_segments.clone();
             log.formatted_transcript = Some(result.final_text.clone());
             log.stt_duration_ms = Some(result.stt_duration_ms);
             log.llm_duration_ms = result.llm_duration_ms;
+            if let Some(ref wav) = request_audio {
+                log.set_audio(wav);
+            }
 
             log.info(format!(
                 "STT completed in {}ms ({} chars)",
@@ -1016,19 +1322,62 @@ pub async fn pipeline_dictate(
 pub async fn pipeline_test_transcribe_last_audio(
     pipeline: State<'_, SharedPipeline>,
     profile_id: Option<String>,
-) -> Result<String, CommandError> {
+) -> Result<String, AppError> {
     pipeline
         .transcribe_last_audio_for_profile(profile_id.as_deref())
         .await
-        .map_err(CommandError::from)
+        .map_err(AppError::from)
 }
 
 /// Whether there is a previously captured audio buffer available for STT testing.
 #[tauri::command]
-pub fn pipeline_has_last_audio(pipeline: State<'_, SharedPipeline>) -> Result<bool, CommandError> {
+pub fn pipeline_has_last_audio(pipeline: State<'_, SharedPipeline>) -> Result<bool, AppError> {
     Ok(pipeline.has_last_audio())
 }
 
+#[derive(Debug, Serialize)]
+pub struct SttProviderTestResponse {
+    pub success: bool,
+    /// Round-trip time of the test transcription, in milliseconds.
+    pub latency_ms: u64,
+    pub transcript: Option<String>,
+    pub error: Option<AppError>,
+}
+
+/// Check an STT provider's key/model by transcribing a 1-second tone generated at runtime.
+///
+/// `key` overrides the stored API key so settings can be validated before they are saved.
+/// The test request is not written to the request log.
+#[tauri::command]
+pub async fn test_stt_provider(
+    pipeline: State<'_, SharedPipeline>,
+    provider: String,
+    key: Option<String>,
+    model: Option<String>,
+) -> Result<SttProviderTestResponse, AppError> {
+    let started = Instant::now();
+    let result = pipeline.test_stt_provider(&provider, key, model).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    Ok(match result {
+        Ok(transcript) => SttProviderTestResponse {
+            success: true,
+            latency_ms,
+            transcript: Some(transcript),
+            error: None,
+        },
+        Err(e) => {
+            log::warn!("STT provider test for '{}' failed: {}", provider, e);
+            SttProviderTestResponse {
+                success: false,
+                latency_ms,
+                transcript: None,
+                error: Some(AppError::from(e)),
+            }
+        }
+    })
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct AudioSettingsTestWavs {
     pub raw_wav_base64: String,
@@ -1042,20 +1391,20 @@ pub struct AudioSettingsTestWavs {
 #[tauri::command]
 pub fn pipeline_test_audio_settings_start_recording(
     pipeline: State<'_, SharedPipeline>,
-) -> Result<(), CommandError> {
-    pipeline.start_recording().map_err(CommandError::from)
+) -> Result<(), AppError> {
+    pipeline.start_recording().map_err(AppError::from)
 }
 
 /// Stop the audio settings A/B test recording and return before/after audio.
 #[tauri::command]
 pub fn pipeline_test_audio_settings_stop_recording(
     pipeline: State<'_, SharedPipeline>,
-) -> Result<AudioSettingsTestWavs, CommandError> {
+) -> Result<AudioSettingsTestWavs, AppError> {
     use base64::Engine;
 
     let (raw_wav, processed_wav) = pipeline
         .stop_recording_before_after()
-        .map_err(CommandError::from)?;
+        .map_err(AppError::from)?;
 
     Ok(AudioSettingsTestWavs {
         raw_wav_base64: base64::engine::general_purpose::STANDARD.encode(raw_wav),
@@ -1067,7 +1416,7 @@ pub fn pipeline_test_audio_settings_stop_recording(
 #[tauri::command]
 pub fn pipeline_get_last_recording_diagnostics(
     pipeline: State<'_, SharedPipeline>,
-) -> Result<Option<AudioCaptureDiagnostics>, CommandError> {
+) -> Result<Option<AudioCaptureDiagnostics>, AppError> {
     Ok(pipeline.last_recording_diagnostics())
 }
 
@@ -1076,14 +1425,14 @@ pub fn pipeline_get_last_recording_diagnostics(
 pub async fn pipeline_toggle(
     app: AppHandle,
     pipeline: State<'_, SharedPipeline>,
-) -> Result<String, CommandError> {
+) -> Result<String, AppError> {
     if pipeline.is_recording() {
         pipeline_dictate(app, pipeline).await
     } else {
         // Try to start the pipeline FIRST - don't create a log if it fails
         pipeline.start_recording().map_err(|e| {
             log::warn!("Toggle: Failed to start recording: {}", e);
-            CommandError::from(e)
+            AppError::from(e)
         })?;
 
         #[cfg(desktop)]
@@ -1116,7 +1465,7 @@ pub async fn pipeline_toggle(
 #[tauri::command]
 pub fn pipeline_is_error(
     pipeline: State<'_, SharedPipeline>,
-) -> Result<bool, CommandError> {
+) -> Result<bool, AppError> {
     Ok(pipeline.is_error())
 }
 
@@ -1126,7 +1475,7 @@ pub fn pipeline_is_error(
 pub fn pipeline_force_reset(
     app: AppHandle,
     pipeline: State<'_, SharedPipeline>,
-) -> Result<(), CommandError> {
+) -> Result<(), AppError> {
     pipeline.force_reset();
     log::info!("Pipeline force reset to Idle state");
 
@@ -1145,7 +1494,8 @@ mod tests {
 
     #[test]
     fn test_command_error_from_string() {
-        let error = CommandError::from("test error".to_string());
-        assert_eq!(error.message, "test error");
+        let error = AppError::from("test error".to_string());
+        assert_eq!(error.to_string(), "test error");
+        assert_eq!(error.code(), "unknown");
     }
 }
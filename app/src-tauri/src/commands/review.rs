@@ -0,0 +1,266 @@
+//! Review-before-output flow.
+//!
+//! With `review_before_output` on, a finished transcript is not typed right away. It is
+//! queued here and shown in an editable review window; confirming outputs the (possibly
+//! edited) text through the normal `output_text_with_mode`, discarding drops it. Edits are
+//! learned as corrections (see `crate::corrections`).
+
+use crate::commands::text::{output_text_with_mode, OutputOptions};
+use crate::corrections::LearnedCorrection;
+use crate::error::AppError;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+#[cfg(desktop)]
+use tauri_plugin_store::StoreExt;
+
+/// Window label of the review window.
+pub const REVIEW_WINDOW_LABEL: &str = "review";
+
+/// A transcript waiting for review, as sent to the review window.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewItem {
+    pub id: u64,
+    /// Request log id of the recording, if request logging is enabled.
+    pub request_id: Option<String>,
+    pub text: String,
+}
+
+struct PendingReview {
+    item: ReviewItem,
+    options: OutputOptions,
+    /// Webhook the confirmed text is also POSTed to (see `crate::webhook`).
+    webhook_url: Option<String>,
+}
+
+/// Transcripts waiting for review, oldest first.
+#[derive(Default)]
+pub struct ReviewQueue {
+    pending: Mutex<VecDeque<PendingReview>>,
+    next_id: Mutex<u64>,
+}
+
+impl ReviewQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(
+        &self,
+        text: String,
+        request_id: Option<String>,
+        options: OutputOptions,
+        webhook_url: Option<String>,
+    ) -> ReviewItem {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let item = ReviewItem { id, request_id, text };
+        self.pending.lock().unwrap().push_back(PendingReview {
+            item: item.clone(),
+            options,
+            webhook_url,
+        });
+        item
+    }
+
+    fn take(&self, id: u64) -> Option<PendingReview> {
+        let mut pending = self.pending.lock().unwrap();
+        let index = pending.iter().position(|p| p.item.id == id)?;
+        pending.remove(index)
+    }
+
+    fn items(&self) -> Vec<ReviewItem> {
+        self.pending.lock().unwrap().iter().map(|p| p.item.clone()).collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.lock().unwrap().is_empty()
+    }
+}
+
+/// Whether transcripts should go through the review window instead of being output.
+#[cfg(desktop)]
+pub fn review_enabled(app: &AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get("review_before_output"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+#[cfg(not(desktop))]
+pub fn review_enabled(_app: &AppHandle) -> bool {
+    false
+}
+
+/// Queue `text` for review and show it in the review window.
+pub fn request_review(
+    app: &AppHandle,
+    text: String,
+    request_id: Option<String>,
+    options: OutputOptions,
+    webhook_url: Option<String>,
+) -> Result<(), String> {
+    let queue = app
+        .try_state::<ReviewQueue>()
+        .ok_or_else(|| "Review queue not available".to_string())?;
+    let item = queue.push(text, request_id, options, webhook_url);
+    show_review_window(app)?;
+    let _ = app.emit_to(REVIEW_WINDOW_LABEL, "review-requested", item);
+    Ok(())
+}
+
+fn show_review_window(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(REVIEW_WINDOW_LABEL) {
+        window.show().map_err(|e| e.to_string())?;
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    let window = tauri::WebviewWindowBuilder::new(
+        app,
+        REVIEW_WINDOW_LABEL,
+        tauri::WebviewUrl::App("review.html".into()),
+    )
+    .title("Tangerine Review")
+    .inner_size(640.0, 320.0)
+    .min_inner_size(360.0, 200.0)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .visible_on_all_workspaces(true)
+    .build()
+    .map_err(|e| e.to_string())?;
+
+    // Centered on the monitor the user is working on.
+    if let Some(display) = crate::placement::active_display(app) {
+        let bounds = display.bounds;
+        let (width, height) = (640.0 * display.scale, 320.0 * display.scale);
+        let x = bounds.x + ((bounds.width - width) / 2.0).max(0.0);
+        let y = bounds.y + ((bounds.height - height) / 2.0).max(0.0);
+        let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
+            x: x.round() as i32,
+            y: y.round() as i32,
+        }));
+    }
+    Ok(())
+}
+
+/// Hide the review window, giving focus back to the app the text is meant for.
+fn hide_review_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(REVIEW_WINDOW_LABEL) {
+        let _ = window.hide();
+    }
+}
+
+/// Learn the corrections between `original` and `edited` and apply them to the pipeline.
+#[cfg(desktop)]
+fn learn_corrections(app: &AppHandle, original: &str, edited: &str) -> Result<(), String> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    let mut corrections: Vec<LearnedCorrection> = store
+        .get("learned_corrections")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    if !crate::corrections::record(&mut corrections, original, edited) {
+        return Ok(());
+    }
+    store.set("learned_corrections", serde_json::json!(corrections));
+    crate::app_settings::save_store(app, &store)?;
+    crate::commands::config::sync_pipeline_config(app.clone()).map_err(|e| e.to_string())
+}
+
+#[cfg(not(desktop))]
+fn learn_corrections(_app: &AppHandle, _original: &str, _edited: &str) -> Result<(), String> {
+    Ok(())
+}
+
+/// Transcripts waiting for review (oldest first).
+#[tauri::command]
+pub fn get_pending_reviews(queue: tauri::State<'_, ReviewQueue>) -> Vec<ReviewItem> {
+    queue.items()
+}
+
+/// Output a reviewed transcript. `text` is the transcript as edited by the user.
+#[tauri::command]
+pub async fn confirm_review(app: AppHandle, id: u64, text: String) -> Result<(), AppError> {
+    let queue = app
+        .try_state::<ReviewQueue>()
+        .ok_or_else(|| AppError::from("Review queue not available".to_string()))?;
+    let review = queue
+        .take(id)
+        .ok_or_else(|| AppError::from(format!("No pending review with id {}", id)))?;
+    let has_more = !queue.is_empty();
+
+    if text.trim() != review.item.text.trim() {
+        if let Err(e) = learn_corrections(&app, &review.item.text, &text) {
+            log::warn!("Review: failed to learn corrections: {}", e);
+        }
+    }
+
+    hide_review_window(&app);
+    crate::ipc_output::publish(&crate::ipc_output::IpcMessage::transcript(
+        &text,
+        review.item.request_id.clone(),
+    ));
+    if let Some(url) = review.webhook_url {
+        crate::webhook::spawn_send(&app, url, text.clone(), review.item.request_id.clone());
+    }
+    let options = review.options;
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        output_text_with_mode(&text, options.mode, options.hit_enter, &options.typing)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if has_more {
+        show_review_window(&app).map_err(AppError::from)?;
+    }
+    output.map_err(AppError::from)
+}
+
+/// Drop a transcript without outputting it.
+#[tauri::command]
+pub fn discard_review(app: AppHandle, id: u64) -> Result<(), AppError> {
+    let queue = app
+        .try_state::<ReviewQueue>()
+        .ok_or_else(|| AppError::from("Review queue not available".to_string()))?;
+    queue.take(id);
+    if queue.is_empty() {
+        hide_review_window(&app);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::text::{OutputMode, TypingConfig};
+    use crate::output_format::OutputFormat;
+
+    #[test]
+    fn test_review_queue_takes_items_by_id() {
+        let queue = ReviewQueue::new();
+        let options = OutputOptions {
+            mode: OutputMode::Paste,
+            hit_enter: false,
+            typing: TypingConfig::default(),
+            format: OutputFormat::default(),
+        };
+        let first = queue.push("first".to_string(), None, options, None);
+        let second = queue.push(
+            "second".to_string(),
+            Some("req".to_string()),
+            options,
+            Some("https://example.com/hook".to_string()),
+        );
+        assert_ne!(first.id, second.id);
+
+        assert_eq!(queue.take(second.id).map(|p| p.item.text), Some("second".to_string()));
+        assert!(queue.take(second.id).is_none());
+        assert_eq!(queue.items().len(), 1);
+        assert!(!queue.is_empty());
+    }
+}
@@ -1,5 +1,8 @@
+use crate::app_settings::{self, AppSettings, SettingsValidationError};
+use crate::settings_transfer::{ConfigFile, FileFormat, ImportConflict, ImportSummary};
+use crate::error::AppError;
 use crate::settings::HotkeyConfig;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 
 #[cfg(desktop)]
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
@@ -11,7 +14,7 @@ use tauri_plugin_store::StoreExt;
 /// Call this before capturing a new hotkey to prevent the shortcuts from intercepting key presses.
 #[cfg(desktop)]
 #[tauri::command]
-pub async fn unregister_shortcuts(app: AppHandle) -> Result<(), String> {
+pub async fn unregister_shortcuts(app: AppHandle) -> Result<(), AppError> {
     log::info!("Temporarily unregistering all shortcuts for hotkey capture");
     let shortcut_manager = app.global_shortcut();
     shortcut_manager
@@ -23,7 +26,7 @@ pub async fn unregister_shortcuts(app: AppHandle) -> Result<(), String> {
 // Stub for non-desktop platforms
 #[cfg(not(desktop))]
 #[tauri::command]
-pub async fn unregister_shortcuts(_app: AppHandle) -> Result<(), String> {
+pub async fn unregister_shortcuts(_app: AppHandle) -> Result<(), AppError> {
     Ok(())
 }
 
@@ -46,7 +49,7 @@ fn get_setting_from_store<T: serde::de::DeserializeOwned>(
 /// Falls back to defaults if stored values are invalid.
 #[cfg(desktop)]
 #[tauri::command]
-pub async fn register_shortcuts(app: AppHandle) -> Result<(), String> {
+pub async fn register_shortcuts(app: AppHandle) -> Result<(), AppError> {
     // Read hotkeys from store with defaults
     let toggle_hotkey: HotkeyConfig =
         get_setting_from_store(&app, "toggle_hotkey", HotkeyConfig::default_toggle());
@@ -80,7 +83,12 @@ pub async fn register_shortcuts(app: AppHandle) -> Result<(), String> {
         .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
 
     // Collect shortcuts to register
-    let shortcuts: Vec<Shortcut> = vec![toggle_shortcut, hold_shortcut, paste_last_shortcut];
+    let mut shortcuts: Vec<Shortcut> = vec![toggle_shortcut, hold_shortcut, paste_last_shortcut];
+    shortcuts.extend(
+        crate::profile_shortcuts(&app, &shortcuts)
+            .into_iter()
+            .map(|(_, s)| s),
+    );
 
     // Register new shortcuts with handler
     shortcut_manager
@@ -104,6 +112,188 @@ pub async fn register_shortcuts(app: AppHandle) -> Result<(), String> {
 // Stub for non-desktop platforms
 #[cfg(not(desktop))]
 #[tauri::command]
-pub async fn register_shortcuts(_app: AppHandle) -> Result<(), String> {
+pub async fn register_shortcuts(_app: AppHandle) -> Result<(), AppError> {
+    Ok(())
+}
+
+/// `settings.json` as `AppSettings`, with the stored values that don't pass validation.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SettingsSnapshot {
+    pub settings: AppSettings,
+    pub errors: Vec<SettingsValidationError>,
+}
+
+/// Push saved settings to the running app: pipeline, shortcuts and other windows.
+#[cfg(desktop)]
+fn apply_changed_settings(
+    app: &AppHandle,
+    patch: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), AppError> {
+    crate::commands::config::sync_pipeline_config(app.clone())?;
+    if let Some(level) = patch.get("log_level").and_then(|v| v.as_str()) {
+        crate::app_log::set_level(level)?;
+    }
+    if patch
+        .keys()
+        .any(|key| key.ends_with("_hotkey") || key == "rewrite_program_prompt_profiles")
+    {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = register_shortcuts(app).await {
+                log::warn!("Failed to re-register shortcuts: {}", e);
+            }
+        });
+    }
+    let _ = app.emit("settings-changed", patch);
     Ok(())
 }
+
+/// Read all settings through the typed schema.
+#[cfg(desktop)]
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> Result<SettingsSnapshot, AppError> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    let (settings, mut errors) = AppSettings::from_values(app_settings::store_values(&store));
+    errors.extend(settings.validate());
+    Ok(SettingsSnapshot { settings, errors })
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub fn get_settings(_app: AppHandle) -> Result<SettingsSnapshot, AppError> {
+    Ok(SettingsSnapshot {
+        settings: AppSettings::default(),
+        errors: Vec::new(),
+    })
+}
+
+/// Validate `patch` (key -> value; `null` resets a key to its default) against the schema
+/// and save it. Nothing is written if any value is rejected; the error lists each one.
+#[cfg(desktop)]
+#[tauri::command]
+pub fn update_settings(
+    app: AppHandle,
+    patch: serde_json::Map<String, serde_json::Value>,
+) -> Result<AppSettings, AppError> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    let (current, _) = AppSettings::from_values(app_settings::store_values(&store));
+    let updated = current
+        .apply_patch(&patch)
+        .map_err(AppError::InvalidSettings)?;
+
+    for (key, value) in &patch {
+        if value.is_null() {
+            store.delete(key);
+        } else {
+            store.set(key.clone(), value.clone());
+        }
+    }
+    app_settings::save_store(&app, &store)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    apply_changed_settings(&app, &patch)?;
+    Ok(updated)
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub fn update_settings(
+    _app: AppHandle,
+    _patch: serde_json::Map<String, serde_json::Value>,
+) -> Result<AppSettings, AppError> {
+    Err(AppError::Config("Settings are not available on this platform".to_string()))
+}
+
+/// Save `settings.json` after the frontend changed it through the store plugin. The
+/// plugin's own `save` writes the file in place; this replaces it atomically.
+#[cfg(desktop)]
+#[tauri::command]
+pub fn save_settings(app: AppHandle) -> Result<(), AppError> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    app_settings::save_store(&app, &store)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    Ok(())
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub fn save_settings(_app: AppHandle) -> Result<(), AppError> {
+    Err(AppError::Config("Settings are not available on this platform".to_string()))
+}
+
+/// Export the configuration to `path` (`.toml` for TOML, JSON otherwise). API keys and
+/// other secrets are left out unless `include_secrets` is set.
+#[cfg(desktop)]
+#[tauri::command]
+pub fn export_settings(
+    app: AppHandle,
+    path: String,
+    include_secrets: bool,
+) -> Result<(), AppError> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    let path = std::path::PathBuf::from(path);
+    let text = ConfigFile::export(app_settings::store_values(&store), include_secrets)
+        .to_text(FileFormat::from_path(&path))?;
+    crate::persist::write_replace(&path, text.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    log::info!("Exported settings to {}", path.display());
+    Ok(())
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub fn export_settings(
+    _app: AppHandle,
+    _path: String,
+    _include_secrets: bool,
+) -> Result<(), AppError> {
+    Err(AppError::Config("Settings are not available on this platform".to_string()))
+}
+
+/// Import a file written by `export_settings`. Imported values are validated like
+/// `update_settings`; nothing is written if any is rejected.
+#[cfg(desktop)]
+#[tauri::command]
+pub fn import_settings(
+    app: AppHandle,
+    path: String,
+    conflict: Option<ImportConflict>,
+) -> Result<ImportSummary, AppError> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    let path = std::path::PathBuf::from(path);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file = ConfigFile::parse(&text, FileFormat::from_path(&path))
+        .map_err(|e| format!("Invalid settings file {}: {}", path.display(), e))?;
+
+    let local = app_settings::store_values(&store);
+    let (patch, summary) = file.import_patch(&local, conflict.unwrap_or_default());
+    let (current, _) = AppSettings::from_values(local);
+    current
+        .apply_patch(&patch)
+        .map_err(AppError::InvalidSettings)?;
+
+    for (key, value) in &patch {
+        store.set(key.clone(), value.clone());
+    }
+    app_settings::save_store(&app, &store)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    log::info!(
+        "Imported {} settings from {}",
+        summary.imported.len(),
+        path.display()
+    );
+
+    apply_changed_settings(&app, &patch)?;
+    Ok(summary)
+}
+
+#[cfg(not(desktop))]
+#[tauri::command]
+pub fn import_settings(
+    _app: AppHandle,
+    _path: String,
+    _conflict: Option<ImportConflict>,
+) -> Result<ImportSummary, AppError> {
+    Err(AppError::Config("Settings are not available on this platform".to_string()))
+}
@@ -1,74 +1,212 @@
+use crate::accessibility;
+use crate::error::AppError;
+use crate::output_audit::{self, OutputAuditEntry};
+use crate::output_format::{self, OutputFormat};
+pub use crate::output_mode::OutputMode;
 use arboard::Clipboard;
-use enigo::{Direction, Enigo, Key, Keyboard, Settings};
-use std::sync::{Mutex, OnceLock};
-use std::sync::mpsc;
+use crate::injection::{self, InjectionBackend, KeyInjector};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
 use tauri::AppHandle;
 
-/// Delay after clipboard operations to ensure system stability
-const CLIPBOARD_STABILIZATION_DELAY_MS: u64 = 50;
+/// Delay after re-focusing the original window so the target is ready to receive input
+const FOCUS_RESTORE_DELAY_MS: u64 = 80;
 
-/// Delay between keyboard key press and release events
-const KEY_EVENT_DELAY_MS: u64 = 50;
+const SERVER_URL: &str = "http://127.0.0.1:8765";
 
-/// Delay before restoring previous clipboard content
-const CLIPBOARD_RESTORE_DELAY_MS: u64 = 100;
+/// An output waiting for, or holding, its turn in the output queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedOutput {
+    pub id: u64,
+    pub mode: OutputMode,
+    pub chars: usize,
+    pub queued_at: chrono::DateTime<chrono::Utc>,
+}
 
-const SERVER_URL: &str = "http://127.0.0.1:8765";
+/// Snapshot returned by `get_output_queue`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OutputQueueState {
+    /// The output currently being typed/pasted.
+    pub active: Option<QueuedOutput>,
+    /// Outputs waiting behind it, oldest first.
+    pub pending: Vec<QueuedOutput>,
+}
+
+#[derive(Default)]
+struct OutputQueueInner {
+    next_id: u64,
+    active: Option<QueuedOutput>,
+    pending: VecDeque<QueuedOutput>,
+}
 
-/// Global lock to ensure we never run multiple output injections concurrently.
+/// FIFO queue that serializes all output injections.
 ///
-/// Without this, two overlapping "type/paste" operations can interleave key events and
-/// produce dropped/mangled text in target applications.
-static OUTPUT_INJECTION_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+/// Without this, two overlapping "type/paste" operations (e.g. a retranscription finishing
+/// during live dictation) can interleave key events and clipboard writes and produce
+/// dropped/mangled text in target applications. Each output runs on its caller's thread
+/// (`type_text` needs the main thread on macOS) once every earlier one has finished.
+#[derive(Default)]
+struct OutputQueue {
+    inner: Mutex<OutputQueueInner>,
+    turn: Condvar,
+}
+
+/// Releases the active slot when the output finishes (or panics).
+struct OutputTurn<'a>(&'a OutputQueue);
 
-fn output_injection_lock() -> &'static Mutex<()> {
-    OUTPUT_INJECTION_LOCK.get_or_init(|| Mutex::new(()))
+impl Drop for OutputTurn<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut inner) = self.0.inner.lock() {
+            inner.active = None;
+        }
+        self.0.turn.notify_all();
+    }
 }
 
-fn maybe_hit_enter(enigo: &mut Enigo, hit_enter: bool) -> Result<(), String> {
-    if !hit_enter {
-        return Ok(());
+impl OutputQueue {
+    /// Wait for our turn, then run `output`.
+    fn run<T>(
+        &self,
+        mode: OutputMode,
+        text: &str,
+        output: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let poisoned = |_| "Output queue poisoned".to_string();
+        let mut inner = self.inner.lock().map_err(poisoned)?;
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.pending.push_back(QueuedOutput {
+            id,
+            mode,
+            chars: text.chars().count(),
+            queued_at: chrono::Utc::now(),
+        });
+
+        let mut inner = self
+            .turn
+            .wait_while(inner, |q| {
+                q.active.is_some() || q.pending.front().map(|job| job.id) != Some(id)
+            })
+            .map_err(poisoned)?;
+        inner.active = inner.pending.pop_front();
+        drop(inner);
+
+        let _turn = OutputTurn(self);
+        output()
     }
 
-    // Small delay to avoid racing the paste keystroke.
-    thread::sleep(Duration::from_millis(KEY_EVENT_DELAY_MS));
+    fn state(&self) -> OutputQueueState {
+        self.inner
+            .lock()
+            .map(|inner| OutputQueueState {
+                active: inner.active.clone(),
+                pending: inner.pending.iter().cloned().collect(),
+            })
+            .unwrap_or_default()
+    }
+}
 
-    enigo
-        .key(Key::Return, Direction::Click)
-        .map_err(|e| e.to_string())?;
+fn output_queue() -> &'static OutputQueue {
+    static QUEUE: OnceLock<OutputQueue> = OnceLock::new();
+    QUEUE.get_or_init(OutputQueue::default)
+}
 
-    Ok(())
+/// Timing of the output typing engine (the `output_typing` setting).
+///
+/// The defaults are the delays that used to be hardcoded; slow or remote targets (VMs,
+/// RDP, some Electron apps) may need longer ones or smaller paste chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TypingConfig {
+    /// Delay before the first key event, so the target window is ready for input.
+    pub initial_delay_ms: u64,
+    /// Delay between synthetic key events (press/release of the paste shortcut, Enter).
+    pub key_delay_ms: u64,
+    /// Delay after setting the clipboard, before pasting it.
+    pub clipboard_settle_ms: u64,
+    /// Delay before restoring the previous clipboard content.
+    pub clipboard_restore_delay_ms: u64,
+    /// Paste long text in chunks of this many characters (0 pastes everything at once).
+    pub paste_chunk_chars: usize,
+    /// Delay between pasted chunks.
+    pub chunk_delay_ms: u64,
+    /// Key injection backend; `None` picks one for the session (see `crate::injection`).
+    pub backend: Option<InjectionBackend>,
 }
 
-/// Output mode for transcribed text
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
-pub enum OutputMode {
-    /// Copy to clipboard and simulate Ctrl+V/Cmd+V, then restore clipboard
-    #[default]
-    Paste,
-    /// Paste and keep in clipboard (no restore)
-    PasteAndClipboard,
-    /// Just copy to clipboard (no paste)
-    Clipboard,
-    // NOTE: Keystrokes mode was removed/disabled due to reliability issues across targets.
+impl Default for TypingConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 0,
+            key_delay_ms: 50,
+            clipboard_settle_ms: 50,
+            clipboard_restore_delay_ms: 100,
+            paste_chunk_chars: 0,
+            chunk_delay_ms: 100,
+            backend: None,
+        }
+    }
+}
+
+impl TypingConfig {
+    /// Split `text` into the chunks to paste (on char boundaries).
+    fn chunks<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        if self.paste_chunk_chars == 0 {
+            return vec![text];
+        }
+        let mut chunks = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let end = rest
+                .char_indices()
+                .nth(self.paste_chunk_chars)
+                .map_or(rest.len(), |(i, _)| i);
+            let (chunk, tail) = rest.split_at(end);
+            chunks.push(chunk);
+            rest = tail;
+        }
+        chunks
+    }
 }
 
-impl OutputMode {
-    pub fn from_str(s: &str) -> Self {
-        match s {
-            "paste" => OutputMode::Paste,
-            "paste_and_clipboard" => OutputMode::PasteAndClipboard,
-            "clipboard" => OutputMode::Clipboard,
-            // Legacy/disabled values: map to paste so existing settings.json doesn't break.
-            "keystrokes" => OutputMode::Paste,
-            "keystrokes_and_clipboard" => OutputMode::Paste,
-            // Handle legacy value
-            "auto_paste" => OutputMode::Paste,
-            _ => OutputMode::Paste,
+/// Read the `output_typing` setting.
+pub fn typing_config(app: &AppHandle) -> TypingConfig {
+    #[cfg(desktop)]
+    {
+        use tauri_plugin_store::StoreExt;
+        if let Some(config) = app
+            .store("settings.json")
+            .ok()
+            .and_then(|store| store.get("output_typing"))
+            .and_then(|v| serde_json::from_value(v).ok())
+        {
+            return config;
         }
     }
+
+    #[cfg(not(desktop))]
+    let _ = app;
+
+    TypingConfig::default()
+}
+
+fn sleep_ms(ms: u64) {
+    if ms > 0 {
+        thread::sleep(Duration::from_millis(ms));
+    }
+}
+
+/// Output settings resolved for one transcript.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputOptions {
+    pub mode: OutputMode,
+    /// Press Enter after pasting.
+    pub hit_enter: bool,
+    pub typing: TypingConfig,
+    pub format: OutputFormat,
 }
 
 #[tauri::command]
@@ -76,85 +214,424 @@ pub async fn get_server_url() -> String {
     SERVER_URL.to_string()
 }
 
+/// Key injection backend picked for this session (before any `output_typing.backend` override)
 #[tauri::command]
-pub async fn type_text(app: AppHandle, text: String) -> Result<(), String> {
-    // macOS HIToolbox APIs (used by enigo) must run on the main thread
-    // Use a channel to get the result back from the main thread
-    let (tx, rx) = mpsc::channel::<Result<(), String>>();
+pub fn get_injection_backend() -> InjectionBackend {
+    injection::detected_backend()
+}
 
-    app.run_on_main_thread(move || {
+#[tauri::command]
+pub async fn type_text(app: AppHandle, text: String) -> Result<(), AppError> {
+    let typing = typing_config(&app);
+    let output = move || {
         // Serialize output across all modes to avoid interleaving key events.
-        let _guard = match output_injection_lock().lock() {
-            Ok(g) => g,
-            Err(_) => {
-                let _ = tx.send(Err("Output lock poisoned".to_string()));
-                return;
-            }
-        };
+        output_queue().run(OutputMode::Paste, &text, || {
+            let entry = OutputAuditEntry::new(OutputMode::Paste, &text, false);
+            let result = type_text_blocking(&text, false, &typing);
+            output_audit::record(entry.with_result(&result));
+            result
+        })
+    };
+
+    // macOS HIToolbox APIs (used by enigo) must run on the main thread. Elsewhere typing
+    // goes to the blocking pool, so its key delays stall neither the UI nor the runtime.
+    #[cfg(target_os = "macos")]
+    let result = {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.run_on_main_thread(move || {
+            let _ = tx.send(output());
+        })
+        .map_err(|e| e.to_string())?;
+        rx.await.map_err(|e| e.to_string())?
+    };
+    #[cfg(not(target_os = "macos"))]
+    let result = tauri::async_runtime::spawn_blocking(output)
+        .await
+        .map_err(|e| e.to_string())?;
 
-        let result = type_text_blocking(&text, false);
-        let _ = tx.send(result);
-    })
-    .map_err(|e| e.to_string())?;
+    result.map_err(AppError::from)
+}
 
-    // Wait for result from main thread
-    rx.recv().map_err(|e| e.to_string())?
+/// Outputs that are being typed or waiting for their turn
+#[tauri::command]
+pub fn get_output_queue() -> OutputQueueState {
+    output_queue().state()
 }
 
 /// Output text based on the specified mode
-pub fn output_text_with_mode(text: &str, mode: OutputMode, hit_enter: bool) -> Result<(), String> {
-    let _guard = output_injection_lock()
-        .lock()
-        .map_err(|_| "Output lock poisoned".to_string())?;
+pub fn output_text_with_mode(
+    text: &str,
+    mode: OutputMode,
+    hit_enter: bool,
+    typing: &TypingConfig,
+) -> Result<(), String> {
+    output_queue().run(mode, text, || output_now(text, None, mode, hit_enter, typing))
+}
+
+/// Replace the last `erase` characters typed into the focused field with `insert`.
+///
+/// Used by live typing to correct the tail of text typed earlier; waits for its turn in
+/// the output queue like any other output.
+pub fn replace_typed_tail(erase: usize, insert: &str, typing: &TypingConfig) -> Result<(), String> {
+    output_queue().run(OutputMode::LiveTyping, insert, || {
+        if erase > 0 {
+            let backend = typing.backend.unwrap_or_else(injection::detected_backend);
+            let mut injector = KeyInjector::new(backend)?
+                .ok_or_else(|| "No key injection backend available".to_string())?;
+            injector.backspace(erase, typing.key_delay_ms)?;
+        }
+        if insert.is_empty() {
+            return Ok(());
+        }
+        paste_restoring_clipboard(insert, None, false, typing)
+    })
+}
+
+/// Output text right away; callers must hold a turn in the output queue.
+///
+/// `html` is put on the clipboard next to `text` by the clipboard modes; accessibility
+/// insertion only writes `text`.
+fn output_now(
+    text: &str,
+    html: Option<&str>,
+    mode: OutputMode,
+    hit_enter: bool,
+    typing: &TypingConfig,
+) -> Result<(), String> {
+    let entry = OutputAuditEntry::new(mode, text, hit_enter);
+    let result = match mode {
+        OutputMode::Paste | OutputMode::LiveTyping => {
+            paste_restoring_clipboard(text, html, hit_enter, typing)
+        }
+        OutputMode::PasteAndClipboard => paste_and_keep_clipboard(text, html, hit_enter, typing),
+        OutputMode::Clipboard => copy_to_clipboard(text, html),
+        OutputMode::Accessibility => insert_via_accessibility(text, hit_enter, typing),
+        OutputMode::Webhook => {
+            log::info!("Output left to the webhook ({} chars)", text.len());
+            Ok(())
+        }
+    };
+    output_audit::record(entry.with_result(&result));
+    result
+}
 
-    match mode {
-        OutputMode::Paste => type_text_blocking(text, hit_enter),
-        OutputMode::PasteAndClipboard => paste_and_keep_clipboard(text, hit_enter),
-        OutputMode::Clipboard => copy_to_clipboard(text),
+// ============================================================================
+// Focus restoration
+// ============================================================================
+
+/// OS-specific window identity: an `HWND` on Windows, the frontmost process id on macOS
+/// and the X11 window id (via `xdotool`) on Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WindowId(i64);
+
+/// What to do when focus moved away from the window that was active when recording
+/// started (the `focus_guard` setting).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusGuard {
+    /// Output to whatever has focus at output time.
+    #[default]
+    Off,
+    /// Re-focus the original window; copy to the clipboard instead if that fails.
+    Refocus,
+    /// Don't paste into another window; copy to the clipboard instead.
+    Clipboard,
+}
+
+/// The window that had focus when recording started, and what to do if it lost focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusTarget {
+    window: WindowId,
+    guard: FocusGuard,
+}
+
+/// How `output_text_restoring_focus` delivered the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputDelivery {
+    /// Output with the configured mode.
+    AsConfigured,
+    /// Only copied to the clipboard because the target window lost focus (the reason).
+    CopiedInstead(String),
+}
+
+#[cfg(target_os = "windows")]
+mod focus_imp {
+    use super::WindowId;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, IsWindow, SetForegroundWindow,
+    };
+
+    pub fn capture() -> Option<WindowId> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.0.is_null() {
+            None
+        } else {
+            Some(WindowId(hwnd.0 as isize as i64))
+        }
+    }
+
+    pub fn restore(target: WindowId) -> Result<(), String> {
+        let hwnd = HWND(target.0 as isize as *mut core::ffi::c_void);
+        unsafe {
+            if !IsWindow(Some(hwnd)).as_bool() {
+                return Err("Original window no longer exists".to_string());
+            }
+            if GetForegroundWindow() == hwnd {
+                return Ok(());
+            }
+            if !SetForegroundWindow(hwnd).as_bool() {
+                return Err("SetForegroundWindow was refused".to_string());
+            }
+        }
+        Ok(())
     }
 }
 
-/// Copy text to clipboard and paste, keeping text in clipboard (no restore)
-pub fn paste_and_keep_clipboard(text: &str, hit_enter: bool) -> Result<(), String> {
-    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+#[cfg(target_os = "macos")]
+mod focus_imp {
+    use super::WindowId;
+    use std::process::Command;
+
+    fn osascript(script: &str) -> Result<String, String> {
+        let output = Command::new("osascript")
+            .args(["-e", script])
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
 
-    // Set new text
-    clipboard.set_text(text).map_err(|e| e.to_string())?;
+    pub fn capture() -> Option<WindowId> {
+        osascript(
+            "tell application \"System Events\" to get unix id of first process whose frontmost is true",
+        )
+        .ok()
+        .and_then(|pid| pid.parse::<i64>().ok())
+        .filter(|pid| *pid > 0 && *pid != i64::from(std::process::id()))
+        .map(WindowId)
+    }
 
-    // Small delay for clipboard to stabilize
-    thread::sleep(Duration::from_millis(CLIPBOARD_STABILIZATION_DELAY_MS));
+    pub fn restore(target: WindowId) -> Result<(), String> {
+        osascript(&format!(
+            "tell application \"System Events\" to set frontmost of (first process whose unix id is {}) to true",
+            target.0
+        ))
+        .map(|_| ())
+    }
+}
 
-    // Simulate Ctrl+V / Cmd+V
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+#[cfg(target_os = "linux")]
+mod focus_imp {
+    use super::WindowId;
+    use std::process::Command;
 
-    #[cfg(target_os = "macos")]
-    let modifier = Key::Meta;
-    #[cfg(not(target_os = "macos"))]
-    let modifier = Key::Control;
+    // X11 only: Wayland compositors don't allow clients to move focus.
+    pub fn capture() -> Option<WindowId> {
+        let output = Command::new("xdotool").arg("getactivewindow").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .map(WindowId)
+    }
 
-    enigo
-        .key(modifier, Direction::Press)
-        .map_err(|e| e.to_string())?;
-    thread::sleep(Duration::from_millis(KEY_EVENT_DELAY_MS));
-    enigo
-        .key(Key::Unicode('v'), Direction::Click)
-        .map_err(|e| e.to_string())?;
-    thread::sleep(Duration::from_millis(KEY_EVENT_DELAY_MS));
-    enigo
-        .key(modifier, Direction::Release)
-        .map_err(|e| e.to_string())?;
+    pub fn restore(target: WindowId) -> Result<(), String> {
+        let status = Command::new("xdotool")
+            .args(["windowactivate", "--sync", &target.0.to_string()])
+            .status()
+            .map_err(|e| format!("xdotool unavailable: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("xdotool windowactivate failed ({})", status))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+mod focus_imp {
+    use super::WindowId;
 
-    maybe_hit_enter(&mut enigo, hit_enter)?;
+    pub fn capture() -> Option<WindowId> {
+        None
+    }
+
+    pub fn restore(_target: WindowId) -> Result<(), String> {
+        Err("Focus restoration is not supported on this platform".to_string())
+    }
+}
+
+/// Remember the currently focused window (best-effort); `None` when `guard` is `Off`.
+pub fn capture_focus_target(guard: FocusGuard) -> Option<FocusTarget> {
+    if guard == FocusGuard::Off {
+        return None;
+    }
+    focus_imp::capture().map(|window| FocusTarget { window, guard })
+}
+
+/// Make sure `target` has focus before pasting; `Err` carries why the text should only be
+/// copied instead.
+fn guard_focus(target: FocusTarget) -> Result<(), String> {
+    if focus_imp::capture() == Some(target.window) {
+        return Ok(());
+    }
+    match target.guard {
+        FocusGuard::Off => Ok(()),
+        FocusGuard::Refocus => match focus_imp::restore(target.window) {
+            Ok(()) => {
+                thread::sleep(Duration::from_millis(FOCUS_RESTORE_DELAY_MS));
+                Ok(())
+            }
+            Err(e) => Err(format!("Could not re-focus the original window: {}", e)),
+        },
+        FocusGuard::Clipboard => Err("Focus moved to another window".to_string()),
+    }
+}
+
+/// Output text into the window that was active when recording started.
+///
+/// The text is first converted to `options.format`. When focus has moved since recording
+/// started, `focus_target.guard` decides between re-focusing that window and copying the
+/// text to the clipboard instead of pasting it into the wrong app. Modes that don't paste
+/// ignore the target.
+pub fn output_text_restoring_focus(
+    text: &str,
+    options: &OutputOptions,
+    focus_target: Option<FocusTarget>,
+) -> Result<OutputDelivery, String> {
+    let (text, html) = match options.format {
+        OutputFormat::AsIs => (text.to_string(), None),
+        OutputFormat::PlainText => (output_format::strip_markdown(text), None),
+        OutputFormat::Html => (
+            output_format::strip_markdown(text),
+            Some(output_format::markdown_to_html(text)),
+        ),
+    };
+    let mode = options.mode;
+
+    // Check focus inside our turn so an earlier output still being typed keeps its window.
+    output_queue().run(mode, &text, || {
+        if let Some(target) = focus_target.filter(|_| mode != OutputMode::Clipboard) {
+            if let Err(reason) = guard_focus(target) {
+                log::warn!("Not pasting into another window ({}); copying instead", reason);
+                let entry = OutputAuditEntry::new(OutputMode::Clipboard, &text, false);
+                let result = copy_to_clipboard(&text, html.as_deref());
+                output_audit::record(entry.with_result(&result));
+                return result.map(|()| OutputDelivery::CopiedInstead(reason));
+            }
+        }
+
+        output_now(&text, html.as_deref(), mode, options.hit_enter, &options.typing)
+            .map(|()| OutputDelivery::AsConfigured)
+    })
+}
+
+/// Put `text` on the clipboard, with `html` as the rich flavor when given.
+fn set_clipboard(clipboard: &mut Clipboard, text: &str, html: Option<&str>) -> Result<(), String> {
+    match html {
+        Some(html) => clipboard.set_html(html, Some(text)),
+        None => clipboard.set_text(text),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Paste `text` through the clipboard, chunk by chunk, then optionally press Enter.
+///
+/// Leaves the last chunk in the clipboard. HTML output is pasted in one piece, since
+/// splitting it could break the markup. Returns `false` when no key injection backend is
+/// available; the whole text is then left in the clipboard for a manual paste.
+fn paste_via_clipboard(
+    clipboard: &mut Clipboard,
+    text: &str,
+    html: Option<&str>,
+    hit_enter: bool,
+    typing: &TypingConfig,
+) -> Result<bool, String> {
+    let backend = typing.backend.unwrap_or_else(injection::detected_backend);
+    let Some(mut injector) = KeyInjector::new(backend)? else {
+        set_clipboard(clipboard, text, html)?;
+        log::warn!("No key injection backend available; copied {} chars for manual paste", text.len());
+        return Ok(false);
+    };
+    sleep_ms(typing.initial_delay_ms);
+
+    let chunks = if html.is_some() {
+        vec![text]
+    } else {
+        typing.chunks(text)
+    };
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        if i > 0 {
+            sleep_ms(typing.chunk_delay_ms);
+        }
+        set_clipboard(clipboard, chunk, html)?;
+
+        // Small delay for clipboard to stabilize
+        sleep_ms(typing.clipboard_settle_ms);
+
+        injector.paste(typing.key_delay_ms)?;
+    }
+
+    if hit_enter {
+        // Small delay to avoid racing the paste keystroke.
+        sleep_ms(typing.key_delay_ms);
+        injector.enter()?;
+    }
+    Ok(true)
+}
+
+/// Copy text to clipboard and paste, keeping text in clipboard (no restore)
+fn paste_and_keep_clipboard(
+    text: &str,
+    html: Option<&str>,
+    hit_enter: bool,
+    typing: &TypingConfig,
+) -> Result<(), String> {
+    let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
+
+    let pasted = paste_via_clipboard(&mut clipboard, text, html, hit_enter, typing)?;
+
+    // Chunked pastes leave only the last chunk behind; keep the whole text there.
+    if pasted && html.is_none() && typing.paste_chunk_chars > 0 {
+        clipboard.set_text(text).map_err(|e| e.to_string())?;
+    }
 
     // Don't restore clipboard - keep the text there
     log::info!("Pasted {} chars (kept in clipboard)", text.len());
     Ok(())
 }
 
+/// Insert text through the accessibility API, then optionally press Enter.
+///
+/// Falls back to a clipboard paste when the focused field can't be written that way.
+pub fn insert_via_accessibility(text: &str, hit_enter: bool, typing: &TypingConfig) -> Result<(), String> {
+    sleep_ms(typing.initial_delay_ms);
+    if let Err(e) = accessibility::insert_text(text) {
+        log::info!("Accessibility insertion unavailable ({}); pasting instead", e);
+        return type_text_blocking(text, hit_enter, typing);
+    }
+
+    if hit_enter {
+        let backend = typing.backend.unwrap_or_else(injection::detected_backend);
+        if let Some(mut injector) = KeyInjector::new(backend)? {
+            sleep_ms(typing.key_delay_ms);
+            injector.enter()?;
+        }
+    }
+    log::info!("Inserted {} chars via accessibility API", text.len());
+    Ok(())
+}
+
 /// Copy text to clipboard only (no paste)
-pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+fn copy_to_clipboard(text: &str, html: Option<&str>) -> Result<(), String> {
     let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
-    clipboard.set_text(text).map_err(|e| e.to_string())?;
+    set_clipboard(&mut clipboard, text, html)?;
     log::info!("Copied {} chars to clipboard", text.len());
     Ok(())
 }
@@ -166,44 +643,189 @@ pub fn type_as_keystrokes(_text: &str) -> Result<(), String> {
     Err("Keystrokes output mode is disabled".to_string())
 }
 
+// ============================================================================
+// Clipboard snapshot
+// ============================================================================
+
+/// Clipboard content saved before pasting and put back afterwards.
+///
+/// Files, images and HTML are kept as such (HTML with its plain-text alternative), so
+/// dictating doesn't destroy a copied screenshot or file selection. Formats arboard can't
+/// read can't be told apart from an empty clipboard; in that case the pasted text is left
+/// in place rather than clearing whatever was there.
+enum ClipboardSnapshot {
+    Empty,
+    Text(String),
+    Html { html: String, alt_text: Option<String> },
+    Image(arboard::ImageData<'static>),
+    Files(Vec<std::path::PathBuf>),
+    /// Content exists but couldn't be read; restoring would lose it.
+    Unrestorable(String),
+}
+
+impl ClipboardSnapshot {
+    fn capture(clipboard: &mut Clipboard) -> Self {
+        let mut failure: Option<String> = None;
+        let mut note = |e: arboard::Error| {
+            if !matches!(
+                e,
+                arboard::Error::ContentNotAvailable | arboard::Error::ClipboardNotSupported
+            ) && failure.is_none()
+            {
+                failure = Some(e.to_string());
+            }
+        };
+
+        match clipboard.get().file_list() {
+            Ok(files) if !files.is_empty() => return Self::Files(files),
+            Ok(_) => {}
+            Err(e) => note(e),
+        }
+        match clipboard.get_image() {
+            Ok(image) => return Self::Image(image),
+            Err(e) => note(e),
+        }
+        let text = match clipboard.get_text() {
+            Ok(text) => Some(text),
+            Err(e) => {
+                note(e);
+                None
+            }
+        };
+        match clipboard.get().html() {
+            Ok(html) if !html.is_empty() => {
+                return Self::Html {
+                    html,
+                    alt_text: text,
+                }
+            }
+            Ok(_) => {}
+            Err(e) => note(e),
+        }
+
+        match (text, failure) {
+            (Some(text), _) => Self::Text(text),
+            (None, Some(reason)) => Self::Unrestorable(reason),
+            (None, None) => Self::Empty,
+        }
+    }
+
+    fn restore(self, clipboard: &mut Clipboard) -> Result<(), String> {
+        let result = match self {
+            Self::Text(text) => clipboard.set_text(text),
+            Self::Html { html, alt_text } => clipboard.set_html(html, alt_text),
+            Self::Image(image) => clipboard.set_image(image),
+            Self::Files(files) => clipboard.set().file_list(&files),
+            Self::Empty => return Ok(()),
+            Self::Unrestorable(reason) => return Err(reason),
+        };
+        result.map_err(|e| e.to_string())
+    }
+}
+
+type ClipboardWarningHandler = Box<dyn Fn(&str) + Send + Sync>;
+
+static CLIPBOARD_WARNING_HANDLER: OnceLock<ClipboardWarningHandler> = OnceLock::new();
+
+/// Register the callback told when the previous clipboard content couldn't be restored
+/// after a paste (the app forwards it to the frontend as `clipboard-restore-skipped`).
+pub fn on_clipboard_restore_skipped(handler: impl Fn(&str) + Send + Sync + 'static) {
+    let _ = CLIPBOARD_WARNING_HANDLER.set(Box::new(handler));
+}
+
 /// Type text using clipboard and paste. Used internally by shortcut handlers.
-pub fn type_text_blocking(text: &str, hit_enter: bool) -> Result<(), String> {
+pub fn type_text_blocking(text: &str, hit_enter: bool, typing: &TypingConfig) -> Result<(), String> {
+    paste_restoring_clipboard(text, None, hit_enter, typing)
+}
+
+fn paste_restoring_clipboard(
+    text: &str,
+    html: Option<&str>,
+    hit_enter: bool,
+    typing: &TypingConfig,
+) -> Result<(), String> {
     let mut clipboard = Clipboard::new().map_err(|e| e.to_string())?;
 
     // Save previous clipboard content
-    let previous = clipboard.get_text().unwrap_or_default();
+    let previous = ClipboardSnapshot::capture(&mut clipboard);
 
-    // Set new text
-    clipboard.set_text(text).map_err(|e| e.to_string())?;
+    if !paste_via_clipboard(&mut clipboard, text, html, hit_enter, typing)? {
+        // Clipboard-only fallback: the text must stay there to be pasted by hand.
+        return Ok(());
+    }
 
-    // Small delay for clipboard to stabilize
-    thread::sleep(Duration::from_millis(CLIPBOARD_STABILIZATION_DELAY_MS));
+    // Restore previous clipboard after a delay
+    sleep_ms(typing.clipboard_restore_delay_ms);
+    if let Err(reason) = previous.restore(&mut clipboard) {
+        log::warn!("Previous clipboard content not restored: {}", reason);
+        if let Some(handler) = CLIPBOARD_WARNING_HANDLER.get() {
+            handler(&reason);
+        }
+    }
 
-    // Simulate Ctrl+V / Cmd+V
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    #[cfg(target_os = "macos")]
-    let modifier = Key::Meta;
-    #[cfg(not(target_os = "macos"))]
-    let modifier = Key::Control;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
 
-    enigo
-        .key(modifier, Direction::Press)
-        .map_err(|e| e.to_string())?;
-    thread::sleep(Duration::from_millis(KEY_EVENT_DELAY_MS));
-    enigo
-        .key(Key::Unicode('v'), Direction::Click)
-        .map_err(|e| e.to_string())?;
-    thread::sleep(Duration::from_millis(KEY_EVENT_DELAY_MS));
-    enigo
-        .key(modifier, Direction::Release)
-        .map_err(|e| e.to_string())?;
+    #[test]
+    fn test_paste_chunks() {
+        let whole = TypingConfig::default();
+        assert_eq!(whole.chunks("hello world"), vec!["hello world"]);
 
-    maybe_hit_enter(&mut enigo, hit_enter)?;
+        let chunked = TypingConfig {
+            paste_chunk_chars: 4,
+            ..TypingConfig::default()
+        };
+        assert_eq!(chunked.chunks("hello world"), vec!["hell", "o wo", "rld"]);
+        assert_eq!(chunked.chunks("héllo"), vec!["héll", "o"]);
+        assert!(chunked.chunks("").is_empty());
+    }
 
-    // Restore previous clipboard after a delay
-    thread::sleep(Duration::from_millis(CLIPBOARD_RESTORE_DELAY_MS));
-    let _ = clipboard.set_text(&previous);
+    #[test]
+    fn test_output_queue_runs_in_order() {
+        let queue = std::sync::Arc::new(OutputQueue::default());
+        let order = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let (release, blocked) = mpsc::channel::<()>();
+
+        let spawn = |text: &'static str, blocked: Option<mpsc::Receiver<()>>| {
+            let (queue, order) = (queue.clone(), order.clone());
+            thread::spawn(move || {
+                queue.run(OutputMode::Paste, text, || {
+                    if let Some(blocked) = blocked {
+                        blocked.recv().unwrap();
+                    }
+                    order.lock().unwrap().push(text);
+                    Ok(())
+                })
+            })
+        };
+        let wait_until = |done: &dyn Fn(&OutputQueueState) -> bool| {
+            while !done(&queue.state()) {
+                thread::sleep(Duration::from_millis(1));
+            }
+        };
 
-    Ok(())
+        let mut handles = vec![spawn("first", Some(blocked))];
+        wait_until(&|state| state.active.is_some());
+        handles.push(spawn("second", None));
+        wait_until(&|state| state.pending.len() == 1);
+        handles.push(spawn("third", None));
+        wait_until(&|state| state.pending.len() == 2);
+
+        let state = queue.state();
+        assert_eq!(state.active.unwrap().chars, 5);
+        let pending: Vec<usize> = state.pending.iter().map(|job| job.chars).collect();
+        assert_eq!(pending, vec![6, 5]);
+
+        release.send(()).unwrap();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["first", "second", "third"]);
+        assert!(queue.state().active.is_none());
+    }
 }
@@ -0,0 +1,221 @@
+//! Tauri commands for local Vosk model management.
+//!
+//! Models are zip archives of a model directory, unpacked into `<app data>/vosk-models`.
+//! These commands are only available when the `local-vosk` feature is enabled.
+
+use crate::error::AppError;
+#[cfg(feature = "local-vosk")]
+use crate::stt::VoskModel;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// Information about a Vosk model
+#[derive(Debug, serde::Serialize)]
+pub struct VoskModelInfo {
+    pub id: String,
+    pub name: String,
+    pub size_bytes: u64,
+    pub size_display: String,
+    pub download_url: String,
+    pub is_downloaded: bool,
+}
+
+/// Check if local Vosk feature is enabled
+#[tauri::command]
+pub fn is_local_vosk_available() -> bool {
+    cfg!(feature = "local-vosk")
+}
+
+/// Get list of available Vosk models with download status
+#[tauri::command]
+pub fn get_vosk_models(app: tauri::AppHandle) -> Result<Vec<VoskModelInfo>, AppError> {
+    #[cfg(feature = "local-vosk")]
+    {
+        let models_dir = get_models_dir(&app)?;
+        Ok(VoskModel::all()
+            .into_iter()
+            .map(|model| VoskModelInfo {
+                id: model_id(model),
+                name: model.display_name().to_string(),
+                size_bytes: model.size_bytes(),
+                size_display: super::whisper::format_size(model.size_bytes()),
+                download_url: model.download_url(),
+                is_downloaded: models_dir.join(model.dir_name()).is_dir(),
+            })
+            .collect())
+    }
+
+    #[cfg(not(feature = "local-vosk"))]
+    {
+        let _ = app;
+        Err(AppError::from(
+            "Local Vosk feature is not enabled".to_string(),
+        ))
+    }
+}
+
+/// Download and unpack a model. Resolves once it is ready to use.
+#[tauri::command]
+pub async fn download_vosk_model(app: tauri::AppHandle, model_id: String) -> Result<(), AppError> {
+    #[cfg(feature = "local-vosk")]
+    {
+        let model = parse_model_id(&model_id)?;
+        let models_dir = get_models_dir(&app)?;
+        log::info!("Downloading Vosk model {}", model.download_url());
+
+        let response = crate::http::shared_client()
+            .get(model.download_url())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::from(format!("Failed to download model: {}", e)))?;
+        let archive = response
+            .bytes()
+            .await
+            .map_err(|e| AppError::from(format!("Failed to download model: {}", e)))?;
+
+        let dir_name = model.dir_name();
+        tokio::task::spawn_blocking(move || unpack_model(&archive, &models_dir, dir_name))
+            .await
+            .map_err(|e| AppError::from(format!("Failed to unpack model: {}", e)))?
+            .map_err(AppError::from)
+    }
+
+    #[cfg(not(feature = "local-vosk"))]
+    {
+        let _ = (app, model_id);
+        Err(AppError::from(
+            "Local Vosk feature is not enabled".to_string(),
+        ))
+    }
+}
+
+/// Delete a downloaded model
+#[tauri::command]
+pub fn delete_vosk_model(app: tauri::AppHandle, model_id: String) -> Result<(), AppError> {
+    #[cfg(feature = "local-vosk")]
+    {
+        let model = parse_model_id(&model_id)?;
+        let model_dir = get_models_dir(&app)?.join(model.dir_name());
+        if model_dir.exists() {
+            std::fs::remove_dir_all(&model_dir)
+                .map_err(|e| AppError::from(format!("Failed to delete model: {}", e)))?;
+            log::info!("Deleted Vosk model: {}", model_dir.display());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "local-vosk"))]
+    {
+        let _ = (app, model_id);
+        Err(AppError::from(
+            "Local Vosk feature is not enabled".to_string(),
+        ))
+    }
+}
+
+// Helper functions
+
+#[cfg_attr(not(feature = "local-vosk"), allow(dead_code))]
+pub(crate) fn get_models_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::from(format!("Failed to get app data dir: {}", e)))?;
+
+    let models_dir = app_data_dir.join("vosk-models");
+    if !models_dir.exists() {
+        std::fs::create_dir_all(&models_dir)
+            .map_err(|e| AppError::from(format!("Failed to create models directory: {}", e)))?;
+    }
+    Ok(models_dir)
+}
+
+/// Directory of the model selected in `vosk_model`, if it is downloaded.
+#[cfg(feature = "local-vosk")]
+pub(crate) fn selected_model_path(
+    app: &tauri::AppHandle,
+    model_id: Option<&str>,
+) -> Option<PathBuf> {
+    let model = model_id
+        .map_or(Ok(VoskModel::default()), parse_model_id)
+        .ok()?;
+    let path = get_models_dir(app).ok()?.join(model.dir_name());
+    path.is_dir().then_some(path)
+}
+
+#[cfg(feature = "local-vosk")]
+fn model_id(model: VoskModel) -> String {
+    serde_json::to_value(model)
+        .ok()
+        .and_then(|id| id.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "local-vosk")]
+fn parse_model_id(model_id: &str) -> Result<VoskModel, AppError> {
+    serde_json::from_value(serde_json::Value::String(model_id.to_string()))
+        .map_err(|_| AppError::from(format!("Unknown model: {}", model_id)))
+}
+
+/// Unpack the `dir_name/` directory of a model archive into `models_dir/dir_name`.
+///
+/// Files are extracted to a temporary directory first, so an interrupted unpack never
+/// leaves a half-written model where `selected_model_path` would find it.
+#[cfg_attr(not(any(test, feature = "local-vosk")), allow(dead_code))]
+fn unpack_model(archive: &[u8], models_dir: &Path, dir_name: &str) -> Result<(), String> {
+    let staging = models_dir.join(format!("{}.partial", dir_name));
+    let _ = std::fs::remove_dir_all(&staging);
+    let result = zip::ZipArchive::new(std::io::Cursor::new(archive))
+        .and_then(|mut zip| zip.extract(&staging))
+        .map_err(|e| format!("Failed to unpack model: {}", e));
+    let unpacked = staging.join(dir_name);
+    let result = result.and_then(|()| {
+        if unpacked.is_dir() {
+            Ok(())
+        } else {
+            Err(format!("Model archive has no {} directory", dir_name))
+        }
+    });
+    let target = models_dir.join(dir_name);
+    let result = result.and_then(|()| {
+        let _ = std::fs::remove_dir_all(&target);
+        std::fs::rename(&unpacked, &target).map_err(|e| format!("Failed to install model: {}", e))
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn archive(files: &[&str]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for name in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(b"data").unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_unpack_model_installs_the_model_directory() {
+        let models_dir =
+            std::env::temp_dir().join(format!("tangerine-vosk-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&models_dir).unwrap();
+
+        let good = archive(&["vosk-model-x/am/final.mdl", "vosk-model-x/conf/model.conf"]);
+        unpack_model(&good, &models_dir, "vosk-model-x").unwrap();
+        assert!(models_dir.join("vosk-model-x/am/final.mdl").is_file());
+        assert!(!models_dir.join("vosk-model-x.partial").exists());
+
+        let wrong = archive(&["other/am/final.mdl"]);
+        assert!(unpack_model(&wrong, &models_dir, "vosk-model-y").is_err());
+        assert!(!models_dir.join("vosk-model-y").exists());
+
+        let _ = std::fs::remove_dir_all(models_dir);
+    }
+}
@@ -4,21 +4,14 @@
 
 #[cfg(feature = "local-whisper")]
 use crate::stt::{LocalWhisperConfig, LocalWhisperProvider, WhisperModel};
+#[cfg(feature = "local-whisper")]
+use crate::model_prefetch::PrefetchJob;
+use crate::error::AppError;
+use crate::model_prefetch::{ModelPrefetcher, PrefetchProgress};
 use std::path::PathBuf;
+use std::sync::Arc;
 use tauri::Manager;
 
-/// Error type for Whisper commands
-#[derive(Debug, serde::Serialize)]
-pub struct WhisperCommandError {
-    pub message: String,
-}
-
-impl From<String> for WhisperCommandError {
-    fn from(message: String) -> Self {
-        Self { message }
-    }
-}
-
 /// Information about a Whisper model
 #[derive(Debug, serde::Serialize)]
 pub struct WhisperModelInfo {
@@ -40,7 +33,7 @@ pub fn is_local_whisper_available() -> bool {
 
 /// Get list of available Whisper models with download status
 #[tauri::command]
-pub fn get_whisper_models(app: tauri::AppHandle) -> Result<Vec<WhisperModelInfo>, WhisperCommandError> {
+pub fn get_whisper_models(app: tauri::AppHandle) -> Result<Vec<WhisperModelInfo>, AppError> {
     #[cfg(feature = "local-whisper")]
     {
         let models_dir = get_models_dir(&app)?;
@@ -70,7 +63,7 @@ pub fn get_whisper_models(app: tauri::AppHandle) -> Result<Vec<WhisperModelInfo>
     #[cfg(not(feature = "local-whisper"))]
     {
         let _ = app;
-        Err(WhisperCommandError::from(
+        Err(AppError::from(
             "Local Whisper feature is not enabled".to_string(),
         ))
     }
@@ -78,7 +71,7 @@ pub fn get_whisper_models(app: tauri::AppHandle) -> Result<Vec<WhisperModelInfo>
 
 /// Get the path to the models directory
 #[tauri::command]
-pub fn get_whisper_models_dir(app: tauri::AppHandle) -> Result<String, WhisperCommandError> {
+pub fn get_whisper_models_dir(app: tauri::AppHandle) -> Result<String, AppError> {
     let models_dir = get_models_dir(&app)?;
     Ok(models_dir.to_string_lossy().to_string())
 }
@@ -88,7 +81,7 @@ pub fn get_whisper_models_dir(app: tauri::AppHandle) -> Result<String, WhisperCo
 pub fn is_whisper_model_downloaded(
     app: tauri::AppHandle,
     model_id: String,
-) -> Result<bool, WhisperCommandError> {
+) -> Result<bool, AppError> {
     #[cfg(feature = "local-whisper")]
     {
         let model = parse_model_id(&model_id)?;
@@ -100,7 +93,7 @@ pub fn is_whisper_model_downloaded(
     #[cfg(not(feature = "local-whisper"))]
     {
         let _ = (app, model_id);
-        Err(WhisperCommandError::from(
+        Err(AppError::from(
             "Local Whisper feature is not enabled".to_string(),
         ))
     }
@@ -108,7 +101,7 @@ pub fn is_whisper_model_downloaded(
 
 /// Get the download URL for a model
 #[tauri::command]
-pub fn get_whisper_model_url(model_id: String) -> Result<String, WhisperCommandError> {
+pub fn get_whisper_model_url(model_id: String) -> Result<String, AppError> {
     #[cfg(feature = "local-whisper")]
     {
         let model = parse_model_id(&model_id)?;
@@ -118,7 +111,7 @@ pub fn get_whisper_model_url(model_id: String) -> Result<String, WhisperCommandE
     #[cfg(not(feature = "local-whisper"))]
     {
         let _ = model_id;
-        Err(WhisperCommandError::from(
+        Err(AppError::from(
             "Local Whisper feature is not enabled".to_string(),
         ))
     }
@@ -129,7 +122,7 @@ pub fn get_whisper_model_url(model_id: String) -> Result<String, WhisperCommandE
 pub fn delete_whisper_model(
     app: tauri::AppHandle,
     model_id: String,
-) -> Result<(), WhisperCommandError> {
+) -> Result<(), AppError> {
     #[cfg(feature = "local-whisper")]
     {
         let model = parse_model_id(&model_id)?;
@@ -138,7 +131,7 @@ pub fn delete_whisper_model(
 
         if model_path.exists() {
             std::fs::remove_file(&model_path).map_err(|e| {
-                WhisperCommandError::from(format!("Failed to delete model: {}", e))
+                AppError::from(format!("Failed to delete model: {}", e))
             })?;
             log::info!("Deleted Whisper model: {}", model_path.display());
         }
@@ -149,7 +142,7 @@ pub fn delete_whisper_model(
     #[cfg(not(feature = "local-whisper"))]
     {
         let _ = (app, model_id);
-        Err(WhisperCommandError::from(
+        Err(AppError::from(
             "Local Whisper feature is not enabled".to_string(),
         ))
     }
@@ -160,7 +153,7 @@ pub fn delete_whisper_model(
 pub fn validate_whisper_model(
     app: tauri::AppHandle,
     model_id: String,
-) -> Result<bool, WhisperCommandError> {
+) -> Result<bool, AppError> {
     #[cfg(feature = "local-whisper")]
     {
         let model = parse_model_id(&model_id)?;
@@ -173,7 +166,7 @@ pub fn validate_whisper_model(
 
         // Check file size is reasonable (at least 50% of expected)
         let metadata = std::fs::metadata(&model_path).map_err(|e| {
-            WhisperCommandError::from(format!("Failed to read model metadata: {}", e))
+            AppError::from(format!("Failed to read model metadata: {}", e))
         })?;
 
         let expected_size = model.size_bytes();
@@ -196,26 +189,87 @@ pub fn validate_whisper_model(
     #[cfg(not(feature = "local-whisper"))]
     {
         let _ = (app, model_id);
-        Err(WhisperCommandError::from(
+        Err(AppError::from(
             "Local Whisper feature is not enabled".to_string(),
         ))
     }
 }
 
+/// Queue a model for background download (see `model_prefetch`).
+#[tauri::command]
+pub fn prefetch_whisper_model(
+    prefetcher: tauri::State<'_, Arc<ModelPrefetcher>>,
+    model_id: String,
+) -> Result<(), AppError> {
+    #[cfg(feature = "local-whisper")]
+    {
+        let model = parse_model_id(&model_id)?;
+        prefetcher
+            .enqueue(PrefetchJob {
+                model_id: format!("{:?}", model).to_lowercase(),
+                url: model.download_url(),
+                filename: model.filename().to_string(),
+                total_bytes: model.size_bytes(),
+            })
+            .map_err(AppError::from)
+    }
+
+    #[cfg(not(feature = "local-whisper"))]
+    {
+        let _ = (prefetcher, model_id);
+        Err(AppError::from(
+            "Local Whisper feature is not enabled".to_string(),
+        ))
+    }
+}
+
+/// Pause background model downloads (partial files are kept).
+#[tauri::command]
+pub fn pause_model_prefetch(
+    prefetcher: tauri::State<'_, Arc<ModelPrefetcher>>,
+) -> Result<(), AppError> {
+    prefetcher.pause().map_err(AppError::from)
+}
+
+/// Resume background model downloads.
+#[tauri::command]
+pub fn resume_model_prefetch(
+    prefetcher: tauri::State<'_, Arc<ModelPrefetcher>>,
+) -> Result<(), AppError> {
+    prefetcher.resume().map_err(AppError::from)
+}
+
+/// Remove a model from the download queue and delete its partial file.
+#[tauri::command]
+pub fn cancel_model_prefetch(
+    prefetcher: tauri::State<'_, Arc<ModelPrefetcher>>,
+    model_id: String,
+) -> Result<bool, AppError> {
+    prefetcher.cancel(&model_id).map_err(AppError::from)
+}
+
+/// Get the state of every queued model download.
+#[tauri::command]
+pub fn get_model_prefetch_status(
+    prefetcher: tauri::State<'_, Arc<ModelPrefetcher>>,
+) -> Vec<PrefetchProgress> {
+    prefetcher.status()
+}
+
 // Helper functions
 
-fn get_models_dir(app: &tauri::AppHandle) -> Result<PathBuf, WhisperCommandError> {
+pub(crate) fn get_models_dir(app: &tauri::AppHandle) -> Result<PathBuf, AppError> {
     let app_data_dir = app
         .path()
         .app_data_dir()
-        .map_err(|e| WhisperCommandError::from(format!("Failed to get app data dir: {}", e)))?;
+        .map_err(|e| AppError::from(format!("Failed to get app data dir: {}", e)))?;
 
     let models_dir = app_data_dir.join("whisper-models");
 
     // Create directory if it doesn't exist
     if !models_dir.exists() {
         std::fs::create_dir_all(&models_dir).map_err(|e| {
-            WhisperCommandError::from(format!("Failed to create models directory: {}", e))
+            AppError::from(format!("Failed to create models directory: {}", e))
         })?;
     }
 
@@ -223,7 +277,7 @@ fn get_models_dir(app: &tauri::AppHandle) -> Result<PathBuf, WhisperCommandError
 }
 
 #[cfg(feature = "local-whisper")]
-fn parse_model_id(model_id: &str) -> Result<WhisperModel, WhisperCommandError> {
+fn parse_model_id(model_id: &str) -> Result<WhisperModel, AppError> {
     let model = match model_id.to_lowercase().as_str() {
         "tiny" => WhisperModel::Tiny,
         "tinyen" | "tiny_en" | "tiny-en" => WhisperModel::TinyEn,
@@ -238,7 +292,7 @@ fn parse_model_id(model_id: &str) -> Result<WhisperModel, WhisperCommandError> {
         "largev3" | "large_v3" | "large-v3" => WhisperModel::LargeV3,
         "largev3turbo" | "large_v3_turbo" | "large-v3-turbo" => WhisperModel::LargeV3Turbo,
         _ => {
-            return Err(WhisperCommandError::from(format!(
+            return Err(AppError::from(format!(
                 "Unknown model: {}",
                 model_id
             )));
@@ -247,8 +301,8 @@ fn parse_model_id(model_id: &str) -> Result<WhisperModel, WhisperCommandError> {
     Ok(model)
 }
 
-#[cfg_attr(not(test), allow(dead_code))]
-fn format_size(bytes: u64) -> String {
+#[cfg_attr(not(any(test, feature = "local-vosk")), allow(dead_code))]
+pub(crate) fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
     const GB: u64 = MB * 1024;
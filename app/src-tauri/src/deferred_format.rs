@@ -0,0 +1,247 @@
+//! Background retries of LLM formatting that failed during transcription.
+//!
+//! When the LLM step fails or times out, the raw transcript is output and saved to history
+//! right away (flagged `formatting_pending`). The formatting is queued here and retried one
+//! job at a time with the background retry policy. When the formatted text lands, the
+//! history entry is updated and `llm-formatting-completed` is emitted; when every attempt
+//! fails, the raw text stays and `llm-formatting-failed` is emitted instead.
+//!
+//! The queue is not persisted: jobs still pending at exit are dropped, and `HistoryStorage`
+//! clears their flag when it next loads.
+
+use crate::history::HistoryStorage;
+use crate::llm::{format_text, LlmError};
+use crate::pipeline::{DeferredFormatting, TranscriptionResult};
+use crate::retry::RetryableError;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+
+struct DeferredFormatJob {
+    request_id: String,
+    formatting: DeferredFormatting,
+}
+
+/// Payload of the `llm-formatting-completed` / `llm-formatting-failed` events.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeferredFormatUpdate {
+    pub request_id: String,
+    /// Formatted text (completed only).
+    pub text: Option<String>,
+    /// Last error (failed only).
+    pub error: Option<String>,
+}
+
+/// Queue of pending formatting jobs, drained by a single background worker.
+pub struct DeferredFormatQueue {
+    tx: mpsc::UnboundedSender<DeferredFormatJob>,
+}
+
+impl DeferredFormatQueue {
+    /// Start the worker and return the queue feeding it.
+    pub fn spawn(app: AppHandle) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<DeferredFormatJob>();
+
+        tauri::async_runtime::spawn(async move {
+            while let Some(job) = rx.recv().await {
+                let outcome = run_with_retry(&job.formatting).await;
+                finish_job(&app, &job.request_id, outcome);
+            }
+        });
+
+        Self { tx }
+    }
+
+    fn enqueue(&self, job: DeferredFormatJob) -> bool {
+        self.tx.send(job).is_ok()
+    }
+}
+
+/// Queue the deferred formatting of `result` (if it has one) for history entry `request_id`.
+pub fn enqueue_if_needed(app: &AppHandle, request_id: Option<&str>, result: &TranscriptionResult) {
+    let (Some(request_id), Some(formatting)) = (request_id, result.deferred_formatting.clone())
+    else {
+        return;
+    };
+    let Some(queue) = app.try_state::<DeferredFormatQueue>() else {
+        return;
+    };
+
+    let queued = queue.enqueue(DeferredFormatJob {
+        request_id: request_id.to_string(),
+        formatting,
+    });
+    if !queued {
+        return;
+    }
+
+    log::info!("Queued deferred LLM formatting for request {}", request_id);
+    if let Some(history) = app.try_state::<HistoryStorage>() {
+        if let Err(e) = history.mark_formatting_pending(request_id) {
+            log::warn!("Failed to flag pending formatting in history: {}", e);
+        }
+        let _ = app.emit("history-changed", ());
+    }
+}
+
+fn finish_job(app: &AppHandle, request_id: &str, outcome: Result<String, LlmError>) {
+    let (formatted, update) = match outcome {
+        Ok(text) => {
+            log::info!("Deferred LLM formatting completed for request {}", request_id);
+            (
+                Some(text.clone()),
+                DeferredFormatUpdate {
+                    request_id: request_id.to_string(),
+                    text: Some(text),
+                    error: None,
+                },
+            )
+        }
+        Err(e) => {
+            log::warn!("Deferred LLM formatting gave up for request {}: {}", request_id, e);
+            (
+                None,
+                DeferredFormatUpdate {
+                    request_id: request_id.to_string(),
+                    text: None,
+                    error: Some(e.to_string()),
+                },
+            )
+        }
+    };
+
+    let event = if formatted.is_some() {
+        "llm-formatting-completed"
+    } else {
+        "llm-formatting-failed"
+    };
+
+    if let Some(history) = app.try_state::<HistoryStorage>() {
+        if let Err(e) = history.complete_formatting(request_id, formatted) {
+            log::warn!("Failed to update history with deferred formatting: {}", e);
+        }
+        let _ = app.emit("history-changed", ());
+    }
+    let _ = app.emit(event, update);
+}
+
+/// Format `formatting.raw_text`, backing off between attempts per its retry config.
+async fn run_with_retry(formatting: &DeferredFormatting) -> Result<String, LlmError> {
+    let config = &formatting.retry_config;
+    let input = formatting
+        .redaction
+        .as_ref()
+        .map_or(formatting.raw_text.as_str(), |r| r.text.as_str());
+    let mut last_error = LlmError::Api("No formatting attempts made".to_string());
+
+    for attempt in 0..=config.max_retries {
+        // The pipeline attempt just failed, so wait before the first try too (as long as
+        // the provider asked, if it did).
+        let Some(delay) = config.next_delay(attempt, last_error.retry_after()) else {
+            return Err(last_error);
+        };
+        tokio::time::sleep(delay).await;
+
+        let result = tokio::time::timeout(
+            formatting.timeout,
+            format_text(formatting.provider.as_ref(), input, &formatting.prompts),
+        )
+        .await
+        .unwrap_or(Err(LlmError::Timeout(formatting.timeout)));
+
+        match result {
+            Ok(text) if !text.is_empty() => {
+                return Ok(match &formatting.redaction {
+                    Some(redaction) => redaction.restore(&text),
+                    None => text,
+                });
+            }
+            Ok(_) => last_error = LlmError::InvalidResponse("Empty response".to_string()),
+            Err(e) if !e.is_retryable(config) => return Err(e),
+            Err(e) => last_error = e,
+        }
+
+        log::warn!(
+            "Deferred LLM formatting attempt {}/{} failed: {}",
+            attempt + 1,
+            config.max_retries + 1,
+            last_error
+        );
+    }
+
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmProvider, PromptSections};
+    use crate::retry::RetryConfig;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct FlakyLlm {
+        failures_left: AtomicU32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl LlmProvider for FlakyLlm {
+        async fn complete(&self, _system: &str, user: &str) -> Result<String, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failures_left.load(Ordering::SeqCst) > 0 {
+                self.failures_left.fetch_sub(1, Ordering::SeqCst);
+                return Err(LlmError::Api("503 Service Unavailable".to_string()));
+            }
+            Ok(format!("{}.", user))
+        }
+
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn model(&self) -> &str {
+            "test"
+        }
+    }
+
+    fn formatting(provider: Arc<FlakyLlm>, max_retries: u32) -> DeferredFormatting {
+        DeferredFormatting {
+            provider,
+            prompts: PromptSections::default(),
+            timeout: Duration::from_secs(5),
+            retry_config: RetryConfig {
+                max_retries,
+                initial_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+                retry_on_rate_limit: true,
+            },
+            raw_text: "hello world".to_string(),
+            redaction: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_formatting_lands() {
+        let llm = Arc::new(FlakyLlm {
+            failures_left: AtomicU32::new(2),
+            calls: AtomicU32::new(0),
+        });
+        let result = run_with_retry(&formatting(llm.clone(), 3)).await;
+        assert_eq!(result.unwrap(), "hello world.");
+        assert_eq!(llm.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let llm = Arc::new(FlakyLlm {
+            failures_left: AtomicU32::new(10),
+            calls: AtomicU32::new(0),
+        });
+        let result = run_with_retry(&formatting(llm.clone(), 1)).await;
+        assert!(matches!(result, Err(LlmError::Api(_))));
+        assert_eq!(llm.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,191 @@
+//! Daily transcript digests: one Markdown file per day in a user-chosen folder (e.g. an
+//! Obsidian vault), with templated front-matter.
+//!
+//! A new day's file starts with `front_matter`; each transcript is appended with
+//! `entry_template`, followed by an HTML comment holding its history id. The comment is
+//! hidden in Markdown previews and lets exports be repeated without duplicating entries.
+//!
+//! Templates use `{{date}}` (YYYY-MM-DD), `{{time}}` (HH:MM), `{{text}}` and `{{tags}}`
+//! (`#tag` list), all in local time.
+
+use crate::history::{HistoryEntry, HistoryStatus};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The `daily_digest` setting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// Folder the daily files are written to; nothing is exported until it is set.
+    pub folder: Option<PathBuf>,
+    /// Append every finished dictation to today's file right away.
+    pub auto_append: bool,
+    /// File name (without `.md`), as a chrono format string.
+    pub file_name: String,
+    /// Written once at the top of each new file.
+    pub front_matter: String,
+    /// Written for each transcript.
+    pub entry_template: String,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            folder: None,
+            auto_append: false,
+            file_name: "%Y-%m-%d".to_string(),
+            front_matter: "---\ndate: {{date}}\ntags: [dictation]\n---\n\n# Dictations {{date}}\n"
+                .to_string(),
+            entry_template: "- **{{time}}** {{text}}".to_string(),
+        }
+    }
+}
+
+impl DigestConfig {
+    /// Daily file for `date`.
+    pub fn file_for(&self, date: NaiveDate) -> Result<PathBuf, String> {
+        let folder = self
+            .folder
+            .as_ref()
+            .ok_or_else(|| "No digest folder configured".to_string())?;
+        let mut name = String::new();
+        use std::fmt::Write as _;
+        write!(name, "{}", date.format(&self.file_name))
+            .map_err(|_| format!("Invalid digest file name format: {}", self.file_name))?;
+        if name.trim().is_empty() || name.contains(['/', '\\']) {
+            return Err(format!("Invalid digest file name: {:?}", name));
+        }
+        Ok(folder.join(format!("{}.md", name)))
+    }
+}
+
+fn render(template: &str, entry: Option<&HistoryEntry>, date: NaiveDate) -> String {
+    let mut out = template.replace("{{date}}", &date.format("%Y-%m-%d").to_string());
+    if let Some(entry) = entry {
+        let time = entry.timestamp.with_timezone(&Local).format("%H:%M").to_string();
+        let tags = entry
+            .tags
+            .iter()
+            .map(|t| format!("#{}", t.replace(char::is_whitespace, "-")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        out = out
+            .replace("{{time}}", &time)
+            .replace("{{tags}}", &tags)
+            // Last, so placeholders dictated as text stay as they are.
+            .replace("{{text}}", entry.text.trim());
+    }
+    out
+}
+
+fn id_marker(entry: &HistoryEntry) -> String {
+    format!("<!-- tangerine:{} -->", entry.id)
+}
+
+/// Append the finished `entries` missing from their day's file. Returns how many were
+/// written.
+pub fn append_entries(config: &DigestConfig, entries: &[HistoryEntry]) -> Result<usize, String> {
+    let mut written = 0;
+    for entry in entries {
+        if entry.status != HistoryStatus::Success || entry.text.trim().is_empty() {
+            continue;
+        }
+        let date = entry.timestamp.with_timezone(&Local).date_naive();
+        let path = config.file_for(date)?;
+        if append_entry(config, &path, entry, date)? {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+fn append_entry(
+    config: &DigestConfig,
+    path: &Path,
+    entry: &HistoryEntry,
+    date: NaiveDate,
+) -> Result<bool, String> {
+    let existing = match fs::read_to_string(path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let marker = id_marker(entry);
+    if existing.as_deref().is_some_and(|c| c.contains(&marker)) {
+        return Ok(false);
+    }
+
+    let mut chunk = String::new();
+    match existing.as_deref() {
+        None => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            let front_matter = render(&config.front_matter, None, date);
+            if !front_matter.trim().is_empty() {
+                chunk.push_str(front_matter.trim_end());
+                chunk.push_str("\n\n");
+            }
+        }
+        Some(content) if !content.is_empty() && !content.ends_with('\n') => chunk.push('\n'),
+        Some(_) => {}
+    }
+    chunk.push_str(render(&config.entry_template, Some(entry), date).trim_end());
+    chunk.push(' ');
+    chunk.push_str(&marker);
+    chunk.push('\n');
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(chunk.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+/// Entries of `entries` dictated on `date` (local time), oldest first.
+pub fn entries_on(entries: &[HistoryEntry], date: NaiveDate) -> Vec<HistoryEntry> {
+    let mut day: Vec<HistoryEntry> = entries
+        .iter()
+        .filter(|e| DateTime::<Local>::from(e.timestamp).date_naive() == date)
+        .cloned()
+        .collect();
+    day.sort_by_key(|e| e.timestamp);
+    day
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_entries_writes_front_matter_once_and_skips_duplicates() {
+        let folder = std::env::temp_dir().join(format!("tangerine-digest-{}", uuid::Uuid::new_v4()));
+        let config = DigestConfig {
+            folder: Some(folder.clone()),
+            ..Default::default()
+        };
+        let mut first = HistoryEntry::new("First thought".to_string());
+        first.tags = vec!["daily log".to_string()];
+        let second = HistoryEntry::new("Second thought".to_string());
+        let mut failed = HistoryEntry::new(String::new());
+        failed.status = HistoryStatus::Error;
+
+        assert_eq!(append_entries(&config, &[first.clone(), failed]).unwrap(), 1);
+        assert_eq!(append_entries(&config, &[first.clone(), second]).unwrap(), 1);
+
+        let date = first.timestamp.with_timezone(&Local).date_naive();
+        let content = fs::read_to_string(config.file_for(date).unwrap()).unwrap();
+        assert_eq!(content.matches("tags: [dictation]").count(), 1);
+        assert_eq!(content.matches("First thought").count(), 1);
+        assert!(content.contains("Second thought"));
+        assert_eq!(render("{{tags}}", Some(&first), date), "#daily-log");
+
+        let _ = fs::remove_dir_all(folder);
+    }
+}
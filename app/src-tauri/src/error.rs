@@ -0,0 +1,232 @@
+//! Error type returned by every Tauri command.
+//!
+//! Commands used to return plain strings, so the frontend could only pattern-match on
+//! message text. `AppError` serializes as `{ "code": "...", "message": "..." }` where `code`
+//! is stable (see `AppError::code`) and `message` is the human-readable text. Module
+//! errors (`PipelineError`, `SttError`, `LlmError`, `AudioCaptureError`) convert into it,
+//! and so does `String`, so `?` keeps working on the many helpers that return
+//! `Result<_, String>`.
+
+use crate::app_settings::SettingsValidationError;
+use crate::audio_capture::AudioCaptureError;
+use crate::llm::LlmError;
+use crate::pipeline::PipelineError;
+use crate::stt::SttError;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("No microphone found (no audio input device available)")]
+    NoMicrophone,
+
+    #[error("{0}")]
+    Audio(String),
+
+    #[error("Pipeline is already recording")]
+    AlreadyRecording,
+
+    #[error("Pipeline is not recording")]
+    NotRecording,
+
+    #[error("No STT provider configured")]
+    NoProvider,
+
+    #[error("{0}")]
+    MissingApiKey(String),
+
+    #[error("{0}")]
+    InvalidApiKey(String),
+
+    #[error("{0}")]
+    RateLimited(String),
+
+    #[error("{0}")]
+    Network(String),
+
+    #[error("{0}")]
+    Provider(String),
+
+    #[error("{0}")]
+    Timeout(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("{0}")]
+    RecordingTooLarge(String),
+
+    #[error("{0}")]
+    Config(String),
+
+    #[error("{0}")]
+    Internal(String),
+
+    /// `update_settings` rejected values; serialized with an extra `errors` list.
+    #[error("Invalid settings: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; "))]
+    InvalidSettings(Vec<SettingsValidationError>),
+
+    /// Anything else (errors that were plain strings).
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AppError {
+    /// Stable identifier the frontend can match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NoMicrophone => "no_microphone",
+            AppError::Audio(_) => "audio",
+            AppError::AlreadyRecording => "already_recording",
+            AppError::NotRecording => "not_recording",
+            AppError::NoProvider => "no_provider",
+            AppError::MissingApiKey(_) => "missing_api_key",
+            AppError::InvalidApiKey(_) => "invalid_api_key",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::Network(_) => "network",
+            AppError::Provider(_) => "provider_error",
+            AppError::Timeout(_) => "timeout",
+            AppError::Cancelled => "cancelled",
+            AppError::RecordingTooLarge(_) => "recording_too_large",
+            AppError::Config(_) => "config",
+            AppError::Internal(_) => "internal",
+            AppError::InvalidSettings(_) => "invalid_settings",
+            AppError::Other(_) => "unknown",
+        }
+    }
+
+    /// Classify a provider's API error message (which carries the HTTP status).
+    fn from_api_message(message: String) -> Self {
+        let lower = message.to_lowercase();
+        if ["401", "403", "unauthorized", "invalid api key", "invalid_api_key", "incorrect api key"]
+            .iter()
+            .any(|marker| lower.contains(marker))
+        {
+            AppError::InvalidApiKey(message)
+        } else if lower.contains("429") || lower.contains("rate limit") {
+            AppError::RateLimited(message)
+        } else {
+            AppError::Provider(message)
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let errors = match self {
+            AppError::InvalidSettings(errors) => Some(errors),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("AppError", 2 + usize::from(errors.is_some()))?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(errors) = errors {
+            state.serialize_field("errors", errors)?;
+        }
+        state.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+impl From<AudioCaptureError> for AppError {
+    fn from(err: AudioCaptureError) -> Self {
+        match err {
+            AudioCaptureError::NoInputDevice => AppError::NoMicrophone,
+            AudioCaptureError::NotActive => AppError::NotRecording,
+            other => AppError::Audio(format!("Audio capture error: {}", other)),
+        }
+    }
+}
+
+impl From<SttError> for AppError {
+    fn from(err: SttError) -> Self {
+        let message = format!("STT error: {}", err);
+        match err {
+            SttError::Network(e) if e.is_timeout() => AppError::Timeout(message),
+            SttError::Network(_) => AppError::Network(message),
+            SttError::Api(_) => AppError::from_api_message(message),
+            SttError::RateLimited { .. } => AppError::RateLimited(message),
+            SttError::Audio(_) => AppError::Audio(message),
+            SttError::Config(_) => AppError::Config(message),
+            SttError::Timeout => AppError::Timeout(message),
+        }
+    }
+}
+
+impl From<LlmError> for AppError {
+    fn from(err: LlmError) -> Self {
+        let message = format!("LLM error: {}", err);
+        match err {
+            LlmError::Network(e) if e.is_timeout() => AppError::Timeout(message),
+            LlmError::Network(_) => AppError::Network(message),
+            LlmError::Api(_) => AppError::from_api_message(message),
+            LlmError::RateLimited { .. } => AppError::RateLimited(message),
+            LlmError::InvalidResponse(_) => AppError::Provider(message),
+            LlmError::Timeout(_) => AppError::Timeout(message),
+            LlmError::NoApiKey(_) => AppError::MissingApiKey(message),
+            LlmError::ProviderNotAvailable(_) => AppError::Config(message),
+        }
+    }
+}
+
+impl From<PipelineError> for AppError {
+    fn from(err: PipelineError) -> Self {
+        match err {
+            PipelineError::AudioCapture(e) => e.into(),
+            PipelineError::Stt(e) => e.into(),
+            PipelineError::Llm(e) => e.into(),
+            PipelineError::NoProvider => AppError::NoProvider,
+            PipelineError::AlreadyRecording => AppError::AlreadyRecording,
+            PipelineError::NotRecording => AppError::NotRecording,
+            PipelineError::Config(_) => AppError::Config(err.to_string()),
+            PipelineError::Lock(_) => AppError::Internal(err.to_string()),
+            PipelineError::Cancelled => AppError::Cancelled,
+            PipelineError::Timeout(_) => AppError::Timeout(err.to_string()),
+            PipelineError::RecordingTooLarge(_, _) => AppError::RecordingTooLarge(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let json = serde_json::to_value(AppError::from(PipelineError::NoProvider)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "code": "no_provider", "message": "No STT provider configured" })
+        );
+    }
+
+    #[test]
+    fn test_api_errors_are_classified_by_status() {
+        let bad_key = AppError::from(SttError::Api("HTTP 401: Invalid API Key".to_string()));
+        assert_eq!(bad_key.code(), "invalid_api_key");
+
+        let limited = AppError::from(LlmError::Api("HTTP 429 Too Many Requests".to_string()));
+        assert_eq!(limited.code(), "rate_limited");
+
+        let other = AppError::from(PipelineError::Stt(SttError::Api("HTTP 500".to_string())));
+        assert_eq!(other.code(), "provider_error");
+        assert_eq!(other.to_string(), "STT error: API error: HTTP 500");
+    }
+
+    #[test]
+    fn test_no_input_device_is_no_microphone() {
+        let err = AppError::from(PipelineError::AudioCapture(AudioCaptureError::NoInputDevice));
+        assert_eq!(err.code(), "no_microphone");
+    }
+}
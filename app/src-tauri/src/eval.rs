@@ -0,0 +1,352 @@
+//! STT accuracy evaluation against reference transcripts.
+//!
+//! An eval folder holds `<name>.wav` recordings next to `<name>.txt` ground-truth
+//! transcripts. `run_stt_eval` transcribes every case with each requested provider and
+//! scores the output with word and character error rates, so provider/model choices can be
+//! based on the user's own voice and vocabulary rather than published benchmarks.
+//!
+//! Both texts are normalized before scoring (lowercased, punctuation dropped, whitespace
+//! collapsed), since formatting differences aren't recognition errors. Provider-level rates
+//! are corpus rates: total edits over total reference length, so long cases weigh more.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One recording with its reference transcript.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalCase {
+    pub name: String,
+    pub audio_path: PathBuf,
+    pub reference: String,
+}
+
+/// Find `<name>.wav` files in `folder` that have a `<name>.txt` reference, sorted by name.
+pub fn discover_cases(folder: &Path) -> Result<Vec<EvalCase>, String> {
+    let entries = std::fs::read_dir(folder)
+        .map_err(|e| format!("Failed to read eval folder {}: {}", folder.display(), e))?;
+
+    let mut cases = Vec::new();
+    for entry in entries.flatten() {
+        let audio_path = entry.path();
+        let is_wav = audio_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+        if !is_wav {
+            continue;
+        }
+        let Some(name) = audio_path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Ok(reference) = std::fs::read_to_string(audio_path.with_extension("txt")) else {
+            log::warn!("Eval: no reference transcript for {}", audio_path.display());
+            continue;
+        };
+        cases.push(EvalCase {
+            name: name.to_string(),
+            audio_path: audio_path.clone(),
+            reference,
+        });
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// Lowercase, drop punctuation (apostrophes inside words are kept) and collapse whitespace.
+pub fn normalize(text: &str) -> String {
+    let cleaned: String = text
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '\'' {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    cleaned
+        .split_whitespace()
+        .map(|word| word.trim_matches('\''))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance (substitutions, insertions and deletions all cost 1).
+fn edit_distance<T: PartialEq>(reference: &[T], hypothesis: &[T]) -> usize {
+    let mut previous: Vec<usize> = (0..=hypothesis.len()).collect();
+    let mut current = vec![0; hypothesis.len() + 1];
+    for (i, r) in reference.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, h) in hypothesis.iter().enumerate() {
+            let substitution = previous[j] + usize::from(r != h);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[hypothesis.len()]
+}
+
+/// Edit count against the reference length (the numerator/denominator of WER or CER).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ErrorCount {
+    pub errors: usize,
+    pub reference_len: usize,
+}
+
+impl ErrorCount {
+    /// `None` for an empty reference.
+    pub fn rate(self) -> Option<f64> {
+        (self.reference_len > 0).then(|| self.errors as f64 / self.reference_len as f64)
+    }
+
+    fn add(self, other: ErrorCount) -> ErrorCount {
+        ErrorCount {
+            errors: self.errors + other.errors,
+            reference_len: self.reference_len + other.reference_len,
+        }
+    }
+}
+
+pub fn word_errors(reference: &str, hypothesis: &str) -> ErrorCount {
+    let (reference, hypothesis) = (normalize(reference), normalize(hypothesis));
+    let reference: Vec<&str> = reference.split(' ').filter(|w| !w.is_empty()).collect();
+    let hypothesis: Vec<&str> = hypothesis.split(' ').filter(|w| !w.is_empty()).collect();
+    ErrorCount {
+        errors: edit_distance(&reference, &hypothesis),
+        reference_len: reference.len(),
+    }
+}
+
+pub fn char_errors(reference: &str, hypothesis: &str) -> ErrorCount {
+    let reference: Vec<char> = normalize(reference).chars().collect();
+    let hypothesis: Vec<char> = normalize(hypothesis).chars().collect();
+    ErrorCount {
+        errors: edit_distance(&reference, &hypothesis),
+        reference_len: reference.len(),
+    }
+}
+
+/// A provider/model to evaluate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalProvider {
+    pub provider: String,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub name: String,
+    pub hypothesis: Option<String>,
+    pub words: ErrorCount,
+    pub chars: ErrorCount,
+    pub wer: Option<f64>,
+    pub cer: Option<f64>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+impl CaseResult {
+    pub fn scored(case: &EvalCase, hypothesis: String, latency_ms: u64) -> Self {
+        let words = word_errors(&case.reference, &hypothesis);
+        let chars = char_errors(&case.reference, &hypothesis);
+        Self {
+            name: case.name.clone(),
+            hypothesis: Some(hypothesis),
+            words,
+            chars,
+            wer: words.rate(),
+            cer: chars.rate(),
+            latency_ms,
+            error: None,
+        }
+    }
+
+    pub fn failed(case: &EvalCase, error: String, latency_ms: u64) -> Self {
+        Self {
+            name: case.name.clone(),
+            hypothesis: None,
+            words: ErrorCount::default(),
+            chars: ErrorCount::default(),
+            wer: None,
+            cer: None,
+            latency_ms,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderReport {
+    pub provider: String,
+    pub model: Option<String>,
+    pub completed: usize,
+    pub failed: usize,
+    /// Corpus WER over the completed cases.
+    pub wer: Option<f64>,
+    /// Corpus CER over the completed cases.
+    pub cer: Option<f64>,
+    pub mean_latency_ms: Option<f64>,
+    pub cases: Vec<CaseResult>,
+}
+
+impl ProviderReport {
+    pub fn new(provider: &EvalProvider, cases: Vec<CaseResult>) -> Self {
+        let completed: Vec<&CaseResult> = cases.iter().filter(|c| c.error.is_none()).collect();
+        let words = completed
+            .iter()
+            .fold(ErrorCount::default(), |acc, c| acc.add(c.words));
+        let chars = completed
+            .iter()
+            .fold(ErrorCount::default(), |acc, c| acc.add(c.chars));
+        let mean_latency_ms = (!completed.is_empty()).then(|| {
+            completed.iter().map(|c| c.latency_ms as f64).sum::<f64>() / completed.len() as f64
+        });
+        Self {
+            provider: provider.provider.clone(),
+            model: provider.model.clone(),
+            completed: completed.len(),
+            failed: cases.len() - completed.len(),
+            wer: words.rate(),
+            cer: chars.rate(),
+            mean_latency_ms,
+            cases,
+        }
+    }
+}
+
+/// Result of `run_stt_eval`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub folder: PathBuf,
+    pub started_at: DateTime<Utc>,
+    pub cases: usize,
+    /// Best (lowest) WER first; providers without a score last.
+    pub providers: Vec<ProviderReport>,
+}
+
+impl EvalReport {
+    pub fn new(
+        folder: PathBuf,
+        started_at: DateTime<Utc>,
+        cases: usize,
+        mut providers: Vec<ProviderReport>,
+    ) -> Self {
+        providers.sort_by(|a, b| {
+            a.wer
+                .unwrap_or(f64::INFINITY)
+                .total_cmp(&b.wer.unwrap_or(f64::INFINITY))
+        });
+        Self {
+            folder,
+            started_at,
+            cases,
+            providers,
+        }
+    }
+
+    /// Comparison table in markdown.
+    pub fn to_markdown(&self) -> String {
+        let percent = |rate: Option<f64>| {
+            rate.map(|r| format!("{:.1}%", r * 100.0))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let mut out = format!(
+            "# STT evaluation ({} cases, {})\n\n| Provider | Model | WER | CER | Mean latency | Failed |\n|---|---|---|---|---|---|\n",
+            self.cases,
+            self.started_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        for p in &self.providers {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                p.provider,
+                p.model.as_deref().unwrap_or("default"),
+                percent(p.wer),
+                percent(p.cer),
+                p.mean_latency_ms
+                    .map(|ms| format!("{:.0} ms", ms))
+                    .unwrap_or_else(|| "-".to_string()),
+                p.failed
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_rates_ignore_formatting() {
+        let reference = "Hello, world! It's a test.";
+        assert_eq!(word_errors(reference, "hello world it's a test").errors, 0);
+
+        // One substitution and one deletion over 5 reference words.
+        let words = word_errors(reference, "hello word it's test");
+        assert_eq!((words.errors, words.reference_len), (2, 5));
+        assert_eq!(words.rate(), Some(0.4));
+
+        let chars = char_errors("abc", "abd");
+        assert_eq!((chars.errors, chars.reference_len), (1, 3));
+        assert_eq!(word_errors("", "anything").rate(), None);
+    }
+
+    #[test]
+    fn test_discover_cases_and_report() {
+        let dir = std::env::temp_dir().join(format!("tangerine-eval-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.wav"), b"RIFF").unwrap();
+        std::fs::write(dir.join("b.txt"), "second case").unwrap();
+        std::fs::write(dir.join("a.WAV"), b"RIFF").unwrap();
+        std::fs::write(dir.join("a.txt"), "first case").unwrap();
+        std::fs::write(dir.join("orphan.wav"), b"RIFF").unwrap();
+
+        let cases = discover_cases(&dir).unwrap();
+        let names: Vec<&str> = cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(cases[1].reference, "second case");
+
+        let good = EvalProvider {
+            provider: "groq".to_string(),
+            model: None,
+        };
+        let bad = EvalProvider {
+            provider: "openai".to_string(),
+            model: None,
+        };
+        let report = EvalReport::new(
+            dir.clone(),
+            Utc::now(),
+            cases.len(),
+            vec![
+                ProviderReport::new(
+                    &bad,
+                    vec![
+                        CaseResult::scored(&cases[0], "first face".to_string(), 100),
+                        CaseResult::failed(&cases[1], "timeout".to_string(), 300),
+                    ],
+                ),
+                ProviderReport::new(
+                    &good,
+                    vec![
+                        CaseResult::scored(&cases[0], "first case".to_string(), 200),
+                        CaseResult::scored(&cases[1], "second case".to_string(), 400),
+                    ],
+                ),
+            ],
+        );
+        assert_eq!(report.providers[0].provider, "groq");
+        assert_eq!(report.providers[0].wer, Some(0.0));
+        assert_eq!(report.providers[0].mean_latency_ms, Some(300.0));
+        assert_eq!(
+            (report.providers[1].completed, report.providers[1].failed),
+            (1, 1)
+        );
+        assert_eq!(report.providers[1].wer, Some(0.5));
+        assert!(report.to_markdown().contains("| groq | default | 0.0% |"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use uuid::Uuid;
 
@@ -43,6 +43,72 @@ pub struct HistoryEntry {
     /// LLM model used for rewriting (if enabled).
     #[serde(default)]
     pub llm_model: Option<String>,
+    /// `text` is the raw transcript and LLM formatting is being retried in the background.
+    #[serde(default)]
+    pub formatting_pending: bool,
+    /// Length of the dictated audio (used for words-per-minute stats).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_duration_secs: Option<f32>,
+    /// User-assigned tags (e.g. "journal", "standup").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Free-form user notes about the dictation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Filter for `HistoryStorage::search`. Empty fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Case-insensitive text looked up in the transcript and the notes.
+    pub query: Option<String>,
+    /// Entries must have all of these tags (case-insensitive).
+    pub tags: Vec<String>,
+    /// Only entries at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries before this time.
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        if self.from.is_some_and(|from| entry.timestamp < from)
+            || self.to.is_some_and(|to| entry.timestamp >= to)
+        {
+            return false;
+        }
+        let has_tags = self
+            .tags
+            .iter()
+            .all(|tag| entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim())));
+        if !has_tags {
+            return false;
+        }
+        match self.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            Some(query) => {
+                let query = query.to_lowercase();
+                entry.text.to_lowercase().contains(&query)
+                    || entry
+                        .notes
+                        .as_deref()
+                        .is_some_and(|notes| notes.to_lowercase().contains(&query))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Trimmed, non-empty tags without case-insensitive duplicates (first spelling wins).
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
 }
 
 /// Metadata about which models were used for a transcription request.
@@ -66,6 +132,10 @@ impl HistoryEntry {
             stt_model: None,
             llm_provider: None,
             llm_model: None,
+            formatting_pending: false,
+            audio_duration_secs: None,
+            tags: Vec::new(),
+            notes: None,
         }
     }
 
@@ -80,6 +150,10 @@ impl HistoryEntry {
             stt_model: model_info.stt_model,
             llm_provider: model_info.llm_provider,
             llm_model: model_info.llm_model,
+            formatting_pending: false,
+            audio_duration_secs: None,
+            tags: Vec::new(),
+            notes: None,
         }
     }
 }
@@ -107,7 +181,21 @@ impl HistoryStorage {
         }
 
         // Load existing history or use empty
-        let data = Self::load_from_file(&file_path).unwrap_or_default();
+        let mut data = Self::load_from_file(&file_path).unwrap_or_default();
+
+        // Deferred formatting jobs only live in memory, so ones cut short by the last exit
+        // will never finish: keep their raw text and drop the flag.
+        let mut interrupted = 0;
+        for entry in data.entries.iter_mut().filter(|entry| entry.formatting_pending) {
+            entry.formatting_pending = false;
+            interrupted += 1;
+        }
+        if interrupted > 0 {
+            log::info!(
+                "Deferred LLM formatting of {} history entries was interrupted; kept the raw text",
+                interrupted
+            );
+        }
 
         Self {
             data: RwLock::new(data),
@@ -115,10 +203,15 @@ impl HistoryStorage {
         }
     }
 
+    /// Rewrite the history file in the current at-rest format.
+    pub fn rewrite(&self) -> Result<bool, String> {
+        let _guard = self.data.write().map_err(|e| e.to_string())?;
+        crate::persist::rewrite(&self.file_path).map_err(|e| e.to_string())
+    }
+
     /// Load history from the JSON file
-    fn load_from_file(file_path: &PathBuf) -> Option<HistoryData> {
-        let content = fs::read_to_string(file_path).ok()?;
-        serde_json::from_str(&content).ok()
+    fn load_from_file(file_path: &Path) -> Option<HistoryData> {
+        crate::persist::read_with_recovery(file_path)
     }
 
     /// Save current history to disk
@@ -131,7 +224,7 @@ impl HistoryStorage {
         let content = serde_json::to_string_pretty(&*data)
             .map_err(|e| format!("Failed to serialize history: {}", e))?;
 
-        fs::write(&self.file_path, content)
+        crate::persist::write_atomic(&self.file_path, content.as_bytes())
             .map_err(|e| format!("Failed to write history file: {}", e))?;
 
         Ok(())
@@ -234,7 +327,12 @@ impl HistoryStorage {
     }
 
     /// Mark an existing request entry as successful and set the final text.
-    pub fn complete_request_success(&self, request_id: &str, text: String) -> Result<(), String> {
+    pub fn complete_request_success(
+        &self,
+        request_id: &str,
+        text: String,
+        audio_duration_secs: Option<f32>,
+    ) -> Result<(), String> {
         {
             let mut data = self
                 .data
@@ -245,6 +343,7 @@ impl HistoryStorage {
                 entry.text = text;
                 entry.status = HistoryStatus::Success;
                 entry.error_message = None;
+                entry.audio_duration_secs = audio_duration_secs;
             } else {
                 // If we somehow missed creating an in-progress entry, fall back to inserting.
                 data.entries.insert(0, HistoryEntry::new_request_in_progress(request_id.to_string(), RequestModelInfo::default()));
@@ -280,6 +379,40 @@ impl HistoryStorage {
         self.save()
     }
 
+    /// Flag a completed request whose text still awaits deferred LLM formatting.
+    pub fn mark_formatting_pending(&self, request_id: &str) -> Result<(), String> {
+        self.update_formatting(request_id, true, None)
+    }
+
+    /// Resolve deferred LLM formatting: replace the raw text with `formatted` when given.
+    pub fn complete_formatting(&self, request_id: &str, formatted: Option<String>) -> Result<(), String> {
+        self.update_formatting(request_id, false, formatted)
+    }
+
+    fn update_formatting(
+        &self,
+        request_id: &str,
+        pending: bool,
+        text: Option<String>,
+    ) -> Result<(), String> {
+        {
+            let mut data = self
+                .data
+                .write()
+                .map_err(|e| format!("Failed to write history: {}", e))?;
+
+            // The entry may have been deleted or pruned in the meantime; nothing to do then.
+            let Some(entry) = data.entries.iter_mut().find(|e| e.id == request_id) else {
+                return Ok(());
+            };
+            entry.formatting_pending = pending;
+            if let Some(text) = text {
+                entry.text = text;
+            }
+        }
+        self.save()
+    }
+
     /// Get all history entries (newest first), optionally limited
     pub fn get_all(&self, limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
         let data = self
@@ -295,6 +428,59 @@ impl HistoryStorage {
         Ok(entries)
     }
 
+    /// Entries matching `query` (newest first), optionally limited.
+    pub fn search(&self, query: &HistoryQuery, limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+        let data = self
+            .data
+            .read()
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+
+        Ok(data
+            .entries
+            .iter()
+            .filter(|entry| query.matches(entry))
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect())
+    }
+
+    /// All tags in use, sorted case-insensitively.
+    pub fn tags(&self) -> Result<Vec<String>, String> {
+        let data = self
+            .data
+            .read()
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+
+        let mut tags = normalize_tags(data.entries.iter().flat_map(|e| e.tags.clone()).collect());
+        tags.sort_by_key(|tag| tag.to_lowercase());
+        Ok(tags)
+    }
+
+    /// Replace the tags and notes of an entry. Returns the updated entry, `None` if there
+    /// is no entry with that id.
+    pub fn annotate(
+        &self,
+        id: &str,
+        tags: Vec<String>,
+        notes: Option<String>,
+    ) -> Result<Option<HistoryEntry>, String> {
+        let updated = {
+            let mut data = self
+                .data
+                .write()
+                .map_err(|e| format!("Failed to write history: {}", e))?;
+
+            let Some(entry) = data.entries.iter_mut().find(|e| e.id == id) else {
+                return Ok(None);
+            };
+            entry.tags = normalize_tags(tags);
+            entry.notes = notes.filter(|n| !n.trim().is_empty());
+            entry.clone()
+        };
+        self.save()?;
+        Ok(Some(updated))
+    }
+
     /// Delete an entry by ID
     pub fn delete(&self, id: &str) -> Result<bool, String> {
         let deleted = {
@@ -327,3 +513,48 @@ impl HistoryStorage {
         self.save()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_query_matches_text_notes_tags_and_dates() {
+        let mut entry = HistoryEntry::new("Standup: shipped the importer".to_string());
+        entry.tags = normalize_tags(vec![" Work ".into(), "work".into(), "standup".into(), "".into()]);
+        entry.notes = Some("Ask Dana about retries".to_string());
+        assert_eq!(entry.tags, vec!["Work".to_string(), "standup".to_string()]);
+
+        let query = |q: &str, tags: &[&str]| HistoryQuery {
+            query: Some(q.to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(query("IMPORTER", &[]).matches(&entry));
+        assert!(query("dana", &["work"]).matches(&entry));
+        assert!(!query("dana", &["work", "journal"]).matches(&entry));
+        assert!(!query("meeting", &[]).matches(&entry));
+
+        let future = HistoryQuery {
+            from: Some(entry.timestamp + chrono::Duration::seconds(1)),
+            ..Default::default()
+        };
+        assert!(!future.matches(&entry));
+        assert!(HistoryQuery::default().matches(&entry));
+    }
+
+    #[test]
+    fn test_load_clears_formatting_interrupted_by_exit() {
+        let dir = std::env::temp_dir().join(format!("tangerine-history-{}", Uuid::new_v4()));
+        let history = HistoryStorage::new(dir.clone());
+        let entry = history.add_entry("raw transcript".to_string(), 10).unwrap();
+        history.mark_formatting_pending(&entry.id).unwrap();
+        assert!(history.get_all(None).unwrap()[0].formatting_pending);
+
+        let reloaded = HistoryStorage::new(dir.clone());
+        let entries = reloaded.get_all(None).unwrap();
+        assert_eq!(entries[0].text, "raw transcript");
+        assert!(!entries[0].formatting_pending);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,217 @@
+//! Backends that send the synthetic paste/Enter keys for output.
+//!
+//! enigo's synthetic key events don't reach native windows on most Wayland compositors.
+//! Wayland sessions therefore use `wtype` (virtual-keyboard protocol, wlroots-based
+//! compositors) or `ydotool` (uinput, works on any compositor but needs `ydotoold`) when
+//! one is installed. Without either, output degrades to clipboard-only: the text is left
+//! in the clipboard for the user to paste.
+
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionBackend {
+    Enigo,
+    Wtype,
+    Ydotool,
+    /// No way to send keys: copy the text and let the user paste it.
+    ClipboardOnly,
+}
+
+/// Linux evdev key codes used with `ydotool key`.
+const KEY_BACKSPACE: u16 = 14;
+const KEY_ENTER: u16 = 28;
+const KEY_LEFTCTRL: u16 = 29;
+const KEY_V: u16 = 47;
+
+fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn command_exists(name: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|dir| dir.join(name).is_file())
+    })
+}
+
+fn choose_backend(wayland: bool, has_command: impl Fn(&str) -> bool) -> InjectionBackend {
+    if !wayland {
+        InjectionBackend::Enigo
+    } else if has_command("wtype") {
+        InjectionBackend::Wtype
+    } else if has_command("ydotool") {
+        InjectionBackend::Ydotool
+    } else {
+        InjectionBackend::ClipboardOnly
+    }
+}
+
+/// The backend for this session (detected once).
+pub fn detected_backend() -> InjectionBackend {
+    static DETECTED: OnceLock<InjectionBackend> = OnceLock::new();
+    *DETECTED.get_or_init(|| {
+        let backend = if cfg!(target_os = "linux") {
+            choose_backend(is_wayland_session(), command_exists)
+        } else {
+            InjectionBackend::Enigo
+        };
+        log::info!("Output key injection backend: {:?}", backend);
+        backend
+    })
+}
+
+fn run(program: &str, args: &[String]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Sends the paste shortcut, Enter and Backspace through one backend.
+pub enum KeyInjector {
+    Enigo(Box<Enigo>),
+    Wtype,
+    Ydotool,
+}
+
+impl KeyInjector {
+    /// `None` for `ClipboardOnly`.
+    pub fn new(backend: InjectionBackend) -> Result<Option<Self>, String> {
+        Ok(match backend {
+            InjectionBackend::Enigo => Some(KeyInjector::Enigo(Box::new(
+                Enigo::new(&Settings::default()).map_err(|e| e.to_string())?,
+            ))),
+            InjectionBackend::Wtype => Some(KeyInjector::Wtype),
+            InjectionBackend::Ydotool => Some(KeyInjector::Ydotool),
+            InjectionBackend::ClipboardOnly => None,
+        })
+    }
+
+    /// Press Ctrl+V (Cmd+V on macOS), waiting `key_delay_ms` between key events.
+    pub fn paste(&mut self, key_delay_ms: u64) -> Result<(), String> {
+        let delay = key_delay_ms.to_string();
+        match self {
+            KeyInjector::Enigo(enigo) => {
+                #[cfg(target_os = "macos")]
+                let modifier = Key::Meta;
+                #[cfg(not(target_os = "macos"))]
+                let modifier = Key::Control;
+
+                enigo
+                    .key(modifier, Direction::Press)
+                    .map_err(|e| e.to_string())?;
+                thread::sleep(Duration::from_millis(key_delay_ms));
+                enigo
+                    .key(Key::Unicode('v'), Direction::Click)
+                    .map_err(|e| e.to_string())?;
+                thread::sleep(Duration::from_millis(key_delay_ms));
+                enigo
+                    .key(modifier, Direction::Release)
+                    .map_err(|e| e.to_string())
+            }
+            KeyInjector::Wtype => run(
+                "wtype",
+                &["-M", "ctrl", "-s", &delay, "-k", "v", "-s", &delay, "-m", "ctrl"].map(String::from),
+            ),
+            KeyInjector::Ydotool => run(
+                "ydotool",
+                &[
+                    "key".to_string(),
+                    "-d".to_string(),
+                    delay,
+                    format!("{}:1", KEY_LEFTCTRL),
+                    format!("{}:1", KEY_V),
+                    format!("{}:0", KEY_V),
+                    format!("{}:0", KEY_LEFTCTRL),
+                ],
+            ),
+        }
+    }
+
+    /// Press Backspace `count` times, waiting `key_delay_ms` between presses.
+    pub fn backspace(&mut self, count: usize, key_delay_ms: u64) -> Result<(), String> {
+        if count == 0 {
+            return Ok(());
+        }
+        let delay = key_delay_ms.to_string();
+        match self {
+            KeyInjector::Enigo(enigo) => {
+                for i in 0..count {
+                    if i > 0 {
+                        thread::sleep(Duration::from_millis(key_delay_ms));
+                    }
+                    enigo
+                        .key(Key::Backspace, Direction::Click)
+                        .map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+            KeyInjector::Wtype => {
+                let mut args = Vec::with_capacity(count * 4);
+                for i in 0..count {
+                    if i > 0 {
+                        args.extend(["-s".to_string(), delay.clone()]);
+                    }
+                    args.extend(["-k".to_string(), "BackSpace".to_string()]);
+                }
+                run("wtype", &args)
+            }
+            KeyInjector::Ydotool => {
+                let mut args = vec!["key".to_string(), "-d".to_string(), delay];
+                for _ in 0..count {
+                    args.push(format!("{}:1", KEY_BACKSPACE));
+                    args.push(format!("{}:0", KEY_BACKSPACE));
+                }
+                run("ydotool", &args)
+            }
+        }
+    }
+
+    pub fn enter(&mut self) -> Result<(), String> {
+        match self {
+            KeyInjector::Enigo(enigo) => enigo
+                .key(Key::Return, Direction::Click)
+                .map_err(|e| e.to_string()),
+            KeyInjector::Wtype => run("wtype", &["-k", "Return"].map(String::from)),
+            KeyInjector::Ydotool => run(
+                "ydotool",
+                &[
+                    "key".to_string(),
+                    format!("{}:1", KEY_ENTER),
+                    format!("{}:0", KEY_ENTER),
+                ],
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_selection() {
+        assert_eq!(choose_backend(false, |_| true), InjectionBackend::Enigo);
+        assert_eq!(choose_backend(true, |_| true), InjectionBackend::Wtype);
+        assert_eq!(
+            choose_backend(true, |name| name == "ydotool"),
+            InjectionBackend::Ydotool
+        );
+        assert_eq!(choose_backend(true, |_| false), InjectionBackend::ClipboardOnly);
+    }
+}
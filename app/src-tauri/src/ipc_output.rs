@@ -0,0 +1,255 @@
+//! IPC output: final transcripts streamed as JSON lines over a Unix domain socket
+//! (macOS/Linux) or a named pipe (Windows), so editor plugins and scripts can receive
+//! dictations without HTTP or the focused window (e.g. `socat - UNIX-CONNECT:<path>`).
+//!
+//! A client first writes a control API token with the `read_transcripts` scope on its own
+//! line (see `crate::api_tokens`); without one it gets an `error` line and is disconnected.
+//! From then on it gets one line per transcript:
+//! `{"type":"transcript","text":"...","request_id":"...","timestamp":"..."}`.
+//! Anything else a client writes is ignored. A client more than `CHANNEL_CAPACITY` lines
+//! behind skips the lines it missed.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::broadcast;
+
+/// Lines buffered per client.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// How long a new client has to send its token.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks the token a client sent; the error is sent back to the client.
+pub type Authorize = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// A line sent to clients.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IpcMessage {
+    /// A final transcript, as it is output.
+    Transcript {
+        text: String,
+        /// Request log / history id of the recording, if any.
+        request_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Why the connection is being closed.
+    Error { message: String },
+}
+
+impl IpcMessage {
+    pub fn transcript(text: &str, request_id: Option<String>) -> Self {
+        IpcMessage::Transcript {
+            text: text.to_string(),
+            request_id,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+fn channel() -> &'static broadcast::Sender<String> {
+    static CHANNEL: OnceLock<broadcast::Sender<String>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Send `message` to every connected client.
+pub fn publish(message: &IpcMessage) {
+    let sender = channel();
+    if sender.receiver_count() == 0 {
+        return;
+    }
+    match serde_json::to_string(message) {
+        Ok(mut line) => {
+            line.push('\n');
+            let _ = sender.send(line);
+        }
+        Err(e) => log::warn!("IPC output: failed to serialize message: {}", e),
+    }
+}
+
+/// Default endpoint: `tangerine.sock` in the app data dir, or the `tangerine` named pipe.
+pub fn default_endpoint(app_data_dir: &Path) -> String {
+    #[cfg(windows)]
+    {
+        let _ = app_data_dir;
+        r"\\.\pipe\tangerine".to_string()
+    }
+    #[cfg(not(windows))]
+    {
+        app_data_dir.join("tangerine.sock").to_string_lossy().into_owned()
+    }
+}
+
+/// Authorize a new client by the token on its first line, then forward published lines
+/// to it.
+async fn serve<S>(stream: S, authorize: Authorize)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut token = String::new();
+    let read = tokio::time::timeout(
+        AUTH_TIMEOUT,
+        BufReader::new(reader).read_line(&mut token),
+    )
+    .await;
+    let result = match read {
+        Ok(Ok(_)) => authorize(token.trim()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("No API token sent".to_string()),
+    };
+    if let Err(message) = result {
+        if let Ok(mut line) = serde_json::to_string(&IpcMessage::Error { message }) {
+            line.push('\n');
+            let _ = writer.write_all(line.as_bytes()).await;
+            let _ = writer.shutdown().await;
+        }
+        return;
+    }
+    forward(writer, channel().subscribe()).await;
+}
+
+/// Write published lines to `stream` until the client goes away.
+async fn forward<W: AsyncWrite + Unpin>(mut stream: W, mut lines: broadcast::Receiver<String>) {
+    loop {
+        match lines.recv().await {
+            Ok(line) => {
+                if stream.write_all(line.as_bytes()).await.is_err() || stream.flush().await.is_err() {
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::warn!("IPC output: client fell behind, skipped {} lines", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind(endpoint: &str) -> std::io::Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    let path = Path::new(endpoint);
+    // A socket left behind by a previous run; don't touch anything else.
+    if std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = std::os::unix::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+#[cfg(unix)]
+async fn accept_loop(listener: std::os::unix::net::UnixListener, authorize: Authorize) {
+    let listener = match tokio::net::UnixListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("IPC output: failed to register socket: {}", e);
+            return;
+        }
+    };
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve(stream, authorize.clone()));
+            }
+            Err(e) => log::warn!("IPC output: connection failed: {}", e),
+        }
+    }
+}
+
+/// Start listening on the Unix socket at `endpoint`, admitting clients `authorize` accepts.
+#[cfg(unix)]
+pub fn spawn_server(endpoint: String, authorize: Authorize) -> std::io::Result<()> {
+    let listener = bind(&endpoint)?;
+    log::info!("IPC output listening on {}", endpoint);
+    tauri::async_runtime::spawn(accept_loop(listener, authorize));
+    Ok(())
+}
+
+/// Start listening on the named pipe `endpoint`, admitting clients `authorize` accepts.
+#[cfg(windows)]
+pub fn spawn_server(endpoint: String, authorize: Authorize) -> std::io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    log::info!("IPC output listening on {}", endpoint);
+    tauri::async_runtime::spawn(async move {
+        let mut server = match ServerOptions::new().first_pipe_instance(true).create(&endpoint) {
+            Ok(server) => server,
+            Err(e) => {
+                log::error!("IPC output: failed to create {}: {}", endpoint, e);
+                return;
+            }
+        };
+        loop {
+            if let Err(e) = server.connect().await {
+                log::warn!("IPC output: connection failed: {}", e);
+                continue;
+            }
+            // A new instance for the next client before handing this one off.
+            let client = server;
+            server = match ServerOptions::new().create(&endpoint) {
+                Ok(server) => server,
+                Err(e) => {
+                    log::error!("IPC output: failed to create {}: {}", endpoint, e);
+                    return;
+                }
+            };
+            tokio::spawn(serve(client, authorize.clone()));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clients_receive_json_lines() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("tangerine-ipc-{}.sock", uuid::Uuid::new_v4()));
+        let endpoint = path.to_string_lossy().into_owned();
+
+        let authorize: Authorize = Arc::new(|token| match token {
+            "tgr_good" => Ok(()),
+            _ => Err("Missing or unknown API token".to_string()),
+        });
+
+        runtime.block_on(async {
+            tokio::spawn(accept_loop(bind(&endpoint).unwrap(), authorize));
+
+            let mut rejected = tokio::net::UnixStream::connect(&path).await.unwrap();
+            rejected.write_all(b"tgr_bad\n").await.unwrap();
+            let mut line = String::new();
+            BufReader::new(rejected).read_line(&mut line).await.unwrap();
+            let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(value["type"], "error");
+            assert_eq!(channel().receiver_count(), 0);
+
+            let mut client = tokio::net::UnixStream::connect(&path).await.unwrap();
+            client.write_all(b"tgr_good\n").await.unwrap();
+            while channel().receiver_count() == 0 {
+                tokio::task::yield_now().await;
+            }
+
+            publish(&IpcMessage::transcript("Hello there.", Some("req-1".to_string())));
+            let mut line = String::new();
+            BufReader::new(client).read_line(&mut line).await.unwrap();
+            let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(value["type"], "transcript");
+            assert_eq!(value["text"], "Hello there.");
+            assert_eq!(value["request_id"], "req-1");
+        });
+        let _ = std::fs::remove_file(path);
+    }
+}
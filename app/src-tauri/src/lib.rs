@@ -3,31 +3,58 @@ use std::time::{Duration, Instant};
 use tauri::{
     menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager,
+    AppHandle, Emitter, Listener, Manager,
 };
 use tauri_utils::config::BackgroundThrottlingPolicy;
 
+mod accessibility;
+mod api_tokens;
+mod app_log;
+mod app_settings;
+mod at_rest;
 mod audio;
-mod audio_capture;
 mod audio_mute;
 mod commands;
+mod deferred_format;
+mod digest;
+mod error;
+mod eval;
 mod history;
-mod llm;
-mod pipeline;
+mod injection;
+mod ipc_output;
+mod live_typing;
+mod model_catalog;
+mod model_prefetch;
+mod network;
+mod output_audit;
+mod permissions;
+mod placement;
+mod readback;
 mod recordings;
-mod request_log;
+mod search_index;
 mod settings;
+mod settings_transfer;
 mod state;
-mod stt;
-mod vad;
+mod stats;
+mod watchdog;
+mod webhook;
 mod windows_apps;
 
+// The pipeline, its providers and everything they need live in the Tauri-independent core
+// crate; `at_rest` and `network` extend the core modules of the same name.
+use tangerine_core::{
+    audio_capture, audio_source, corrections, hooks, http, llm, metrics, mock, output_format,
+    output_mode, persist, pipeline, pipeline_metrics, plugins, rate_limit, recovery, redaction,
+    request_log, retry, session, stt, text_diff, vad,
+};
+
 #[cfg(test)]
 mod tests;
 
 use audio_mute::AudioMuteManager;
 use history::{HistoryStorage, RequestModelInfo};
-use recordings::RecordingStore;
+use output_format::OutputFormat;
+use recordings::{RecordingOutcome, RecordingStore};
 use request_log::{RequestLogStore, RequestLogsRetentionConfig, RequestLogsRetentionMode};
 use settings::HotkeyConfig;
 use state::AppState;
@@ -65,6 +92,41 @@ pub(crate) fn normalize_shortcut_string(s: &str) -> String {
         .replace("win", "super")
 }
 
+/// Hotkeys bound to prompt profiles, as (profile id, shortcut). Hotkeys that don't parse,
+/// or that are already taken by a main hotkey or an earlier profile, are skipped.
+#[cfg(desktop)]
+pub(crate) fn profile_shortcuts(app: &AppHandle, taken: &[Shortcut]) -> Vec<(String, Shortcut)> {
+    let profiles: Vec<settings::RewriteProgramPromptProfile> =
+        get_setting_from_store(app, "rewrite_program_prompt_profiles", Vec::new());
+    let mut taken: Vec<String> = taken
+        .iter()
+        .map(|s| normalize_shortcut_string(&s.to_string()))
+        .collect();
+    let mut shortcuts = Vec::new();
+    for profile in profiles {
+        let Some(hotkey) = profile.hotkey else {
+            continue;
+        };
+        match hotkey.to_shortcut() {
+            Ok(shortcut) => {
+                let normalized = normalize_shortcut_string(&shortcut.to_string());
+                if taken.contains(&normalized) {
+                    log::warn!(
+                        "Hotkey {} of profile '{}' is already in use, skipped",
+                        hotkey.to_shortcut_string(),
+                        profile.name
+                    );
+                    continue;
+                }
+                taken.push(normalized);
+                shortcuts.push((profile.id, shortcut));
+            }
+            Err(e) => log::warn!("Invalid hotkey for profile '{}': {}", profile.name, e),
+        }
+    }
+    shortcuts
+}
+
 /// Helper to read a setting from the store with a default fallback
 #[cfg(desktop)]
 fn get_setting_from_store<T: serde::de::DeserializeOwned>(
@@ -92,6 +154,7 @@ fn ensure_default_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Er
     use tauri_plugin_store::StoreExt;
 
     let store = app.store("settings.json")?;
+    let migrated = app_settings::migrate_store(&store);
 
     // Keep these defaults aligned with pipeline defaults / expected backend behavior.
     // We intentionally seed these so a brand new install has the same effective
@@ -102,7 +165,7 @@ fn ensure_default_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Er
         matches!(v, None | Some(Value::Null))
     };
 
-    let mut dirty = false;
+    let mut dirty = migrated;
     let mut set_if_missing = |key: &str, value: Value| {
         if is_missing(store.get(key)) {
             store.set(key.to_string(), value);
@@ -112,10 +175,50 @@ fn ensure_default_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Er
 
     set_if_missing("stt_provider", json!("groq"));
     set_if_missing("stt_transcription_prompt", json!(null));
+    // Send personal dictionary terms to the STT provider as recognition hints.
+    set_if_missing("stt_vocabulary_boost", json!(true));
+    // Seconds of recent dictation passed to STT as context (0 = off).
+    set_if_missing("stt_context_secs", json!(0));
+    // Dictation language hint for the STT provider (null = detect).
+    set_if_missing("stt_language", json!(null));
+    // whisper.cpp server URL for the "whisper-server" STT provider (null = localhost).
+    set_if_missing("whisper_server_url", json!(null));
+    // Extra request parameters per STT provider id, e.g. {"deepgram": {"diarize": "true"}}.
+    set_if_missing("stt_extra_params", json!({}));
+    // OpenAI/Groq Whisper sampling temperature (null = provider default) and verbose_json.
+    set_if_missing("stt_temperature", json!(null));
+    set_if_missing("stt_verbose_json", json!(false));
+    // Local Whisper decoding (beam size, temperature fallback, no-speech threshold, ...).
+    set_if_missing("whisper_decoding", json!({}));
+    // Local Whisper thread count (0 = all cores but two), priority and pause-while-recording.
+    set_if_missing("whisper_threading", json!({}));
+    // Local Vosk model id (null = the small English model).
+    set_if_missing("vosk_model", json!(null));
+    // Proxy, no-proxy list, extra root CA and TLS verification for all network calls.
+    set_if_missing("network_config", json!({}));
+    set_if_missing("rate_limit", json!({}));
+    set_if_missing("pipeline_watchdog", json!({}));
+    // Secondary STT provider tried when the primary one keeps failing (null = none).
+    set_if_missing("fallback_stt_provider", json!(null));
     set_if_missing("stt_timeout_seconds", json!(10.0));
+    // Provider/model combos behind the fast/balanced/accurate profile tiers.
+    set_if_missing("quality_tiers", json!(settings::QualityTiers::default()));
+    // PII masking before cloud LLM calls / in request logs (profiles can override `enabled`).
+    set_if_missing("pii_redaction", json!(redaction::RedactionConfig::default()));
+    // Command run on each transcript before output (see `hooks`); off while empty
+    set_if_missing("output_hook", json!(hooks::HookConfig::default()));
+    set_if_missing("mock_providers_enabled", json!(false));
+    set_if_missing("mock_providers", json!(mock::MockProviders::default()));
+    // STT/LLM providers implemented by external programs (see `plugins`)
+    set_if_missing("provider_plugins", json!([]));
     // How many recordings/history items to retain (impacts disk usage).
     // Keep this aligned with the UI default.
     set_if_missing("max_saved_recordings", json!(1000));
+    // When audio is written to disk: "always", "on_error" (for retries) or "never".
+    set_if_missing("recording_storage_mode", json!(recordings::RecordingStorageMode::default()));
+    // Extra caps on saved recordings (0 = no limit), enforced after each save and hourly.
+    set_if_missing("recordings_max_total_mb", json!(0));
+    set_if_missing("recordings_max_age_days", json!(0));
 
     // Request logs retention (in-memory request log history).
     // Keep this aligned with the UI default.
@@ -123,6 +226,8 @@ fn ensure_default_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Er
     set_if_missing("request_logs_retention_amount", json!(10));
     // Only used when mode == "time" (days; 0 = forever)
     set_if_missing("request_logs_retention_days", json!(7));
+    // Level of the application log (stderr + rotating files; see `app_log`).
+    set_if_missing("log_level", json!(app_log::DEFAULT_LOG_LEVEL));
     // Time-based retention for history/transcriptions. 0 = keep forever.
     set_if_missing("transcription_retention_days", json!(0));
     // New retention keys (unit+value) used by newer UI.
@@ -133,8 +238,57 @@ fn ensure_default_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Er
     set_if_missing("transcription_retention_delete_recordings", json!(false));
     set_if_missing("overlay_mode", json!("recording_only"));
     set_if_missing("widget_position", json!("bottom-center"));
+    set_if_missing("overlay_placement", json!("preset"));
+    set_if_missing("overlay_monitor", json!("active"));
     set_if_missing("output_mode", json!("paste"));
     set_if_missing("output_hit_enter", json!(false));
+    // Key/clipboard delays and paste chunking of the output typing engine.
+    set_if_missing("output_typing", json!(commands::text::TypingConfig::default()));
+    set_if_missing("daily_digest", json!(digest::DigestConfig::default()));
+    set_if_missing("output_webhook", json!(webhook::WebhookConfig::default()));
+    // IPC output (JSON lines over a Unix socket / named pipe); null path = default endpoint
+    set_if_missing("ipc_output_enabled", json!(false));
+    set_if_missing("ipc_output_path", json!(null));
+    // Markdown handling of the output ("as_is" | "plain_text" | "html"); profiles can override.
+    set_if_missing("output_format", json!(OutputFormat::AsIs));
+    // What to do when the window active at recording start lost focus before pasting
+    // ("off" | "refocus" | "clipboard").
+    set_if_missing("focus_guard", json!("off"));
+    // How long to listen for "confirm"/"cancel" after a read-back (profiles with confirm_by_voice).
+    set_if_missing(
+        "readback_listen_secs",
+        json!(readback::DEFAULT_READBACK_LISTEN_SECS),
+    );
+    // Append mode: merge recordings made in quick succession into one output.
+    set_if_missing("dictation_session_enabled", json!(false));
+    set_if_missing(
+        "dictation_session_window_secs",
+        json!(session::DEFAULT_SESSION_WINDOW_SECS),
+    );
+    // Pre-roll: seconds of audio buffered while idle and prepended to the next recording.
+    // 0 = disabled (the mic is only open while recording).
+    set_if_missing("preroll_secs", json!(0.0));
+    // End recordings at the max duration (and transcribe them) instead of keeping only the
+    // most recent audio.
+    set_if_missing("stop_at_max_duration", json!(false));
+    // Per-device ambient noise levels from `calibrate_microphone`, keyed by CPAL device name
+    // ("default" for the system default).
+    set_if_missing("noise_profiles", json!({}));
+    // Background Whisper model downloads.
+    set_if_missing("model_prefetch_unmetered_only", json!(true));
+    set_if_missing("model_prefetch_max_kib_per_sec", json!(0));
+    // Large-text captions window (accessibility).
+    let captions_style = commands::captions::CaptionsStyle::default();
+    set_if_missing("captions_enabled", json!(false));
+    set_if_missing("captions_font_size_px", json!(captions_style.font_size_px));
+    set_if_missing("captions_high_contrast", json!(captions_style.high_contrast));
+    set_if_missing("captions_max_lines", json!(captions_style.max_lines));
+    // Probe connectivity and switch to local providers when offline.
+    set_if_missing("offline_mode_auto", json!(true));
+    // Metrics exporter (off, local Prometheus /metrics endpoint, or OTLP push).
+    set_if_missing("metrics_exporter", json!(metrics::DEFAULT_METRICS_EXPORTER));
+    set_if_missing("metrics_port", json!(metrics::DEFAULT_METRICS_PORT));
+    set_if_missing("metrics_otlp_endpoint", json!(metrics::DEFAULT_OTLP_ENDPOINT));
     set_if_missing("playing_audio_handling", json!("mute"));
     set_if_missing("sound_enabled", json!(true));
     set_if_missing("rewrite_llm_enabled", json!(false));
@@ -181,6 +335,11 @@ fn ensure_default_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Er
         "quiet_audio_require_speech",
         json!(default_pipeline_config.quiet_audio_require_speech),
     );
+    set_if_missing(
+        "hallucination_filter_enabled",
+        json!(default_pipeline_config.hallucination_filter_enabled),
+    );
+    set_if_missing("hallucination_filter_phrases", json!([]));
 
     // Stop-time preprocessing defaults.
     set_if_missing(
@@ -207,11 +366,47 @@ fn ensure_default_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Er
         "audio_noise_suppression_enabled",
         json!(default_pipeline_config.audio_noise_suppression_enabled),
     );
+    set_if_missing(
+        "audio_trim_silence",
+        json!(default_pipeline_config.audio_trim_silence),
+    );
+    set_if_missing(
+        "audio_max_pause_secs",
+        json!(default_pipeline_config.audio_max_pause_secs),
+    );
+    set_if_missing(
+        "identifier_casing_enabled",
+        json!(default_pipeline_config.identifier_casing_enabled),
+    );
+    // Review transcripts in an editable window before they are output; edits are learned
+    // as corrections (see `corrections`).
+    set_if_missing("review_before_output", json!(false));
+    set_if_missing("capture_surrounding_text", json!(false));
+    set_if_missing("learned_corrections", json!([]));
+    // Encrypt recordings, history and logs at rest (key in the OS keychain).
+    set_if_missing("encrypt_at_rest", json!(false));
+    set_if_missing(
+        "deferred_llm_formatting",
+        json!(default_pipeline_config.deferred_llm_formatting),
+    );
+    set_if_missing(
+        "llm_timeout_secs",
+        json!(default_pipeline_config.llm_config.timeout.as_secs()),
+    );
+    set_if_missing(
+        "llm_max_retries",
+        json!(default_pipeline_config.llm_config.retry_config.max_retries),
+    );
+    // LLM max_tokens, temperature and top_p (null = provider default).
+    set_if_missing(
+        "llm_generation",
+        json!(default_pipeline_config.llm_config.generation),
+    );
 
     if dirty {
         // Persist seeded defaults.
         // If saving fails, we don't want to crash the app; the runtime fallbacks will still work.
-        if let Err(e) = store.save() {
+        if let Err(e) = app_settings::save_store(app, &store) {
             log::warn!("Failed to save seeded default settings: {}", e);
         }
     }
@@ -406,8 +601,10 @@ fn is_non_system_audio_session_active() -> Result<bool, String> {
     Ok(true)
 }
 
-/// Start recording with sound and audio mute handling
+/// Start recording with sound and audio mute handling. `profile_id` pins a prompt profile
+/// for the recording (see `SharedPipeline::start_recording_for_profile`).
 #[cfg(desktop)]
+#[allow(clippy::too_many_arguments)]
 fn start_recording(
     app: &AppHandle,
     state: &AppState,
@@ -416,6 +613,7 @@ fn start_recording(
     audio_mute_manager: &Option<tauri::State<'_, AudioMuteManager>>,
     playing_audio_handling: PlayingAudioHandling,
     source: &str,
+    profile_id: Option<String>,
 ) {
     // Log current pipeline state before attempting to start
     let current_state = app
@@ -426,7 +624,7 @@ fn start_recording(
 
     // Start pipeline recording FIRST - if it fails, don't do anything else
     if let Some(pipeline) = app.try_state::<pipeline::SharedPipeline>() {
-        if let Err(e) = pipeline.start_recording() {
+        if let Err(e) = pipeline.start_recording_for_profile(profile_id) {
             log::error!("{}: Failed to start pipeline recording: {} (state was: {:?})", source, e, current_state);
             let error_msg = format!("{} (pipeline state: {:?})", e, current_state);
             emit_system_event(app, "error", &format!("{}: Failed to start recording", source), Some(&error_msg));
@@ -460,6 +658,50 @@ fn start_recording(
     // Pipeline started successfully - now update state and do side effects
     state.is_recording.store(true, Ordering::SeqCst);
 
+    // Remember where the user was typing, before the overlay/main window can take focus.
+    if let Ok(mut target) = state.focus_target.lock() {
+        *target = commands::text::capture_focus_target(get_setting_from_store(
+            app,
+            "focus_guard",
+            commands::text::FocusGuard::Off,
+        ));
+    }
+
+    // Optional: give the LLM the text before the caret, so it can continue it.
+    if get_setting_from_store(app, "capture_surrounding_text", false) {
+        if let Some(pipeline) = app.try_state::<pipeline::SharedPipeline>() {
+            let pipeline = (*pipeline).clone();
+            tauri::async_runtime::spawn_blocking(move || match accessibility::text_before_caret() {
+                Ok(text) => pipeline.set_surrounding_text(text),
+                Err(e) => log::debug!("Surrounding text not captured: {}", e),
+            });
+        }
+    }
+
+    // Live typing: type partial transcripts while recording.
+    if let Ok(mut live_typer) = state.live_typer.lock() {
+        *live_typer = None;
+        let output_mode: String = get_setting_from_store(app, "output_mode", "paste".to_string());
+        let output_mode = app
+            .try_state::<pipeline::SharedPipeline>()
+            .and_then(|p| p.output_mode_for_active_profile())
+            .unwrap_or_else(|| commands::text::OutputMode::from_str(&output_mode));
+        if output_mode == commands::text::OutputMode::LiveTyping {
+            if let Some(pipeline) = app.try_state::<pipeline::SharedPipeline>() {
+                let typer = std::sync::Arc::new(std::sync::Mutex::new(live_typing::LiveTyper::new(
+                    commands::text::typing_config(app),
+                )));
+                *live_typer = Some(typer.clone());
+                let app_handle = app.clone();
+                tauri::async_runtime::spawn(live_typing::run_partials(
+                    (*pipeline).clone(),
+                    typer,
+                    move |partial| commands::captions::publish_caption(&app_handle, partial, false),
+                ));
+            }
+        }
+    }
+
     // Start the recording chime ASAP.
     // Showing/snapping the overlay window can be a bit slow on some systems (monitor queries,
     // position math, window show), so we kick off audio playback *before* that work.
@@ -610,12 +852,51 @@ fn stop_recording(
     let overlay_mode: String =
         get_setting_from_store(app, "overlay_mode", "recording_only".to_string());
 
-    // Get output mode for how to output text
+    // The profile of this recording, resolved now: by the time the transcript is ready a
+    // newer recording may have set another hotkey profile, or another app may be in front.
+    let profile = app
+        .try_state::<pipeline::SharedPipeline>()
+        .and_then(|p| p.active_profile());
+    let confirm_by_voice = profile.as_ref().is_some_and(|p| p.confirm_by_voice);
+    let profile_webhook_url = profile.as_ref().and_then(|p| p.webhook_url.clone());
+
+    // Get output mode for how to output text (the active profile can override it)
     let output_mode_str: String = get_setting_from_store(app, "output_mode", "paste".to_string());
-    let output_mode = commands::text::OutputMode::from_str(&output_mode_str);
+    let output_options = commands::text::OutputOptions {
+        mode: profile
+            .as_ref()
+            .and_then(|p| p.output_mode)
+            .unwrap_or_else(|| commands::text::OutputMode::from_str(&output_mode_str)),
+        // Optional: after pasting, press Enter.
+        hit_enter: get_setting_from_store(app, "output_hit_enter", false),
+        typing: commands::text::typing_config(app),
+        format: profile
+            .as_ref()
+            .and_then(|p| p.output_format)
+            .unwrap_or_else(|| get_setting_from_store(app, "output_format", OutputFormat::AsIs)),
+    };
+
+    // Optional: collect consecutive recordings into one dictation session.
+    let dictation_session_window = get_dictation_session_window(app);
 
-    // Optional: after pasting, press Enter.
-    let output_hit_enter: bool = get_setting_from_store(app, "output_hit_enter", false);
+    // Optional: re-focus the window that was active when recording started.
+    let focus_target = state
+        .focus_target
+        .lock()
+        .ok()
+        .and_then(|mut target| target.take());
+
+    // Live typing: no more partials; the final transcript corrects what was typed.
+    let live_typer = state
+        .live_typer
+        .lock()
+        .ok()
+        .and_then(|mut typer| typer.take());
+    if let Some(typer) = &live_typer {
+        if let Ok(mut typer) = typer.lock() {
+            typer.close();
+        }
+    }
 
     // Stop pipeline and trigger transcription in background
     if let Some(pipeline) = app.try_state::<pipeline::SharedPipeline>() {
@@ -643,7 +924,7 @@ fn stop_recording(
             }
         };
 
-        tauri::async_runtime::spawn(async move {
+        let transcription = async move {
             // Emit transcription started only once the pipeline actually transitions
             // into Transcribing (quiet-audio gate skips should fade out without ever
             // showing "TRANSCRIBING...").
@@ -710,9 +991,17 @@ fn stop_recording(
                 });
             }
 
-            match pipeline_clone.stop_and_transcribe_detailed().await {
+            let outcome = pipeline_clone.stop_and_transcribe_detailed().await;
+            // This recording's audio, even if a newer one has been made since.
+            let request_audio = request_id
+                .as_deref()
+                .and_then(|id| pipeline_clone.audio_for_request(id))
+                .or_else(|| pipeline_clone.clone_last_wav_bytes());
+
+            match outcome {
                 Ok(result) => {
                     log::info!("Transcription complete: {} chars", result.final_text.len());
+                    commands::recording::notify_if_empty_recording(&app_clone, &result);
 
                     // Final output after pipeline (STT + optional LLM) normalization.
                     // Quiet recordings should already have been skipped in the pipeline.
@@ -731,6 +1020,9 @@ fn stop_recording(
 
                             log.stt_duration_ms = Some(result.stt_duration_ms);
                             log.llm_duration_ms = result.llm_duration_ms;
+                            if let Some(wav) = request_audio.clone() {
+                                log.set_audio(&wav);
+                            }
 
                             log.info(format!(
                                 "STT completed in {}ms ({} chars)",
@@ -786,47 +1078,111 @@ fn stop_recording(
                         request_id.as_ref(),
                         app_clone.try_state::<RecordingStore>(),
                     ) {
-                        if let Some(wav) = pipeline_clone.clone_last_wav_bytes() {
-                            if store.save_wav(req_id, &wav).is_ok() {
-                                let max_saved_recordings: usize = (get_setting_from_store(
-                                    &app_clone,
-                                    "max_saved_recordings",
-                                    1000u64,
-                                ))
-                                .clamp(1, 100_000) as usize;
-
-                                let _ = store.prune_to_max_files(max_saved_recordings);
+                        if let Some(ref wav) = request_audio {
+                            if let Ok(true) = store.save_wav(req_id, wav, RecordingOutcome::Succeeded) {
+                                commands::recording::apply_recording_retention(&app_clone);
                             }
                         }
                     }
 
                     if let Some(ref text) = filtered_transcript {
                         let _ = app_clone.emit("pipeline-transcript-ready", text);
+                        commands::captions::publish_caption(&app_clone, text, true);
 
-                        // Output the transcript based on mode
-                        if let Err(e) = commands::text::output_text_with_mode(text, output_mode, output_hit_enter) {
-                            log::error!("Failed to output transcript: {}", e);
+                        let confirmed = if confirm_by_voice {
+                            confirm_output_by_voice(&app_clone, &pipeline_clone, text).await
+                        } else {
+                            true
+                        };
 
-                            if let Some(log_store) = app_clone.try_state::<RequestLogStore>() {
-                                log_store.with_current(|log| {
-                                    log.warn(format!("Output failed: {}", e));
-                                });
+                        let webhook_url = webhook::webhook_config(&app_clone)
+                            .target(output_options.mode, profile_webhook_url.as_deref());
+                        let review =
+                            live_typer.is_none() && commands::review::review_enabled(&app_clone);
+                        // In review mode the text is sent once confirmed, as edited.
+                        if confirmed && !review {
+                            ipc_output::publish(&ipc_output::IpcMessage::transcript(text, request_id.clone()));
+                            if let Some(url) = webhook_url.clone() {
+                                webhook::spawn_send(&app_clone, url, text.clone(), request_id.clone());
+                            }
+                        }
+
+                        if !confirmed {
+                            log::info!("Read-back: output discarded");
+                            finish_live_typing(live_typer.clone(), None).await;
+                        } else if let Some(typer) = live_typer.clone() {
+                            // Live typing: correct the partials already typed.
+                            finish_live_typing(Some(typer), Some(text.clone())).await;
+                        } else if review {
+                            // Review mode: the review window outputs the text once confirmed.
+                            if let Err(e) = commands::review::request_review(
+                                &app_clone,
+                                text.clone(),
+                                request_id.clone(),
+                                output_options,
+                                webhook_url,
+                            ) {
+                                log::error!("Failed to open review window: {}", e);
+                            }
+                        } else if let Some(window) = dictation_session_window {
+                            // Append mode: hold the segment until the session window passes.
+                            queue_dictation_session_segment(
+                                &app_clone,
+                                text.clone(),
+                                window,
+                                output_options,
+                                focus_target,
+                            )
+                            .await;
+                        } else {
+                            let typing_started = Instant::now();
+                            let output = output_transcript(
+                                &app_clone,
+                                text,
+                                &output_options,
+                                focus_target,
+                            )
+                            .await;
+                            if let Some(ref req_id) = request_id {
+                                let typing_elapsed = typing_started.elapsed();
+                                pipeline_metrics::global().record_typing(req_id, typing_elapsed);
+                                metrics::global().observe_typing(typing_elapsed);
+                            }
+
+                            if let Err(e) = output {
+                                // Output the transcript based on mode
+                                log::error!("Failed to output transcript: {}", e);
+
+                                if let Some(log_store) = app_clone.try_state::<RequestLogStore>() {
+                                    log_store.with_current(|log| {
+                                        log.warn(format!("Output failed: {}", e));
+                                    });
+                                }
                             }
                         }
 
                         // Save to history
                         if let Some(ref req_id) = request_id {
                             if let Some(history) = app_clone.try_state::<HistoryStorage>() {
-                                if let Err(e) = history.complete_request_success(req_id, text.clone()) {
-                                    log::warn!("Failed to update history: {}", e);
+                                match history.complete_request_success(
+                                    req_id,
+                                    text.clone(),
+                                    result.audio_duration_secs,
+                                ) {
+                                    Ok(()) => commands::digest::append_after_dictation(&app_clone, req_id),
+                                    Err(e) => log::warn!("Failed to update history: {}", e),
                                 }
                                 let _ = app_clone.emit("history-changed", ());
                             }
                         }
+                        deferred_format::enqueue_if_needed(&app_clone, request_id.as_deref(), &result);
 
                         // Time-based retention (best-effort). This path is used by global shortcuts.
                         commands::recording::apply_transcription_retention(&app_clone);
                     } else {
+                        finish_live_typing(live_typer.clone(), None).await;
+                        commands::captions::clear_partial_caption(&app_clone);
+
                         // Emit empty transcript event so UI can update appropriately
                         let _ = app_clone.emit("pipeline-transcript-ready", "");
                         log::info!("No transcript output (empty/whitespace), not outputting");
@@ -834,7 +1190,7 @@ fn stop_recording(
                         // Mark history entry as success with empty text (keeps timeline consistent)
                         if let Some(ref req_id) = request_id {
                             if let Some(history) = app_clone.try_state::<HistoryStorage>() {
-                                let _ = history.complete_request_success(req_id, String::new());
+                                let _ = history.complete_request_success(req_id, String::new(), None);
                                 let _ = app_clone.emit("history-changed", ());
                             }
                         }
@@ -868,6 +1224,10 @@ fn stop_recording(
                     }
                 }
                 Err(e) => {
+                    // Whatever was typed live is not the transcript.
+                    finish_live_typing(live_typer.clone(), None).await;
+                    commands::captions::clear_partial_caption(&app_clone);
+
                     if matches!(e, pipeline::PipelineError::Cancelled) {
                         log::info!("Transcription cancelled");
 
@@ -890,8 +1250,10 @@ fn stop_recording(
                             }
                         }
 
-                        // Done - stop stealing Escape.
-                        crate::set_escape_cancel_shortcut_enabled(&app_clone, false);
+                        // Done - stop stealing Escape (unless another recording is running).
+                        if !pipeline_clone.state().can_cancel() {
+                            crate::set_escape_cancel_shortcut_enabled(&app_clone, false);
+                        }
                         return;
                     }
 
@@ -915,16 +1277,9 @@ fn stop_recording(
                         request_id.as_ref(),
                         app_clone.try_state::<RecordingStore>(),
                     ) {
-                        if let Some(wav) = pipeline_clone.clone_last_wav_bytes() {
-                            if store.save_wav(req_id, &wav).is_ok() {
-                                let max_saved_recordings: usize = (get_setting_from_store(
-                                    &app_clone,
-                                    "max_saved_recordings",
-                                    1000u64,
-                                ))
-                                .clamp(1, 100_000) as usize;
-
-                                let _ = store.prune_to_max_files(max_saved_recordings);
+                        if let Some(ref wav) = request_audio {
+                            if let Ok(true) = store.save_wav(req_id, wav, RecordingOutcome::Failed) {
+                                commands::recording::apply_recording_retention(&app_clone);
                             }
                         }
                     }
@@ -947,14 +1302,259 @@ fn stop_recording(
                 }
             }
 
-            // Transcription finished (success or error) - stop stealing Escape.
-            crate::set_escape_cancel_shortcut_enabled(&app_clone, false);
+            // Transcription finished (success or error) - stop stealing Escape, unless a
+            // newer recording or transcription still wants it.
+            if !pipeline_clone.state().can_cancel() {
+                crate::set_escape_cancel_shortcut_enabled(&app_clone, false);
+            }
+        };
+
+        // Keep logging to this request even after the next recording starts its own.
+        let log_store = app.try_state::<RequestLogStore>().map(|store| (*store).clone());
+        tauri::async_runtime::spawn(async move {
+            match log_store {
+                Some(store) => store.scope_current(transcription).await,
+                None => transcription.await,
+            }
         });
     }
 
     let _ = app.emit("recording-stop", ());
 }
 
+// ============================================================================
+// Read-back confirmation
+// ============================================================================
+
+/// Read `text` aloud and listen for a spoken "confirm"/"cancel".
+///
+/// Returns `true` only for an explicit confirmation; errors, silence and unclear replies
+/// all discard the output.
+#[cfg(desktop)]
+async fn confirm_output_by_voice(
+    app: &AppHandle,
+    pipeline: &pipeline::SharedPipeline,
+    text: &str,
+) -> bool {
+    let _ = app.emit("readback-started", text);
+
+    let to_speak = text.to_string();
+    let spoken = tauri::async_runtime::spawn_blocking(move || readback::speak_blocking(&to_speak))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    match spoken {
+        Ok(()) => {}
+        Err(e) => {
+            log::warn!("Read-back: TTS failed ({}), discarding output", e);
+            let _ = app.emit(
+                "readback-result",
+                serde_json::json!({ "reply": "error", "message": e }),
+            );
+            return false;
+        }
+    }
+
+    let listen_secs: f64 = get_setting_from_store(
+        app,
+        "readback_listen_secs",
+        readback::DEFAULT_READBACK_LISTEN_SECS,
+    );
+    let _ = app.emit("readback-listening", ());
+
+    let reply = match pipeline
+        .listen_for_reply(readback::listen_duration(listen_secs))
+        .await
+    {
+        Ok(transcript) => {
+            log::info!("Read-back: heard {:?}", transcript);
+            readback::parse_confirmation(&transcript)
+        }
+        Err(e) => {
+            log::warn!("Read-back: failed to capture reply ({})", e);
+            readback::ConfirmationReply::Unclear
+        }
+    };
+
+    let reply_str = match reply {
+        readback::ConfirmationReply::Confirm => "confirm",
+        readback::ConfirmationReply::Cancel => "cancel",
+        readback::ConfirmationReply::Unclear => "unclear",
+    };
+    let _ = app.emit("readback-result", serde_json::json!({ "reply": reply_str }));
+
+    reply == readback::ConfirmationReply::Confirm
+}
+
+// ============================================================================
+// Multi-segment dictation sessions (append mode)
+// ============================================================================
+
+/// Session window when append mode is enabled, `None` when it's off.
+#[cfg(desktop)]
+fn get_dictation_session_window(app: &AppHandle) -> Option<Duration> {
+    if !get_setting_from_store(app, "dictation_session_enabled", false) {
+        return None;
+    }
+
+    let secs: f64 = get_setting_from_store(
+        app,
+        "dictation_session_window_secs",
+        session::DEFAULT_SESSION_WINDOW_SECS,
+    );
+    let secs = if secs.is_finite() && secs > 0.0 {
+        secs.min(300.0)
+    } else {
+        session::DEFAULT_SESSION_WINDOW_SECS
+    };
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// Output a transcript into the window that was focused at recording start.
+///
+/// Typing blocks (key delays, waiting for an earlier output's turn), so it runs on the
+/// blocking pool. Tells the UI (`output-redirected-to-clipboard`) when the focus guard
+/// copied the text instead of pasting it into another window.
+#[cfg(desktop)]
+async fn output_transcript(
+    app: &AppHandle,
+    text: &str,
+    output_options: &commands::text::OutputOptions,
+    focus_target: Option<commands::text::FocusTarget>,
+) -> Result<(), String> {
+    let text = text.to_string();
+    let output_options = *output_options;
+    let delivery = tauri::async_runtime::spawn_blocking(move || {
+        commands::text::output_text_restoring_focus(&text, &output_options, focus_target)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if let commands::text::OutputDelivery::CopiedInstead(reason) = delivery {
+        let _ = app.emit("output-redirected-to-clipboard", reason);
+    }
+    Ok(())
+}
+
+/// Correct the text typed in live typing mode to `text`, or erase it when `None`.
+#[cfg(desktop)]
+async fn finish_live_typing(
+    typer: Option<std::sync::Arc<std::sync::Mutex<live_typing::LiveTyper>>>,
+    text: Option<String>,
+) {
+    let Some(typer) = typer else {
+        return;
+    };
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut typer = typer.lock().map_err(|e| e.to_string())?;
+        match text {
+            Some(text) => typer.finish(&text),
+            None => typer.abandon(),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+    if let Err(e) = result {
+        log::error!("Live typing: failed to correct typed text: {}", e);
+    }
+}
+
+/// Add a transcript to the current dictation session and schedule its flush.
+///
+/// The flush waits for `window` after this segment. If a new recording is in flight at that
+/// point, it waits for it to finish: a new segment takes over the flush, while a cancelled or
+/// empty recording lets this timer flush what was collected so far.
+#[cfg(desktop)]
+async fn queue_dictation_session_segment(
+    app: &AppHandle,
+    text: String,
+    window: Duration,
+    output_options: commands::text::OutputOptions,
+    focus_target: Option<commands::text::FocusTarget>,
+) {
+    let Some(session) = app.try_state::<session::DictationSession>() else {
+        if let Err(e) = output_transcript(app, &text, &output_options, focus_target).await {
+            log::error!("Failed to output transcript: {}", e);
+        }
+        return;
+    };
+
+    let generation = session.append(text);
+    let segments = session.segment_count();
+    log::info!("Dictation session: queued segment {}", segments);
+    let _ = app.emit(
+        "dictation-session-updated",
+        serde_json::json!({
+            "segments": segments,
+            "window_secs": window.as_secs_f64(),
+        }),
+    );
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(window).await;
+
+        loop {
+            let busy = app
+                .try_state::<pipeline::SharedPipeline>()
+                .map(|p| {
+                    matches!(
+                        p.state(),
+                        pipeline::PipelineState::Recording
+                            | pipeline::PipelineState::Transcribing
+                            | pipeline::PipelineState::Rewriting
+                    )
+                })
+                .unwrap_or(false);
+            if !busy {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+
+        let Some(segments) = app
+            .try_state::<session::DictationSession>()
+            .and_then(|s| s.take_if_current(generation))
+        else {
+            return;
+        };
+
+        let merged = match app.try_state::<pipeline::SharedPipeline>() {
+            Some(pipeline) => match pipeline.merge_session_segments(&segments).await {
+                Ok((text, outcome)) => {
+                    log::info!(
+                        "Dictation session: merged {} segments ({:?})",
+                        segments.len(),
+                        outcome
+                    );
+                    text
+                }
+                Err(e) => {
+                    log::warn!("Dictation session: merge failed ({}), joining locally", e);
+                    session::join_segments(&segments)
+                }
+            },
+            None => session::join_segments(&segments),
+        };
+
+        let Some(text) = sanitize_transcript(&merged) else {
+            return;
+        };
+
+        let _ = app.emit(
+            "dictation-session-flushed",
+            serde_json::json!({
+                "segments": segments.len(),
+                "text": text,
+            }),
+        );
+
+        if let Err(e) = output_transcript(&app, &text, &output_options, focus_target).await {
+            log::error!("Failed to output dictation session: {}", e);
+        }
+    });
+}
+
 // ============================================================================
 // Escape-to-cancel support
 // ============================================================================
@@ -1045,6 +1645,18 @@ pub(crate) fn cancel_pipeline_session(app: &AppHandle, source: &str) {
     // Clear recording state flags.
     state.is_recording.store(false, Ordering::SeqCst);
     state.toggle_key_held.store(false, Ordering::SeqCst);
+    state.profile_key_held.store(false, Ordering::SeqCst);
+
+    // Erase partials typed for a recording cancelled before it stopped.
+    let live_typer = state
+        .live_typer
+        .lock()
+        .ok()
+        .and_then(|mut typer| typer.take());
+    if live_typer.is_some() {
+        tauri::async_runtime::spawn(finish_live_typing(live_typer, None));
+    }
+    commands::captions::clear_partial_caption(app);
     state.ptt_key_held.store(false, Ordering::SeqCst);
 
     // Restore audio side effects (unmute + resume playback if we paused).
@@ -1127,6 +1739,87 @@ pub(crate) fn cancel_pipeline_session(app: &AppHandle, source: &str) {
     set_escape_cancel_shortcut_enabled(app, false);
 }
 
+/// Stop the current recording and transcribe it, as if the record hotkey had been released.
+///
+/// Used when a recording ends on its own rather than from a shortcut.
+#[cfg(desktop)]
+pub(crate) fn stop_pipeline_session(app: &AppHandle, source: &str) {
+    let is_recording = app
+        .try_state::<pipeline::SharedPipeline>()
+        .map(|p| p.state() == pipeline::PipelineState::Recording)
+        .unwrap_or(false);
+    if !is_recording {
+        return;
+    }
+
+    let state = app.state::<AppState>();
+    let sound_enabled: bool = get_setting_from_store(app, "sound_enabled", true);
+    let audio_cue_raw: String = get_setting_from_store(app, "audio_cue", "tangerine".to_string());
+    let audio_cue = audio::AudioCue::from_str(&audio_cue_raw);
+    let playing_audio_handling: PlayingAudioHandling = get_playing_audio_handling(app);
+    let audio_mute_manager = app.try_state::<AudioMuteManager>();
+
+    stop_recording(
+        app,
+        &state,
+        sound_enabled,
+        audio_cue,
+        &audio_mute_manager,
+        playing_audio_handling,
+        source,
+    );
+}
+
+/// React to an event from the pipeline's capture watcher.
+#[cfg(desktop)]
+fn handle_pipeline_event(app: &AppHandle, event: pipeline::PipelineEvent) {
+    use pipeline::{AutoStopReason, PipelineEvent};
+
+    match event {
+        PipelineEvent::InputDeviceSwitched { lost, switched_to } => {
+            emit_system_event(
+                app,
+                "audio",
+                &format!("Input device '{}' lost; recording continues on '{}'", lost, switched_to),
+                None,
+            );
+            let _ = app.emit(
+                "audio-device-switched",
+                serde_json::json!({ "lost": lost, "switched_to": switched_to }),
+            );
+        }
+        PipelineEvent::PartialTranscript(text) => {
+            commands::captions::publish_caption(app, &text, false);
+        }
+        PipelineEvent::AutoStop(AutoStopReason::SpeechEnd) => {
+            emit_system_event(app, "audio", "Speech ended", Some("VAD auto-stop"));
+            stop_pipeline_session(app, "VAD auto-stop");
+        }
+        PipelineEvent::AutoStop(AutoStopReason::MaxDuration) => {
+            emit_system_event(
+                app,
+                "audio",
+                "Recording reached its maximum duration",
+                Some("Stopping and transcribing"),
+            );
+            let _ = app.emit("recording-max-duration-reached", ());
+            stop_pipeline_session(app, "Max duration");
+        }
+        PipelineEvent::AutoStop(AutoStopReason::DeviceLost(device)) => {
+            emit_system_event(
+                app,
+                "error",
+                &format!("Input device '{}' lost", device),
+                Some("No replacement device; the recording fails"),
+            );
+            // The pipeline reports `AudioCaptureError::DeviceLost` through the usual
+            // pipeline-error path.
+            stop_pipeline_session(app, "Device lost");
+        }
+        _ => {}
+    }
+}
+
 /// Handle a shortcut event - public so it can be called from commands/settings.rs
 #[cfg(desktop)]
 pub fn handle_shortcut_event(app: &AppHandle, shortcut: &Shortcut, event: &ShortcutEvent) {
@@ -1177,6 +1870,58 @@ pub fn handle_shortcut_event(app: &AppHandle, shortcut: &Shortcut, event: &Short
     let is_hold = shortcut_str == hold_shortcut_str;
     let is_paste_last = shortcut_str == paste_last_shortcut_str;
 
+    if !is_toggle && !is_hold && !is_paste_last {
+        let profile_id = profile_shortcuts(app, &[])
+            .into_iter()
+            .find(|(_, s)| normalize_shortcut_string(&s.to_string()) == shortcut_str)
+            .map(|(id, _)| id);
+        if let Some(profile_id) = profile_id {
+            // Like Toggle (on release), but recording with the hotkey's profile.
+            match event.state {
+                ShortcutState::Pressed => {
+                    state.profile_key_held.swap(true, Ordering::SeqCst);
+                }
+                ShortcutState::Released => {
+                    if !state.profile_key_held.swap(false, Ordering::SeqCst) {
+                        return;
+                    }
+                    let pipeline_state = app
+                        .try_state::<pipeline::SharedPipeline>()
+                        .map(|p| p.state());
+                    log::info!(
+                        "Profile hotkey released ({}): pipeline state = {:?}",
+                        profile_id,
+                        pipeline_state
+                    );
+
+                    if pipeline_state == Some(pipeline::PipelineState::Recording) {
+                        stop_recording(
+                            app,
+                            &state,
+                            sound_enabled,
+                            audio_cue,
+                            &audio_mute_manager,
+                            playing_audio_handling,
+                            "Profile",
+                        );
+                    } else {
+                        start_recording(
+                            app,
+                            &state,
+                            sound_enabled,
+                            audio_cue,
+                            &audio_mute_manager,
+                            playing_audio_handling,
+                            "Profile",
+                            Some(profile_id),
+                        );
+                    }
+                }
+            }
+            return;
+        }
+    }
+
     if is_toggle {
         // Toggle mode: action happens on key release (debounced)
         match event.state {
@@ -1214,6 +1959,7 @@ pub fn handle_shortcut_event(app: &AppHandle, shortcut: &Shortcut, event: &Short
                             &audio_mute_manager,
                             playing_audio_handling,
                             "Toggle",
+                            None,
                         );
                     }
                 }
@@ -1245,6 +1991,7 @@ pub fn handle_shortcut_event(app: &AppHandle, shortcut: &Shortcut, event: &Short
                             &audio_mute_manager,
                             playing_audio_handling,
                             "Hold",
+                            None,
                         );
                     }
                 }
@@ -1288,14 +2035,23 @@ pub fn handle_shortcut_event(app: &AppHandle, shortcut: &Shortcut, event: &Short
                     let output_mode = commands::text::OutputMode::from_str(&output_mode_str);
 
                     let output_hit_enter: bool = get_setting_from_store(app, "output_hit_enter", false);
+                    let output_typing = commands::text::typing_config(app);
 
                     let history_storage = app.state::<HistoryStorage>();
 
                     if let Ok(entries) = history_storage.get_all(Some(1)) {
-                        if let Some(entry) = entries.first() {
-                            if let Err(e) = commands::text::output_text_with_mode(&entry.text, output_mode, output_hit_enter) {
-                                log::error!("Failed to output last transcription: {}", e);
-                            }
+                        if let Some(entry) = entries.into_iter().next() {
+                            // Off the shortcut handler's thread: typing sleeps between keys.
+                            tauri::async_runtime::spawn_blocking(move || {
+                                if let Err(e) = commands::text::output_text_with_mode(
+                                    &entry.text,
+                                    output_mode,
+                                    output_hit_enter,
+                                    &output_typing,
+                                ) {
+                                    log::error!("Failed to output last transcription: {}", e);
+                                }
+                            });
                         } else {
                             log::info!("OutputLast: no history entries available");
                         }
@@ -1316,8 +2072,8 @@ fn is_audio_mute_supported() -> bool {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // Initialize logger (log files are opened in setup, once the app data dir is known)
+    app_log::init();
 
     let mut builder = tauri::Builder::default();
 
@@ -1339,26 +2095,60 @@ pub fn run() {
             commands::audio::play_audio_cue_preview,
             commands::audio::list_audio_input_devices,
             commands::audio::get_default_audio_input_device_name,
+            commands::audio::run_audio_diagnostics,
             commands::text::type_text,
             commands::text::get_server_url,
+            commands::text::get_injection_backend,
+            commands::text::get_output_queue,
             commands::settings::register_shortcuts,
             commands::settings::unregister_shortcuts,
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::settings::save_settings,
+            commands::settings::export_settings,
+            commands::settings::import_settings,
             is_audio_mute_supported,
             commands::history::add_history_entry,
             commands::history::get_history,
             commands::history::delete_history_entry,
             commands::history::clear_history,
+            commands::history::get_dictation_stats,
+            commands::history::search_history,
+            commands::history::annotate_history_entry,
+            commands::history::get_history_tags,
+            commands::history::search_transcripts,
+            commands::digest::export_daily_digest,
+            commands::audit::query_output_audit,
+            commands::audit::export_output_audit,
+            commands::models::list_provider_models,
+            commands::encryption::get_encryption_at_rest,
+            commands::encryption::get_encryption_key_error,
+            commands::encryption::set_encryption_at_rest,
             commands::overlay::resize_overlay,
             commands::overlay::show_overlay,
             commands::overlay::hide_overlay,
             commands::overlay::set_overlay_mode,
             commands::overlay::set_widget_position,
+            commands::captions::show_captions_window,
+            commands::captions::hide_captions_window,
+            commands::captions::get_captions_style,
+            commands::captions::set_captions_style,
+            commands::review::get_pending_reviews,
+            commands::review::confirm_review,
+            commands::review::discard_review,
             // Pipeline commands for all-in-app STT
             commands::recording::pipeline_start_recording,
+            commands::recording::pipeline_start_external_recording,
+            commands::recording::pipeline_start_file_recording,
+            commands::recording::pipeline_push_audio_frames,
             commands::recording::pipeline_stop_and_transcribe,
             commands::recording::pipeline_cancel,
             commands::recording::pipeline_get_state,
             commands::recording::pipeline_is_recording,
+            commands::recording::pipeline_get_network_status,
+            commands::recording::pipeline_list_jobs,
+            commands::recording::pipeline_cancel_job,
+            commands::recording::pipeline_clear_stt_cache,
             commands::recording::pipeline_is_error,
             commands::recording::pipeline_update_config,
             commands::recording::pipeline_dictate,
@@ -1366,6 +2156,7 @@ pub fn run() {
             commands::recording::pipeline_force_reset,
             commands::recording::pipeline_test_transcribe_last_audio,
             commands::recording::pipeline_has_last_audio,
+            commands::recording::test_stt_provider,
             commands::recording::pipeline_get_last_recording_diagnostics,
             commands::recording::pipeline_test_audio_settings_start_recording,
             commands::recording::pipeline_test_audio_settings_stop_recording,
@@ -1377,6 +2168,12 @@ pub fn run() {
             commands::recording::recordings_open_folder,
             commands::recording::recordings_get_storage_bytes,
             commands::recording::recordings_get_stats,
+            commands::recording::recordings_get_storage_mode,
+            commands::recording::recordings_set_storage_mode,
+            commands::recording::get_orphaned_recordings,
+            commands::recording::recover_orphaned_recording,
+            commands::recording::discard_orphaned_recording,
+            commands::debug_bundle::export_debug_bundle,
             // Config commands (replacing Python server)
             commands::config::get_default_sections,
             commands::config::get_available_providers,
@@ -1384,6 +2181,7 @@ pub fn run() {
             // VAD settings commands
             commands::config::get_vad_settings,
             commands::config::set_vad_settings,
+            commands::config::calibrate_microphone,
             // LLM formatting commands
             commands::llm::get_llm_default_prompts,
             commands::llm::get_llm_providers,
@@ -1391,6 +2189,9 @@ pub fn run() {
             commands::llm::update_llm_prompts,
             commands::llm::get_llm_config,
             commands::llm::test_llm_rewrite,
+            commands::llm::compare_prompts,
+            commands::eval::run_stt_eval,
+            commands::llm::test_llm_provider,
             commands::llm::llm_complete,
             // Local Whisper model management commands
             commands::whisper::is_local_whisper_available,
@@ -1400,19 +2201,81 @@ pub fn run() {
             commands::whisper::get_whisper_model_url,
             commands::whisper::delete_whisper_model,
             commands::whisper::validate_whisper_model,
+            commands::whisper::prefetch_whisper_model,
+            commands::whisper::pause_model_prefetch,
+            commands::whisper::resume_model_prefetch,
+            commands::whisper::cancel_model_prefetch,
+            commands::whisper::get_model_prefetch_status,
+            commands::vosk::is_local_vosk_available,
+            commands::vosk::get_vosk_models,
+            commands::vosk::download_vosk_model,
+            commands::vosk::delete_vosk_model,
             // Request logging commands
             commands::logs::get_request_logs,
             commands::logs::clear_request_logs,
+            commands::logs::get_pipeline_metrics,
+            commands::logs::clear_pipeline_metrics,
+            commands::logs::get_app_log_tail,
+            commands::logs::set_log_level,
+            // Control API token management
+            commands::api_tokens::list_api_tokens,
+            commands::api_tokens::create_api_token,
+            commands::api_tokens::revoke_api_token,
+            commands::api_tokens::delete_api_token,
+            commands::api_tokens::get_api_audit_log,
+            // OS permission status
+            commands::permissions::get_permission_status,
+            commands::permissions::open_permission_settings,
             // Window/process commands (used for per-program prompts)
             commands::windows::list_open_windows,
             commands::windows::get_foreground_process_path,
         ])
         .setup(|app| {
+            if let Ok(dir) = app.path().app_data_dir() {
+                if let Err(e) = app_log::open_files(&dir) {
+                    log::warn!("{}", e);
+                }
+            }
+
             // Seed defaults into settings.json so UI and backend agree on effective settings.
             // Must run before pipeline initialization and any settings reads.
             #[cfg(desktop)]
             {
+                // The store plugin silently falls back to defaults when settings.json
+                // doesn't parse, so restore the last good copy before it loads.
+                let settings_path = app.path().app_data_dir().ok().map(|dir| dir.join("settings.json"));
+                if let Some(path) = &settings_path {
+                    let _ = persist::read_with_recovery::<serde_json::Value>(path);
+                }
+                app_settings::open_store(app.handle())?;
+
                 ensure_default_settings(app.handle())?;
+
+                let log_level: String = get_setting_from_store(
+                    app.handle(),
+                    "log_level",
+                    app_log::DEFAULT_LOG_LEVEL.to_string(),
+                );
+                if let Err(e) = app_log::set_level(&log_level) {
+                    log::warn!("Invalid log_level setting: {}", e);
+                }
+
+                if let Some(path) = &settings_path {
+                    if let Err(e) = persist::refresh_backup(path) {
+                        log::warn!("Failed to back up settings.json: {}", e);
+                    }
+                }
+            }
+
+            permissions::log_missing();
+
+            // Encryption at rest has to be configured before any store loads its files.
+            #[cfg(desktop)]
+            if get_setting_from_store(app.handle(), "encrypt_at_rest", false) {
+                match at_rest::load_or_create_cipher() {
+                    Ok(cipher) => at_rest::configure(Some(cipher), true),
+                    Err(e) => at_rest::set_key_unavailable(e),
+                }
             }
 
             // Initialize history storage
@@ -1423,11 +2286,80 @@ pub fn run() {
 
             // Initialize recording store (saved WAVs for retry)
             let recording_store = RecordingStore::new(app_data_dir.clone());
+            #[cfg(desktop)]
+            recording_store.set_storage_mode(get_setting_from_store(
+                app.handle(),
+                "recording_storage_mode",
+                recordings::RecordingStorageMode::default(),
+            ));
+            // Audio of running transcriptions, and of those the last run didn't finish
+            recovery::global().load(
+                &app_data_dir,
+                recording_store.storage_mode() != recordings::RecordingStorageMode::Never,
+            );
             app.manage(recording_store);
 
-            let history_storage = HistoryStorage::new(app_data_dir);
+            // Stage timings of past requests (fed by the request log store)
+            pipeline_metrics::global().load(app_data_dir.clone());
+
+            // Let the UI know when a paste couldn't put the user's clipboard back.
+            let clipboard_warning_app = app.handle().clone();
+            commands::text::on_clipboard_restore_skipped(move |reason| {
+                let _ = clipboard_warning_app.emit("clipboard-restore-skipped", reason);
+            });
+
+            // Scoped tokens for the local control API
+            app.manage(api_tokens::ApiTokenStore::new(app_data_dir.clone()));
+
+            // Append-only audit of every typed/pasted/copied output
+            let output_audit_log = output_audit::OutputAuditLog::new(app_data_dir.clone());
+            output_audit::install(output_audit_log.clone());
+            app.manage(output_audit_log);
+
+            // Provider model lists for the settings dropdowns (cached across restarts)
+            app.manage(model_catalog::ModelCatalog::new(app_data_dir.clone()));
+
+            // Background Whisper model downloads (resumes a queue left from the last run)
+            #[cfg(desktop)]
+            {
+                let models_dir = commands::whisper::get_models_dir(app.handle())
+                    .unwrap_or_else(|_| app_data_dir.join("whisper-models"));
+                let prefetcher = std::sync::Arc::new(model_prefetch::ModelPrefetcher::new(
+                    app_data_dir.clone(),
+                    models_dir,
+                ));
+                model_prefetch::spawn(app.handle().clone(), prefetcher.clone());
+                app.manage(prefetcher);
+            }
+
+            let history_storage = HistoryStorage::new(app_data_dir.clone());
             app.manage(history_storage);
 
+            // Full-text transcript search, re-synced whenever history changes
+            match search_index::SearchIndex::open(&app_data_dir) {
+                Ok(index) => {
+                    app.manage(index);
+                    commands::history::sync_search_index(app.handle());
+                    let handle = app.handle().clone();
+                    app.listen("history-changed", move |_| {
+                        commands::history::sync_search_index(&handle);
+                    });
+                }
+                Err(e) => log::warn!("Transcript search unavailable: {}", e),
+            }
+            app.manage(session::DictationSession::new());
+            app.manage(commands::review::ReviewQueue::new());
+
+            // Seal anything still in plaintext (files from before encryption was switched on).
+            if at_rest::is_enabled() {
+                let handle = app.handle().clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = at_rest::migrate(&handle) {
+                        log::warn!("At-rest migration failed: {}", e);
+                    }
+                });
+            }
+
             // Apply the configured history retention limit immediately so existing installs
             // don't keep more entries than the UI/backend intend.
             #[cfg(desktop)]
@@ -1439,6 +2371,9 @@ pub fn run() {
                 }
             }
 
+            // Same for saved recordings, and keep checking (age limits) while the app runs.
+            commands::recording::spawn_recording_retention_sweep(app.handle().clone());
+
             // Initialize request log store
             #[cfg(desktop)]
             {
@@ -1480,6 +2415,55 @@ pub fn run() {
                 app.manage(request_log_store);
             }
 
+            #[cfg(desktop)]
+            match get_setting_from_store(
+                app.handle(),
+                "metrics_exporter",
+                metrics::DEFAULT_METRICS_EXPORTER.to_string(),
+            )
+            .as_str()
+            {
+                "prometheus" => {
+                    let port: u16 =
+                        get_setting_from_store(app.handle(), "metrics_port", metrics::DEFAULT_METRICS_PORT);
+                    if let Err(e) = metrics::spawn_server(port) {
+                        log::warn!("Failed to start metrics endpoint on port {}: {}", port, e);
+                    }
+                }
+                "otlp" => {
+                    let endpoint = get_setting_from_store::<Option<String>>(
+                        app.handle(),
+                        "metrics_otlp_endpoint",
+                        None,
+                    )
+                    .filter(|endpoint| !endpoint.trim().is_empty())
+                    .unwrap_or_else(|| metrics::DEFAULT_OTLP_ENDPOINT.to_string());
+                    tauri::async_runtime::spawn(metrics::run_otlp_exporter(endpoint));
+                }
+                _ => {}
+            }
+
+            #[cfg(desktop)]
+            if get_setting_from_store(app.handle(), "ipc_output_enabled", false) {
+                let endpoint = get_setting_from_store::<Option<String>>(app.handle(), "ipc_output_path", None)
+                    .filter(|path| !path.trim().is_empty())
+                    .unwrap_or_else(|| ipc_output::default_endpoint(&app_data_dir));
+                let app_handle = app.handle().clone();
+                let authorize: ipc_output::Authorize = std::sync::Arc::new(move |token| {
+                    commands::api_tokens::authorize_api_request(
+                        &app_handle,
+                        token,
+                        api_tokens::ApiScope::ReadTranscripts,
+                        "ipc_output.subscribe",
+                    )
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+                });
+                if let Err(e) = ipc_output::spawn_server(endpoint.clone(), authorize) {
+                    log::warn!("Failed to start IPC output on {}: {}", endpoint, e);
+                }
+            }
+
             // Initialize audio mute manager (may be None on unsupported platforms)
             if let Some(audio_mute_manager) = AudioMuteManager::new() {
                 app.manage(audio_mute_manager);
@@ -1489,7 +2473,15 @@ pub fn run() {
             #[cfg(desktop)]
             {
                 let pipeline = initialize_pipeline_from_settings(app.handle());
+                let recording_store = app.state::<RecordingStore>();
+                pipeline.set_stt_cache_path(
+                    (recording_store.storage_mode() != recordings::RecordingStorageMode::Never)
+                        .then(|| recording_store.stt_cache_path()),
+                );
                 app.manage(pipeline);
+                app.manage(deferred_format::DeferredFormatQueue::spawn(app.handle().clone()));
+                network::spawn(app.handle().clone());
+                tauri::async_runtime::spawn(watchdog::run(app.handle().clone()));
             }
 
             // Backend-driven overlay waveform: publish realtime mic levels to the overlay.
@@ -1592,6 +2584,14 @@ pub fn run() {
                 });
             }
 
+            // End recordings on VAD auto-stop, max duration or a lost input device.
+            #[cfg(desktop)]
+            {
+                let app_handle = app.handle().clone();
+                app.state::<pipeline::SharedPipeline>()
+                    .spawn_capture_watcher(move |event| handle_pipeline_event(&app_handle, event));
+            }
+
             // Register shortcuts from store (now that store plugin is available)
             #[cfg(desktop)]
             {
@@ -1648,45 +2648,16 @@ pub fn run() {
                 }
             }
 
-            // Position overlay based on saved setting
-            if let Ok(Some(monitor)) = overlay.current_monitor() {
-                let size = monitor.size();
-                let scale = monitor.scale_factor();
-                let screen_width = size.width as f64 / scale;
-                let screen_height = size.height as f64 / scale;
-
+            // Position overlay based on saved setting, on the monitor the user is working on.
+            {
                 // Estimate initial widget size (before content loads). The frontend will
                 // auto-resize after mount, but using a closer estimate prevents off-screen drift.
-                let window_width = 264.0;
-                let window_height = 56.0;
-                let margin = 50.0;
-
-                let widget_position: String = get_setting_from_store(
-                    app.handle(),
-                    "widget_position",
-                    "bottom-center".to_string(),
-                );
-
-                let (x, y) = match widget_position.as_str() {
-                    "top-left" => (margin, margin),
-                    "top-center" => ((screen_width - window_width) / 2.0, margin),
-                    "top-right" => (screen_width - window_width - margin, margin),
-                    "center" => (
-                        (screen_width - window_width) / 2.0,
-                        (screen_height - window_height) / 2.0,
-                    ),
-                    "bottom-left" => (margin, screen_height - window_height - margin),
-                    "bottom-center" => (
-                        (screen_width - window_width) / 2.0,
-                        screen_height - window_height - margin,
-                    ),
-                    _ => ( // "bottom-right" or unknown
-                        screen_width - window_width - margin,
-                        screen_height - window_height - margin,
-                    ),
-                };
+                let scale = overlay.scale_factor().unwrap_or(1.0);
+                let estimated_size = (264.0 * scale, 56.0 * scale);
 
-                let _ = overlay.set_position(tauri::Position::Logical(tauri::LogicalPosition { x, y }));
+                if let Some(position) = placement::window_position(app.handle(), estimated_size, None) {
+                    let _ = overlay.set_position(tauri::Position::Physical(position));
+                }
             }
 
             // Set initial overlay visibility based on saved settings
@@ -1703,6 +2674,12 @@ pub fn run() {
                     }
                     _ => {} // "always" - keep visible (default)
                 }
+
+                if get_setting_from_store(app.handle(), "captions_enabled", false) {
+                    if let Err(e) = commands::captions::show_captions_window_impl(app.handle()) {
+                        log::warn!("Failed to open captions window: {}", e);
+                    }
+                }
             }
 
             // Setup system tray
@@ -1710,8 +2687,18 @@ pub fn run() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // The store plugin saves every store in place on `Exit`; settings.json is saved
+            // atomically and closed before that.
+            #[cfg(desktop)]
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                app_settings::close_store(app);
+            }
+            #[cfg(not(desktop))]
+            let _ = (app, event);
+        });
 }
 
 fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
@@ -1783,6 +2770,23 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
     use std::time::Duration;
     use std::collections::HashMap;
 
+    // Network settings first: providers capture the shared HTTP client when created.
+    let network_config: http::NetworkConfig =
+        get_setting_from_store(app, "network_config", http::NetworkConfig::default());
+    if let Err(e) = http::configure(network_config) {
+        log::error!("Network settings not applied: {}", e);
+    }
+    rate_limit::configure(get_setting_from_store(
+        app,
+        "rate_limit",
+        rate_limit::RateLimitConfig::default(),
+    ));
+    watchdog::configure(get_setting_from_store(
+        app,
+        "pipeline_watchdog",
+        watchdog::WatchdogConfig::default(),
+    ));
+
     // Read STT settings from store
     let stt_provider: String = get_setting_from_store(app, "stt_provider", "groq".to_string());
 
@@ -1792,6 +2796,33 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
     // Read global STT transcription prompt from store
     let stt_transcription_prompt: Option<String> =
         get_setting_from_store(app, "stt_transcription_prompt", None);
+    let stt_vocabulary_boost: bool = get_setting_from_store(app, "stt_vocabulary_boost", true);
+    let stt_context_secs: u32 = get_setting_from_store::<u32>(app, "stt_context_secs", 0)
+        .min(pipeline::MAX_STT_CONTEXT_SECS);
+    let stt_language: Option<String> = get_setting_from_store(app, "stt_language", None);
+    let whisper_server_url: Option<String> =
+        get_setting_from_store::<Option<String>>(app, "whisper_server_url", None)
+            .filter(|url| !url.trim().is_empty());
+    let stt_extra_params: HashMap<String, stt::ExtraParams> =
+        get_setting_from_store(app, "stt_extra_params", HashMap::new());
+    let stt_temperature: Option<f32> =
+        get_setting_from_store::<Option<f32>>(app, "stt_temperature", None)
+            .map(|t| t.clamp(0.0, 1.0));
+    let stt_verbose_json: bool = get_setting_from_store(app, "stt_verbose_json", false);
+    #[cfg(feature = "local-whisper")]
+    let whisper_decoding: stt::WhisperDecodingParams =
+        get_setting_from_store(app, "whisper_decoding", stt::WhisperDecodingParams::default())
+            .sanitized();
+    #[cfg(feature = "local-whisper")]
+    let whisper_threading: stt::WhisperThreading =
+        get_setting_from_store(app, "whisper_threading", stt::WhisperThreading::default());
+    #[cfg(feature = "local-vosk")]
+    let vosk_model: Option<String> = get_setting_from_store(app, "vosk_model", None);
+
+    // Read fallback STT provider from store (empty = none)
+    let fallback_stt_provider: Option<String> =
+        get_setting_from_store::<Option<String>>(app, "fallback_stt_provider", None)
+            .filter(|p| !p.trim().is_empty());
 
     // Read STT timeout from store (seconds)
     let stt_timeout_seconds_raw: f64 = get_setting_from_store(app, "stt_timeout_seconds", 10.0);
@@ -1807,7 +2838,7 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
 
     // Read all available STT API keys (for per-profile provider overrides at runtime)
     let mut stt_api_keys: HashMap<String, String> = HashMap::new();
-    for provider in ["openai", "groq", "deepgram"] {
+    for provider in ["openai", "groq", "deepgram", "speechmatics"] {
         let key_name = format!("{}_api_key", provider);
         let key: String = get_setting_from_store(app, &key_name, String::new());
         if !key.is_empty() {
@@ -1820,6 +2851,7 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
         "openai" => get_setting_from_store(app, "openai_api_key", String::new()),
         "groq" => get_setting_from_store(app, "groq_api_key", String::new()),
         "deepgram" => get_setting_from_store(app, "deepgram_api_key", String::new()),
+        "speechmatics" => get_setting_from_store(app, "speechmatics_api_key", String::new()),
         _ => String::new(),
     };
 
@@ -1937,12 +2969,61 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
         "audio_noise_suppression_enabled",
         default_pipeline_config.audio_noise_suppression_enabled,
     );
+    let audio_trim_silence: bool = get_setting_from_store(
+        app,
+        "audio_trim_silence",
+        default_pipeline_config.audio_trim_silence,
+    );
+    let audio_max_pause_secs: Option<f32> = {
+        let raw: Option<f32> = get_setting_from_store(app, "audio_max_pause_secs", None);
+        raw.filter(|v| v.is_finite() && *v > 0.0)
+    };
+    let identifier_casing_enabled: bool = get_setting_from_store(
+        app,
+        "identifier_casing_enabled",
+        default_pipeline_config.identifier_casing_enabled,
+    );
+    let learned_corrections: Vec<corrections::LearnedCorrection> =
+        get_setting_from_store(app, "learned_corrections", Vec::new());
+    let deferred_llm_formatting: bool = get_setting_from_store(
+        app,
+        "deferred_llm_formatting",
+        default_pipeline_config.deferred_llm_formatting,
+    );
+    // Slow local models (e.g. large Ollama models) may need a much longer timeout.
+    let llm_timeout_secs: u64 = get_setting_from_store(
+        app,
+        "llm_timeout_secs",
+        default_pipeline_config.llm_config.timeout.as_secs(),
+    );
+    let redaction: redaction::RedactionConfig =
+        get_setting_from_store(app, "pii_redaction", default_pipeline_config.redaction.clone());
+    let output_hook: hooks::HookConfig =
+        get_setting_from_store(app, "output_hook", default_pipeline_config.output_hook.clone());
+    let provider_plugins: Vec<plugins::ProviderPlugin> =
+        plugins::from_setting(Some(get_setting_from_store(app, "provider_plugins", serde_json::json!([]))));
+    // Debug: scripted `mock` STT/LLM providers.
+    let mock_providers_enabled: bool = get_setting_from_store(app, "mock_providers_enabled", false);
+    let mock_providers: Option<mock::MockProviders> = mock_providers_enabled
+        .then(|| get_setting_from_store(app, "mock_providers", mock::MockProviders::default()));
+    let llm_max_retries: u32 = get_setting_from_store(
+        app,
+        "llm_max_retries",
+        default_pipeline_config.llm_config.retry_config.max_retries,
+    );
 
     let quiet_audio_require_speech: bool = get_setting_from_store(
         app,
         "quiet_audio_require_speech",
         default_pipeline_config.quiet_audio_require_speech,
     );
+    let hallucination_filter_enabled: bool = get_setting_from_store(
+        app,
+        "hallucination_filter_enabled",
+        default_pipeline_config.hallucination_filter_enabled,
+    );
+    let hallucination_filter_phrases: Vec<String> =
+        get_setting_from_store(app, "hallucination_filter_phrases", Vec::new());
 
     // Read LLM settings from store
     let rewrite_llm_enabled: bool = get_setting_from_store(app, "rewrite_llm_enabled", false);
@@ -1959,6 +3040,9 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
         get_setting_from_store(app, "gemini_thinking_level", None);
     let anthropic_thinking_budget: Option<i64> =
         get_setting_from_store(app, "anthropic_thinking_budget", None);
+    let llm_generation: llm::GenerationParams =
+        get_setting_from_store(app, "llm_generation", llm::GenerationParams::default())
+            .sanitized();
 
     // If the user never explicitly selected a model, treat "default" as the provider's
     // concrete default model so request logs can display the exact model used.
@@ -2009,26 +3093,8 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
 
     let rewrite_program_prompt_profiles: Vec<settings::RewriteProgramPromptProfile> =
         get_setting_from_store(app, "rewrite_program_prompt_profiles", Vec::new());
-
-    let program_prompt_profiles: Vec<llm::ProgramPromptProfile> = rewrite_program_prompt_profiles
-        .into_iter()
-        .map(|p| llm::ProgramPromptProfile {
-            id: p.id,
-            name: p.name,
-            program_paths: p.program_paths,
-            prompts: p
-                .cleanup_prompt_sections
-                .as_ref()
-                .map(|o| o.apply_to(&base_prompts))
-                .unwrap_or_else(|| base_prompts.clone()),
-            rewrite_llm_enabled: p.rewrite_llm_enabled,
-            stt_provider: p.stt_provider,
-            stt_model: p.stt_model,
-            stt_timeout_seconds: p.stt_timeout_seconds,
-            llm_provider: p.llm_provider,
-            llm_model: p.llm_model,
-        })
-        .collect();
+    let quality_tiers: settings::QualityTiers =
+        get_setting_from_store(app, "quality_tiers", settings::QualityTiers::default());
 
     // Microphone selection (backend / CPAL).
     // Historical key name is `selected_mic_id` (originally from browser deviceId).
@@ -2045,6 +3111,53 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
         })
     };
 
+    let stop_at_max_duration: bool = get_setting_from_store(app, "stop_at_max_duration", false);
+
+    let noise_profiles: HashMap<String, vad::NoiseProfile> =
+        get_setting_from_store(app, "noise_profiles", HashMap::new());
+
+    let preroll_secs_raw: f64 = get_setting_from_store(app, "preroll_secs", 0.0);
+    let preroll_secs = if preroll_secs_raw.is_finite() {
+        (preroll_secs_raw as f32).clamp(0.0, pipeline::MAX_PREROLL_SECS)
+    } else {
+        0.0
+    };
+
+    let noise_profile = noise_profiles.get(settings::noise_profile_key(input_device_name.as_deref()));
+    let vad_config = vad_settings.to_vad_auto_stop_config(noise_profile);
+
+    let program_prompt_profiles: Vec<llm::ProgramPromptProfile> = rewrite_program_prompt_profiles
+        .into_iter()
+        .map(|p| {
+            let models = p.resolved_models(&quality_tiers);
+            llm::ProgramPromptProfile {
+                id: p.id,
+                name: p.name,
+                program_paths: p.program_paths,
+                prompts: p
+                    .cleanup_prompt_sections
+                    .as_ref()
+                    .map(|o| o.apply_to(&base_prompts))
+                    .unwrap_or_else(|| base_prompts.clone()),
+                rewrite_llm_enabled: p.rewrite_llm_enabled,
+                stt_provider: models.stt_provider,
+                stt_model: models.stt_model,
+                stt_timeout_seconds: p.stt_timeout_seconds,
+                llm_provider: models.llm_provider,
+                llm_model: models.llm_model,
+                confirm_by_voice: p.confirm_by_voice.unwrap_or(false),
+                redact_pii: p.redact_pii,
+                output_format: p.output_format,
+                webhook_url: p.webhook_url,
+                output_mode: p.output_mode,
+                vad_config: p
+                    .vad_settings
+                    .as_ref()
+                    .map(|v| v.to_vad_auto_stop_config(noise_profile)),
+            }
+        })
+        .collect();
+
     let config = pipeline::PipelineConfig {
         input_device_name,
         stt_provider,
@@ -2052,9 +3165,19 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
         stt_api_keys,
         stt_model,
         stt_transcription_prompt,
+        stt_vocabulary_boost,
+        stt_context_secs,
+        stt_language,
+        whisper_server_url,
+        stt_extra_params,
+        stt_temperature,
+        stt_verbose_json,
+        fallback_stt_provider,
         max_duration_secs: 300.0,
-        retry_config: stt::RetryConfig::default(),
-        vad_config: vad_settings.to_vad_auto_stop_config(),
+        stop_at_max_duration,
+        preroll_secs,
+        retry_config: retry::RetryConfig::default(),
+        vad_config,
         transcription_timeout: Duration::from_secs_f64(stt_timeout_seconds),
         max_recording_bytes: 50 * 1024 * 1024, // 50MB
 
@@ -2070,8 +3193,14 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
         audio_highpass_enabled,
         audio_agc_enabled,
         audio_noise_suppression_enabled,
+        audio_trim_silence,
+        audio_max_pause_secs,
+        identifier_casing_enabled,
+        learned_corrections,
 
         quiet_audio_require_speech,
+        hallucination_filter_enabled,
+        hallucination_filter_phrases,
 
         llm_config: llm::LlmConfig {
             enabled: llm_enabled,
@@ -2082,14 +3211,33 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
             gemini_thinking_budget,
             gemini_thinking_level,
             anthropic_thinking_budget,
+            generation: llm_generation,
             prompts: base_prompts,
             program_prompt_profiles,
+            timeout: std::time::Duration::from_secs(llm_timeout_secs.max(1)),
+            retry_config: retry::RetryConfig {
+                max_retries: llm_max_retries,
+                ..default_pipeline_config.llm_config.retry_config.clone()
+            },
             ..Default::default()
         },
         llm_api_keys,
+        deferred_llm_formatting,
+        redaction,
+        output_hook,
 
         // Allow providers to enrich the active RequestLog with request/response payloads.
         request_log_store: app.try_state::<RequestLogStore>().map(|s| s.inner().clone()),
+        mock_providers,
+        provider_plugins,
+        #[cfg(feature = "local-whisper")]
+        whisper_model_path: None,
+        #[cfg(feature = "local-whisper")]
+        whisper_decoding,
+        #[cfg(feature = "local-whisper")]
+        whisper_threading,
+        #[cfg(feature = "local-vosk")]
+        vosk_model_path: commands::vosk::selected_model_path(app, vosk_model.as_deref()),
     };
 
     log::info!(
@@ -2098,7 +3246,22 @@ fn initialize_pipeline_from_settings(app: &AppHandle) -> pipeline::SharedPipelin
         config.vad_config.enabled
     );
 
-    pipeline::SharedPipeline::new(config)
+    pipeline::SharedPipeline::with_host(config, std::sync::Arc::new(AppPipelineHost))
+}
+
+/// Runs pipeline tasks on Tauri's runtime and reports the foreground app to it.
+#[cfg(desktop)]
+struct AppPipelineHost;
+
+#[cfg(desktop)]
+impl pipeline::PipelineHost for AppPipelineHost {
+    fn runtime(&self) -> tokio::runtime::Handle {
+        tauri::async_runtime::handle().inner().clone()
+    }
+
+    fn foreground_app_path(&self) -> Option<String> {
+        windows_apps::get_foreground_process_path()
+    }
 }
 
 /// Register shortcuts from store settings (called from setup() after store plugin is available)
@@ -2127,7 +3290,8 @@ fn register_initial_shortcuts(app: &AppHandle) -> Result<(), Box<dyn std::error:
         paste_last_hotkey.to_shortcut_string()
     );
 
-    let shortcuts: Vec<Shortcut> = vec![toggle_shortcut, hold_shortcut, paste_last_shortcut];
+    let mut shortcuts: Vec<Shortcut> = vec![toggle_shortcut, hold_shortcut, paste_last_shortcut];
+    shortcuts.extend(profile_shortcuts(app, &shortcuts).into_iter().map(|(_, s)| s));
 
     app.global_shortcut()
         .on_shortcuts(shortcuts, |app, shortcut, event| {
@@ -0,0 +1,156 @@
+//! Live typing (`OutputMode::LiveTyping`).
+//!
+//! While recording, partial transcripts are typed into the focused field; once the final
+//! transcript is in, the typed text is corrected to match it. There is no streaming STT
+//! provider, so partials come from re-transcribing the growing recording every
+//! `PARTIAL_INTERVAL` (see `SharedPipeline::transcribe_partial`).
+//!
+//! Each update only touches the tail that changed: the typed characters past the common
+//! prefix are erased with Backspace and the rest of the new text is pasted.
+
+use crate::commands::text::{replace_typed_tail, TypingConfig};
+use crate::pipeline::{PipelineState, SharedPipeline};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Time between partial transcriptions.
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Text typed for one recording.
+pub struct LiveTyper {
+    typed: String,
+    typing: TypingConfig,
+    /// Set when the recording stopped: later partials are stale.
+    closed: bool,
+}
+
+impl LiveTyper {
+    pub fn new(typing: TypingConfig) -> Self {
+        Self {
+            typed: String::new(),
+            typing,
+            closed: false,
+        }
+    }
+
+    /// Ignore partials from now on; only `finish` or `abandon` still edit the text.
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    /// Type a partial transcript.
+    ///
+    /// The last word is held back, as it is likely cut off mid-word.
+    pub fn update(&mut self, partial: &str) -> Result<(), String> {
+        let stable = stable_prefix(partial);
+        if self.closed || stable.is_empty() {
+            return Ok(());
+        }
+        self.edit_to(stable)
+    }
+
+    /// Correct the typed text to the final transcript.
+    pub fn finish(&mut self, text: &str) -> Result<(), String> {
+        self.closed = true;
+        self.edit_to(text)
+    }
+
+    /// Erase everything typed (cancelled, failed or empty recording).
+    pub fn abandon(&mut self) -> Result<(), String> {
+        self.closed = true;
+        self.edit_to("")
+    }
+
+    fn edit_to(&mut self, target: &str) -> Result<(), String> {
+        let (erase, insert) = edit(&self.typed, target);
+        if erase == 0 && insert.is_empty() {
+            return Ok(());
+        }
+        replace_typed_tail(erase, insert, &self.typing)?;
+        self.typed = target.to_string();
+        Ok(())
+    }
+}
+
+/// `partial` without its last word (and the whitespace before it).
+fn stable_prefix(partial: &str) -> &str {
+    let partial = partial.trim();
+    match partial.rfind(char::is_whitespace) {
+        Some(i) => partial[..i].trim_end(),
+        None => "",
+    }
+}
+
+/// Characters of `typed` to erase from the end, and the text to type after that, to turn
+/// `typed` into `target`.
+fn edit<'a>(typed: &str, target: &'a str) -> (usize, &'a str) {
+    let common = typed
+        .char_indices()
+        .zip(target.chars())
+        .take_while(|((_, a), b)| a == b)
+        .last()
+        .map(|((i, c), _)| i + c.len_utf8())
+        .unwrap_or(0);
+    (typed[common..].chars().count(), &target[common..])
+}
+
+/// Type partial transcripts into `typer` while `pipeline` is recording, and pass each one
+/// to `on_partial` (live captions).
+pub async fn run_partials<F>(
+    pipeline: SharedPipeline,
+    typer: Arc<Mutex<LiveTyper>>,
+    on_partial: F,
+) where
+    F: Fn(&str) + Send + 'static,
+{
+    loop {
+        tokio::time::sleep(PARTIAL_INTERVAL).await;
+        let closed = typer.lock().map(|t| t.closed).unwrap_or(true);
+        if closed || pipeline.state() != PipelineState::Recording {
+            return;
+        }
+
+        let partial = match pipeline.transcribe_partial().await {
+            Ok(Some(text)) => text,
+            Ok(None) => continue,
+            Err(e) => {
+                log::debug!("Live typing: partial transcription failed: {}", e);
+                continue;
+            }
+        };
+        on_partial(&partial);
+
+        let typer = typer.clone();
+        let typed = tauri::async_runtime::spawn_blocking(move || match typer.lock() {
+            Ok(mut typer) => typer.update(&partial),
+            Err(e) => Err(e.to_string()),
+        })
+        .await;
+        match typed {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log::warn!("Live typing: failed to type partial: {}", e),
+            Err(e) => log::warn!("Live typing: typing task failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_only_touches_changed_tail() {
+        assert_eq!(edit("", "hello world"), (0, "hello world"));
+        assert_eq!(edit("hello world", "hello world how"), (0, " how"));
+        assert_eq!(edit("hello word", "hello world."), (1, "ld."));
+        assert_eq!(edit("café au", "café ol"), (2, "ol"));
+        assert_eq!(edit("hello", ""), (5, ""));
+    }
+
+    #[test]
+    fn test_stable_prefix_holds_back_last_word() {
+        assert_eq!(stable_prefix("hello wor"), "hello");
+        assert_eq!(stable_prefix("  one two  three "), "one two");
+        assert_eq!(stable_prefix("hello"), "");
+    }
+}
@@ -0,0 +1,361 @@
+//! Model lists fetched from each provider's catalog endpoint.
+//!
+//! The settings UI asks for the models of a provider (OpenAI/Groq `/models`, Anthropic
+//! `/v1/models`, Gemini `models`, Deepgram `/v1/models`, Ollama `/api/tags`) instead of
+//! offering free-text model names. Lists are cached in `model_catalog.json` for a day; when
+//! a provider can't be reached the last cached list is returned, and failing that a built-in
+//! fallback list, so the dropdowns always have something to show.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const CATALOG_FILE_NAME: &str = "model_catalog.json";
+
+/// How long a fetched list is used before asking the provider again.
+const CACHE_TTL_HOURS: i64 = 24;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which kind of model a list is for; a provider like OpenAI or Groq serves both.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    Stt,
+    Llm,
+}
+
+impl ModelKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ModelKind::Stt => "stt",
+            ModelKind::Llm => "llm",
+        }
+    }
+}
+
+/// Where a returned list came from.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogSource {
+    /// Fetched from the provider just now.
+    Live,
+    /// From the cache (fresh, or stale because the provider couldn't be reached).
+    Cached,
+    /// Built-in list; the provider couldn't be reached and nothing was cached.
+    Fallback,
+}
+
+/// Response of `list_provider_models`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderModels {
+    pub provider: String,
+    pub kind: ModelKind,
+    pub models: Vec<String>,
+    pub source: CatalogSource,
+    /// When the list was fetched from the provider (none for fallback lists).
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// Why the provider couldn't be queried, if it couldn't.
+    pub error: Option<String>,
+}
+
+/// What's needed to query a provider.
+#[derive(Debug, Clone, Default)]
+pub struct CatalogCredentials {
+    pub api_key: Option<String>,
+    pub ollama_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedList {
+    models: Vec<String>,
+    fetched_at: DateTime<Utc>,
+}
+
+/// Cached model lists, persisted in `model_catalog.json`.
+pub struct ModelCatalog {
+    path: PathBuf,
+    /// Keyed by `"<kind>:<provider>"`.
+    cache: Mutex<HashMap<String, CachedList>>,
+}
+
+impl ModelCatalog {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        let path = app_data_dir.join(CATALOG_FILE_NAME);
+        let cache = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            cache: Mutex::new(cache),
+        }
+    }
+
+    fn cached(&self, key: &str) -> Option<CachedList> {
+        self.cache.lock().ok()?.get(key).cloned()
+    }
+
+    fn store(&self, key: String, list: CachedList) {
+        let Ok(mut cache) = self.cache.lock() else {
+            return;
+        };
+        cache.insert(key, list);
+        match serde_json::to_vec_pretty(&*cache) {
+            Ok(bytes) => {
+                if let Err(e) = fs::write(&self.path, bytes) {
+                    log::warn!("Failed to save model catalog cache: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize model catalog cache: {}", e),
+        }
+    }
+
+    /// Models of `provider`, from the cache while it's fresh (unless `refresh`), otherwise
+    /// from the provider, falling back to stale cache and then the built-in list.
+    pub async fn models(
+        &self,
+        provider: &str,
+        kind: ModelKind,
+        credentials: &CatalogCredentials,
+        refresh: bool,
+    ) -> ProviderModels {
+        let key = format!("{}:{}", kind.as_str(), provider);
+        let cached = self.cached(&key);
+        let respond = |models, source, fetched_at, error| ProviderModels {
+            provider: provider.to_string(),
+            kind,
+            models,
+            source,
+            fetched_at,
+            error,
+        };
+
+        if let Some(list) = cached.as_ref().filter(|_| !refresh) {
+            if Utc::now() - list.fetched_at < chrono::Duration::hours(CACHE_TTL_HOURS) {
+                return respond(
+                    list.models.clone(),
+                    CatalogSource::Cached,
+                    Some(list.fetched_at),
+                    None,
+                );
+            }
+        }
+
+        match fetch_models(provider, credentials).await {
+            Ok(ids) => {
+                let models = filter_for_kind(provider, kind, ids);
+                let fetched_at = Utc::now();
+                self.store(
+                    key,
+                    CachedList {
+                        models: models.clone(),
+                        fetched_at,
+                    },
+                );
+                respond(models, CatalogSource::Live, Some(fetched_at), None)
+            }
+            Err(e) => {
+                log::warn!("Failed to fetch {} models from {}: {}", kind.as_str(), provider, e);
+                match cached {
+                    Some(list) => {
+                        respond(list.models, CatalogSource::Cached, Some(list.fetched_at), Some(e))
+                    }
+                    None => {
+                        respond(fallback_models(provider, kind), CatalogSource::Fallback, None, Some(e))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Query the provider's catalog endpoint for all model ids it serves.
+async fn fetch_models(
+    provider: &str,
+    credentials: &CatalogCredentials,
+) -> Result<Vec<String>, String> {
+    let api_key = credentials.api_key.as_deref().filter(|k| !k.trim().is_empty());
+    let require_key = || api_key.ok_or_else(|| format!("No API key configured for {}", provider));
+    let client = crate::http::client_builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let request = match provider {
+        "openai" => client
+            .get("https://api.openai.com/v1/models")
+            .bearer_auth(require_key()?),
+        "groq" => client
+            .get("https://api.groq.com/openai/v1/models")
+            .bearer_auth(require_key()?),
+        "anthropic" => client
+            .get("https://api.anthropic.com/v1/models?limit=1000")
+            .header("x-api-key", require_key()?)
+            .header("anthropic-version", "2023-06-01"),
+        "gemini" => client
+            .get("https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000")
+            .header("x-goog-api-key", require_key()?),
+        "deepgram" => {
+            let request = client.get("https://api.deepgram.com/v1/models");
+            match api_key {
+                Some(key) => request.header("Authorization", format!("Token {}", key)),
+                None => request,
+            }
+        }
+        "ollama" => {
+            let base = credentials
+                .ollama_url
+                .as_deref()
+                .unwrap_or("http://localhost:11434")
+                .trim_end_matches('/');
+            client.get(format!("{}/api/tags", base))
+        }
+        other => return Err(format!("{} has no model catalog", other)),
+    };
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Catalog request returned {}", status));
+    }
+    let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    Ok(parse_model_ids(provider, &body))
+}
+
+/// Pull the model ids out of a catalog response.
+fn parse_model_ids(provider: &str, body: &serde_json::Value) -> Vec<String> {
+    let (list, field) = match provider {
+        "gemini" => ("models", "name"),
+        "ollama" => ("models", "name"),
+        "deepgram" => ("stt", "canonical_name"),
+        _ => ("data", "id"),
+    };
+
+    body.get(list)
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.get(field)?.as_str())
+                .map(|id| id.trim_start_matches("models/").to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_transcription_model(id: &str) -> bool {
+    let id = id.to_lowercase();
+    id.contains("whisper") || id.contains("transcribe")
+}
+
+/// Models that are neither chat/completion nor transcription models.
+fn is_other_model(id: &str) -> bool {
+    let id = id.to_lowercase();
+    [
+        "embed", "tts", "dall-e", "image", "moderation", "realtime", "search", "guard", "aqa",
+        "veo",
+    ]
+    .iter()
+    .any(|marker| id.contains(marker))
+}
+
+/// Keep the models of `kind`, sorted and deduplicated.
+fn filter_for_kind(provider: &str, kind: ModelKind, ids: Vec<String>) -> Vec<String> {
+    let mut models: Vec<String> = ids
+        .into_iter()
+        .filter(|id| match (provider, kind) {
+            // Deepgram only serves speech models; Ollama only chat models.
+            ("deepgram", _) | ("ollama", _) => true,
+            (_, ModelKind::Stt) => is_transcription_model(id),
+            (_, ModelKind::Llm) => !is_transcription_model(id) && !is_other_model(id),
+        })
+        .collect();
+    models.sort();
+    models.dedup();
+    models
+}
+
+/// Built-in list used when the provider can't be reached and nothing is cached.
+pub fn fallback_models(provider: &str, kind: ModelKind) -> Vec<String> {
+    match kind {
+        ModelKind::Llm => crate::commands::llm::get_llm_providers()
+            .into_iter()
+            .find(|p| p.id == provider)
+            .map(|p| p.models)
+            .unwrap_or_default(),
+        ModelKind::Stt => {
+            let models: &[&str] = match provider {
+                "openai" => &["gpt-4o-transcribe", "gpt-4o-mini-transcribe", "whisper-1"],
+                "groq" => &["whisper-large-v3-turbo", "whisper-large-v3"],
+                "deepgram" => &["nova-3", "nova-2"],
+                "speechmatics" => &["enhanced", "standard"],
+                _ => &[],
+            };
+            models.iter().map(|m| m.to_string()).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_filter_openai_catalog() {
+        let body = json!({
+            "data": [
+                {"id": "gpt-4o-mini"},
+                {"id": "whisper-1"},
+                {"id": "text-embedding-3-small"},
+                {"id": "gpt-4o-transcribe"},
+                {"id": "tts-1"},
+                {"id": "gpt-4.1"}
+            ]
+        });
+        let ids = parse_model_ids("openai", &body);
+        assert_eq!(
+            filter_for_kind("openai", ModelKind::Llm, ids.clone()),
+            ["gpt-4.1", "gpt-4o-mini"]
+        );
+        assert_eq!(
+            filter_for_kind("openai", ModelKind::Stt, ids),
+            ["gpt-4o-transcribe", "whisper-1"]
+        );
+    }
+
+    #[test]
+    fn test_parse_gemini_and_ollama_catalogs() {
+        let gemini = json!({"models": [{"name": "models/gemini-2.5-flash"}]});
+        assert_eq!(parse_model_ids("gemini", &gemini), ["gemini-2.5-flash"]);
+
+        let ollama = json!({"models": [{"name": "llama3.2:latest"}, {"name": "mistral"}]});
+        assert_eq!(parse_model_ids("ollama", &ollama), ["llama3.2:latest", "mistral"]);
+    }
+
+    #[test]
+    fn test_stale_cache_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("tangerine-catalog-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let catalog = ModelCatalog::new(dir.clone());
+        catalog.store(
+            "llm:groq".to_string(),
+            CachedList {
+                models: vec!["llama-3.3-70b-versatile".to_string()],
+                fetched_at: Utc::now(),
+            },
+        );
+
+        let reloaded = ModelCatalog::new(dir.clone());
+        let list = reloaded.cached("llm:groq").unwrap();
+        assert_eq!(list.models, ["llama-3.3-70b-versatile"]);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,628 @@
+//! Background prefetch of local Whisper models.
+//!
+//! Queued models are downloaded one at a time by a background task:
+//! - only on un-metered connections when `model_prefetch_unmetered_only` is set (where the
+//!   OS lets us tell; an unknown connection type is treated as un-metered),
+//! - throttled to `model_prefetch_max_kib_per_sec` (0 = unlimited),
+//! - into `<model>.part` with HTTP range requests, so a pause, a dropped connection or an
+//!   app restart resumes where it left off.
+//!
+//! The queue and the paused flag are persisted in `model_prefetch.json`. Progress is
+//! reported through `model-prefetch-progress` events.
+
+use crate::retry::{retry, RetryConfig, RetryableError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+/// How long to wait before re-checking a metered connection.
+const METERED_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long to wait before retrying a failed download.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Quick retries of a dropped download (each resuming from the partial file) before the
+/// job is reported as failed and parked for `RETRY_DELAY`.
+const DOWNLOAD_RETRY: RetryConfig = RetryConfig {
+    max_retries: 3,
+    initial_delay: Duration::from_secs(2),
+    max_delay: Duration::from_secs(15),
+    retry_on_rate_limit: true,
+};
+
+/// Minimum interval between progress events while downloading.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Connection cost as reported by the OS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkCost {
+    Unmetered,
+    Metered,
+    Unknown,
+}
+
+/// A model queued for download.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PrefetchJob {
+    pub model_id: String,
+    pub url: String,
+    pub filename: String,
+    /// Expected size, used until the server reports one.
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefetchPhase {
+    Queued,
+    Downloading,
+    Paused,
+    WaitingForUnmetered,
+    Completed,
+    Failed,
+}
+
+/// Payload of `model-prefetch-progress` events (and entries of the status list).
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefetchProgress {
+    pub model_id: String,
+    pub phase: PrefetchPhase,
+    pub downloaded_bytes: u64,
+    pub total_bytes: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PrefetchQueue {
+    jobs: Vec<PrefetchJob>,
+    #[serde(default)]
+    paused: bool,
+}
+
+enum DownloadOutcome {
+    Completed,
+    Interrupted,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum DownloadError {
+    /// Connection failed or dropped mid-transfer.
+    #[error("{0}")]
+    Network(String),
+    #[error("Server returned {0}")]
+    Status(reqwest::StatusCode),
+    /// Local filesystem problem; retrying right away won't help.
+    #[error("{0}")]
+    Io(String),
+}
+
+impl RetryableError for DownloadError {
+    fn is_retryable(&self, config: &RetryConfig) -> bool {
+        match self {
+            DownloadError::Network(_) => true,
+            DownloadError::Status(status) => {
+                status.is_server_error()
+                    || (config.retry_on_rate_limit
+                        && *status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+            }
+            DownloadError::Io(_) => false,
+        }
+    }
+}
+
+#[cfg(desktop)]
+fn get_setting_from_store<T: serde::de::DeserializeOwned>(
+    app: &AppHandle,
+    key: &str,
+    default: T,
+) -> T {
+    use tauri_plugin_store::StoreExt;
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(key))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default)
+}
+
+/// Download queue shared between the commands and the background task.
+pub struct ModelPrefetcher {
+    queue: Mutex<PrefetchQueue>,
+    progress: Mutex<HashMap<String, PrefetchProgress>>,
+    queue_path: PathBuf,
+    models_dir: PathBuf,
+    /// Wakes the background task after the queue or paused flag changed.
+    wake: Notify,
+    /// Model currently being downloaded, if any.
+    active: Mutex<Option<String>>,
+    /// Asks the running download to stop at the next chunk.
+    interrupt: AtomicBool,
+}
+
+impl ModelPrefetcher {
+    pub fn new(app_data_dir: PathBuf, models_dir: PathBuf) -> Self {
+        let _ = fs::create_dir_all(&app_data_dir);
+        let queue_path = app_data_dir.join("model_prefetch.json");
+        let queue = crate::persist::read_with_recovery(&queue_path).unwrap_or_default();
+
+        Self {
+            queue: Mutex::new(queue),
+            progress: Mutex::new(HashMap::new()),
+            queue_path,
+            models_dir,
+            wake: Notify::new(),
+            active: Mutex::new(None),
+            interrupt: AtomicBool::new(false),
+        }
+    }
+
+    fn save(&self, queue: &PrefetchQueue) {
+        let result = serde_json::to_string_pretty(queue)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                crate::persist::write_atomic(&self.queue_path, content.as_bytes())
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::warn!("Failed to save model prefetch queue: {}", e);
+        }
+    }
+
+    fn part_path(&self, job: &PrefetchJob) -> PathBuf {
+        self.models_dir.join(format!("{}.part", job.filename))
+    }
+
+    fn final_path(&self, job: &PrefetchJob) -> PathBuf {
+        self.models_dir.join(&job.filename)
+    }
+
+    /// Queue a model for download. Already queued or downloaded models are ignored.
+    #[cfg_attr(not(feature = "local-whisper"), allow(dead_code))]
+    pub fn enqueue(&self, job: PrefetchJob) -> Result<(), String> {
+        if self.final_path(&job).exists() {
+            return Ok(());
+        }
+        let mut queue = self.queue.lock().map_err(|e| e.to_string())?;
+        if queue.jobs.iter().any(|j| j.model_id == job.model_id) {
+            return Ok(());
+        }
+        queue.jobs.push(job);
+        self.save(&queue);
+        drop(queue);
+
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Pause downloading; the partial file is kept.
+    pub fn pause(&self) -> Result<(), String> {
+        let mut queue = self.queue.lock().map_err(|e| e.to_string())?;
+        queue.paused = true;
+        self.save(&queue);
+        self.interrupt.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn resume(&self) -> Result<(), String> {
+        let mut queue = self.queue.lock().map_err(|e| e.to_string())?;
+        queue.paused = false;
+        self.save(&queue);
+        drop(queue);
+
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.retain(|_, p| p.phase != PrefetchPhase::Paused);
+        }
+
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Remove a model from the queue and delete its partial download.
+    pub fn cancel(&self, model_id: &str) -> Result<bool, String> {
+        let mut queue = self.queue.lock().map_err(|e| e.to_string())?;
+        let Some(idx) = queue.jobs.iter().position(|j| j.model_id == model_id) else {
+            return Ok(false);
+        };
+        let job = queue.jobs.remove(idx);
+        self.save(&queue);
+
+        let downloading = self
+            .active
+            .lock()
+            .map(|a| a.as_deref() == Some(model_id))
+            .unwrap_or(false);
+        if downloading {
+            // The worker deletes the partial file once the download has stopped.
+            self.interrupt.store(true, Ordering::SeqCst);
+        } else {
+            let _ = fs::remove_file(self.part_path(&job));
+        }
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.remove(model_id);
+        }
+        Ok(true)
+    }
+
+    /// Current state of every queued model.
+    pub fn status(&self) -> Vec<PrefetchProgress> {
+        let (jobs, paused) = match self.queue.lock() {
+            Ok(queue) => (queue.jobs.clone(), queue.paused),
+            Err(_) => return Vec::new(),
+        };
+        let progress = self.progress.lock().map(|p| p.clone()).unwrap_or_default();
+
+        jobs.iter()
+            .map(|job| {
+                progress.get(&job.model_id).cloned().unwrap_or_else(|| PrefetchProgress {
+                    model_id: job.model_id.clone(),
+                    phase: if paused { PrefetchPhase::Paused } else { PrefetchPhase::Queued },
+                    downloaded_bytes: fs::metadata(self.part_path(job)).map(|m| m.len()).unwrap_or(0),
+                    total_bytes: job.total_bytes,
+                    error: None,
+                })
+            })
+            .collect()
+    }
+
+    fn report(&self, app: &AppHandle, progress: PrefetchProgress) {
+        if let Ok(mut map) = self.progress.lock() {
+            if progress.phase == PrefetchPhase::Completed {
+                map.remove(&progress.model_id);
+            } else {
+                map.insert(progress.model_id.clone(), progress.clone());
+            }
+        }
+        let _ = app.emit("model-prefetch-progress", progress);
+    }
+
+    fn is_queued(&self, model_id: &str) -> bool {
+        self.queue
+            .lock()
+            .map(|q| q.jobs.iter().any(|j| j.model_id == model_id))
+            .unwrap_or(false)
+    }
+
+    fn finish(&self, model_id: &str) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.jobs.retain(|j| j.model_id != model_id);
+            self.save(&queue);
+        }
+    }
+
+    async fn download(
+        &self,
+        app: &AppHandle,
+        job: &PrefetchJob,
+        max_kib_per_sec: u64,
+    ) -> Result<DownloadOutcome, DownloadError> {
+        let final_path = self.final_path(job);
+        if final_path.exists() {
+            return Ok(DownloadOutcome::Completed);
+        }
+        fs::create_dir_all(&self.models_dir)
+            .map_err(|e| DownloadError::Io(format!("Failed to create models directory: {}", e)))?;
+
+        let part_path = self.part_path(job);
+        let mut offset = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+
+        let client = crate::http::client_builder()
+            .build()
+            .map_err(|e| DownloadError::Network(format!("Failed to create HTTP client: {}", e)))?;
+        let mut request = client.get(&job.url);
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| DownloadError::Network(format!("Request failed: {}", e)))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+            // The partial file already has everything.
+            fs::rename(&part_path, &final_path).map_err(|e| DownloadError::Io(e.to_string()))?;
+            return Ok(DownloadOutcome::Completed);
+        }
+        if !status.is_success() {
+            return Err(DownloadError::Status(status));
+        }
+        if offset > 0 && status != reqwest::StatusCode::PARTIAL_CONTENT {
+            log::info!("Server ignored range request for {}; restarting", job.model_id);
+            offset = 0;
+        }
+
+        let total_bytes = response
+            .content_length()
+            .map(|len| len + offset)
+            .unwrap_or(job.total_bytes);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(offset > 0)
+            .write(true)
+            .truncate(offset == 0)
+            .open(&part_path)
+            .map_err(|e| DownloadError::Io(format!("Failed to open {}: {}", part_path.display(), e)))?;
+
+        let started = Instant::now();
+        let mut session_bytes: u64 = 0;
+        let mut downloaded = offset;
+        let mut last_emit = Instant::now();
+
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| DownloadError::Network(format!("Download interrupted: {}", e)))?
+        {
+            file.write_all(&chunk)
+                .map_err(|e| DownloadError::Io(format!("Failed to write {}: {}", part_path.display(), e)))?;
+            downloaded += chunk.len() as u64;
+            session_bytes += chunk.len() as u64;
+
+            if self.interrupt.load(Ordering::SeqCst) {
+                let _ = file.sync_all();
+                return Ok(DownloadOutcome::Interrupted);
+            }
+
+            if let Some(delay) = throttle_delay(session_bytes, started.elapsed(), max_kib_per_sec) {
+                tokio::time::sleep(delay).await;
+            }
+
+            if last_emit.elapsed() >= PROGRESS_EMIT_INTERVAL {
+                last_emit = Instant::now();
+                self.report(
+                    app,
+                    PrefetchProgress {
+                        model_id: job.model_id.clone(),
+                        phase: PrefetchPhase::Downloading,
+                        downloaded_bytes: downloaded,
+                        total_bytes,
+                        error: None,
+                    },
+                );
+            }
+        }
+
+        file.sync_all().map_err(|e| DownloadError::Io(e.to_string()))?;
+        drop(file);
+        fs::rename(&part_path, &final_path)
+            .map_err(|e| {
+                DownloadError::Io(format!("Failed to finalize {}: {}", final_path.display(), e))
+            })?;
+        Ok(DownloadOutcome::Completed)
+    }
+}
+
+/// How long to sleep so that `bytes` transferred over `elapsed` stays under the limit.
+fn throttle_delay(bytes: u64, elapsed: Duration, max_kib_per_sec: u64) -> Option<Duration> {
+    if max_kib_per_sec == 0 {
+        return None;
+    }
+    let target = Duration::from_secs_f64(bytes as f64 / (max_kib_per_sec as f64 * 1024.0));
+    target.checked_sub(elapsed).filter(|d| !d.is_zero())
+}
+
+/// Interpret NetworkManager's `Metered` property (`busctl` output such as `u 4`).
+#[cfg_attr(not(any(target_os = "linux", test)), allow(dead_code))]
+fn parse_networkmanager_metered(output: &str) -> NetworkCost {
+    // NMMetered: 0 unknown, 1 yes, 2 no, 3 guess-yes, 4 guess-no.
+    match output.split_whitespace().last() {
+        Some("1") | Some("3") => NetworkCost::Metered,
+        Some("2") | Some("4") => NetworkCost::Unmetered,
+        _ => NetworkCost::Unknown,
+    }
+}
+
+/// Interpret a WinRT `NetworkCostType` name.
+#[cfg_attr(not(any(target_os = "windows", test)), allow(dead_code))]
+fn parse_windows_cost_type(output: &str) -> NetworkCost {
+    match output.trim() {
+        "Unrestricted" => NetworkCost::Unmetered,
+        "Fixed" | "Variable" => NetworkCost::Metered,
+        _ => NetworkCost::Unknown,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn detect_network_cost() -> NetworkCost {
+    std::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| parse_networkmanager_metered(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or(NetworkCost::Unknown)
+}
+
+#[cfg(target_os = "windows")]
+fn detect_network_cost() -> NetworkCost {
+    std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "[void][Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime]; \
+             $p = [Windows.Networking.Connectivity.NetworkInformation]::GetInternetConnectionProfile(); \
+             if ($p) { $p.GetConnectionCost().NetworkCostType }",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| parse_windows_cost_type(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or(NetworkCost::Unknown)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn detect_network_cost() -> NetworkCost {
+    NetworkCost::Unknown
+}
+
+/// Run the download queue until the app exits.
+#[cfg(desktop)]
+pub fn spawn(app: AppHandle, prefetcher: Arc<ModelPrefetcher>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let next = match prefetcher.queue.lock() {
+                Ok(queue) if !queue.paused => queue.jobs.first().cloned(),
+                _ => None,
+            };
+            let Some(job) = next else {
+                prefetcher.wake.notified().await;
+                continue;
+            };
+
+            let unmetered_only: bool = get_setting_from_store(&app, "model_prefetch_unmetered_only", true);
+            if unmetered_only {
+                let cost = tauri::async_runtime::spawn_blocking(detect_network_cost)
+                    .await
+                    .unwrap_or(NetworkCost::Unknown);
+                if cost == NetworkCost::Metered {
+                    prefetcher.report(
+                        &app,
+                        PrefetchProgress {
+                            model_id: job.model_id.clone(),
+                            phase: PrefetchPhase::WaitingForUnmetered,
+                            downloaded_bytes: part_len(&prefetcher.part_path(&job)),
+                            total_bytes: job.total_bytes,
+                            error: None,
+                        },
+                    );
+                    let _ = tokio::time::timeout(METERED_RECHECK_INTERVAL, prefetcher.wake.notified()).await;
+                    continue;
+                }
+            }
+
+            let max_kib_per_sec: u64 = get_setting_from_store(&app, "model_prefetch_max_kib_per_sec", 0u64);
+            prefetcher.interrupt.store(false, Ordering::SeqCst);
+            log::info!("Prefetching Whisper model {}", job.model_id);
+
+            if let Ok(mut active) = prefetcher.active.lock() {
+                *active = Some(job.model_id.clone());
+            }
+            let outcome = retry(&DOWNLOAD_RETRY, "Model download", || {
+                prefetcher.download(&app, &job, max_kib_per_sec)
+            })
+            .await;
+            if let Ok(mut active) = prefetcher.active.lock() {
+                *active = None;
+            }
+
+            match outcome {
+                Ok(DownloadOutcome::Completed) => {
+                    log::info!("Prefetched Whisper model {}", job.model_id);
+                    prefetcher.finish(&job.model_id);
+                    prefetcher.report(
+                        &app,
+                        PrefetchProgress {
+                            model_id: job.model_id.clone(),
+                            phase: PrefetchPhase::Completed,
+                            downloaded_bytes: job.total_bytes,
+                            total_bytes: job.total_bytes,
+                            error: None,
+                        },
+                    );
+                }
+                Ok(DownloadOutcome::Interrupted) => {
+                    if prefetcher.is_queued(&job.model_id) {
+                        prefetcher.report(
+                            &app,
+                            PrefetchProgress {
+                                model_id: job.model_id.clone(),
+                                phase: PrefetchPhase::Paused,
+                                downloaded_bytes: part_len(&prefetcher.part_path(&job)),
+                                total_bytes: job.total_bytes,
+                                error: None,
+                            },
+                        );
+                    } else {
+                        // Cancelled while downloading.
+                        let _ = fs::remove_file(prefetcher.part_path(&job));
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Prefetch of {} failed: {}", job.model_id, e);
+                    prefetcher.report(
+                        &app,
+                        PrefetchProgress {
+                            model_id: job.model_id.clone(),
+                            phase: PrefetchPhase::Failed,
+                            downloaded_bytes: part_len(&prefetcher.part_path(&job)),
+                            total_bytes: job.total_bytes,
+                            error: Some(e.to_string()),
+                        },
+                    );
+                    let _ = tokio::time::timeout(RETRY_DELAY, prefetcher.wake.notified()).await;
+                }
+            }
+        }
+    });
+}
+
+fn part_len(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_delay() {
+        assert_eq!(throttle_delay(1 << 20, Duration::ZERO, 0), None);
+        // 1 MiB at 512 KiB/s should take 2s.
+        assert_eq!(
+            throttle_delay(1 << 20, Duration::from_millis(500), 512),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(throttle_delay(1 << 20, Duration::from_secs(3), 512), None);
+    }
+
+    #[test]
+    fn test_parse_network_cost() {
+        assert_eq!(parse_networkmanager_metered("u 4\n"), NetworkCost::Unmetered);
+        assert_eq!(parse_networkmanager_metered("u 1"), NetworkCost::Metered);
+        assert_eq!(parse_networkmanager_metered("u 0"), NetworkCost::Unknown);
+        assert_eq!(parse_windows_cost_type("Unrestricted\r\n"), NetworkCost::Unmetered);
+        assert_eq!(parse_windows_cost_type("Variable"), NetworkCost::Metered);
+        assert_eq!(parse_windows_cost_type(""), NetworkCost::Unknown);
+    }
+
+    #[test]
+    fn test_queue_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("tangerine-prefetch-{}", uuid::Uuid::new_v4()));
+        let job = PrefetchJob {
+            model_id: "base".to_string(),
+            url: "https://example.invalid/ggml-base.bin".to_string(),
+            filename: "ggml-base.bin".to_string(),
+            total_bytes: 100,
+        };
+
+        let prefetcher = ModelPrefetcher::new(dir.clone(), dir.join("models"));
+        prefetcher.enqueue(job.clone()).unwrap();
+        prefetcher.enqueue(job.clone()).unwrap();
+        prefetcher.pause().unwrap();
+
+        let reloaded = ModelPrefetcher::new(dir.clone(), dir.join("models"));
+        let status = reloaded.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].phase, PrefetchPhase::Paused);
+
+        assert!(reloaded.cancel("base").unwrap());
+        assert!(reloaded.status().is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
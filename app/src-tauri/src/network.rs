@@ -0,0 +1,84 @@
+//! Connectivity detection for offline mode.
+//!
+//! A background task periodically sends a request to the configured cloud STT and LLM
+//! providers through the shared HTTP client (so proxy and certificate settings apply). When
+//! none of them answer, the pipeline is switched to offline mode (local Whisper instead of
+//! cloud STT, no cloud LLM formatting) and a `network-status-changed` event is emitted so
+//! the UI can show it. Detection can be turned off with the
+//! `offline_mode_auto` setting, in which case the app always behaves as online.
+
+pub use tangerine_core::network::*;
+
+use crate::pipeline::SharedPipeline;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Probe interval while online.
+const ONLINE_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Probe interval while offline, so recovery is noticed quickly.
+const OFFLINE_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Payload of the `network-status-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkStatus {
+    pub online: bool,
+    /// Whether a local Whisper model is available to transcribe while offline.
+    pub local_stt_available: bool,
+}
+
+fn get_setting_from_store<T: serde::de::DeserializeOwned>(
+    app: &AppHandle,
+    key: &str,
+    default: T,
+) -> T {
+    use tauri_plugin_store::StoreExt;
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(key))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or(default)
+}
+
+/// Start the background connectivity monitor (the pipeline must already be managed).
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut online = true;
+        loop {
+            let auto: bool = get_setting_from_store(&app, "offline_mode_auto", true);
+            let now_online = if auto {
+                let urls = app.state::<SharedPipeline>().connectivity_probe_urls();
+                probe_connectivity(&urls).await
+            } else {
+                true
+            };
+
+            if now_online != online {
+                online = now_online;
+                let pipeline = app.state::<SharedPipeline>();
+                pipeline.set_offline(!online);
+                let status = NetworkStatus {
+                    online,
+                    local_stt_available: pipeline.local_stt_available(),
+                };
+                if online {
+                    log::info!("Network connectivity restored; leaving offline mode");
+                } else {
+                    log::warn!(
+                        "No network connectivity; entering offline mode (local STT available: {})",
+                        status.local_stt_available
+                    );
+                }
+                let _ = app.emit("network-status-changed", status);
+            }
+
+            tokio::time::sleep(if online {
+                ONLINE_PROBE_INTERVAL
+            } else {
+                OFFLINE_PROBE_INTERVAL
+            })
+            .await;
+        }
+    });
+}
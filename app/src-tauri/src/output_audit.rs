@@ -0,0 +1,370 @@
+//! Append-only audit log of output actions.
+//!
+//! Every time text is typed, pasted or copied into another application, an entry is
+//! appended to `output_audit.jsonl` in the app data directory: when it happened, which
+//! output mode was used, which application had focus, and the exact text. Entries are never
+//! rewritten or pruned by the app, so the file can be used to reconstruct what was injected
+//! where.
+//!
+//! The output functions in `commands::text` have no `AppHandle`, so the store is also
+//! installed process-wide (`install`) and recorded into via `record`.
+
+use crate::commands::text::OutputMode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use uuid::Uuid;
+
+const AUDIT_FILE_NAME: &str = "output_audit.jsonl";
+
+/// A single recorded output action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputAuditEntry {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    /// How the text was delivered (paste, paste and keep on clipboard, clipboard only).
+    pub mode: OutputMode,
+    /// Executable of the foreground application, where the OS lets us tell.
+    pub target_app: Option<String>,
+    pub text: String,
+    /// Whether Enter was pressed after the text.
+    pub hit_enter: bool,
+    pub success: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl OutputAuditEntry {
+    /// Start an entry for an output about to happen. The target app is captured now, before
+    /// the output itself can move focus.
+    pub fn new(mode: OutputMode, text: &str, hit_enter: bool) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            mode,
+            target_app: crate::windows_apps::get_foreground_process_path(),
+            text: text.to_string(),
+            hit_enter,
+            success: true,
+            error: None,
+        }
+    }
+
+    /// Fill in the outcome of the output.
+    pub fn with_result(mut self, result: &Result<(), String>) -> Self {
+        self.success = result.is_ok();
+        self.error = result.as_ref().err().cloned();
+        self
+    }
+}
+
+/// Filter for `OutputAuditLog::query`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OutputAuditQuery {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Case-insensitive substring of the target application.
+    pub app_contains: Option<String>,
+    /// Case-insensitive substring of the output text.
+    pub text_contains: Option<String>,
+    /// Maximum number of entries (newest first).
+    pub limit: Option<usize>,
+}
+
+impl OutputAuditQuery {
+    fn matches(&self, entry: &OutputAuditEntry) -> bool {
+        let contains = |haystack: &str, needle: &str| {
+            haystack.to_lowercase().contains(&needle.to_lowercase())
+        };
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+            && self.app_contains.as_deref().is_none_or(|needle| {
+                entry
+                    .target_app
+                    .as_deref()
+                    .is_some_and(|app| contains(app, needle))
+            })
+            && self
+                .text_contains
+                .as_deref()
+                .is_none_or(|needle| contains(&entry.text, needle))
+    }
+}
+
+/// Export format for `OutputAuditLog::export`.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputAuditExportFormat {
+    Jsonl,
+    Csv,
+}
+
+/// Append-only store backed by `output_audit.jsonl`.
+#[derive(Clone)]
+pub struct OutputAuditLog {
+    path: PathBuf,
+    /// Serializes appends so concurrent entries never interleave within a line.
+    write_lock: Arc<Mutex<()>>,
+}
+
+static INSTALLED: OnceLock<OutputAuditLog> = OnceLock::new();
+
+/// Make `log` the process-wide audit log used by `record`.
+pub fn install(log: OutputAuditLog) {
+    let _ = INSTALLED.set(log);
+}
+
+/// Append `entry` to the installed audit log (no-op before `install`).
+pub fn record(entry: OutputAuditEntry) {
+    if let Some(log) = INSTALLED.get() {
+        if let Err(e) = log.append(&entry) {
+            log::warn!("Failed to write output audit entry: {}", e);
+        }
+    }
+}
+
+impl OutputAuditLog {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        if let Err(e) = fs::create_dir_all(&app_data_dir) {
+            log::warn!("Failed to create app data dir for output audit: {}", e);
+        }
+        Self {
+            path: app_data_dir.join(AUDIT_FILE_NAME),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Append one entry as a JSON line (sealed when encryption at rest is on) and flush it
+    /// to disk.
+    pub fn append(&self, entry: &OutputAuditEntry) -> Result<(), String> {
+        let json = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        let mut line = crate::at_rest::seal_line(&json)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+        file.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+        file.sync_data().map_err(|e| e.to_string())
+    }
+
+    /// All entries in the order they were written. Unreadable lines (a torn write, bytes
+    /// that aren't UTF-8) are skipped; the entries after them are still read.
+    fn read_all(&self) -> Result<Vec<OutputAuditEntry>, String> {
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        // Split on raw bytes so a line that isn't UTF-8 is skipped like any other bad line
+        // (`lines()` would end the read there).
+        Ok(BufReader::new(file)
+            .split(b'\n')
+            .map_while(Result::ok)
+            .filter_map(|line| String::from_utf8(line).ok())
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| crate::at_rest::open_line(&line).ok())
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect())
+    }
+
+    /// Rewrite every line in the current at-rest format. Returns whether the file changed.
+    pub fn rewrite(&self) -> Result<bool, String> {
+        let _guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let want_sealed = crate::at_rest::is_enabled();
+        let mut changed = false;
+        let mut out = String::with_capacity(contents.len());
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            let converted = if crate::at_rest::is_sealed_line(line) == want_sealed {
+                None
+            } else {
+                // Lines we can't read are kept as they are rather than lost.
+                crate::at_rest::open_line(line)
+                    .and_then(|plain| crate::at_rest::seal_line(&plain))
+                    .ok()
+            };
+            changed |= converted.is_some();
+            out.push_str(converted.as_deref().unwrap_or(line));
+            out.push('\n');
+        }
+        if !changed {
+            return Ok(false);
+        }
+
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, out).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, &self.path).map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    /// Entries matching `query`, newest first.
+    pub fn query(&self, query: &OutputAuditQuery) -> Result<Vec<OutputAuditEntry>, String> {
+        let mut entries: Vec<OutputAuditEntry> = self
+            .read_all()?
+            .into_iter()
+            .filter(|e| query.matches(e))
+            .collect();
+        entries.reverse();
+        if let Some(limit) = query.limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    /// Write the entries matching `query` to `path`, oldest first. Returns the entry count.
+    pub fn export(
+        &self,
+        path: &Path,
+        format: OutputAuditExportFormat,
+        query: &OutputAuditQuery,
+    ) -> Result<usize, String> {
+        let mut entries = self.query(query)?;
+        entries.reverse();
+
+        let mut out = String::new();
+        match format {
+            OutputAuditExportFormat::Jsonl => {
+                for entry in &entries {
+                    out.push_str(&serde_json::to_string(entry).map_err(|e| e.to_string())?);
+                    out.push('\n');
+                }
+            }
+            OutputAuditExportFormat::Csv => {
+                out.push_str("timestamp,mode,target_app,hit_enter,success,error,text\n");
+                for entry in &entries {
+                    let fields = [
+                        entry.timestamp.to_rfc3339(),
+                        entry.mode.as_str().to_string(),
+                        entry.target_app.clone().unwrap_or_default(),
+                        entry.hit_enter.to_string(),
+                        entry.success.to_string(),
+                        entry.error.clone().unwrap_or_default(),
+                        entry.text.clone(),
+                    ];
+                    let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                    out.push_str(&row.join(","));
+                    out.push('\n');
+                }
+            }
+        }
+
+        fs::write(path, out).map_err(|e| e.to_string())?;
+        Ok(entries.len())
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log() -> (OutputAuditLog, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("tangerine-audit-{}", Uuid::new_v4()));
+        (OutputAuditLog::new(dir.clone()), dir)
+    }
+
+    fn entry(text: &str, app: Option<&str>) -> OutputAuditEntry {
+        OutputAuditEntry {
+            target_app: app.map(str::to_string),
+            ..OutputAuditEntry::new(OutputMode::Paste, text, false)
+        }
+    }
+
+    #[test]
+    fn test_append_and_query_newest_first() {
+        let (log, dir) = temp_log();
+        log.append(&entry("first", Some("C:\\Apps\\Word.exe"))).unwrap();
+        log.append(&entry("second", Some("C:\\Apps\\Slack.exe"))).unwrap();
+        log.append(&entry("third", None)).unwrap();
+
+        let all = log.query(&OutputAuditQuery::default()).unwrap();
+        let texts: Vec<&str> = all.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, ["third", "second", "first"]);
+
+        let word = log
+            .query(&OutputAuditQuery {
+                app_contains: Some("word".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(word.len(), 1);
+        assert_eq!(word[0].text, "first");
+
+        let limited = log
+            .query(&OutputAuditQuery {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(limited[0].text, "third");
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_torn_line_is_skipped() {
+        let (log, dir) = temp_log();
+        log.append(&entry("kept", None)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&log.path).unwrap();
+        file.write_all(br#"{"id":"x","timest"#).unwrap();
+
+        let all = log.query(&OutputAuditQuery::default()).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].text, "kept");
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_unreadable_line_does_not_hide_later_entries() {
+        let (log, dir) = temp_log();
+        log.append(&entry("before", None)).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&log.path).unwrap();
+        file.write_all(b"{\"id\":\"x\",\"te\xff\xfe\n").unwrap();
+        file.write_all(b"not json\n").unwrap();
+        log.append(&entry("after", None)).unwrap();
+
+        let all = log.query(&OutputAuditQuery::default()).unwrap();
+        let texts: Vec<&str> = all.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, ["after", "before"]);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_csv_export_escapes_fields() {
+        let (log, dir) = temp_log();
+        log.append(&entry("hello, \"world\"\nbye", Some("app.exe"))).unwrap();
+
+        let out = dir.join("audit.csv");
+        let count = log
+            .export(&out, OutputAuditExportFormat::Csv, &OutputAuditQuery::default())
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let csv = fs::read_to_string(&out).unwrap();
+        assert!(csv.contains(",paste,app.exe,false,true,,\"hello, \"\"world\"\"\nbye\"\n"));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,329 @@
+//! OS permissions the app depends on, so the UI can say why recording or typing fails.
+//!
+//! - Microphone: recording. macOS asks on first use (`AVCaptureDevice`); Windows has privacy
+//!   switches for all apps and for desktop apps.
+//! - Accessibility (macOS): typing/pasting the transcript and `OutputMode::Accessibility`.
+//! - Input monitoring (macOS): reading keyboard input from other apps.
+//! - Notifications: OS notifications.
+//!
+//! Linux has no per-app permissions for any of these, so everything is `NotRequired`.
+
+use serde::{Deserialize, Serialize};
+
+/// A permission, as passed to `settings_url`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    Microphone,
+    Accessibility,
+    InputMonitoring,
+    Notifications,
+}
+
+impl PermissionKind {
+    pub const ALL: [PermissionKind; 4] = [
+        PermissionKind::Microphone,
+        PermissionKind::Accessibility,
+        PermissionKind::InputMonitoring,
+        PermissionKind::Notifications,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    /// Not asked yet; the OS prompts the first time it is needed.
+    NotDetermined,
+    /// Blocked by policy (MDM, parental controls, ...); the user can't grant it.
+    Restricted,
+    /// This OS has no such permission.
+    NotRequired,
+    /// The OS doesn't let us check.
+    Unknown,
+}
+
+/// State of every permission on this machine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PermissionStatus {
+    pub microphone: PermissionState,
+    pub accessibility: PermissionState,
+    pub input_monitoring: PermissionState,
+    pub notifications: PermissionState,
+}
+
+impl PermissionStatus {
+    pub fn current() -> Self {
+        Self {
+            microphone: imp::microphone(),
+            accessibility: imp::accessibility(),
+            input_monitoring: imp::input_monitoring(),
+            notifications: imp::notifications(),
+        }
+    }
+
+    pub fn get(&self, kind: PermissionKind) -> PermissionState {
+        match kind {
+            PermissionKind::Microphone => self.microphone,
+            PermissionKind::Accessibility => self.accessibility,
+            PermissionKind::InputMonitoring => self.input_monitoring,
+            PermissionKind::Notifications => self.notifications,
+        }
+    }
+}
+
+/// Log a warning for each permission that is turned off, since recording or typing will fail.
+pub fn log_missing() {
+    let status = PermissionStatus::current();
+    for kind in PermissionKind::ALL {
+        let state = status.get(kind);
+        if matches!(state, PermissionState::Denied | PermissionState::Restricted) {
+            log::warn!("{:?} permission is {:?}", kind, state);
+        }
+    }
+}
+
+/// URL of the system settings page where `kind` is granted, if this OS has one.
+pub fn settings_url(kind: PermissionKind) -> Option<&'static str> {
+    imp::settings_url(kind)
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::{PermissionKind, PermissionState};
+    use std::ffi::{c_char, c_void};
+
+    type Id = *const c_void;
+
+    // IOHIDRequestType / IOHIDAccessType
+    const IOHID_REQUEST_TYPE_LISTEN_EVENT: u32 = 1;
+    const IOHID_ACCESS_TYPE_GRANTED: u32 = 0;
+    const IOHID_ACCESS_TYPE_DENIED: u32 = 1;
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOHIDCheckAccess(request_type: u32) -> u32;
+    }
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeAudio: Id;
+    }
+
+    #[link(name = "objc")]
+    extern "C" {
+        fn objc_getClass(name: *const c_char) -> Id;
+        fn sel_registerName(name: *const c_char) -> Id;
+        fn objc_msgSend();
+    }
+
+    pub fn microphone() -> PermissionState {
+        // SAFETY: `+[AVCaptureDevice authorizationStatusForMediaType:]` takes an NSString
+        // and returns an NSInteger; objc_msgSend is called through that exact signature.
+        let status = unsafe {
+            let class = objc_getClass(c"AVCaptureDevice".as_ptr());
+            if class.is_null() {
+                return PermissionState::Unknown;
+            }
+            let selector = sel_registerName(c"authorizationStatusForMediaType:".as_ptr());
+            let send: unsafe extern "C" fn(Id, Id, Id) -> isize =
+                std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            send(class, selector, AVMediaTypeAudio)
+        };
+        // AVAuthorizationStatus
+        match status {
+            0 => PermissionState::NotDetermined,
+            1 => PermissionState::Restricted,
+            2 => PermissionState::Denied,
+            3 => PermissionState::Granted,
+            _ => PermissionState::Unknown,
+        }
+    }
+
+    pub fn accessibility() -> PermissionState {
+        // SAFETY: no arguments, no ownership.
+        if unsafe { AXIsProcessTrusted() } {
+            PermissionState::Granted
+        } else {
+            PermissionState::Denied
+        }
+    }
+
+    pub fn input_monitoring() -> PermissionState {
+        // SAFETY: plain C call (macOS 10.15+).
+        match unsafe { IOHIDCheckAccess(IOHID_REQUEST_TYPE_LISTEN_EVENT) } {
+            IOHID_ACCESS_TYPE_GRANTED => PermissionState::Granted,
+            IOHID_ACCESS_TYPE_DENIED => PermissionState::Denied,
+            _ => PermissionState::NotDetermined,
+        }
+    }
+
+    /// `UNUserNotificationCenter` only reports its settings asynchronously to a block.
+    pub fn notifications() -> PermissionState {
+        PermissionState::Unknown
+    }
+
+    pub fn settings_url(kind: PermissionKind) -> Option<&'static str> {
+        Some(match kind {
+            PermissionKind::Microphone => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone"
+            }
+            PermissionKind::Accessibility => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility"
+            }
+            PermissionKind::InputMonitoring => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_ListenEvent"
+            }
+            PermissionKind::Notifications => {
+                "x-apple.systempreferences:com.apple.preference.notifications"
+            }
+        })
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::{PermissionKind, PermissionState};
+    use windows::core::PCWSTR;
+    use windows::Win32::System::Registry::{
+        RegGetValueW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD, RRF_RT_REG_SZ,
+    };
+
+    const MICROPHONE_CONSENT: &str = r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone";
+    const PUSH_NOTIFICATIONS: &str = r"Software\Microsoft\Windows\CurrentVersion\PushNotifications";
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn read_string(root: HKEY, key: &str, value: &str) -> Option<String> {
+        let (key, value) = (wide(key), wide(value));
+        let mut buffer = [0u16; 64];
+        let mut size = std::mem::size_of_val(&buffer) as u32;
+        // SAFETY: both names are NUL-terminated and `size` is the buffer's size in bytes.
+        let result = unsafe {
+            RegGetValueW(
+                root,
+                PCWSTR(key.as_ptr()),
+                PCWSTR(value.as_ptr()),
+                RRF_RT_REG_SZ,
+                None,
+                Some(buffer.as_mut_ptr().cast()),
+                Some(&mut size),
+            )
+        };
+        if result.is_err() {
+            return None;
+        }
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    }
+
+    fn read_dword(root: HKEY, key: &str, value: &str) -> Option<u32> {
+        let (key, value) = (wide(key), wide(value));
+        let mut data = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        // SAFETY: both names are NUL-terminated and `data` holds a DWORD.
+        let result = unsafe {
+            RegGetValueW(
+                root,
+                PCWSTR(key.as_ptr()),
+                PCWSTR(value.as_ptr()),
+                RRF_RT_REG_DWORD,
+                None,
+                Some((&mut data as *mut u32).cast()),
+                Some(&mut size),
+            )
+        };
+        result.is_ok().then_some(data)
+    }
+
+    /// Off if any of the switches is "Deny": device-wide (set by an admin), for this user, or
+    /// for desktop (non-packaged) apps. Missing values mean allowed.
+    pub fn microphone() -> PermissionState {
+        let denied =
+            |root: HKEY, key: &str| read_string(root, key, "Value").as_deref() == Some("Deny");
+        if denied(HKEY_LOCAL_MACHINE, MICROPHONE_CONSENT) {
+            return PermissionState::Restricted;
+        }
+        let non_packaged = format!(r"{}\NonPackaged", MICROPHONE_CONSENT);
+        if denied(HKEY_CURRENT_USER, MICROPHONE_CONSENT) || denied(HKEY_CURRENT_USER, &non_packaged)
+        {
+            return PermissionState::Denied;
+        }
+        PermissionState::Granted
+    }
+
+    pub fn accessibility() -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn input_monitoring() -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn notifications() -> PermissionState {
+        match read_dword(HKEY_CURRENT_USER, PUSH_NOTIFICATIONS, "ToastEnabled") {
+            Some(0) => PermissionState::Denied,
+            _ => PermissionState::Granted,
+        }
+    }
+
+    pub fn settings_url(kind: PermissionKind) -> Option<&'static str> {
+        match kind {
+            PermissionKind::Microphone => Some("ms-settings:privacy-microphone"),
+            PermissionKind::Notifications => Some("ms-settings:notifications"),
+            PermissionKind::Accessibility | PermissionKind::InputMonitoring => None,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod imp {
+    use super::{PermissionKind, PermissionState};
+
+    pub fn microphone() -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn accessibility() -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn input_monitoring() -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn notifications() -> PermissionState {
+        PermissionState::NotRequired
+    }
+
+    pub fn settings_url(_kind: PermissionKind) -> Option<&'static str> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_url_only_for_permissions_this_os_has() {
+        let status = PermissionStatus::current();
+        for kind in PermissionKind::ALL {
+            let required = status.get(kind) != PermissionState::NotRequired;
+            assert_eq!(settings_url(kind).is_some(), required, "{:?}", kind);
+        }
+        assert_eq!(
+            serde_json::from_str::<PermissionKind>("\"input_monitoring\"").unwrap(),
+            PermissionKind::InputMonitoring
+        );
+    }
+}
@@ -0,0 +1,653 @@
+//! Monitor- and DPI-aware placement of the overlay and captions windows.
+//!
+//! All geometry is in physical pixels in the global desktop space, which is what Tauri
+//! reports for monitors. The display a window goes on is the one containing the user's
+//! focus (caret, else active window) unless `overlay_monitor` is `"primary"`, and margins
+//! are scaled by that display's scale factor, so placement is stable across mixed-DPI
+//! setups.
+//!
+//! Focus geometry per OS:
+//! - Windows: caret via `GetGUIThreadInfo`, active window via `GetWindowRect`.
+//! - macOS: active window frame via System Events (no caret; needs AX APIs).
+//! - Linux (X11): active window via `xdotool` (no caret).
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Monitor};
+
+/// Values of the `widget_position` setting.
+pub const WIDGET_POSITIONS: &[&str] = &[
+    "top-left",
+    "top-center",
+    "top-right",
+    "center",
+    "bottom-left",
+    "bottom-center",
+    "bottom-right",
+];
+
+/// Distance from display/window edges for preset positions (logical pixels).
+const PRESET_MARGIN: f64 = 50.0;
+
+/// Gap between the caret and the overlay (logical pixels).
+const CARET_GAP: f64 = 16.0;
+
+/// Minimum distance kept from display edges when clamping (logical pixels).
+const EDGE_MARGIN: f64 = 12.0;
+
+/// A rectangle in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicalRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PhysicalRect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self { x, y, width, height }
+    }
+
+    fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    fn contains(&self, (x, y): (f64, f64)) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    fn intersection_area(&self, other: &PhysicalRect) -> f64 {
+        let w = self.right().min(other.right()) - self.x.max(other.x);
+        let h = self.bottom().min(other.bottom()) - self.y.max(other.y);
+        if w > 0.0 && h > 0.0 {
+            w * h
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A monitor as seen by the placement logic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Display {
+    pub bounds: PhysicalRect,
+    pub scale: f64,
+}
+
+impl Display {
+    fn from_monitor(monitor: &Monitor) -> Self {
+        let pos = monitor.position();
+        let size = monitor.size();
+        Self {
+            bounds: PhysicalRect::new(
+                pos.x as f64,
+                pos.y as f64,
+                size.width as f64,
+                size.height as f64,
+            ),
+            scale: monitor.scale_factor(),
+        }
+    }
+
+    /// Bounds in logical pixels (what macOS reports window frames in).
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    fn logical_bounds(&self) -> PhysicalRect {
+        PhysicalRect::new(
+            self.bounds.x / self.scale,
+            self.bounds.y / self.scale,
+            self.bounds.width / self.scale,
+            self.bounds.height / self.scale,
+        )
+    }
+}
+
+/// What the overlay is positioned relative to (`overlay_placement` setting).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayPlacement {
+    /// The `widget_position` preset on the chosen display.
+    #[default]
+    Preset,
+    /// Just below (or above) the text caret; falls back to the active window.
+    NearCaret,
+    /// The `widget_position` preset within the active window's frame.
+    NearActiveWindow,
+}
+
+/// Which display preset positions use (`overlay_monitor` setting).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorPreference {
+    /// The display with the caret / active window.
+    #[default]
+    Active,
+    Primary,
+}
+
+/// Where the user is currently working, as far as the OS lets us tell.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FocusGeometry {
+    pub caret: Option<PhysicalRect>,
+    pub window: Option<PhysicalRect>,
+}
+
+impl FocusGeometry {
+    fn anchor(&self) -> Option<PhysicalRect> {
+        self.caret.or(self.window)
+    }
+}
+
+/// The display that best contains `rect` (largest overlap, then containing its center).
+pub fn display_for_rect<'a>(displays: &'a [Display], rect: &PhysicalRect) -> Option<&'a Display> {
+    let best = displays
+        .iter()
+        .map(|d| (d, d.bounds.intersection_area(rect)))
+        .filter(|(_, area)| *area > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(d, _)| d);
+    best.or_else(|| displays.iter().find(|d| d.bounds.contains(rect.center())))
+}
+
+/// Top-left of a `size` window at `preset` (e.g. "bottom-center") within `area`.
+///
+/// Returns `None` for an unknown preset.
+pub fn preset_position(
+    area: &PhysicalRect,
+    scale: f64,
+    size: (f64, f64),
+    preset: &str,
+) -> Option<(f64, f64)> {
+    let (w, h) = size;
+    let margin = PRESET_MARGIN * scale;
+    let left = area.x + margin;
+    let center_x = area.x + (area.width - w) / 2.0;
+    let right = area.right() - w - margin;
+    let top = area.y + margin;
+    let center_y = area.y + (area.height - h) / 2.0;
+    let bottom = area.bottom() - h - margin;
+
+    Some(match preset {
+        "top-left" => (left, top),
+        "top-center" => (center_x, top),
+        "top-right" => (right, top),
+        "center" => (center_x, center_y),
+        "bottom-left" => (left, bottom),
+        "bottom-center" => (center_x, bottom),
+        "bottom-right" => (right, bottom),
+        _ => return None,
+    })
+}
+
+/// Top-left of a `size` window just below `caret`, or above it when there is no room below.
+pub fn near_caret_position(display: &Display, caret: &PhysicalRect, size: (f64, f64)) -> (f64, f64) {
+    let (w, h) = size;
+    let gap = CARET_GAP * display.scale;
+    let x = caret.x - w / 2.0;
+    let below = caret.bottom() + gap;
+    let y = if below + h <= display.bounds.bottom() {
+        below
+    } else {
+        caret.y - gap - h
+    };
+    (x, y)
+}
+
+/// Keep a `size` window at `pos` fully on `display`.
+pub fn clamp_to_display(display: &Display, pos: (f64, f64), size: (f64, f64)) -> (f64, f64) {
+    let margin = EDGE_MARGIN * display.scale;
+    let b = &display.bounds;
+    let max_x = (b.right() - size.0 - margin).max(b.x + margin);
+    let max_y = (b.bottom() - size.1 - margin).max(b.y + margin);
+    (
+        pos.0.clamp(b.x + margin, max_x),
+        pos.1.clamp(b.y + margin, max_y),
+    )
+}
+
+/// Placement preferences read from settings.
+#[derive(Debug, Clone)]
+pub struct PlacementPrefs {
+    pub placement: OverlayPlacement,
+    pub monitor: MonitorPreference,
+    pub preset: String,
+}
+
+impl Default for PlacementPrefs {
+    fn default() -> Self {
+        Self {
+            placement: OverlayPlacement::default(),
+            monitor: MonitorPreference::default(),
+            preset: "bottom-center".to_string(),
+        }
+    }
+}
+
+impl PlacementPrefs {
+    #[cfg(desktop)]
+    pub fn load(app: &AppHandle) -> Self {
+        use tauri_plugin_store::StoreExt;
+        let store = app.store("settings.json").ok();
+        let get = |key: &str| store.as_ref().and_then(|s| s.get(key));
+        let defaults = Self::default();
+        Self {
+            placement: get("overlay_placement")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or(defaults.placement),
+            monitor: get("overlay_monitor")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or(defaults.monitor),
+            preset: get("widget_position")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or(defaults.preset),
+        }
+    }
+
+    #[cfg(not(desktop))]
+    pub fn load(_app: &AppHandle) -> Self {
+        Self::default()
+    }
+}
+
+/// Compute where a window of physical `size` should go.
+///
+/// Pure so it can be tested without monitors: `displays` must be non-empty for a result.
+pub fn compute_position(
+    prefs: &PlacementPrefs,
+    displays: &[Display],
+    primary: Option<&Display>,
+    focus: &FocusGeometry,
+    size: (f64, f64),
+) -> Option<(f64, f64)> {
+    let focused_display = focus
+        .anchor()
+        .and_then(|anchor| display_for_rect(displays, &anchor));
+    let preset_display = match prefs.monitor {
+        MonitorPreference::Active => focused_display.or(primary),
+        MonitorPreference::Primary => primary.or(focused_display),
+    }
+    .or(displays.first())?;
+
+    let preset_on = |display: &Display, area: &PhysicalRect| {
+        preset_position(area, display.scale, size, &prefs.preset)
+            .or_else(|| preset_position(area, display.scale, size, "bottom-center"))
+            .map(|pos| clamp_to_display(display, pos, size))
+    };
+
+    match (prefs.placement, focused_display) {
+        (OverlayPlacement::NearCaret, Some(display)) if focus.caret.is_some() => {
+            let caret = focus.caret?;
+            Some(clamp_to_display(
+                display,
+                near_caret_position(display, &caret, size),
+                size,
+            ))
+        }
+        (OverlayPlacement::NearCaret | OverlayPlacement::NearActiveWindow, Some(display))
+            if focus.window.is_some() =>
+        {
+            preset_on(display, &focus.window?)
+        }
+        _ => preset_on(preset_display, &preset_display.bounds),
+    }
+}
+
+fn available_displays(app: &AppHandle) -> (Vec<Display>, Option<Display>) {
+    let displays = app
+        .available_monitors()
+        .map(|monitors| monitors.iter().map(Display::from_monitor).collect())
+        .unwrap_or_default();
+    let primary = app
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .map(|m| Display::from_monitor(&m));
+    (displays, primary)
+}
+
+/// Physical top-left for a window of physical `size`, using the saved preferences with
+/// `preset` overriding `widget_position` when given.
+pub fn window_position(
+    app: &AppHandle,
+    size: (f64, f64),
+    preset: Option<&str>,
+) -> Option<tauri::PhysicalPosition<i32>> {
+    let mut prefs = PlacementPrefs::load(app);
+    if let Some(preset) = preset {
+        prefs.preset = preset.to_string();
+        prefs.placement = OverlayPlacement::Preset;
+    }
+
+    let (displays, primary) = available_displays(app);
+    let focus = if prefs.placement == OverlayPlacement::Preset
+        && prefs.monitor == MonitorPreference::Primary
+    {
+        FocusGeometry::default()
+    } else {
+        imp::focus_geometry(&displays)
+    };
+
+    compute_position(&prefs, &displays, primary.as_ref(), &focus, size).map(|(x, y)| {
+        tauri::PhysicalPosition {
+            x: x.round() as i32,
+            y: y.round() as i32,
+        }
+    })
+}
+
+/// Physical bounds and scale of the display the user is working on (for windows that
+/// size themselves to the display, like captions).
+pub fn active_display(app: &AppHandle) -> Option<Display> {
+    let prefs = PlacementPrefs::load(app);
+    let (displays, primary) = available_displays(app);
+    if prefs.monitor == MonitorPreference::Primary {
+        if let Some(primary) = primary {
+            return Some(primary);
+        }
+    }
+    imp::focus_geometry(&displays)
+        .anchor()
+        .and_then(|anchor| display_for_rect(&displays, &anchor).copied())
+        .or(primary)
+        .or(displays.first().copied())
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use super::{Display, FocusGeometry, PhysicalRect};
+    use windows::Win32::Foundation::{POINT, RECT};
+    use windows::Win32::Graphics::Gdi::ClientToScreen;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetGUIThreadInfo, GetWindowRect, GetWindowThreadProcessId,
+        GUITHREADINFO,
+    };
+
+    fn rect_from(r: &RECT) -> PhysicalRect {
+        PhysicalRect::new(
+            r.left as f64,
+            r.top as f64,
+            (r.right - r.left) as f64,
+            (r.bottom - r.top) as f64,
+        )
+    }
+
+    // Tauri apps are per-monitor DPI aware, so these are physical pixels.
+    pub fn focus_geometry(_displays: &[Display]) -> FocusGeometry {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.0.is_null() {
+                return FocusGeometry::default();
+            }
+
+            let mut window_rect = RECT::default();
+            let window = GetWindowRect(hwnd, &mut window_rect)
+                .ok()
+                .map(|_| rect_from(&window_rect));
+
+            let thread = GetWindowThreadProcessId(hwnd, None);
+            let mut info = GUITHREADINFO {
+                cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+                ..Default::default()
+            };
+            let caret = if GetGUIThreadInfo(thread, &mut info).is_ok() && !info.hwndCaret.0.is_null()
+            {
+                let mut origin = POINT {
+                    x: info.rcCaret.left,
+                    y: info.rcCaret.top,
+                };
+                ClientToScreen(info.hwndCaret, &mut origin)
+                    .as_bool()
+                    .then(|| {
+                        PhysicalRect::new(
+                            origin.x as f64,
+                            origin.y as f64,
+                            (info.rcCaret.right - info.rcCaret.left).max(1) as f64,
+                            (info.rcCaret.bottom - info.rcCaret.top).max(1) as f64,
+                        )
+                    })
+            } else {
+                None
+            };
+
+            FocusGeometry { caret, window }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::{display_for_rect, Display, FocusGeometry, PhysicalRect};
+    use std::process::Command;
+
+    pub fn focus_geometry(displays: &[Display]) -> FocusGeometry {
+        let script = "tell application \"System Events\" to tell (first process whose frontmost is true) to get {position, size} of front window";
+        let window = Command::new("osascript")
+            .args(["-e", script])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .and_then(|o| super::parse_frame_list(&String::from_utf8_lossy(&o.stdout)))
+            .and_then(|logical| to_physical(displays, &logical));
+        FocusGeometry {
+            caret: None,
+            window,
+        }
+    }
+
+    /// System Events reports points; convert with the scale of the display it is on.
+    fn to_physical(displays: &[Display], logical: &PhysicalRect) -> Option<PhysicalRect> {
+        let logical_displays: Vec<Display> = displays
+            .iter()
+            .map(|d| Display {
+                bounds: d.logical_bounds(),
+                scale: d.scale,
+            })
+            .collect();
+        let index = display_for_rect(&logical_displays, logical)
+            .and_then(|found| logical_displays.iter().position(|d| d == found))?;
+        let display = &displays[index];
+        let origin = &logical_displays[index].bounds;
+        Some(PhysicalRect::new(
+            display.bounds.x + (logical.x - origin.x) * display.scale,
+            display.bounds.y + (logical.y - origin.y) * display.scale,
+            logical.width * display.scale,
+            logical.height * display.scale,
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::{Display, FocusGeometry};
+    use std::process::Command;
+
+    // X11 only; Wayland doesn't expose other clients' geometry.
+    pub fn focus_geometry(_displays: &[Display]) -> FocusGeometry {
+        let window = Command::new("xdotool")
+            .args(["getactivewindow", "getwindowgeometry", "--shell"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .and_then(|o| super::parse_xdotool_geometry(&String::from_utf8_lossy(&o.stdout)));
+        FocusGeometry {
+            caret: None,
+            window,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+mod imp {
+    use super::{Display, FocusGeometry};
+
+    pub fn focus_geometry(_displays: &[Display]) -> FocusGeometry {
+        FocusGeometry::default()
+    }
+}
+
+/// Parse `xdotool getwindowgeometry --shell` output (`X=..`, `Y=..`, `WIDTH=..`, `HEIGHT=..`).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_xdotool_geometry(output: &str) -> Option<PhysicalRect> {
+    let value = |key: &str| {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix('='))
+            .and_then(|v| v.trim().parse::<f64>().ok())
+    };
+    Some(PhysicalRect::new(
+        value("X")?,
+        value("Y")?,
+        value("WIDTH")?,
+        value("HEIGHT")?,
+    ))
+}
+
+/// Parse an AppleScript `{{x, y}, {w, h}}` list as printed by osascript (`x, y, w, h`).
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_frame_list(output: &str) -> Option<PhysicalRect> {
+    let nums: Vec<f64> = output
+        .split(',')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match nums.as_slice() {
+        [x, y, w, h] => Some(PhysicalRect::new(*x, *y, *w, *h)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn displays() -> Vec<Display> {
+        vec![
+            // Primary 1920x1080 @ 100%.
+            Display {
+                bounds: PhysicalRect::new(0.0, 0.0, 1920.0, 1080.0),
+                scale: 1.0,
+            },
+            // 4K @ 200% to the right.
+            Display {
+                bounds: PhysicalRect::new(1920.0, 0.0, 3840.0, 2160.0),
+                scale: 2.0,
+            },
+        ]
+    }
+
+    fn prefs(placement: OverlayPlacement, monitor: MonitorPreference) -> PlacementPrefs {
+        PlacementPrefs {
+            placement,
+            monitor,
+            preset: "bottom-center".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_preset_lands_on_active_display_with_scaled_margin() {
+        let displays = displays();
+        let focus = FocusGeometry {
+            caret: None,
+            window: Some(PhysicalRect::new(2200.0, 200.0, 1600.0, 1200.0)),
+        };
+        let pos = compute_position(
+            &prefs(OverlayPlacement::Preset, MonitorPreference::Active),
+            &displays,
+            Some(&displays[0]),
+            &focus,
+            (528.0, 112.0),
+        )
+        .unwrap();
+        assert_eq!(pos, (1920.0 + (3840.0 - 528.0) / 2.0, 2160.0 - 112.0 - 100.0));
+
+        let pos = compute_position(
+            &prefs(OverlayPlacement::Preset, MonitorPreference::Primary),
+            &displays,
+            Some(&displays[0]),
+            &focus,
+            (264.0, 56.0),
+        )
+        .unwrap();
+        assert_eq!(pos, ((1920.0 - 264.0) / 2.0, 1080.0 - 56.0 - 50.0));
+    }
+
+    #[test]
+    fn test_near_caret_flips_above_at_bottom_edge() {
+        let displays = displays();
+        let display = &displays[0];
+
+        let caret = PhysicalRect::new(500.0, 300.0, 2.0, 20.0);
+        assert_eq!(
+            near_caret_position(display, &caret, (200.0, 50.0)),
+            (400.0, 336.0)
+        );
+
+        let caret = PhysicalRect::new(500.0, 1050.0, 2.0, 20.0);
+        assert_eq!(
+            near_caret_position(display, &caret, (200.0, 50.0)),
+            (400.0, 1050.0 - 16.0 - 50.0)
+        );
+    }
+
+    #[test]
+    fn test_near_caret_falls_back_to_active_window_and_clamps() {
+        let displays = displays();
+        let focus = FocusGeometry {
+            caret: None,
+            window: Some(PhysicalRect::new(100.0, 100.0, 800.0, 600.0)),
+        };
+        let pos = compute_position(
+            &prefs(OverlayPlacement::NearCaret, MonitorPreference::Active),
+            &displays,
+            Some(&displays[0]),
+            &focus,
+            (200.0, 50.0),
+        )
+        .unwrap();
+        assert_eq!(pos, (400.0, 600.0));
+
+        // Caret at the far left edge of the 200% display: clamped to a scaled margin.
+        let focus = FocusGeometry {
+            caret: Some(PhysicalRect::new(1925.0, 400.0, 2.0, 40.0)),
+            window: None,
+        };
+        let pos = compute_position(
+            &prefs(OverlayPlacement::NearCaret, MonitorPreference::Active),
+            &displays,
+            Some(&displays[0]),
+            &focus,
+            (400.0, 100.0),
+        )
+        .unwrap();
+        assert_eq!(pos, (1920.0 + 24.0, 472.0));
+    }
+
+    #[test]
+    fn test_display_for_rect_prefers_largest_overlap() {
+        let displays = displays();
+        let straddling = PhysicalRect::new(1800.0, 0.0, 600.0, 400.0);
+        assert_eq!(display_for_rect(&displays, &straddling), Some(&displays[1]));
+        let offscreen = PhysicalRect::new(-5000.0, 0.0, 10.0, 10.0);
+        assert_eq!(display_for_rect(&displays, &offscreen), None);
+    }
+
+    #[test]
+    fn test_parse_geometry_outputs() {
+        assert_eq!(
+            parse_xdotool_geometry("WINDOW=123\nX=10\nY=20\nWIDTH=800\nHEIGHT=600\nSCREEN=0\n"),
+            Some(PhysicalRect::new(10.0, 20.0, 800.0, 600.0))
+        );
+        assert_eq!(
+            parse_frame_list("-1440, 25, 1200, 800\n"),
+            Some(PhysicalRect::new(-1440.0, 25.0, 1200.0, 800.0))
+        );
+        assert_eq!(parse_frame_list(""), None);
+    }
+}
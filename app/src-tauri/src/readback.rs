@@ -0,0 +1,159 @@
+//! Read-back confirmation ("confirm by voice") for critical profiles.
+//!
+//! When the active profile has `confirm_by_voice` enabled, the final text is read aloud
+//! with the OS text-to-speech engine, then a short reply is recorded and transcribed.
+//! Output is only delivered if the reply is a confirmation; anything else (including
+//! silence or an unclear reply) discards the text.
+
+use std::time::Duration;
+
+/// Default time to listen for the spoken reply after the read-back.
+pub const DEFAULT_READBACK_LISTEN_SECS: f64 = 4.0;
+
+/// Words that confirm delivery.
+const CONFIRM_WORDS: &[&str] = &["confirm", "confirmed", "yes", "send", "ok", "okay", "deliver"];
+
+/// Words that cancel delivery. These win over confirm words ("no, don't send").
+const CANCEL_WORDS: &[&str] = &["cancel", "no", "stop", "discard", "abort", "don't", "dont"];
+
+/// Interpretation of the spoken reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationReply {
+    Confirm,
+    Cancel,
+    Unclear,
+}
+
+/// Classify a transcribed reply as confirm / cancel / unclear.
+pub fn parse_confirmation(transcript: &str) -> ConfirmationReply {
+    let words: Vec<String> = transcript
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase().replace('’', "'"))
+        .collect();
+
+    if words.iter().any(|w| CANCEL_WORDS.contains(&w.as_str())) {
+        ConfirmationReply::Cancel
+    } else if words.iter().any(|w| CONFIRM_WORDS.contains(&w.as_str())) {
+        ConfirmationReply::Confirm
+    } else {
+        ConfirmationReply::Unclear
+    }
+}
+
+/// Listen duration from a settings value, guarding against invalid numbers.
+pub fn listen_duration(secs: f64) -> Duration {
+    if secs.is_finite() && secs > 0.0 {
+        Duration::from_secs_f64(secs.min(30.0))
+    } else {
+        Duration::from_secs_f64(DEFAULT_READBACK_LISTEN_SECS)
+    }
+}
+
+/// Speak `text` with the OS text-to-speech engine and wait until it finishes.
+pub fn speak_blocking(text: &str) -> Result<(), String> {
+    imp::speak(text)
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    pub fn speak(text: &str) -> Result<(), String> {
+        // Pass the text on stdin so nothing in it is interpreted by PowerShell.
+        let mut child = Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                "Add-Type -AssemblyName System.Speech; \
+                 $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+                 $s.Speak([Console]::In.ReadToEnd())",
+            ])
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start PowerShell TTS: {}", e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to send text to TTS: {}", e))?;
+        }
+
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("TTS exited with {}", status))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::process::Command;
+
+    pub fn speak(text: &str) -> Result<(), String> {
+        let status = Command::new("say")
+            .arg("--")
+            .arg(text)
+            .status()
+            .map_err(|e| format!("Failed to run say: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("say exited with {}", status))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod imp {
+    use std::process::Command;
+
+    pub fn speak(text: &str) -> Result<(), String> {
+        // Prefer speech-dispatcher, fall back to espeak(-ng).
+        let attempts: [(&str, &[&str]); 3] = [
+            ("spd-say", &["--wait", "--"]),
+            ("espeak-ng", &["--"]),
+            ("espeak", &["--"]),
+        ];
+
+        let mut last_err = String::from("No TTS engine found (install speech-dispatcher or espeak)");
+        for (program, args) in attempts {
+            match Command::new(program).args(args).arg(text).status() {
+                Ok(status) if status.success() => return Ok(()),
+                Ok(status) => last_err = format!("{} exited with {}", program, status),
+                Err(_) => continue,
+            }
+        }
+        Err(last_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_confirmation() {
+        assert_eq!(parse_confirmation("Confirm."), ConfirmationReply::Confirm);
+        assert_eq!(parse_confirmation("yes, send it"), ConfirmationReply::Confirm);
+        assert_eq!(parse_confirmation("Cancel"), ConfirmationReply::Cancel);
+        assert_eq!(parse_confirmation("No, don't send it"), ConfirmationReply::Cancel);
+        assert_eq!(parse_confirmation("Don’t"), ConfirmationReply::Cancel);
+        assert_eq!(parse_confirmation(""), ConfirmationReply::Unclear);
+        assert_eq!(parse_confirmation("hmm maybe"), ConfirmationReply::Unclear);
+    }
+
+    #[test]
+    fn test_listen_duration_guards_invalid_values() {
+        assert_eq!(listen_duration(2.5), Duration::from_millis(2500));
+        assert_eq!(listen_duration(120.0), Duration::from_secs(30));
+        assert_eq!(
+            listen_duration(f64::NAN),
+            Duration::from_secs_f64(DEFAULT_READBACK_LISTEN_SECS)
+        );
+    }
+}
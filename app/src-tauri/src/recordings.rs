@@ -1,7 +1,10 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::RwLock;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+
+/// File name of the STT response cache inside the recordings directory.
+const STT_CACHE_FILE_NAME: &str = "stt_cache.json";
 
 #[derive(Debug, Clone, Copy, serde::Serialize)]
 pub struct RecordingsStats {
@@ -9,6 +12,74 @@ pub struct RecordingsStats {
     pub bytes: u64,
 }
 
+/// When recordings are written to disk (the `recording_storage_mode` setting).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingStorageMode {
+    #[default]
+    Always,
+    /// Keep only the audio of failed transcriptions, so they can be retried.
+    OnError,
+    /// Never write audio; recordings only ever live in memory.
+    Never,
+}
+
+/// How the transcription a recording belongs to ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingOutcome {
+    Succeeded,
+    Failed,
+}
+
+impl RecordingStorageMode {
+    pub fn allows(self, outcome: RecordingOutcome) -> bool {
+        match self {
+            RecordingStorageMode::Always => true,
+            RecordingStorageMode::OnError => outcome == RecordingOutcome::Failed,
+            RecordingStorageMode::Never => false,
+        }
+    }
+}
+
+/// Limits on saved recordings; `None` means no limit on that axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_files: Option<usize>,
+    pub max_total_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    fn is_unlimited(&self) -> bool {
+        self.max_files.is_none() && self.max_total_bytes.is_none() && self.max_age.is_none()
+    }
+
+    /// How many of `files` (`(modified, size)`, oldest first) have to go. Every limit drops
+    /// the oldest files first, so the answer is always a prefix.
+    fn excess_oldest(&self, files: &[(SystemTime, u64)], now: SystemTime) -> usize {
+        let mut remaining_count = files.len();
+        let mut remaining_bytes: u64 = files.iter().map(|(_, len)| *len).sum();
+
+        files
+            .iter()
+            .take_while(|(modified, len)| {
+                let too_old = self.max_age.is_some_and(|max_age| {
+                    now.duration_since(*modified).unwrap_or_default() > max_age
+                });
+                let too_many = self.max_files.is_some_and(|max| remaining_count > max);
+                let too_big = self.max_total_bytes.is_some_and(|max| remaining_bytes > max);
+                if too_old || too_many || too_big {
+                    remaining_count -= 1;
+                    remaining_bytes = remaining_bytes.saturating_sub(*len);
+                    true
+                } else {
+                    false
+                }
+            })
+            .count()
+    }
+}
+
 /// Simple on-disk store for WAV recordings keyed by request id.
 ///
 /// Files are stored under `<app_data_dir>/recordings/<id>.wav`.
@@ -18,6 +89,7 @@ pub struct RecordingStore {
     // Keep a tiny in-memory cache of existence checks to avoid repeated fs hits.
     // This is best-effort; correctness still relies on the filesystem.
     known_existing: RwLock<std::collections::HashSet<String>>,
+    storage_mode: RwLock<RecordingStorageMode>,
 }
 
 impl RecordingStore {
@@ -27,6 +99,7 @@ impl RecordingStore {
         Self {
             dir,
             known_existing: RwLock::new(std::collections::HashSet::new()),
+            storage_mode: RwLock::new(RecordingStorageMode::default()),
         }
     }
 
@@ -79,7 +152,29 @@ impl RecordingStore {
         self.path_for_id(id).exists()
     }
 
-    pub fn save_wav(&self, id: &str, wav_bytes: &[u8]) -> Result<(), String> {
+    pub fn storage_mode(&self) -> RecordingStorageMode {
+        self.storage_mode.read().map(|m| *m).unwrap_or(RecordingStorageMode::Never)
+    }
+
+    pub fn set_storage_mode(&self, mode: RecordingStorageMode) {
+        if let Ok(mut current) = self.storage_mode.write() {
+            *current = mode;
+        }
+    }
+
+    /// Save the audio of a transcription, if the storage mode allows it for `outcome`.
+    ///
+    /// Returns whether the file was written. With `RecordingStorageMode::Never` nothing is
+    /// ever written, whoever calls this.
+    pub fn save_wav(
+        &self,
+        id: &str,
+        wav_bytes: &[u8],
+        outcome: RecordingOutcome,
+    ) -> Result<bool, String> {
+        if !self.storage_mode().allows(outcome) {
+            return Ok(false);
+        }
         if id.trim().is_empty() {
             return Err("Cannot save recording: empty id".to_string());
         }
@@ -92,18 +187,42 @@ impl RecordingStore {
             fs::create_dir_all(parent).map_err(|e| format!("Failed to create recordings dir: {}", e))?;
         }
 
-        fs::write(&path, wav_bytes).map_err(|e| format!("Failed to write recording {}: {}", path.display(), e))?;
+        let contents = crate::at_rest::seal(wav_bytes)?;
+        fs::write(&path, contents).map_err(|e| format!("Failed to write recording {}: {}", path.display(), e))?;
 
         if let Ok(mut known) = self.known_existing.write() {
             known.insert(id.to_string());
         }
 
-        Ok(())
+        Ok(true)
     }
 
     pub fn load_wav(&self, id: &str) -> Result<Vec<u8>, String> {
         let path = self.path_for_id(id);
-        fs::read(&path).map_err(|e| format!("Failed to read recording {}: {}", path.display(), e))
+        let contents = fs::read(&path)
+            .map_err(|e| format!("Failed to read recording {}: {}", path.display(), e))?;
+        crate::at_rest::open(contents)
+    }
+
+    /// Rewrite every saved recording (and the STT cache) in the current at-rest format.
+    /// Returns how many files changed.
+    pub fn rewrite_all(&self) -> Result<usize, String> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read recordings dir: {}", e))?;
+
+        let mut changed = 0;
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some("wav") {
+                continue;
+            }
+            if crate::at_rest::rewrite_file(&path)? {
+                changed += 1;
+            }
+        }
+        if crate::persist::rewrite(&self.stt_cache_path()).map_err(|e| e.to_string())? {
+            changed += 1;
+        }
+        Ok(changed)
     }
 
     /// Delete a saved WAV file if it exists.
@@ -200,16 +319,11 @@ impl RecordingStore {
         Ok(RecordingsStats { count, bytes })
     }
 
-    /// Prune old recordings to keep at most `max_keep` files.
+    /// Saved `.wav` files with their modified time and size, oldest first.
     ///
-    /// Oldest is determined by filesystem modified time.
-    /// Best-effort: skips files it can't stat, continues on individual delete errors.
-    pub fn prune_to_max_files(&self, max_keep: usize) -> Result<usize, String> {
-        if max_keep == 0 {
-            return Ok(0);
-        }
-
-        let mut files: Vec<(PathBuf, SystemTime)> = Vec::new();
+    /// Best-effort: skips files it can't stat.
+    fn wav_files_oldest_first(&self) -> Result<Vec<(PathBuf, SystemTime, u64)>, String> {
+        let mut files: Vec<(PathBuf, SystemTime, u64)> = Vec::new();
         let entries = fs::read_dir(&self.dir)
             .map_err(|e| format!("Failed to read recordings dir {}: {}", self.dir.display(), e))?;
 
@@ -230,19 +344,28 @@ impl RecordingStore {
                 continue;
             };
             let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
-            files.push((path, modified));
+            files.push((path, modified, meta.len()));
         }
 
-        if files.len() <= max_keep {
+        files.sort_by_key(|(_, modified, _)| *modified);
+        Ok(files)
+    }
+
+    /// Delete the oldest recordings until `policy` is satisfied.
+    ///
+    /// Oldest is determined by filesystem modified time. Returns how many files were deleted.
+    /// Best-effort: continues on individual delete errors.
+    pub fn enforce_retention(&self, policy: &RetentionPolicy) -> Result<usize, String> {
+        if policy.is_unlimited() {
             return Ok(0);
         }
 
-        // Oldest first.
-        files.sort_by_key(|(_, modified)| *modified);
-        let delete_count = files.len() - max_keep;
+        let files = self.wav_files_oldest_first()?;
+        let meta: Vec<(SystemTime, u64)> = files.iter().map(|(_, m, len)| (*m, *len)).collect();
+        let delete_count = policy.excess_oldest(&meta, SystemTime::now());
 
         let mut deleted = 0usize;
-        for (path, _) in files.into_iter().take(delete_count) {
+        for (path, _, _) in files.into_iter().take(delete_count) {
             // Best-effort delete.
             if fs::remove_file(&path).is_ok() {
                 deleted += 1;
@@ -259,8 +382,78 @@ impl RecordingStore {
         Ok(deleted)
     }
 
+    /// Location of the on-disk STT response cache (see `tangerine_core::fingerprint::SttResponseCache`),
+    /// kept next to the recordings it was computed from.
+    pub fn stt_cache_path(&self) -> PathBuf {
+        self.dir.join(STT_CACHE_FILE_NAME)
+    }
+
     #[cfg_attr(not(test), allow(dead_code))]
     pub fn directory(&self) -> &Path {
         &self.dir
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    /// Five 100-byte files, one hour apart, the newest `now`.
+    fn files(now: SystemTime) -> Vec<(SystemTime, u64)> {
+        (0..5).rev().map(|h| (now - HOUR * h, 100)).collect()
+    }
+
+    #[test]
+    fn test_each_limit_drops_oldest_first() {
+        let now = SystemTime::now();
+        let files = files(now);
+
+        let by_count = RetentionPolicy {
+            max_files: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(by_count.excess_oldest(&files, now), 2);
+
+        let by_size = RetentionPolicy {
+            max_total_bytes: Some(250),
+            ..Default::default()
+        };
+        assert_eq!(by_size.excess_oldest(&files, now), 3);
+
+        let by_age = RetentionPolicy {
+            max_age: Some(HOUR * 3 + Duration::from_secs(60)),
+            ..Default::default()
+        };
+        assert_eq!(by_age.excess_oldest(&files, now), 1);
+    }
+
+    #[test]
+    fn test_storage_mode_gates_saves() {
+        let dir = std::env::temp_dir().join(format!("tangerine-recordings-{}", uuid::Uuid::new_v4()));
+        let store = RecordingStore::new(dir.clone());
+
+        store.set_storage_mode(RecordingStorageMode::Never);
+        assert_eq!(store.save_wav("a", b"RIFF", RecordingOutcome::Failed), Ok(false));
+
+        store.set_storage_mode(RecordingStorageMode::OnError);
+        assert_eq!(store.save_wav("b", b"RIFF", RecordingOutcome::Succeeded), Ok(false));
+        assert_eq!(store.save_wav("c", b"RIFF", RecordingOutcome::Failed), Ok(true));
+
+        assert!(!store.has("a") && !store.has("b") && store.has("c"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_strictest_limit_wins() {
+        let now = SystemTime::now();
+        let policy = RetentionPolicy {
+            max_files: Some(4),
+            max_total_bytes: Some(1_000),
+            max_age: Some(HOUR + Duration::from_secs(60)),
+        };
+        assert_eq!(policy.excess_oldest(&files(now), now), 3);
+        assert_eq!(RetentionPolicy::default().excess_oldest(&files(now), now), 0);
+    }
+}
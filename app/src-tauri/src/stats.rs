@@ -0,0 +1,207 @@
+//! Dictation productivity statistics computed from the transcript history.
+//!
+//! Words-per-minute only counts entries whose audio length is known (entries recorded
+//! before `audio_duration_secs` existed still count towards words and characters). Time
+//! saved compares the speaking time with typing the same words at `typing_wpm`.
+
+use crate::history::{HistoryEntry, HistoryStatus};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Typing speed assumed for "time saved" when the caller doesn't give one.
+pub const DEFAULT_TYPING_WPM: f64 = 40.0;
+
+/// Time window of `get_dictation_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsRange {
+    /// Last 24 hours
+    Day,
+    /// Last 7 days
+    Week,
+    /// Last 30 days
+    Month,
+    /// Last 365 days
+    Year,
+    All,
+}
+
+impl StatsRange {
+    pub fn since(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let days = match self {
+            StatsRange::Day => 1,
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+            StatsRange::Year => 365,
+            StatsRange::All => return None,
+        };
+        Some(now - Duration::days(days))
+    }
+}
+
+/// Totals for one calendar day (in the local time zone).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub dictations: usize,
+    pub words: usize,
+    pub characters: usize,
+    pub speaking_secs: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DictationStats {
+    pub range: StatsRange,
+    pub dictations: usize,
+    pub words: usize,
+    pub characters: usize,
+    /// Total length of the audio behind `words_timed`.
+    pub speaking_secs: f64,
+    /// Words of the entries with a known audio length.
+    pub words_timed: usize,
+    /// `words_timed` per minute of speaking; `None` without timed entries.
+    pub average_wpm: Option<f64>,
+    pub typing_wpm: f64,
+    /// Minutes it would have taken to type `words_timed` at `typing_wpm`, minus the
+    /// minutes spent speaking them.
+    pub minutes_saved: Option<f64>,
+    /// Oldest day first; days without dictations are omitted.
+    pub per_day: Vec<DayStats>,
+}
+
+fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// Compute stats for successful, non-empty entries in `range`, bucketing days in `tz`.
+pub fn compute<Tz: TimeZone>(
+    entries: &[HistoryEntry],
+    range: StatsRange,
+    typing_wpm: f64,
+    now: DateTime<Utc>,
+    tz: &Tz,
+) -> DictationStats {
+    let since = range.since(now);
+    let mut per_day: BTreeMap<NaiveDate, DayStats> = BTreeMap::new();
+    let mut stats = DictationStats {
+        range,
+        dictations: 0,
+        words: 0,
+        characters: 0,
+        speaking_secs: 0.0,
+        words_timed: 0,
+        average_wpm: None,
+        typing_wpm,
+        minutes_saved: None,
+        per_day: Vec::new(),
+    };
+
+    for entry in entries {
+        if entry.status != HistoryStatus::Success || entry.text.trim().is_empty() {
+            continue;
+        }
+        if since.is_some_and(|since| entry.timestamp < since) {
+            continue;
+        }
+
+        let words = word_count(&entry.text);
+        let characters = entry.text.chars().count();
+        let secs = entry
+            .audio_duration_secs
+            .filter(|secs| secs.is_finite() && *secs > 0.0)
+            .map(f64::from);
+
+        stats.dictations += 1;
+        stats.words += words;
+        stats.characters += characters;
+        if let Some(secs) = secs {
+            stats.speaking_secs += secs;
+            stats.words_timed += words;
+        }
+
+        let date = entry.timestamp.with_timezone(tz).date_naive();
+        let day = per_day.entry(date).or_insert(DayStats {
+            date,
+            dictations: 0,
+            words: 0,
+            characters: 0,
+            speaking_secs: 0.0,
+        });
+        day.dictations += 1;
+        day.words += words;
+        day.characters += characters;
+        day.speaking_secs += secs.unwrap_or(0.0);
+    }
+
+    if stats.speaking_secs > 0.0 {
+        let speaking_minutes = stats.speaking_secs / 60.0;
+        stats.average_wpm = Some(stats.words_timed as f64 / speaking_minutes);
+        if typing_wpm > 0.0 {
+            stats.minutes_saved = Some(stats.words_timed as f64 / typing_wpm - speaking_minutes);
+        }
+    }
+    stats.per_day = per_day.into_values().collect();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(text: &str, hours_ago: i64, secs: Option<f32>, now: DateTime<Utc>) -> HistoryEntry {
+        let mut entry = HistoryEntry::new(text.to_string());
+        entry.timestamp = now - Duration::hours(hours_ago);
+        entry.audio_duration_secs = secs;
+        entry
+    }
+
+    #[test]
+    fn test_totals_wpm_and_time_saved() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 12, 0, 0).unwrap();
+        let mut failed = entry("ignored words here", 1, Some(5.0), now);
+        failed.status = HistoryStatus::Error;
+        let entries = vec![
+            // 60 words in 30s -> 120 WPM
+            entry(&"word ".repeat(60), 1, Some(30.0), now),
+            // Untimed (older entry): counts for words, not for WPM
+            entry("one two three", 2, None, now),
+            entry("", 3, Some(2.0), now),
+            failed,
+        ];
+
+        let stats = compute(&entries, StatsRange::Day, 40.0, now, &Utc);
+        assert_eq!(stats.dictations, 2);
+        assert_eq!(stats.words, 63);
+        assert_eq!(stats.words_timed, 60);
+        assert_eq!(stats.average_wpm, Some(120.0));
+        // Typing 60 words at 40 WPM takes 1.5 min, speaking them took 0.5 min.
+        assert_eq!(stats.minutes_saved, Some(1.0));
+    }
+
+    #[test]
+    fn test_range_and_per_day_buckets() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 10, 12, 0, 0).unwrap();
+        let entries = vec![
+            entry("today", 1, Some(1.0), now),
+            entry("yesterday one", 24, Some(2.0), now),
+            entry("yesterday two", 25, None, now),
+            entry("last month", 24 * 40, None, now),
+        ];
+
+        let week = compute(&entries, StatsRange::Week, DEFAULT_TYPING_WPM, now, &Utc);
+        assert_eq!(week.dictations, 3);
+        let days: Vec<(NaiveDate, usize)> = week.per_day.iter().map(|d| (d.date, d.words)).collect();
+        assert_eq!(
+            days,
+            vec![
+                (NaiveDate::from_ymd_opt(2024, 6, 9).unwrap(), 4),
+                (NaiveDate::from_ymd_opt(2024, 6, 10).unwrap(), 1),
+            ]
+        );
+
+        let all = compute(&entries, StatsRange::All, DEFAULT_TYPING_WPM, now, &Utc);
+        assert_eq!(all.dictations, 4);
+        assert!(compute(&[], StatsRange::All, 40.0, now, &Utc).average_wpm.is_none());
+    }
+}
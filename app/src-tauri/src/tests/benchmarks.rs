@@ -171,6 +171,35 @@ mod audio_benchmarks {
             per_iter
         );
     }
+
+    /// Benchmark the persistent streaming resampler used by the realtime VAD thread.
+    #[test]
+    fn benchmark_streaming_resample_10ms_chunks() {
+        use crate::vad::StreamingResampler;
+
+        // 1 second of audio at 48kHz, pushed as 10ms callback chunks
+        let samples_48k: Vec<f32> = (0..48000).map(|i| (i as f32 * 0.001).sin()).collect();
+        let mut resampler = StreamingResampler::new(48000);
+        let mut out = Vec::with_capacity(16000);
+
+        let per_iter = benchmark("Streaming resample 48kHz→16kHz (1s in 10ms chunks)", 20, || {
+            out.clear();
+            for chunk in samples_48k.chunks(480) {
+                resampler.process(chunk, &mut out);
+            }
+            resampler.flush(&mut out);
+            out.len()
+        });
+
+        #[cfg(not(debug_assertions))]
+        assert!(
+            per_iter < Duration::from_millis(50),
+            "Streaming resampling too slow: {:?}",
+            per_iter
+        );
+        #[cfg(debug_assertions)]
+        let _ = per_iter;
+    }
 }
 
 #[cfg(test)]
@@ -351,7 +380,8 @@ mod memory_benchmarks {
     #[test]
     fn benchmark_struct_sizes() {
         use crate::pipeline::PipelineConfig;
-        use crate::stt::{AudioFormat, RetryConfig};
+        use crate::retry::RetryConfig;
+        use crate::stt::AudioFormat;
         use crate::vad::VadConfig;
 
         println!("\n[MEMORY] Struct sizes:");
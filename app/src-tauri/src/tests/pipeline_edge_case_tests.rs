@@ -10,7 +10,7 @@
 //! and should be run manually with `cargo test -- --ignored`
 
 use crate::pipeline::{PipelineConfig, PipelineError, PipelineState, SharedPipeline};
-use crate::stt::RetryConfig;
+use crate::retry::RetryConfig;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -29,9 +29,9 @@ fn test_pipeline_state_guards() {
     assert!(recording.can_stop_recording());
     assert!(recording.can_cancel());
 
-    // Test Transcribing state
+    // Test Transcribing state (transcriptions run in the background)
     let transcribing = PipelineState::Transcribing;
-    assert!(!transcribing.can_start_recording());
+    assert!(transcribing.can_start_recording());
     assert!(!transcribing.can_stop_recording());
     assert!(transcribing.can_cancel());
 
@@ -4,6 +4,7 @@
 //! Note: Actual API calls require API keys - run with `cargo test -- --ignored`
 //! when you have `GROQ_API_KEY`, `OPENAI_API_KEY`, or `DEEPGRAM_API_KEY` set.
 
+use bytes::Bytes;
 use crate::stt::{
     AudioEncoding, AudioFormat, DeepgramSttProvider, GroqSttProvider, OpenAiSttProvider,
     SttProvider,
@@ -70,7 +71,7 @@ async fn test_groq_transcription_integration() {
         encoding: AudioEncoding::Wav,
     };
 
-    let result = provider.transcribe(&wav_data, &format).await;
+    let result = provider.transcribe(wav_data, &format).await;
 
     // Should succeed (may return empty string for silence)
     assert!(result.is_ok(), "Groq transcription failed: {:?}", result);
@@ -97,7 +98,7 @@ async fn test_openai_transcription_integration() {
         encoding: AudioEncoding::Wav,
     };
 
-    let result = provider.transcribe(&wav_data, &format).await;
+    let result = provider.transcribe(wav_data, &format).await;
     assert!(result.is_ok(), "OpenAI transcription failed: {:?}", result);
 }
 
@@ -122,7 +123,7 @@ async fn test_deepgram_transcription_integration() {
         encoding: AudioEncoding::Wav,
     };
 
-    let result = provider.transcribe(&wav_data, &format).await;
+    let result = provider.transcribe(wav_data, &format).await;
     assert!(
         result.is_ok(),
         "Deepgram transcription failed: {:?}",
@@ -131,7 +132,7 @@ async fn test_deepgram_transcription_integration() {
 }
 
 /// Creates a minimal WAV file with silence for testing.
-fn create_test_wav_silence(duration_secs: f32) -> Vec<u8> {
+fn create_test_wav_silence(duration_secs: f32) -> Bytes {
     use std::io::Write;
 
     let sample_rate: u32 = 16000;
@@ -167,5 +168,5 @@ fn create_test_wav_silence(duration_secs: f32) -> Vec<u8> {
     // Silence (zeros)
     buffer.resize(44 + data_size as usize, 0);
 
-    buffer
+    Bytes::from(buffer)
 }
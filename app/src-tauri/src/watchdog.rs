@@ -0,0 +1,251 @@
+//! Watchdog that heals a wedged pipeline.
+//!
+//! Every `CHECK_INTERVAL` it looks at `SharedPipeline::progress`. A recording is wedged when
+//! the microphone stopped delivering audio for `recording_stall_secs` (a dead device or
+//! stream); a transcription when no job was queued, moved on to the LLM step or finished for
+//! `transcribing_stall_secs` (well past the STT/LLM timeouts, which normally end a job).
+//!
+//! A wedged pipeline is cancelled like a user cancel (sounds, overlay, audio muting), then
+//! `force_reset`. A diagnostic snapshot is logged and `pipeline-recovered` is emitted.
+
+use crate::pipeline::{PipelineProgress, PipelineState};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Bounds after which the pipeline is reset (stored as `pipeline_watchdog`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub enabled: bool,
+    /// Seconds a recording may go without receiving audio
+    pub recording_stall_secs: u64,
+    /// Seconds a transcription may run without finishing a stage
+    pub transcribing_stall_secs: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl WatchdogConfig {
+    const DEFAULT: Self = Self {
+        enabled: true,
+        recording_stall_secs: 30,
+        transcribing_stall_secs: 600,
+    };
+
+    fn bound(&self, state: PipelineState) -> Option<Duration> {
+        let secs = match state {
+            PipelineState::Recording => self.recording_stall_secs,
+            PipelineState::Transcribing | PipelineState::Rewriting => self.transcribing_stall_secs,
+            PipelineState::Idle | PipelineState::Error => return None,
+        };
+        Some(Duration::from_secs(secs.max(1)))
+    }
+}
+
+static CONFIG: Mutex<WatchdogConfig> = Mutex::new(WatchdogConfig::DEFAULT);
+
+/// Apply new watchdog settings.
+pub fn configure(config: WatchdogConfig) {
+    *CONFIG.lock().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+fn config() -> WatchdogConfig {
+    *CONFIG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Payload of the `pipeline-recovered` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PipelineRecovered {
+    /// `recording`, `transcribing` or `rewriting`
+    pub state: String,
+    pub stalled_secs: u64,
+}
+
+/// Progress seen so far; reports a stall once nothing moved for longer than the bound.
+#[derive(Debug)]
+struct ProgressWatch {
+    last: Option<PipelineProgress>,
+    since: Instant,
+}
+
+impl ProgressWatch {
+    fn new(now: Instant) -> Self {
+        Self {
+            last: None,
+            since: now,
+        }
+    }
+
+    /// How long the pipeline has been stuck in `progress.state`, once that exceeds its bound.
+    fn observe(
+        &mut self,
+        progress: PipelineProgress,
+        now: Instant,
+        config: &WatchdogConfig,
+    ) -> Option<Duration> {
+        if self.last != Some(progress) {
+            self.last = Some(progress);
+            self.since = now;
+            return None;
+        }
+        let stalled = now.duration_since(self.since);
+        let bound = config.bound(progress.state)?;
+        (config.enabled && stalled > bound).then_some(stalled)
+    }
+
+    /// Start over after the pipeline was reset.
+    fn reset(&mut self, now: Instant) {
+        *self = Self::new(now);
+    }
+}
+
+/// Check the pipeline every `CHECK_INTERVAL` until the app exits.
+#[cfg(desktop)]
+pub async fn run(app: tauri::AppHandle) {
+    use tauri::Manager;
+
+    let mut watch = ProgressWatch::new(Instant::now());
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(pipeline) = app.try_state::<crate::pipeline::SharedPipeline>() else {
+            continue;
+        };
+        let progress = pipeline.progress();
+        if let Some(stalled) = watch.observe(progress, Instant::now(), &config()) {
+            heal(&app, &pipeline, progress, stalled);
+            watch.reset(Instant::now());
+        }
+    }
+}
+
+#[cfg(desktop)]
+fn heal(
+    app: &tauri::AppHandle,
+    pipeline: &crate::pipeline::SharedPipeline,
+    progress: PipelineProgress,
+    stalled: Duration,
+) {
+    use crate::request_log::RequestLogStore;
+    use tauri::{Emitter, Manager};
+
+    let state = format!("{:?}", progress.state).to_lowercase();
+    tracing::error!(
+        state = %state,
+        stalled_secs = stalled.as_secs(),
+        audio_seq = progress.audio_seq,
+        job_progress = progress.job_progress,
+        jobs = ?pipeline.jobs(),
+        "Watchdog: pipeline made no progress, resetting it"
+    );
+
+    if let Some(log_store) = app.try_state::<RequestLogStore>() {
+        log_store.with_current(|log| {
+            let message = format!(
+                "Pipeline stuck in {} for {}s; reset by the watchdog",
+                state,
+                stalled.as_secs()
+            );
+            log.error(message.clone());
+            log.complete_error(message);
+        });
+        log_store.complete_current();
+    }
+
+    // Undo recording side effects (muted audio, overlay, Escape shortcut) like a cancel.
+    crate::cancel_pipeline_session(app, "Watchdog");
+    pipeline.force_reset();
+    crate::set_escape_cancel_shortcut_enabled(app, false);
+
+    let _ = app.emit("pipeline-reset", ());
+    let _ = app.emit(
+        "pipeline-recovered",
+        PipelineRecovered {
+            state,
+            stalled_secs: stalled.as_secs(),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(state: PipelineState, audio_seq: u64) -> PipelineProgress {
+        PipelineProgress {
+            state,
+            audio_seq,
+            job_progress: 0,
+        }
+    }
+
+    #[test]
+    fn test_stall_reported_only_when_nothing_moves_past_the_bound() {
+        let config = WatchdogConfig {
+            enabled: true,
+            recording_stall_secs: 30,
+            transcribing_stall_secs: 600,
+        };
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut watch = ProgressWatch::new(start);
+
+        // Audio keeps arriving: no stall however long the recording runs.
+        for (i, secs) in [0, 20, 40, 60].into_iter().enumerate() {
+            let seen = watch.observe(
+                progress(PipelineState::Recording, i as u64),
+                at(secs),
+                &config,
+            );
+            assert_eq!(seen, None);
+        }
+        // The microphone goes silent.
+        assert_eq!(
+            watch.observe(progress(PipelineState::Recording, 3), at(85), &config),
+            None
+        );
+        assert_eq!(
+            watch.observe(progress(PipelineState::Recording, 3), at(95), &config),
+            Some(Duration::from_secs(35))
+        );
+
+        // A long transcription has a longer bound; idle never stalls.
+        watch.reset(at(100));
+        assert_eq!(
+            watch.observe(progress(PipelineState::Transcribing, 3), at(100), &config),
+            None
+        );
+        assert_eq!(
+            watch.observe(progress(PipelineState::Transcribing, 3), at(400), &config),
+            None
+        );
+        assert_eq!(
+            watch.observe(progress(PipelineState::Idle, 3), at(2000), &config),
+            None
+        );
+        assert_eq!(
+            watch.observe(progress(PipelineState::Idle, 3), at(9000), &config),
+            None
+        );
+
+        let disabled = WatchdogConfig {
+            enabled: false,
+            ..config
+        };
+        assert_eq!(
+            watch.observe(progress(PipelineState::Recording, 3), at(9000), &disabled),
+            None
+        );
+        assert_eq!(
+            watch.observe(progress(PipelineState::Recording, 3), at(9999), &disabled),
+            None
+        );
+    }
+}
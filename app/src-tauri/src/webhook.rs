@@ -0,0 +1,212 @@
+//! Webhook output: the final transcript is POSTed as JSON to a user-specified endpoint
+//! (n8n, Zapier, Home Assistant, ...).
+//!
+//! It is sent instead of typing with `OutputMode::Webhook`, and in addition to typing when
+//! `also_when_typing` is on or the foreground app's profile sets its own `webhook_url`.
+
+use crate::commands::text::OutputMode;
+use crate::retry::{parse_retry_after, retry, RetryConfig, RetryableError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// The `output_webhook` setting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Endpoint to POST transcripts to.
+    pub url: Option<String>,
+    /// Also POST when the output mode types or copies the text.
+    pub also_when_typing: bool,
+    /// Timeout of a single attempt.
+    pub timeout_secs: u64,
+    /// Retries after a network error, rate limit or 5xx response.
+    pub max_retries: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            also_when_typing: false,
+            timeout_secs: 10,
+            max_retries: 3,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Endpoint for a transcript: the profile's, else the global one.
+    pub fn url_for(&self, profile_url: Option<&str>) -> Option<String> {
+        [profile_url, self.url.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|url| !url.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Endpoint a transcript output with `mode` goes to, if any. A profile URL always
+    /// applies; the global one only for `OutputMode::Webhook` or with `also_when_typing`.
+    pub fn target(&self, mode: OutputMode, profile_url: Option<&str>) -> Option<String> {
+        let wanted = mode == OutputMode::Webhook
+            || self.also_when_typing
+            || profile_url.is_some_and(|url| !url.trim().is_empty());
+        if wanted {
+            self.url_for(profile_url)
+        } else {
+            None
+        }
+    }
+}
+
+/// Read the `output_webhook` setting.
+pub fn webhook_config(app: &AppHandle) -> WebhookConfig {
+    #[cfg(desktop)]
+    {
+        use tauri_plugin_store::StoreExt;
+        if let Some(config) = app
+            .store("settings.json")
+            .ok()
+            .and_then(|store| store.get("output_webhook"))
+            .and_then(|v| serde_json::from_value(v).ok())
+        {
+            return config;
+        }
+    }
+
+    #[cfg(not(desktop))]
+    let _ = app;
+
+    WebhookConfig::default()
+}
+
+/// JSON body sent for each transcript.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    pub text: String,
+    /// Request log / history id of the recording, if any.
+    pub request_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Executable of the foreground app the dictation was meant for, if known.
+    pub app: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum WebhookError {
+    #[error("Webhook request failed: {0}")]
+    Network(String),
+    #[error("Webhook returned HTTP {status}")]
+    Status {
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+}
+
+impl RetryableError for WebhookError {
+    fn is_retryable(&self, config: &RetryConfig) -> bool {
+        match self {
+            WebhookError::Network(_) => true,
+            WebhookError::Status { status: 429, .. } => config.retry_on_rate_limit,
+            WebhookError::Status { status, .. } => *status >= 500,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            WebhookError::Status { retry_after, .. } => *retry_after,
+            WebhookError::Network(_) => None,
+        }
+    }
+}
+
+/// POST `text` to `url` in the background; failures are logged.
+pub fn spawn_send(app: &AppHandle, url: String, text: String, request_id: Option<String>) {
+    let config = webhook_config(app);
+    let payload = WebhookPayload {
+        text,
+        request_id,
+        timestamp: Utc::now(),
+        app: crate::windows_apps::get_foreground_process_path().and_then(|path| {
+            std::path::Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        }),
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = send(&url, &payload, &config).await {
+            log::error!("Webhook: {}", e);
+        }
+    });
+}
+
+/// POST `payload` to `url`, retrying per `config`.
+pub async fn send(
+    url: &str,
+    payload: &WebhookPayload,
+    config: &WebhookConfig,
+) -> Result<(), String> {
+    let client = crate::http::shared_client();
+    let timeout = Duration::from_secs(config.timeout_secs.max(1));
+    let retry_config = RetryConfig::with_max_retries(config.max_retries);
+
+    retry(&retry_config, "Webhook", || async {
+        let response = client
+            .post(url)
+            .timeout(timeout)
+            .json(payload)
+            .send()
+            .await
+            .map_err(|e| WebhookError::Network(e.to_string()))?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(WebhookError::Status {
+                status: status.as_u16(),
+                retry_after: parse_retry_after(response.headers()),
+            })
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    log::info!("Webhook: posted {} chars", payload.text.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_url_overrides_global() {
+        let config = WebhookConfig {
+            url: Some("https://n8n.local/hook".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.url_for(Some(" https://ha.local/x ")).as_deref(),
+            Some("https://ha.local/x")
+        );
+        assert_eq!(
+            config.url_for(None).as_deref(),
+            Some("https://n8n.local/hook")
+        );
+        assert_eq!(
+            config.url_for(Some("  ")).as_deref(),
+            Some("https://n8n.local/hook")
+        );
+        assert_eq!(config.target(OutputMode::Paste, None), None);
+        assert!(config.target(OutputMode::Webhook, None).is_some());
+
+        let retry = RetryConfig::default();
+        let status = |status| WebhookError::Status {
+            status,
+            retry_after: None,
+        };
+        assert!(status(503).is_retryable(&retry));
+        assert!(status(429).is_retryable(&retry));
+        assert!(!status(404).is_retryable(&retry));
+    }
+}
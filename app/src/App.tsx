@@ -27,6 +27,7 @@ import {
   AudioSettings,
   DataSettings,
   HotkeySettings,
+  NetworkSettings,
   PromptSettings,
   ProfileConfigModal,
   UiSettings,
@@ -384,6 +385,7 @@ function SettingsView() {
         <Tabs.Panel value="api-keys" pt="md">
           <div className="settings-card">
             <ApiKeysSettings editingProfileId={editingProfileId} />
+            <NetworkSettings editingProfileId={editingProfileId} />
           </div>
         </Tabs.Panel>
 
@@ -623,6 +625,7 @@ function SettingsViewWithGuideLauncher({
         <Tabs.Panel value="api-keys" pt="md">
           <div className="settings-card">
             <ApiKeysSettings editingProfileId={editingProfileId} />
+            <NetworkSettings editingProfileId={editingProfileId} />
           </div>
         </Tabs.Panel>
 
@@ -0,0 +1,99 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useRef, useState } from "react";
+
+interface CaptionsStyle {
+	font_size_px: number;
+	high_contrast: boolean;
+	max_lines: number;
+}
+
+interface CaptionUpdate {
+	text: string;
+	is_final: boolean;
+}
+
+const DEFAULT_STYLE: CaptionsStyle = {
+	font_size_px: 40,
+	high_contrast: true,
+	max_lines: 3,
+};
+
+/** Large-text captions window (see src-tauri/src/commands/captions.rs). */
+export default function CaptionsApp() {
+	const [style, setStyle] = useState<CaptionsStyle>(DEFAULT_STYLE);
+	// Settled caption lines, oldest first.
+	const [lines, setLines] = useState<string[]>([]);
+	// Text that may still change (live partials).
+	const [pending, setPending] = useState("");
+	const maxLinesRef = useRef(DEFAULT_STYLE.max_lines);
+
+	useEffect(() => {
+		maxLinesRef.current = style.max_lines;
+		setLines((prev) => prev.slice(-style.max_lines));
+	}, [style.max_lines]);
+
+	useEffect(() => {
+		invoke<CaptionsStyle>("get_captions_style")
+			.then(setStyle)
+			.catch(() => {});
+
+		const unlisteners = [
+			listen<CaptionsStyle>("captions-style-changed", (event) => {
+				setStyle(event.payload);
+			}),
+			listen<CaptionUpdate>("caption-update", (event) => {
+				const { text, is_final } = event.payload;
+				if (is_final) {
+					setPending("");
+					setLines((prev) => [...prev, text].slice(-maxLinesRef.current));
+				} else {
+					setPending(text);
+				}
+			}),
+		];
+
+		return () => {
+			for (const unlisten of unlisteners) {
+				unlisten.then((fn) => fn());
+			}
+		};
+	}, []);
+
+	const shown = pending ? [...lines, pending].slice(-style.max_lines) : lines;
+
+	return (
+		<div
+			role="log"
+			aria-live="polite"
+			style={{
+				boxSizing: "border-box",
+				height: "100vh",
+				margin: 0,
+				padding: "16px 24px",
+				display: "flex",
+				flexDirection: "column",
+				justifyContent: "flex-end",
+				overflow: "hidden",
+				fontFamily: "Sora, sans-serif",
+				fontSize: `${style.font_size_px}px`,
+				lineHeight: 1.4,
+				fontWeight: 600,
+				background: style.high_contrast ? "#000" : "rgba(24, 24, 27, 0.85)",
+				color: style.high_contrast ? "#fff" : "#e4e4e7",
+			}}
+		>
+			{shown.map((line, i) => (
+				<div
+					// biome-ignore lint/suspicious/noArrayIndexKey: lines are append-only
+					key={i}
+					style={{
+						opacity: pending && i === shown.length - 1 ? 0.75 : 1,
+					}}
+				>
+					{line}
+				</div>
+			))}
+		</div>
+	);
+}
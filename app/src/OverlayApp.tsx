@@ -7,7 +7,7 @@ import { useDrag } from "@use-gesture/react";
 import { useCallback, useEffect, useRef, useState } from "react";
 import { applyAccentColor } from "./lib/accentColor";
 import { useSettings, useTypeText } from "./lib/queries";
-import { type ConnectionState, tauriAPI } from "./lib/tauri";
+import { type ConnectionState, isAppError, tauriAPI } from "./lib/tauri";
 import "./app.css";
 
 /**
@@ -50,7 +50,35 @@ interface ErrorInfo {
  * Parse error message to user-friendly format
  */
 function parseError(error: unknown): ErrorInfo {
-  // Handle object errors (e.g., CommandError from Tauri with { message, error_type })
+  if (isAppError(error)) {
+    switch (error.code) {
+      case "no_microphone":
+        return { message: "No microphone", recoverable: true };
+      case "missing_api_key":
+        return { message: "API key missing", recoverable: true };
+      case "invalid_api_key":
+        return { message: "Invalid API key", recoverable: true };
+      case "rate_limited":
+        return { message: "Rate limited", recoverable: true };
+      case "network":
+        return { message: "Network error", recoverable: true };
+      case "timeout":
+        return { message: "Timed out", recoverable: true };
+      case "no_provider":
+        return { message: "No STT provider configured", recoverable: true };
+      case "not_recording":
+        return { message: "Not recording", recoverable: true };
+      case "already_recording":
+        return { message: "Already recording", recoverable: true };
+      case "recording_too_large":
+        return { message: "Recording too long", recoverable: true };
+      default:
+        // Fall through to message matching below.
+        break;
+    }
+  }
+
+  // Handle other object errors with a `message`
   let errorStr: string;
   if (error && typeof error === "object" && "message" in error) {
     errorStr = String((error as { message: unknown }).message);
@@ -0,0 +1,135 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+
+interface ReviewItem {
+	id: number;
+	request_id: string | null;
+	text: string;
+}
+
+const buttonStyle = {
+	padding: "8px 16px",
+	borderRadius: 6,
+	border: "1px solid #3f3f46",
+	fontFamily: "inherit",
+	fontSize: 14,
+	cursor: "pointer",
+};
+
+/** Editable transcript preview (see src-tauri/src/commands/review.rs). */
+export default function ReviewApp() {
+	// Transcripts waiting for review, oldest first; the first one is being edited.
+	const [items, setItems] = useState<ReviewItem[]>([]);
+	const [draft, setDraft] = useState("");
+	const [error, setError] = useState<string | null>(null);
+	const current = items[0];
+
+	useEffect(() => {
+		invoke<ReviewItem[]>("get_pending_reviews")
+			.then(setItems)
+			.catch(() => {});
+
+		const unlisten = listen<ReviewItem>("review-requested", (event) => {
+			setItems((prev) =>
+				prev.some((item) => item.id === event.payload.id)
+					? prev
+					: [...prev, event.payload],
+			);
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, []);
+
+	// Start each transcript from the text the pipeline produced.
+	useEffect(() => {
+		setDraft(current?.text ?? "");
+		setError(null);
+	}, [current?.id, current?.text]);
+
+	const finish = async (command: "confirm_review" | "discard_review") => {
+		if (!current) return;
+		try {
+			await invoke(
+				command,
+				command === "confirm_review"
+					? { id: current.id, text: draft }
+					: { id: current.id },
+			);
+			setItems((prev) => prev.filter((item) => item.id !== current.id));
+		} catch (e) {
+			setError(String(e));
+		}
+	};
+
+	return (
+		<div
+			style={{
+				boxSizing: "border-box",
+				height: "100vh",
+				margin: 0,
+				padding: 16,
+				display: "flex",
+				flexDirection: "column",
+				gap: 12,
+				fontFamily: "Sora, sans-serif",
+				background: "#18181b",
+				color: "#e4e4e7",
+			}}
+			onKeyDown={(e) => {
+				if (e.key === "Enter" && (e.ctrlKey || e.metaKey)) {
+					e.preventDefault();
+					finish("confirm_review");
+				} else if (e.key === "Escape") {
+					e.preventDefault();
+					finish("discard_review");
+				}
+			}}
+		>
+			<div style={{ fontSize: 13, opacity: 0.7 }}>
+				{current
+					? `Review transcript${items.length > 1 ? ` (1 of ${items.length})` : ""}`
+					: "Nothing to review"}
+			</div>
+			<textarea
+				// biome-ignore lint/a11y/noAutofocus: the window only exists to edit this text
+				autoFocus
+				value={draft}
+				disabled={!current}
+				onChange={(e) => setDraft(e.target.value)}
+				style={{
+					flex: 1,
+					resize: "none",
+					padding: 12,
+					borderRadius: 6,
+					border: "1px solid #3f3f46",
+					background: "#09090b",
+					color: "inherit",
+					fontFamily: "inherit",
+					fontSize: 16,
+					lineHeight: 1.5,
+				}}
+			/>
+			{error && <div style={{ fontSize: 13, color: "#f87171" }}>{error}</div>}
+			<div style={{ display: "flex", justifyContent: "flex-end", gap: 8 }}>
+				<button
+					type="button"
+					disabled={!current}
+					onClick={() => finish("discard_review")}
+					style={{ ...buttonStyle, background: "transparent", color: "inherit" }}
+				>
+					Discard (Esc)
+				</button>
+				<button
+					type="button"
+					disabled={!current || !draft.trim()}
+					onClick={() => finish("confirm_review")}
+					style={{ ...buttonStyle, background: "#f97316", color: "#fff" }}
+				>
+					Output (Ctrl+Enter)
+				</button>
+			</div>
+		</div>
+	);
+}
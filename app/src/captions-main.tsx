@@ -0,0 +1,15 @@
+import "@fontsource/sora/index.css";
+import { StrictMode } from "react";
+import { createRoot } from "react-dom/client";
+import CaptionsApp from "./CaptionsApp";
+
+const rootElement = document.getElementById("root");
+if (!rootElement) {
+	throw new Error("Root element not found");
+}
+
+createRoot(rootElement).render(
+	<StrictMode>
+		<CaptionsApp />
+	</StrictMode>,
+);
@@ -54,11 +54,15 @@ import {
   useDeleteHistoryEntry,
   useHistory,
   useRecordingsStats,
+  useRecoverOrphanedRecording,
   useRetryTranscription,
 } from "../lib/queries";
 import {
+  encryptionAPI,
+  errorMessage,
   llmAPI,
   recordingsAPI,
+  sttAPI,
   tauriAPI,
   type LlmProviderInfo,
 } from "../lib/tauri";
@@ -78,6 +82,10 @@ type PersistedHistoryFilters = {
 
 let historyFiltersStore: Store | null = null;
 
+// Interrupted recordings are offered once per app run, not on every remount.
+let orphansOffered = false;
+let keyErrorShown = false;
+
 async function getHistoryFiltersStore(): Promise<Store> {
   if (!historyFiltersStore) {
     historyFiltersStore = await Store.load(HISTORY_FILTERS_STORE_FILE);
@@ -339,6 +347,7 @@ export function HistoryFeed() {
   const deleteEntry = useDeleteHistoryEntry();
   const clearHistory = useClearHistory();
   const retryMutation = useRetryTranscription();
+  const recoverOrphan = useRecoverOrphanedRecording();
   const clipboard = useClipboard();
 
   const recordingsGbForTooltip = (() => {
@@ -514,6 +523,126 @@ export function HistoryFeed() {
     };
   }, [queryClient]);
 
+  // Warn when a paste couldn't put the previous clipboard content back, or when the
+  // transcript was only copied because the target window lost focus
+  useEffect(() => {
+    const unlisteners: Array<() => void> = [];
+
+    const setup = async () => {
+      unlisteners.push(
+        await tauriAPI.onClipboardRestoreSkipped((reason) => {
+          notifications.show({
+            title: "Clipboard not restored",
+            message: `Your previous clipboard content could not be restored: ${reason}`,
+            color: "yellow",
+          });
+        }),
+        await tauriAPI.onOutputRedirectedToClipboard((reason) => {
+          notifications.show({
+            title: "Copied instead of pasted",
+            message: `${reason}. The transcript is in your clipboard.`,
+            color: "yellow",
+          });
+        }),
+        await tauriAPI.onPipelineRecovered(({ state, stalled_secs }) => {
+          notifications.show({
+            title: "Dictation reset",
+            message:
+              state === "recording"
+                ? `No audio arrived from the microphone for ${stalled_secs}s, so the recording was stopped.`
+                : `The transcription made no progress for ${stalled_secs}s and was cancelled.`,
+            color: "orange",
+          });
+        })
+      );
+    };
+
+    setup();
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Encrypted history can't be read or saved without the keychain key
+  useEffect(() => {
+    if (keyErrorShown) return;
+    keyErrorShown = true;
+
+    encryptionAPI
+      .getKeyError()
+      .then((reason) => {
+        if (!reason) return;
+        notifications.show({
+          title: "Encryption key unavailable",
+          message: `Tangerine couldn't load its encryption key (${reason}). History, recordings and logs won't be saved until you unlock the keychain and restart Tangerine.`,
+          color: "red",
+          autoClose: false,
+        });
+      })
+      .catch((e) => console.warn("Failed to check the encryption key:", e));
+  }, []);
+
+  // Offer to finish transcriptions that were interrupted when the app last exited
+  useEffect(() => {
+    if (orphansOffered) return;
+    orphansOffered = true;
+
+    sttAPI
+      .getOrphanedRecordings()
+      .then((orphans) => {
+        for (const orphan of orphans) {
+          const id = `orphaned-recording-${orphan.id}`;
+          notifications.show({
+            id,
+            title: "Interrupted transcription",
+            color: "orange",
+            autoClose: false,
+            message: (
+              <Stack gap="xs">
+                <Text size="sm">
+                  A {orphan.duration_secs.toFixed(1)}s recording from{" "}
+                  {format(new Date(orphan.started_at), "MMM d, HH:mm")} was not
+                  transcribed before Tangerine closed.
+                </Text>
+                <Group gap="xs">
+                  <Button
+                    size="xs"
+                    onClick={() => {
+                      notifications.hide(id);
+                      recoverOrphan.mutate(orphan.id, {
+                        onError: (e) => {
+                          notifications.show({
+                            title: "Recovery failed",
+                            message: errorMessage(e),
+                            color: "red",
+                          });
+                        },
+                      });
+                    }}
+                  >
+                    Transcribe
+                  </Button>
+                  <Button
+                    size="xs"
+                    variant="subtle"
+                    color="gray"
+                    onClick={() => {
+                      notifications.hide(id);
+                      void sttAPI.discardOrphanedRecording(orphan.id);
+                    }}
+                  >
+                    Discard
+                  </Button>
+                </Group>
+              </Stack>
+            ),
+          });
+        }
+      })
+      .catch((e) => console.warn("Failed to list interrupted recordings:", e));
+  }, []);
+
   const handleDelete = (id: string) => {
     deleteEntry.mutate(id);
   };
@@ -532,7 +661,7 @@ export function HistoryFeed() {
     } catch (e) {
       notifications.show({
         title: "Recordings",
-        message: String(e),
+        message: errorMessage(e),
         color: "red",
       });
     }
@@ -1477,7 +1606,7 @@ export function HistoryFeed() {
                   } catch (e) {
                     notifications.show({
                       title: "Send to LLM",
-                      message: String(e),
+                      message: errorMessage(e),
                       color: "red",
                     });
                   }
@@ -1659,7 +1788,7 @@ export function HistoryFeed() {
                           onError: (e) => {
                             notifications.show({
                               title: "Retry failed",
-                              message: String(e),
+                              message: errorMessage(e),
                               color: "red",
                             });
                           },
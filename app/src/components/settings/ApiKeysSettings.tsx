@@ -44,6 +44,13 @@ const API_KEYS: ApiKeyConfig[] = [
     storeKey: "deepgram_api_key",
     getKeyUrl: "https://console.deepgram.com/project",
   },
+  {
+    id: "speechmatics",
+    label: "Speechmatics",
+    placeholder: "Enter API key",
+    storeKey: "speechmatics_api_key",
+    getKeyUrl: "https://portal.speechmatics.com/settings/api-keys",
+  },
   {
     id: "anthropic",
     label: "Anthropic",
@@ -23,7 +23,9 @@ import {
   useUpdateQuietAudioMinDurationSecs,
   useUpdateQuietAudioPeakDbfsThreshold,
   useUpdateQuietAudioRequireSpeech,
+  useUpdateHallucinationFilterEnabled,
   useUpdateQuietAudioRmsDbfsThreshold,
+  useUpdateStopAtMaxDuration,
 } from "../../lib/queries";
 import { type RewriteProgramPromptProfile } from "../../lib/tauri";
 import { DeviceSelector } from "../DeviceSelector";
@@ -46,8 +48,10 @@ export function AudioSettings({
   const updateQuietAudioPeakDbfsThreshold =
     useUpdateQuietAudioPeakDbfsThreshold();
   const updateQuietAudioRequireSpeech = useUpdateQuietAudioRequireSpeech();
+  const updateHallucinationFilterEnabled = useUpdateHallucinationFilterEnabled();
 
   const updateNoiseGateThresholdDbfs = useUpdateNoiseGateThresholdDbfs();
+  const updateStopAtMaxDuration = useUpdateStopAtMaxDuration();
   const updateAudioDownmixToMono = useUpdateAudioDownmixToMono();
   const updateAudioResampleTo16khz = useUpdateAudioResampleTo16khz();
   const updateAudioHighpassEnabled = useUpdateAudioHighpassEnabled();
@@ -74,9 +78,11 @@ export function AudioSettings({
   const quietAudioPeakDbfsThreshold =
     settings?.quiet_audio_peak_dbfs_threshold ?? -50;
   const quietAudioRequireSpeech = settings?.quiet_audio_require_speech ?? false;
+  const hallucinationFilterEnabled = settings?.hallucination_filter_enabled ?? true;
 
+  const stopAtMaxDuration = settings?.stop_at_max_duration ?? false;
   const audioDownmixToMono = settings?.audio_downmix_to_mono ?? true;
-  const audioResampleTo16khz = settings?.audio_resample_to_16khz ?? false;
+  const audioResampleTo16khz = settings?.audio_resample_to_16khz ?? true;
   const audioHighpassEnabled = settings?.audio_highpass_enabled ?? true;
   const audioAgcEnabled = settings?.audio_agc_enabled ?? false;
   const audioNoiseSuppressionEnabled =
@@ -317,6 +323,24 @@ export function AudioSettings({
         />
       </div>
 
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">Drop hallucinations</p>
+          <p className="settings-description">
+            Discard "Thanks for watching!"-style text on short or quiet recordings
+          </p>
+        </div>
+        <Switch
+          checked={hallucinationFilterEnabled}
+          onChange={(event) =>
+            updateHallucinationFilterEnabled.mutate(event.currentTarget.checked)
+          }
+          disabled={isProfileScope}
+          color="gray"
+          size="md"
+        />
+      </div>
+
       <div className="settings-row">
         <div>
           <p className="settings-label">Skip quiet — Minimum duration</p>
@@ -409,6 +433,25 @@ export function AudioSettings({
         </Group>
       </div>
 
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">Stop at max duration</p>
+          <p className="settings-description">
+            End and transcribe recordings that reach 5 minutes, instead of
+            keeping only the last 5 minutes
+          </p>
+        </div>
+        <Switch
+          checked={stopAtMaxDuration}
+          onChange={(event) =>
+            updateStopAtMaxDuration.mutate(event.currentTarget.checked)
+          }
+          disabled={isProfileScope}
+          color="gray"
+          size="md"
+        />
+      </div>
+
       <div className="settings-row">
         <div>
           <p className="settings-label">Convert to mono</p>
@@ -4,6 +4,7 @@ import {
   Group,
   NumberInput,
   SegmentedControl,
+  Switch,
   Tooltip,
 } from "@mantine/core";
 import { notifications } from "@mantine/notifications";
@@ -14,10 +15,12 @@ import {
   useRecordingsStats,
   useSettings,
   useUpdateMaxSavedRecordings,
+  useUpdateMockProvidersEnabled,
   useUpdateTranscriptionRetention,
   useUpdateTranscriptionRetentionDeleteRecordings,
 } from "../../lib/queries";
 import {
+  errorMessage,
   recordingsAPI,
   tauriAPI,
   type RewriteProgramPromptProfile,
@@ -54,6 +57,7 @@ export function DataSettings({
   });
 
   const updateMaxSavedRecordings = useUpdateMaxSavedRecordings();
+  const updateMockProvidersEnabled = useUpdateMockProvidersEnabled();
   const updateTranscriptionRetention = useUpdateTranscriptionRetention();
   const updateTranscriptionRetentionDeleteRecordings =
     useUpdateTranscriptionRetentionDeleteRecordings();
@@ -193,7 +197,7 @@ export function DataSettings({
     } catch (e) {
       notifications.show({
         title: "Recordings",
-        message: String(e),
+        message: errorMessage(e),
         color: "red",
       });
     }
@@ -775,6 +779,27 @@ export function DataSettings({
           />
         </Group>
       </div>
+
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">Mock providers</p>
+          <p
+            className="settings-description settings-description--single-line"
+            title="Adds a scripted Mock (debug) STT and LLM provider that needs no API key, for testing and demos."
+          >
+            Debug: scripted STT/LLM providers without API keys.
+          </p>
+        </div>
+        <Switch
+          checked={settings?.mock_providers_enabled ?? false}
+          onChange={(event) =>
+            updateMockProvidersEnabled.mutate(event.currentTarget.checked)
+          }
+          disabled={isProfileScope}
+          color="gray"
+          size="md"
+        />
+      </div>
     </>
   );
 
@@ -0,0 +1,145 @@
+import { Button, Switch, Text, TextInput, Tooltip } from "@mantine/core";
+import { type ReactNode, useEffect, useState } from "react";
+import { useSettings, useUpdateNetworkConfig } from "../../lib/queries";
+import {
+  DEFAULT_NETWORK_CONFIG,
+  errorMessage,
+  type NetworkConfig,
+} from "../../lib/tauri";
+
+const GLOBAL_ONLY_TOOLTIP =
+  "This setting can only be changed in the Default profile";
+
+const inputStyles = {
+  input: {
+    backgroundColor: "var(--bg-elevated)",
+    borderColor: "var(--border-default)",
+    color: "var(--text-primary)",
+    height: 36,
+    width: 260,
+  },
+};
+
+function emptyToNull(value: string): string | null {
+  const trimmed = value.trim();
+  return trimmed ? trimmed : null;
+}
+
+export function NetworkSettings({
+  editingProfileId,
+}: {
+  editingProfileId?: string;
+}) {
+  const isProfileScope = editingProfileId && editingProfileId !== "default";
+  const { data: settings } = useSettings();
+  const updateNetworkConfig = useUpdateNetworkConfig();
+
+  const saved = settings?.network_config ?? DEFAULT_NETWORK_CONFIG;
+  const [draft, setDraft] = useState<NetworkConfig>(saved);
+
+  useEffect(() => {
+    setDraft(saved);
+  }, [saved]);
+
+  const normalized: NetworkConfig = {
+    proxy_url: emptyToNull(draft.proxy_url ?? ""),
+    no_proxy: emptyToNull(draft.no_proxy ?? ""),
+    ca_cert_path: emptyToNull(draft.ca_cert_path ?? ""),
+    tls_verify: draft.tls_verify,
+  };
+  const isUnchanged = JSON.stringify(normalized) === JSON.stringify(saved);
+
+  const row = (label: string, description: string, input: ReactNode) => (
+    <div className="settings-row">
+      <div>
+        <p className="settings-label">{label}</p>
+        <p className="settings-description">{description}</p>
+      </div>
+      {input}
+    </div>
+  );
+
+  const content = (
+    <>
+      {row(
+        "Proxy",
+        "HTTP(S) proxy for all requests. Empty uses HTTPS_PROXY from the environment.",
+        <TextInput
+          value={draft.proxy_url ?? ""}
+          onChange={(e) =>
+            setDraft({ ...draft, proxy_url: e.currentTarget.value })
+          }
+          placeholder="http://proxy.example.com:8080"
+          size="sm"
+          styles={inputStyles}
+        />
+      )}
+      {row(
+        "No proxy",
+        "Comma-separated hosts that bypass the proxy",
+        <TextInput
+          value={draft.no_proxy ?? ""}
+          onChange={(e) =>
+            setDraft({ ...draft, no_proxy: e.currentTarget.value })
+          }
+          placeholder="localhost,127.0.0.1,.corp"
+          size="sm"
+          styles={inputStyles}
+        />
+      )}
+      {row(
+        "Custom root CA",
+        "Path to a PEM or DER certificate to trust in addition to the system roots",
+        <TextInput
+          value={draft.ca_cert_path ?? ""}
+          onChange={(e) =>
+            setDraft({ ...draft, ca_cert_path: e.currentTarget.value })
+          }
+          placeholder="/path/to/corp-root.pem"
+          size="sm"
+          styles={inputStyles}
+        />
+      )}
+      {row(
+        "Verify TLS certificates",
+        "Only turn off to debug an intercepting proxy",
+        <Switch
+          checked={draft.tls_verify}
+          onChange={(e) =>
+            setDraft({ ...draft, tls_verify: e.currentTarget.checked })
+          }
+          color="gray"
+          size="md"
+        />
+      )}
+      <div className="settings-row">
+        <Text size="sm" c="red">
+          {updateNetworkConfig.isError
+            ? errorMessage(updateNetworkConfig.error)
+            : null}
+        </Text>
+        <Button
+          color="orange"
+          size="sm"
+          onClick={() => updateNetworkConfig.mutate(normalized)}
+          loading={updateNetworkConfig.isPending}
+          disabled={isUnchanged || updateNetworkConfig.isPending}
+        >
+          Apply
+        </Button>
+      </div>
+    </>
+  );
+
+  if (isProfileScope) {
+    return (
+      <Tooltip label={GLOBAL_ONLY_TOOLTIP} withArrow position="top-start">
+        <div style={{ opacity: 0.5, cursor: "not-allowed" }}>
+          <div style={{ pointerEvents: "none" }}>{content}</div>
+        </div>
+      </Tooltip>
+    );
+  }
+
+  return content;
+}
@@ -137,6 +137,10 @@ export function ProfileConfigModal({
       overlay_mode: null,
       widget_position: null,
       output_mode: null,
+      output_format: null,
+      webhook_url: null,
+      vad_settings: null,
+      hotkey: null,
     };
 
     const next = [...profiles, newProfile];
@@ -213,6 +217,9 @@ export function ProfileConfigModal({
           overlay_mode: null,
           widget_position: null,
           output_mode: null,
+          output_format: null,
+          webhook_url: null,
+          vad_settings: null,
         });
       },
     });
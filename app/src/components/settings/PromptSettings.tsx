@@ -11,6 +11,7 @@ import {
   Switch,
   Textarea,
   Text,
+  TextInput,
   Tooltip,
 } from "@mantine/core";
 import { Info, RotateCcw } from "lucide-react";
@@ -32,6 +33,17 @@ import {
   useUpdateSTTModel,
   useUpdateSTTProvider,
   useUpdateSTTTranscriptionPrompt,
+  useUpdateSttVocabularyBoost,
+  useUpdateSttContextSecs,
+  useUpdateSttLanguage,
+  useUpdateVoskModel,
+  useUpdateWhisperServerUrl,
+  useUpdateSttExtraParams,
+  useUpdateSttTemperature,
+  useUpdateSttVerboseJson,
+  useUpdateLlmGeneration,
+  useVoskModels,
+  useDownloadVoskModel,
   useUpdateSTTTimeout,
   useUpdateGeminiThinkingBudget,
   useUpdateGeminiThinkingLevel,
@@ -60,6 +72,49 @@ const DEFAULT_SECTIONS: CleanupPromptSections = {
 // Keep this default aligned with backend fallbacks so "unset" settings don't lie.
 const DEFAULT_STT_TIMEOUT = 10;
 
+// Select value for "no language" (the provider detects it).
+const STT_LANGUAGE_AUTO = "auto";
+const STT_LANGUAGE_OPTIONS = [
+  { value: STT_LANGUAGE_AUTO, label: "Detect automatically" },
+  { value: "en", label: "English" },
+  { value: "de", label: "German" },
+  { value: "fr", label: "French" },
+  { value: "es", label: "Spanish" },
+  { value: "it", label: "Italian" },
+  { value: "pt", label: "Portuguese" },
+  { value: "nl", label: "Dutch" },
+  { value: "pl", label: "Polish" },
+  { value: "ru", label: "Russian" },
+  { value: "ja", label: "Japanese" },
+  { value: "ko", label: "Korean" },
+  { value: "cmn", label: "Mandarin" },
+  { value: "hi", label: "Hindi" },
+  { value: "ar", label: "Arabic" },
+  { value: "he", label: "Hebrew" },
+];
+
+// Providers that take no extra request parameters.
+const LOCAL_STT_PROVIDERS = new Set(["whisper", "vosk"]);
+
+// Extra STT request parameters are edited as one "name=value" per line.
+function formatExtraParams(params: Record<string, string> | undefined): string {
+  return Object.entries(params ?? {})
+    .map(([name, value]) => `${name}=${value}`)
+    .join("\n");
+}
+
+function parseExtraParams(text: string): Record<string, string> {
+  const params: Record<string, string> = {};
+  for (const line of text.split("\n")) {
+    const separator = line.indexOf("=");
+    const name = (separator === -1 ? line : line.slice(0, separator)).trim();
+    if (name) {
+      params[name] = separator === -1 ? "" : line.slice(separator + 1).trim();
+    }
+  }
+  return params;
+}
+
 type SectionKey = "main" | "advanced" | "dictionary";
 
 function errorToMessage(err: unknown): string {
@@ -123,6 +178,20 @@ export function PromptSettings({
   const updateSTTProvider = useUpdateSTTProvider();
   const updateSTTModel = useUpdateSTTModel();
   const updateSTTTranscriptionPrompt = useUpdateSTTTranscriptionPrompt();
+  const updateSttVocabularyBoost = useUpdateSttVocabularyBoost();
+  const updateSttContextSecs = useUpdateSttContextSecs();
+  const updateSttLanguage = useUpdateSttLanguage();
+  const updateWhisperServerUrl = useUpdateWhisperServerUrl();
+  const updateSttExtraParams = useUpdateSttExtraParams();
+  const updateSttTemperature = useUpdateSttTemperature();
+  const updateSttVerboseJson = useUpdateSttVerboseJson();
+  const updateLlmGeneration = useUpdateLlmGeneration();
+  const updateVoskModel = useUpdateVoskModel();
+  const downloadVoskModel = useDownloadVoskModel();
+  const { data: voskModels } = useVoskModels();
+  const selectedVoskModel =
+    voskModels?.find((model) => model.id === settings?.vosk_model) ??
+    voskModels?.[0];
   const updateLLMProvider = useUpdateLLMProvider();
   const updateLLMModel = useUpdateLLMModel();
   const updateOpenAiReasoningEffort = useUpdateOpenAiReasoningEffort();
@@ -1428,6 +1497,306 @@ export function PromptSettings({
                     }}
                   />
                 </div>
+
+                <div
+                  style={{
+                    display: "flex",
+                    alignItems: "center",
+                    justifyContent: "space-between",
+                    gap: 12,
+                  }}
+                >
+                  <div>
+                    <p className="settings-label">Boost dictionary terms</p>
+                    <p className="settings-description">
+                      Send Personal Dictionary entries to the STT provider as
+                      vocabulary hints.
+                    </p>
+                  </div>
+                  <Switch
+                    checked={settings?.stt_vocabulary_boost ?? true}
+                    onChange={(event) =>
+                      updateSttVocabularyBoost.mutate(
+                        event.currentTarget.checked
+                      )
+                    }
+                    color="gray"
+                    size="md"
+                  />
+                </div>
+
+                <div
+                  style={{
+                    display: "flex",
+                    alignItems: "center",
+                    justifyContent: "space-between",
+                    gap: 12,
+                  }}
+                >
+                  <div>
+                    <p className="settings-label">Recent dictation as context</p>
+                    <p className="settings-description">
+                      Seconds of previous dictation passed to Whisper as
+                      context (0 = off).
+                    </p>
+                  </div>
+                  <NumberInput
+                    value={settings?.stt_context_secs ?? 0}
+                    onChange={(value) => {
+                      const next = typeof value === "number" ? value : 0;
+                      updateSttContextSecs.mutate(next);
+                    }}
+                    min={0}
+                    max={600}
+                    step={30}
+                    styles={{
+                      input: {
+                        backgroundColor: "var(--bg-elevated)",
+                        borderColor: "var(--border-default)",
+                        color: "var(--text-primary)",
+                        width: 100,
+                      },
+                    }}
+                  />
+                </div>
+
+                <div
+                  style={{
+                    display: "flex",
+                    alignItems: "center",
+                    justifyContent: "space-between",
+                    gap: 12,
+                  }}
+                >
+                  <div>
+                    <p className="settings-label">Dictation language</p>
+                    <p className="settings-description">
+                      Sent to the STT provider as a hint; setting it helps
+                      accuracy for non-English dictation.
+                    </p>
+                  </div>
+                  <Select
+                    data={STT_LANGUAGE_OPTIONS}
+                    value={settings?.stt_language ?? STT_LANGUAGE_AUTO}
+                    onChange={(value) =>
+                      updateSttLanguage.mutate(
+                        !value || value === STT_LANGUAGE_AUTO ? null : value
+                      )
+                    }
+                    allowDeselect={false}
+                    styles={{
+                      input: {
+                        backgroundColor: "var(--bg-elevated)",
+                        borderColor: "var(--border-default)",
+                        color: "var(--text-primary)",
+                        width: 160,
+                      },
+                    }}
+                  />
+                </div>
+
+                {effectiveSttProvider === "whisper-server" && (
+                  <div
+                    style={{
+                      display: "flex",
+                      alignItems: "center",
+                      justifyContent: "space-between",
+                      gap: 12,
+                    }}
+                  >
+                    <div>
+                      <p className="settings-label">Whisper server URL</p>
+                      <p className="settings-description">
+                        A whisper.cpp server; recordings are posted to its
+                        /inference endpoint.
+                      </p>
+                    </div>
+                    <TextInput
+                      key={settings?.whisper_server_url ?? ""}
+                      defaultValue={settings?.whisper_server_url ?? ""}
+                      placeholder="http://127.0.0.1:8080"
+                      onBlur={(event) => {
+                        const url = event.currentTarget.value.trim();
+                        if (url !== (settings?.whisper_server_url ?? "")) {
+                          updateWhisperServerUrl.mutate(url || null);
+                        }
+                      }}
+                      styles={{
+                        input: {
+                          backgroundColor: "var(--bg-elevated)",
+                          borderColor: "var(--border-default)",
+                          color: "var(--text-primary)",
+                          width: 220,
+                        },
+                      }}
+                    />
+                  </div>
+                )}
+
+                {effectiveSttProvider === "vosk" && (
+                  <div
+                    style={{
+                      display: "flex",
+                      alignItems: "center",
+                      justifyContent: "space-between",
+                      gap: 12,
+                    }}
+                  >
+                    <div>
+                      <p className="settings-label">Vosk model</p>
+                      <p className="settings-description">
+                        Small models are fast on older machines but less
+                        accurate than Whisper.
+                      </p>
+                    </div>
+                    <Group gap={8} wrap="nowrap">
+                      <Select
+                        data={(voskModels ?? []).map((model) => ({
+                          value: model.id,
+                          label: model.is_downloaded
+                            ? model.name
+                            : `${model.name} (not downloaded)`,
+                        }))}
+                        value={selectedVoskModel?.id ?? null}
+                        onChange={(value) => value && updateVoskModel.mutate(value)}
+                        allowDeselect={false}
+                        styles={{
+                          input: {
+                            backgroundColor: "var(--bg-elevated)",
+                            borderColor: "var(--border-default)",
+                            color: "var(--text-primary)",
+                            width: 220,
+                          },
+                        }}
+                      />
+                      {selectedVoskModel && !selectedVoskModel.is_downloaded && (
+                        <Button
+                          variant="default"
+                          loading={downloadVoskModel.isPending}
+                          onClick={() =>
+                            downloadVoskModel.mutate(selectedVoskModel.id)
+                          }
+                        >
+                          Download
+                        </Button>
+                      )}
+                    </Group>
+                  </div>
+                )}
+
+                {(isOpenAiStt || isGroqStt) && (
+                  <>
+                    <div
+                      style={{
+                        display: "flex",
+                        alignItems: "center",
+                        justifyContent: "space-between",
+                        gap: 12,
+                      }}
+                    >
+                      <div>
+                        <p className="settings-label">Temperature</p>
+                        <p className="settings-description">
+                          Whisper sampling temperature (0-1). Leave empty for
+                          the provider default.
+                        </p>
+                      </div>
+                      <NumberInput
+                        value={settings?.stt_temperature ?? ""}
+                        onChange={(value) =>
+                          updateSttTemperature.mutate(
+                            typeof value === "number" ? value : null
+                          )
+                        }
+                        min={0}
+                        max={1}
+                        step={0.1}
+                        decimalScale={2}
+                        placeholder="Default"
+                        styles={{
+                          input: {
+                            backgroundColor: "var(--bg-elevated)",
+                            borderColor: "var(--border-default)",
+                            color: "var(--text-primary)",
+                            width: 100,
+                          },
+                        }}
+                      />
+                    </div>
+
+                    <div
+                      style={{
+                        display: "flex",
+                        alignItems: "center",
+                        justifyContent: "space-between",
+                        gap: 12,
+                      }}
+                    >
+                      <div>
+                        <p className="settings-label">Verbose responses</p>
+                        <p className="settings-description">
+                          Request verbose_json so the request log shows the
+                          detected language and timed segments (whisper-1 and
+                          Groq models).
+                        </p>
+                      </div>
+                      <Switch
+                        checked={settings?.stt_verbose_json ?? false}
+                        onChange={(event) =>
+                          updateSttVerboseJson.mutate(
+                            event.currentTarget.checked
+                          )
+                        }
+                        color="gray"
+                        size="md"
+                      />
+                    </div>
+                  </>
+                )}
+
+                {effectiveSttProvider &&
+                  !LOCAL_STT_PROVIDERS.has(effectiveSttProvider) && (
+                    <div>
+                      <p className="settings-label">Extra request parameters</p>
+                      <p className="settings-description">
+                        One name=value per line, added to every request to this
+                        provider (e.g. diarize=true for Deepgram). A parameter
+                        Tangerine already sends is replaced.
+                      </p>
+                      <Textarea
+                        key={`${effectiveSttProvider}-${formatExtraParams(
+                          settings?.stt_extra_params?.[effectiveSttProvider]
+                        )}`}
+                        defaultValue={formatExtraParams(
+                          settings?.stt_extra_params?.[effectiveSttProvider]
+                        )}
+                        onBlur={(event) => {
+                          const params = parseExtraParams(
+                            event.currentTarget.value
+                          );
+                          const next = { ...(settings?.stt_extra_params ?? {}) };
+                          if (Object.keys(params).length > 0) {
+                            next[effectiveSttProvider] = params;
+                          } else {
+                            delete next[effectiveSttProvider];
+                          }
+                          updateSttExtraParams.mutate(next);
+                        }}
+                        placeholder="name=value"
+                        autosize
+                        minRows={2}
+                        styles={{
+                          input: {
+                            backgroundColor: "var(--bg-elevated)",
+                            borderColor: "var(--border-default)",
+                            color: "var(--text-primary)",
+                            fontFamily: "monospace",
+                            fontSize: "13px",
+                          },
+                        }}
+                      />
+                    </div>
+                  )}
               </div>
             </Accordion.Panel>
           </Accordion.Item>
@@ -2157,6 +2526,101 @@ export function PromptSettings({
         </div>
       )}
 
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">Max tokens</p>
+          <p className="settings-description">
+            Longest rewrite the model may return, in tokens.
+          </p>
+        </div>
+        <NumberInput
+          value={settings?.llm_generation?.max_tokens ?? 4096}
+          onChange={(value) =>
+            typeof value === "number" &&
+            value > 0 &&
+            updateLlmGeneration.mutate({
+              ...settings?.llm_generation,
+              max_tokens: value,
+            })
+          }
+          min={1}
+          step={256}
+          allowDecimal={false}
+          styles={{
+            input: {
+              backgroundColor: "var(--bg-elevated)",
+              borderColor: "var(--border-default)",
+              color: "var(--text-primary)",
+              width: 100,
+            },
+          }}
+        />
+      </div>
+
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">Temperature</p>
+          <p className="settings-description">
+            Sampling temperature (0-2). Leave empty for the provider
+            default.
+          </p>
+        </div>
+        <NumberInput
+          value={settings?.llm_generation?.temperature ?? ""}
+          onChange={(value) =>
+            updateLlmGeneration.mutate({
+              ...settings?.llm_generation,
+              temperature: typeof value === "number" ? value : null,
+            })
+          }
+          min={0}
+          max={2}
+          step={0.1}
+          decimalScale={2}
+          placeholder="Default"
+          styles={{
+            input: {
+              backgroundColor: "var(--bg-elevated)",
+              borderColor: "var(--border-default)",
+              color: "var(--text-primary)",
+              width: 100,
+            },
+          }}
+        />
+      </div>
+
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">Top P</p>
+          <p className="settings-description">
+            Nucleus sampling (0-1). Leave empty for the provider default;
+            ignored by models that don't support it.
+          </p>
+        </div>
+        <NumberInput
+          value={settings?.llm_generation?.top_p ?? ""}
+          onChange={(value) =>
+            updateLlmGeneration.mutate({
+              ...settings?.llm_generation,
+              top_p: typeof value === "number" ? value : null,
+            })
+          }
+          min={0}
+          max={1}
+          step={0.05}
+          decimalScale={2}
+          placeholder="Default"
+          styles={{
+            input: {
+              backgroundColor: "var(--bg-elevated)",
+              borderColor: "var(--border-default)",
+              color: "var(--text-primary)",
+              width: 100,
+            },
+          }}
+        />
+      </div>
+
       <div style={{ marginTop: 16 }}>
         <Accordion variant="separated" radius="md">
           <Accordion.Item value={`${activeProfileId}-test-rewrite`}>
@@ -17,6 +17,8 @@ import {
   useSettings,
   useUpdateAccentColor,
   useUpdateAudioCue,
+  useUpdateFocusGuard,
+  useUpdateOutputFormat,
   useUpdateOutputHitEnter,
   useUpdateOutputMode,
   useUpdateOverlayMode,
@@ -28,6 +30,8 @@ import {
 import { DEFAULT_ACCENT_HEX, applyAccentColor } from "../../lib/accentColor";
 import type {
   AudioCue,
+  FocusGuard,
+  OutputFormat,
   OutputMode,
   OverlayMode,
   PlayingAudioHandling,
@@ -72,6 +76,11 @@ function outputModeToFlags(mode: OutputMode): {
       return { paste: true, clipboard: true };
     case "clipboard":
       return { paste: false, clipboard: true };
+    case "accessibility":
+    case "live_typing":
+      return { paste: true, clipboard: false };
+    case "webhook":
+      return { paste: false, clipboard: false };
     default:
       return { paste: true, clipboard: false };
   }
@@ -85,6 +94,18 @@ function flagsToOutputMode(paste: boolean, clipboard: boolean): OutputMode {
   return "paste";
 }
 
+const OUTPUT_FORMAT_OPTIONS: Array<{ value: OutputFormat; label: string }> = [
+  { value: "as_is", label: "As written" },
+  { value: "plain_text", label: "Plain text" },
+  { value: "html", label: "Rich text" },
+];
+
+const FOCUS_GUARD_OPTIONS: Array<{ value: FocusGuard; label: string }> = [
+  { value: "off", label: "Paste anyway" },
+  { value: "refocus", label: "Switch back" },
+  { value: "clipboard", label: "Copy only" },
+];
+
 const PLAYING_AUDIO_HANDLING_OPTIONS: Array<{
   value: PlayingAudioHandling;
   label: string;
@@ -128,6 +149,8 @@ export function UiSettings({
   const updateOverlayMode = useUpdateOverlayMode();
   const updateWidgetPosition = useUpdateWidgetPosition();
   const updateOutputMode = useUpdateOutputMode();
+  const updateFocusGuard = useUpdateFocusGuard();
+  const updateOutputFormat = useUpdateOutputFormat();
   const updateOutputHitEnter = useUpdateOutputHitEnter();
   const updateRewriteProgramPromptProfiles =
     useUpdateRewriteProgramPromptProfiles();
@@ -219,6 +242,13 @@ export function UiSettings({
 
   const outputFlags = outputModeToFlags(outputMode);
 
+  const globalOutputFormat: OutputFormat = settings?.output_format ?? "as_is";
+  const outputFormat = isProfileScope
+    ? getProfileValue(profile?.output_format, globalOutputFormat)
+    : globalOutputFormat;
+  const outputFormatInheriting =
+    isProfileScope && isInheriting(profile?.output_format);
+
   // Accent color (global only)
   const ACCENT_COLOR_OPTIONS: Array<{ value: string; label: string }> = [
     { value: "tangerine", label: "Tangerine" },
@@ -300,6 +330,16 @@ export function UiSettings({
     updatePlayingAudioHandling.mutate(next);
   };
 
+  const handleOutputFormatChange = (value: string | null) => {
+    if (!value) return;
+    const next = value as OutputFormat;
+    if (isProfileScope) {
+      updateProfile({ output_format: next });
+      return;
+    }
+    updateOutputFormat.mutate(next);
+  };
+
   const handleOverlayModeChange = (value: string | null) => {
     if (!value) return;
     if (isProfileScope) {
@@ -738,6 +778,9 @@ export function UiSettings({
                 { value: "paste", label: "Paste" },
                 { value: "clipboard", label: "Copy" },
                 { value: "paste_and_clipboard", label: "Both" },
+                { value: "accessibility", label: "Insert" },
+                { value: "live_typing", label: "Live" },
+                { value: "webhook", label: "Webhook" },
               ]}
               size="sm"
               radius="md"
@@ -745,7 +788,7 @@ export function UiSettings({
                 root: {
                   backgroundColor: "var(--bg-elevated)",
                   border: "1px solid var(--border-default)",
-                  minWidth: 260,
+                  minWidth: 320,
                 },
               }}
             />
@@ -763,6 +806,90 @@ export function UiSettings({
         </div>
       </div>
 
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">Output format</p>
+          <p className="settings-description">
+            Keep markdown, strip it for plain-text fields, or paste formatted
+            text into rich editors
+          </p>
+        </div>
+        <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+          {isProfileScope && !outputFormatInheriting && (
+            <Tooltip label="Disable override (inherit from Default)" withArrow>
+              <ActionIcon
+                variant="subtle"
+                color="gray"
+                size="sm"
+                disabled={isLoading}
+                onClick={() =>
+                  openDisableOverrideDialog({
+                    title: "Disable Output format override?",
+                    onConfirm: () => updateProfile({ output_format: null }),
+                  })
+                }
+              >
+                <RotateCcw size={14} style={{ opacity: 0.65 }} />
+              </ActionIcon>
+            </Tooltip>
+          )}
+          {outputFormatInheriting && (
+            <Tooltip label={INHERIT_TOOLTIP} withArrow>
+              <Info size={14} style={{ opacity: 0.5, flexShrink: 0 }} />
+            </Tooltip>
+          )}
+          <Select
+            data={OUTPUT_FORMAT_OPTIONS}
+            value={outputFormat}
+            onChange={handleOutputFormatChange}
+            disabled={isLoading}
+            withCheckIcon={false}
+            styles={{
+              input: {
+                backgroundColor: "var(--bg-elevated)",
+                borderColor: "var(--border-default)",
+                color: "var(--text-primary)",
+                minWidth: 180,
+              },
+            }}
+          />
+        </div>
+      </div>
+
+      <div className="settings-row">
+        <div>
+          <p className="settings-label">If the window changed</p>
+          <p className="settings-description">
+            What to do when you switched windows before the text is output
+          </p>
+        </div>
+        <div style={{ display: "flex", alignItems: "center", gap: 8 }}>
+          <Tooltip
+            label={GLOBAL_ONLY_TOOLTIP}
+            disabled={!isProfileScope}
+            withArrow
+          >
+            <Select
+              data={FOCUS_GUARD_OPTIONS}
+              value={settings?.focus_guard ?? "off"}
+              onChange={(value) => {
+                if (value) updateFocusGuard.mutate(value as FocusGuard);
+              }}
+              disabled={isLoading || isProfileScope}
+              withCheckIcon={false}
+              styles={{
+                input: {
+                  backgroundColor: "var(--bg-elevated)",
+                  borderColor: "var(--border-default)",
+                  color: "var(--text-primary)",
+                  minWidth: 180,
+                },
+              }}
+            />
+          </Tooltip>
+        </div>
+      </div>
+
       <div className="settings-row">
         <div>
           <p className="settings-label">Accent color</p>
@@ -24,7 +24,13 @@ export const STT_MODELS: Record<string, ModelOption[]> = {
     { value: "enhanced", label: "Enhanced" },
     { value: "base", label: "Base" },
   ],
+  speechmatics: [
+    { value: "enhanced", label: "Enhanced" },
+    { value: "standard", label: "Standard" },
+  ],
   whisper: [], // Local whisper has its own model management
+  vosk: [], // So does local Vosk
+  "whisper-server": [], // Uses whatever model the server was started with
 };
 
 // Model options for each LLM provider.
@@ -5,6 +5,11 @@ import {
   audioSettingsTestAPI,
   type CleanupPromptSections,
   configAPI,
+  type FocusGuard,
+  type MockProviders,
+  type NetworkConfig,
+  type OutputFormat,
+  type RateLimitConfig,
   type HotkeyConfig,
   llmAPI,
   logsAPI,
@@ -18,6 +23,10 @@ import {
   tauriAPI,
   type TestLlmRewriteResponse,
   validateHotkeyNotDuplicate,
+  voskAPI,
+  type WhisperDecodingParams,
+  type LlmGenerationParams,
+  type WhisperThreading,
   type WidgetPosition,
 } from "./tauri";
 
@@ -283,6 +292,53 @@ export function useUpdateOutputHitEnter() {
   });
 }
 
+export function useUpdateOutputFormat() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (format: OutputFormat) => tauriAPI.updateOutputFormat(format),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateFocusGuard() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (guard: FocusGuard) => tauriAPI.updateFocusGuard(guard),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateMockProvidersEnabled() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (enabled: boolean) => {
+      await tauriAPI.updateMockProvidersEnabled(enabled);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+      queryClient.invalidateQueries({ queryKey: ["availableProviders"] });
+    },
+  });
+}
+
+export function useUpdateMockProviders() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (providers: MockProviders) => {
+      await tauriAPI.updateMockProviders(providers);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
 export function useUpdateQuietAudioGateEnabled() {
   const queryClient = useQueryClient();
   return useMutation({
@@ -374,6 +430,45 @@ export function useUpdateQuietAudioRequireSpeech() {
   });
 }
 
+export function useUpdateHallucinationFilterEnabled() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (enabled: boolean) => {
+      await tauriAPI.updateHallucinationFilterEnabled(enabled);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateHallucinationFilterPhrases() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (phrases: string[]) => {
+      await tauriAPI.updateHallucinationFilterPhrases(phrases);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateStopAtMaxDuration() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (enabled: boolean) => {
+      await tauriAPI.updateStopAtMaxDuration(enabled);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
 export function useUpdateAudioDownmixToMono() {
   const queryClient = useQueryClient();
   return useMutation({
@@ -590,6 +685,8 @@ export function useUpdateRewriteProgramPromptProfiles() {
     mutationFn: async (profiles: RewriteProgramPromptProfile[]) => {
       await tauriAPI.updateRewriteProgramPromptProfiles(profiles);
       await configAPI.syncPipelineConfig();
+      // Profiles can carry their own hotkeys.
+      await tauriAPI.registerShortcuts();
     },
     onMutate: async (nextProfiles: RewriteProgramPromptProfile[]) => {
       // Optimistically update the settings cache so toggles/selects don't
@@ -746,6 +843,196 @@ export function useUpdateSTTTranscriptionPrompt() {
   });
 }
 
+export function useUpdateSttVocabularyBoost() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (enabled: boolean) => {
+      await tauriAPI.updateSttVocabularyBoost(enabled);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateSttContextSecs() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (seconds: number) => {
+      await tauriAPI.updateSttContextSecs(seconds);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateSttLanguage() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (language: string | null) => {
+      await tauriAPI.updateSttLanguage(language);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateWhisperServerUrl() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (url: string | null) => {
+      await tauriAPI.updateWhisperServerUrl(url);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateSttTemperature() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (temperature: number | null) => {
+      await tauriAPI.updateSttTemperature(temperature);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateSttVerboseJson() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (enabled: boolean) => {
+      await tauriAPI.updateSttVerboseJson(enabled);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateSttExtraParams() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (extraParams: Record<string, Record<string, string>>) => {
+      await tauriAPI.updateSttExtraParams(extraParams);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateLlmGeneration() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (params: Partial<LlmGenerationParams>) => {
+      await tauriAPI.updateLlmGeneration(params);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateWhisperDecoding() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (params: Partial<WhisperDecodingParams>) => {
+      await tauriAPI.updateWhisperDecoding(params);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateWhisperThreading() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (threading: Partial<WhisperThreading>) => {
+      await tauriAPI.updateWhisperThreading(threading);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateVoskModel() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (modelId: string | null) => {
+      await tauriAPI.updateVoskModel(modelId);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useVoskModels() {
+  return useQuery({
+    queryKey: ["voskModels"],
+    queryFn: () => voskAPI.getModels(),
+  });
+}
+
+export function useDownloadVoskModel() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (modelId: string) => {
+      await voskAPI.downloadModel(modelId);
+      // Picks up the newly downloaded model.
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["voskModels"] });
+    },
+  });
+}
+
+export function useUpdateNetworkConfig() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (config: NetworkConfig) => {
+      await tauriAPI.updateNetworkConfig(config);
+      await configAPI.syncPipelineConfig();
+    },
+    onSettled: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
+export function useUpdateRateLimit() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: async (config: Partial<RateLimitConfig>) => {
+      await tauriAPI.updateRateLimit(config);
+      await configAPI.syncPipelineConfig();
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["settings"] });
+    },
+  });
+}
+
 export function useUpdateLLMProvider() {
 	const queryClient = useQueryClient();
 	return useMutation({
@@ -870,3 +1157,15 @@ export function useRetryTranscription() {
     },
   });
 }
+
+// Transcribe a recording left unfinished when the app last exited.
+export function useRecoverOrphanedRecording() {
+  const queryClient = useQueryClient();
+  return useMutation({
+    mutationFn: (id: string) => sttAPI.recoverOrphanedRecording(id),
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["history"] });
+      queryClient.invalidateQueries({ queryKey: ["requestLogs"] });
+    },
+  });
+}
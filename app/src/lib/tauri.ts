@@ -41,6 +41,47 @@ interface HistoryEntry {
   stt_model?: string | null;
   llm_provider?: string | null;
   llm_model?: string | null;
+  audio_duration_secs?: number;
+  tags?: string[];
+  notes?: string | null;
+}
+
+/** Time range for history search (ISO 8601); either end may be open */
+export interface HistoryDateRange {
+  from?: string | null;
+  to?: string | null;
+}
+
+/** A full-text search result; matched terms in `snippet` are wrapped in `**` */
+export interface TranscriptHit {
+  id: string;
+  source: "history" | "request_log";
+  timestamp: string;
+  snippet: string;
+  score: number;
+}
+
+export type StatsRange = "day" | "week" | "month" | "year" | "all";
+
+export interface DayStats {
+  date: string;
+  dictations: number;
+  words: number;
+  characters: number;
+  speaking_secs: number;
+}
+
+export interface DictationStats {
+  range: StatsRange;
+  dictations: number;
+  words: number;
+  characters: number;
+  speaking_secs: number;
+  words_timed: number;
+  average_wpm: number | null;
+  typing_wpm: number;
+  minutes_saved: number | null;
+  per_day: DayStats[];
 }
 
 export interface PromptSection {
@@ -61,6 +102,8 @@ export interface CleanupPromptSectionsOverride {
   dictionary?: PromptSection | null;
 }
 
+export type QualityTier = "fast" | "balanced" | "accurate";
+
 export interface RewriteProgramPromptProfile {
   id: string;
   name: string;
@@ -76,6 +119,10 @@ export interface RewriteProgramPromptProfile {
   stt_timeout_seconds?: number | null;
   llm_provider?: string | null;
   llm_model?: string | null;
+  // Fills in any provider/model left unset above from the central `quality_tiers` setting
+  quality_tier?: QualityTier | null;
+  // Mask PII before cloud LLM calls and in request logs (null = global `pii_redaction.enabled`)
+  redact_pii?: boolean | null;
 
   // Per-profile overrides for UI (Option 1: override-or-inherit)
   // NOTE: These are persisted in settings.json as part of the profile object.
@@ -89,6 +136,32 @@ export interface RewriteProgramPromptProfile {
   // After paste, optionally press Enter.
   // (May be ignored by backend until runtime/profile routing supports it.)
   output_hit_enter?: boolean | null;
+
+  // Markdown handling of the output (null = global `output_format`)
+  output_format?: OutputFormat | null;
+
+  // Also POST transcripts dictated into this program to this URL
+  webhook_url?: string | null;
+
+  // VAD settings while recording with this profile (null = global `vad_settings`)
+  vad_settings?: VadSettings | null;
+  // Toggle hotkey that records with this profile whatever app is in the foreground
+  hotkey?: HotkeyConfig | null;
+}
+
+// What ends the recording for auto-stop: the local VAD, or the STT provider's endpointing
+// on audio streamed to it (Deepgram; other providers use the local VAD)
+export type Endpointing = "local" | "provider";
+
+export interface VadSettings {
+  enabled: boolean;
+  auto_stop: boolean;
+  aggressiveness: number;
+  speech_frames_threshold: number;
+  hangover_frames: number;
+  pre_roll_ms: number;
+  adaptive: boolean;
+  endpointing?: Endpointing;
 }
 
 export type PlayingAudioHandling = "none" | "mute" | "pause" | "mute_and_pause";
@@ -106,7 +179,81 @@ export type WidgetPosition =
   | "bottom-center"
   | "bottom-right";
 
-export type OutputMode = "paste" | "paste_and_clipboard" | "clipboard";
+export type OutputMode =
+  | "paste"
+  | "paste_and_clipboard"
+  | "clipboard"
+  // Accessibility-API insertion (macOS/Windows); falls back to paste
+  | "accessibility"
+  // Types partial transcripts while recording, then corrects them to the final one
+  | "live_typing"
+  // POSTs the transcript to the `output_webhook` URL instead of typing it
+  | "webhook";
+
+/** How markdown in the output is handled: kept, stripped, or pasted as rich text */
+export type OutputFormat = "as_is" | "plain_text" | "html";
+
+function normalizeOutputFormat(value: unknown): OutputFormat | null {
+  return value === "as_is" || value === "plain_text" || value === "html"
+    ? value
+    : null;
+}
+
+/** What to do when the window active at recording start lost focus before output */
+export type FocusGuard = "off" | "refocus" | "clipboard";
+
+/** Per-call behavior of a mock provider (cycled) */
+export type MockOutcome = "ok" | "transient" | "fatal" | "hang";
+
+export interface MockScript {
+  /** Canned responses, cycled (empty: fixed STT sentence / echoed LLM input) */
+  responses: string[];
+  latency_ms: number;
+  outcomes: MockOutcome[];
+}
+
+export interface MockProviders {
+  stt: MockScript;
+  llm: MockScript;
+}
+
+export interface QueuedOutput {
+  id: number;
+  mode: OutputMode;
+  chars: number;
+  queued_at: string;
+}
+
+export interface OutputQueueState {
+  /** Output currently being typed/pasted */
+  active: QueuedOutput | null;
+  /** Outputs waiting behind it, oldest first */
+  pending: QueuedOutput[];
+}
+
+export type InjectionBackend = "enigo" | "wtype" | "ydotool" | "clipboard_only";
+
+export interface TypingConfig {
+  initial_delay_ms: number;
+  key_delay_ms: number;
+  clipboard_settle_ms: number;
+  clipboard_restore_delay_ms: number;
+  /** 0 pastes the whole text at once. */
+  paste_chunk_chars: number;
+  chunk_delay_ms: number;
+  /** null picks a backend for the session (wtype/ydotool on Wayland). */
+  backend: InjectionBackend | null;
+}
+
+export const defaultTypingConfig: TypingConfig = {
+  initial_delay_ms: 0,
+  key_delay_ms: 50,
+  clipboard_settle_ms: 50,
+  clipboard_restore_delay_ms: 100,
+  paste_chunk_chars: 0,
+  chunk_delay_ms: 100,
+  backend: null,
+};
 
 export type TranscriptionRetentionUnit = "days" | "hours";
 
@@ -122,11 +269,76 @@ export type OpenAiReasoningEffort =
   | "high"
   | "xhigh";
 
+/** Stable codes of the `AppError` returned by every Tauri command. */
+export type AppErrorCode =
+  | "no_microphone"
+  | "audio"
+  | "already_recording"
+  | "not_recording"
+  | "no_provider"
+  | "missing_api_key"
+  | "invalid_api_key"
+  | "rate_limited"
+  | "network"
+  | "provider_error"
+  | "timeout"
+  | "cancelled"
+  | "recording_too_large"
+  | "config"
+  | "internal"
+  | "invalid_settings"
+  | "unknown";
+
+/** A settings value rejected by the backend schema (`vad_settings.aggressiveness`, ...). */
+export interface SettingsValidationError {
+  field: string;
+  message: string;
+}
+
+/**
+ * How imported settings combine with local ones: "merge" combines lists (prompt profiles,
+ * phrases, ...) by entry `id` and overwrites other values.
+ */
+export type SettingsImportConflict = "overwrite" | "keep_local" | "merge";
+
+export interface SettingsImportSummary {
+  imported: string[];
+  unchanged: string[];
+  /** Hooks, plugins and webhooks in the file, which are never imported. */
+  skipped: string[];
+}
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  /** Only for `invalid_settings`. */
+  errors?: SettingsValidationError[];
+}
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    !!error &&
+    typeof error === "object" &&
+    typeof (error as { code?: unknown }).code === "string" &&
+    typeof (error as { message?: unknown }).message === "string"
+  );
+}
+
+/** Human-readable message of a rejected `invoke` (or any other thrown value). */
+export function errorMessage(error: unknown): string {
+  if (isAppError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return String(error);
+}
+
 function normalizeOutputMode(value: unknown): OutputMode {
   if (
     value === "paste" ||
     value === "paste_and_clipboard" ||
-    value === "clipboard"
+    value === "clipboard" ||
+    value === "accessibility" ||
+    value === "live_typing" ||
+    value === "webhook"
   ) {
     return value;
   }
@@ -155,6 +367,29 @@ export interface AppSettings {
   stt_model: string | null;
   // Global STT prompt (applies to all transcriptions when supported by the selected provider/model)
   stt_transcription_prompt: string | null;
+  // Send Personal Dictionary terms to the STT provider as recognition hints.
+  stt_vocabulary_boost: boolean;
+  // Seconds of recent dictation passed to STT as context (0 = off).
+  stt_context_secs: number;
+  // Dictation language hint sent to the STT provider; null = detect.
+  stt_language: string | null;
+  // whisper.cpp server URL for the "whisper-server" provider; null = localhost:8080.
+  whisper_server_url: string | null;
+  // Extra request parameters per STT provider id, sent as is.
+  stt_extra_params: Record<string, Record<string, string>>;
+  // OpenAI/Groq Whisper sampling temperature (0-1); null = provider default.
+  stt_temperature: number | null;
+  // Ask OpenAI/Groq Whisper for verbose_json (detected language and segments).
+  stt_verbose_json: boolean;
+  // Local Whisper decoding parameters; omitted keys use whisper.cpp defaults.
+  whisper_decoding: Partial<WhisperDecodingParams>;
+  whisper_threading: Partial<WhisperThreading>;
+  // Local Vosk model id (see voskAPI.getModels); null = the small English model.
+  vosk_model: string | null;
+  // Proxy and TLS settings applied to every HTTP client.
+  network_config: NetworkConfig;
+  // Client-side limits for provider requests; omitted keys use the defaults.
+  rate_limit: Partial<RateLimitConfig>;
   llm_provider: string | null;
   llm_model: string | null;
 
@@ -164,6 +399,8 @@ export interface AppSettings {
   anthropic_thinking_budget: number | null;
   gemini_thinking_budget: number | null;
   gemini_thinking_level: "minimal" | "low" | "medium" | "high" | null;
+  // LLM max tokens, temperature and top_p; null/omitted = provider default.
+  llm_generation: Partial<LlmGenerationParams>;
 
   playing_audio_handling: PlayingAudioHandling;
   stt_timeout_seconds: number | null;
@@ -171,6 +408,9 @@ export interface AppSettings {
   widget_position: WidgetPosition;
   output_mode: OutputMode;
   output_hit_enter: boolean;
+  output_typing: TypingConfig;
+  output_format: OutputFormat;
+  focus_guard: FocusGuard;
 
   // Hallucination protection (quiet-audio gate)
   quiet_audio_gate_enabled: boolean;
@@ -179,6 +419,17 @@ export interface AppSettings {
   quiet_audio_peak_dbfs_threshold: number;
   // Extra protection: if enabled, also require that VAD detects speech.
   quiet_audio_require_speech: boolean;
+  // Drop "Thanks for watching!"-style Whisper output on short/quiet recordings.
+  hallucination_filter_enabled: boolean;
+  // Extra phrases for the hallucination filter (on top of the built-in list).
+  hallucination_filter_phrases: string[];
+
+  // End (and transcribe) recordings at the max duration instead of keeping the latest audio
+  stop_at_max_duration: boolean;
+
+  // Debug: offer the scripted "mock" STT/LLM providers
+  mock_providers_enabled: boolean;
+  mock_providers: MockProviders;
 
   // Experimental: noise gate threshold (dBFS). null means off.
   noise_gate_threshold_dbfs: number | null;
@@ -206,8 +457,20 @@ export interface AppSettings {
   request_logs_retention_amount: number;
   // Only used when mode === "time" (0 = forever)
   request_logs_retention_days: number;
+
+  // Level of the application log (stderr + rotating JSONL files in app data)
+  log_level: AppLogLevel;
+
+  // Opt-in metrics export (applied on restart): Prometheus endpoint on
+  // 127.0.0.1:metrics_port, or OTLP/HTTP push to metrics_otlp_endpoint
+  metrics_exporter: MetricsExporter;
+  metrics_port: number;
+  metrics_otlp_endpoint: string;
 }
 
+export type AppLogLevel = "error" | "warn" | "info" | "debug" | "trace";
+export type MetricsExporter = "off" | "prometheus" | "otlp";
+
 function normalizePlayingAudioHandling(value: unknown): PlayingAudioHandling {
   if (
     value === "none" ||
@@ -380,6 +643,26 @@ function normalizeRequestLogsRetentionDays(value: unknown): number {
   return Math.min(36500, Math.max(0, rounded));
 }
 
+function normalizeLogLevel(value: unknown): AppLogLevel {
+  if (
+    value === "error" ||
+    value === "warn" ||
+    value === "info" ||
+    value === "debug" ||
+    value === "trace"
+  ) {
+    return value;
+  }
+  return "info";
+}
+
+function normalizeMetricsExporter(value: unknown): MetricsExporter {
+  if (value === "prometheus" || value === "otlp") {
+    return value;
+  }
+  return "off";
+}
+
 // ============================================================================
 // Default values - must match Rust defaults
 // ============================================================================
@@ -423,6 +706,12 @@ async function getStore(): Promise<Store> {
   return storeInstance;
 }
 
+// The store plugin's own save() rewrites settings.json in place; the backend replaces it
+// atomically instead.
+async function saveStore(): Promise<void> {
+  await invoke("save_settings");
+}
+
 // ============================================================================
 // Hotkey validation helpers (Zod-based)
 // ============================================================================
@@ -511,6 +800,52 @@ export const tauriAPI = {
     return listen("recording-stop", callback);
   },
 
+  /** Fired when the focus guard copied a transcript instead of pasting it (payload: reason). */
+  async onOutputRedirectedToClipboard(
+    callback: (reason: string) => void
+  ): Promise<UnlistenFn> {
+    return listen<string>("output-redirected-to-clipboard", (event) => {
+      callback(event.payload);
+    });
+  },
+
+  /** Fired when a paste couldn't restore the previous clipboard content (payload: reason). */
+  async onClipboardRestoreSkipped(
+    callback: (reason: string) => void
+  ): Promise<UnlistenFn> {
+    return listen<string>("clipboard-restore-skipped", (event) => {
+      callback(event.payload);
+    });
+  },
+
+  /** Fired when the watchdog reset a pipeline that stopped making progress. */
+  async onPipelineRecovered(
+    callback: (payload: PipelineRecovered) => void
+  ): Promise<UnlistenFn> {
+    return listen<PipelineRecovered>("pipeline-recovered", (event) => {
+      callback(event.payload);
+    });
+  },
+
+  /** Fired when a recording was ended because it reached the maximum duration. */
+  async onMaxDurationReached(callback: () => void): Promise<UnlistenFn> {
+    return listen("recording-max-duration-reached", callback);
+  },
+
+  /** Fired when a recording held no speech, so it was not sent for transcription. */
+  async onEmptyRecording(callback: () => void): Promise<UnlistenFn> {
+    return listen("pipeline-empty-recording", callback);
+  },
+
+  /** Fired when the input device was lost mid-recording and capture moved to another one. */
+  async onAudioDeviceSwitched(
+    callback: (payload: { lost: string; switched_to: string }) => void
+  ): Promise<UnlistenFn> {
+    return listen<{ lost: string; switched_to: string }>("audio-device-switched", (event) => {
+      callback(event.payload);
+    });
+  },
+
   // Settings API - using store plugin directly
   async getSettings(): Promise<AppSettings> {
     const store = await getStore();
@@ -589,6 +924,16 @@ export const tauriAPI = {
           : null;
       const llm_model =
         typeof (p as any).llm_model === "string" ? (p as any).llm_model : null;
+      const quality_tier =
+        (p as any).quality_tier === "fast" ||
+        (p as any).quality_tier === "balanced" ||
+        (p as any).quality_tier === "accurate"
+          ? ((p as any).quality_tier as QualityTier)
+          : null;
+      const redact_pii =
+        typeof (p as any).redact_pii === "boolean"
+          ? (p as any).redact_pii
+          : null;
       const rewrite_llm_enabled =
         typeof (p as any).rewrite_llm_enabled === "boolean"
           ? (p as any).rewrite_llm_enabled
@@ -638,6 +983,8 @@ export const tauriAPI = {
           ? (p as any).output_hit_enter
           : null;
 
+      const output_format = normalizeOutputFormat((p as any).output_format);
+
       if (!id) return null;
 
       return {
@@ -651,12 +998,15 @@ export const tauriAPI = {
         stt_timeout_seconds,
         llm_provider,
         llm_model,
+        quality_tier,
+        redact_pii,
         sound_enabled,
         playing_audio_handling,
         overlay_mode,
         widget_position,
         output_mode,
         output_hit_enter,
+        output_format,
       };
     };
 
@@ -695,6 +1045,34 @@ export const tauriAPI = {
       stt_model: (await store.get<string | null>("stt_model")) ?? null,
       stt_transcription_prompt:
         (await store.get<string | null>("stt_transcription_prompt")) ?? null,
+      stt_vocabulary_boost:
+        (await store.get<boolean>("stt_vocabulary_boost")) ?? true,
+      stt_context_secs: (await store.get<number>("stt_context_secs")) ?? 0,
+      stt_language: (await store.get<string | null>("stt_language")) ?? null,
+      whisper_server_url:
+        (await store.get<string | null>("whisper_server_url")) ?? null,
+      stt_extra_params:
+        (await store.get<Record<string, Record<string, string>>>(
+          "stt_extra_params"
+        )) ?? {},
+      stt_temperature:
+        (await store.get<number | null>("stt_temperature")) ?? null,
+      llm_generation:
+        (await store.get<Partial<LlmGenerationParams>>("llm_generation")) ??
+        {},
+      stt_verbose_json: (await store.get<boolean>("stt_verbose_json")) ?? false,
+      whisper_decoding:
+        (await store.get<Partial<WhisperDecodingParams>>("whisper_decoding")) ??
+        {},
+      whisper_threading:
+        (await store.get<Partial<WhisperThreading>>("whisper_threading")) ?? {},
+      vosk_model: (await store.get<string | null>("vosk_model")) ?? null,
+      network_config: {
+        ...DEFAULT_NETWORK_CONFIG,
+        ...((await store.get<Partial<NetworkConfig>>("network_config")) ?? {}),
+      },
+      rate_limit:
+        (await store.get<Partial<RateLimitConfig>>("rate_limit")) ?? {},
       llm_provider: (await store.get<string | null>("llm_provider")) ?? null,
       llm_model: (await store.get<string | null>("llm_model")) ?? null,
       openai_reasoning_effort: normalizeOpenAiReasoningEffort(
@@ -724,6 +1102,13 @@ export const tauriAPI = {
         (await store.get<WidgetPosition>("widget_position")) ?? "bottom-center",
       output_mode: normalizeOutputMode(await store.get("output_mode")),
       output_hit_enter: (await store.get<boolean>("output_hit_enter")) ?? false,
+      output_typing: {
+        ...defaultTypingConfig,
+        ...((await store.get<Partial<TypingConfig>>("output_typing")) ?? {}),
+      },
+      output_format:
+        normalizeOutputFormat(await store.get("output_format")) ?? "as_is",
+      focus_guard: (await store.get<FocusGuard>("focus_guard")) ?? "off",
 
       quiet_audio_gate_enabled:
         (await store.get<boolean>("quiet_audio_gate_enabled")) ?? true,
@@ -735,6 +1120,16 @@ export const tauriAPI = {
         (await store.get<number>("quiet_audio_peak_dbfs_threshold")) ?? -50,
       quiet_audio_require_speech:
         (await store.get<boolean>("quiet_audio_require_speech")) ?? false,
+      hallucination_filter_enabled:
+        (await store.get<boolean>("hallucination_filter_enabled")) ?? true,
+      hallucination_filter_phrases:
+        (await store.get<string[]>("hallucination_filter_phrases")) ?? [],
+      mock_providers_enabled:
+        (await store.get<boolean>("mock_providers_enabled")) ?? false,
+      mock_providers: (await store.get<MockProviders>("mock_providers")) ?? {
+        stt: { responses: [], latency_ms: 0, outcomes: [] },
+        llm: { responses: [], latency_ms: 0, outcomes: [] },
+      },
 
       noise_gate_threshold_dbfs: await(async () => {
         const configured = normalizeNoiseGateThresholdDbfs(
@@ -749,10 +1144,13 @@ export const tauriAPI = {
         return noiseGateStrengthToThresholdDbfs(legacyStrength);
       })(),
 
+      stop_at_max_duration:
+        (await store.get<boolean>("stop_at_max_duration")) ?? false,
+
       audio_downmix_to_mono:
         (await store.get<boolean>("audio_downmix_to_mono")) ?? true,
       audio_resample_to_16khz:
-        (await store.get<boolean>("audio_resample_to_16khz")) ?? false,
+        (await store.get<boolean>("audio_resample_to_16khz")) ?? true,
       audio_highpass_enabled:
         (await store.get<boolean>("audio_highpass_enabled")) ?? true,
       audio_agc_enabled:
@@ -774,6 +1172,12 @@ export const tauriAPI = {
         await store.get("request_logs_retention_days")
       ),
 
+      log_level: normalizeLogLevel(await store.get("log_level")),
+      metrics_exporter: normalizeMetricsExporter(await store.get("metrics_exporter")),
+      metrics_port: (await store.get<number>("metrics_port")) ?? 9464,
+      metrics_otlp_endpoint:
+        (await store.get<string>("metrics_otlp_endpoint")) ?? "http://127.0.0.1:4318/v1/metrics",
+
       // Time retention: new (unit+value), with legacy fallback to transcription_retention_days.
       ...await(async () => {
         const rawUnit = await store.get("transcription_retention_unit");
@@ -824,7 +1228,7 @@ export const tauriAPI = {
       await store.set("accent_color", normalized);
     }
 
-    await store.save();
+    await saveStore();
 
     // Notify other windows (overlay) to refresh cached settings.
     // Include the new accent in the payload so the overlay can update immediately
@@ -835,25 +1239,25 @@ export const tauriAPI = {
   async updateToggleHotkey(hotkey: HotkeyConfig): Promise<void> {
     const store = await getStore();
     await store.set("toggle_hotkey", hotkey);
-    await store.save();
+    await saveStore();
   },
 
   async updateHoldHotkey(hotkey: HotkeyConfig): Promise<void> {
     const store = await getStore();
     await store.set("hold_hotkey", hotkey);
-    await store.save();
+    await saveStore();
   },
 
   async updatePasteLastHotkey(hotkey: HotkeyConfig): Promise<void> {
     const store = await getStore();
     await store.set("paste_last_hotkey", hotkey);
-    await store.save();
+    await saveStore();
   },
 
   async updateSelectedMic(micId: string | null): Promise<void> {
     const store = await getStore();
     await store.set("selected_mic_id", micId);
-    await store.save();
+    await saveStore();
 
     // Notify other windows (overlay) to refresh cached settings.
     await emit("settings-changed", {});
@@ -862,13 +1266,13 @@ export const tauriAPI = {
   async updateSoundEnabled(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("sound_enabled", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateAudioCue(cue: AudioCue): Promise<void> {
     const store = await getStore();
     await store.set("audio_cue", normalizeAudioCue(cue));
-    await store.save();
+    await saveStore();
 
     // Notify other windows (overlay) to refresh cached settings.
     await emit("settings-changed", {});
@@ -877,7 +1281,7 @@ export const tauriAPI = {
   async updateRewriteLlmEnabled(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("rewrite_llm_enabled", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateCleanupPromptSections(
@@ -885,7 +1289,7 @@ export const tauriAPI = {
   ): Promise<void> {
     const store = await getStore();
     await store.set("cleanup_prompt_sections", sections);
-    await store.save();
+    await saveStore();
   },
 
   async updateRewriteProgramPromptProfiles(
@@ -893,7 +1297,7 @@ export const tauriAPI = {
   ): Promise<void> {
     const store = await getStore();
     await store.set("rewrite_program_prompt_profiles", profiles);
-    await store.save();
+    await saveStore();
   },
 
   async listOpenWindows(): Promise<OpenWindowInfo[]> {
@@ -904,34 +1308,120 @@ export const tauriAPI = {
     return invoke("get_foreground_process_path");
   },
 
+  async getPermissionStatus(): Promise<PermissionStatus> {
+    return invoke("get_permission_status");
+  },
+
+  async openPermissionSettings(kind: PermissionKind): Promise<void> {
+    return invoke("open_permission_settings", { kind });
+  },
+
   async updateSTTProvider(provider: string | null): Promise<void> {
     const store = await getStore();
     await store.set("stt_provider", provider);
-    await store.save();
+    await saveStore();
   },
 
   async updateSTTModel(model: string | null): Promise<void> {
     const store = await getStore();
     await store.set("stt_model", model);
-    await store.save();
+    await saveStore();
   },
 
   async updateSTTTranscriptionPrompt(prompt: string | null): Promise<void> {
     const store = await getStore();
     await store.set("stt_transcription_prompt", prompt);
-    await store.save();
+    await saveStore();
+  },
+
+  async updateSttVocabularyBoost(enabled: boolean): Promise<void> {
+    const store = await getStore();
+    await store.set("stt_vocabulary_boost", enabled);
+    await saveStore();
+  },
+
+  async updateSttContextSecs(seconds: number): Promise<void> {
+    const store = await getStore();
+    await store.set("stt_context_secs", Math.max(0, Math.round(seconds)));
+    await saveStore();
+  },
+
+  async updateSttLanguage(language: string | null): Promise<void> {
+    const store = await getStore();
+    await store.set("stt_language", language);
+    await saveStore();
+  },
+
+  async updateWhisperServerUrl(url: string | null): Promise<void> {
+    const store = await getStore();
+    await store.set("whisper_server_url", url);
+    await saveStore();
+  },
+
+  async updateSttTemperature(temperature: number | null): Promise<void> {
+    const store = await getStore();
+    await store.set("stt_temperature", temperature);
+    await saveStore();
+  },
+
+  async updateSttVerboseJson(enabled: boolean): Promise<void> {
+    const store = await getStore();
+    await store.set("stt_verbose_json", enabled);
+    await saveStore();
+  },
+
+  async updateSttExtraParams(
+    extraParams: Record<string, Record<string, string>>
+  ): Promise<void> {
+    const store = await getStore();
+    await store.set("stt_extra_params", extraParams);
+    await saveStore();
+  },
+
+  async updateWhisperDecoding(
+    params: Partial<WhisperDecodingParams>
+  ): Promise<void> {
+    const store = await getStore();
+    await store.set("whisper_decoding", params);
+    await saveStore();
+  },
+
+  async updateWhisperThreading(
+    threading: Partial<WhisperThreading>
+  ): Promise<void> {
+    const store = await getStore();
+    await store.set("whisper_threading", threading);
+    await saveStore();
+  },
+
+  async updateVoskModel(modelId: string | null): Promise<void> {
+    const store = await getStore();
+    await store.set("vosk_model", modelId);
+    await saveStore();
+  },
+
+  async updateNetworkConfig(config: NetworkConfig): Promise<void> {
+    const store = await getStore();
+    await store.set("network_config", config);
+    await saveStore();
+  },
+
+  async updateRateLimit(config: Partial<RateLimitConfig>): Promise<void> {
+    const store = await getStore();
+    await store.set("rate_limit", config);
+    await saveStore();
   },
 
   async updateLLMProvider(provider: string | null): Promise<void> {
     const store = await getStore();
     await store.set("llm_provider", provider);
-    await store.save();
+    await saveStore();
   },
 
   async updateLLMModel(model: string | null): Promise<void> {
     const store = await getStore();
     await store.set("llm_model", model);
-    await store.save();
+    await saveStore();
   },
 
   async updateOpenAiReasoningEffort(
@@ -946,7 +1436,15 @@ export const tauriAPI = {
         normalizeOpenAiReasoningEffort(effort)
       );
     }
-    await store.save();
+    await saveStore();
+  },
+
+  async updateLlmGeneration(
+    params: Partial<LlmGenerationParams>
+  ): Promise<void> {
+    const store = await getStore();
+    await store.set("llm_generation", params);
+    await saveStore();
   },
 
   async updateAnthropicThinkingBudget(budget: number | null): Promise<void> {
@@ -959,7 +1457,7 @@ export const tauriAPI = {
         normalizeAnthropicThinkingBudget(budget)
       );
     }
-    await store.save();
+    await saveStore();
   },
 
   async updateGeminiThinkingBudget(budget: number | null): Promise<void> {
@@ -972,7 +1470,7 @@ export const tauriAPI = {
         normalizeGeminiThinkingBudget(budget)
       );
     }
-    await store.save();
+    await saveStore();
   },
 
   async updateGeminiThinkingLevel(
@@ -987,7 +1485,7 @@ export const tauriAPI = {
         normalizeGeminiThinkingLevel(level)
       );
     }
-    await store.save();
+    await saveStore();
   },
 
   async updatePlayingAudioHandling(
@@ -995,19 +1493,19 @@ export const tauriAPI = {
   ): Promise<void> {
     const store = await getStore();
     await store.set("playing_audio_handling", handling);
-    await store.save();
+    await saveStore();
   },
 
   async updateSTTTimeout(timeoutSeconds: number | null): Promise<void> {
     const store = await getStore();
     await store.set("stt_timeout_seconds", timeoutSeconds);
-    await store.save();
+    await saveStore();
   },
 
   async updateOverlayMode(mode: OverlayMode): Promise<void> {
     const store = await getStore();
     await store.set("overlay_mode", mode);
-    await store.save();
+    await saveStore();
     // Apply the mode immediately
     await invoke("set_overlay_mode", { mode });
 
@@ -1018,7 +1516,7 @@ export const tauriAPI = {
   async updateWidgetPosition(position: WidgetPosition): Promise<void> {
     const store = await getStore();
     await store.set("widget_position", position);
-    await store.save();
+    await saveStore();
     // Apply the position immediately
     await invoke("set_widget_position", { position });
 
@@ -1029,43 +1527,93 @@ export const tauriAPI = {
   async updateOutputMode(mode: OutputMode): Promise<void> {
     const store = await getStore();
     await store.set("output_mode", mode);
-    await store.save();
+    await saveStore();
   },
 
   async updateOutputHitEnter(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("output_hit_enter", enabled);
-    await store.save();
+    await saveStore();
+  },
+
+  async updateOutputFormat(format: OutputFormat): Promise<void> {
+    const store = await getStore();
+    await store.set("output_format", format);
+    await saveStore();
+  },
+
+  async updateFocusGuard(guard: FocusGuard): Promise<void> {
+    const store = await getStore();
+    await store.set("focus_guard", guard);
+    await saveStore();
+  },
+
+  async updateMockProvidersEnabled(enabled: boolean): Promise<void> {
+    const store = await getStore();
+    await store.set("mock_providers_enabled", enabled);
+    await saveStore();
+  },
+
+  async updateMockProviders(providers: MockProviders): Promise<void> {
+    const store = await getStore();
+    await store.set("mock_providers", providers);
+    await saveStore();
+  },
+
+  async getInjectionBackend(): Promise<InjectionBackend> {
+    return invoke("get_injection_backend");
+  },
+
+  async getOutputQueue(): Promise<OutputQueueState> {
+    return invoke("get_output_queue");
+  },
+
+  async updateOutputTyping(config: TypingConfig): Promise<void> {
+    const store = await getStore();
+    await store.set("output_typing", config);
+    await saveStore();
   },
 
   async updateQuietAudioGateEnabled(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("quiet_audio_gate_enabled", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateQuietAudioMinDurationSecs(seconds: number): Promise<void> {
     const store = await getStore();
     await store.set("quiet_audio_min_duration_secs", seconds);
-    await store.save();
+    await saveStore();
   },
 
   async updateQuietAudioRmsDbfsThreshold(dbfs: number): Promise<void> {
     const store = await getStore();
     await store.set("quiet_audio_rms_dbfs_threshold", dbfs);
-    await store.save();
+    await saveStore();
   },
 
   async updateQuietAudioPeakDbfsThreshold(dbfs: number): Promise<void> {
     const store = await getStore();
     await store.set("quiet_audio_peak_dbfs_threshold", dbfs);
-    await store.save();
+    await saveStore();
   },
 
   async updateQuietAudioRequireSpeech(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("quiet_audio_require_speech", enabled);
-    await store.save();
+    await saveStore();
+  },
+
+  async updateHallucinationFilterEnabled(enabled: boolean): Promise<void> {
+    const store = await getStore();
+    await store.set("hallucination_filter_enabled", enabled);
+    await saveStore();
+  },
+
+  async updateHallucinationFilterPhrases(phrases: string[]): Promise<void> {
+    const store = await getStore();
+    await store.set("hallucination_filter_phrases", phrases);
+    await saveStore();
   },
 
   async updateNoiseGateThresholdDbfs(
@@ -1079,7 +1627,7 @@ export const tauriAPI = {
       "noise_gate_strength",
       noiseGateThresholdDbfsToStrength(normalized)
     );
-    await store.save();
+    await saveStore();
   },
 
   async updateNoiseGateStrength(strength: number): Promise<void> {
@@ -1091,43 +1639,49 @@ export const tauriAPI = {
       "noise_gate_threshold_dbfs",
       noiseGateStrengthToThresholdDbfs(normalizedStrength)
     );
-    await store.save();
+    await saveStore();
+  },
+
+  async updateStopAtMaxDuration(enabled: boolean): Promise<void> {
+    const store = await getStore();
+    await store.set("stop_at_max_duration", enabled);
+    await saveStore();
   },
 
   async updateAudioDownmixToMono(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("audio_downmix_to_mono", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateAudioResampleTo16khz(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("audio_resample_to_16khz", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateAudioHighpassEnabled(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("audio_highpass_enabled", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateAudioAgcEnabled(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("audio_agc_enabled", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateAudioNoiseSuppressionEnabled(enabled: boolean): Promise<void> {
     const store = await getStore();
     await store.set("audio_noise_suppression_enabled", enabled);
-    await store.save();
+    await saveStore();
   },
 
   async updateMaxSavedRecordings(max: number): Promise<void> {
     const store = await getStore();
     await store.set("max_saved_recordings", normalizeMaxSavedRecordings(max));
-    await store.save();
+    await saveStore();
   },
 
   async updateRequestLogsRetention(params: {
@@ -1144,7 +1698,7 @@ export const tauriAPI = {
     await store.set("request_logs_retention_mode", mode);
     await store.set("request_logs_retention_amount", amount);
     await store.set("request_logs_retention_days", days);
-    await store.save();
+    await saveStore();
   },
 
   async updateTranscriptionRetentionDays(days: number): Promise<void> {
@@ -1155,7 +1709,7 @@ export const tauriAPI = {
     // New keys
     await store.set("transcription_retention_unit", "days");
     await store.set("transcription_retention_value", normalized);
-    await store.save();
+    await saveStore();
   },
 
   async updateTranscriptionRetention(params: {
@@ -1176,7 +1730,7 @@ export const tauriAPI = {
       await store.set("transcription_retention_days", value);
     }
 
-    await store.save();
+    await saveStore();
   },
 
   async updateTranscriptionRetentionDeleteRecordings(
@@ -1187,7 +1741,7 @@ export const tauriAPI = {
       "transcription_retention_delete_recordings",
       normalizeTranscriptionRetentionDeleteRecordings(enabled)
     );
-    await store.save();
+    await saveStore();
   },
 
   async isAudioMuteSupported(): Promise<boolean> {
@@ -1210,13 +1764,13 @@ export const tauriAPI = {
   async setApiKey(storeKey: string, apiKey: string): Promise<void> {
     const store = await getStore();
     await store.set(storeKey, apiKey);
-    await store.save();
+    await saveStore();
   },
 
   async clearApiKey(storeKey: string): Promise<void> {
     const store = await getStore();
     await store.delete(storeKey);
-    await store.save();
+    await saveStore();
   },
 
   // Onboarding / guide state
@@ -1232,7 +1786,7 @@ export const tauriAPI = {
       SETTINGS_GUIDE_STATE_KEY,
       normalizeSettingsGuideState(state)
     );
-    await store.save();
+    await saveStore();
 
     // Notify other windows that persisted state changed.
     await emit("settings-changed", { [SETTINGS_GUIDE_STATE_KEY]: state });
@@ -1243,7 +1797,7 @@ export const tauriAPI = {
     await store.set("toggle_hotkey", defaultToggleHotkey);
     await store.set("hold_hotkey", defaultHoldHotkey);
     await store.set("paste_last_hotkey", defaultPasteLastHotkey);
-    await store.save();
+    await saveStore();
   },
 
   async registerShortcuts(): Promise<void> {
@@ -1267,10 +1821,52 @@ export const tauriAPI = {
     return invoke("delete_history_entry", { id });
   },
 
+  async searchHistory(
+    query?: string,
+    tags?: string[],
+    dateRange?: HistoryDateRange,
+    limit?: number,
+  ): Promise<HistoryEntry[]> {
+    return invoke("search_history", {
+      query: query ?? null,
+      tags: tags ?? null,
+      dateRange: dateRange ?? null,
+      limit: limit ?? null,
+    });
+  },
+
+  async searchTranscripts(query: string, limit?: number): Promise<TranscriptHit[]> {
+    return invoke("search_transcripts", { query, limit: limit ?? null });
+  },
+
+  /** Append a day's transcripts (YYYY-MM-DD, today by default) to its digest file */
+  async exportDailyDigest(date?: string): Promise<number> {
+    return invoke("export_daily_digest", { date: date ?? null });
+  },
+
+  async annotateHistoryEntry(
+    id: string,
+    tags: string[],
+    notes: string | null,
+  ): Promise<HistoryEntry> {
+    return invoke("annotate_history_entry", { id, tags, notes });
+  },
+
+  async getHistoryTags(): Promise<string[]> {
+    return invoke("get_history_tags");
+  },
+
   async clearHistory(): Promise<void> {
     return invoke("clear_history");
   },
 
+  async getDictationStats(
+    range: StatsRange,
+    typingWpm?: number,
+  ): Promise<DictationStats> {
+    return invoke("get_dictation_stats", { range, typingWpm: typingWpm ?? null });
+  },
+
   // Overlay API
   async resizeOverlay(width: number, height: number): Promise<void> {
     return invoke("resize_overlay", { width, height });
@@ -1329,6 +1925,27 @@ export interface OpenWindowInfo {
   process_path: string;
 }
 
+export type PermissionKind =
+  | "microphone"
+  | "accessibility"
+  | "input_monitoring"
+  | "notifications";
+
+export type PermissionState =
+  | "granted"
+  | "denied"
+  | "not_determined"
+  | "restricted"
+  | "not_required"
+  | "unknown";
+
+export interface PermissionStatus {
+  microphone: PermissionState;
+  accessibility: PermissionState;
+  input_monitoring: PermissionState;
+  notifications: PermissionState;
+}
+
 // ============================================================================
 // LLM API
 // ============================================================================
@@ -1347,12 +1964,84 @@ export interface LlmProviderInfo {
   models: string[];
 }
 
+/** Prompt sections as the backend's `PromptSections` (null custom = built-in default) */
+export interface LlmPromptSections {
+  main_custom: string | null;
+  advanced_enabled: boolean;
+  advanced_custom: string | null;
+  dictionary_enabled: boolean;
+  dictionary_custom: string | null;
+}
+
+/** One side of `compare_prompts`; unset fields come from the profile (or Default) */
+export interface PromptVariant {
+  profile_id?: string | null;
+  prompts?: LlmPromptSections | null;
+  provider?: string | null;
+  model?: string | null;
+}
+
+export interface PromptVariantOutput {
+  output: string;
+  provider_used: string;
+  model_used: string;
+  duration_ms: number;
+  error: string | null;
+}
+
+export interface DiffSegment {
+  op: "equal" | "delete" | "insert";
+  text: string;
+}
+
+export interface ComparePromptsResponse {
+  raw_transcript: string;
+  a: PromptVariantOutput;
+  b: PromptVariantOutput;
+  /** Word-level diff from `a.output` to `b.output` (empty if either failed) */
+  diff: DiffSegment[];
+}
+
 export interface LlmCompleteResponse {
   output: string;
   provider_used: string;
   model_used: string;
 }
 
+export type ModelKind = "stt" | "llm";
+
+export interface ProviderModels {
+  provider: string;
+  kind: ModelKind;
+  models: string[];
+  // "live" = just fetched, "cached" = from the local cache, "fallback" = built-in list
+  source: "live" | "cached" | "fallback";
+  fetched_at: string | null;
+  error: string | null;
+}
+
+export const modelCatalogAPI = {
+  listProviderModels: (params: {
+    provider: string;
+    kind: ModelKind;
+    refresh?: boolean;
+  }) =>
+    invoke<ProviderModels>("list_provider_models", {
+      provider: params.provider,
+      kind: params.kind,
+      refresh: params.refresh ?? false,
+    }),
+};
+
+export const encryptionAPI = {
+  isEnabled: () => invoke<boolean>("get_encryption_at_rest"),
+  /** Why the at-rest key failed to load at startup; nothing is saved this run if set. */
+  getKeyError: () => invoke<string | null>("get_encryption_key_error"),
+  /** Switches encryption at rest and migrates existing files; resolves to how many changed. */
+  setEnabled: (enabled: boolean) =>
+    invoke<number>("set_encryption_at_rest", { enabled }),
+};
+
 export const llmAPI = {
   getLlmProviders: () => invoke<LlmProviderInfo[]>("get_llm_providers"),
 
@@ -1363,6 +2052,17 @@ export const llmAPI = {
       profile_id: params.profileId ?? null,
     }),
 
+  comparePrompts: (params: {
+    recordingId: string;
+    promptA: PromptVariant;
+    promptB: PromptVariant;
+  }) =>
+    invoke<ComparePromptsResponse>("compare_prompts", {
+      recordingId: params.recordingId,
+      promptA: params.promptA,
+      promptB: params.promptB,
+    }),
+
   complete: (params: {
     provider: string;
     model?: string | null;
@@ -1382,6 +2082,81 @@ export const llmAPI = {
     }),
 };
 
+export interface EvalProvider {
+  provider: string;
+  /** null = the provider's default model */
+  model?: string | null;
+}
+
+export interface ErrorCount {
+  errors: number;
+  reference_len: number;
+}
+
+export interface EvalCaseResult {
+  name: string;
+  hypothesis: string | null;
+  words: ErrorCount;
+  chars: ErrorCount;
+  wer: number | null;
+  cer: number | null;
+  latency_ms: number;
+  error: string | null;
+}
+
+export interface EvalProviderReport {
+  provider: string;
+  model: string | null;
+  completed: number;
+  failed: number;
+  /** Corpus WER/CER over the completed cases (0..1) */
+  wer: number | null;
+  cer: number | null;
+  mean_latency_ms: number | null;
+  cases: EvalCaseResult[];
+}
+
+export interface EvalReport {
+  folder: string;
+  started_at: string;
+  cases: number;
+  /** Sorted by WER, best first */
+  providers: EvalProviderReport[];
+}
+
+export interface EvalProgress {
+  provider: string;
+  model: string | null;
+  case_name: string;
+  done: number;
+  total: number;
+}
+
+export const evalAPI = {
+  /**
+   * Transcribe each `<name>.wav` in `folder` (scored against `<name>.txt`) with every
+   * provider; defaults to the configured STT provider.
+   */
+  runSttEval: (params: {
+    folder: string;
+    providers?: EvalProvider[] | null;
+    writeReport?: boolean;
+  }) =>
+    invoke<EvalReport>("run_stt_eval", {
+      folder: params.folder,
+      providers: params.providers ?? null,
+      writeReport: params.writeReport ?? false,
+    }),
+
+  async onProgress(
+    callback: (progress: EvalProgress) => void
+  ): Promise<UnlistenFn> {
+    return listen<EvalProgress>("stt-eval-progress", (event) => {
+      callback(event.payload);
+    });
+  },
+};
+
 export const sttAPI = {
   testTranscribeLastAudio: (params: { profileId?: string | null }) =>
     invoke<string>("pipeline_test_transcribe_last_audio", {
@@ -1396,14 +2171,48 @@ export const sttAPI = {
       "pipeline_get_last_recording_diagnostics"
     ),
 
+  // Record 3 seconds (default: the configured microphone) and report levels/VAD.
+  runAudioDiagnostics: (params: { deviceName?: string | null } = {}) =>
+    invoke<AudioDiagnostics>("run_audio_diagnostics", {
+      deviceName: params.deviceName ?? null,
+    }),
+
   // Retry a previous request using its persisted audio.
   // Returns the final text (STT + optional LLM), same as normal transcription.
   retryTranscription: (params: { requestId: string }) =>
     invoke<string>("pipeline_retry_transcription", {
       requestId: params.requestId,
     }),
+
+  // Transcriptions the app was running when it last exited (see recovery.rs).
+  getOrphanedRecordings: () =>
+    invoke<OrphanedRecording[]>("get_orphaned_recordings"),
+
+  // Transcribe an interrupted recording into history; null if cancelled.
+  recoverOrphanedRecording: (id: string) =>
+    invoke<string | null>("recover_orphaned_recording", { id }),
+
+  discardOrphanedRecording: (id: string) =>
+    invoke<void>("discard_orphaned_recording", { id }),
+
+  // Transcriptions run one at a time, in the order their recordings stopped.
+  listJobs: () => invoke<TranscriptionJob[]>("pipeline_list_jobs"),
+
+  // Returns false if the job had already finished.
+  cancelJob: (id: number) => invoke<boolean>("pipeline_cancel_job", { id }),
 };
 
+export type TranscriptionJobStatus = "queued" | "running" | "done" | "failed";
+
+export interface TranscriptionJob {
+  id: number;
+  status: TranscriptionJobStatus;
+  request_id: string | null;
+  queued_at: string;
+  // Set for failed (including cancelled) jobs.
+  error: string | null;
+}
+
 export interface AudioLevelStats {
   duration_secs: number;
   rms: number;
@@ -1414,6 +2223,36 @@ export interface AudioCaptureDiagnostics {
   stats: AudioLevelStats;
   // null when speech detection wasn't computed for the last recording.
   speech_detected: boolean | null;
+  // Whether the realtime VAD heard speech (null when realtime VAD was off).
+  realtime_speech_detected?: boolean | null;
+}
+
+export interface PipelineRecovered {
+  state: "recording" | "transcribing" | "rewriting";
+  stalled_secs: number;
+}
+
+export interface OrphanedRecording {
+  id: string;
+  request_id: string | null;
+  started_at: string;
+  duration_secs: number;
+  stt_provider: string;
+}
+
+export interface AudioDiagnostics {
+  // null = system default device
+  device_name: string | null;
+  sample_rate: number;
+  channels: number;
+  duration_secs: number;
+  // null for digital silence
+  peak_dbfs: number | null;
+  rms_dbfs: number | null;
+  speech_detected: boolean;
+  // Why a dictation with this audio would not be transcribed.
+  skip_reason: "empty_recording" | "quiet" | "duplicate" | null;
+  wav_base64: string;
 }
 
 export interface AudioSettingsTestWavs {
@@ -1421,6 +2260,25 @@ export interface AudioSettingsTestWavs {
   processed_wav_base64: string;
 }
 
+export interface VoskModelInfo {
+  id: string;
+  name: string;
+  size_bytes: number;
+  size_display: string;
+  download_url: string;
+  is_downloaded: boolean;
+}
+
+export const voskAPI = {
+  isAvailable: () => invoke<boolean>("is_local_vosk_available"),
+  getModels: () => invoke<VoskModelInfo[]>("get_vosk_models"),
+  // Resolves once the model is downloaded and unpacked.
+  downloadModel: (modelId: string) =>
+    invoke<void>("download_vosk_model", { modelId }),
+  deleteModel: (modelId: string) =>
+    invoke<void>("delete_vosk_model", { modelId }),
+};
+
 export const audioSettingsTestAPI = {
   startRecording: () => invoke<void>("pipeline_test_audio_settings_start_recording"),
   stopRecording: () =>
@@ -1448,6 +2306,52 @@ interface AvailableProvidersResponse {
   llm: ProviderInfo[];
 }
 
+export interface NoiseProfile {
+  noise_floor_dbfs: number;
+}
+
+export interface WhisperDecodingParams {
+  beam_size: number; // 0 or 1 = greedy
+  temperature: number;
+  temperature_inc: number; // 0 = no temperature fallback
+  no_speech_threshold: number;
+  max_segment_len: number; // characters, 0 = unlimited
+}
+
+export interface LlmGenerationParams {
+  max_tokens: number;
+  temperature: number | null; // 0-2
+  top_p: number | null; // 0-1
+}
+
+export type InferencePriority = "normal" | "low" | "idle";
+
+export interface NetworkConfig {
+  proxy_url: string | null; // null = proxy from environment variables
+  no_proxy: string | null; // comma-separated hosts that bypass the proxy
+  ca_cert_path: string | null; // extra root certificates (PEM bundle or DER)
+  tls_verify: boolean;
+}
+
+export const DEFAULT_NETWORK_CONFIG: NetworkConfig = {
+  proxy_url: null,
+  no_proxy: null,
+  ca_cert_path: null,
+  tls_verify: true,
+};
+
+export interface RateLimitConfig {
+  requests_per_minute: number; // per provider, 0 = unlimited
+  burst: number;
+  max_in_flight: number; // across all providers, 0 = unlimited
+}
+
+export interface WhisperThreading {
+  n_threads: number; // 0 = all cores but two
+  priority: InferencePriority;
+  pause_while_recording: boolean;
+}
+
 export const configAPI = {
   // Default prompt sections (from Tauri)
   getDefaultSections: () =>
@@ -1459,6 +2363,31 @@ export const configAPI = {
 
   // Sync pipeline config when settings change
   syncPipelineConfig: () => invoke<void>("sync_pipeline_config"),
+
+  // Measure a mic's ambient noise (stay quiet while it runs) and store it for VAD.
+  // deviceName null = system default.
+  calibrateMicrophone: (deviceName: string | null, durationMs?: number) =>
+    invoke<NoiseProfile>("calibrate_microphone", { deviceName, durationMs }),
+
+  // All settings through the typed backend schema, plus stored values that fail validation.
+  getSettings: () =>
+    invoke<{ settings: Record<string, unknown>; errors: SettingsValidationError[] }>(
+      "get_settings"
+    ),
+
+  // Validate and save several keys at once (null resets a key). Rejects with an
+  // `invalid_settings` AppError listing every bad value; nothing is saved then.
+  updateSettings: (patch: Record<string, unknown>) =>
+    invoke<Record<string, unknown>>("update_settings", { patch }),
+
+  // Write the whole configuration to one file (.toml for TOML, JSON otherwise).
+  // API keys and other secrets are left out unless includeSecrets is set.
+  exportSettings: (path: string, includeSecrets = false) =>
+    invoke<void>("export_settings", { path, includeSecrets }),
+
+  // Import a file written by exportSettings on this or another machine.
+  importSettings: (path: string, conflict: SettingsImportConflict = "overwrite") =>
+    invoke<SettingsImportSummary>("import_settings", { path, conflict }),
 };
 
 // ============================================================================
@@ -1475,6 +2404,12 @@ export interface LogEntry {
   details: string | null;
 }
 
+export interface TranscriptSegment {
+  start_secs: number;
+  end_secs: number;
+  text: string;
+}
+
 export interface RequestLog {
   id: string;
   started_at: string;
@@ -1487,6 +2422,7 @@ export interface RequestLog {
   final_text: string | null;
   stt_duration_ms: number | null;
   llm_duration_ms: number | null;
+  typing_duration_ms?: number;
   status: RequestStatus;
   error_message: string | null;
   entries: LogEntry[];
@@ -1495,10 +2431,40 @@ export interface RequestLog {
   // Binary audio is redacted and represented with placeholders.
   stt_request_json?: unknown;
   stt_response_json?: unknown;
+  // Detected language and timed segments, from verbose STT responses.
+  stt_language?: string;
+  stt_segments?: TranscriptSegment[];
   llm_request_json?: unknown;
   llm_response_json?: unknown;
 }
 
+export interface Percentiles {
+  count: number;
+  p50: number;
+  p95: number;
+  mean: number;
+}
+
+export interface StageBreakdown {
+  requests: number;
+  errors: number;
+  capture_ms: Percentiles | null;
+  wav_bytes: Percentiles | null;
+  stt_ms: Percentiles | null;
+  llm_ms: Percentiles | null;
+  typing_ms: Percentiles | null;
+  total_ms: Percentiles | null;
+}
+
+export interface PipelineMetrics {
+  since: string | null;
+  overall: StageBreakdown;
+  by_stt_provider: Record<string, StageBreakdown>;
+  by_llm_provider: Record<string, StageBreakdown>;
+}
+
+export type RecordingStorageMode = "always" | "on_error" | "never";
+
 export interface RecordingsStats {
   count: number;
   bytes: number;
@@ -1509,8 +2475,31 @@ export const logsAPI = {
     invoke<RequestLog[]>("get_request_logs", { limit: limit ?? 100 }),
 
   clearRequestLogs: () => invoke<void>("clear_request_logs"),
+
+  getPipelineMetrics: (since?: string, groupByModel?: boolean) =>
+    invoke<PipelineMetrics>("get_pipeline_metrics", {
+      since: since ?? null,
+      groupByModel: groupByModel ?? false,
+    }),
+
+  clearPipelineMetrics: () => invoke<void>("clear_pipeline_metrics"),
+
+  // Last entries of the application log (JSON objects with timestamp, level, message,
+  // target and any structured fields), oldest first.
+  getAppLogTail: (lines?: number) =>
+    invoke<AppLogEntry[]>("get_app_log_tail", { lines: lines ?? 200 }),
+
+  setLogLevel: (level: AppLogLevel) => invoke<void>("set_log_level", { level }),
 };
 
+export interface AppLogEntry {
+  timestamp: string;
+  level: "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE";
+  message: string;
+  target: string;
+  [field: string]: unknown;
+}
+
 // ============================================================================
 // Recordings API (playback)
 // ============================================================================
@@ -1539,4 +2528,18 @@ export const recordingsAPI = {
 
   // Stats for UI display (count + bytes).
   getRecordingsStats: () => invoke<RecordingsStats>("recordings_get_stats"),
+
+  // When audio is written to disk ("never" keeps recordings in memory only).
+  getRecordingStorageMode: () =>
+    invoke<RecordingStorageMode>("recordings_get_storage_mode"),
+  setRecordingStorageMode: (mode: RecordingStorageMode) =>
+    invoke<void>("recordings_set_storage_mode", { mode }),
+
+  // Zip the recording, request log, history entry and redacted settings of a request for a
+  // bug report. Resolves to the path of the written file.
+  exportDebugBundle: (params: { requestId: string; destination?: string }) =>
+    invoke<string>("export_debug_bundle", {
+      requestId: params.requestId,
+      destination: params.destination ?? null,
+    }),
 };
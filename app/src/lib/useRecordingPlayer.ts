@@ -1,5 +1,5 @@
 import { useCallback, useEffect, useMemo, useRef, useState } from "react";
-import { recordingsAPI } from "./tauri";
+import { errorMessage, recordingsAPI } from "./tauri";
 
 class PlaybackTimeoutError extends Error {
   override name = "PlaybackTimeoutError";
@@ -288,7 +288,7 @@ export function useRecordingPlayer(
         }
       } catch (e) {
         setPlayingRequestId(null);
-        onError?.(`Failed to play recording: ${String(e)}`);
+        onError?.(`Failed to play recording: ${errorMessage(e)}`);
       } finally {
         setLoadingRequestId(null);
       }
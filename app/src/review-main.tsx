@@ -0,0 +1,15 @@
+import "@fontsource/sora/index.css";
+import { StrictMode } from "react";
+import { createRoot } from "react-dom/client";
+import ReviewApp from "./ReviewApp";
+
+const rootElement = document.getElementById("root");
+if (!rootElement) {
+	throw new Error("Root element not found");
+}
+
+createRoot(rootElement).render(
+	<StrictMode>
+		<ReviewApp />
+	</StrictMode>,
+);
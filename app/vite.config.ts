@@ -37,6 +37,8 @@ export default defineConfig({
 			input: {
 				main: "index.html",
 				overlay: "overlay.html",
+				captions: "captions.html",
+				review: "review.html",
 			},
 		},
 	},